@@ -0,0 +1,105 @@
+// Keccak-256 の速さ (asm-keccak の有無で比べる)
+// Keccak だけでなく、効果の大きい大きな calldata の署名用ハッシュと sign-batch の署名も計る
+// asm-keccak は aarch64 でしか効かないため、ほかのアーキテクチャでは有無で同じ結果になる
+//
+//   cargo bench --bench keccak
+//   cargo bench --bench keccak --features asm-keccak
+use ethereum_transaction_signer::{
+    batch, bench::synthetic_message, sigcache::SignatureCache, signer, transaction,
+};
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+// 1回あたりの時間とスループットを表示
+fn measure(name: &str, data: &[u8]) {
+    // 1秒ほどかかる回数を見積もってから計る
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < Duration::from_millis(100) {
+        black_box(Keccak256::digest(black_box(data)));
+        iterations += 1;
+    }
+    let iterations = iterations * 10;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(Keccak256::digest(black_box(data)));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<32} {:>10.2?}/hash {:>10.1} MB/s",
+        elapsed / iterations,
+        (data.len() as f64 * f64::from(iterations)) / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+// トランザクションの署名用ハッシュ (calldata の大きさごと)
+fn measure_signing_hash(name: &str, calldata_size: usize) {
+    let message = synthetic_message(0, calldata_size);
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < Duration::from_secs(1) {
+        black_box(transaction::signing_hash(black_box(&message)));
+        iterations += 1;
+    }
+    println!("{name:<32} {:>10.2?}/hash", start.elapsed() / iterations);
+}
+
+// sign-batch と同じパイプラインで count 件に署名する (送信と記録はしない)
+fn measure_batch(name: &str, count: usize, calldata_size: usize) {
+    let key = signer::Key::new(
+        SigningKey::from_slice(signer::keccak256(b"bench sign").as_bytes()).unwrap(),
+    );
+    let messages: Vec<_> = (0..count)
+        .map(|i| synthetic_message(i, calldata_size))
+        .collect();
+    let jobs = batch::default_jobs();
+
+    // 署名済みのものは署名し直さないため、毎回新しいキャッシュを使う
+    let start = Instant::now();
+    let signed = batch::sign_pipeline(
+        messages,
+        &key,
+        &SignatureCache::default(),
+        jobs,
+        None,
+        |_, _| Ok(()),
+    )
+    .unwrap();
+    let elapsed = start.elapsed();
+    black_box(signed);
+    println!(
+        "{name:<32} {:>10.2?}/tx {:>10.0} tx/s ({jobs} threads)",
+        elapsed / count as u32,
+        count as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    println!(
+        "keccak backend: {}",
+        if cfg!(all(feature = "asm-keccak", target_arch = "aarch64")) {
+            "armv8 sha3 (when the CPU supports it)"
+        } else if cfg!(feature = "asm-keccak") {
+            "portable (asm-keccak only has an effect on aarch64)"
+        } else {
+            "portable"
+        }
+    );
+    // 送金や ERC-20 の transfer の署名用ハッシュ
+    measure("transfer (120 B)", &[0xab; 120]);
+    // まとめて署名する Multicall3 / Disperse の calldata
+    measure("multicall (4 KiB)", &[0xab; 4 * 1024]);
+    // コントラクトのデプロイ (initcode の上限 48 KiB より大きいもの)
+    measure("large calldata (128 KiB)", &[0xab; 128 * 1024]);
+
+    measure_signing_hash("signing hash: 4 KiB calldata", 4 * 1024);
+    measure_signing_hash("signing hash: 128 KiB calldata", 128 * 1024);
+
+    measure_batch("sign-batch: 10000 transfers", 10_000, 0);
+    measure_batch("sign-batch: 1000 x 128 KiB", 1_000, 128 * 1024);
+}
@@ -0,0 +1,126 @@
+// 署名の各段階の速さ (パラメータの読み込み、署名用ハッシュ、署名、パラメータから raw トランザクションまで)
+// 新しい機能で遅くなっていないか、変更の前後で比べる
+// (criterion はオフラインのビルドで取得できないため使わない)
+//
+//   cargo bench --bench signing
+use ethereum::EIP1559TransactionMessage;
+use ethereum_transaction_signer::{
+    config::Config,
+    params::Params,
+    policy::Policy,
+    signer::{self, keccak256},
+    transaction,
+};
+use ethereum_types::U256;
+use k256::ecdsa::SigningKey;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+// 100ms ほど回して回数を見積もってから、約1秒ぶん計る
+fn measure<T>(name: &str, mut f: impl FnMut() -> T) {
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < Duration::from_millis(100) {
+        black_box(f());
+        iterations += 1;
+    }
+    let iterations = iterations * 10;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<40} {:>10.2?}/iter ({iterations} iterations)",
+        elapsed / iterations
+    );
+}
+
+fn config() -> Config {
+    serde_json::from_str(
+        r#"{
+            "chain_id": 11155111,
+            "max_fee_per_gas": 50000000000,
+            "max_priority_fee_per_gas": 2000000000,
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        }"#,
+    )
+    .unwrap()
+}
+
+// ERC-20 の transfer (関数シグネチャと引数で指定)
+const TRANSFER_PARAMS: &str = r#"{
+    "nonce": 1,
+    "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+    "value": "0",
+    "gas_limit": 65000,
+    "function": "transfer(address,uint256)",
+    "args": ["0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266", "1000000000000000000"]
+}"#;
+
+fn parse(json: &str) -> Params {
+    serde_json::from_str::<Params>(json)
+        .unwrap()
+        .encode_function()
+        .unwrap()
+}
+
+// calldata が size バイトのトランザクション
+fn message(config: &Config, size: usize) -> EIP1559TransactionMessage {
+    let mut params = parse(TRANSFER_PARAMS);
+    params.input = vec![0xab; size];
+    transaction::build_message(config, U256::one(), &params)
+}
+
+fn main() {
+    let config = config();
+
+    measure("params: transfer(address,uint256)", || {
+        parse(TRANSFER_PARAMS)
+    });
+
+    for (name, size) in [
+        ("signing hash: 68 B calldata", 68),
+        ("signing hash: 4 KiB calldata", 4 * 1024),
+        ("signing hash: 128 KiB calldata", 128 * 1024),
+    ] {
+        let message = message(&config, size);
+        measure(name, || transaction::signing_hash(&message));
+    }
+
+    let signing_key = SigningKey::from_slice(keccak256(b"bench sign").as_bytes()).unwrap();
+    let hash = transaction::signing_hash(&message(&config, 68));
+    measure("sign: secp256k1 (prehashed)", || {
+        signer::sign_hash(&signing_key, &hash).unwrap()
+    });
+
+    let key = config.get_key().unwrap();
+    measure("end to end: params -> raw transaction", || {
+        let params = parse(TRANSFER_PARAMS);
+        let message = transaction::build_message(&config, U256::one(), &params);
+        hex::encode(transaction::sign(message, &key).unwrap())
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("policy.toml");
+    std::fs::write(
+        &path,
+        r#"
+            max_value_per_tx = "1"
+            allowed_chains = [11155111]
+            allowed_selectors = ["transfer(address,uint256)"]
+            max_calldata_size = 1024
+        "#,
+    )
+    .unwrap();
+    let policy = Policy::from_path(&path).unwrap();
+    measure("end to end: with policy", || {
+        let params = parse(TRANSFER_PARAMS);
+        let message = transaction::build_message(&config, U256::one(), &params);
+        policy.check_transaction(&message).unwrap();
+        hex::encode(transaction::sign(message, &key).unwrap())
+    });
+}
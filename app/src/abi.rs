@@ -0,0 +1,959 @@
+use crate::{Result, error::Error, signer::keccak256};
+use ethereum_types::{H160, H256, U256};
+use serde_json::Value;
+use std::fmt;
+
+// 関数シグネチャ ("transfer(address,uint256)") から4バイトのセレクタを計算
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+// uint256 を32バイトのワードにエンコード
+pub fn encode_u256(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+// address を32バイトのワードにエンコード (左側をゼロ埋め)
+pub fn encode_address(address: &H160) -> [u8; 32] {
+    H256::from(*address).0
+}
+
+// セレクタと静的な引数ワードを連結して calldata を作成
+pub fn encode_call(signature: &str, words: &[[u8; 32]]) -> Vec<u8> {
+    let mut calldata = Vec::with_capacity(4 + 32 * words.len());
+    calldata.extend_from_slice(&selector(signature));
+    for word in words {
+        calldata.extend_from_slice(word);
+    }
+    calldata
+}
+
+// 関数の引数 (静的な32バイトワード、もしくはエンコード済みの動的データ)
+pub enum Arg {
+    Word([u8; 32]),
+    // 静的なタプルや固定長配列 (複数ワードをそのまま head に置く)
+    Static(Vec<u8>),
+    Dynamic(Vec<u8>),
+}
+
+// bytes をエンコード (長さ + 32バイト境界までゼロ埋めしたデータ)
+pub fn encode_bytes(data: &[u8]) -> Vec<u8> {
+    let padded_len = 32 + data.len().div_ceil(32) * 32;
+    let mut encoded = Vec::with_capacity(padded_len);
+    encoded.extend_from_slice(&encode_u256(U256::from(data.len())));
+    encoded.extend_from_slice(data);
+    encoded.resize(padded_len, 0);
+    encoded
+}
+
+// uint256[] をエンコード (長さ + 各要素)
+pub fn encode_u256_array(values: &[U256]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(32 * (values.len() + 1));
+    encoded.extend_from_slice(&encode_u256(U256::from(values.len())));
+    for value in values {
+        encoded.extend_from_slice(&encode_u256(*value));
+    }
+    encoded
+}
+
+// address[] をエンコード (長さ + 各要素)
+pub fn encode_address_array(addresses: &[H160]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(32 * (addresses.len() + 1));
+    encoded.extend_from_slice(&encode_u256(U256::from(addresses.len())));
+    for address in addresses {
+        encoded.extend_from_slice(&encode_address(address));
+    }
+    encoded
+}
+
+// 引数列をエンコード
+// 先頭部 (head) には静的な値か動的データへのオフセットを置き、動的データは末尾 (tail) に続ける
+pub fn encode_args(args: &[Arg]) -> Vec<u8> {
+    let head_len = args
+        .iter()
+        .map(|arg| match arg {
+            Arg::Static(data) => data.len(),
+            Arg::Word(_) | Arg::Dynamic(_) => 32,
+        })
+        .sum();
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+    for arg in args {
+        match arg {
+            Arg::Word(word) => head.extend_from_slice(word),
+            Arg::Static(data) => head.extend_from_slice(data),
+            Arg::Dynamic(data) => {
+                head.extend_from_slice(&encode_u256(U256::from(head_len + tail.len())));
+                tail.extend_from_slice(data);
+            }
+        }
+    }
+    head.extend_from_slice(&tail);
+    head
+}
+
+// セレクタと動的データを含む引数から calldata を作成
+pub fn encode_call_with_args(signature: &str, args: &[Arg]) -> Vec<u8> {
+    let mut calldata = selector(signature).to_vec();
+    calldata.extend_from_slice(&encode_args(args));
+    calldata
+}
+
+// ABI の型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Address,
+    Bool,
+    // ビット数
+    Uint(usize),
+    Int(usize),
+    // バイト数
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    // "uint256", "address[]", "(address,uint256)[2]" などをパース
+    // "address to" のような引数名は読み飛ばす
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = strip_param_name(s.trim());
+
+        if let Some(inner) = s.strip_suffix(']') {
+            let open = inner
+                .rfind('[')
+                .ok_or_else(|| invalid(format!("invalid type '{s}'")))?;
+            let item = Box::new(Self::parse(&inner[..open])?);
+            let length = &inner[open + 1..];
+            if length.is_empty() {
+                return Ok(Self::Array(item));
+            }
+            return length
+                .parse()
+                .map(|length| Self::FixedArray(item, length))
+                .map_err(|_| invalid(format!("invalid array length in '{s}'")));
+        }
+
+        let tuple = s.strip_prefix("tuple").unwrap_or(s);
+        if let Some(inner) = tuple.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            let components = split_top_level(inner)?
+                .into_iter()
+                .map(Self::parse)
+                .collect::<Result<_>>()?;
+            return Ok(Self::Tuple(components));
+        }
+
+        match s {
+            "address" => Ok(Self::Address),
+            "bool" => Ok(Self::Bool),
+            "bytes" => Ok(Self::Bytes),
+            "string" => Ok(Self::String),
+            _ if s.starts_with("uint") => parse_bits(s, "uint").map(Self::Uint),
+            _ if s.starts_with("int") => parse_bits(s, "int").map(Self::Int),
+            _ if s.starts_with("bytes") => match s["bytes".len()..].parse() {
+                Ok(size @ 1..=32) => Ok(Self::FixedBytes(size)),
+                _ => Err(invalid(format!("unknown type '{s}'"))),
+            },
+            _ => Err(invalid(format!("unknown type '{s}'"))),
+        }
+    }
+
+    // 動的な型 (head にはオフセットを置き、データは tail に置く) か
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Self::Bytes | Self::String | Self::Array(_) => true,
+            Self::FixedArray(item, _) => item.is_dynamic(),
+            Self::Tuple(components) => components.iter().any(Self::is_dynamic),
+            _ => false,
+        }
+    }
+}
+
+// セレクタの計算に使う正規化された型名 ("uint" -> "uint256")
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address => write!(f, "address"),
+            Self::Bool => write!(f, "bool"),
+            Self::Uint(bits) => write!(f, "uint{bits}"),
+            Self::Int(bits) => write!(f, "int{bits}"),
+            Self::FixedBytes(size) => write!(f, "bytes{size}"),
+            Self::Bytes => write!(f, "bytes"),
+            Self::String => write!(f, "string"),
+            Self::Array(item) => write!(f, "{item}[]"),
+            Self::FixedArray(item, length) => write!(f, "{item}[{length}]"),
+            Self::Tuple(components) => {
+                write!(f, "(")?;
+                for (i, component) in components.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{component}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+// "uint256" -> 256, "uint" -> 256 (8の倍数のみ)
+fn parse_bits(s: &str, prefix: &str) -> Result<usize> {
+    let suffix = &s[prefix.len()..];
+    if suffix.is_empty() {
+        return Ok(256);
+    }
+    match suffix.parse() {
+        Ok(bits) if bits > 0 && bits <= 256 && bits % 8 == 0 => Ok(bits),
+        _ => Err(invalid(format!("unknown type '{s}'"))),
+    }
+}
+
+// "address to" -> "address" (括弧の外にある末尾の識別子を取り除く)
+fn strip_param_name(s: &str) -> &str {
+    let mut depth = 0usize;
+    let mut last_space = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => last_space = Some(i),
+            _ => {}
+        }
+    }
+
+    match last_space {
+        Some(i) => {
+            let name = &s[i + 1..];
+            let is_identifier = name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if is_identifier && !name.is_empty() {
+                s[..i].trim_end()
+            } else {
+                s
+            }
+        }
+        None => s,
+    }
+}
+
+// 括弧の外にあるカンマで分割 ("" は要素なし)
+pub fn split_top_level(s: &str) -> Result<Vec<&str>> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut in_quote = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if in_quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quote = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quote = true,
+            '(' | '[' => depth += 1,
+            ')' | ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| invalid(format!("unbalanced brackets in '{s}'")))?
+            }
+            ',' if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 || in_quote {
+        return Err(invalid(format!("unbalanced brackets or quotes in '{s}'")));
+    }
+    parts.push(s[start..].trim());
+
+    Ok(parts)
+}
+
+// 関数のシグネチャ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<ParamType>,
+}
+
+impl Function {
+    // "transfer(address,uint256)" や "transfer(address to, uint256 amount)" をパース
+    pub fn parse(signature: &str) -> Result<Self> {
+        let signature = signature.trim();
+        let open = signature
+            .find('(')
+            .ok_or_else(|| invalid(format!("invalid function signature '{signature}'")))?;
+        let name = signature[..open].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(invalid(format!("invalid function name in '{signature}'")));
+        }
+
+        match ParamType::parse(&signature[open..])? {
+            ParamType::Tuple(inputs) => Ok(Self {
+                name: name.to_string(),
+                inputs,
+            }),
+            _ => Err(invalid(format!("invalid function signature '{signature}'"))),
+        }
+    }
+
+    // 正規化したシグネチャ ("transfer(address,uint256)")
+    pub fn signature(&self) -> String {
+        format!("{}{}", self.name, ParamType::Tuple(self.inputs.clone()))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    // 引数をエンコードしてセレクタと連結
+    pub fn encode_call(&self, args: &[Value]) -> Result<Vec<u8>> {
+        if args.len() != self.inputs.len() {
+            return Err(invalid(format!(
+                "{} expects {} arguments, got {}",
+                self.signature(),
+                self.inputs.len(),
+                args.len()
+            )));
+        }
+
+        let mut calldata = self.selector().to_vec();
+        calldata.extend_from_slice(&encode_params(&self.inputs, args)?);
+        Ok(calldata)
+    }
+}
+
+// 型に合わせて値 (JSON) を引数列としてエンコード
+// 整数は数値か文字列 (10進数 / 0x 付き16進数)、bytes は16進数の文字列、配列とタプルは JSON の配列
+pub fn encode_params(types: &[ParamType], values: &[Value]) -> Result<Vec<u8>> {
+    let args = types
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, (param_type, value))| {
+            // エラーメッセージに引数の位置を付与
+            let encoded = encode_value(param_type, value).map_err(|e| match e {
+                Error::InvalidAbiData(message) => {
+                    invalid(format!("argument {i} ({param_type}): {message}"))
+                }
+                e => e,
+            })?;
+            Ok(if param_type.is_dynamic() {
+                Arg::Dynamic(encoded)
+            } else {
+                Arg::Static(encoded)
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(encode_args(&args))
+}
+
+fn encode_value(param_type: &ParamType, value: &Value) -> Result<Vec<u8>> {
+    let items = || {
+        value
+            .as_array()
+            .ok_or_else(|| invalid(format!("expected array for '{param_type}'")))
+    };
+
+    match param_type {
+        ParamType::Bytes => Ok(encode_bytes(&value_to_bytes(value)?)),
+        ParamType::String => {
+            let s = value.as_str().ok_or_else(|| invalid("expected string"))?;
+            Ok(encode_bytes(s.as_bytes()))
+        }
+        ParamType::Array(item) => {
+            let items = items()?;
+            let mut encoded = encode_u256(U256::from(items.len())).to_vec();
+            let types = vec![item.as_ref().clone(); items.len()];
+            encoded.extend_from_slice(&encode_params(&types, items)?);
+            Ok(encoded)
+        }
+        ParamType::FixedArray(item, length) => {
+            let items = items()?;
+            if items.len() != *length {
+                return Err(invalid(format!(
+                    "expected {length} items for '{param_type}', got {}",
+                    items.len()
+                )));
+            }
+            encode_params(&vec![item.as_ref().clone(); *length], items)
+        }
+        ParamType::Tuple(components) => {
+            let items = items()?;
+            if items.len() != components.len() {
+                return Err(invalid(format!(
+                    "expected {} components for '{param_type}', got {}",
+                    components.len(),
+                    items.len()
+                )));
+            }
+            encode_params(components, items)
+        }
+        _ => Ok(encode_atomic(param_type, value)?.to_vec()),
+    }
+}
+
+// 32バイトに収まる基本型 (address, bool, uintN, intN, bytesN) をエンコード
+pub fn encode_atomic(param_type: &ParamType, value: &Value) -> Result<[u8; 32]> {
+    match param_type {
+        ParamType::Address => {
+            let s = value
+                .as_str()
+                .ok_or_else(|| invalid("expected address string"))?;
+            let address: H160 = s
+                .parse()
+                .map_err(|_| invalid(format!("invalid address '{s}'")))?;
+            Ok(encode_address(&address))
+        }
+        ParamType::Bool => {
+            let b = match value {
+                Value::Bool(b) => *b,
+                Value::String(s) if s == "true" => true,
+                Value::String(s) if s == "false" => false,
+                _ => return Err(invalid("expected bool")),
+            };
+            Ok(encode_u256(U256::from(b as u8)))
+        }
+        ParamType::FixedBytes(size) => {
+            let bytes = value_to_bytes(value)?;
+            if bytes.len() != *size {
+                return Err(invalid(format!(
+                    "expected {size} bytes for '{param_type}', got {}",
+                    bytes.len()
+                )));
+            }
+            // bytesN は左詰め
+            let mut word = [0u8; 32];
+            word[..*size].copy_from_slice(&bytes);
+            Ok(word)
+        }
+        ParamType::Uint(bits) => {
+            let (negative, magnitude) = value_to_integer(value)?;
+            if negative || magnitude.bits() > *bits {
+                return Err(invalid(format!("value out of range for '{param_type}'")));
+            }
+            Ok(encode_u256(magnitude))
+        }
+        ParamType::Int(bits) => {
+            let (negative, magnitude) = value_to_integer(value)?;
+            // 範囲: -2^(bits-1) <= value < 2^(bits-1)
+            let limit = U256::one() << (bits - 1);
+            if (negative && magnitude > limit) || (!negative && magnitude >= limit) {
+                return Err(invalid(format!("value out of range for '{param_type}'")));
+            }
+            // 負数は2の補数表現
+            let word = if negative {
+                (!magnitude).overflowing_add(U256::one()).0
+            } else {
+                magnitude
+            };
+            Ok(encode_u256(word))
+        }
+        _ => Err(invalid(format!("'{param_type}' is not a single-word type"))),
+    }
+}
+
+// 16進数の文字列 (0x は省略可能) をバイト列として解釈
+pub fn value_to_bytes(value: &Value) -> Result<Vec<u8>> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| invalid("expected hex string"))?;
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(Into::into)
+}
+
+// 数値または文字列 (10進数 / 0xプレフィックス付き16進数) を (負かどうか, 絶対値) として解釈
+fn value_to_integer(value: &Value) -> Result<(bool, U256)> {
+    match value {
+        // u64 を超える整数も元の文字列のまま読む (serde_json の arbitrary_precision)
+        Value::Number(n) => {
+            let text = n.to_string();
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, text.as_str()),
+            };
+            let magnitude = digits
+                .bytes()
+                .all(|byte| byte.is_ascii_digit())
+                .then(|| U256::from_dec_str(digits).ok())
+                .flatten()
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "number {n} is not an integer or too large, use a string instead"
+                    ))
+                })?;
+            Ok((negative && !magnitude.is_zero(), magnitude))
+        }
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.as_str()),
+            };
+            let magnitude = match digits.strip_prefix("0x") {
+                Some(hex_digits) => U256::from_str_radix(hex_digits, 16).ok(),
+                None => U256::from_dec_str(digits).ok(),
+            }
+            .ok_or_else(|| invalid(format!("invalid integer '{s}'")))?;
+            Ok((negative && !magnitude.is_zero(), magnitude))
+        }
+        _ => Err(invalid("expected integer")),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidAbiData(message.into())
+}
+
+// 指定位置の32バイトワードを取得
+// オフセットはノードの戻り値から読むため、足し算のオーバーフローも範囲外として扱う
+fn word_at(data: &[u8], offset: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(32)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| invalid(format!("data too short to read word at {offset}")))
+}
+
+// ワードを usize として読む (オフセットや長さ)
+fn usize_at(data: &[u8], offset: usize) -> Result<usize> {
+    let value = U256::from_big_endian(word_at(data, offset)?);
+    if value > U256::from(usize::MAX) {
+        return Err(invalid(format!("offset or length too large at {offset}")));
+    }
+    Ok(value.as_usize())
+}
+
+// 戻り値の uint256 をデコード
+pub fn decode_u256(data: &[u8]) -> Result<U256> {
+    Ok(U256::from_big_endian(word_at(data, 0)?))
+}
+
+// 戻り値の string をデコード (オフセット + 長さ + データ)
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    let offset = usize_at(data, 0)?;
+    let length = usize_at(data, offset)?;
+    let bytes = offset
+        .checked_add(32)
+        .and_then(|start| Some(start..start.checked_add(length)?))
+        .and_then(|range| data.get(range))
+        .ok_or_else(|| invalid("string data out of range"))?;
+
+    String::from_utf8(bytes.to_vec()).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+        assert_eq!(selector("nonces(address)"), [0x7e, 0xce, 0xbe, 0x00]);
+        assert_eq!(selector("name()"), [0x06, 0xfd, 0xde, 0x03]);
+    }
+
+    #[test]
+    fn test_encode_call() {
+        let to: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+        let calldata = encode_call(
+            "transfer(address,uint256)",
+            &[
+                encode_address(&to),
+                encode_u256(U256::from_dec_str("1000000000000000000").unwrap()),
+            ],
+        );
+
+        assert_eq!(
+            hex::encode(calldata),
+            "a9059cbb000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        );
+    }
+
+    #[test]
+    fn test_encode_bytes() {
+        assert_eq!(encode_bytes(&[]), encode_u256(U256::zero()).to_vec());
+
+        let encoded = encode_bytes(&[0x12, 0x34]);
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded[31], 2);
+        assert_eq!(encoded[32..34], [0x12, 0x34]);
+        assert!(encoded[34..].iter().all(|b| *b == 0));
+
+        // ちょうど32バイトの場合はゼロ埋めしない
+        assert_eq!(encode_bytes(&[0xff; 32]).len(), 64);
+    }
+
+    #[test]
+    fn test_encode_args_dynamic() {
+        // Solidity ドキュメントの f(uint256,uint32[],bytes10,bytes) の例
+        // (uint32[] の要素も32バイトワードなので encode_u256_array でエンコードできる)
+        let mut bytes10 = [0u8; 32];
+        bytes10[..10].copy_from_slice(b"1234567890");
+        let calldata = encode_call_with_args(
+            "f(uint256,uint32[],bytes10,bytes)",
+            &[
+                Arg::Word(encode_u256(U256::from(0x123))),
+                Arg::Dynamic(encode_u256_array(&[U256::from(0x456), U256::from(0x789)])),
+                Arg::Word(bytes10),
+                Arg::Dynamic(encode_bytes(b"Hello, world!")),
+            ],
+        );
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "8be65246",
+                "0000000000000000000000000000000000000000000000000000000000000123",
+                "0000000000000000000000000000000000000000000000000000000000000080",
+                "3132333435363738393000000000000000000000000000000000000000000000",
+                "00000000000000000000000000000000000000000000000000000000000000e0",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000456",
+                "0000000000000000000000000000000000000000000000000000000000000789",
+                "000000000000000000000000000000000000000000000000000000000000000d",
+                "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    fn encode(signature: &str, args: Value) -> Result<String> {
+        let function = Function::parse(signature)?;
+        let calldata = function.encode_call(args.as_array().unwrap())?;
+        Ok(hex::encode(calldata))
+    }
+
+    #[test]
+    fn test_param_type_parse() {
+        assert_eq!(ParamType::parse("uint").unwrap(), ParamType::Uint(256));
+        assert_eq!(ParamType::parse("int8").unwrap(), ParamType::Int(8));
+        assert_eq!(
+            ParamType::parse("bytes32").unwrap(),
+            ParamType::FixedBytes(32)
+        );
+        assert_eq!(
+            ParamType::parse("address[]").unwrap(),
+            ParamType::Array(Box::new(ParamType::Address))
+        );
+        assert_eq!(
+            ParamType::parse("uint256[2][]").unwrap(),
+            ParamType::Array(Box::new(ParamType::FixedArray(
+                Box::new(ParamType::Uint(256)),
+                2
+            )))
+        );
+        assert_eq!(
+            ParamType::parse("(address to, uint value)[]")
+                .unwrap()
+                .to_string(),
+            "(address,uint256)[]"
+        );
+        assert_eq!(
+            ParamType::parse("tuple(bool,string)").unwrap().to_string(),
+            "(bool,string)"
+        );
+
+        for invalid in [
+            "uint7",
+            "uint264",
+            "bytes0",
+            "bytes33",
+            "foo",
+            "uint256[x]",
+            "(uint256",
+        ] {
+            assert!(ParamType::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_param_type_is_dynamic() {
+        let dynamic = [
+            "bytes",
+            "string",
+            "uint256[]",
+            "string[2]",
+            "(uint256,bytes)",
+        ];
+        let fixed = ["address", "bytes32", "uint256[2]", "(uint256,address)[3]"];
+
+        for type_name in dynamic {
+            assert!(
+                ParamType::parse(type_name).unwrap().is_dynamic(),
+                "{type_name}"
+            );
+        }
+        for type_name in fixed {
+            assert!(
+                !ParamType::parse(type_name).unwrap().is_dynamic(),
+                "{type_name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_function_parse() {
+        let function = Function::parse("transfer(address to, uint amount)").unwrap();
+        assert_eq!(function.name, "transfer");
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+
+        assert_eq!(Function::parse("decimals()").unwrap().inputs, vec![]);
+        assert!(Function::parse("transfer").is_err());
+        assert!(Function::parse("(address)").is_err());
+        assert!(Function::parse("transfer(address)[]").is_err());
+    }
+
+    #[test]
+    fn test_encode_call_static() {
+        // Solidity ドキュメントの baz(uint32,bool) の例
+        assert_eq!(
+            encode("baz(uint32,bool)", serde_json::json!([69, true])).unwrap(),
+            concat!(
+                "cdcd77c0",
+                "0000000000000000000000000000000000000000000000000000000000000045",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+            )
+        );
+
+        // bar(bytes3[2])
+        assert_eq!(
+            encode(
+                "bar(bytes3[2])",
+                serde_json::json!([["0x616263", "0x646566"]])
+            )
+            .unwrap(),
+            concat!(
+                "fce353f6",
+                "6162630000000000000000000000000000000000000000000000000000000000",
+                "6465660000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_call_dynamic() {
+        // Solidity ドキュメントの sam(bytes,bool,uint256[]) の例
+        assert_eq!(
+            encode(
+                "sam(bytes,bool,uint256[])",
+                serde_json::json!(["0x64617665", "true", [1, 2, "0x3"]])
+            )
+            .unwrap(),
+            concat!(
+                "a5643bf2",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000004",
+                "6461766500000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_call_nested_dynamic() {
+        // Solidity ドキュメントの g(uint256[][],string[]) の例
+        assert_eq!(
+            encode(
+                "g(uint256[][],string[])",
+                serde_json::json!([[[1, 2], [3]], ["one", "two", "three"]])
+            )
+            .unwrap(),
+            concat!(
+                "2289b18c",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000140",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "00000000000000000000000000000000000000000000000000000000000000e0",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "6f6e650000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "74776f0000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000005",
+                "7468726565000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_call_tuples() {
+        // 静的なタプルは head にそのまま置かれる
+        let static_tuple =
+            encode("f((uint256,bool),uint8)", serde_json::json!([[1, true], 2])).unwrap();
+        assert_eq!(
+            &static_tuple[8..],
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+            )
+        );
+
+        // 動的なタプルはオフセットで参照される
+        let dynamic_tuple = encode(
+            "f((uint256,string),uint8)",
+            serde_json::json!([[1, "a"], 2]),
+        )
+        .unwrap();
+        assert_eq!(
+            &dynamic_tuple[8..],
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "6100000000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_call_errors() {
+        // 引数の数が合わない
+        assert!(encode("transfer(address,uint256)", serde_json::json!(["0x00"])).is_err());
+        // 範囲外
+        assert!(encode("f(uint8)", serde_json::json!([256])).is_err());
+        assert!(encode("f(int8)", serde_json::json!(["-129"])).is_err());
+        // 固定長配列の要素数
+        assert!(encode("f(uint256[2])", serde_json::json!([[1]])).is_err());
+
+        // エラーメッセージに引数の位置が含まれる
+        match encode("f(uint256,address)", serde_json::json!([1, "0x1234"])) {
+            Err(Error::InvalidAbiData(message)) => {
+                assert!(message.starts_with("argument 1 (address)"), "{message}")
+            }
+            result => panic!("Expected InvalidAbiData, got: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_encode_atomic_int() {
+        assert_eq!(
+            encode_atomic(&ParamType::Int(256), &serde_json::json!(-1)).unwrap(),
+            [0xff; 32]
+        );
+        assert_eq!(
+            encode_atomic(&ParamType::Int(8), &"-128".into()).unwrap()[31],
+            0x80
+        );
+        assert!(encode_atomic(&ParamType::Bytes, &"0x".into()).is_err());
+
+        // u64 を超える数値も桁を落とさない
+        let large: Value = serde_json::from_str("1000000000000000000000").unwrap();
+        assert_eq!(
+            U256::from_big_endian(&encode_atomic(&ParamType::Uint(256), &large).unwrap()),
+            U256::from(1_000_000_000_000_000_000_000u128)
+        );
+        let negative: Value = serde_json::from_str("-1000000000000000000000").unwrap();
+        assert_eq!(
+            encode_atomic(&ParamType::Int(256), &negative).unwrap(),
+            encode_atomic(&ParamType::Int(256), &"-1000000000000000000000".into()).unwrap()
+        );
+        assert!(encode_atomic(&ParamType::Uint(256), &serde_json::json!(1.5)).is_err());
+    }
+
+    #[test]
+    fn test_split_top_level() {
+        assert_eq!(split_top_level("").unwrap(), Vec::<&str>::new());
+        assert_eq!(
+            split_top_level("1, [2,3], (4,[5]), \"a,b\"").unwrap(),
+            vec!["1", "[2,3]", "(4,[5])", "\"a,b\""]
+        );
+        assert!(split_top_level("[1,2").is_err());
+        assert!(split_top_level("1)").is_err());
+        assert!(split_top_level("\"a").is_err());
+    }
+
+    #[test]
+    fn test_decode_u256() {
+        let word = encode_u256(U256::from(42));
+        assert_eq!(decode_u256(&word).unwrap(), U256::from(42));
+        assert!(decode_u256(&[0u8; 31]).is_err());
+    }
+
+    #[test]
+    fn test_decode_string() {
+        // "USD Coin" をABIエンコードしたもの
+        let data = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000008",
+            "55534420436f696e000000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+
+        assert_eq!(decode_string(&data).unwrap(), "USD Coin");
+    }
+
+    #[test]
+    fn test_decode_string_out_of_range() {
+        let data = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "55534420436f696e000000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            decode_string(&data),
+            Err(Error::InvalidAbiData(_))
+        ));
+        assert!(decode_string(&[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_string_overflow() {
+        let word = |value: U256| encode_u256(value).to_vec();
+        let max = U256::from(usize::MAX);
+
+        // オフセットが usize::MAX 付近
+        for offset in [max, max - 31, max - 32] {
+            assert!(matches!(
+                decode_string(&word(offset)),
+                Err(Error::InvalidAbiData(_))
+            ));
+        }
+        // 長さが usize::MAX 付近
+        for length in [max, max - 63] {
+            let data = [word(U256::from(32)), word(length)].concat();
+            assert!(matches!(
+                decode_string(&data),
+                Err(Error::InvalidAbiData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::signer::keccak256;
+use ethereum_types::H160;
+use k256::ecdsa::{SigningKey, VerifyingKey};
+
+// 秘密鍵に対応するアドレス
+pub fn from_signing_key(signing_key: &SigningKey) -> H160 {
+    from_verifying_key(signing_key.verifying_key())
+}
+
+// 公開鍵に対応するアドレス (非圧縮公開鍵のKeccak-256ハッシュの下位20バイト)
+pub fn from_verifying_key(verifying_key: &VerifyingKey) -> H160 {
+    let public_key = verifying_key.to_encoded_point(false);
+    // 先頭の 0x04 (非圧縮形式を表すタグ) を除いた64バイトをハッシュ
+    let hash = keccak256(&public_key.as_bytes()[1..]);
+    H160::from_slice(&hash[12..])
+}
+
+// EIP-55 のチェックサム付きアドレス文字列
+// 小文字16進数表現のKeccak-256ハッシュの各ニブルが8以上なら大文字にする
+pub fn to_checksum(address: &H160) -> String {
+    let lower = hex::encode(address.as_bytes());
+    let hash = keccak256(lower.as_bytes());
+
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    format!("0x{checksummed}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+
+    #[test]
+    fn test_from_signing_key() {
+        assert_eq!(
+            from_signing_key(&test_signing_key()),
+            TEST_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_checksum_eip55_vectors() {
+        // EIP-55 のテストケース
+        for expected in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address: H160 = expected.parse().unwrap();
+            assert_eq!(to_checksum(&address), expected);
+        }
+    }
+
+    #[test]
+    fn test_to_checksum_signer_address() {
+        let address = from_signing_key(&test_signing_key());
+        assert_eq!(to_checksum(&address), TEST_ADDRESS);
+    }
+
+    #[test]
+    fn test_to_checksum_zero() {
+        assert_eq!(
+            to_checksum(&H160::zero()),
+            "0x0000000000000000000000000000000000000000"
+        );
+    }
+}
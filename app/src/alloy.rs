@@ -0,0 +1,336 @@
+use alloy_primitives::{Address, B256, TxKind};
+use alloy_rlp::{Encodable, RlpEncodable};
+use ethereum::{
+    AccessListItem, EIP1559Transaction, EIP1559TransactionMessage, LegacyTransaction,
+    LegacyTransactionMessage, TransactionAction,
+};
+use ethereum_types::{H160, H256, U256};
+
+// alloy フィーチャーで ethereum / ethereum_types / rlp の代わりに使う alloy-rs の型とエンコーダ
+// 内部の型はそのままで、エンコードの直前に alloy の型に変換する (数値は U256 のままで範囲は変わらない)
+// 公開している API の型は変わらないため、alloy-rs を使うコードとの受け渡しには以下の変換を使う
+
+pub fn address(address: &H160) -> Address {
+    Address::from(address.0)
+}
+
+pub fn b256(hash: &H256) -> B256 {
+    B256::from(hash.0)
+}
+
+// どちらもリトルエンディアンの u64 の limb
+pub fn u256(value: &U256) -> alloy_primitives::U256 {
+    alloy_primitives::U256::from_limbs(value.0)
+}
+
+pub fn tx_kind(action: &TransactionAction) -> TxKind {
+    match action {
+        TransactionAction::Call(to) => TxKind::Call(address(to)),
+        TransactionAction::Create => TxKind::Create,
+    }
+}
+
+#[derive(RlpEncodable)]
+struct AlloyAccessListItem {
+    address: Address,
+    storage_keys: Vec<B256>,
+}
+
+fn access_list(items: &[AccessListItem]) -> Vec<AlloyAccessListItem> {
+    items
+        .iter()
+        .map(|item| AlloyAccessListItem {
+            address: address(&item.address),
+            storage_keys: item.storage_keys.iter().map(b256).collect(),
+        })
+        .collect()
+}
+
+// EIP-1559 の署名前のフィールド (calldata はコピーしない)
+#[derive(RlpEncodable)]
+struct Eip1559Fields<'a> {
+    chain_id: u64,
+    nonce: alloy_primitives::U256,
+    max_priority_fee_per_gas: alloy_primitives::U256,
+    max_fee_per_gas: alloy_primitives::U256,
+    gas_limit: alloy_primitives::U256,
+    to: TxKind,
+    value: alloy_primitives::U256,
+    input: &'a [u8],
+    access_list: Vec<AlloyAccessListItem>,
+}
+
+#[derive(RlpEncodable)]
+struct SignedEip1559Fields<'a> {
+    chain_id: u64,
+    nonce: alloy_primitives::U256,
+    max_priority_fee_per_gas: alloy_primitives::U256,
+    max_fee_per_gas: alloy_primitives::U256,
+    gas_limit: alloy_primitives::U256,
+    to: TxKind,
+    value: alloy_primitives::U256,
+    input: &'a [u8],
+    access_list: Vec<AlloyAccessListItem>,
+    odd_y_parity: bool,
+    r: alloy_primitives::U256,
+    s: alloy_primitives::U256,
+}
+
+// EIP-155 の署名前のフィールド (chain_id の後に 0, 0)
+#[derive(RlpEncodable)]
+struct LegacyFields<'a> {
+    nonce: alloy_primitives::U256,
+    gas_price: alloy_primitives::U256,
+    gas_limit: alloy_primitives::U256,
+    to: TxKind,
+    value: alloy_primitives::U256,
+    input: &'a [u8],
+    chain_id: u64,
+    zero1: u8,
+    zero2: u8,
+}
+
+// chain id のない (EIP-155 以前の) 署名前のフィールド
+#[derive(RlpEncodable)]
+struct PreEip155Fields<'a> {
+    nonce: alloy_primitives::U256,
+    gas_price: alloy_primitives::U256,
+    gas_limit: alloy_primitives::U256,
+    to: TxKind,
+    value: alloy_primitives::U256,
+    input: &'a [u8],
+}
+
+// 署名済みの Legacy のフィールド (v は EIP-155 の chain_id * 2 + 35 + y_parity)
+#[derive(RlpEncodable)]
+struct SignedLegacyFields<'a> {
+    nonce: alloy_primitives::U256,
+    gas_price: alloy_primitives::U256,
+    gas_limit: alloy_primitives::U256,
+    to: TxKind,
+    value: alloy_primitives::U256,
+    input: &'a [u8],
+    v: u64,
+    r: alloy_primitives::U256,
+    s: alloy_primitives::U256,
+}
+
+// Type 2 のペイロード (0x02 の後の RLP) を書き込めるトランザクション
+pub trait Eip1559Encodable {
+    fn encode_rlp(&self, out: &mut Vec<u8>);
+}
+
+impl Eip1559Encodable for EIP1559TransactionMessage {
+    fn encode_rlp(&self, out: &mut Vec<u8>) {
+        Eip1559Fields {
+            chain_id: self.chain_id,
+            nonce: u256(&self.nonce),
+            max_priority_fee_per_gas: u256(&self.max_priority_fee_per_gas),
+            max_fee_per_gas: u256(&self.max_fee_per_gas),
+            gas_limit: u256(&self.gas_limit),
+            to: tx_kind(&self.action),
+            value: u256(&self.value),
+            input: &self.input,
+            access_list: access_list(&self.access_list),
+        }
+        .encode(out);
+    }
+}
+
+impl Eip1559Encodable for EIP1559Transaction {
+    fn encode_rlp(&self, out: &mut Vec<u8>) {
+        SignedEip1559Fields {
+            chain_id: self.chain_id,
+            nonce: u256(&self.nonce),
+            max_priority_fee_per_gas: u256(&self.max_priority_fee_per_gas),
+            max_fee_per_gas: u256(&self.max_fee_per_gas),
+            gas_limit: u256(&self.gas_limit),
+            to: tx_kind(&self.action),
+            value: u256(&self.value),
+            input: &self.input,
+            access_list: access_list(&self.access_list),
+            odd_y_parity: self.odd_y_parity,
+            r: alloy_primitives::U256::from_be_bytes(self.r.0),
+            s: alloy_primitives::U256::from_be_bytes(self.s.0),
+        }
+        .encode(out);
+    }
+}
+
+// 署名前の Legacy のトランザクション (RLP)
+pub fn encode_legacy_message(message: &LegacyTransactionMessage) -> Vec<u8> {
+    let mut out = Vec::new();
+    match message.chain_id {
+        Some(chain_id) => LegacyFields {
+            nonce: u256(&message.nonce),
+            gas_price: u256(&message.gas_price),
+            gas_limit: u256(&message.gas_limit),
+            to: tx_kind(&message.action),
+            value: u256(&message.value),
+            input: &message.input,
+            chain_id,
+            zero1: 0,
+            zero2: 0,
+        }
+        .encode(&mut out),
+        None => PreEip155Fields {
+            nonce: u256(&message.nonce),
+            gas_price: u256(&message.gas_price),
+            gas_limit: u256(&message.gas_limit),
+            to: tx_kind(&message.action),
+            value: u256(&message.value),
+            input: &message.input,
+        }
+        .encode(&mut out),
+    }
+    out
+}
+
+// 署名済みの Legacy の raw トランザクション (RLP)
+pub fn encode_legacy(transaction: &LegacyTransaction) -> Vec<u8> {
+    let mut out = Vec::new();
+    SignedLegacyFields {
+        nonce: u256(&transaction.nonce),
+        gas_price: u256(&transaction.gas_price),
+        gas_limit: u256(&transaction.gas_limit),
+        to: tx_kind(&transaction.action),
+        value: u256(&transaction.value),
+        input: &transaction.input,
+        v: transaction.signature.v(),
+        r: alloy_primitives::U256::from_be_bytes(transaction.signature.r().0),
+        s: alloy_primitives::U256::from_be_bytes(transaction.signature.s().0),
+    }
+    .encode(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum::TransactionSignature;
+
+    fn messages() -> Vec<EIP1559TransactionMessage> {
+        let message = EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(1),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(H160::repeat_byte(0x74)),
+            value: U256::one(),
+            input: vec![],
+            access_list: vec![],
+        };
+        vec![
+            message.clone(),
+            EIP1559TransactionMessage {
+                action: TransactionAction::Create,
+                value: U256::zero(),
+                input: vec![0x60, 0x80, 0x60, 0x40],
+                ..message.clone()
+            },
+            EIP1559TransactionMessage {
+                chain_id: u64::MAX,
+                nonce: U256::MAX,
+                max_priority_fee_per_gas: U256::MAX,
+                max_fee_per_gas: U256::MAX,
+                gas_limit: U256::MAX,
+                value: U256::MAX,
+                input: vec![0xff; 1000],
+                access_list: vec![
+                    AccessListItem {
+                        address: H160::repeat_byte(0xff),
+                        storage_keys: vec![H256::repeat_byte(0xff), H256::zero()],
+                    },
+                    AccessListItem {
+                        address: H160::zero(),
+                        storage_keys: vec![],
+                    },
+                ],
+                ..message
+            },
+        ]
+    }
+
+    #[test]
+    fn test_conversions() {
+        let value = U256::from_dec_str("340282366920938463463374607431768211457").unwrap();
+        assert_eq!(
+            u256(&value).to_string(),
+            "340282366920938463463374607431768211457"
+        );
+        assert_eq!(
+            address(&H160::repeat_byte(0x12)),
+            Address::repeat_byte(0x12)
+        );
+        assert_eq!(tx_kind(&TransactionAction::Create), TxKind::Create);
+    }
+
+    #[test]
+    fn test_eip1559_same_as_rlp() {
+        for message in messages() {
+            let mut out = Vec::new();
+            message.encode_rlp(&mut out);
+            assert_eq!(out, rlp::encode(&message).to_vec());
+
+            // r, s の先頭の 0 は省かれる
+            let transaction = EIP1559Transaction {
+                chain_id: message.chain_id,
+                nonce: message.nonce,
+                max_priority_fee_per_gas: message.max_priority_fee_per_gas,
+                max_fee_per_gas: message.max_fee_per_gas,
+                gas_limit: message.gas_limit,
+                action: message.action,
+                value: message.value,
+                input: message.input.clone(),
+                access_list: message.access_list.clone(),
+                odd_y_parity: true,
+                r: H256::from_low_u64_be(0x1234),
+                s: H256::repeat_byte(0x7f),
+            };
+            let mut out = Vec::new();
+            transaction.encode_rlp(&mut out);
+            assert_eq!(out, rlp::encode(&transaction).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_legacy_same_as_rlp() {
+        for message in messages() {
+            for chain_id in [Some(message.chain_id), None] {
+                let legacy = LegacyTransactionMessage {
+                    nonce: message.nonce,
+                    gas_price: message.max_fee_per_gas,
+                    gas_limit: message.gas_limit,
+                    action: message.action,
+                    value: message.value,
+                    input: message.input.clone(),
+                    chain_id,
+                };
+                assert_eq!(
+                    encode_legacy_message(&legacy),
+                    rlp::encode(&legacy).to_vec()
+                );
+            }
+
+            let transaction = LegacyTransaction {
+                nonce: message.nonce,
+                gas_price: message.max_fee_per_gas,
+                gas_limit: message.gas_limit,
+                action: message.action,
+                value: message.value,
+                input: message.input.clone(),
+                signature: TransactionSignature::new(
+                    37,
+                    H256::repeat_byte(1),
+                    H256::repeat_byte(2),
+                )
+                .unwrap(),
+            };
+            assert_eq!(
+                encode_legacy(&transaction),
+                rlp::encode(&transaction).to_vec()
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::Result;
+use serde_json::Value;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+// 署名要求ごとに外部コマンドで承認を得るフック
+// 要求の JSON を標準入力に渡し、終了コード 0 なら承認、それ以外は拒否
+#[derive(Debug, Clone)]
+pub struct ApprovalHook {
+    command: String,
+}
+
+impl ApprovalHook {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    pub fn approve(&self, request: &Value) -> Result<bool> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        // コマンドが標準入力を読まずに終了した場合の書き込みエラーは無視する
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(request.to_string().as_bytes());
+        }
+
+        Ok(child.wait()?.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_approve_by_exit_status() {
+        let request = json!({ "method": "account_signTransaction" });
+
+        assert!(ApprovalHook::new("true").approve(&request).unwrap());
+        assert!(!ApprovalHook::new("false").approve(&request).unwrap());
+        assert!(!ApprovalHook::new("exit 3").approve(&request).unwrap());
+    }
+
+    #[test]
+    fn test_approve_reads_request_from_stdin() {
+        let hook = ApprovalHook::new("grep -q '\"method\":\"account_signTransaction\"'");
+
+        assert!(
+            hook.approve(&json!({ "method": "account_signTransaction" }))
+                .unwrap()
+        );
+        assert!(
+            !hook
+                .approve(&json!({ "method": "eth_sendTransaction" }))
+                .unwrap()
+        );
+    }
+}
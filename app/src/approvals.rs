@@ -0,0 +1,372 @@
+use crate::{
+    Result,
+    secret::Secret,
+    signer::keccak256,
+    transaction::{self, TxType},
+};
+use ethereum::EIP1559TransactionMessage;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+// 承認待ちの一覧と操作の REST API
+pub const APPROVALS_PATH: &str = "/api/v1/approvals";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Pending,
+    // 承認されて署名中 (同時に承認されても1回だけ署名する)
+    Signing { approver: String },
+    Approved { approver: String, raw: Vec<u8> },
+    // reason は承認の時点のルールで拒否した理由
+    Rejected { by: String, reason: Option<String> },
+}
+
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    requester: String,
+    method: String,
+    message: EIP1559TransactionMessage,
+    created_at: u64,
+    status: Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalError {
+    NotFound,
+    // すでに承認・拒否されている
+    NotPending,
+    // 要求した本人は承認できない
+    SameOperator,
+}
+
+impl ApprovalError {
+    pub fn status_code(self) -> u16 {
+        match self {
+            Self::NotFound => 404,
+            Self::NotPending => 409,
+            Self::SameOperator => 403,
+        }
+    }
+}
+
+// 2人目のオペレーターの承認を待つ署名要求
+pub struct ApprovalQueue {
+    requests: Mutex<HashMap<String, PendingRequest>>,
+    counter: AtomicU64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl Default for ApprovalQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApprovalQueue {
+    pub fn new() -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    // 承認待ちに追加して ID を返す
+    pub fn submit(
+        &self,
+        requester: &str,
+        method: &str,
+        message: EIP1559TransactionMessage,
+    ) -> String {
+        // 推測されにくい ID (トランザクションのハッシュ、時刻、連番から作る)
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let seed = [
+            transaction::signing_hash(&message).as_bytes(),
+            &nanos.to_be_bytes(),
+            &counter.to_be_bytes(),
+        ]
+        .concat();
+        let id = hex::encode(&keccak256(seed).as_bytes()[..16]);
+
+        self.requests.lock().unwrap().insert(
+            id.clone(),
+            PendingRequest {
+                requester: requester.to_string(),
+                method: method.to_string(),
+                message,
+                created_at: now(),
+                status: Status::Pending,
+            },
+        );
+        id
+    }
+
+    fn to_json(id: &str, request: &PendingRequest) -> Value {
+        let mut json = json!({
+            "id": id,
+            "requester": request.requester,
+            "method": request.method,
+            "createdAt": request.created_at,
+            "transaction": transaction::message_json(&TxType::Eip1559, &request.message),
+        });
+        let status = match &request.status {
+            Status::Pending => "pending",
+            Status::Signing { approver } => {
+                json["approver"] = json!(approver);
+                "signing"
+            }
+            Status::Approved { approver, raw } => {
+                json["approver"] = json!(approver);
+                json["raw"] = json!(format!("0x{}", hex::encode(raw)));
+                "approved"
+            }
+            Status::Rejected { by, reason } => {
+                json["rejectedBy"] = json!(by);
+                if let Some(reason) = reason {
+                    json["reason"] = json!(reason);
+                }
+                "rejected"
+            }
+        };
+        json["status"] = json!(status);
+        json
+    }
+
+    // 承認待ちの要求 (古い順)
+    pub fn pending(&self) -> Vec<Value> {
+        let requests = self.requests.lock().unwrap();
+        let mut pending: Vec<_> = requests
+            .iter()
+            .filter(|(_, request)| request.status == Status::Pending)
+            .collect();
+        pending.sort_by_key(|(_, request)| request.created_at);
+        pending
+            .into_iter()
+            .map(|(id, request)| Self::to_json(id, request))
+            .collect()
+    }
+
+    // 要求の状態 (承認済みなら署名済みトランザクションを含む) と要求者
+    pub fn get(&self, id: &str) -> Option<(String, Value)> {
+        self.requests
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|request| (request.requester.clone(), Self::to_json(id, request)))
+    }
+
+    // 承認して署名するために取り出す (要求者と署名するトランザクション)
+    pub fn start_approval(
+        &self,
+        id: &str,
+        approver: &str,
+    ) -> std::result::Result<(String, EIP1559TransactionMessage), ApprovalError> {
+        let mut requests = self.requests.lock().unwrap();
+        let request = requests.get_mut(id).ok_or(ApprovalError::NotFound)?;
+        if request.status != Status::Pending {
+            return Err(ApprovalError::NotPending);
+        }
+        if request.requester == approver {
+            return Err(ApprovalError::SameOperator);
+        }
+
+        request.status = Status::Signing {
+            approver: approver.to_string(),
+        };
+        Ok((request.requester.clone(), request.message.clone()))
+    }
+
+    // 署名の結果を記録する (失敗した場合は承認待ちに戻す)
+    pub fn finish_approval(&self, id: &str, raw: Option<Vec<u8>>) -> Option<Value> {
+        let mut requests = self.requests.lock().unwrap();
+        let request = requests.get_mut(id)?;
+        let Status::Signing { approver } = &request.status else {
+            return None;
+        };
+
+        request.status = match raw {
+            Some(raw) => Status::Approved {
+                approver: approver.clone(),
+                raw,
+            },
+            None => Status::Pending,
+        };
+        Some(Self::to_json(id, request))
+    }
+
+    pub fn reject(&self, id: &str, by: &str) -> std::result::Result<Value, ApprovalError> {
+        let mut requests = self.requests.lock().unwrap();
+        let request = requests.get_mut(id).ok_or(ApprovalError::NotFound)?;
+        if request.status != Status::Pending {
+            return Err(ApprovalError::NotPending);
+        }
+
+        request.status = Status::Rejected {
+            by: by.to_string(),
+            reason: None,
+        };
+        Ok(Self::to_json(id, request))
+    }
+
+    // 承認の時点のポリシーなどで署名できなくなった要求を拒否する (承認待ちには戻さない)
+    pub fn reject_approval(&self, id: &str, reason: &str) -> Option<Value> {
+        let mut requests = self.requests.lock().unwrap();
+        let request = requests.get_mut(id)?;
+        let Status::Signing { approver } = &request.status else {
+            return None;
+        };
+
+        request.status = Status::Rejected {
+            by: approver.clone(),
+            reason: Some(reason.to_string()),
+        };
+        Some(Self::to_json(id, request))
+    }
+}
+
+// 署名サーバーの承認待ちの要求を承認 (または拒否) する
+pub fn decide(
+    server: &str,
+    id: &str,
+    token: Option<&Secret<String>>,
+    approve: bool,
+) -> Result<Value> {
+    let url = format!(
+        "{}{APPROVALS_PATH}/{id}/{}",
+        server.trim_end_matches('/'),
+        if approve { "approve" } else { "reject" }
+    );
+    let mut request = ureq::post(&url);
+    if let Some(token) = token {
+        request = request.header("Authorization", &format!("Bearer {}", token.expose()));
+    }
+
+    request
+        .send_empty()?
+        .body_mut()
+        .read_json()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum::{AccessList, TransactionAction};
+    use ethereum_types::{H160, U256};
+
+    fn message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 1,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::zero(),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(H160::zero()),
+            value: U256::from(10).pow(U256::from(18)),
+            input: Vec::new(),
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_approve() {
+        let queue = ApprovalQueue::new();
+        let id = queue.submit("alice", "eth_signTransaction", message());
+        assert_eq!(id.len(), 32);
+        assert_ne!(queue.submit("alice", "eth_signTransaction", message()), id);
+        assert_eq!(queue.pending().len(), 2);
+
+        // 要求した本人は承認できない
+        assert_eq!(
+            queue.start_approval(&id, "alice").unwrap_err(),
+            ApprovalError::SameOperator
+        );
+        let (requester, _) = queue.start_approval(&id, "bob").unwrap();
+        assert_eq!(requester, "alice");
+        // 署名中に重ねて承認できない
+        assert_eq!(
+            queue.start_approval(&id, "carol").unwrap_err(),
+            ApprovalError::NotPending
+        );
+
+        let json = queue.finish_approval(&id, Some(vec![0x02, 0xab])).unwrap();
+        assert_eq!(json["status"], "approved");
+        assert_eq!(json["approver"], "bob");
+        assert_eq!(json["raw"], "0x02ab");
+        assert_eq!(queue.pending().len(), 1);
+
+        let (requester, json) = queue.get(&id).unwrap();
+        assert_eq!(requester, "alice");
+        assert_eq!(json["transaction"]["value"], "0xde0b6b3a7640000");
+        assert!(queue.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_failed_signing_returns_to_pending() {
+        let queue = ApprovalQueue::new();
+        let id = queue.submit("alice", "eth_signTransaction", message());
+
+        queue.start_approval(&id, "bob").unwrap();
+        assert_eq!(
+            queue.finish_approval(&id, None).unwrap()["status"],
+            "pending"
+        );
+        assert!(queue.start_approval(&id, "bob").is_ok());
+    }
+
+    #[test]
+    fn test_reject_approval() {
+        let queue = ApprovalQueue::new();
+        let id = queue.submit("alice", "eth_signTransaction", message());
+        assert!(queue.reject_approval(&id, "denied").is_none());
+
+        queue.start_approval(&id, "bob").unwrap();
+        let json = queue.reject_approval(&id, "denied").unwrap();
+        assert_eq!(json["status"], "rejected");
+        assert_eq!(json["rejectedBy"], "bob");
+        assert_eq!(json["reason"], "denied");
+        assert_eq!(
+            queue.start_approval(&id, "bob").unwrap_err(),
+            ApprovalError::NotPending
+        );
+        assert!(queue.pending().is_empty());
+    }
+
+    #[test]
+    fn test_reject() {
+        let queue = ApprovalQueue::new();
+        let id = queue.submit("alice", "eth_signTransaction", message());
+
+        // 要求した本人も取り下げられる
+        assert_eq!(queue.reject(&id, "alice").unwrap()["rejectedBy"], "alice");
+        assert_eq!(
+            queue.reject(&id, "bob").unwrap_err(),
+            ApprovalError::NotPending
+        );
+        assert_eq!(
+            queue.start_approval(&id, "bob").unwrap_err(),
+            ApprovalError::NotPending
+        );
+        assert_eq!(
+            queue.start_approval("missing", "bob").unwrap_err(),
+            ApprovalError::NotFound
+        );
+        assert!(queue.pending().is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Result, abi};
+use ethereum_types::{H160, U256};
+
+// Arbitrum の NodeInterface (ノードだけが実装する仮想コントラクト)
+pub const NODE_INTERFACE: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0xc8,
+]);
+
+// NodeInterface.gasEstimateComponents の戻り値
+// Arbitrum の gas には L1 にデータを載せる分 (gas_estimate_for_l1) が含まれるため、
+// L1 のガス代と L2 の base fee が変わると必要な gas も変わる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasEstimate {
+    // L1 の分を含めた gas
+    pub gas_estimate: U256,
+    pub gas_estimate_for_l1: U256,
+    pub base_fee: U256,
+    pub l1_base_fee_estimate: U256,
+}
+
+// gasEstimateComponents(address to, bool contractCreation, bytes data) の calldata
+// eth_call の to を NODE_INTERFACE にし、from と value は送るトランザクションのものにする
+pub fn gas_estimate_components_calldata(to: Option<H160>, input: &[u8]) -> Vec<u8> {
+    abi::encode_call_with_args(
+        "gasEstimateComponents(address,bool,bytes)",
+        &[
+            abi::Arg::Word(abi::encode_address(&to.unwrap_or_default())),
+            abi::Arg::Word(abi::encode_u256(U256::from(to.is_none() as u8))),
+            abi::Arg::Dynamic(abi::encode_bytes(input)),
+        ],
+    )
+}
+
+// (uint64 gasEstimate, uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate)
+pub fn decode_gas_estimate(data: &[u8]) -> Result<GasEstimate> {
+    let word = |index: usize| abi::decode_u256(data.get(index * 32..).unwrap_or_default());
+    Ok(GasEstimate {
+        gas_estimate: word(0)?,
+        gas_estimate_for_l1: word(1)?,
+        base_fee: word(2)?,
+        l1_base_fee_estimate: word(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_estimate_components() {
+        let to: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+        let calldata = gas_estimate_components_calldata(Some(to), &[0xab]);
+        assert_eq!(
+            calldata[..4],
+            abi::selector("gasEstimateComponents(address,bool,bytes)")
+        );
+        assert_eq!(calldata[4 + 12..4 + 32], to.0);
+        assert_eq!(calldata[4 + 63], 0);
+        // デプロイは to を 0 にして contractCreation を立てる
+        let calldata = gas_estimate_components_calldata(None, &[]);
+        assert_eq!(calldata[4..4 + 32], [0; 32]);
+        assert_eq!(calldata[4 + 63], 1);
+
+        let data: Vec<u8> = [30_000u64, 9_000, 10_000_000, 20_000_000_000]
+            .into_iter()
+            .flat_map(|value| abi::encode_u256(U256::from(value)))
+            .collect();
+        assert_eq!(
+            decode_gas_estimate(&data).unwrap(),
+            GasEstimate {
+                gas_estimate: U256::from(30_000),
+                gas_estimate_for_l1: U256::from(9_000),
+                base_fee: U256::from(10_000_000),
+                l1_base_fee_estimate: U256::from(20_000_000_000u64),
+            }
+        );
+        assert!(decode_gas_estimate(&data[..96]).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    Result, csv,
+    error::Error,
+    journal::{Entry, Status},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+const VERSION: u32 = 1;
+
+// CSV の列 (to と value は読むときには使わず、raw から求める)
+const CSV_HEADER: [&str; 11] = [
+    "hash",
+    "chainId",
+    "from",
+    "nonce",
+    "to",
+    "value",
+    "status",
+    "requester",
+    "signedAt",
+    "updatedAt",
+    "raw",
+];
+
+// 別のマシンに持ち出すジャーナルのアーカイブ
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Archive {
+    version: u32,
+    entries: Vec<Entry>,
+}
+
+// 署名した時刻 (UNIX 時刻) が範囲内のエントリを書き出す
+pub fn export<P: AsRef<Path>>(
+    path: P,
+    entries: Vec<Entry>,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<usize> {
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| since.is_none_or(|since| entry.signed_at >= since))
+        .filter(|entry| until.is_none_or(|until| entry.signed_at < until))
+        .collect();
+
+    let content = if csv::is_csv(path.as_ref()) {
+        to_csv(&entries)
+    } else {
+        serde_json::to_string_pretty(&Archive {
+            version: VERSION,
+            entries: entries.clone(),
+        })? + "\n"
+    };
+    std::fs::write(path, content)?;
+
+    Ok(entries.len())
+}
+
+// アーカイブを読み込む (エントリは raw トランザクションから作り直して検証する)
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<Entry>> {
+    let content = std::fs::read_to_string(path.as_ref())?;
+    if csv::is_csv(path.as_ref()) {
+        return from_csv(&content);
+    }
+
+    let archive: Archive = serde_json::from_str(&content)
+        .map_err(|error| Error::InvalidJournal(format!("archive: {error}")))?;
+    if archive.version != VERSION {
+        return Err(Error::InvalidJournal(format!(
+            "unsupported archive version {}",
+            archive.version
+        )));
+    }
+    archive
+        .entries
+        .iter()
+        .map(|entry| {
+            let rebuilt = Entry::from_raw(
+                &entry.raw,
+                entry.from,
+                entry.status,
+                &entry.requester,
+                entry.signed_at,
+                entry.updated_at,
+            )?;
+            if rebuilt != *entry {
+                return Err(Error::InvalidJournal(format!(
+                    "entry {:?} does not match its raw transaction",
+                    entry.hash
+                )));
+            }
+            Ok(rebuilt)
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv(entries: &[Entry]) -> String {
+    let mut csv = CSV_HEADER.join(",") + "\n";
+    for entry in entries {
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        let row = [
+            format!("{:?}", entry.hash),
+            entry.chain_id.to_string(),
+            format!("{:?}", entry.from),
+            entry.nonce.to_string(),
+            text(&entry.transaction["to"]),
+            text(&entry.transaction["value"]),
+            entry.status.as_str().to_string(),
+            entry.requester.clone(),
+            entry.signed_at.to_string(),
+            entry.updated_at.to_string(),
+            entry.raw.clone(),
+        ];
+        let row: Vec<_> = row.iter().map(|field| csv_field(field)).collect();
+        csv += &(row.join(",") + "\n");
+    }
+    csv
+}
+
+fn from_csv(content: &str) -> Result<Vec<Entry>> {
+    let rows = csv::Records::new(content.as_bytes())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|error| Error::InvalidJournal(format!("archive: {error}")))?;
+    let mut rows = rows.into_iter().enumerate();
+    match rows.next() {
+        Some((_, header)) if header == CSV_HEADER => {}
+        _ => {
+            return Err(Error::InvalidJournal(format!(
+                "archive: the CSV header must be {}",
+                CSV_HEADER.join(",")
+            )));
+        }
+    }
+
+    rows.map(|(i, row)| {
+        let invalid = |message: String| Error::InvalidJournal(format!("line {}: {message}", i + 1));
+        let [
+            hash,
+            _,
+            from,
+            _,
+            _,
+            _,
+            status,
+            requester,
+            signed_at,
+            updated_at,
+            raw,
+        ] = <[String; 11]>::try_from(row)
+            .map_err(|row| invalid(format!("expected 11 fields, got {}", row.len())))?;
+        let status: Status = serde_json::from_value(Value::String(status.clone()))
+            .map_err(|_| invalid(format!("unknown status '{status}'")))?;
+        let from = from
+            .parse()
+            .map_err(|_| invalid(format!("invalid address '{from}'")))?;
+        let timestamp = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| invalid(format!("invalid timestamp '{value}'")))
+        };
+
+        let entry = Entry::from_raw(
+            &raw,
+            from,
+            status,
+            &requester,
+            timestamp(&signed_at)?,
+            timestamp(&updated_at)?,
+        )?;
+        if format!("{:?}", entry.hash) != hash.to_lowercase() {
+            return Err(invalid(format!(
+                "hash {hash} does not match the raw transaction"
+            )));
+        }
+        Ok(entry)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::tests::signed_entry;
+
+    fn entry(nonce: u64, signed_at: u64) -> Entry {
+        Entry {
+            requester: "payments, \"ops\"".to_string(),
+            signed_at,
+            updated_at: signed_at + 10,
+            ..signed_entry(nonce)
+        }
+    }
+
+    #[test]
+    fn test_export_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = vec![entry(0, 100), entry(1, 200), entry(2, 300)];
+
+        for name in ["archive.json", "archive.csv"] {
+            let path = dir.path().join(name);
+            assert_eq!(
+                export(&path, entries.clone(), Some(200), Some(300)).unwrap(),
+                1
+            );
+            assert_eq!(read(&path).unwrap(), vec![entries[1].clone()]);
+
+            export(&path, entries.clone(), None, None).unwrap();
+            assert_eq!(read(&path).unwrap(), entries);
+        }
+
+        let csv = std::fs::read_to_string(dir.path().join("archive.csv")).unwrap();
+        assert!(csv.starts_with("hash,chainId,from,nonce,to,value,status,"));
+        assert!(csv.contains(",0x1,signed,\"payments, \"\"ops\"\"\",100,110,0x02"));
+    }
+
+    #[test]
+    fn test_read_rejects_tampered_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.json");
+        export(&path, vec![entry(0, 100)], None, None).unwrap();
+
+        // raw と合わない nonce
+        let json = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"nonce\": \"0x0\"", "\"nonce\": \"0x5\"");
+        std::fs::write(&path, json).unwrap();
+        assert!(matches!(read(&path).unwrap_err(), Error::InvalidJournal(_)));
+
+        let path = dir.path().join("archive.csv");
+        export(&path, vec![entry(0, 100)], None, None).unwrap();
+        let csv = std::fs::read_to_string(&path)
+            .unwrap()
+            .replacen("0x", "0xff", 1);
+        std::fs::write(&path, csv).unwrap();
+        assert!(matches!(read(&path).unwrap_err(), Error::InvalidJournal(_)));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{Result, error::Error, signer::keccak256};
+use ethereum_types::H256;
+use serde_json::{Value, json};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+// 末尾の行を探すときに一度に読む量
+const TAIL_CHUNK_SIZE: u64 = 64 * 1024;
+
+// 署名要求に対する判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Signed,
+    Rejected,
+    // 2人目のオペレーターの承認待ち
+    PendingApproval,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Signed => "signed",
+            Self::Rejected => "rejected",
+            Self::PendingApproval => "pending_approval",
+        }
+    }
+}
+
+// 監査ログに記録する署名要求
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub requester: String,
+    pub method: String,
+    pub params: Value,
+    pub decision: Decision,
+    // 拒否の理由など
+    pub reason: Option<String>,
+    // 署名したトランザクションのハッシュ (メッセージ署名では署名したダイジェスト)
+    pub hash: Option<H256>,
+}
+
+impl Record {
+    // 監査ログのエントリや Webhook の通知に含める内容
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            "requester": self.requester,
+            "method": self.method,
+            "params": self.params,
+            "decision": self.decision.as_str(),
+        });
+        if let Some(reason) = &self.reason {
+            json["reason"] = json!(reason);
+        }
+        if let Some(hash) = self.hash {
+            json["txHash"] = json!(hash);
+        }
+        json
+    }
+}
+
+// 追記のみの監査ログ (JSON Lines)
+// 各エントリは直前のエントリのハッシュを含み、改ざんや削除は verify で検出できる
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    // 同じプロセス内の追記を直列化する
+    lock: Mutex<()>,
+}
+
+// エントリのハッシュ (hash 以外のフィールドの JSON に対して計算する)
+fn entry_hash(entry: &Value) -> H256 {
+    keccak256(entry.to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// ファイルの最後の行 (空のファイルなら None)
+fn last_line(file: &mut File) -> Result<Option<String>> {
+    let len = file.metadata()?.len();
+    let mut window = TAIL_CHUNK_SIZE;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start))?;
+        let mut tail = String::new();
+        file.read_to_string(&mut tail)?;
+
+        let trimmed = tail.trim_end_matches('\n');
+        match trimmed.rfind('\n') {
+            Some(i) => return Ok(Some(trimmed[i + 1..].to_string())),
+            None if start == 0 => {
+                return Ok(Some(trimmed.to_string()).filter(|line| !line.is_empty()));
+            }
+            None => window *= 2,
+        }
+    }
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    // エントリを追記してそのハッシュを返す
+    // 別のプロセスも同じファイルに書けるよう、直前のハッシュは毎回ファイルの末尾から読む
+    pub fn append(&self, record: &Record) -> Result<H256> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let (seq, prev_hash) = match last_line(&mut file)? {
+            Some(line) => {
+                let last: Value = serde_json::from_str(&line)?;
+                let seq = last["seq"].as_u64().ok_or_else(|| {
+                    Error::AuditLogTampered("the last entry has no seq".to_string())
+                })?;
+                let hash = serde_json::from_value::<H256>(last["hash"].clone()).map_err(|_| {
+                    Error::AuditLogTampered("the last entry has no hash".to_string())
+                })?;
+                (seq + 1, hash)
+            }
+            None => (0, H256::zero()),
+        };
+
+        let mut entry = record.to_json();
+        entry["seq"] = json!(seq);
+        entry["timestamp"] = json!(now());
+        entry["prevHash"] = json!(prev_hash);
+        let hash = entry_hash(&entry);
+        entry["hash"] = json!(hash);
+
+        file.write_all(format!("{entry}\n").as_bytes())?;
+        file.sync_data()?;
+
+        Ok(hash)
+    }
+}
+
+// ハッシュチェーンを検証し、エントリ数と最後のハッシュを返す
+// 末尾のエントリの削除は検出できないため、最後のハッシュを別の場所に控えておく
+pub fn verify<P: AsRef<Path>>(path: P) -> Result<(u64, H256)> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut count = 0;
+    let mut prev_hash = H256::zero();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let tampered = |reason: &str| Error::AuditLogTampered(format!("line {}: {reason}", i + 1));
+        if line.is_empty() {
+            return Err(tampered("empty line"));
+        }
+
+        let mut entry: Value =
+            serde_json::from_str(&line).map_err(|_| tampered("not a JSON entry"))?;
+        let hash = entry
+            .as_object_mut()
+            .and_then(|entry| entry.remove("hash"))
+            .and_then(|hash| serde_json::from_value::<H256>(hash).ok())
+            .ok_or_else(|| tampered("missing hash"))?;
+        if entry["seq"].as_u64() != Some(count) {
+            return Err(tampered(&format!("expected seq {count}")));
+        }
+        if serde_json::from_value::<H256>(entry["prevHash"].clone()).ok() != Some(prev_hash) {
+            return Err(tampered("prevHash does not match the previous entry"));
+        }
+        if entry_hash(&entry) != hash {
+            return Err(tampered("hash does not match the entry"));
+        }
+
+        count += 1;
+        prev_hash = hash;
+    }
+
+    Ok((count, prev_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(decision: Decision) -> Record {
+        Record {
+            requester: "payments".to_string(),
+            method: "eth_signTransaction".to_string(),
+            params: json!([{ "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "value": "0x1" }]),
+            decision,
+            reason: (decision == Decision::Rejected).then(|| "Request denied".to_string()),
+            hash: (decision == Decision::Signed).then(|| H256::repeat_byte(0xab)),
+        }
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::new(&path);
+
+        let first = log.append(&record(Decision::Signed)).unwrap();
+        log.append(&record(Decision::Rejected)).unwrap();
+        // 別のインスタンス (別のプロセス) からも続けて書ける
+        let last = AuditLog::new(&path)
+            .append(&record(Decision::PendingApproval))
+            .unwrap();
+
+        assert_eq!(verify(&path).unwrap(), (3, last));
+
+        let entries: Vec<Value> = lines(&path)
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries[0]["prevHash"], json!(H256::zero()));
+        assert_eq!(entries[0]["decision"], "signed");
+        assert_eq!(entries[0]["txHash"], json!(H256::repeat_byte(0xab)));
+        assert_eq!(entries[1]["prevHash"], json!(first));
+        assert_eq!(entries[1]["reason"], "Request denied");
+        assert_eq!(entries[2]["seq"], 2);
+        assert_eq!(entries[2]["decision"], "pending_approval");
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::new(&path);
+        for _ in 0..3 {
+            log.append(&record(Decision::Signed)).unwrap();
+        }
+        let original = lines(&path);
+        let write = |lines: &[String]| std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let error = || verify(&path).unwrap_err().to_string();
+
+        // 内容の書き換え
+        let mut edited = original.clone();
+        edited[1] = edited[1].replace("payments", "someone");
+        write(&edited);
+        assert!(error().contains("line 2: hash does not match"));
+
+        // 途中のエントリの削除
+        write(&[original[0].clone(), original[2].clone()]);
+        assert!(error().contains("line 2: expected seq 1"));
+
+        // ハッシュを付け直しても次のエントリの prevHash と合わない
+        let mut entry: Value = serde_json::from_str(&original[1]).unwrap();
+        entry["requester"] = json!("someone");
+        entry.as_object_mut().unwrap().remove("hash");
+        entry["hash"] = json!(entry_hash(&entry));
+        write(&[original[0].clone(), entry.to_string(), original[2].clone()]);
+        assert!(error().contains("line 3: prevHash does not match"));
+
+        write(&original);
+        assert_eq!(verify(&path).unwrap().0, 3);
+    }
+
+    #[test]
+    fn test_last_line_of_large_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::new(&path);
+        let mut large = record(Decision::Signed);
+        large.params = json!(format!("0x{}", "00".repeat(TAIL_CHUNK_SIZE as usize)));
+
+        log.append(&large).unwrap();
+        log.append(&large).unwrap();
+        assert_eq!(verify(&path).unwrap().0, 2);
+    }
+}
@@ -0,0 +1,787 @@
+use crate::{
+    Result, csv,
+    error::Error,
+    mmap::Mmap,
+    params::{Params, ParseOptions},
+    rpc::RpcClient,
+    sigcache::SignatureCache,
+    signer::Key,
+};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::H256;
+use serde::{
+    Deserializer,
+    de::{self, SeqAccess, Visitor},
+};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    num::NonZeroUsize,
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, SyncSender},
+    },
+};
+
+// 1度に読み込んで署名する件数 (ファイル全体は読み込まず、これだけずつ署名して出力する)
+pub const CHUNK_SIZE: usize = 10_000;
+
+// パラメータを先頭から1件ずつ読み込む
+// 別のスレッドで少しずつパースし、CHUNK_SIZE 件より先は読まずに待つ
+// 通常のファイルはメモリにマップする (数GBのファイルでも、内容を読み込み用のバッファにコピーしない)
+// パイプや FIFO (`<(gen)`、/dev/stdin) は長さが 0 に見えてマップできないため、先頭から順に読む
+// (CSV は読み終えてからパースする)
+// - JSON 配列 (params.json と同じ形式のオブジェクトの配列) か、1行に1つのオブジェクトを書いた NDJSON
+// - 拡張子が .csv なら、1行目に params.json のキーを並べた CSV
+// options の読み方でパースする (--strict-hex、--lenient)
+pub fn read_params<P: AsRef<Path>>(
+    path: P,
+    options: ParseOptions,
+) -> Result<impl Iterator<Item = Result<Params>>> {
+    let is_csv = csv::is_csv(path.as_ref());
+    let input = Input::open(&path).map_err(Error::in_file(&path))?;
+
+    let (sender, receiver) = mpsc::sync_channel(CHUNK_SIZE);
+    std::thread::spawn(move || {
+        let result = options.apply(|| match (input, is_csv) {
+            (Input::Map(map), true) => parse_csv(&map, &sender),
+            (Input::Map(map), false) => parse_json(
+                is_array(&map),
+                serde_json::Deserializer::from_slice(&map),
+                &sender,
+            ),
+            (Input::Stream(mut reader), true) => {
+                let mut buffer = Vec::new();
+                reader
+                    .read_to_end(&mut buffer)
+                    .map_err(|error| error.to_string())?;
+                parse_csv(&buffer, &sender)
+            }
+            (Input::Stream(mut reader), false) => {
+                let array = starts_with_bracket(&mut reader).map_err(|error| error.to_string())?;
+                parse_json(
+                    array,
+                    serde_json::Deserializer::from_reader(reader),
+                    &sender,
+                )
+            }
+        });
+        if let Err(message) = result {
+            let _ = sender.send(Err(Error::InvalidArgument(format!("batch: {message}"))));
+        }
+    });
+
+    Ok(receiver.into_iter().enumerate().map(|(i, params)| {
+        params?
+            .encode_function()
+            .map_err(|error| Error::InvalidArgument(format!("batch: transaction {i}: {error}")))
+    }))
+}
+
+// 読み込むファイル (通常のファイルだけマップし、それ以外は先頭から読む)
+enum Input {
+    Map(Mmap),
+    Stream(BufReader<File>),
+}
+
+impl Input {
+    fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(&path)?;
+        match file.metadata()?.is_file() {
+            true => Ok(Self::Map(Mmap::open(path)?)),
+            false => Ok(Self::Stream(BufReader::new(file))),
+        }
+    }
+}
+
+// 先頭の空白以外の1文字が '[' か
+fn is_array(input: &[u8]) -> bool {
+    input
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|&byte| byte == b'[')
+}
+
+// 先頭の空白を読み飛ばし、次の1文字が '[' か (その文字は読まずに残す)
+fn starts_with_bracket<R: BufRead>(reader: &mut R) -> std::io::Result<bool> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        let Some(&byte) = buffer.first() else {
+            return Ok(false);
+        };
+        if !byte.is_ascii_whitespace() {
+            return Ok(byte == b'[');
+        }
+        let spaces = buffer
+            .iter()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        reader.consume(spaces);
+    }
+}
+
+// array なら JSON 配列、それ以外は NDJSON
+// 受け取り側が止まった場合は終わる (エラーを送る相手もいない)
+fn parse_json<'de, R: serde_json::de::Read<'de>>(
+    array: bool,
+    mut deserializer: serde_json::Deserializer<R>,
+    sender: &SyncSender<Result<Params>>,
+) -> std::result::Result<(), String> {
+    let result = match array {
+        true => deserializer
+            .deserialize_seq(Forward(sender))
+            .and_then(|()| deserializer.end()),
+        false => deserializer.into_iter::<Params>().try_for_each(|params| {
+            let _ = sender.send(Ok(params?));
+            Ok(())
+        }),
+    };
+    result.map_err(|error| error.to_string())
+}
+
+// 1行目の列名を params.json のキーとして、2行目以降を1件ずつパースして送る
+// 空のフィールドは省略したものとし、args は JSON の配列で書く
+fn parse_csv(input: &[u8], sender: &SyncSender<Result<Params>>) -> std::result::Result<(), String> {
+    let mut records = csv::Records::new(input).enumerate();
+    let header = match records.next() {
+        Some((_, header)) => header.map_err(|error| format!("line 1: {error}"))?,
+        None => return Ok(()),
+    };
+    for (i, name) in header.iter().enumerate() {
+        if header[..i].contains(name) {
+            return Err(format!("line 1: duplicate column `{name}`"));
+        }
+    }
+
+    for (i, record) in records {
+        let at_line = |message: String| format!("line {}: {message}", i + 1);
+        let record = record.map_err(at_line)?;
+        // 空行は読み飛ばす
+        if record == [""] {
+            continue;
+        }
+        if record.len() != header.len() {
+            return Err(at_line(format!(
+                "expected {} fields, got {}",
+                header.len(),
+                record.len()
+            )));
+        }
+
+        let object = header
+            .iter()
+            .zip(record)
+            .filter(|(_, field)| !field.is_empty())
+            .map(|(name, field)| {
+                let value = match name.as_str() {
+                    "args" => serde_json::from_str(&field).map_err(|error| error.to_string())?,
+                    _ => Value::String(field),
+                };
+                Ok((name.clone(), value))
+            })
+            .collect::<std::result::Result<Map<_, _>, String>>()
+            .map_err(at_line)?;
+        let params = serde_json::from_value(Value::Object(object))
+            .map_err(|error| at_line(error.to_string()))?;
+        if sender.send(Ok(params)).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+// JSON 配列の要素を1件ずつパースして送る
+struct Forward<'a>(&'a SyncSender<Result<Params>>);
+
+impl<'de> Visitor<'de> for Forward<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of transaction parameters")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(params) = seq.next_element()? {
+            if self.0.send(Ok(params)).is_err() {
+                return Err(de::Error::custom("stopped reading"));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 既定の並列数 (CPU のコア数)
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+// 署名したトランザクションと、送信した場合はその結果
+#[derive(Debug)]
+pub struct Signed {
+    pub raw: Vec<u8>,
+    pub sent: Option<Result<H256>>,
+}
+
+// 送信先のノードと、同時に送信するリクエストの数
+#[derive(Debug, Clone, Copy)]
+pub struct Broadcast<'a> {
+    pub client: &'a RpcClient,
+    pub concurrency: usize,
+}
+
+// 署名・記録・送信を重ねて行い、結果を messages の順番で返す
+// - jobs 個のスレッドがハッシュの計算と署名を行う (signatures にある同じトランザクションは署名し直さない)
+// - 呼び出し元のスレッドが messages の順番に record を呼ぶ (記録できたものだけを送信する)
+// - broadcast があれば、concurrency 個のスレッドが記録済みのトランザクションを順番に送信する
+// 各段の間のキューには上限があり、送信が詰まれば署名も待つ
+// record が失敗すれば、それ以降は署名も送信もしない (記録済みのものは送信される場合がある)
+// rayon ではなくスコープ付きスレッドを使うのは、段の間を上限付きのキューでつなぎ、順番と背圧を自分で制御するため
+pub fn sign_pipeline(
+    messages: Vec<EIP1559TransactionMessage>,
+    key: &Key,
+    signatures: &SignatureCache,
+    jobs: usize,
+    broadcast: Option<Broadcast>,
+    mut record: impl FnMut(usize, &[u8]) -> Result<()>,
+) -> Result<Vec<Signed>> {
+    let jobs = jobs.clamp(1, messages.len().max(1));
+    let next = AtomicUsize::new(0);
+    let (send_sender, send_receiver) =
+        mpsc::sync_channel::<(usize, Vec<u8>)>(broadcast.map_or(0, |b| b.concurrency * 2));
+    let send_receiver = Mutex::new(send_receiver);
+
+    std::thread::scope(|scope| {
+        let (signed_sender, signed_receiver) = mpsc::sync_channel(jobs * 2);
+        for _ in 0..jobs {
+            let signed_sender = signed_sender.clone();
+            let (messages, next) = (&messages, &next);
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(message) = messages.get(i) else {
+                        return;
+                    };
+                    let raw = signatures.sign(key, message.clone());
+                    // 受け取り側が止まった (record が失敗した) 場合は終わる
+                    if signed_sender.send((i, raw)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(signed_sender);
+
+        let senders: Vec<_> = broadcast
+            .iter()
+            .flat_map(|broadcast| {
+                std::iter::repeat_n(broadcast.client, broadcast.concurrency.max(1))
+            })
+            .map(|client| {
+                let send_receiver = &send_receiver;
+                scope.spawn(move || {
+                    let mut sent = Vec::new();
+                    loop {
+                        let received = send_receiver.lock().unwrap().recv();
+                        let Ok((i, raw)) = received else {
+                            return sent;
+                        };
+                        sent.push((i, client.send_raw_transaction(&raw)));
+                    }
+                })
+            })
+            .collect();
+
+        // 署名は順不同で終わるため、順番が来るまで取っておく
+        let mut waiting = BTreeMap::new();
+        let mut raws = Vec::with_capacity(messages.len());
+        let result: Result<()> = (|| {
+            for (i, raw) in signed_receiver {
+                waiting.insert(i, raw?);
+                while let Some(raw) = waiting.remove(&raws.len()) {
+                    record(raws.len(), &raw)?;
+                    if broadcast.is_some() {
+                        // 送信スレッドは終わらないため失敗しない
+                        let _ = send_sender.send((raws.len(), raw.clone()));
+                    }
+                    raws.push(raw);
+                }
+            }
+            Ok(())
+        })();
+        drop(send_sender);
+
+        let mut sent: Vec<Option<Result<H256>>> = (0..raws.len()).map(|_| None).collect();
+        for handle in senders {
+            for (i, result) in handle.join().expect("broadcasting thread panicked") {
+                sent[i] = Some(result);
+            }
+        }
+        result?;
+
+        Ok(raws
+            .into_iter()
+            .zip(sent)
+            .map(|(raw, sent)| Signed { raw, sent })
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rpc::tests::MockServer,
+        signer::tests::{TEST_ADDRESS, test_signing_key},
+        transaction,
+    };
+    use ethereum::{AccessList, TransactionAction};
+    use ethereum_types::U256;
+    use serde_json::json;
+
+    fn message(nonce: u64) -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(nonce),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(TEST_ADDRESS.parse().unwrap()),
+            value: U256::from(nonce),
+            input: vec![],
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_sign_pipeline_keeps_order() {
+        let messages: Vec<_> = (0..25).map(message).collect();
+        let expected: Vec<_> = messages
+            .iter()
+            .map(|message| transaction::sign(message.clone(), &test_signing_key()).unwrap())
+            .collect();
+
+        for jobs in [1, 4, 64] {
+            let mut recorded = Vec::new();
+            let signed = sign_pipeline(
+                messages.clone(),
+                &Key::new(test_signing_key()),
+                &SignatureCache::default(),
+                jobs,
+                None,
+                |i, raw| {
+                    recorded.push((i, raw.to_vec()));
+                    Ok(())
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                signed
+                    .iter()
+                    .map(|signed| signed.raw.clone())
+                    .collect::<Vec<_>>(),
+                expected
+            );
+            assert!(signed.iter().all(|signed| signed.sent.is_none()));
+            assert_eq!(
+                recorded,
+                expected.iter().cloned().enumerate().collect::<Vec<_>>()
+            );
+        }
+        assert!(
+            sign_pipeline(
+                Vec::new(),
+                &Key::new(test_signing_key()),
+                &SignatureCache::default(),
+                4,
+                None,
+                |_, _| Ok(())
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_sign_pipeline_reuses_signatures() {
+        // 同じトランザクションは覚えている raw トランザクションを返す
+        let key = Key::new(test_signing_key());
+        let signatures = SignatureCache::default();
+        let messages: Vec<_> = (0..4).map(message).collect();
+        signatures.insert(
+            key.address(),
+            transaction::signing_hash(&messages[2]),
+            b"cached",
+        );
+
+        let signed =
+            sign_pipeline(messages.clone(), &key, &signatures, 2, None, |_, _| Ok(())).unwrap();
+        assert_eq!(signed[2].raw, b"cached");
+        for i in [0, 1, 3] {
+            assert_eq!(
+                signatures.get(key.address(), &transaction::signing_hash(&messages[i])),
+                Some(signed[i].raw.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_sign_pipeline_stops_when_record_fails() {
+        let messages: Vec<_> = (0..50).map(message).collect();
+        let mut recorded = 0;
+        let error = sign_pipeline(
+            messages,
+            &Key::new(test_signing_key()),
+            &SignatureCache::default(),
+            4,
+            None,
+            |i, _| {
+                if i == 10 {
+                    return Err(Error::InvalidArgument("disk full".to_string()));
+                }
+                recorded += 1;
+                Ok(())
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::InvalidArgument(_)));
+        assert_eq!(recorded, 10);
+    }
+
+    #[test]
+    fn test_sign_pipeline_broadcasts() {
+        let hash = H256::repeat_byte(0xab);
+        let server = MockServer::start(
+            (0..6)
+                .map(|i| match i {
+                    // 1件だけノードに拒否される
+                    3 => (
+                        200,
+                        r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"nonce too low"}}"#
+                            .to_string(),
+                    ),
+                    _ => MockServer::rpc_result(json!(hash)),
+                })
+                .collect(),
+        );
+        let client = RpcClient::new(&server.url);
+        let broadcast = Broadcast {
+            client: &client,
+            concurrency: 3,
+        };
+
+        let messages: Vec<_> = (0..6).map(message).collect();
+        let signed = sign_pipeline(
+            messages,
+            &Key::new(test_signing_key()),
+            &SignatureCache::default(),
+            2,
+            Some(broadcast),
+            |_, _| Ok(()),
+        )
+        .unwrap();
+        assert_eq!(signed.len(), 6);
+        let failed = signed
+            .iter()
+            .filter(|signed| matches!(signed.sent, Some(Err(Error::Rpc { .. }))))
+            .count();
+        assert_eq!(failed, 1);
+        assert!(
+            signed
+                .iter()
+                .filter_map(|signed| signed.sent.as_ref()?.as_ref().ok())
+                .all(|sent| *sent == hash)
+        );
+
+        // 記録したすべてのトランザクションが1度ずつ送信される
+        let mut sent: Vec<_> = server
+            .json_requests()
+            .into_iter()
+            .map(|request| {
+                assert_eq!(request["method"], "eth_sendRawTransaction");
+                request["params"][0].as_str().unwrap().to_string()
+            })
+            .collect();
+        sent.sort();
+        let mut expected: Vec<_> = signed
+            .iter()
+            .map(|signed| format!("0x{}", hex::encode(&signed.raw)))
+            .collect();
+        expected.sort();
+        assert_eq!(sent, expected);
+    }
+
+    fn read(path: &Path) -> Result<Vec<Params>> {
+        read_params(path, ParseOptions::default())?.collect()
+    }
+
+    #[test]
+    fn test_read_params() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.json");
+        let first = format!(
+            r#"{{ "to_address": "{TEST_ADDRESS}", "value": "0x1", "gas_limit": "0x5208" }}"#
+        );
+        let second = format!(
+            r#"{{ "nonce": 7, "to_address": "{TEST_ADDRESS}", "value": "0x0", "gas_limit": "0x15f90",
+                  "function": "transfer(address,uint256)", "args": ["{TEST_ADDRESS}", "1"] }}"#
+        )
+        .replace('\n', "");
+
+        // JSON 配列と NDJSON
+        for content in [
+            format!("\n  [\n{first},\n{second}\n]\n"),
+            format!("{first}\n{second}\n"),
+        ] {
+            std::fs::write(&path, content).unwrap();
+            let params = read(&path).unwrap();
+            assert_eq!(params.len(), 2);
+            assert_eq!(params[0].nonce, None);
+            assert_eq!(params[1].nonce, Some(U256::from(7)));
+            assert_eq!(&params[1].input[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        }
+
+        std::fs::write(&path, "").unwrap();
+        assert!(read(&path).unwrap().is_empty());
+        std::fs::write(&path, "[]").unwrap();
+        assert!(read(&path).unwrap().is_empty());
+
+        // 途中までのパラメータは読み込め、エラーには位置が入る
+        for content in [
+            format!("[{first},\n{{}}]"),
+            format!("[{first},\n{first}] x"),
+            format!("{first}\n{{}}"),
+            "{}".to_string(),
+        ] {
+            std::fs::write(&path, &content).unwrap();
+            let results: Vec<_> = read_params(&path, ParseOptions::default())
+                .unwrap()
+                .collect();
+            let Some(Err(Error::InvalidArgument(message))) = results.last() else {
+                panic!("expected an error for {content}");
+            };
+            assert!(message.contains("line"), "{message}");
+        }
+
+        std::fs::write(&path, format!(r#"[{{ "to_address": "{TEST_ADDRESS}", "value": "0x0", "gas_limit": "0x5208", "args": [1] }}]"#)).unwrap();
+        assert!(matches!(
+            read(&path).unwrap_err(),
+            Error::InvalidArgument(message) if message.starts_with("batch: transaction 0:")
+        ));
+    }
+
+    #[test]
+    fn test_read_params_fifo() {
+        let dir = tempfile::tempdir().unwrap();
+        let params = format!(
+            r#"{{ "to_address": "{TEST_ADDRESS}", "value": "0x1", "gas_limit": "0x5208" }}"#
+        );
+        let header = "to_address,value,gas_limit";
+        let row = format!("{TEST_ADDRESS},0x1,0x5208");
+
+        // パイプは長さが 0 に見えるが、書き込まれた内容を最後まで読む
+        for (name, content) in [
+            ("batch.json", format!("  [{params},\n{params}]")),
+            ("batch.ndjson", format!("{params}\n{params}\n")),
+            ("batch.csv", format!("{header}\n{row}\n{row}\n")),
+        ] {
+            let path = dir.path().join(name);
+            let c_path = std::ffi::CString::new(path.as_os_str().as_encoded_bytes()).unwrap();
+            // SAFETY: NUL で終わるパスを渡す
+            assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+            let writer = {
+                let path = path.clone();
+                std::thread::spawn(move || std::fs::write(path, content).unwrap())
+            };
+            let params = read(&path).unwrap();
+            writer.join().unwrap();
+            assert_eq!(params.len(), 2, "{name}");
+            assert_eq!(params[1].value, U256::one());
+        }
+    }
+
+    #[test]
+    fn test_read_params_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payouts.csv");
+        std::fs::write(
+            &path,
+            format!(
+                "to_address,value,gas_limit,nonce,function,args\r\n\
+                 {TEST_ADDRESS},0x1,0x5208,,,\r\n\
+                 \r\n\
+                 {TEST_ADDRESS},0x0,0x15f90,0x7,\"transfer(address,uint256)\",\"[\"\"{TEST_ADDRESS}\"\", \"\"1\"\"]\"\r\n"
+            ),
+        )
+        .unwrap();
+        let params = read(&path).unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].nonce, None);
+        assert_eq!(params[0].value, U256::from(1));
+        assert!(params[0].input.is_empty());
+        assert_eq!(params[1].nonce, Some(U256::from(7)));
+        assert_eq!(&params[1].input[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(read(&path).unwrap().is_empty());
+
+        // 途中までのパラメータは読み込め、エラーには行が入る
+        for (content, expected) in [
+            (
+                format!(
+                    "to_address,value,gas_limit\n{TEST_ADDRESS},0x1,0x5208\n{TEST_ADDRESS},0x1\n"
+                ),
+                "batch: line 3: expected 3 fields, got 2",
+            ),
+            (
+                format!(
+                    "to_address,value,gas_limit\n{TEST_ADDRESS},0x1,0x5208\n{TEST_ADDRESS},0x1,\n"
+                ),
+                "batch: line 3: missing field `gas_limit`",
+            ),
+            (
+                format!(
+                    "to_address,value,gas_limit\n{TEST_ADDRESS},0x1,0x5208\n\"{TEST_ADDRESS},0x1,0x5208\n"
+                ),
+                "batch: line 3: unterminated quoted field",
+            ),
+        ] {
+            std::fs::write(&path, &content).unwrap();
+            let results: Vec<_> = read_params(&path, ParseOptions::default())
+                .unwrap()
+                .collect();
+            assert_eq!(results.len(), 2);
+            assert!(results[0].is_ok());
+            let Err(Error::InvalidArgument(message)) = &results[1] else {
+                panic!("expected an error for {content}");
+            };
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[test]
+    fn test_read_params_strict_hex() {
+        // パースするスレッドでも 0x を必須にする
+        let strict_hex = ParseOptions {
+            strict_hex: true,
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        for (name, strict, lenient) in [
+            (
+                "batch.ndjson",
+                format!(
+                    r#"{{ "to_address": "{TEST_ADDRESS}", "value": 1000, "gas_limit": "0x5208" }}"#
+                ),
+                format!(
+                    r#"{{ "to_address": "{TEST_ADDRESS}", "value": "1000", "gas_limit": "0x5208" }}"#
+                ),
+            ),
+            (
+                "batch.csv",
+                format!("to_address,value,gas_limit\n{TEST_ADDRESS},0x3e8,0x5208\n"),
+                format!("to_address,value,gas_limit\n{TEST_ADDRESS},1000,0x5208\n"),
+            ),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, &strict).unwrap();
+            let params: Vec<_> = read_params(&path, strict_hex)
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(params[0].value, U256::from(1000));
+
+            std::fs::write(&path, &lenient).unwrap();
+            let decimal_strings = ParseOptions {
+                decimal_strings: true,
+                ..Default::default()
+            };
+            let params: Vec<_> = read_params(&path, decimal_strings)
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(params[0].value, U256::from(1000));
+            // --decimal-strings がなければ、以前の16進数と紛らわしいためエラー
+            assert!(read(&path).is_err());
+            let error = read_params(&path, strict_hex)
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap_err();
+            assert!(error.to_string().contains("'1000' is ambiguous"), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_read_params_unknown_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let lenient = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        for (name, content) in [
+            (
+                "batch.ndjson",
+                format!(
+                    r#"{{ "to_address": "{TEST_ADDRESS}", "value": 0, "gas_limit": 21000, "gaslimit": 30000 }}"#
+                ),
+            ),
+            (
+                "batch.csv",
+                format!("to_address,value,gas_limit,gaslimit\n{TEST_ADDRESS},0,0x5208,30000\n"),
+            ),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            let error = read(&path).unwrap_err();
+            assert!(
+                error.to_string().contains("unknown field `gaslimit`"),
+                "{error}"
+            );
+            let params: Vec<_> = read_params(&path, lenient)
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(params[0].gas_limit, U256::from(21000));
+        }
+
+        // CSV の同じ列名は --lenient でもエラー
+        let path = dir.path().join("duplicate.csv");
+        std::fs::write(
+            &path,
+            format!("to_address,value,gas_limit,value\n{TEST_ADDRESS},0,21000,1\n"),
+        )
+        .unwrap();
+        let error = read_params(&path, lenient)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("line 1: duplicate column `value`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_read_params_stops_early() {
+        // 読み込む側が止まれば、ファイルの残りはパースしない
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.ndjson");
+        let line = format!(
+            r#"{{ "to_address": "{TEST_ADDRESS}", "value": "0x1", "gas_limit": "0x5208" }}"#
+        );
+        std::fs::write(&path, format!("{line}\n").repeat(3 * CHUNK_SIZE)).unwrap();
+
+        let params: Vec<_> = read_params(&path, ParseOptions::default())
+            .unwrap()
+            .take(10)
+            .collect();
+        assert_eq!(params.len(), 10);
+    }
+}
@@ -0,0 +1,148 @@
+// 署名の負荷試験 (bench sign)
+// 合成したトランザクションに使い捨ての鍵で署名し、1秒あたりの署名数と1件ごとの時間を計る
+use crate::{Result, batch, error::Error, signer::keccak256, transaction};
+use ethereum::{AccessList, EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, U256};
+use k256::ecdsa::SigningKey;
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+// 負荷試験の結果
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub count: usize,
+    pub jobs: usize,
+    pub elapsed: Duration,
+    // 1件ごとの署名にかかった時間 (短い順)
+    latencies: Vec<Duration>,
+}
+
+impl Report {
+    pub fn signatures_per_second(&self) -> f64 {
+        self.count as f64 / self.elapsed.as_secs_f64()
+    }
+
+    // percent パーセンタイルの時間 (nearest-rank)
+    pub fn percentile(&self, percent: usize) -> Duration {
+        let rank = (self.latencies.len() * percent).div_ceil(100).max(1);
+        self.latencies[rank - 1]
+    }
+
+    pub fn max(&self) -> Duration {
+        self.latencies.last().copied().unwrap_or_default()
+    }
+}
+
+// 合成したトランザクション (calldata は calldata_size バイト、nonce と value は1件ごとに変える)
+pub fn synthetic_message(i: usize, calldata_size: usize) -> EIP1559TransactionMessage {
+    EIP1559TransactionMessage {
+        chain_id: 11155111,
+        nonce: U256::from(i),
+        max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+        max_fee_per_gas: U256::from(50_000_000_000u64),
+        gas_limit: U256::from(21000 + 16 * calldata_size),
+        action: TransactionAction::Call(H160::repeat_byte(0x42)),
+        value: U256::from(i + 1),
+        input: vec![0xab; calldata_size],
+        access_list: AccessList::default(),
+    }
+}
+
+// count 件を jobs 個のスレッドで署名する (sign-batch と同じく、各スレッドが次の1件を取りに行く)
+// PRIVATE_KEY には触れず、固定の使い捨ての鍵を使う
+pub fn sign(count: usize, jobs: usize, calldata_size: usize) -> Result<Report> {
+    let signing_key = SigningKey::from_slice(keccak256(b"bench sign").as_bytes())?;
+    let messages: Vec<_> = (0..count)
+        .map(|i| synthetic_message(i, calldata_size))
+        .collect();
+    let jobs = jobs.clamp(1, count.max(1));
+    let next = AtomicUsize::new(0);
+
+    let start = Instant::now();
+    let mut latencies = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs)
+            .map(|_| {
+                let (messages, next, signing_key) = (&messages, &next, &signing_key);
+                scope.spawn(move || {
+                    let mut latencies = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(message) = messages.get(i) else {
+                            return Ok::<_, Error>(latencies);
+                        };
+                        let start = Instant::now();
+                        transaction::sign(message.clone(), signing_key)?;
+                        latencies.push(start.elapsed());
+                    }
+                })
+            })
+            .collect();
+
+        let mut latencies = Vec::with_capacity(count);
+        for handle in handles {
+            latencies.extend(handle.join().expect("signing thread panicked")?);
+        }
+        Ok::<_, Error>(latencies)
+    })?;
+    let elapsed = start.elapsed();
+    latencies.sort_unstable();
+
+    Ok(Report {
+        count,
+        jobs,
+        elapsed,
+        latencies,
+    })
+}
+
+// --jobs がなければ1スレッドと既定の並列数で計り、並列にした効果も表示する
+pub fn print_sign(count: usize, jobs: Option<usize>, calldata_size: usize) -> Result<()> {
+    let mut runs = match jobs {
+        Some(jobs) => vec![jobs],
+        None => vec![1, batch::default_jobs()],
+    };
+    runs.dedup();
+    let mut reports: Vec<Report> = Vec::new();
+    for jobs in runs {
+        let report = sign(count, jobs, calldata_size)?;
+        println!(
+            "{} transactions, {} thread(s): {:.0} signatures/s in {:.2?} (latency p50 {:.2?}, p99 {:.2?}, max {:.2?})",
+            report.count,
+            report.jobs,
+            report.signatures_per_second(),
+            report.elapsed,
+            report.percentile(50),
+            report.percentile(99),
+            report.max()
+        );
+        if let Some(baseline) = reports.first().filter(|_| report.jobs > 1) {
+            println!(
+                "Speedup over 1 thread: {:.1}x",
+                report.signatures_per_second() / baseline.signatures_per_second()
+            );
+        }
+        reports.push(report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_report() {
+        let report = sign(40, 4, 68).unwrap();
+        assert_eq!(report.count, 40);
+        assert_eq!(report.jobs, 4);
+        assert_eq!(report.latencies.len(), 40);
+        assert!(report.percentile(50) <= report.percentile(99));
+        assert!(report.percentile(99) <= report.max());
+        assert!(report.signatures_per_second() > 0.0);
+
+        // 件数よりスレッドは増やさない
+        assert_eq!(sign(2, 8, 0).unwrap().jobs, 2);
+    }
+}
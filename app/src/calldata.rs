@@ -0,0 +1,225 @@
+use crate::{
+    Result,
+    abi::{self, Function, ParamType},
+    error::Error,
+    json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+// 関数呼び出しを記述したJSON
+// { "function": "transfer(address,uint256)", "args": ["0x...", "1000000"] }
+#[derive(Debug, Deserialize)]
+pub struct FunctionCall {
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<Value>,
+}
+
+impl FunctionCall {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        json::parse(&json_content).map_err(Error::in_file(path))
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Function::parse(&self.function)?.encode_call(&self.args)
+    }
+}
+
+// コマンドライン引数の文字列から calldata を作成
+pub fn encode_from_args(signature: &str, args: &[String]) -> Result<Vec<u8>> {
+    let function = Function::parse(signature)?;
+    function.encode_call(&parse_args(&function, args)?)
+}
+
+// コンストラクタの引数をエンコード (セレクタなし)
+// signature は "constructor(address,uint256)" の形式
+pub fn encode_constructor_args(signature: &str, args: &[String]) -> Result<Vec<u8>> {
+    let function = Function::parse(signature)?;
+    abi::encode_params(&function.inputs, &parse_args(&function, args)?)
+}
+
+fn parse_args(function: &Function, args: &[String]) -> Result<Vec<Value>> {
+    if args.len() != function.inputs.len() {
+        return Err(Error::InvalidArgument(format!(
+            "{} expects {} arguments, got {}",
+            function.signature(),
+            function.inputs.len(),
+            args.len()
+        )));
+    }
+
+    function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param_type, arg)| parse_arg(param_type, arg, false))
+        .collect()
+}
+
+// コマンドライン引数を型に合わせて JSON の値に変換
+// 配列とタプルは "[1,2]" や "(0x...,1)" のように書き、要素の文字列はダブルクォートで囲める
+fn parse_arg(param_type: &ParamType, arg: &str, nested: bool) -> Result<Value> {
+    match param_type {
+        ParamType::Array(item) | ParamType::FixedArray(item, _) => split_list(param_type, arg)?
+            .into_iter()
+            .map(|element| parse_arg(item, element, true))
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        ParamType::Tuple(components) => {
+            let elements = split_list(param_type, arg)?;
+            if elements.len() != components.len() {
+                return Err(Error::InvalidArgument(format!(
+                    "expected {} components for '{param_type}', got '{arg}'",
+                    components.len()
+                )));
+            }
+            components
+                .iter()
+                .zip(elements)
+                .map(|(component, element)| parse_arg(component, element, true))
+                .collect::<Result<_>>()
+                .map(Value::Array)
+        }
+        // 要素内の文字列はクォートを外す
+        _ if nested && arg.starts_with('"') => serde_json::from_str(arg)
+            .map_err(|_| Error::InvalidArgument(format!("invalid quoted string {arg}"))),
+        _ => Ok(Value::String(arg.to_string())),
+    }
+}
+
+// "[1,2]" や "(a,b)" を要素に分割
+fn split_list<'a>(param_type: &ParamType, arg: &'a str) -> Result<Vec<&'a str>> {
+    let trimmed = arg.trim();
+    let inner = trimmed
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .or_else(|| trimmed.strip_prefix('(').and_then(|s| s.strip_suffix(')')))
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "expected [...] or (...) for '{param_type}', got '{arg}'"
+            ))
+        })?;
+
+    abi::split_top_level(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_encode_from_args_transfer() {
+        let calldata = encode_from_args(
+            "transfer(address,uint256)",
+            &args(&[
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "1000000000000000000",
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(calldata),
+            "a9059cbb000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        );
+    }
+
+    #[test]
+    fn test_encode_constructor_args() {
+        let encoded = encode_constructor_args(
+            "constructor(address owner, uint256 supply)",
+            &args(&["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "1000"]),
+        )
+        .unwrap();
+
+        // セレクタは付かない
+        assert_eq!(
+            hex::encode(encoded),
+            concat!(
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "00000000000000000000000000000000000000000000000000000000000003e8",
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_nested() {
+        let param_type = ParamType::parse("(string,uint256[],bool)[]").unwrap();
+        let value = parse_arg(
+            &param_type,
+            r#"[("a,b", [1, 0x2], true), (plain, [], false)]"#,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!([["a,b", ["1", "0x2"], "true"], ["plain", [], "false"]])
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_top_level_string_is_raw() {
+        // 最上位の string 引数はクォートやカンマをそのまま使う
+        let value = parse_arg(&ParamType::String, r#""quoted", raw"#, false).unwrap();
+        assert_eq!(value, json!(r#""quoted", raw"#));
+    }
+
+    #[test]
+    fn test_parse_arg_errors() {
+        let tuple = ParamType::parse("(uint256,bool)").unwrap();
+        assert!(parse_arg(&tuple, "(1)", false).is_err());
+        assert!(parse_arg(&tuple, "(1,true,2)", false).is_err());
+        assert!(parse_arg(&ParamType::parse("uint256[]").unwrap(), "1,2", false).is_err());
+    }
+
+    #[test]
+    fn test_encode_from_args_count_mismatch() {
+        assert!(matches!(
+            encode_from_args("transfer(address,uint256)", &args(&["0x00"])),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_function_call_from_path() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            temp_file,
+            "{}",
+            json!({
+                "function": "transfer(address to, uint256 amount)",
+                "args": ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", 1000000]
+            })
+        )
+        .unwrap();
+
+        let call = FunctionCall::from_path(temp_file.path()).unwrap();
+        let calldata = call.encode().unwrap();
+
+        assert_eq!(
+            calldata,
+            encode_from_args(
+                "transfer(address,uint256)",
+                &args(&["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "1000000"]),
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_function_call_without_args() {
+        let call: FunctionCall =
+            serde_json::from_value(json!({ "function": "deposit()" })).unwrap();
+        assert_eq!(call.encode().unwrap(), abi::selector("deposit()").to_vec());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    Result, abi,
+    error::Error,
+    signer::{self, HashSigner},
+};
+use ethereum::{EIP1559Transaction, EIP1559TransactionMessage};
+use ethereum_types::{H160, H256, U256};
+use rlp::{Rlp, RlpStream};
+
+// Celo の CIP-64 トランザクション (手数料を ERC-20 の feeCurrency で払える) の EIP-2718 タイプ
+pub const CIP64_TX_TYPE: u8 = 0x7b;
+
+// Type 2 のフィールドの後ろ (accessList の次) に feeCurrency を置いた RLP
+// signed が Some なら yParity, r, s まで含めた13項目、None なら署名前の10項目
+fn encode(
+    message: &EIP1559TransactionMessage,
+    fee_currency: H160,
+    signed: Option<&signer::Signature>,
+) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(if signed.is_some() { 13 } else { 10 });
+    stream
+        .append(&message.chain_id)
+        .append(&message.nonce)
+        .append(&message.max_priority_fee_per_gas)
+        .append(&message.max_fee_per_gas)
+        .append(&message.gas_limit)
+        .append(&message.action)
+        .append(&message.value)
+        .append(&message.input)
+        .append_list(&message.access_list)
+        .append(&fee_currency);
+    if let Some(signature) = signed {
+        stream
+            .append(&signature.odd_y_parity)
+            .append(&U256::from_big_endian(signature.r.as_bytes()))
+            .append(&U256::from_big_endian(signature.s.as_bytes()));
+    }
+
+    let mut raw = vec![CIP64_TX_TYPE];
+    raw.extend_from_slice(&stream.out());
+    raw
+}
+
+// 署名用ハッシュ (0x7b + RLP(署名前の10項目) の Keccak-256)
+pub fn signing_hash(message: &EIP1559TransactionMessage, fee_currency: H160) -> H256 {
+    signer::keccak256(encode(message, fee_currency, None))
+}
+
+// 署名して 0x7b + RLP の raw トランザクションを作成
+pub fn sign<K: HashSigner + ?Sized>(
+    message: &EIP1559TransactionMessage,
+    fee_currency: H160,
+    key: &K,
+) -> Result<Vec<u8>> {
+    let signature = key.sign_hash(&signing_hash(message, fee_currency))?;
+    Ok(encode(message, fee_currency, Some(&signature)))
+}
+
+// 0x7b の raw トランザクションを Type 2 と同じ形にデコードする (ジャーナルの読み込み用、feeCurrency は含めない)
+pub fn decode(raw: &[u8]) -> Result<EIP1559Transaction> {
+    let invalid = |error: rlp::DecoderError| {
+        Error::InvalidArgument(format!("invalid Celo transaction: {error}"))
+    };
+    let rlp = Rlp::new(raw.get(1..).unwrap_or_default());
+    if rlp.item_count().map_err(invalid)? != 13 {
+        return Err(invalid(rlp::DecoderError::RlpIncorrectListLen));
+    }
+    let h256 = |index: usize| -> Result<H256> {
+        let value: U256 = rlp.val_at(index).map_err(invalid)?;
+        Ok(H256(abi::encode_u256(value)))
+    };
+
+    Ok(EIP1559Transaction {
+        chain_id: rlp.val_at(0).map_err(invalid)?,
+        nonce: rlp.val_at(1).map_err(invalid)?,
+        max_priority_fee_per_gas: rlp.val_at(2).map_err(invalid)?,
+        max_fee_per_gas: rlp.val_at(3).map_err(invalid)?,
+        gas_limit: rlp.val_at(4).map_err(invalid)?,
+        action: rlp.val_at(5).map_err(invalid)?,
+        value: rlp.val_at(6).map_err(invalid)?,
+        input: rlp.val_at(7).map_err(invalid)?,
+        access_list: rlp.list_at(8).map_err(invalid)?,
+        odd_y_parity: rlp.val_at(10).map_err(invalid)?,
+        r: h256(11)?,
+        s: h256(12)?,
+    })
+}
+
+// 0x7b の raw トランザクションの feeCurrency (ジャーナルと監査ログの形式に使う)
+pub fn decode_fee_currency(raw: &[u8]) -> Result<H160> {
+    Rlp::new(raw.get(1..).unwrap_or_default())
+        .val_at(9)
+        .map_err(|error| Error::InvalidArgument(format!("invalid Celo transaction: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, recover_address, test_signing_key};
+    use ethereum::{AccessList, TransactionAction};
+
+    // Celo の cUSD
+    const CUSD: &str = "0x765DE816845861e75A25fCA122bb6898B8B1282a";
+
+    fn test_message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 42220,
+            nonce: U256::from(7),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            gas_limit: U256::from(100_000),
+            action: TransactionAction::Call(
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: U256::from(1_000_000_000_000_000u64),
+            input: vec![],
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        let message = test_message();
+        let fee_currency: H160 = CUSD.parse().unwrap();
+        let raw = sign(&message, fee_currency, &test_signing_key()).unwrap();
+        assert_eq!(raw[0], CIP64_TX_TYPE);
+
+        let rlp = Rlp::new(&raw[1..]);
+        assert_eq!(rlp.item_count().unwrap(), 13);
+        assert_eq!(rlp.val_at::<u64>(0).unwrap(), 42220);
+        assert_eq!(rlp.val_at::<H160>(9).unwrap(), fee_currency);
+
+        let transaction = decode(&raw).unwrap();
+        assert_eq!(
+            EIP1559TransactionMessage::from(transaction.clone()),
+            message
+        );
+        let signature = signer::Signature {
+            r: transaction.r,
+            s: transaction.s,
+            odd_y_parity: transaction.odd_y_parity,
+        };
+        assert_eq!(
+            recover_address(&signing_hash(&message, fee_currency), &signature),
+            TEST_ADDRESS.parse().unwrap()
+        );
+
+        // feeCurrency も署名の対象 (Type 2 の署名とも別)
+        assert_ne!(
+            signing_hash(&message, fee_currency),
+            signing_hash(&message, H160::zero())
+        );
+        assert_ne!(
+            signing_hash(&message, fee_currency),
+            crate::transaction::signing_hash(&message)
+        );
+
+        assert!(decode(&[CIP64_TX_TYPE, 0xc0]).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{Result, chains, error::Error};
+use serde::Deserialize;
+use std::path::Path;
+
+// ethereum-lists/chains のデータセット (chains import --refresh の既定の取得先)
+pub const CHAINLIST_URL: &str = "https://chainid.network/chains.json";
+
+// ネットワークに接続できない環境でも使えるように同梱する主要なチェーンのスナップショット
+const SNAPSHOT: &str = include_str!("chainlist.json");
+
+// データセットの1チェーン (使わない項目は読み飛ばす)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chain {
+    pub name: String,
+    pub chain_id: u64,
+    pub short_name: String,
+    #[serde(default)]
+    pub rpc: Vec<String>,
+    pub native_currency: NativeCurrency,
+    #[serde(default)]
+    pub explorers: Vec<Explorer>,
+    #[serde(default)]
+    pub features: Vec<Feature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NativeCurrency {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Explorer {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Feature {
+    pub name: String,
+}
+
+impl Chain {
+    fn eip1559(&self) -> bool {
+        self.features
+            .iter()
+            .any(|feature| feature.name == "EIP1559")
+    }
+
+    // API キーが要らない (${INFURA_API_KEY} などを含まない) HTTP(S) の最初の RPC
+    fn rpc_url(&self) -> Option<&str> {
+        self.rpc.iter().map(String::as_str).find(|url| {
+            (url.starts_with("https://") || url.starts_with("http://")) && !url.contains("${")
+        })
+    }
+
+    // chains.toml の [[chains]] の1項目
+    // 名前は CHAIN で選びやすい shortName、優先手数料はデータセットにないため 0
+    fn definition(&self) -> String {
+        let string = |value: &str| serde_json::to_string(value).unwrap();
+        let mut lines = vec![
+            "[[chains]]".to_string(),
+            format!("name = {}", string(&self.short_name.to_ascii_lowercase())),
+            format!("chain_id = {}", self.chain_id),
+            format!(
+                "fee_model = \"{}\"",
+                if self.eip1559() { "eip1559" } else { "legacy" }
+            ),
+        ];
+        if let Some(url) = self.rpc_url() {
+            lines.push(format!("rpc_url = {}", string(url)));
+        }
+        if let Some(explorer) = self.explorers.first() {
+            lines.push(format!("explorer_url = {}", string(&explorer.url)));
+        }
+        lines.push(format!("symbol = {}", string(&self.native_currency.symbol)));
+        lines.push(format!("decimals = {}", self.native_currency.decimals));
+        lines.join("\n") + "\n"
+    }
+}
+
+// 同梱のスナップショット
+pub fn snapshot() -> Vec<Chain> {
+    serde_json::from_str(SNAPSHOT).expect("bundled chainlist snapshot must be valid")
+}
+
+// 最新のデータセットを取得する
+pub fn fetch(url: &str) -> Result<Vec<Chain>> {
+    ureq::get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(Error::at_endpoint(url))
+}
+
+// chain id のチェーンを chains.toml (CHAINS_PATH) に追記する
+// 組み込みのプリセットとファイルで定義済みのチェーンは飛ばし、結果を1チェーン1行で返す
+pub fn import<P: AsRef<Path>>(
+    path: P,
+    chain_ids: &[u64],
+    dataset: &[Chain],
+) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    // YAML などに TOML を追記すると読めなくなる
+    if path.extension().is_none_or(|extension| extension != "toml") {
+        return Err(Error::InvalidArgument(format!(
+            "chains import writes TOML, but CHAINS_PATH {} does not end in .toml",
+            path.display()
+        )));
+    }
+
+    let mut content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(Error::in_file(path)(error)),
+    };
+    let defined = chains::parse(&content).map_err(Error::in_file(path))?;
+
+    let mut summary = Vec::new();
+    let mut imported = Vec::new();
+    for &chain_id in chain_ids {
+        if let Some(preset) = chains::PRESETS
+            .iter()
+            .chain(defined.iter())
+            .find(|preset| preset.chain_id == chain_id)
+        {
+            summary.push(format!(
+                "Skipped {chain_id}: already defined as {}",
+                preset.name
+            ));
+            continue;
+        }
+        // 同じ chain id を2回指定した場合
+        if imported.contains(&chain_id) {
+            continue;
+        }
+        let chain = dataset
+            .iter()
+            .find(|chain| chain.chain_id == chain_id)
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "chain id {chain_id} is not in the chainlist dataset"
+                ))
+            })?;
+        if !content.is_empty() {
+            content.push_str(if content.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        content.push_str(&chain.definition());
+        imported.push(chain_id);
+        summary.push(format!(
+            "Imported {} ({chain_id}, {})",
+            chain.short_name.to_ascii_lowercase(),
+            chain.name
+        ));
+    }
+    if imported.is_empty() {
+        return Ok(summary);
+    }
+
+    // 名前の重複などで読み込めなくなる場合は書き込まない
+    chains::parse(&content).map_err(Error::in_file(path))?;
+    std::fs::write(path, content).map_err(Error::in_file(path))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+
+    #[test]
+    fn test_snapshot() {
+        let dataset = snapshot();
+        assert!(dataset.len() >= 10);
+        for chain in &dataset {
+            assert!(chains::validate(chain.chain_id).is_ok(), "{}", chain.name);
+            // 組み込みのプリセット以外はそのまま取り込める
+            if chains::PRESETS
+                .iter()
+                .any(|preset| preset.chain_id == chain.chain_id)
+            {
+                continue;
+            }
+            let definition = chain.definition();
+            assert_eq!(
+                chains::parse(&definition).unwrap()[0].chain_id,
+                chain.chain_id
+            );
+        }
+
+        let celo = dataset
+            .iter()
+            .find(|chain| chain.chain_id == 42220)
+            .unwrap();
+        let preset = chains::parse(&celo.definition()).unwrap()[0];
+        assert_eq!((preset.name, preset.symbol), ("celo", "CELO"));
+        assert!(preset.eip1559);
+        assert_eq!(preset.rpc_url, Some("https://forno.celo.org"));
+        assert_eq!(preset.explorer_url, Some("https://celoscan.io"));
+
+        // API キーの要る RPC は使わない
+        let ethereum = dataset.iter().find(|chain| chain.chain_id == 1).unwrap();
+        assert_eq!(ethereum.rpc_url(), Some("https://api.mycryptoapi.com/eth"));
+        // EIP1559 の feature がなければ legacy
+        let fantom = dataset.iter().find(|chain| chain.chain_id == 250).unwrap();
+        assert!(!chains::parse(&fantom.definition()).unwrap()[0].eip1559);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chains.toml");
+        std::fs::write(&path, "[[chains]]\nname = \"devnet\"\nchain_id = 7777\n").unwrap();
+        let dataset = snapshot();
+
+        let summary = import(&path, &[42220, 250, 42220, 100], &dataset).unwrap();
+        assert_eq!(
+            summary,
+            [
+                "Imported celo (42220, Celo Mainnet)",
+                "Imported ftm (250, Fantom Opera)",
+                "Skipped 100: already defined as gnosis",
+            ]
+        );
+        let content = std::fs::read_to_string(&path).unwrap();
+        let presets = chains::parse(&content).unwrap();
+        let ids: Vec<_> = presets.iter().map(|preset| preset.chain_id).collect();
+        assert_eq!(ids, [7777, 42220, 250]);
+
+        // 取り込み済みは飛ばしてファイルを変えない
+        let summary = import(&path, &[250, 7777], &dataset).unwrap();
+        assert_eq!(
+            summary,
+            [
+                "Skipped 250: already defined as ftm",
+                "Skipped 7777: already defined as devnet"
+            ]
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+
+        // データセットにない chain id と TOML 以外のファイルはエラー
+        let error = import(&path, &[1, 123456789], &dataset).unwrap_err();
+        assert!(matches!(error, Error::InvalidArgument(_)), "{error}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        let yaml = dir.path().join("chains.yaml");
+        assert!(matches!(
+            import(&yaml, &[1], &dataset),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        // 定義済みの名前と重なると書き込まない
+        std::fs::write(&path, "[[chains]]\nname = \"eth\"\nchain_id = 7777\n").unwrap();
+        let error = import(&path, &[1], &dataset).unwrap_err();
+        assert!(matches!(error, Error::File { .. }), "{error}");
+    }
+
+    #[test]
+    fn test_fetch() {
+        let body = r#"[{"name":"Devnet","chain":"DEV","chainId":7777,"shortName":"Dev","rpc":[],"nativeCurrency":{"name":"Dev","symbol":"DEV","decimals":6},"faucets":[],"infoURL":"https://example.com"}]"#;
+        let server = MockServer::start(vec![(200, body.to_string())]);
+        let dataset = fetch(&server.url).unwrap();
+        assert_eq!(dataset[0].chain_id, 7777);
+        let preset = chains::parse(&dataset[0].definition()).unwrap()[0];
+        assert_eq!(
+            (preset.name, preset.decimals, preset.rpc_url),
+            ("dev", 6, None)
+        );
+        assert!(!preset.eip1559);
+    }
+}
@@ -0,0 +1,486 @@
+use crate::{Result, error::Error, fees, units};
+use serde::Deserialize;
+use std::{path::Path, sync::RwLock};
+
+const NATIVE_SYMBOL: &str = "ETH";
+const NATIVE_DECIMALS: u8 = 18;
+// 10^77 < 2^256 (units::parse_units と同じ上限)
+const MAX_DECIMALS: u8 = 77;
+
+// EIP-155 の v (chain_id * 2 + 35 または 36) が u64 に収まる最大の chain id (EIP-2294)
+pub const MAX_CHAIN_ID: u64 = u64::MAX / 2 - 36;
+
+// CHAIN=base のように名前で選べる主要な L2 とサイドチェーンの設定
+// CHAIN_ID・RPC_URL・MAX_PRIORITY_FEE_PER_GAS を省略するとこの値を使う
+// chains.toml (CHAINS_PATH) で定義したチェーンも同じように使える
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub chain_id: u64,
+    pub eip1559: bool,
+    // 優先手数料の目安 (wei)
+    // Arbitrum と zkSync はシーケンサーが順番を決めるため優先手数料は使われない
+    pub priority_fee: u64,
+    pub explorer_url: Option<&'static str>,
+    pub rpc_url: Option<&'static str>,
+    // ネイティブ通貨の単位と小数点以下の桁数
+    pub symbol: &'static str,
+    pub decimals: u8,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "base",
+        chain_id: 8453,
+        eip1559: true,
+        priority_fee: 1_000_000,
+        explorer_url: Some("https://basescan.org"),
+        rpc_url: Some("https://mainnet.base.org"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    Preset {
+        name: "arbitrum",
+        chain_id: 42161,
+        eip1559: true,
+        priority_fee: 0,
+        explorer_url: Some("https://arbiscan.io"),
+        rpc_url: Some("https://arb1.arbitrum.io/rpc"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    Preset {
+        name: "optimism",
+        chain_id: 10,
+        eip1559: true,
+        priority_fee: 1_000_000,
+        explorer_url: Some("https://optimistic.etherscan.io"),
+        rpc_url: Some("https://mainnet.optimism.io"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    Preset {
+        name: "linea",
+        chain_id: 59144,
+        eip1559: true,
+        priority_fee: 50_000_000,
+        explorer_url: Some("https://lineascan.build"),
+        rpc_url: Some("https://rpc.linea.build"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    Preset {
+        name: "scroll",
+        chain_id: 534352,
+        eip1559: true,
+        priority_fee: 1_000_000,
+        explorer_url: Some("https://scrollscan.com"),
+        rpc_url: Some("https://rpc.scroll.io"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    Preset {
+        name: "zksync",
+        chain_id: 324,
+        eip1559: true,
+        priority_fee: 0,
+        explorer_url: Some("https://explorer.zksync.io"),
+        rpc_url: Some("https://mainnet.era.zksync.io"),
+        symbol: NATIVE_SYMBOL,
+        decimals: NATIVE_DECIMALS,
+    },
+    // Gnosis Chain と BSC は優先手数料 (ガス価格) の下限を目安にする (fees::minimum_priority_fee)
+    Preset {
+        name: "gnosis",
+        chain_id: 100,
+        eip1559: true,
+        priority_fee: fees::GNOSIS_MIN_PRIORITY_FEE,
+        explorer_url: Some("https://gnosisscan.io"),
+        rpc_url: Some("https://rpc.gnosischain.com"),
+        symbol: "XDAI",
+        decimals: NATIVE_DECIMALS,
+    },
+    // BSC は Legacy のガス価格で手数料が決まる (MAX_FEE_PER_GAS がガス価格になる)
+    Preset {
+        name: "bsc",
+        chain_id: 56,
+        eip1559: false,
+        priority_fee: fees::BSC_MIN_GAS_PRICE,
+        explorer_url: Some("https://bscscan.com"),
+        rpc_url: Some("https://bsc-dataseed.bnbchain.org"),
+        symbol: "BNB",
+        decimals: NATIVE_DECIMALS,
+    },
+];
+
+// CHAINS_PATH で読み込んだチェーンの定義 (プロセスの終了まで使う)
+static CUSTOM: RwLock<Vec<Preset>> = RwLock::new(Vec::new());
+
+// 組み込みのプリセットと chains.toml のチェーン
+pub fn presets() -> Vec<Preset> {
+    let custom = CUSTOM.read().unwrap();
+    PRESETS.iter().chain(custom.iter()).copied().collect()
+}
+
+// 名前 (大文字小文字は区別しない) からプリセットを探す
+pub fn preset(name: &str) -> Option<Preset> {
+    presets()
+        .into_iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+pub fn preset_for(chain_id: u64) -> Option<Preset> {
+    presets()
+        .into_iter()
+        .find(|preset| preset.chain_id == chain_id)
+}
+
+// ネイティブ通貨の単位と小数点以下の桁数 (定義がなければ ETH と 18)
+pub fn symbol(chain_id: u64) -> &'static str {
+    preset_for(chain_id).map_or(NATIVE_SYMBOL, |preset| preset.symbol)
+}
+
+pub fn decimals(chain_id: u64) -> u8 {
+    preset_for(chain_id).map_or(NATIVE_DECIMALS, |preset| preset.decimals)
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainsFile {
+    #[serde(default)]
+    chains: Vec<ChainDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChainDefinition {
+    name: String,
+    chain_id: u64,
+    #[serde(default)]
+    fee_model: FeeModel,
+    #[serde(default)]
+    priority_fee: u64,
+    rpc_url: Option<String>,
+    explorer_url: Option<String>,
+    symbol: Option<String>,
+    #[serde(default = "native_decimals")]
+    decimals: u8,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FeeModel {
+    #[default]
+    Eip1559,
+    Legacy,
+}
+
+fn native_decimals() -> u8 {
+    NATIVE_DECIMALS
+}
+
+// chains.toml (TOML / YAML) を読み込み、組み込みのプリセットと同じように使えるようにする
+//
+// [[chains]]
+// name = "devnet"
+// chain_id = 7777
+// fee_model = "legacy"
+// rpc_url = "http://localhost:8545"
+pub fn load<P: AsRef<Path>>(path: P) -> Result<()> {
+    let file: ChainsFile = config::Config::builder()
+        .add_source(config::File::from(path.as_ref()))
+        .build()
+        .and_then(config::Config::try_deserialize)
+        .map_err(Error::in_file(&path))?;
+    let custom = custom_presets(file).map_err(Error::in_file(&path))?;
+    *CUSTOM.write().unwrap() = custom;
+    Ok(())
+}
+
+// TOML の chains.toml を読み込まずに解釈する (chains import で書き込む前の確認)
+pub fn parse(content: &str) -> Result<Vec<Preset>> {
+    let file: ChainsFile = config::Config::builder()
+        .add_source(config::File::from_str(content, config::FileFormat::Toml))
+        .build()
+        .and_then(config::Config::try_deserialize)?;
+    custom_presets(file)
+}
+
+fn custom_presets(file: ChainsFile) -> Result<Vec<Preset>> {
+    let mut custom: Vec<Preset> = Vec::new();
+    for definition in file.chains {
+        let invalid = |message: String| {
+            Error::InvalidArgument(format!("chain '{}': {message}", definition.name))
+        };
+        if definition.name.is_empty() {
+            return Err(Error::InvalidArgument(
+                "chain name must not be empty".to_string(),
+            ));
+        }
+        validate(definition.chain_id).map_err(|error| invalid(error.to_string()))?;
+        if definition.decimals > MAX_DECIMALS {
+            return Err(invalid(format!("decimals must be at most {MAX_DECIMALS}")));
+        }
+        // 同じ名前や chain id があるとどちらを使うか分からない
+        if let Some(other) = PRESETS.iter().chain(custom.iter()).find(|preset| {
+            preset.name.eq_ignore_ascii_case(&definition.name)
+                || preset.chain_id == definition.chain_id
+        }) {
+            return Err(invalid(format!(
+                "conflicts with {} (chain id {})",
+                other.name, other.chain_id
+            )));
+        }
+        // 読み込んだ定義はプロセスの終了まで使うため &'static にする
+        let leak = |value: String| -> &'static str { Box::leak(value.into_boxed_str()) };
+        custom.push(Preset {
+            name: leak(definition.name),
+            chain_id: definition.chain_id,
+            eip1559: matches!(definition.fee_model, FeeModel::Eip1559),
+            priority_fee: definition.priority_fee,
+            explorer_url: definition.explorer_url.map(leak),
+            rpc_url: definition.rpc_url.map(leak),
+            symbol: definition.symbol.map_or(NATIVE_SYMBOL, leak),
+            decimals: definition.decimals,
+        });
+    }
+    Ok(custom)
+}
+
+// chains コマンドの一覧
+pub fn preset_table() -> String {
+    presets()
+        .iter()
+        .map(|preset| {
+            format!(
+                "{:<10}{:<8}{:<14}{:<8}{:<16}{:<34}{}",
+                preset.name,
+                preset.chain_id,
+                name(preset.chain_id).unwrap_or(preset.name),
+                if preset.eip1559 { "1559" } else { "legacy" },
+                format!(
+                    "{} gwei",
+                    units::format_units(preset.priority_fee.into(), 9)
+                ),
+                preset.rpc_url.unwrap_or("-"),
+                preset.explorer_url.unwrap_or("-")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// よく使うチェーンの名前 (chains.toml で定義したチェーンはその名前)
+pub fn name(chain_id: u64) -> Option<&'static str> {
+    let name = match chain_id {
+        1 => "Ethereum",
+        10 => "OP Mainnet",
+        56 => "BNB Smart Chain",
+        97 => "BSC Testnet",
+        100 => "Gnosis",
+        137 => "Polygon",
+        300 => "zkSync Sepolia",
+        324 => "zkSync Era",
+        1337 => "Local development chain",
+        8453 => "Base",
+        10200 => "Gnosis Chiado",
+        17000 => "Holesky",
+        31337 => "Hardhat / Anvil",
+        42161 => "Arbitrum One",
+        42170 => "Arbitrum Nova",
+        42220 => "Celo",
+        43114 => "Avalanche C-Chain",
+        44787 => "Celo Alfajores",
+        59144 => "Linea",
+        80002 => "Polygon Amoy",
+        84532 => "Base Sepolia",
+        421614 => "Arbitrum Sepolia",
+        534352 => "Scroll",
+        560048 => "Hoodi",
+        11142220 => "Celo Sepolia",
+        11155111 => "Sepolia",
+        11155420 => "OP Sepolia",
+        _ => {
+            let custom = CUSTOM.read().unwrap();
+            return custom
+                .iter()
+                .find(|preset| preset.chain_id == chain_id)
+                .map(|preset| preset.name);
+        }
+    };
+    Some(name)
+}
+
+// L1 のデータ手数料がかかる OP Stack のチェーン
+pub fn is_op_stack(chain_id: u64) -> bool {
+    matches!(chain_id, 10 | 8453 | 84532 | 11155420)
+}
+
+// gas に L1 のデータの分が含まれる Arbitrum のチェーン
+pub fn is_arbitrum(chain_id: u64) -> bool {
+    matches!(chain_id, 42161 | 42170 | 421614)
+}
+
+// 優先手数料に下限がある Polygon PoS のチェーン
+pub fn is_polygon(chain_id: u64) -> bool {
+    matches!(chain_id, 137 | 80002)
+}
+
+// paymaster を使える zkSync のチェーン
+pub fn is_zksync(chain_id: u64) -> bool {
+    matches!(chain_id, 300 | 324)
+}
+
+// feeCurrency (CIP-64) を使える Celo のチェーン
+pub fn is_celo(chain_id: u64) -> bool {
+    matches!(chain_id, 42220 | 44787 | 11142220)
+}
+
+// 主要なテストネットと開発用のチェーン (ネイティブ通貨に市場の価格がない)
+pub fn is_testnet(chain_id: u64) -> bool {
+    matches!(
+        chain_id,
+        97 | 300
+            | 1337
+            | 10200
+            | 17000
+            | 31337
+            | 44787
+            | 80002
+            | 84532
+            | 421614
+            | 560048
+            | 11142220
+            | 11155111
+            | 11155420
+    )
+}
+
+// 署名に使えない chain id はエラー
+// - 0 は EIP-155 のリプレイ保護にならず、どのチェーンのトランザクションか分からない
+// - MAX_CHAIN_ID を超えると v が u64 に収まらず、多くのクライアントやノードが扱えない
+pub fn validate(chain_id: u64) -> Result<()> {
+    match chain_id {
+        0 => Err(Error::InvalidChainId(
+            "CHAIN_ID must not be 0: the transaction would not be bound to any chain".to_string(),
+        )),
+        chain_id if chain_id > MAX_CHAIN_ID => Err(Error::InvalidChainId(format!(
+            "CHAIN_ID {chain_id} exceeds {MAX_CHAIN_ID}, the largest chain id whose EIP-155 v fits in 64 bits"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// 知らない chain id は打ち間違いの可能性があるため警告する
+pub fn warning(chain_id: u64) -> Option<String> {
+    match name(chain_id) {
+        Some(_) => None,
+        None => Some(format!(
+            "CHAIN_ID {chain_id} is not a well-known chain; make sure it is the intended network"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(1).is_ok());
+        assert!(validate(123456789).is_ok());
+        assert!(validate(MAX_CHAIN_ID).is_ok());
+        assert!(matches!(validate(0), Err(Error::InvalidChainId(_))));
+        assert!(matches!(
+            validate(MAX_CHAIN_ID + 1),
+            Err(Error::InvalidChainId(_))
+        ));
+        // v = chain_id * 2 + 36 が u64 に収まる
+        assert!(
+            MAX_CHAIN_ID
+                .checked_mul(2)
+                .and_then(|v| v.checked_add(36))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_presets() {
+        let base = preset("Base").unwrap();
+        assert_eq!(base.chain_id, 8453);
+        assert_eq!(preset("zksync").unwrap().chain_id, 324);
+        assert_eq!(preset("polygon"), None);
+        let bsc = preset("bsc").unwrap();
+        assert!(!bsc.eip1559);
+        assert_eq!((bsc.chain_id, bsc.symbol), (56, "BNB"));
+        assert_eq!(
+            fees::minimum_priority_fee(100),
+            Some(preset("gnosis").unwrap().priority_fee.into())
+        );
+        for preset in PRESETS {
+            assert!(name(preset.chain_id).is_some(), "{}", preset.name);
+            assert!(validate(preset.chain_id).is_ok());
+        }
+        let table = preset_table();
+        for preset in PRESETS {
+            assert!(table.contains(preset.rpc_url.unwrap()), "{table}");
+        }
+        assert!(table.contains("0.001 gwei"), "{table}");
+        assert!(table.contains("https://arb1.arbitrum.io/rpc"), "{table}");
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chains.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[chains]]
+name = "devnet-test"
+chain_id = 987654321
+fee_model = "legacy"
+priority_fee = 1000000000
+rpc_url = "http://localhost:8545"
+symbol = "DEV"
+decimals = 6
+"#,
+        )
+        .unwrap();
+        load(&path).unwrap();
+
+        let devnet = preset("DEVNET-TEST").unwrap();
+        assert_eq!(devnet.chain_id, 987654321);
+        assert!(!devnet.eip1559);
+        assert_eq!(devnet.explorer_url, None);
+        assert_eq!(preset_for(987654321), Some(devnet));
+        assert_eq!(name(987654321), Some("devnet-test"));
+        assert_eq!(warning(987654321), None);
+        assert_eq!((symbol(987654321), decimals(987654321)), ("DEV", 6));
+        assert_eq!(decimals(1), 18);
+        assert!(preset_table().contains("devnet-test"));
+
+        // 組み込みのプリセットと同じ名前や chain id、使えない値はエラー
+        for definition in [
+            "name = \"Base\"\nchain_id = 7",
+            "name = \"mine\"\nchain_id = 8453",
+            "name = \"mine\"\nchain_id = 0",
+            "name = \"mine\"\nchain_id = 7\ndecimals = 78",
+            "name = \"mine\"\nchain_id = 7\nfee_model = \"eip4844\"",
+            "name = \"mine\"\nchain_id = 7\nrpc = \"http://localhost:8545\"",
+        ] {
+            std::fs::write(&path, format!("[[chains]]\n{definition}\n")).unwrap();
+            let error = load(&path).unwrap_err();
+            assert!(matches!(error, Error::File { .. }), "{definition}: {error}");
+        }
+        // 失敗しても読み込み済みの定義はそのまま
+        assert!(preset("devnet-test").is_some());
+    }
+
+    #[test]
+    fn test_warning() {
+        assert_eq!(name(11155111), Some("Sepolia"));
+        assert_eq!(warning(1), None);
+        assert_eq!(warning(31337), None);
+        assert!(warning(111551111).unwrap().contains("111551111"));
+    }
+}
@@ -0,0 +1,2743 @@
+use crate::{
+    chainlist,
+    fees::Speed,
+    hardware,
+    i18n::Lang,
+    keystore,
+    params::InputFormat,
+    price::{self, Currency},
+    secret::Secret,
+};
+use clap::{Args, Parser, Subcommand};
+use ethereum_types::{H160, H256, U256};
+use std::{net::SocketAddr, path::PathBuf};
+
+// コマンドライン引数
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    /// Path to the parameter JSON file (same as `sign <PARAMS>`)
+    pub params: Option<PathBuf>,
+
+    /// Sign even if the journal (JOURNAL_PATH) has a transaction with the same nonce or a pending one with the same payload
+    #[arg(long, global = true)]
+    pub allow_duplicate: bool,
+
+    /// Require a 0x prefix on every hex value in parameter files and reject ambiguous strings such as "1234"
+    #[arg(long, global = true)]
+    pub strict_hex: bool,
+
+    /// Read digit-only strings such as "5208" in parameter files as decimal (without it, those whose hex and decimal readings differ are rejected)
+    #[arg(long, global = true, conflicts_with = "strict_hex")]
+    pub decimal_strings: bool,
+
+    /// Treat warnings about implausible values (a nonce over 2^32, an unfamiliar CHAIN_ID) as errors
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Confirm that CHAIN_ID 1 (Ethereum mainnet) is intended instead of typing it at the prompt
+    #[arg(long, global = true)]
+    pub mainnet: bool,
+
+    /// Sign transactions whose value is above CONFIRM_ABOVE without typing the amount at the prompt
+    #[arg(long, global = true)]
+    pub yes: bool,
+
+    /// Sign transfers and approvals to addresses on the denylist (DENYLIST_PATH) with a warning instead of refusing
+    #[arg(long, global = true)]
+    pub allow_flagged: bool,
+
+    /// Ignore unknown fields in parameter files instead of rejecting them as typos (such as "gaslimit")
+    #[arg(long, global = true)]
+    pub lenient: bool,
+
+    /// Replace MAX_FEE_PER_GAS and MAX_PRIORITY_FEE_PER_GAS with current fees (Polygon gas station, or eth_feeHistory on RPC_URL)
+    #[arg(long, global = true, value_enum)]
+    pub speed: Option<Speed>,
+
+    /// Produce byte-identical output for identical inputs: never take a nonce, fees or other values from RPC_URL or NONCE_STORE_PATH
+    #[arg(long, global = true, conflicts_with = "speed")]
+    pub deterministic: bool,
+
+    /// Run the transaction on RPC_URL (eth_simulateV1) before signing and print the tokens and ETH it will send and receive
+    #[arg(long, global = true, conflicts_with = "deterministic")]
+    pub simulate: bool,
+
+    /// Also show fees in this currency, converted at the native token price from PRICE_API_URL (CoinGecko by default)
+    #[arg(long, global = true, value_enum)]
+    pub fiat: Option<Currency>,
+
+    /// Price of one native token (such as 1 ETH) in the --fiat currency (USD by default) to convert fees with instead of fetching it
+    #[arg(long, global = true, value_parser = price::parse_price)]
+    pub eth_price: Option<U256>,
+
+    /// Language of messages, previews and prompts (defaults to Japanese when LC_ALL, LC_MESSAGES or LANG is ja_*, otherwise English)
+    #[arg(long, global = true, value_enum)]
+    pub lang: Option<Lang>,
+
+    /// Print the full chain of causes when a command fails
+    #[arg(long, global = true)]
+    pub verbose: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Sign an EIP-1559 transaction described by a parameter JSON file
+    Sign {
+        /// Path to the parameter JSON file
+        params: PathBuf,
+
+        /// Format of the file: this tool's params.json, a TypedTransaction serialized by ethers-rs, or an eth_signTransaction transaction object (rpc)
+        #[arg(long, value_enum, default_value_t)]
+        format: InputFormat,
+    },
+
+    /// Sign every transaction in a JSON array, NDJSON or CSV file of parameters, printing one raw transaction per line
+    SignBatch {
+        /// Path to the JSON array, NDJSON or CSV (.csv) file of parameters
+        params: PathBuf,
+
+        /// Number of signing threads (defaults to the number of CPUs)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        /// Send each transaction to RPC_URL with eth_sendRawTransaction as soon as it is journaled
+        #[arg(long)]
+        broadcast: bool,
+
+        /// Maximum number of eth_sendRawTransaction requests in flight
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u64).range(1..), requires = "broadcast")]
+        concurrency: u64,
+    },
+
+    /// Print the maximum fee of the transaction described by a parameter JSON file without signing it (with the L1 data fee on OP Stack chains when RPC_URL is set)
+    Cost {
+        /// Path to the parameter JSON file
+        params: PathBuf,
+    },
+
+    /// Print the unsigned transaction and its signing hash (sighash) for a parameter JSON file, to be signed outside this tool
+    Prepare {
+        /// Path to the parameter JSON file (the nonce is required)
+        params: PathBuf,
+
+        /// Also print the payload for a hardware wallet (ledger: rawTxHex for hw-app-eth, keystone: an eth-sign-request UR for a QR code)
+        #[arg(long)]
+        device: Option<hardware::Device>,
+
+        /// BIP-32 derivation path of the hardware wallet account
+        #[arg(long, default_value = hardware::DEFAULT_DERIVATION_PATH, requires = "device")]
+        derivation_path: hardware::DerivationPath,
+
+        /// Master key fingerprint (8 hex digits) shown by Keystone, included in the eth-sign-request
+        #[arg(long, value_parser = hardware::parse_master_fingerprint, requires = "device")]
+        master_fingerprint: Option<u32>,
+    },
+
+    /// Combine the output of prepare with an external signature over its sighash into a raw transaction
+    Assemble {
+        /// Path to the JSON printed by prepare
+        prepared: PathBuf,
+
+        /// r of the signature (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256, required_unless_present = "signature")]
+        r: Option<U256>,
+
+        /// s of the signature (decimal or 0x-prefixed hex); a high s is normalized
+        #[arg(long, value_parser = parse_u256, required_unless_present = "signature")]
+        s: Option<U256>,
+
+        /// Recovery value: 0/1, 27/28, or chain_id * 2 + 35/36 (EIP-155)
+        #[arg(long, required_unless_present = "signature")]
+        v: Option<u64>,
+
+        /// Signature scanned from a Keystone QR code (ur:eth-signature/...) instead of --r, --s and --v
+        #[arg(long, conflicts_with_all = ["r", "s", "v"])]
+        signature: Option<String>,
+
+        /// Fail unless the signature recovers to this address
+        #[arg(long)]
+        from: Option<H160>,
+    },
+
+    /// Sign a message with the EIP-191 personal_sign prefix
+    SignMessage {
+        /// Message to sign
+        message: String,
+
+        /// Interpret the message as hex-encoded bytes
+        #[arg(long)]
+        hex: bool,
+
+        /// Use EIP-191 version 0x00 with this intended validator address instead of personal_sign
+        #[arg(long)]
+        validator: Option<H160>,
+    },
+
+    /// Sign an arbitrary 32-byte digest (dangerous: the digest may be a transaction or permit)
+    SignHash {
+        /// 32-byte digest to sign (hex)
+        hash: H256,
+
+        /// Acknowledge that signing an unknown digest can authorize anything
+        #[arg(long)]
+        i_know_what_im_doing: bool,
+    },
+
+    /// Sign EIP-712 typed data ({types, domain, primaryType, message} JSON)
+    SignTypedData {
+        /// Path to the typed data JSON file
+        typed_data: PathBuf,
+
+        /// Also print the domain separator and every struct hash for debugging
+        #[arg(long)]
+        debug_hashes: bool,
+    },
+
+    /// Sign an ERC-2612 permit for a token
+    Permit {
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Spender address
+        #[arg(long)]
+        spender: H160,
+
+        /// Allowance in the token's base units (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256, required_unless_present = "amount")]
+        value: Option<U256>,
+
+        /// Allowance in whole tokens, e.g. 12.5 (scaled by the token's decimals)
+        #[arg(long, conflicts_with = "value")]
+        amount: Option<String>,
+
+        /// Token decimals for --amount (fetched via decimals() over RPC if omitted)
+        #[arg(long, conflicts_with = "value")]
+        decimals: Option<u8>,
+
+        /// Unix timestamp after which the permit is invalid
+        #[arg(long, value_parser = parse_u256)]
+        deadline: U256,
+
+        /// Permit nonce (fetched via nonces(owner) over RPC if omitted)
+        #[arg(long, value_parser = parse_u256)]
+        nonce: Option<U256>,
+
+        /// EIP-712 domain name (fetched via name() over RPC if omitted)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// EIP-712 domain version (fetched via version() over RPC if omitted, defaults to "1")
+        #[arg(long)]
+        version: Option<String>,
+    },
+
+    /// Safe{Wallet} multisig operations
+    Safe {
+        #[command(subcommand)]
+        command: SafeCommand,
+    },
+
+    /// Build and sign ERC-20 token transactions
+    Erc20 {
+        #[command(subcommand)]
+        command: Erc20Command,
+    },
+
+    /// Build and sign ERC-721 (NFT) transactions
+    Erc721 {
+        #[command(subcommand)]
+        command: Erc721Command,
+    },
+
+    /// Build and sign ERC-1155 (multi-token) transactions
+    Erc1155 {
+        #[command(subcommand)]
+        command: Erc1155Command,
+    },
+
+    /// Sign a contract creation transaction and print the predicted address (sender + nonce)
+    Deploy {
+        #[command(flatten)]
+        init_code: InitCodeArgs,
+
+        /// Constructor signature, e.g. "constructor(address,uint256)"
+        #[arg(long)]
+        constructor: Option<String>,
+
+        /// Constructor arguments; arrays and tuples are written as [1,2] and (0x...,1)
+        #[arg(allow_negative_numbers = true, requires = "constructor")]
+        args: Vec<String>,
+
+        /// ETH sent to a payable constructor, in wei (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256, default_value = "0")]
+        value: U256,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Predict and deploy CREATE2 addresses
+    Create2 {
+        #[command(subcommand)]
+        command: Create2Command,
+    },
+
+    /// Bundle several calls into one Multicall3 aggregate3 transaction and sign it
+    Multicall {
+        /// Path to a JSON array of {target, allowFailure, callData | function + args}
+        calls: PathBuf,
+
+        /// Multicall3 contract address (defaults to 0xcA11bde05977b3631167028862bE2a173976CA11)
+        #[arg(long)]
+        multicall: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Pay many recipients in one transaction through the Disperse contract
+    Disperse {
+        #[command(subcommand)]
+        command: DisperseCommand,
+    },
+
+    /// Wrap ETH into WETH or unwrap it
+    Weth {
+        #[command(subcommand)]
+        command: WethCommand,
+    },
+
+    /// Run a signer over HTTP (JSON-RPC eth_* / Clef account_* methods and the Web3Signer eth1 REST API)
+    Serve(ServeArgs),
+
+    /// Signer plugins (SIGNER_PLUGIN) that sign hashes in a separate process
+    Plugin {
+        #[command(subcommand)]
+        command: PluginCommand,
+    },
+
+    /// Call a JSON-RPC method like an EIP-1193 provider: signing methods use this key, the rest are forwarded to RPC_URL
+    Request {
+        /// Method name, e.g. eth_signTransaction or eth_blockNumber
+        method: String,
+
+        /// Params as a JSON array, e.g. '[{"to":"0x...","value":"0x1"}]'
+        #[arg(default_value = "[]")]
+        params: String,
+    },
+
+    /// Encrypt PRIVATE_KEY with a password read from stdin into a key file for serve --key-file
+    EncryptKey {
+        /// Path to write the encrypted key file
+        output: PathBuf,
+
+        /// PBKDF2 iterations
+        #[arg(long, default_value_t = keystore::DEFAULT_ITERATIONS)]
+        iterations: u32,
+    },
+
+    /// Unlock the key file of a running signer server with a password read from stdin
+    Unlock {
+        /// Base URL of the signer server
+        #[arg(long, default_value = "http://127.0.0.1:8550")]
+        server: String,
+
+        /// Bearer token with the unlock permission
+        #[arg(long)]
+        token: Option<Secret<String>>,
+    },
+
+    /// Lock the key file of a running signer server
+    Lock {
+        /// Base URL of the signer server
+        #[arg(long, default_value = "http://127.0.0.1:8550")]
+        server: String,
+
+        /// Bearer token with the unlock permission
+        #[arg(long)]
+        token: Option<Secret<String>>,
+    },
+
+    /// Approve (or reject) a transaction waiting for a second operator on a running signer server
+    Approve {
+        /// Request ID returned in the JSON-RPC error data (requestId)
+        request_id: String,
+
+        /// Base URL of the signer server
+        #[arg(long, default_value = "http://127.0.0.1:8550")]
+        server: String,
+
+        /// Bearer token of the approving operator
+        #[arg(long)]
+        token: Option<Secret<String>>,
+
+        /// Reject the request instead of approving it
+        #[arg(long)]
+        reject: bool,
+    },
+
+    /// ABI-encode function calls
+    Calldata {
+        #[command(subcommand)]
+        command: CalldataCommand,
+    },
+
+    /// Convert transactions proposed in other wallets into a parameter JSON file (printed to stdout)
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+
+    /// Read geth and Parity/OpenEthereum keystore files (Web3 Secret Storage)
+    Keystore {
+        #[command(subcommand)]
+        command: KeystoreCommand,
+    },
+
+    /// Inspect the audit log of signing requests
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+
+    /// Air-gapped workflow: prepare signing requests on the online machine and sign them on the offline one
+    Offline {
+        #[command(subcommand)]
+        command: OfflineCommand,
+    },
+
+    /// Air-gapped workflow: broadcast transactions signed on the offline machine
+    Online {
+        #[command(subcommand)]
+        command: OnlineCommand,
+    },
+
+    /// Review the signing policy (POLICY_PATH) against example transactions
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+
+    /// Inspect or correct the local nonce store (NONCE_STORE_PATH)
+    Nonce {
+        #[command(subcommand)]
+        command: NonceCommand,
+    },
+
+    /// Check journaled pending transactions against RPC and report nonce gaps
+    Status {
+        /// Keep checking at this interval (seconds)
+        #[arg(long, value_name = "SECONDS")]
+        watch: Option<u64>,
+    },
+
+    /// Sign zero-value self-transfers for the missing nonces that block journaled transactions
+    FillGaps,
+
+    /// Re-sign journaled transactions pending longer than N blocks with bumped fees
+    Rebump {
+        /// Only bump transactions signed more than this many blocks ago
+        #[arg(long, default_value = "20")]
+        blocks: u64,
+
+        /// Fee increase in percent (nodes require at least 10 to replace a transaction)
+        #[arg(long, default_value = "12", value_parser = clap::value_parser!(u64).range(10..))]
+        percent: u64,
+
+        /// Skip transactions whose worst-case fee (gas limit × max fee per gas) would exceed this (wei)
+        #[arg(long, value_parser = parse_u256)]
+        max_fee: Option<U256>,
+
+        /// Stop bumping once the worst-case fees would exceed this in total (wei)
+        #[arg(long, value_parser = parse_u256)]
+        max_total_fee: Option<U256>,
+    },
+
+    /// Query the journal of signed transactions (JOURNAL_PATH)
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+
+    /// List the chain presets that CHAIN can select (built-in and CHAINS_PATH)
+    Chains {
+        #[command(subcommand)]
+        command: Option<ChainsCommand>,
+    },
+
+    /// Measure signing throughput with synthetic transactions and a throwaway key
+    Bench {
+        #[command(subcommand)]
+        command: BenchCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OfflineCommand {
+    /// Print a signing request (chain, sender, fees, expiry and the unsigned transaction) for a parameter JSON file
+    Prepare {
+        /// Path to the parameter JSON file
+        params: PathBuf,
+
+        /// Address of the offline key (used to take the nonce from NONCE_STORE_PATH or RPC_URL when it is omitted)
+        #[arg(long)]
+        from: H160,
+
+        /// Minutes until the request can no longer be signed or broadcast
+        #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+        expires_in: u64,
+    },
+
+    /// Sign a signing request with PRIVATE_KEY and print the signed transaction file
+    Sign {
+        /// Path to the signing request printed by offline prepare
+        request: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OnlineCommand {
+    /// Check a signed transaction file and send it to RPC_URL with eth_sendRawTransaction
+    Broadcast {
+        /// Path to the signed transaction file printed by offline sign
+        signed: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PolicyCommand {
+    /// Evaluate every example transaction (*.json) in a directory and report which rule allows or denies it
+    Test {
+        /// Directory of example transactions ({"chain_id", "expect", "transaction": <params>})
+        examples: PathBuf,
+
+        /// Policy file to test instead of POLICY_PATH
+        #[arg(long)]
+        policy: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ChainsCommand {
+    /// Add chains from the ethereum-lists/chains dataset to the CHAINS_PATH file
+    Import {
+        /// Chain ids to import
+        #[arg(required = true)]
+        chain_ids: Vec<u64>,
+
+        /// Fetch the latest dataset instead of the bundled snapshot (chainid.network unless a URL is given)
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = chainlist::CHAINLIST_URL)]
+        refresh: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// List journaled transactions in signing order
+    List {
+        /// Only list transactions that are not yet included in a block
+        #[arg(long)]
+        pending: bool,
+    },
+
+    /// Print a journaled transaction as JSON
+    Show {
+        /// Transaction hash
+        hash: H256,
+    },
+
+    /// Export the journal to a portable archive (CSV if the file ends in .csv, JSON otherwise)
+    Export {
+        /// Archive file to write
+        output: PathBuf,
+
+        /// Only export transactions signed on or after this date (YYYY-MM-DD in UTC, or UNIX seconds)
+        #[arg(long, value_parser = parse_date)]
+        since: Option<u64>,
+
+        /// Only export transactions signed before this date (YYYY-MM-DD in UTC, or UNIX seconds)
+        #[arg(long, value_parser = parse_date)]
+        until: Option<u64>,
+    },
+
+    /// Import an archive written by `history export` into the journal
+    Import {
+        /// Archive file to read
+        input: PathBuf,
+    },
+
+    /// Report gas used, fees and outcomes of journaled transactions from their receipts on RPC_URL
+    Report {
+        /// File to write the per-transaction breakdown to (CSV if it ends in .csv, JSON otherwise); prints JSON when omitted
+        output: Option<PathBuf>,
+
+        /// Only report transactions signed on or after this date (YYYY-MM-DD in UTC, or UNIX seconds)
+        #[arg(long, value_parser = parse_date)]
+        since: Option<u64>,
+
+        /// Only report transactions signed before this date (YYYY-MM-DD in UTC, or UNIX seconds)
+        #[arg(long, value_parser = parse_date)]
+        until: Option<u64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BenchCommand {
+    /// Sign synthetic transactions and report signatures per second and latency percentiles
+    Sign {
+        /// Number of transactions to sign
+        #[arg(long, default_value_t = 10000, value_parser = clap::value_parser!(u64).range(1..))]
+        count: u64,
+
+        /// Number of signing threads (defaults to comparing 1 thread against the number of CPUs)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+
+        /// Size of the synthetic calldata in bytes
+        #[arg(long, default_value_t = 68)]
+        calldata: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NonceCommand {
+    /// Print the next nonce in the store and the pending nonce reported by RPC
+    Show {
+        /// Sender address (defaults to the address of PRIVATE_KEY)
+        #[arg(long)]
+        address: Option<H160>,
+    },
+
+    /// Set the next nonce to allocate (the pending nonce reported by RPC if omitted)
+    Set {
+        #[arg(value_parser = parse_u256)]
+        nonce: Option<U256>,
+
+        /// Sender address (defaults to the address of PRIVATE_KEY)
+        #[arg(long)]
+        address: Option<H160>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// Verify the hash chain of an audit log and print the entry count and head hash
+    Verify {
+        /// Path to the audit log (AUDIT_LOG_PATH)
+        log: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SafeCommand {
+    /// Sign a Safe transaction as an owner and optionally propose it
+    Sign {
+        /// Path to the Safe transaction JSON file
+        safe_tx: PathBuf,
+
+        /// Submit the transaction and signature to the Safe Transaction Service
+        #[arg(long)]
+        propose: bool,
+
+        /// Safe Transaction Service base URL (defaults to the official one for the chain)
+        #[arg(long, requires = "propose")]
+        service_url: Option<String>,
+
+        /// Origin label attached to the proposal
+        #[arg(long, requires = "propose")]
+        origin: Option<String>,
+    },
+
+    /// Print parameter JSON files as a Safe Transaction Builder batch to import into Safe{Wallet}
+    Export {
+        /// Paths to the parameter JSON files, one transaction each, in order
+        #[arg(required = true)]
+        params: Vec<PathBuf>,
+
+        /// Safe address recorded in the batch
+        #[arg(long)]
+        safe: Option<H160>,
+
+        /// Batch name shown in the Transaction Builder
+        #[arg(long, default_value = "Transactions Batch")]
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Erc20Command {
+    /// Sign a transfer(address,uint256) transaction
+    Transfer {
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Recipient address
+        #[arg(long)]
+        to: H160,
+
+        /// Amount in whole tokens, e.g. 12.5 (scaled by the token's decimals)
+        #[arg(long)]
+        amount: String,
+
+        /// Token decimals (fetched via decimals() over RPC if omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Sign an approve(address,uint256) transaction
+    Approve {
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Spender address
+        #[arg(long)]
+        spender: H160,
+
+        /// Allowance in whole tokens, e.g. 12.5, or "max" for type(uint256).max
+        #[arg(long, required_unless_present = "revoke")]
+        amount: Option<String>,
+
+        /// Set the allowance to 0 (same as --amount 0)
+        #[arg(long, conflicts_with = "amount")]
+        revoke: bool,
+
+        /// Token decimals (fetched via decimals() over RPC if omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+
+        /// Acknowledge that an unlimited allowance lets the spender move all of your tokens
+        #[arg(long)]
+        allow_unlimited: bool,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Erc721Command {
+    /// Sign a safeTransferFrom(address,address,uint256) transaction from the signer's address
+    Transfer {
+        /// NFT contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Recipient address
+        #[arg(long)]
+        to: H160,
+
+        /// Token ID (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256)]
+        token_id: U256,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Erc1155Command {
+    /// Sign a safeTransferFrom(address,address,uint256,uint256,bytes) transaction from the signer's address
+    Transfer {
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Recipient address
+        #[arg(long)]
+        to: H160,
+
+        /// Token ID (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256)]
+        id: U256,
+
+        /// Amount in the token's base units (decimal or 0x-prefixed hex)
+        #[arg(long, value_parser = parse_u256)]
+        amount: U256,
+
+        /// Extra data passed to the recipient's onERC1155Received hook (hex)
+        // Vec<u8> だと複数の値を受け取る引数として扱われるため完全修飾パスで書く
+        #[arg(long, value_parser = parse_hex_bytes, default_value = "0x")]
+        data: ::std::vec::Vec<u8>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Sign a safeBatchTransferFrom(address,address,uint256[],uint256[],bytes) transaction from the signer's address
+    BatchTransfer {
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Recipient address
+        #[arg(long)]
+        to: H160,
+
+        /// Comma-separated token IDs
+        #[arg(long, value_parser = parse_u256, value_delimiter = ',', required = true)]
+        ids: Vec<U256>,
+
+        /// Comma-separated amounts, one for each ID
+        #[arg(long, value_parser = parse_u256, value_delimiter = ',', required = true)]
+        amounts: Vec<U256>,
+
+        /// Extra data passed to the recipient's onERC1155BatchReceived hook (hex)
+        // Vec<u8> だと複数の値を受け取る引数として扱われるため完全修飾パスで書く
+        #[arg(long, value_parser = parse_hex_bytes, default_value = "0x")]
+        data: ::std::vec::Vec<u8>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Read a MetaMask txParams object, transaction or state log and print it as a parameter JSON file
+    Metamask {
+        /// Path to the JSON copied or downloaded from MetaMask
+        path: PathBuf,
+
+        /// Transaction id to pick from a state log (defaults to the only unapproved transaction)
+        #[arg(long)]
+        id: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeystoreCommand {
+    /// Print the format, cipher, KDF parameters and quirks found in a keystore file as JSON
+    Inspect {
+        /// Path to the keystore file
+        path: PathBuf,
+
+        /// Also decrypt the key with a password read from stdin and check the address
+        #[arg(long)]
+        decrypt: bool,
+    },
+
+    /// Decrypt a keystore file with a password read from stdin and re-encrypt it into a key file for serve --key-file
+    Import {
+        /// Path to the keystore file
+        path: PathBuf,
+
+        /// Path to write the encrypted key file
+        output: PathBuf,
+
+        /// PBKDF2 iterations
+        #[arg(long, default_value_t = keystore::DEFAULT_ITERATIONS)]
+        iterations: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PluginCommand {
+    /// Act as a signer plugin for PRIVATE_KEY, answering requests on stdin with responses on stdout
+    Serve {
+        /// Acknowledge that the plugin signs whatever hashes it is sent (POLICY_PATH is refused)
+        #[arg(long)]
+        i_know_what_im_doing: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CalldataCommand {
+    /// Print the calldata for a function call, e.g. encode "transfer(address,uint256)" 0x... 1000000
+    Encode {
+        /// Function signature, e.g. "transfer(address,uint256)"
+        #[arg(required_unless_present = "file")]
+        signature: Option<String>,
+
+        /// Arguments; arrays and tuples are written as [1,2] and (0x...,1)
+        #[arg(allow_negative_numbers = true)]
+        args: Vec<String>,
+
+        /// Read {"function": ..., "args": [...]} from a JSON file instead
+        #[arg(long, conflicts_with_all = ["signature", "args"])]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Create2Command {
+    /// Print the address a CREATE2 deployment will have
+    Address {
+        /// Deploying factory (defaults to the deterministic deployment proxy 0x4e59b44847b379578588920cA78FbF26c0B4956C)
+        #[arg(long)]
+        factory: Option<H160>,
+
+        /// 32-byte salt (hex)
+        #[arg(long)]
+        salt: H256,
+
+        /// keccak256 of the init code
+        #[arg(
+            long,
+            required_unless_present = "init_code",
+            conflicts_with = "init_code"
+        )]
+        init_code_hash: Option<H256>,
+
+        #[command(flatten)]
+        init_code: InitCodeArgs,
+    },
+
+    /// Sign a deployment through the deterministic deployment proxy (calldata = salt + init code)
+    Deploy {
+        /// Deploying factory (defaults to the deterministic deployment proxy 0x4e59b44847b379578588920cA78FbF26c0B4956C)
+        #[arg(long)]
+        factory: Option<H160>,
+
+        /// 32-byte salt (hex)
+        #[arg(long)]
+        salt: H256,
+
+        #[command(flatten)]
+        init_code: InitCodeArgs,
+
+        /// Refuse to sign unless the predicted address equals this one
+        #[arg(long)]
+        expect_address: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+// コントラクトの init code (デプロイ用バイトコード) の指定
+#[derive(Debug, Args)]
+#[group(id = "init_code", multiple = false)]
+pub struct InitCodeArgs {
+    /// Init code (hex)
+    #[arg(long = "init-code", value_parser = parse_hex_bytes)]
+    pub hex: Option<::std::vec::Vec<u8>>,
+
+    /// File containing the init code as hex, or a Foundry / Hardhat artifact JSON
+    #[arg(long = "init-code-file")]
+    pub file: Option<PathBuf>,
+}
+
+// 署名サーバーの設定
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8550")]
+    pub listen: SocketAddr,
+
+    /// Serve on a Unix domain socket at this path instead of TCP
+    #[arg(long, conflicts_with = "listen")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Permission bits (octal) of the Unix domain socket
+    #[arg(long, default_value = "600", value_parser = parse_octal_mode, requires = "unix_socket")]
+    pub socket_mode: u32,
+
+    /// Serve over TLS with this PEM certificate chain
+    #[arg(long, requires = "tls_key", conflicts_with = "unix_socket")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Require client certificates issued by this PEM CA bundle (mutual TLS)
+    #[arg(long, requires = "tls_cert")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// Shell command asked to approve each signing request (request JSON on stdin, exit 0 to approve)
+    #[arg(long)]
+    pub approval_command: Option<String>,
+
+    /// JSON file of bearer tokens and JWT settings required to call the server
+    #[arg(long)]
+    pub auth_config: Option<PathBuf>,
+
+    /// Maximum requests per minute for each token (or client IP without --auth-config)
+    #[arg(long)]
+    pub max_requests_per_minute: Option<u32>,
+
+    /// Maximum total value in ETH (e.g. 1.5) each token or client IP may sign per UTC day
+    #[arg(long)]
+    pub max_value_per_day: Option<String>,
+
+    /// Encrypted key file (see encrypt-key) used instead of PRIVATE_KEY; starts locked until unlocked
+    #[arg(long)]
+    pub key_file: Option<PathBuf>,
+
+    /// Lock the key file again after this many seconds without signing (0 to keep it unlocked)
+    #[arg(long, default_value_t = 300, requires = "key_file")]
+    pub unlock_timeout: u64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DisperseCommand {
+    /// Sign a disperseEther(address[],uint256[]) transaction sending the total as value
+    Eth {
+        /// File with one "address,amount" line per recipient (amounts in ETH, e.g. 0.5)
+        payouts: PathBuf,
+
+        /// Disperse contract address (defaults to 0xD152f549545093347A162Dce210e7293f1452150)
+        #[arg(long)]
+        disperse: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Sign a disperseToken(address,address[],uint256[]) transaction (needs a prior approve of the total)
+    Token {
+        /// File with one "address,amount" line per recipient (amounts in whole tokens, e.g. 12.5)
+        payouts: PathBuf,
+
+        /// Token contract address
+        #[arg(long)]
+        token: H160,
+
+        /// Token decimals (fetched via decimals() over RPC if omitted)
+        #[arg(long)]
+        decimals: Option<u8>,
+
+        /// Disperse contract address (defaults to 0xD152f549545093347A162Dce210e7293f1452150)
+        #[arg(long)]
+        disperse: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WethCommand {
+    /// Sign a deposit() transaction sending the amount as value
+    Wrap {
+        /// Amount in ETH, e.g. 1.5
+        #[arg(long)]
+        amount: String,
+
+        /// WETH contract address (defaults to the canonical one for the chain)
+        #[arg(long)]
+        weth: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+
+    /// Sign a withdraw(uint256) transaction
+    Unwrap {
+        /// Amount in WETH, e.g. 1.5
+        #[arg(long)]
+        amount: String,
+
+        /// WETH contract address (defaults to the canonical one for the chain)
+        #[arg(long)]
+        weth: Option<H160>,
+
+        #[command(flatten)]
+        tx: TransactionArgs,
+    },
+}
+
+// コントラクト呼び出しのトランザクションを組み立てるサブコマンドで共通の引数
+// 手数料とチェーンIDは params.json と同様に環境変数から取得する
+#[derive(Debug, Args)]
+pub struct TransactionArgs {
+    /// Transaction nonce (allocated from NONCE_STORE_PATH or fetched via RPC if omitted)
+    #[arg(long, value_parser = parse_u256)]
+    pub nonce: Option<U256>,
+
+    /// Gas limit
+    #[arg(long, value_parser = parse_u256, default_value = "100000")]
+    pub gas_limit: U256,
+}
+
+// 10進数、または 0x プレフィックス付き16進数の数値
+pub fn parse_u256(s: &str) -> Result<U256, String> {
+    match s.strip_prefix("0x") {
+        Some(hex_digits) => U256::from_str_radix(hex_digits, 16).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(s).map_err(|e| e.to_string()),
+    }
+}
+
+// 0x プレフィックスは省略可能な16進数のバイト列
+pub fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| e.to_string())
+}
+
+// 600 や 0o660 のような8進数のパーミッション
+pub fn parse_octal_mode(s: &str) -> Result<u32, String> {
+    u32::from_str_radix(s.strip_prefix("0o").unwrap_or(s), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| format!("invalid permission bits '{s}' (expected octal such as 600)"))
+}
+
+// YYYY-MM-DD (UTC の0時) または UNIX 時刻 (秒)
+pub fn parse_date(s: &str) -> Result<u64, String> {
+    if let Ok(timestamp) = s.parse() {
+        return Ok(timestamp);
+    }
+
+    let invalid = || format!("invalid date '{s}' (expected YYYY-MM-DD or UNIX seconds)");
+    let parts: Vec<u64> = s
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [year, month, day] = parts[..] else {
+        return Err(invalid());
+    };
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // 1970-01-01 からの日数 (3月始まりの年で数え、うるう日を年の最後にする)
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year - 719_468;
+    Ok(days * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_params_without_subcommand() {
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+
+        assert_eq!(cli.params, Some(PathBuf::from("params.json")));
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_cli_sign_format() {
+        let cli = Cli::try_parse_from(["signer", "sign", "tx.json", "--format", "ethers"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Sign {
+                format: InputFormat::Ethers,
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Sign {
+                format: InputFormat::Params,
+                ..
+            })
+        ));
+        let cli =
+            Cli::try_parse_from(["signer", "sign", "request.json", "--format", "rpc"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Sign {
+                format: InputFormat::Rpc,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["signer", "sign", "tx.json", "--format", "web3"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_sign_message() {
+        let cli = Cli::try_parse_from(["signer", "sign-message", "--hex", "0x1234"]).unwrap();
+
+        match cli.command {
+            Some(Command::SignMessage {
+                message,
+                hex,
+                validator,
+            }) => {
+                assert_eq!(message, "0x1234");
+                assert!(hex);
+                assert!(validator.is_none());
+            }
+            _ => panic!("Expected SignMessage, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_safe_export() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "safe",
+            "export",
+            "approve.json",
+            "swap.json",
+            "--safe",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Safe {
+                command: SafeCommand::Export { params, safe, name },
+            }) => {
+                assert_eq!(
+                    params,
+                    [PathBuf::from("approve.json"), PathBuf::from("swap.json")]
+                );
+                assert!(safe.is_some());
+                assert_eq!(name, "Transactions Batch");
+            }
+            _ => panic!("expected safe export"),
+        }
+        assert!(Cli::try_parse_from(["signer", "safe", "export"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_safe_sign_service_url_requires_propose() {
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "safe",
+                "sign",
+                "safe_tx.json",
+                "--service-url",
+                "http://localhost"
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "safe",
+                "sign",
+                "safe_tx.json",
+                "--propose",
+                "--service-url",
+                "http://localhost"
+            ])
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_cli_sign_hash() {
+        let hash = "0x1111111111111111111111111111111111111111111111111111111111111111";
+        let cli = Cli::try_parse_from(["signer", "sign-hash", hash]).unwrap();
+
+        match cli.command {
+            Some(Command::SignHash {
+                hash: parsed,
+                i_know_what_im_doing,
+            }) => {
+                assert_eq!(parsed, H256::repeat_byte(0x11));
+                assert!(!i_know_what_im_doing);
+            }
+            _ => panic!("Expected SignHash, got: {:?}", cli.command),
+        }
+
+        // 32バイトでなければエラー
+        assert!(Cli::try_parse_from(["signer", "sign-hash", "0x1234"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_u256() {
+        assert_eq!(parse_u256("1000").unwrap(), U256::from(1000));
+        assert_eq!(parse_u256("0x3e8").unwrap(), U256::from(1000));
+        assert!(parse_u256("3e8").is_err());
+        assert!(parse_u256("-1").is_err());
+    }
+
+    #[test]
+    fn test_cli_permit() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "permit",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--spender",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--value",
+            "1000000",
+            "--deadline",
+            "1700000000",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Permit {
+                value, nonce, name, ..
+            }) => {
+                assert_eq!(value, Some(U256::from(1_000_000)));
+                assert!(nonce.is_none());
+                assert!(name.is_none());
+            }
+            _ => panic!("Expected Permit, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_erc20_transfer() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "erc20",
+            "transfer",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--to",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--amount",
+            "12.5",
+            "--decimals",
+            "6",
+            "--nonce",
+            "3",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Erc20 {
+                command:
+                    Erc20Command::Transfer {
+                        amount,
+                        decimals,
+                        tx,
+                        ..
+                    },
+            }) => {
+                assert_eq!(amount, "12.5");
+                assert_eq!(decimals, Some(6));
+                assert_eq!(tx.nonce, Some(U256::from(3)));
+                assert_eq!(tx.gas_limit, U256::from(100_000));
+            }
+            _ => panic!("Expected Erc20 Transfer, got: {:?}", cli.command),
+        }
+
+        // nonce は省略できる (NONCE_STORE_PATH のストアか RPC で割り当てる)
+        let cli = Cli::try_parse_from([
+            "signer",
+            "erc20",
+            "transfer",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--to",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--amount",
+            "1",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Erc20 {
+                command: Erc20Command::Transfer { tx, .. },
+            }) => assert!(tx.nonce.is_none()),
+            _ => panic!("Expected Erc20 Transfer, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_erc20_approve_revoke() {
+        let base = [
+            "signer",
+            "erc20",
+            "approve",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--spender",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--nonce",
+            "0",
+        ];
+
+        let cli = Cli::try_parse_from(base.iter().chain(&["--revoke"])).unwrap();
+        match cli.command {
+            Some(Command::Erc20 {
+                command: Erc20Command::Approve { amount, revoke, .. },
+            }) => {
+                assert!(amount.is_none());
+                assert!(revoke);
+            }
+            _ => panic!("Expected Erc20 Approve, got: {:?}", cli.command),
+        }
+
+        // --amount と --revoke のどちらかが必要で、同時には指定できない
+        assert!(Cli::try_parse_from(base).is_err());
+        assert!(Cli::try_parse_from(base.iter().chain(&["--revoke", "--amount", "1"])).is_err());
+    }
+
+    #[test]
+    fn test_cli_erc721_transfer() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "erc721",
+            "transfer",
+            "--token",
+            "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D",
+            "--to",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--token-id",
+            "0x10",
+            "--nonce",
+            "1",
+            "--gas-limit",
+            "150000",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Erc721 {
+                command: Erc721Command::Transfer { token_id, tx, .. },
+            }) => {
+                assert_eq!(token_id, U256::from(16));
+                assert_eq!(tx.gas_limit, U256::from(150_000));
+            }
+            _ => panic!("Expected Erc721 Transfer, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_bytes() {
+        assert_eq!(parse_hex_bytes("0x").unwrap(), Vec::<u8>::new());
+        assert_eq!(parse_hex_bytes("0x1234").unwrap(), vec![0x12, 0x34]);
+        assert_eq!(parse_hex_bytes("abcd").unwrap(), vec![0xab, 0xcd]);
+        assert!(parse_hex_bytes("0x123").is_err());
+    }
+
+    #[test]
+    fn test_cli_erc1155_batch_transfer() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "erc1155",
+            "batch-transfer",
+            "--token",
+            "0x76BE3b62873462d2142405439777e971754E8E77",
+            "--to",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--ids",
+            "1,2,0x3",
+            "--amounts",
+            "10,20,30",
+            "--nonce",
+            "0",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Erc1155 {
+                command:
+                    Erc1155Command::BatchTransfer {
+                        ids, amounts, data, ..
+                    },
+            }) => {
+                assert_eq!(ids, vec![U256::from(1), U256::from(2), U256::from(3)]);
+                assert_eq!(amounts.len(), 3);
+                assert!(data.is_empty());
+            }
+            _ => panic!("Expected Erc1155 BatchTransfer, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_import_metamask() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "import",
+            "metamask",
+            "state-logs.json",
+            "--id",
+            "b2",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Import {
+                command: ImportCommand::Metamask { path, id },
+            }) => {
+                assert_eq!(path, PathBuf::from("state-logs.json"));
+                assert_eq!(id.as_deref(), Some("b2"));
+            }
+            _ => panic!("expected import metamask"),
+        }
+        assert!(Cli::try_parse_from(["signer", "import", "metamask"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_plugin() {
+        let cli = Cli::try_parse_from(["signer", "plugin", "serve"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Plugin {
+                command: PluginCommand::Serve {
+                    i_know_what_im_doing: false
+                }
+            })
+        ));
+        let cli =
+            Cli::try_parse_from(["signer", "plugin", "serve", "--i-know-what-im-doing"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Plugin {
+                command: PluginCommand::Serve {
+                    i_know_what_im_doing: true
+                }
+            })
+        ));
+        assert!(Cli::try_parse_from(["signer", "plugin"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_keystore_inspect() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "keystore",
+            "inspect",
+            "UTC--2016.json",
+            "--decrypt",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Keystore {
+                command: KeystoreCommand::Inspect { path, decrypt },
+            }) => {
+                assert_eq!(path, PathBuf::from("UTC--2016.json"));
+                assert!(decrypt);
+            }
+            _ => panic!("expected keystore inspect"),
+        }
+        assert!(Cli::try_parse_from(["signer", "keystore", "inspect"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_keystore_import() {
+        let cli = Cli::try_parse_from(["signer", "keystore", "import", "parity.json", "key.json"])
+            .unwrap();
+        match cli.command {
+            Some(Command::Keystore {
+                command:
+                    KeystoreCommand::Import {
+                        path,
+                        output,
+                        iterations,
+                    },
+            }) => {
+                assert_eq!(path, PathBuf::from("parity.json"));
+                assert_eq!(output, PathBuf::from("key.json"));
+                assert_eq!(iterations, keystore::DEFAULT_ITERATIONS);
+            }
+            _ => panic!("expected keystore import"),
+        }
+        assert!(Cli::try_parse_from(["signer", "keystore", "import", "parity.json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_calldata_encode() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "calldata",
+            "encode",
+            "f(int256,uint256[])",
+            "-1",
+            "[1,2]",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Calldata {
+                command:
+                    CalldataCommand::Encode {
+                        signature,
+                        args,
+                        file,
+                    },
+            }) => {
+                assert_eq!(signature.as_deref(), Some("f(int256,uint256[])"));
+                assert_eq!(args, vec!["-1", "[1,2]"]);
+                assert!(file.is_none());
+            }
+            _ => panic!("Expected Calldata Encode, got: {:?}", cli.command),
+        }
+
+        assert!(
+            Cli::try_parse_from(["signer", "calldata", "encode", "--file", "call.json"]).is_ok()
+        );
+        assert!(Cli::try_parse_from(["signer", "calldata", "encode"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_multicall() {
+        let cli =
+            Cli::try_parse_from(["signer", "multicall", "calls.json", "--nonce", "9"]).unwrap();
+
+        match cli.command {
+            Some(Command::Multicall {
+                calls,
+                multicall,
+                tx,
+            }) => {
+                assert_eq!(calls, PathBuf::from("calls.json"));
+                assert!(multicall.is_none());
+                assert_eq!(tx.nonce, Some(U256::from(9)));
+            }
+            _ => panic!("Expected Multicall, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_create2_address() {
+        let salt = "0x0000000000000000000000000000000000000000000000000000000000000001";
+        let cli = Cli::try_parse_from([
+            "signer",
+            "create2",
+            "address",
+            "--salt",
+            salt,
+            "--init-code",
+            "0x6000",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Create2 {
+                command:
+                    Create2Command::Address {
+                        factory,
+                        init_code_hash,
+                        init_code,
+                        ..
+                    },
+            }) => {
+                assert!(factory.is_none());
+                assert!(init_code_hash.is_none());
+                assert_eq!(init_code.hex, Some(vec![0x60, 0x00]));
+            }
+            _ => panic!("Expected Create2 Address, got: {:?}", cli.command),
+        }
+
+        // init code かそのハッシュのどちらか1つが必要
+        let base = ["signer", "create2", "address", "--salt", salt];
+        assert!(Cli::try_parse_from(base).is_err());
+        assert!(
+            Cli::try_parse_from(base.iter().chain(&[
+                "--init-code",
+                "0x6000",
+                "--init-code-file",
+                "code.hex"
+            ]))
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from(base.iter().chain(&[
+                "--init-code-hash",
+                salt,
+                "--init-code",
+                "0x6000"
+            ]))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_deploy() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "deploy",
+            "--init-code-file",
+            "Token.json",
+            "--constructor",
+            "constructor(address,uint256)",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "1000",
+            "--nonce",
+            "0",
+            "--gas-limit",
+            "2000000",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Deploy {
+                init_code,
+                constructor,
+                args,
+                value,
+                tx,
+            }) => {
+                assert_eq!(init_code.file, Some(PathBuf::from("Token.json")));
+                assert_eq!(constructor.as_deref(), Some("constructor(address,uint256)"));
+                assert_eq!(args.len(), 2);
+                assert!(value.is_zero());
+                assert_eq!(tx.gas_limit, U256::from(2_000_000));
+            }
+            _ => panic!("Expected Deploy, got: {:?}", cli.command),
+        }
+
+        // 引数だけ渡してもシグネチャがなければエラー
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "deploy",
+                "--init-code",
+                "0x6080",
+                "1000",
+                "--nonce",
+                "0"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_weth_wrap() {
+        let cli =
+            Cli::try_parse_from(["signer", "weth", "wrap", "--amount", "1.5", "--nonce", "2"])
+                .unwrap();
+
+        match cli.command {
+            Some(Command::Weth {
+                command: WethCommand::Wrap { amount, weth, .. },
+            }) => {
+                assert_eq!(amount, "1.5");
+                assert!(weth.is_none());
+            }
+            _ => panic!("Expected Weth Wrap, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_permit_amount() {
+        let base = [
+            "signer",
+            "permit",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--spender",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "--deadline",
+            "1700000000",
+        ];
+
+        let cli = Cli::try_parse_from(base.iter().chain(&["--amount", "1.5", "--decimals", "6"]))
+            .unwrap();
+        match cli.command {
+            Some(Command::Permit {
+                value,
+                amount,
+                decimals,
+                ..
+            }) => {
+                assert!(value.is_none());
+                assert_eq!(amount.as_deref(), Some("1.5"));
+                assert_eq!(decimals, Some(6));
+            }
+            _ => panic!("Expected Permit, got: {:?}", cli.command),
+        }
+
+        // --value と --amount のどちらか1つが必要
+        assert!(Cli::try_parse_from(base).is_err());
+        assert!(
+            Cli::try_parse_from(base.iter().chain(&["--value", "1", "--amount", "1"])).is_err()
+        );
+        // --decimals は --amount と一緒に使う
+        assert!(
+            Cli::try_parse_from(base.iter().chain(&["--value", "1", "--decimals", "6"])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_disperse_token() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "disperse",
+            "token",
+            "payouts.csv",
+            "--token",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "--decimals",
+            "6",
+            "--nonce",
+            "4",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Disperse {
+                command:
+                    DisperseCommand::Token {
+                        payouts,
+                        decimals,
+                        disperse,
+                        ..
+                    },
+            }) => {
+                assert_eq!(payouts, PathBuf::from("payouts.csv"));
+                assert_eq!(decimals, Some(6));
+                assert!(disperse.is_none());
+            }
+            _ => panic!("Expected Disperse Token, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_request() {
+        let cli = Cli::try_parse_from(["signer", "request", "eth_blockNumber"]).unwrap();
+        match cli.command {
+            Some(Command::Request { method, params }) => {
+                assert_eq!(method, "eth_blockNumber");
+                assert_eq!(params, "[]");
+            }
+            _ => panic!("expected request"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "request",
+            "eth_signTransaction",
+            r#"[{"to":"0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df"}]"#,
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Request { params, .. }) if params.starts_with("[{")
+        ));
+        assert!(Cli::try_parse_from(["signer", "request"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_serve() {
+        let cli = Cli::try_parse_from(["signer", "serve"]).unwrap();
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.listen, "127.0.0.1:8550".parse().unwrap());
+                assert!(args.unix_socket.is_none());
+                assert!(args.approval_command.is_none());
+                assert!(args.auth_config.is_none());
+            }
+            _ => panic!("Expected Serve, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "serve", "--listen", "localhost"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "serve",
+            "--auth-config",
+            "auth.json",
+            "--max-requests-per-minute",
+            "60",
+            "--max-value-per-day",
+            "1.5",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.auth_config, Some(PathBuf::from("auth.json")));
+                assert_eq!(args.max_requests_per_minute, Some(60));
+                assert_eq!(args.max_value_per_day.as_deref(), Some("1.5"));
+            }
+            _ => panic!("Expected Serve, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from(["signer", "serve", "--key-file", "key.json"]).unwrap();
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.key_file, Some(PathBuf::from("key.json")));
+                assert_eq!(args.unlock_timeout, 300);
+            }
+            _ => panic!("Expected Serve, got: {:?}", cli.command),
+        }
+        assert!(Cli::try_parse_from(["signer", "serve", "--unlock-timeout", "60"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_key_commands() {
+        let cli = Cli::try_parse_from(["signer", "encrypt-key", "key.json"]).unwrap();
+        match cli.command {
+            Some(Command::EncryptKey { output, iterations }) => {
+                assert_eq!(output, PathBuf::from("key.json"));
+                assert_eq!(iterations, 600_000);
+            }
+            _ => panic!("Expected EncryptKey, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from(["signer", "unlock", "--token", "t"]).unwrap();
+        match cli.command {
+            Some(Command::Unlock { server, token }) => {
+                assert_eq!(server, "http://127.0.0.1:8550");
+                assert_eq!(
+                    token.as_ref().map(|token| token.expose().as_str()),
+                    Some("t")
+                );
+            }
+            _ => panic!("Expected Unlock, got: {:?}", cli.command),
+        }
+
+        let cli =
+            Cli::try_parse_from(["signer", "lock", "--server", "https://signer:8550"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Lock { server, token: None }) if server == "https://signer:8550"
+        ));
+    }
+
+    #[test]
+    fn test_cli_approve() {
+        let cli = Cli::try_parse_from(["signer", "approve", "0123abcd", "--token", "t"]).unwrap();
+        match cli.command {
+            Some(Command::Approve {
+                request_id,
+                server,
+                token,
+                reject,
+            }) => {
+                assert_eq!(request_id, "0123abcd");
+                assert_eq!(server, "http://127.0.0.1:8550");
+                assert_eq!(
+                    token.as_ref().map(|token| token.expose().as_str()),
+                    Some("t")
+                );
+                assert!(!reject);
+            }
+            _ => panic!("Expected Approve, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "approve"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_audit_verify() {
+        let cli = Cli::try_parse_from(["signer", "audit", "verify", "audit.log"]).unwrap();
+        match cli.command {
+            Some(Command::Audit {
+                command: AuditCommand::Verify { log },
+            }) => assert_eq!(log, PathBuf::from("audit.log")),
+            _ => panic!("Expected Audit Verify, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "audit", "verify"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_policy_test() {
+        let cli = Cli::try_parse_from(["signer", "policy", "test", "examples"]).unwrap();
+        match cli.command {
+            Some(Command::Policy {
+                command: PolicyCommand::Test { examples, policy },
+            }) => {
+                assert_eq!(examples, PathBuf::from("examples"));
+                assert_eq!(policy, None);
+            }
+            _ => panic!("Expected Policy Test, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "policy",
+            "test",
+            "examples",
+            "--policy",
+            "new-policy.toml",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Policy {
+                command: PolicyCommand::Test { policy, .. },
+            }) => assert_eq!(policy, Some(PathBuf::from("new-policy.toml"))),
+            _ => panic!("Expected Policy Test, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "policy", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_nonce() {
+        let cli = Cli::try_parse_from(["signer", "nonce", "show"]).unwrap();
+        match cli.command {
+            Some(Command::Nonce {
+                command: NonceCommand::Show { address },
+            }) => assert!(address.is_none()),
+            _ => panic!("Expected Nonce Show, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "nonce",
+            "set",
+            "0x10",
+            "--address",
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Nonce {
+                command: NonceCommand::Set { nonce, address },
+            }) => {
+                assert_eq!(nonce, Some(U256::from(16)));
+                assert!(address.is_some());
+            }
+            _ => panic!("Expected Nonce Set, got: {:?}", cli.command),
+        }
+    }
+
+    #[test]
+    fn test_cli_bench() {
+        let cli = Cli::try_parse_from(["signer", "bench", "sign"]).unwrap();
+        match cli.command {
+            Some(Command::Bench {
+                command:
+                    BenchCommand::Sign {
+                        count,
+                        jobs,
+                        calldata,
+                    },
+            }) => {
+                assert_eq!(count, 10000);
+                assert_eq!(jobs, None);
+                assert_eq!(calldata, 68);
+            }
+            _ => panic!("Expected Bench Sign, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "bench",
+            "sign",
+            "--count",
+            "500",
+            "--jobs",
+            "4",
+            "--calldata",
+            "0",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Bench {
+                command:
+                    BenchCommand::Sign {
+                        count,
+                        jobs,
+                        calldata,
+                    },
+            }) => {
+                assert_eq!(count, 500);
+                assert_eq!(jobs, Some(4));
+                assert_eq!(calldata, 0);
+            }
+            _ => panic!("Expected Bench Sign, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "bench", "sign", "--count", "0"]).is_err());
+        assert!(Cli::try_parse_from(["signer", "bench", "sign", "--jobs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_history() {
+        let cli = Cli::try_parse_from(["signer", "history", "list", "--pending"]).unwrap();
+        match cli.command {
+            Some(Command::History {
+                command: HistoryCommand::List { pending },
+            }) => assert!(pending),
+            _ => panic!("Expected History List, got: {:?}", cli.command),
+        }
+
+        let hash = format!("0x{}", "ab".repeat(32));
+        let cli = Cli::try_parse_from(["signer", "history", "show", &hash]).unwrap();
+        match cli.command {
+            Some(Command::History {
+                command: HistoryCommand::Show { hash },
+            }) => assert_eq!(hash, H256::repeat_byte(0xab)),
+            _ => panic!("Expected History Show, got: {:?}", cli.command),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "history", "show", "0x1234"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "history",
+            "export",
+            "history.csv",
+            "--since",
+            "2024-03-01",
+            "--until",
+            "1710000000",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::History {
+                command:
+                    HistoryCommand::Export {
+                        output,
+                        since,
+                        until,
+                    },
+            }) => {
+                assert_eq!(output, PathBuf::from("history.csv"));
+                assert_eq!(since, Some(1709251200));
+                assert_eq!(until, Some(1710000000));
+            }
+            _ => panic!("Expected History Export, got: {:?}", cli.command),
+        }
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "history",
+                "export",
+                "a.json",
+                "--since",
+                "2024-13-01"
+            ])
+            .is_err()
+        );
+
+        let cli = Cli::try_parse_from(["signer", "history", "import", "history.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::History {
+                command: HistoryCommand::Import { .. }
+            })
+        ));
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "history",
+            "report",
+            "fees.csv",
+            "--since",
+            "2024-03-01",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::History {
+                command:
+                    HistoryCommand::Report {
+                        output,
+                        since,
+                        until,
+                    },
+            }) => {
+                assert_eq!(output, Some(PathBuf::from("fees.csv")));
+                assert_eq!(since, Some(1709251200));
+                assert_eq!(until, None);
+            }
+            _ => panic!("Expected History Report, got: {:?}", cli.command),
+        }
+        let cli = Cli::try_parse_from(["signer", "history", "report"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::History {
+                command: HistoryCommand::Report { output: None, .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cli_status() {
+        let cli = Cli::try_parse_from(["signer", "status"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Status { watch: None })));
+        let cli = Cli::try_parse_from(["signer", "status", "--watch", "15"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Status { watch: Some(15) })
+        ));
+    }
+
+    #[test]
+    fn test_cli_mainnet() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--mainnet"]).unwrap();
+        assert!(cli.mainnet);
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(!cli.mainnet);
+    }
+
+    #[test]
+    fn test_cli_yes() {
+        let cli = Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--yes"]).unwrap();
+        assert!(cli.yes);
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(!cli.yes);
+    }
+
+    #[test]
+    fn test_cli_allow_flagged() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign", "params.json", "--allow-flagged"]).unwrap();
+        assert!(cli.allow_flagged);
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(!cli.allow_flagged);
+    }
+
+    #[test]
+    fn test_cli_simulate() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--simulate"]).unwrap();
+        assert!(cli.simulate);
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(!cli.simulate);
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "sign",
+                "params.json",
+                "--simulate",
+                "--deterministic"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_deterministic() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--deterministic"]).unwrap();
+        assert!(cli.deterministic);
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert!(!cli.deterministic);
+        // 手数料はその時点の値になるため --speed とは併用できない
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "sign",
+                "params.json",
+                "--deterministic",
+                "--speed",
+                "fast"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_chains() {
+        let cli = Cli::try_parse_from(["signer", "chains"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Chains { command: None })
+        ));
+    }
+
+    #[test]
+    fn test_cli_chains_import() {
+        let cli = Cli::try_parse_from(["signer", "chains", "import", "100", "56"]).unwrap();
+        match cli.command {
+            Some(Command::Chains {
+                command: Some(ChainsCommand::Import { chain_ids, refresh }),
+            }) => {
+                assert_eq!(chain_ids, [100, 56]);
+                assert_eq!(refresh, None);
+            }
+            _ => panic!("Expected Chains Import command"),
+        }
+
+        let cli = Cli::try_parse_from(["signer", "chains", "import", "100", "--refresh"]).unwrap();
+        match cli.command {
+            Some(Command::Chains {
+                command: Some(ChainsCommand::Import { refresh, .. }),
+            }) => assert_eq!(refresh.as_deref(), Some(chainlist::CHAINLIST_URL)),
+            _ => panic!("Expected Chains Import command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "chains",
+            "import",
+            "--refresh=http://localhost:8000/chains.json",
+            "100",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Chains {
+                command: Some(ChainsCommand::Import { refresh, .. }),
+            }) => assert_eq!(
+                refresh.as_deref(),
+                Some("http://localhost:8000/chains.json")
+            ),
+            _ => panic!("Expected Chains Import command"),
+        }
+
+        assert!(Cli::try_parse_from(["signer", "chains", "import"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_cost() {
+        let cli = Cli::try_parse_from(["signer", "cost", "params.json"]).unwrap();
+        match cli.command {
+            Some(Command::Cost { params }) => assert_eq!(params, PathBuf::from("params.json")),
+            _ => panic!("Expected Cost command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_prepare() {
+        let cli = Cli::try_parse_from(["signer", "prepare", "params.json"]).unwrap();
+        match cli.command {
+            Some(Command::Prepare {
+                params,
+                device,
+                derivation_path,
+                master_fingerprint,
+            }) => {
+                assert_eq!(params, PathBuf::from("params.json"));
+                assert_eq!(device, None);
+                assert_eq!(derivation_path.to_string(), "44'/60'/0'/0/0");
+                assert_eq!(master_fingerprint, None);
+            }
+            _ => panic!("Expected Prepare command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "prepare",
+            "params.json",
+            "--device",
+            "keystone",
+            "--derivation-path",
+            "m/44'/60'/0'/0/3",
+            "--master-fingerprint",
+            "12ab34cd",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Prepare {
+                device,
+                derivation_path,
+                master_fingerprint,
+                ..
+            }) => {
+                assert_eq!(device, Some(hardware::Device::Keystone));
+                assert_eq!(derivation_path.to_string(), "44'/60'/0'/0/3");
+                assert_eq!(master_fingerprint, Some(0x12ab34cd));
+            }
+            _ => panic!("Expected Prepare command"),
+        }
+
+        // --device なしの導出パスや不正な値はエラー
+        for args in [
+            &["--derivation-path", "m/44'/60'/0'/0/1"][..],
+            &["--device", "trezor"],
+            &["--device", "ledger", "--derivation-path", "m/44'/x"],
+            &["--device", "keystone", "--master-fingerprint", "12ab"],
+        ] {
+            let argv = ["signer", "prepare", "params.json"].iter().chain(args);
+            assert!(Cli::try_parse_from(argv).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_cli_assemble() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "assemble",
+            "prepared.json",
+            "--r",
+            "0x01",
+            "--s",
+            "2",
+            "--v",
+            "27",
+            "--from",
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Assemble {
+                prepared,
+                r,
+                s,
+                v,
+                signature,
+                from,
+            }) => {
+                assert_eq!(prepared, PathBuf::from("prepared.json"));
+                assert_eq!(
+                    (r, s, v),
+                    (Some(U256::from(1)), Some(U256::from(2)), Some(27))
+                );
+                assert!(signature.is_none());
+                assert!(from.is_some());
+            }
+            _ => panic!("Expected Assemble command"),
+        }
+        assert!(Cli::try_parse_from(["signer", "assemble", "prepared.json", "--r", "1"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "assemble",
+            "prepared.json",
+            "--signature",
+            "UR:ETH-SIGNATURE/OTADTPDAGD",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Assemble {
+                r, v, signature, ..
+            }) => {
+                assert_eq!((r, v), (None, None));
+                assert_eq!(signature.as_deref(), Some("UR:ETH-SIGNATURE/OTADTPDAGD"));
+            }
+            _ => panic!("Expected Assemble command"),
+        }
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "assemble",
+                "prepared.json",
+                "--signature",
+                "ur:eth-signature/x",
+                "--v",
+                "27",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_offline() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "offline",
+            "prepare",
+            "params.json",
+            "--from",
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Offline {
+                command:
+                    OfflineCommand::Prepare {
+                        params, expires_in, ..
+                    },
+            }) => {
+                assert_eq!(params, PathBuf::from("params.json"));
+                assert_eq!(expires_in, 60);
+            }
+            _ => panic!("Expected Offline Prepare, got: {:?}", cli.command),
+        }
+        // 送信者のアドレスは必須
+        assert!(Cli::try_parse_from(["signer", "offline", "prepare", "params.json"]).is_err());
+
+        let cli = Cli::try_parse_from(["signer", "offline", "sign", "request.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Offline {
+                command: OfflineCommand::Sign { .. }
+            })
+        ));
+        let cli = Cli::try_parse_from(["signer", "online", "broadcast", "signed.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Online {
+                command: OnlineCommand::Broadcast { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cli_sign_batch() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--jobs", "8"]).unwrap();
+        match cli.command {
+            Some(Command::SignBatch {
+                params,
+                jobs,
+                broadcast,
+                concurrency,
+            }) => {
+                assert_eq!(params, PathBuf::from("batch.json"));
+                assert_eq!(jobs, Some(8));
+                assert!(!broadcast);
+                assert_eq!(concurrency, 8);
+            }
+            _ => panic!("Expected SignBatch, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "sign-batch",
+            "batch.json",
+            "--broadcast",
+            "--concurrency",
+            "32",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::SignBatch {
+                broadcast: true,
+                concurrency: 32,
+                ..
+            })
+        ));
+        assert!(
+            Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--concurrency", "4"])
+                .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "sign-batch",
+                "batch.json",
+                "--broadcast",
+                "--concurrency",
+                "0"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_fill_gaps() {
+        let cli = Cli::try_parse_from(["signer", "fill-gaps"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::FillGaps)));
+    }
+
+    #[test]
+    fn test_cli_rebump() {
+        let cli = Cli::try_parse_from(["signer", "rebump"]).unwrap();
+        match cli.command {
+            Some(Command::Rebump {
+                blocks,
+                percent,
+                max_fee,
+                max_total_fee,
+            }) => {
+                assert_eq!(blocks, 20);
+                assert_eq!(percent, 12);
+                assert!(max_fee.is_none());
+                assert!(max_total_fee.is_none());
+            }
+            _ => panic!("Expected Rebump, got: {:?}", cli.command),
+        }
+
+        let cli = Cli::try_parse_from([
+            "signer",
+            "rebump",
+            "--blocks",
+            "5",
+            "--max-total-fee",
+            "0xde0b6b3a7640000",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Rebump { blocks: 5, max_total_fee: Some(total), .. })
+                if total == U256::exp10(18)
+        ));
+        // 10% 未満ではノードが置き換えない
+        assert!(Cli::try_parse_from(["signer", "rebump", "--percent", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_strict_hex() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--strict-hex"]).unwrap();
+        assert!(cli.strict_hex);
+        let cli =
+            Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--strict-hex"]).unwrap();
+        assert!(cli.strict_hex);
+        let cli = Cli::try_parse_from(["signer", "params.json", "--strict-hex"]).unwrap();
+        assert!(cli.strict_hex);
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.strict_hex);
+    }
+
+    #[test]
+    fn test_cli_decimal_strings() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign", "params.json", "--decimal-strings"]).unwrap();
+        assert!(cli.decimal_strings);
+        let cli = Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--decimal-strings"])
+            .unwrap();
+        assert!(cli.decimal_strings);
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.decimal_strings);
+        assert!(
+            Cli::try_parse_from(["signer", "params.json", "--decimal-strings", "--strict-hex"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_verbose() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--verbose"]).unwrap();
+        assert!(cli.verbose);
+        let cli =
+            Cli::try_parse_from(["signer", "calldata", "encode", "f()", "--verbose"]).unwrap();
+        assert!(cli.verbose);
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.verbose);
+    }
+
+    #[test]
+    fn test_cli_strict() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--strict"]).unwrap();
+        assert!(cli.strict);
+        assert!(!cli.strict_hex);
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.strict);
+    }
+
+    #[test]
+    fn test_cli_speed() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign", "params.json", "--speed", "fast"]).unwrap();
+        assert_eq!(cli.speed, Some(Speed::Fast));
+        let cli =
+            Cli::try_parse_from(["signer", "cost", "params.json", "--speed", "safe"]).unwrap();
+        assert_eq!(cli.speed, Some(Speed::Safe));
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert_eq!(cli.speed, None);
+        assert!(
+            Cli::try_parse_from(["signer", "sign", "params.json", "--speed", "rapid"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_lang() {
+        let cli = Cli::try_parse_from(["signer", "cost", "params.json", "--lang", "ja"]).unwrap();
+        assert_eq!(cli.lang, Some(Lang::Ja));
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json"]).unwrap();
+        assert_eq!(cli.lang, None);
+        assert!(Cli::try_parse_from(["signer", "sign", "params.json", "--lang", "fr"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_fiat() {
+        let cli = Cli::try_parse_from(["signer", "cost", "params.json", "--fiat", "jpy"]).unwrap();
+        assert_eq!(cli.fiat, Some(Currency::Jpy));
+        assert_eq!(cli.eth_price, None);
+        let cli = Cli::try_parse_from([
+            "signer",
+            "history",
+            "report",
+            "--fiat",
+            "eur",
+            "--eth-price",
+            "2750.5",
+        ])
+        .unwrap();
+        assert_eq!(cli.fiat, Some(Currency::Eur));
+        assert_eq!(cli.eth_price, Some(U256::from(275_050_000_000u64)));
+        let cli = Cli::try_parse_from(["signer", "cost", "params.json"]).unwrap();
+        assert_eq!(cli.fiat, None);
+        assert!(Cli::try_parse_from(["signer", "cost", "params.json", "--fiat", "gbp"]).is_err());
+        assert!(
+            Cli::try_parse_from(["signer", "cost", "params.json", "--eth-price", "$3000"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_lenient() {
+        let cli = Cli::try_parse_from(["signer", "sign", "params.json", "--lenient"]).unwrap();
+        assert!(cli.lenient);
+        let cli = Cli::try_parse_from(["signer", "sign-batch", "batch.json", "--lenient"]).unwrap();
+        assert!(cli.lenient);
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.lenient);
+    }
+
+    #[test]
+    fn test_cli_allow_duplicate() {
+        let cli =
+            Cli::try_parse_from(["signer", "sign", "params.json", "--allow-duplicate"]).unwrap();
+        assert!(cli.allow_duplicate);
+        let cli = Cli::try_parse_from(["signer", "--allow-duplicate", "params.json"]).unwrap();
+        assert!(cli.allow_duplicate);
+        assert_eq!(cli.params, Some(PathBuf::from("params.json")));
+        let cli = Cli::try_parse_from(["signer", "params.json"]).unwrap();
+        assert!(!cli.allow_duplicate);
+    }
+
+    #[test]
+    fn test_cli_serve_tls() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "serve",
+            "--tls-cert",
+            "cert.pem",
+            "--tls-key",
+            "key.pem",
+            "--tls-client-ca",
+            "ca.pem",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.tls_cert, Some(PathBuf::from("cert.pem")));
+                assert_eq!(args.tls_key, Some(PathBuf::from("key.pem")));
+                assert_eq!(args.tls_client_ca, Some(PathBuf::from("ca.pem")));
+            }
+            _ => panic!("Expected Serve, got: {:?}", cli.command),
+        }
+
+        // 証明書と鍵は両方必要
+        assert!(Cli::try_parse_from(["signer", "serve", "--tls-cert", "cert.pem"]).is_err());
+        assert!(Cli::try_parse_from(["signer", "serve", "--tls-client-ca", "ca.pem"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "serve",
+                "--unix-socket",
+                "s.sock",
+                "--tls-cert",
+                "cert.pem",
+                "--tls-key",
+                "key.pem",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_serve_unix_socket() {
+        let cli = Cli::try_parse_from([
+            "signer",
+            "serve",
+            "--unix-socket",
+            "/run/signer.sock",
+            "--socket-mode",
+            "660",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.unix_socket, Some(PathBuf::from("/run/signer.sock")));
+                assert_eq!(args.socket_mode, 0o660);
+            }
+            _ => panic!("Expected Serve, got: {:?}", cli.command),
+        }
+
+        // TCP と同時には指定できない
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "serve",
+                "--unix-socket",
+                "/run/signer.sock",
+                "--listen",
+                "127.0.0.1:8550",
+            ])
+            .is_err()
+        );
+        // --socket-mode は --unix-socket と一緒に使う
+        assert!(Cli::try_parse_from(["signer", "serve", "--socket-mode", "600"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "signer",
+                "serve",
+                "--unix-socket",
+                "s.sock",
+                "--socket-mode",
+                "999",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_verify_definition() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+}
@@ -1,39 +1,477 @@
-use crate::{Result, de::deserialize_u256, error::Error};
+use crate::{
+    Result,
+    audit::AuditLog,
+    chains,
+    contracts::{self, VerifiedContracts},
+    de::{self, deserialize_u256},
+    denylist::Denylist,
+    error::Error,
+    journal::Journal,
+    keystore,
+    nonce::NonceStore,
+    plugin::Plugin,
+    policy::Policy,
+    price::{self, PriceSource},
+    rawtx::RawTxDir,
+    rpc::RpcClient,
+    secret::{self, Secret},
+    signer::Key,
+    state::StateKey,
+    transaction::TxType,
+    units,
+    webhook::Webhooks,
+};
+use ethereum::EIP1559TransactionMessage;
 use ethereum_types::U256;
-use serde::Deserialize;
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, de::DeserializeOwned};
+use std::{collections::HashMap, path::PathBuf};
 
 // 環境変数パラメータ
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub chain_id: u64,
     #[serde(deserialize_with = "deserialize_u256")]
     pub max_fee_per_gas: U256,
     #[serde(deserialize_with = "deserialize_u256")]
     pub max_priority_fee_per_gas: U256,
-    pub private_key: String,
+    // 鍵ファイルで serve する場合は省略できる
+    #[serde(default)]
+    pub private_key: Secret<String>,
+    // PRIVATE_KEY の代わりに署名する外部のプラグインの実行ファイル (任意)
+    #[serde(default)]
+    pub signer_plugin: Option<String>,
+    // トークン情報の取得などに使う JSON-RPC エンドポイント (任意)
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    // 署名前に確認するポリシーファイル (任意、TOML / YAML)
+    #[serde(default)]
+    pub policy_path: Option<String>,
+    // approve / setApprovalForAll で権限を渡してよいコントラクトとコードのハッシュ (任意、TOML / YAML)
+    #[serde(default)]
+    pub verified_contracts_path: Option<String>,
+    // 送金や approve を拒否するアドレスのリスト (任意、署名のたびに読み込む)
+    #[serde(default)]
+    pub denylist_path: Option<String>,
+    // 署名要求を記録する監査ログ (任意)
+    #[serde(default)]
+    pub audit_log_path: Option<String>,
+    // 署名や送信を通知する Webhook の設定ファイル (任意、TOML / YAML)
+    #[serde(default)]
+    pub webhooks_path: Option<String>,
+    // nonce を省略したときに割り当てるローカルのストア (任意)
+    #[serde(default)]
+    pub nonce_store_path: Option<String>,
+    // 署名したトランザクションを記録するジャーナル (任意)
+    #[serde(default)]
+    pub journal_path: Option<String>,
+    // ジャーナルにある署名済みのトランザクションとの重複を許可する (CLI では --allow-duplicate)
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    // 署名した raw トランザクションを書き出すディレクトリ (任意)
+    #[serde(default)]
+    pub raw_tx_dir: Option<String>,
+    // ジャーナルと nonce ストアを暗号化するパスフレーズ、または32バイトのデータキー (どちらか一方、任意)
+    #[serde(default)]
+    pub state_passphrase: Option<Secret<String>>,
+    #[serde(default)]
+    pub state_key: Option<Secret<String>>,
+    // --speed で Polygon の手数料を取得する gas station の URL (任意、既定は公式の API)
+    #[serde(default)]
+    pub polygon_gas_station_url: Option<String>,
+    // これを超える value のトランザクションは確認の入力が必要 ("0.5 eth"、任意)
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    pub confirm_above: Option<U256>,
+    // 署名するトランザクションの形式 (TX_TYPE か RPC_URL のチェーンから判定したもの)
+    #[serde(skip)]
+    pub tx_type: TxType,
+    // TX_TYPE で指定した形式 ("eip1559" か "legacy"、任意、指定すれば RPC_URL のチェーンから判定しない)
+    #[serde(default, rename = "tx_type", deserialize_with = "deserialize_tx_type")]
+    pub explicit_tx_type: Option<TxType>,
+    // CONFIRM_ABOVE の確認を省く (CLI の --yes)
+    #[serde(skip)]
+    pub yes: bool,
+    // DENYLIST_PATH のアドレスへの署名を警告だけにする (CLI の --allow-flagged)
+    #[serde(skip)]
+    pub allow_flagged: bool,
+    // 署名前にトランザクションを RPC_URL で実行し、残高の増減を表示する (CLI の --simulate)
+    #[serde(skip)]
+    pub simulate: bool,
+    // 実行ごとに変わりうる値 (RPC の応答や nonce ストア) を使わない (CLI の --deterministic)
+    #[serde(skip)]
+    pub deterministic: bool,
+}
+
+fn deserialize_amount<'de, D>(deserializer: D) -> std::result::Result<Option<U256>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let amount = String::deserialize(deserializer)?;
+    units::parse_amount(&amount)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn deserialize_tx_type<'de, D>(deserializer: D) -> std::result::Result<Option<TxType>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    match name.to_ascii_lowercase().as_str() {
+        "eip1559" => Ok(Some(TxType::Eip1559)),
+        "legacy" => Ok(Some(TxType::Legacy)),
+        _ => Err(serde::de::Error::custom(format!(
+            "'{name}' is not a transaction type, expected eip1559 or legacy"
+        ))),
+    }
+}
+
+// ジャーナルだけを使うコマンド (history) の設定
+// 署名の設定 (CHAIN_ID など) や PRIVATE_KEY は読まないため、それらがなくても実行できる
+#[derive(Debug, Deserialize)]
+pub struct JournalConfig {
+    #[serde(default)]
+    pub journal_path: Option<String>,
+    #[serde(default)]
+    pub state_passphrase: Option<Secret<String>>,
+    #[serde(default)]
+    pub state_key: Option<Secret<String>>,
+    // history report でレシートを取得する
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+}
+
+impl JournalConfig {
+    pub fn from_env() -> Result<Self> {
+        deserialize(config::Environment::default())
+    }
+
+    // JOURNAL_PATH が設定されていればジャーナルを開く
+    pub fn get_journal(&self) -> Result<Option<Journal>> {
+        let Some(path) = &self.journal_path else {
+            return Ok(None);
+        };
+        let state_key = state_key(&self.state_passphrase, &self.state_key)?;
+        Ok(Some(Journal::new(path, state_key)))
+    }
+    pub fn get_rpc_client(&self) -> Result<RpcClient> {
+        self.rpc_url
+            .as_ref()
+            .map(RpcClient::new)
+            .ok_or(Error::MissingRpcUrl)
+    }
+}
+
+// 手数料を法定通貨に換算する価格の取得先とキャッシュ (--fiat)
+#[derive(Debug, Deserialize)]
+pub struct PriceConfig {
+    // {id} と {currency} を置き換える CoinGecko 互換の API (任意、既定は CoinGecko)
+    #[serde(default)]
+    pub price_api_url: Option<String>,
+    // 取得した価格を覚えておくファイル (任意)
+    #[serde(default)]
+    pub price_cache_path: Option<String>,
+    // キャッシュの価格を使う秒数 (任意)
+    #[serde(default)]
+    pub price_cache_ttl: Option<u64>,
+}
+
+impl PriceConfig {
+    pub fn from_env() -> Result<Self> {
+        deserialize(config::Environment::default())
+    }
+
+    pub fn get_price_source(&self) -> PriceSource {
+        let source = PriceSource::new(
+            self.price_api_url
+                .as_deref()
+                .unwrap_or(price::DEFAULT_PRICE_API_URL),
+        );
+        match &self.price_cache_path {
+            Some(path) => source.with_cache(
+                path,
+                self.price_cache_ttl.unwrap_or(price::DEFAULT_CACHE_TTL),
+            ),
+            None => source,
+        }
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(environment: config::Environment) -> Result<T> {
+    let config = config::Config::builder().add_source(environment).build()?;
+
+    config.try_deserialize().map_err(Into::into)
+}
+
+// STATE_PASSPHRASE または STATE_KEY が設定されていればローカルの状態を暗号化する
+fn state_key(
+    passphrase: &Option<Secret<String>>,
+    key: &Option<Secret<String>>,
+) -> Result<Option<StateKey>> {
+    match (passphrase, key) {
+        (Some(_), Some(_)) => Err(Error::InvalidArgument(
+            "set either STATE_PASSPHRASE or STATE_KEY, not both".to_string(),
+        )),
+        (Some(passphrase), None) if passphrase.expose().is_empty() => Err(Error::InvalidArgument(
+            "STATE_PASSPHRASE must not be empty".to_string(),
+        )),
+        (Some(passphrase), None) => Ok(Some(StateKey::Passphrase {
+            passphrase: passphrase.clone(),
+            iterations: keystore::DEFAULT_ITERATIONS,
+        })),
+        (None, Some(key)) => {
+            let key = secret::decode_hex(key.expose()).map_err(Error::in_env("STATE_KEY"))?;
+            let key = key.try_into().map_err(|key: Vec<u8>| {
+                Error::InvalidArgument(format!("STATE_KEY must be 32 bytes, got {}", key.len()))
+            })?;
+            Ok(Some(StateKey::DataKey(Secret::new(key))))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+// CHAINS_PATH があればチェーンの定義を読み込む
+// CHAIN でプリセットを選んでいれば、省略された CHAIN_ID・RPC_URL・MAX_PRIORITY_FEE_PER_GAS に使う
+// 知らない名前と、CHAIN_ID との食い違いは problems で報告する
+fn with_preset(config: config::Config) -> Result<config::Config> {
+    if let Ok(path) = config.get_string("chains_path") {
+        chains::load(path)?;
+    }
+    let Some(preset) = config
+        .get_string("chain")
+        .ok()
+        .and_then(|name| chains::preset(&name))
+    else {
+        return Ok(config);
+    };
+    let mut builder = config::Config::builder()
+        .set_default("chain_id", preset.chain_id)?
+        .set_default("max_priority_fee_per_gas", preset.priority_fee.to_string())?;
+    if let Some(rpc_url) = preset.rpc_url {
+        builder = builder.set_default("rpc_url", rpc_url)?;
+    }
+    builder.add_source(config).build().map_err(Into::into)
+}
+
+// 署名の設定の環境変数を1つずつ確かめ、問題をすべて返す
+fn problems(config: &config::Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let preset = match config.get_string("chain") {
+        Ok(name) => {
+            let preset = chains::preset(&name);
+            if preset.is_none() {
+                let names: Vec<_> = chains::presets().iter().map(|preset| preset.name).collect();
+                problems.push(format!(
+                    "CHAIN '{name}' is not a known preset, expected one of {}",
+                    names.join(", ")
+                ));
+            }
+            preset
+        }
+        Err(_) => None,
+    };
+    match config.get::<u64>("chain_id") {
+        Ok(chain_id) => {
+            if let Err(Error::InvalidChainId(message)) = chains::validate(chain_id) {
+                problems.push(message);
+            }
+            if let Some(preset) = preset.filter(|preset| preset.chain_id != chain_id) {
+                problems.push(format!(
+                    "CHAIN_ID {chain_id} does not match CHAIN {} ({})",
+                    preset.name, preset.chain_id
+                ));
+            }
+        }
+        Err(error) => problems.push(problem("chain_id", error)),
+    }
+    for name in ["max_fee_per_gas", "max_priority_fee_per_gas"] {
+        match config.get_string(name) {
+            Ok(value) => {
+                if let Err(message) = de::parse_u256(&value) {
+                    problems.push(format!("{}: {message}", name.to_uppercase()));
+                }
+            }
+            Err(error) => problems.push(problem(name, error)),
+        }
+    }
+    if let Some(error) = config
+        .get_string("confirm_above")
+        .ok()
+        .and_then(|amount| units::parse_amount(&amount).err())
+    {
+        problems.push(format!("CONFIRM_ABOVE: {error}"));
+    }
+    match config.get::<bool>("allow_duplicate") {
+        Ok(_) | Err(config::ConfigError::NotFound(_)) => {}
+        Err(error) => problems.push(problem("allow_duplicate", error)),
+    }
+    problems
+}
+
+fn problem(name: &str, error: config::ConfigError) -> String {
+    let name = name.to_uppercase();
+    match error {
+        config::ConfigError::NotFound(_) => format!("{name} is not set"),
+        error => format!("{name}: {error}"),
+    }
+}
+
+// 署名サーバーが設定をリロードするときの読み込み元
+// .env を読み込む前の環境変数を覚えておき、起動時と同じく .env より優先する
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSource {
+    environment: HashMap<String, String>,
+    dotenv_path: Option<PathBuf>,
+}
+
+impl ConfigSource {
+    pub fn new(environment: HashMap<String, String>, dotenv_path: Option<PathBuf>) -> Self {
+        Self {
+            environment,
+            dotenv_path,
+        }
+    }
+
+    pub fn dotenv_path(&self) -> Option<&PathBuf> {
+        self.dotenv_path.as_ref()
+    }
+
+    // .env ファイルを読み直して設定を作る
+    // dotenv::from_path はプロセスの環境変数を書き換え、既存の値も上書きしないため使わない
+    #[allow(deprecated)]
+    pub fn load(&self) -> Result<Config> {
+        let mut variables = match &self.dotenv_path {
+            Some(path) => dotenv::from_path_iter(path)?.collect::<std::result::Result<_, _>>()?,
+            None => config::Map::new(),
+        };
+        variables.extend(self.environment.clone());
+
+        Config::from_environment(config::Environment::default().source(Some(variables)))
+    }
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
-        let config = config::Config::builder()
-            .add_source(config::Environment::default())
-            .build()?;
+        Self::from_environment(config::Environment::default())
+    }
 
+    fn from_environment(environment: config::Environment) -> Result<Self> {
+        let config = config::Config::builder().add_source(environment).build()?;
+        let config = with_preset(config)?;
+        // 最初の1つで止めず、足りない・読めない環境変数をまとめて報告する
+        let problems = problems(&config);
+        if !problems.is_empty() {
+            return Err(Error::InvalidEnvironment(problems));
+        }
         config.try_deserialize().map_err(Into::into)
     }
 
     pub fn get_private_key_bytes(&self) -> Result<[u8; 32]> {
-        // 0xプレフィックスを削除
-        let hex_str = self
-            .private_key
-            .strip_prefix("0x")
-            .unwrap_or(&self.private_key);
-        let decoded = hex::decode(hex_str)?;
+        if self.private_key.expose().is_empty() {
+            return Err(Error::MissingPrivateKey);
+        }
+        // 0xプレフィックスを削除 (不正な文字はエラーに含めない)
+        let decoded =
+            secret::decode_hex(self.private_key.expose()).map_err(Error::in_env("PRIVATE_KEY"))?;
 
         decoded
             .try_into()
             .map_err(|data: Vec<u8>| Error::InvalidPrivateKeyLength(data.len()))
     }
+
+    // 秘密鍵から SigningKey 作成
+    pub fn get_signing_key(&self) -> Result<SigningKey> {
+        let private_key_bytes = self.get_private_key_bytes()?;
+        SigningKey::from_slice(&private_key_bytes).map_err(Into::into)
+    }
+
+    // 秘密鍵とアドレス (実行ごとに1度だけ作り、トランザクションごとには作り直さない)
+    // SIGNER_PLUGIN が設定されていればプラグインを起動する
+    pub fn get_key(&self) -> Result<Key> {
+        match &self.signer_plugin {
+            Some(_) if !self.private_key.expose().is_empty() => Err(Error::InvalidArgument(
+                "set either PRIVATE_KEY or SIGNER_PLUGIN, not both".to_string(),
+            )),
+            Some(path) => Plugin::spawn(path, self.chain_id).map(Key::from_plugin),
+            None => self.get_signing_key().map(Key::new),
+        }
+    }
+
+    // RPC_URL が設定されていれば JSON-RPC クライアントを作成
+    pub fn get_rpc_client(&self) -> Result<RpcClient> {
+        if self.deterministic {
+            return Err(Error::Nondeterministic("RPC_URL"));
+        }
+        self.rpc_url
+            .as_ref()
+            .map(RpcClient::new)
+            .ok_or(Error::MissingRpcUrl)
+    }
+
+    // POLICY_PATH が設定されていればポリシーを読み込む
+    pub fn get_policy(&self) -> Result<Option<Policy>> {
+        self.policy_path.as_ref().map(Policy::from_path).transpose()
+    }
+
+    // DENYLIST_PATH が設定されていればリストを読み込む
+    pub fn get_denylist(&self) -> Result<Option<Denylist>> {
+        self.denylist_path
+            .as_ref()
+            .map(Denylist::from_path)
+            .transpose()
+    }
+
+    // VERIFIED_CONTRACTS_PATH が設定されていれば、権限を渡す相手のコードを署名前に確認する
+    // 取り消しや権限を渡さないトランザクションでは RPC を使わない
+    pub fn verify_approval(&self, message: &EIP1559TransactionMessage) -> Result<()> {
+        let Some(path) = &self.verified_contracts_path else {
+            return Ok(());
+        };
+        let Some(spender) = contracts::approval_spender(message) else {
+            return Ok(());
+        };
+        VerifiedContracts::from_path(path)?.verify(&self.get_rpc_client()?, &spender)?;
+        Ok(())
+    }
+
+    // AUDIT_LOG_PATH が設定されていれば監査ログに記録する
+    pub fn get_audit_log(&self) -> Option<AuditLog> {
+        self.audit_log_path.as_ref().map(AuditLog::new)
+    }
+
+    // STATE_PASSPHRASE または STATE_KEY が設定されていればローカルの状態を暗号化する
+    pub fn get_state_key(&self) -> Result<Option<StateKey>> {
+        state_key(&self.state_passphrase, &self.state_key)
+    }
+
+    // NONCE_STORE_PATH が設定されていれば nonce をローカルで割り当てる
+    pub fn get_nonce_store(&self) -> Result<Option<NonceStore>> {
+        let Some(path) = &self.nonce_store_path else {
+            return Ok(None);
+        };
+        Ok(Some(NonceStore::new(path, self.get_state_key()?)))
+    }
+
+    // JOURNAL_PATH が設定されていれば署名したトランザクションを記録する
+    pub fn get_journal(&self) -> Result<Option<Journal>> {
+        let Some(path) = &self.journal_path else {
+            return Ok(None);
+        };
+        Ok(Some(Journal::new(path, self.get_state_key()?)))
+    }
+
+    // RAW_TX_DIR が設定されていれば署名した raw トランザクションを書き出す
+    pub fn get_raw_tx_dir(&self) -> Option<RawTxDir> {
+        self.raw_tx_dir.as_ref().map(RawTxDir::new)
+    }
+
+    // WEBHOOKS_PATH が設定されていれば Webhook の設定を読み込む
+    pub fn get_webhooks(&self) -> Result<Option<Webhooks>> {
+        self.webhooks_path
+            .as_ref()
+            .map(Webhooks::from_path)
+            .transpose()
+    }
 }
 
 #[cfg(test)]
@@ -51,13 +489,34 @@ mod tests {
             chain_id,
             max_fee_per_gas,
             max_priority_fee_per_gas,
-            private_key: private_key.to_string(),
+            private_key: Secret::new(private_key.to_string()),
+            signer_plugin: None,
+            rpc_url: None,
+            policy_path: None,
+            verified_contracts_path: None,
+            denylist_path: None,
+            audit_log_path: None,
+            webhooks_path: None,
+            nonce_store_path: None,
+            journal_path: None,
+            allow_duplicate: false,
+            raw_tx_dir: None,
+            state_passphrase: None,
+            state_key: None,
+            polygon_gas_station_url: None,
+            confirm_above: None,
+            tx_type: TxType::default(),
+            explicit_tx_type: None,
+            yes: false,
+            allow_flagged: false,
+            simulate: false,
+            deterministic: false,
         }
     }
 
     // JSON文字列から直接Configをデシリアライズするテストヘルパー
     fn config_from_json(json: &str) -> serde_json::Result<Config> {
-        serde_json::from_str(json).map_err(Into::into)
+        serde_json::from_str(json)
     }
 
     #[test]
@@ -75,7 +534,7 @@ mod tests {
         assert_eq!(config.max_fee_per_gas, U256::from(0x77359400u64));
         assert_eq!(config.max_priority_fee_per_gas, U256::from(0x3b9aca00u64));
         assert_eq!(
-            config.private_key,
+            config.private_key.expose(),
             "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
         );
     }
@@ -95,7 +554,7 @@ mod tests {
         assert_eq!(config.max_fee_per_gas, U256::from(0x1dcd65000u64));
         assert_eq!(config.max_priority_fee_per_gas, U256::from(0x77359400u64));
         assert_eq!(
-            config.private_key,
+            config.private_key.expose(),
             "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
         );
     }
@@ -121,15 +580,209 @@ mod tests {
     #[test]
     fn test_config_deserialization_missing_required_field() {
         let json = r#"{
-            "chain_id": 1,
             "max_fee_per_gas": "0x77359400",
-            "max_priority_fee_per_gas": "0x3b9aca00"
+            "max_priority_fee_per_gas": "0x3b9aca00",
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
         }"#;
 
         let result = config_from_json(json);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_config_source_reads_dotenv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        std::fs::write(
+            &path,
+            "CHAIN_ID=1\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=0x2\n",
+        )
+        .unwrap();
+        // 起動時からある環境変数は .env より優先する
+        let environment = HashMap::from([("MAX_FEE_PER_GAS".to_string(), "0x32".to_string())]);
+        let source = ConfigSource::new(environment, Some(path.clone()));
+
+        let config = source.load().unwrap();
+        assert_eq!(config.chain_id, 1);
+        assert_eq!(config.max_fee_per_gas, U256::from(50));
+        assert_eq!(config.max_priority_fee_per_gas, U256::from(2));
+
+        std::fs::write(
+            &path,
+            "CHAIN_ID=1\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=0x3\nPOLICY_PATH=policy.toml\n",
+        )
+        .unwrap();
+        let config = source.load().unwrap();
+        assert_eq!(config.max_priority_fee_per_gas, U256::from(3));
+        assert_eq!(config.policy_path.as_deref(), Some("policy.toml"));
+        assert_eq!(config.confirm_above, None);
+
+        std::fs::write(
+            &path,
+            "CHAIN_ID=1\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=0x3\nCONFIRM_ABOVE=\"0.5 eth\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            source.load().unwrap().confirm_above,
+            Some(U256::from(500_000_000_000_000_000u64))
+        );
+
+        // 環境変数の数字だけの値は10進数
+        std::fs::write(
+            &path,
+            "CHAIN_ID=1\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=2000000000\n",
+        )
+        .unwrap();
+        let config = source.load().unwrap();
+        assert_eq!(
+            config.max_priority_fee_per_gas,
+            U256::from(2_000_000_000u64)
+        );
+
+        // 使えない chain id は読み込み時にエラー
+        std::fs::write(
+            &path,
+            "CHAIN_ID=0\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=0x2\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            source.load(),
+            Err(Error::InvalidEnvironment(problems)) if problems[0].starts_with("CHAIN_ID must not be 0")
+        ));
+
+        // CHAIN で選んだプリセットの値は省略できる
+        std::fs::write(&path, "CHAIN=base\nMAX_FEE_PER_GAS=0x64\n").unwrap();
+        let config = source.load().unwrap();
+        assert_eq!(config.chain_id, 8453);
+        assert_eq!(config.rpc_url.as_deref(), Some("https://mainnet.base.org"));
+        assert_eq!(config.max_priority_fee_per_gas, U256::from(1_000_000));
+        std::fs::write(
+            &path,
+            "CHAIN=base\nCHAIN_ID=8453\nRPC_URL=http://localhost:8545\nMAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=0x2\n",
+        )
+        .unwrap();
+        let config = source.load().unwrap();
+        assert_eq!(config.rpc_url.as_deref(), Some("http://localhost:8545"));
+        assert_eq!(config.max_priority_fee_per_gas, U256::from(2));
+
+        std::fs::write(&path, "CHAIN=base\nCHAIN_ID=10\nMAX_FEE_PER_GAS=0x64\n").unwrap();
+        assert!(matches!(
+            source.load(),
+            Err(Error::InvalidEnvironment(problems)) if problems == ["CHAIN_ID 10 does not match CHAIN base (8453)"]
+        ));
+        std::fs::write(&path, "CHAIN=basee\nMAX_FEE_PER_GAS=0x64\n").unwrap();
+        let Err(Error::InvalidEnvironment(problems)) = source.load() else {
+            panic!("expected InvalidEnvironment");
+        };
+        assert!(
+            problems[0].starts_with("CHAIN 'basee' is not a known preset"),
+            "{problems:?}"
+        );
+
+        // 問題のある環境変数はまとめて報告する
+        std::fs::write(
+            &path,
+            "MAX_FEE_PER_GAS=0x64\nMAX_PRIORITY_FEE_PER_GAS=2gwei\nALLOW_DUPLICATE=maybe\nCONFIRM_ABOVE=\"0.5 btc\"\n",
+        )
+        .unwrap();
+        let Err(Error::InvalidEnvironment(problems)) = source.load() else {
+            panic!("expected InvalidEnvironment");
+        };
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert_eq!(problems[0], "CHAIN_ID is not set");
+        assert!(
+            problems[1].starts_with("MAX_PRIORITY_FEE_PER_GAS: '2gwei'"),
+            "{problems:?}"
+        );
+        assert!(
+            problems[2].starts_with("CONFIRM_ABOVE: Invalid amount: '0.5 btc'"),
+            "{problems:?}"
+        );
+        assert!(problems[3].starts_with("ALLOW_DUPLICATE: "), "{problems:?}");
+    }
+
+    #[test]
+    fn test_journal_config_without_signing_settings() {
+        // CHAIN_ID などがなくても読み込め、PRIVATE_KEY は読まない
+        let environment = |variables: &[(&str, &str)]| {
+            let variables: config::Map<String, String> = variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            config::Environment::default().source(Some(variables))
+        };
+        let journal_config: JournalConfig = deserialize(environment(&[
+            ("JOURNAL_PATH", "journal.jsonl"),
+            ("PRIVATE_KEY", "not a key"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            journal_config.journal_path.as_deref(),
+            Some("journal.jsonl")
+        );
+        assert!(journal_config.get_journal().unwrap().is_some());
+        assert!(deserialize::<Config>(environment(&[("JOURNAL_PATH", "journal.jsonl")])).is_err());
+
+        let journal_config: JournalConfig = deserialize(environment(&[])).unwrap();
+        assert!(journal_config.get_journal().unwrap().is_none());
+
+        let journal_config: JournalConfig = deserialize(environment(&[
+            ("JOURNAL_PATH", "journal.jsonl"),
+            ("STATE_KEY", "0x0707"),
+        ]))
+        .unwrap();
+        assert!(journal_config.get_journal().is_err());
+    }
+
+    #[test]
+    fn test_config_tx_type() {
+        let environment = |tx_type: Option<&str>| {
+            let mut variables: config::Map<String, String> = [
+                ("CHAIN_ID", "56"),
+                ("MAX_FEE_PER_GAS", "0x77359400"),
+                ("MAX_PRIORITY_FEE_PER_GAS", "0x3b9aca00"),
+            ]
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+            if let Some(tx_type) = tx_type {
+                variables.insert("TX_TYPE".to_string(), tx_type.to_string());
+            }
+            config::Environment::default().source(Some(variables))
+        };
+
+        let config: Config = deserialize(environment(None)).unwrap();
+        assert_eq!(config.explicit_tx_type, None);
+        let config: Config = deserialize(environment(Some("legacy"))).unwrap();
+        assert_eq!(config.explicit_tx_type, Some(TxType::Legacy));
+        // 読み込んだだけでは署名する形式は変わらない (main で判定の代わりに使う)
+        assert_eq!(config.tx_type, TxType::Eip1559);
+        let config: Config = deserialize(environment(Some("EIP1559"))).unwrap();
+        assert_eq!(config.explicit_tx_type, Some(TxType::Eip1559));
+
+        let error = deserialize::<Config>(environment(Some("0x71"))).unwrap_err();
+        assert!(
+            error.to_string().contains("expected eip1559 or legacy"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_config_missing_private_key() {
+        // 鍵ファイルで serve する場合は省略できるが、署名には使えない
+        let json = r#"{
+            "chain_id": 1,
+            "max_fee_per_gas": "0x77359400",
+            "max_priority_fee_per_gas": "0x3b9aca00"
+        }"#;
+
+        let config = config_from_json(json).unwrap();
+        assert!(matches!(
+            config.get_signing_key().unwrap_err(),
+            Error::MissingPrivateKey
+        ));
+    }
+
     #[test]
     fn test_config_deserialization_invalid_gas_values() {
         let json = r#"{
@@ -259,7 +912,7 @@ mod tests {
         assert!(result.is_err());
 
         // FromHexエラーが発生することを確認
-        match result {
+        match result.as_ref().map_err(Error::root) {
             Err(Error::FromHex(hex::FromHexError::OddLength)) => {
                 // 期待通り
             }
@@ -267,6 +920,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_signing_key() {
+        let config = create_test_config(
+            1,
+            U256::zero(),
+            U256::zero(),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        );
+
+        let signing_key = config.get_signing_key().unwrap();
+        assert_eq!(
+            signing_key.to_bytes().as_slice(),
+            config.get_private_key_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_key() {
+        let config = create_test_config(
+            1,
+            U256::zero(),
+            U256::zero(),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        );
+
+        let key = config.get_key().unwrap();
+        assert_eq!(key.signing_key(), Some(&config.get_signing_key().unwrap()));
+        assert_eq!(
+            key.address(),
+            crate::signer::tests::TEST_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_key_signer_plugin() {
+        let mut config = create_test_config(
+            1,
+            U256::zero(),
+            U256::zero(),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        );
+        config.signer_plugin = Some("/nonexistent/signer-plugin".to_string());
+
+        // PRIVATE_KEY と両方は指定できない
+        assert!(matches!(config.get_key(), Err(Error::InvalidArgument(_))));
+
+        config.private_key = Secret::new(String::new());
+        assert!(matches!(config.get_key(), Err(Error::Plugin(_))));
+    }
+
+    #[test]
+    fn test_get_signing_key_zero_is_invalid() {
+        // 0 は secp256k1 の秘密鍵として無効
+        let config = create_test_config(
+            1,
+            U256::zero(),
+            U256::zero(),
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        );
+
+        assert!(matches!(config.get_signing_key(), Err(Error::Ecdsa(_))));
+    }
+
+    #[test]
+    fn test_config_rpc_url_optional() {
+        let json = r#"{
+            "chain_id": 1,
+            "max_fee_per_gas": "0x77359400",
+            "max_priority_fee_per_gas": "0x3b9aca00",
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        }"#;
+        let config = config_from_json(json).unwrap();
+
+        assert!(config.rpc_url.is_none());
+        assert!(matches!(config.get_rpc_client(), Err(Error::MissingRpcUrl)));
+    }
+
+    #[test]
+    fn test_config_rpc_url() {
+        let json = r#"{
+            "chain_id": 1,
+            "max_fee_per_gas": "0x77359400",
+            "max_priority_fee_per_gas": "0x3b9aca00",
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            "rpc_url": "https://ethereum-sepolia-rpc.publicnode.com"
+        }"#;
+        let mut config = config_from_json(json).unwrap();
+
+        assert_eq!(
+            config.rpc_url.as_deref(),
+            Some("https://ethereum-sepolia-rpc.publicnode.com")
+        );
+        assert!(config.get_rpc_client().is_ok());
+
+        // --deterministic ではノードに問い合わせない
+        config.deterministic = true;
+        assert!(matches!(
+            config.get_rpc_client(),
+            Err(Error::Nondeterministic("RPC_URL"))
+        ));
+    }
+
     #[test]
     fn test_config_debug_output() {
         let config = create_test_config(
@@ -281,6 +1036,11 @@ mod tests {
         assert!(debug_str.contains("max_fee_per_gas"));
         assert!(debug_str.contains("max_priority_fee_per_gas"));
         assert!(debug_str.contains("private_key"));
+        // 秘密鍵そのものは出力しない
+        assert!(debug_str.contains("private_key: [REDACTED]"));
+        assert!(
+            !debug_str.contains("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+        );
     }
 
     // ===== 実用的なシナリオテスト =====
@@ -358,4 +1118,29 @@ mod tests {
         assert_eq!(config.max_fee_per_gas, high_gas);
         assert!(config.max_fee_per_gas > U256::from(50_000_000_000u64)); // > 50 Gwei
     }
+
+    #[test]
+    fn test_get_state_key() {
+        let mut config = create_test_config(1, U256::zero(), U256::zero(), "");
+        assert!(config.get_state_key().unwrap().is_none());
+
+        config.state_key = Some(Secret::new(format!("0x{}", "07".repeat(32))));
+        assert!(matches!(
+            config.get_state_key().unwrap(),
+            Some(StateKey::DataKey(key)) if *key.expose() == [7; 32]
+        ));
+
+        config.state_passphrase = Some(Secret::new("correct horse".to_string()));
+        assert!(config.get_state_key().is_err());
+
+        config.state_key = None;
+        assert!(matches!(
+            config.get_state_key().unwrap(),
+            Some(StateKey::Passphrase { iterations, .. }) if iterations == keystore::DEFAULT_ITERATIONS
+        ));
+
+        config.state_passphrase = None;
+        config.state_key = Some(Secret::new("0x0707".to_string()));
+        assert!(config.get_state_key().is_err());
+    }
 }
@@ -0,0 +1,143 @@
+use crate::{Result, chains, error::Error, i18n::t, units};
+use ethereum_types::U256;
+use std::io::{BufRead, IsTerminal};
+
+// Ethereum メインネットの chain id
+pub const MAINNET_CHAIN_ID: u64 = 1;
+
+// 端末から実行しているか (標準入力と標準エラー出力がどちらも端末)
+// パイプやスクリプト、CI からの実行では確認の入力を求めない
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+// 指定した語句をそのまま入力させて確認する (y/N ではうっかり通るため)
+pub fn typed(prompt: &str, phrase: &str, input: &mut impl BufRead) -> Result<bool> {
+    eprint!("{}", t!("confirm.type", prompt = prompt, phrase = phrase));
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim() == phrase)
+}
+
+// テストネット向けの手順をうっかりメインネットで実行しないよう、
+// 端末からメインネットを使う場合は --mainnet か確認の入力を求める
+pub fn mainnet(
+    chain_id: u64,
+    confirmed: bool,
+    interactive: bool,
+    input: &mut impl BufRead,
+) -> Result<()> {
+    if chain_id != MAINNET_CHAIN_ID || confirmed || !interactive {
+        return Ok(());
+    }
+    match typed(&t!("confirm.mainnet"), "mainnet", input)? {
+        true => Ok(()),
+        false => Err(Error::MainnetNotConfirmed),
+    }
+}
+
+// CONFIRM_ABOVE を超える value は、端末からは金額の入力で確認し、端末以外からは --yes がなければ拒否する
+pub fn value(
+    chain_id: u64,
+    value: U256,
+    threshold: Option<U256>,
+    yes: bool,
+    interactive: bool,
+    input: &mut impl BufRead,
+) -> Result<()> {
+    let Some(threshold) = threshold.filter(|threshold| value > *threshold) else {
+        return Ok(());
+    };
+    if yes {
+        return Ok(());
+    }
+    // CONFIRM_ABOVE の "eth" と同じ18桁で表示する
+    let symbol = chains::symbol(chain_id);
+    let amount = units::format_units(value, 18);
+    let error = || Error::ValueNotConfirmed {
+        value: format!("{amount} {symbol}"),
+        threshold: format!("{} {symbol}", units::format_units(threshold, 18)),
+    };
+    if !interactive {
+        return Err(error());
+    }
+    let prompt = t!("confirm.value", amount = format!("{amount} {symbol}"));
+    match typed(&prompt, &amount, input)? {
+        true => Ok(()),
+        false => Err(error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_mainnet() {
+        // メインネット以外、--mainnet、端末以外からの実行は入力を読まない
+        let mut empty = Cursor::new("");
+        assert!(mainnet(11155111, false, true, &mut empty).is_ok());
+        assert!(mainnet(1, true, true, &mut empty).is_ok());
+        assert!(mainnet(1, false, false, &mut empty).is_ok());
+
+        assert!(mainnet(1, false, true, &mut Cursor::new("mainnet\n")).is_ok());
+        assert!(mainnet(1, false, true, &mut Cursor::new("  mainnet \r\n")).is_ok());
+        for input in ["y\n", "Mainnet\n", ""] {
+            assert!(
+                matches!(
+                    mainnet(1, false, true, &mut Cursor::new(input)),
+                    Err(Error::MainnetNotConfirmed)
+                ),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_value() {
+        let ether = U256::from(1_000_000_000_000_000_000u64);
+        let threshold = Some(ether / 2);
+        let mut empty = Cursor::new("");
+        // 閾値以下、閾値なし、--yes は確認しない
+        assert!(value(1, ether / 2, threshold, false, false, &mut empty).is_ok());
+        assert!(value(1, ether, None, false, false, &mut empty).is_ok());
+        assert!(value(1, ether, threshold, true, false, &mut empty).is_ok());
+
+        // 端末以外からは拒否する
+        let error = value(1, ether * 3 / 2, threshold, false, false, &mut empty).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Refusing to sign a value of 1.5 ETH above CONFIRM_ABOVE (0.5 ETH) without confirmation. Pass --yes to proceed."
+        );
+
+        // 端末からは金額をそのまま入力する
+        assert!(
+            value(
+                1,
+                ether * 3 / 2,
+                threshold,
+                false,
+                true,
+                &mut Cursor::new("1.5\n")
+            )
+            .is_ok()
+        );
+        for input in ["yes\n", "1.50\n", ""] {
+            assert!(
+                matches!(
+                    value(
+                        1,
+                        ether * 3 / 2,
+                        threshold,
+                        false,
+                        true,
+                        &mut Cursor::new(input)
+                    ),
+                    Err(Error::ValueNotConfirmed { .. })
+                ),
+                "{input:?}"
+            );
+        }
+    }
+}
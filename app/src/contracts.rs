@@ -0,0 +1,211 @@
+use crate::{Result, abi, error::Error, rpc::RpcClient, signer};
+use ethereum::{EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, H256};
+use serde::Deserialize;
+use std::path::Path;
+
+// 検証済みのコントラクトのリスト (TOML / YAML、拡張子で判別)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContractsFile {
+    #[serde(default)]
+    contracts: Vec<VerifiedContract>,
+}
+
+// アドレスと、そのアドレスにデプロイされているはずのコードの Keccak-256
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifiedContract {
+    pub address: H160,
+    pub code_hash: H256,
+    // エラーやログに出す名前 (任意)
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+// approve などで権限を渡してよいコントラクト (VERIFIED_CONTRACTS_PATH)
+// 見た目の似たアドレスの詐欺コントラクトに approve しないよう、署名前に RPC でコードを取得してハッシュを比べる
+#[derive(Debug)]
+pub struct VerifiedContracts {
+    contracts: Vec<VerifiedContract>,
+}
+
+// 第1引数のアドレスに権限を渡す関数 (approve / increaseAllowance は量、setApprovalForAll は bool が第2引数)
+const APPROVAL_FUNCTIONS: &[&str] = &[
+    "approve(address,uint256)",
+    "increaseAllowance(address,uint256)",
+    "setApprovalForAll(address,bool)",
+];
+
+// calldata が権限を渡す呼び出しであれば、渡す相手 (spender / operator) を返す
+// 第2引数が 0 (allowance を 0 にする、setApprovalForAll(.., false)) の取り消しは対象にしない
+pub fn approval_spender(message: &EIP1559TransactionMessage) -> Option<H160> {
+    if !matches!(message.action, TransactionAction::Call(_)) {
+        return None;
+    }
+    let input = &message.input;
+    if input.len() < 4 + 64 {
+        return None;
+    }
+    if !APPROVAL_FUNCTIONS
+        .iter()
+        .any(|signature| input[..4] == abi::selector(signature))
+    {
+        return None;
+    }
+    if input[36..68].iter().all(|&byte| byte == 0) {
+        return None;
+    }
+
+    Some(H160::from_slice(&input[16..36]))
+}
+
+impl VerifiedContract {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({:?})", self.address),
+            None => format!("{:?}", self.address),
+        }
+    }
+}
+
+impl VerifiedContracts {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file: ContractsFile = config::Config::builder()
+            .add_source(config::File::from(path.as_ref()))
+            .build()
+            .and_then(config::Config::try_deserialize)
+            .map_err(Error::in_file(&path))?;
+
+        Ok(Self {
+            contracts: file.contracts,
+        })
+    }
+
+    // spender が検証済みのコントラクトで、RPC で取得したコードのハッシュが一致するか確認する
+    pub fn verify(&self, client: &RpcClient, spender: &H160) -> Result<&VerifiedContract> {
+        let Some(contract) = self
+            .contracts
+            .iter()
+            .find(|contract| contract.address == *spender)
+        else {
+            return Err(Error::UnverifiedContract(format!(
+                "{spender:?} is not in VERIFIED_CONTRACTS_PATH"
+            )));
+        };
+
+        let code = client.code(spender)?;
+        if code.is_empty() {
+            return Err(Error::UnverifiedContract(format!(
+                "{} has no code",
+                contract.label()
+            )));
+        }
+        let code_hash = signer::keccak256(&code);
+        if code_hash != contract.code_hash {
+            return Err(Error::UnverifiedContract(format!(
+                "the code hash of {} is {code_hash:?}, not the pinned {:?}",
+                contract.label(),
+                contract.code_hash
+            )));
+        }
+
+        Ok(contract)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{erc20, rpc::tests::MockServer};
+    use ethereum::AccessList;
+    use ethereum_types::U256;
+    use serde_json::json;
+
+    const SPENDER: &str = "0x000000000022d473030f116ddee9f6b43ac78ba3";
+
+    fn call(input: Vec<u8>) -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 1,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::zero(),
+            gas_limit: U256::from(60000),
+            action: TransactionAction::Call(H160::repeat_byte(0x11)),
+            value: U256::zero(),
+            input,
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_approval_spender() {
+        let spender: H160 = SPENDER.parse().unwrap();
+        assert_eq!(
+            approval_spender(&call(erc20::approve_calldata(&spender, U256::one()))),
+            Some(spender)
+        );
+        let set_approval_for_all = abi::encode_call(
+            "setApprovalForAll(address,bool)",
+            &[abi::encode_address(&spender), abi::encode_u256(U256::one())],
+        );
+        assert_eq!(approval_spender(&call(set_approval_for_all)), Some(spender));
+
+        // 取り消しと、権限を渡さない呼び出し
+        assert_eq!(
+            approval_spender(&call(erc20::approve_calldata(&spender, U256::zero()))),
+            None
+        );
+        assert_eq!(
+            approval_spender(&call(erc20::transfer_calldata(&spender, U256::one()))),
+            None
+        );
+        assert_eq!(approval_spender(&call(vec![])), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contracts.toml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+                [[contracts]]
+                name = "Permit2"
+                address = "{SPENDER}"
+                code_hash = "{:?}"
+                "#,
+                signer::keccak256([0x60, 0x80])
+            ),
+        )
+        .unwrap();
+        let contracts = VerifiedContracts::from_path(&path).unwrap();
+        let spender: H160 = SPENDER.parse().unwrap();
+
+        let server = MockServer::start(vec![
+            MockServer::rpc_result(json!("0x6080")),
+            MockServer::rpc_result(json!("0x6081")),
+            MockServer::rpc_result(json!("0x")),
+        ]);
+        let client = RpcClient::new(&server.url);
+        let contract = contracts.verify(&client, &spender).unwrap();
+        assert_eq!(contract.name.as_deref(), Some("Permit2"));
+        // 同じアドレスでもコードが違うもの、コードがないもの
+        let error = contracts.verify(&client, &spender).unwrap_err();
+        assert!(error.to_string().contains("Permit2"), "{error}");
+        assert!(matches!(
+            contracts.verify(&client, &spender),
+            Err(Error::UnverifiedContract(_))
+        ));
+        // リストにないアドレスは RPC に問い合わせずに拒否する
+        assert!(matches!(
+            contracts.verify(&client, &H160::repeat_byte(0x22)),
+            Err(Error::UnverifiedContract(_))
+        ));
+        let requests = server.json_requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0]["method"], "eth_getCode");
+        assert_eq!(requests[0]["params"], json!([SPENDER, "latest"]));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{Result, abi, chains, i18n::t, price::Price, rpc::RpcClient, transaction, units};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::{H160, U256};
+
+// OP Stack の GasPriceOracle predeploy
+pub const GAS_PRICE_ORACLE: H160 = H160([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0f,
+]);
+
+// トランザクションにかかりうる手数料 (wei)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cost {
+    pub gas_limit: U256,
+    pub max_fee_per_gas: U256,
+    // gas_limit をすべて max_fee_per_gas で使い切った場合の実行手数料
+    pub execution_fee: U256,
+    // OP Stack のチェーンで L1 にデータを載せる手数料 (L2 の実行手数料とは別に引かれる)
+    pub l1_data_fee: Option<U256>,
+    // ネイティブ通貨の単位と小数点以下の桁数 (chains.toml で変えられる)
+    pub symbol: &'static str,
+    pub decimals: u8,
+}
+
+impl Cost {
+    pub fn total(&self) -> U256 {
+        self.execution_fee
+            .saturating_add(self.l1_data_fee.unwrap_or_default())
+    }
+
+    // ネイティブ通貨の単位の表示 ("0.00105 ETH")
+    pub fn format_native(&self, amount: U256) -> String {
+        format!(
+            "{} {}",
+            units::format_units(amount, self.decimals),
+            self.symbol
+        )
+    }
+}
+
+// 署名前のトランザクションの手数料の上限を見積もる
+// OP Stack のチェーンで client があれば GasPriceOracle で L1 のデータ手数料も求める
+pub fn estimate(message: &EIP1559TransactionMessage, client: Option<&RpcClient>) -> Result<Cost> {
+    let l1_data_fee = match client {
+        Some(client) if chains::is_op_stack(message.chain_id) => {
+            Some(l1_data_fee(client, message)?)
+        }
+        _ => None,
+    };
+
+    Ok(Cost {
+        gas_limit: message.gas_limit,
+        max_fee_per_gas: message.max_fee_per_gas,
+        execution_fee: message.gas_limit.saturating_mul(message.max_fee_per_gas),
+        l1_data_fee,
+        symbol: chains::symbol(message.chain_id),
+        decimals: chains::decimals(message.chain_id),
+    })
+}
+
+// GasPriceOracle.getL1Fee(bytes) に署名前のトランザクションを渡す
+// (オラクルが署名の分のバイト数を足して計算する)
+pub fn l1_data_fee(client: &RpcClient, message: &EIP1559TransactionMessage) -> Result<U256> {
+    let calldata = abi::encode_call_with_args(
+        "getL1Fee(bytes)",
+        &[abi::Arg::Dynamic(abi::encode_bytes(
+            &transaction::encode_unsigned(message),
+        ))],
+    );
+    abi::decode_u256(&client.call(&GAS_PRICE_ORACLE, &calldata)?)
+}
+
+// 確認用の表示 (price があれば手数料の上限を法定通貨でも示す)
+pub fn summary(cost: &Cost, price: Option<&Price>) -> String {
+    let mut lines = vec![
+        t!("cost.gas_limit", gas_limit = cost.gas_limit),
+        t!(
+            "cost.max_fee_per_gas",
+            gwei = units::format_units(cost.max_fee_per_gas, 9)
+        ),
+        t!(
+            "cost.max_execution_fee",
+            fee = cost.format_native(cost.execution_fee)
+        ),
+    ];
+    if let Some(l1_data_fee) = cost.l1_data_fee {
+        lines.push(t!(
+            "cost.l1_data_fee",
+            fee = cost.format_native(l1_data_fee)
+        ));
+    }
+    lines.push(t!(
+        "cost.max_total_fee",
+        fee = cost.format_native(cost.total())
+    ));
+    if let Some(price) = price {
+        lines.push(t!(
+            "cost.max_total_fee_fiat",
+            currency = price.currency.code().to_ascii_uppercase(),
+            fee = price.format(cost.total(), cost.decimals),
+            price = price.describe(cost.symbol)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bench::synthetic_message,
+        price::{self, Currency},
+        rpc::tests::MockServer,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_estimate() {
+        // OP Stack 以外は RPC に問い合わせない
+        let mut message = synthetic_message(0, 0);
+        message.gas_limit = U256::from(21000);
+        message.max_fee_per_gas = U256::from(50_000_000_000u64);
+        let cost = estimate(&message, None).unwrap();
+        assert_eq!(cost.execution_fee, U256::from(1_050_000_000_000_000u64));
+        assert_eq!(cost.l1_data_fee, None);
+        assert_eq!(
+            summary(&cost, None),
+            "Gas limit: 21000\nMax fee per gas: 50 gwei\nMax execution fee: 0.00105 ETH\nMax total fee: 0.00105 ETH"
+        );
+        let price = Price {
+            currency: Currency::Usd,
+            value: price::parse_price("3012.45").unwrap(),
+        };
+        assert!(summary(&cost, Some(&price)).ends_with(
+            "\nMax total fee: 0.00105 ETH\nMax total fee in USD: $3.16 at 3012.45 USD/ETH"
+        ));
+
+        message.chain_id = 8453;
+        let server = MockServer::start(vec![MockServer::rpc_result(json!(format!(
+            "0x{:064x}",
+            12_345_678_900u64
+        )))]);
+        let client = RpcClient::new(&server.url);
+        let cost = estimate(&message, Some(&client)).unwrap();
+        assert_eq!(cost.l1_data_fee, Some(U256::from(12_345_678_900u64)));
+        assert_eq!(cost.total(), U256::from(1_050_012_345_678_900u64));
+        assert!(summary(&cost, None).contains("L1 data fee: 0.0000000123456789 ETH\n"));
+
+        let requests = server.json_requests();
+        let call = &requests[0]["params"][0];
+        assert_eq!(call["to"], "0x420000000000000000000000000000000000000f");
+        let data = call["data"].as_str().unwrap();
+        assert!(data.starts_with(&format!(
+            "0x{}",
+            hex::encode(abi::selector("getL1Fee(bytes)"))
+        )));
+        assert!(data.contains(&hex::encode(transaction::encode_unsigned(&message))));
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+// 拡張子が .csv なら CSV として読み書きする
+pub fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+}
+
+// RFC 4180 の CSV を先頭から1行ずつフィールドに分ける
+// 区切りの文字はすべて ASCII のため、バイト列のまま分けてからフィールドごとに UTF-8 として読む
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Records<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, position: 0 }
+    }
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<Vec<String>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.input.len() {
+            return None;
+        }
+
+        let mut row = Vec::new();
+        let mut field = Vec::new();
+        let mut quoted = false;
+        while let Some(&byte) = self.input.get(self.position) {
+            self.position += 1;
+            match (quoted, byte) {
+                (true, b'"') if self.input.get(self.position) == Some(&b'"') => {
+                    field.push(b'"');
+                    self.position += 1;
+                }
+                (true, b'"') => quoted = false,
+                (true, byte) => field.push(byte),
+                (false, b'"') if field.is_empty() => quoted = true,
+                (false, b',') => row.push(std::mem::take(&mut field)),
+                (false, b'\r') => {}
+                (false, b'\n') => {
+                    row.push(field);
+                    return Some(into_strings(row));
+                }
+                (false, byte) => field.push(byte),
+            }
+        }
+        if quoted {
+            return Some(Err("unterminated quoted field".to_string()));
+        }
+        row.push(field);
+        Some(into_strings(row))
+    }
+}
+
+fn into_strings(row: Vec<Vec<u8>>) -> Result<Vec<String>, String> {
+    row.into_iter()
+        .map(|field| String::from_utf8(field).map_err(|_| "invalid UTF-8".to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let csv = "a,b,c\r\n\"x, \"\"y\"\"\",,\"multi\nline\"\n\nlast,row";
+        let rows: Vec<_> = Records::new(csv.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a", "b", "c"],
+                vec!["x, \"y\"", "", "multi\nline"],
+                vec![""],
+                vec!["last", "row"],
+            ]
+        );
+
+        assert_eq!(Records::new(b"").count(), 0);
+        assert_eq!(Records::new(b"a\n").count(), 1);
+
+        let mut records = Records::new(b"a,b\n\"open");
+        assert!(records.next().unwrap().is_ok());
+        assert_eq!(
+            records.next().unwrap().unwrap_err(),
+            "unterminated quoted field"
+        );
+        assert!(records.next().is_none());
+
+        assert!(Records::new(b"\xff\n").next().unwrap().is_err());
+    }
+}
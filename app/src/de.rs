@@ -1,18 +1,176 @@
-use ethereum_types::U256;
-use serde::{Deserialize, Deserializer};
+use ethereum_types::{H160, U256};
+use serde::{
+    Deserialize, Deserializer,
+    de::{self, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+use std::{cell::Cell, fmt};
+
+thread_local! {
+    static STRICT_HEX: Cell<bool> = const { Cell::new(false) };
+    static DECIMAL_STRINGS: Cell<bool> = const { Cell::new(true) };
+}
+
+// f の中でパースする16進数に 0x を必須にする (--strict-hex)
+// - U256 の文字列は 0x 付きの16進数のみ ("1234" は10進数か16進数か紛らわしいため拒否する、10進数は数値で書く)
+// - バイト列とアドレスは 0x 付きのみ
+// パースするスレッドで呼ぶ (環境変数の設定などには影響しない)
+pub fn with_strict_hex<T>(strict: bool, f: impl FnOnce() -> T) -> T {
+    let previous = STRICT_HEX.replace(strict);
+    let result = f();
+    STRICT_HEX.set(previous);
+    result
+}
+
+fn strict_hex() -> bool {
+    STRICT_HEX.get()
+}
+
+// f の中でパースする U256 の数字だけの文字列 ("5208") を10進数として読むか
+// params.json は以前16進数として読んでいたため、--decimal-strings がなければ10進数と16進数で値が変わるものを拒否する
+// (ParseOptions::apply で false にする、環境変数・JSON-RPC などは常に10進数)
+pub fn with_decimal_strings<T>(decimal: bool, f: impl FnOnce() -> T) -> T {
+    let previous = DECIMAL_STRINGS.replace(decimal);
+    let result = f();
+    DECIMAL_STRINGS.set(previous);
+    result
+}
+
+fn decimal_strings() -> bool {
+    DECIMAL_STRINGS.get()
+}
+
+fn require_prefix(value: &str) -> Result<(), String> {
+    match strict_hex() && !value.starts_with("0x") {
+        true => Err(format!(
+            "'{value}' has no 0x prefix, which --strict-hex requires"
+        )),
+        false => Ok(()),
+    }
+}
 
 pub fn deserialize_u256<'de, D>(deserializer: D) -> Result<U256, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let value: serde_json::Value = serde::Deserialize::deserialize(deserializer)?;
+    deserializer.deserialize_any(U256Visitor)
+}
+
+// 数値と文字列を、serde_json::Value を経由せずに U256 にする
+// - 数値: u64 を超える整数も桁を落とさない (serde_json は arbitrary_precision で元の文字列のまま渡す)
+// - 文字列: parse_u256 (10進数または16進数)
+struct U256Visitor;
+
+impl<'de> Visitor<'de> for U256Visitor {
+    type Value = U256;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a non-negative integer, a decimal string or a hex string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+        Ok(U256::from(value))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<U256, E> {
+        Ok(U256::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<U256, E> {
+        u64::try_from(value)
+            .map(U256::from)
+            .map_err(|_| E::custom(format!("negative number {value}")))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<U256, E> {
+        u128::try_from(value)
+            .map(U256::from)
+            .map_err(|_| E::custom(format!("negative number {value}")))
+    }
+
+    // 小数は桁が落ちている場合があるため受け付けない
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<U256, E> {
+        Err(E::custom(format!(
+            "{value} is not an integer; pass fractional or exponent values as an integer string"
+        )))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<U256, E> {
+        parse_u256(value).map_err(E::custom)
+    }
+
+    // serde_json の数値 (arbitrary_precision)
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<U256, A::Error> {
+        let number = serde_json::Number::deserialize(MapAccessDeserializer::new(map))?.to_string();
+        match number.strip_prefix('-') {
+            Some(_) => Err(de::Error::custom(format!("negative number {number}"))),
+            None if number.bytes().all(|byte| byte.is_ascii_digit()) => U256::from_dec_str(&number)
+                .map_err(|_| de::Error::custom(format!("{number} does not fit in 256 bits"))),
+            None => Err(de::Error::custom(format!(
+                "{number} is not an integer; pass fractional or exponent values as an integer string"
+            ))),
+        }
+    }
+}
 
-    match value {
-        serde_json::Value::Number(n) => Ok(U256::from(n.as_u64().unwrap_or(0))),
-        serde_json::Value::String(s) => {
-            U256::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
+// U256 の文字列
+// - 0x で始まれば16進数
+// - 数字だけなら10進数 ("5208" は 5208)
+//   with_decimal_strings(false) の中では10進数と16進数で値が同じもの ("7") だけを受け付け、"5208" はエラー
+// - それ以外は 0x を省略した16進数 ("ff")
+// --strict-hex では 0x で始まるもののみ
+pub fn parse_u256(value: &str) -> Result<U256, String> {
+    if strict_hex() && !value.starts_with("0x") {
+        return Err(format!(
+            "'{value}' is ambiguous; --strict-hex requires a 0x-prefixed hex string or a JSON number"
+        ));
+    }
+    if let Some(hex_digits) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        return parse_hex_u256(value, hex_digits);
+    }
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        let decimal = U256::from_dec_str(value)
+            .map_err(|_| format!("'{}' does not fit in 256 bits", abbreviate(value)));
+        if decimal_strings() {
+            return decimal;
         }
-        _ => Err(serde::de::Error::custom("Expected number or hex string")),
+        return match (decimal, parse_hex_u256(value, value)) {
+            (Ok(decimal), Ok(hex)) if decimal == hex => Ok(decimal),
+            (_, hex) => Err(format!(
+                "'{value}' is ambiguous ({}decimal {}); write \"0x{value}\" for hex or the JSON number {value} for decimal, or pass --decimal-strings to read digit-only strings as decimal",
+                hex.map(|hex| format!("hex {hex} or ")).unwrap_or_default(),
+                abbreviate(value)
+            )),
+        };
+    }
+    parse_hex_u256(value, value)
+}
+
+fn parse_hex_u256(value: &str, hex_digits: &str) -> Result<U256, String> {
+    if hex_digits.len() > 64 {
+        return Err(format!("'{}' does not fit in 256 bits", abbreviate(value)));
+    }
+    U256::from_str_radix(hex_digits, 16).map_err(|_| {
+        format!(
+            "'{}' is not a number, expected a decimal string or a hex string",
+            abbreviate(value)
+        )
+    })
+}
+
+// エラーメッセージに入れる値 (長い calldata などは前後だけにする)
+fn abbreviate(value: &str) -> String {
+    const HEAD: usize = 10;
+    const TAIL: usize = 4;
+    match value.char_indices().nth(HEAD + TAIL + 8) {
+        Some(_) => {
+            let head: String = value.chars().take(HEAD).collect();
+            let tail: String = value.chars().skip(value.chars().count() - TAIL).collect();
+            format!("{head}…{tail}")
+        }
+        None => value.to_string(),
     }
 }
 
@@ -21,19 +179,79 @@ where
     D: Deserializer<'de>,
 {
     let hex_string = String::deserialize(deserializer)?;
+    require_prefix(&hex_string).map_err(de::Error::custom)?;
 
     let trimmed_hex_string = hex_string.strip_prefix("0x").unwrap_or(&hex_string);
     if trimmed_hex_string.is_empty() {
         Ok(vec![])
     } else {
-        hex::decode(trimmed_hex_string).map_err(serde::de::Error::custom)
+        hex::decode(trimmed_hex_string).map_err(|error| {
+            de::Error::custom(format!(
+                "'{}' is not hex bytes ({}), expected a 0x-prefixed string of hex digit pairs",
+                abbreviate(&hex_string),
+                error.to_string().to_lowercase()
+            ))
+        })
+    }
+}
+
+// 0x は省略可能な20バイトのアドレス (--strict-hex では必須)
+pub fn deserialize_address<'de, D>(deserializer: D) -> Result<H160, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let address = String::deserialize(deserializer)?;
+    require_prefix(&address).map_err(de::Error::custom)?;
+
+    let hex_digits = address.strip_prefix("0x").unwrap_or(&address);
+    if !hex_digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(de::Error::custom(format!(
+            "'{}' is not hex, expected a 0x-prefixed 20-byte address",
+            abbreviate(&address)
+        )));
+    }
+    match hex_digits.len() {
+        40 => Ok(H160::from_slice(
+            &hex::decode(hex_digits).map_err(de::Error::custom)?,
+        )),
+        length if length % 2 == 0 => Err(de::Error::custom(format!(
+            "'{}' is {} byte{}, expected 20",
+            abbreviate(&address),
+            length / 2,
+            if length == 2 { "" } else { "s" }
+        ))),
+        length => Err(de::Error::custom(format!(
+            "'{}' has {length} hex digits, expected 40",
+            abbreviate(&address)
+        ))),
     }
 }
 
+// null や欠落を許容する deserialize_u256
+pub fn deserialize_optional_u256<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_u256")] U256);
+
+    Option::<Wrapper>::deserialize(deserializer).map(|value| value.map(|Wrapper(value)| value))
+}
+
+// null や欠落を許容する deserialize_hex_bytes
+pub fn deserialize_optional_hex_bytes<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_hex_bytes")] Vec<u8>);
+
+    Option::<Wrapper>::deserialize(deserializer).map(|value| value.map(|Wrapper(value)| value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json;
 
     // Test helper: JSON値からデシリアライズするヘルパー関数
     fn test_deserialize_u256_from_json(json_value: &str) -> Result<U256, serde_json::Error> {
@@ -108,10 +326,64 @@ mod tests {
             test_deserialize_u256_from_json(r#""ff""#).unwrap(),
             U256::from(255)
         );
+        // params.json の数字だけの文字列は、以前の16進数と10進数で値が変わるならエラー
+        with_decimal_strings(false, || {
+            let error = test_deserialize_u256_from_json(r#""5208""#).unwrap_err();
+            assert!(
+                error.to_string().contains("hex 21000 or decimal 5208"),
+                "{error}"
+            );
+            assert_eq!(
+                test_deserialize_u256_from_json(r#""7""#).unwrap(),
+                U256::from(7)
+            );
+            assert_eq!(
+                test_deserialize_u256_from_json(r#""ff""#).unwrap(),
+                U256::from(255)
+            );
+        });
+        assert_eq!(
+            test_deserialize_u256_from_json(r#""0x5208""#).unwrap(),
+            U256::from(21000)
+        );
+        assert_eq!(
+            test_deserialize_u256_from_json(r#""0X5208""#).unwrap(),
+            U256::from(21000)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_u256_decimal_string() {
+        // --decimal-strings のない params.json では紛らわしいため受け付けない
+        with_decimal_strings(false, || {
+            assert!(test_deserialize_u256_from_json(r#""1000000000000000000""#).is_err());
+            assert!(test_deserialize_u256_from_json(&format!(r#""{}""#, U256::MAX)).is_err());
+        });
+
+        assert_eq!(
+            test_deserialize_u256_from_json(r#""1000000000000000000""#).unwrap(),
+            U256::from(1_000_000_000_000_000_000u64)
+        );
         assert_eq!(
             test_deserialize_u256_from_json(r#""5208""#).unwrap(),
+            U256::from(5208)
+        );
+        assert_eq!(
+            test_deserialize_u256_from_json(&format!(r#""{}""#, U256::MAX)).unwrap(),
+            U256::MAX
+        );
+        assert!(test_deserialize_u256_from_json(&format!(r#""{}0""#, U256::MAX)).is_err());
+        assert!(test_deserialize_u256_from_json(r#""-1""#).is_err());
+        assert!(test_deserialize_u256_from_json(r#""1.5""#).is_err());
+        // 0x 付きと "ff" は16進数のまま
+        assert_eq!(
+            test_deserialize_u256_from_json(r#""0x5208""#).unwrap(),
             U256::from(21000)
         );
+        assert_eq!(
+            test_deserialize_u256_from_json(r#""ff""#).unwrap(),
+            U256::from(255)
+        );
     }
 
     #[test]
@@ -137,6 +409,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_u256_number_out_of_range() {
+        // u64 を超える整数も桁を落とさない
+        assert_eq!(
+            test_deserialize_u256_from_json("18446744073709551616").unwrap(),
+            U256::from(u64::MAX) + 1
+        );
+        assert_eq!(
+            test_deserialize_u256_from_json(&U256::MAX.to_string()).unwrap(),
+            U256::MAX
+        );
+        assert_eq!(
+            test_deserialize_u256_from_json("18446744073709551615").unwrap(),
+            U256::from(u64::MAX)
+        );
+        // 256 ビットを超える整数、小数、負の数はエラーにする
+        assert!(test_deserialize_u256_from_json(&format!("{}0", U256::MAX)).is_err());
+        assert!(test_deserialize_u256_from_json("1e18").is_err());
+        assert!(test_deserialize_u256_from_json("1.5").is_err());
+        assert!(test_deserialize_u256_from_json("-1").is_err());
+        assert!(test_deserialize_u256_from_json("-18446744073709551616").is_err());
+
+        // serde_json::Value を経由しても同じ
+        let value: serde_json::Value = serde_json::from_str("100000000000000000000").unwrap();
+        assert_eq!(
+            deserialize_u256(value).unwrap(),
+            U256::from(100_000_000_000_000_000_000u128)
+        );
+
+        // u128 を渡すデシリアライザー (config など)
+        use serde::de::{IntoDeserializer, value::Error};
+        assert_eq!(
+            deserialize_u256(IntoDeserializer::<Error>::into_deserializer(u128::MAX)).unwrap(),
+            U256::from(u128::MAX)
+        );
+        assert!(deserialize_u256(IntoDeserializer::<Error>::into_deserializer(-1i128)).is_err());
+    }
+
     #[test]
     fn test_deserialize_u256_invalid_hex() {
         assert!(test_deserialize_u256_from_json(r#""0xgg""#).is_err());
@@ -286,4 +596,72 @@ mod tests {
         assert_eq!(result.len(), 32);
         assert!(result.iter().all(|&b| b == 0xa1));
     }
+
+    #[test]
+    fn test_strict_hex() {
+        #[derive(Debug, Deserialize)]
+        struct TestStruct {
+            #[serde(deserialize_with = "deserialize_u256")]
+            value: U256,
+            #[serde(deserialize_with = "deserialize_hex_bytes")]
+            data: Vec<u8>,
+            #[serde(deserialize_with = "deserialize_address")]
+            to: H160,
+        }
+        let address = "742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df";
+        let parse = |strict: bool, value: &str, data: &str, to: &str| {
+            let json = format!(r#"{{"value": {value}, "data": "{data}", "to": "{to}"}}"#);
+            with_strict_hex(strict, || serde_json::from_str::<TestStruct>(&json))
+        };
+
+        let lenient = parse(false, r#""1234""#, "abcd", address).unwrap();
+        assert_eq!(lenient.value, U256::from(1234));
+        assert_eq!(lenient.data, vec![0xab, 0xcd]);
+        assert_eq!(lenient.to, format!("0x{address}").parse().unwrap());
+
+        let to = format!("0x{address}");
+        let strict = parse(true, r#""0x1234""#, "0xabcd", &to).unwrap();
+        assert_eq!(strict.value, U256::from(0x1234));
+        // 10進数は数値で書く
+        assert_eq!(
+            parse(true, "1234", "0x", &to).unwrap().value,
+            U256::from(1234)
+        );
+
+        let error = parse(true, r#""1234""#, "0xabcd", &to).unwrap_err();
+        assert!(error.to_string().contains("'1234' is ambiguous"), "{error}");
+        assert!(parse(true, r#""ff""#, "0xabcd", &to).is_err());
+        assert!(parse(true, r#""0X12""#, "0xabcd", &to).is_err());
+        assert!(parse(true, "1", "abcd", &to).is_err());
+        assert!(parse(true, "1", "", &to).is_err());
+        assert!(parse(true, "1", "0x", address).is_err());
+
+        // スレッドの設定は元に戻る
+        assert!(parse(false, r#""1234""#, "abcd", address).is_ok());
+        assert!(!strict_hex());
+    }
+
+    // ===== Option 版のテスト =====
+
+    #[test]
+    fn test_deserialize_optional_values() {
+        #[derive(Deserialize)]
+        struct TestStruct {
+            #[serde(default, deserialize_with = "deserialize_optional_u256")]
+            value: Option<U256>,
+            #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+            data: Option<Vec<u8>>,
+        }
+
+        let result: TestStruct =
+            serde_json::from_str(r#"{"value": "0x10", "data": "0xab"}"#).unwrap();
+        assert_eq!(result.value, Some(U256::from(16)));
+        assert_eq!(result.data, Some(vec![0xab]));
+
+        let result: TestStruct = serde_json::from_str(r#"{"value": null}"#).unwrap();
+        assert_eq!(result.value, None);
+        assert_eq!(result.data, None);
+
+        assert!(serde_json::from_str::<TestStruct>(r#"{"value": "0xzz"}"#).is_err());
+    }
 }
@@ -0,0 +1,200 @@
+use crate::{Result, abi, address, contracts, error::Error};
+use ethereum::{EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::H160;
+use std::{collections::HashMap, path::Path};
+
+// 受取人 (to) が第1引数の関数
+const TRANSFER_FUNCTIONS: &[&str] = &["transfer(address,uint256)"];
+
+// 受取人が第2引数の関数 (第1引数は送信元)
+const TRANSFER_FROM_FUNCTIONS: &[&str] = &[
+    "transferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+];
+
+// 詐欺や流出した鍵のアドレスなど、送金や approve をしないアドレスのリスト (DENYLIST_PATH)
+// 公開されているデータベースから定期的に同期したファイルを想定し、署名のたびに読み込む
+// 形式は1行に1つのアドレス (空白の後はラベル、# 以降はコメント) か、アドレスの JSON 配列
+#[derive(Debug, Default)]
+pub struct Denylist {
+    // アドレス → ラベル (取得元や理由、なければ空)
+    entries: HashMap<H160, String>,
+}
+
+fn parse_address(line: usize, address: &str) -> Result<H160> {
+    address
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("line {line}: invalid address '{address}'")))
+}
+
+// calldata の index 番目の引数のアドレス
+fn address_arg(input: &[u8], index: usize) -> Option<H160> {
+    let start = 4 + 32 * index;
+    input.get(start + 12..start + 32).map(H160::from_slice)
+}
+
+fn is_call_to(input: &[u8], signatures: &[&str]) -> bool {
+    input.len() >= 4
+        && signatures
+            .iter()
+            .any(|signature| input[..4] == abi::selector(signature))
+}
+
+// トランザクションで送金先や権限を渡す相手になるアドレス
+// to と、トークンの transfer / transferFrom の受取人、approve などの相手
+pub fn counterparties(message: &EIP1559TransactionMessage) -> Vec<H160> {
+    let TransactionAction::Call(to) = message.action else {
+        return Vec::new();
+    };
+    let input = &message.input;
+    let recipient = if is_call_to(input, TRANSFER_FUNCTIONS) {
+        address_arg(input, 0)
+    } else if is_call_to(input, TRANSFER_FROM_FUNCTIONS) {
+        address_arg(input, 1)
+    } else {
+        None
+    };
+
+    std::iter::once(to)
+        .chain(recipient)
+        .chain(contracts::approval_spender(message))
+        .collect()
+}
+
+impl Denylist {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        Self::parse(&content).map_err(Error::in_file(&path))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries = HashMap::new();
+        if content.trim_start().starts_with('[') {
+            let addresses: Vec<String> = serde_json::from_str(content)?;
+            for (index, address) in addresses.iter().enumerate() {
+                entries.insert(parse_address(index + 1, address.trim())?, String::new());
+            }
+            return Ok(Self { entries });
+        }
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (address, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            entries.insert(parse_address(index + 1, address)?, label.trim().to_string());
+        }
+        Ok(Self { entries })
+    }
+
+    // 送金先や権限を渡す相手がリストにあれば拒否する
+    pub fn check(&self, message: &EIP1559TransactionMessage) -> Result<()> {
+        for address in counterparties(message) {
+            if let Some(label) = self.entries.get(&address) {
+                return Err(Error::FlaggedAddress {
+                    address: address::to_checksum(&address),
+                    label: if label.is_empty() {
+                        "no label".to_string()
+                    } else {
+                        label.clone()
+                    },
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{erc20, erc721};
+    use ethereum::AccessList;
+    use ethereum_types::U256;
+
+    const FLAGGED: &str = "0x098b716b8aaf21512996dc57eb0615e2383e2f96";
+    const TOKEN: &str = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238";
+
+    fn call(to: &str, input: Vec<u8>) -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 1,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::zero(),
+            gas_limit: U256::from(60000),
+            action: TransactionAction::Call(to.parse().unwrap()),
+            value: U256::zero(),
+            input,
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let denylist = Denylist::parse(&format!(
+            "# synced from example feed\n\n{FLAGGED} Ronin bridge exploiter\n0x{} # no label\n",
+            "11".repeat(20)
+        ))
+        .unwrap();
+        assert_eq!(denylist.entries.len(), 2);
+        assert_eq!(
+            denylist.entries[&FLAGGED.parse().unwrap()],
+            "Ronin bridge exploiter"
+        );
+
+        let denylist = Denylist::parse(&format!(r#"["{FLAGGED}"]"#)).unwrap();
+        assert_eq!(denylist.entries.len(), 1);
+
+        let error = Denylist::parse("0x1234\n").unwrap_err();
+        assert!(error.to_string().contains("line 1"), "{error}");
+    }
+
+    #[test]
+    fn test_check() {
+        let denylist = Denylist::parse(&format!("{FLAGGED} scam\n")).unwrap();
+        let flagged: H160 = FLAGGED.parse().unwrap();
+        let other = H160::repeat_byte(0x22);
+
+        // 送金先、トークンの受取人、approve の相手
+        assert!(denylist.check(&call(FLAGGED, vec![])).is_err());
+        assert!(
+            denylist
+                .check(&call(
+                    TOKEN,
+                    erc20::transfer_calldata(&flagged, U256::one())
+                ))
+                .is_err()
+        );
+        assert!(
+            denylist
+                .check(&call(
+                    TOKEN,
+                    erc721::safe_transfer_from_calldata(&other, &flagged, U256::one())
+                ))
+                .is_err()
+        );
+        let error = denylist
+            .check(&call(TOKEN, erc20::approve_calldata(&flagged, U256::one())))
+            .unwrap_err();
+        assert!(error.to_string().contains("scam"), "{error}");
+
+        assert!(
+            denylist
+                .check(&call(TOKEN, erc20::transfer_calldata(&other, U256::one())))
+                .is_ok()
+        );
+        // 送信元としての引数は対象にしない
+        assert!(
+            denylist
+                .check(&call(
+                    TOKEN,
+                    erc721::safe_transfer_from_calldata(&flagged, &other, U256::one())
+                ))
+                .is_ok()
+        );
+    }
+}
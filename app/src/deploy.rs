@@ -0,0 +1,240 @@
+use crate::{Result, error::Error, signer::keccak256};
+use ethereum_types::{H160, H256, U256};
+use std::path::Path;
+
+// Arachnid の deterministic-deployment-proxy (多くのチェーンで同じアドレス)
+// calldata は salt (32バイト) + init code
+// https://github.com/Arachnid/deterministic-deployment-proxy
+pub const CREATE2_FACTORY_ADDRESS: H160 = H160([
+    0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf, 0x26,
+    0xc0, 0xb4, 0x95, 0x6c,
+]);
+
+// 呼び出すとコントラクトをデプロイする、よく使われる CREATE2 のファクトリー
+// (ポリシーの allow_contract_creation = false では呼び出しもコントラクトの作成とみなす)
+// - deterministic-deployment-proxy (create2 deploy の既定)
+// - Safe の singleton factory (calldata は deterministic-deployment-proxy と同じ形式)
+// - CreateX
+// - ImmutableCreate2Factory
+pub const KNOWN_CREATE2_FACTORIES: [H160; 4] = [
+    CREATE2_FACTORY_ADDRESS,
+    H160([
+        0x91, 0x4d, 0x7f, 0xec, 0x6a, 0xac, 0x8c, 0xd5, 0x42, 0xe7, 0x2b, 0xca, 0x78, 0xb3, 0x06,
+        0x50, 0xd4, 0x56, 0x43, 0xd7,
+    ]),
+    H160([
+        0xba, 0x5e, 0xd0, 0x99, 0x63, 0x3d, 0x3b, 0x31, 0x3e, 0x4d, 0x5f, 0x7b, 0xdc, 0x13, 0x05,
+        0xd3, 0xc2, 0x8b, 0xa5, 0xed,
+    ]),
+    H160([
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xe8, 0xb4, 0x7b, 0x3e, 0x21, 0x30, 0x21, 0x3b, 0x80,
+        0x22, 0x12, 0x43, 0x94, 0x97,
+    ]),
+];
+
+// CREATE でデプロイされるアドレス
+// keccak256(rlp([sender, nonce])) の下位20バイト
+pub fn create_address(sender: &H160, nonce: U256) -> H160 {
+    let mut stream = rlp::RlpStream::new_list(2);
+    stream.append(sender);
+    stream.append(&nonce);
+
+    H160::from_slice(&keccak256(stream.out())[12..])
+}
+
+// CREATE2 でデプロイされるアドレス
+// keccak256(0xff + factory + salt + keccak256(init_code)) の下位20バイト
+pub fn create2_address(factory: &H160, salt: &H256, init_code_hash: &H256) -> H160 {
+    let mut buf = Vec::with_capacity(1 + 20 + 32 + 32);
+    buf.push(0xff);
+    buf.extend_from_slice(factory.as_bytes());
+    buf.extend_from_slice(salt.as_bytes());
+    buf.extend_from_slice(init_code_hash.as_bytes());
+
+    H160::from_slice(&keccak256(buf)[12..])
+}
+
+// deterministic-deployment-proxy に渡す calldata
+pub fn create2_factory_calldata(salt: &H256, init_code: &[u8]) -> Vec<u8> {
+    let mut calldata = Vec::with_capacity(32 + init_code.len());
+    calldata.extend_from_slice(salt.as_bytes());
+    calldata.extend_from_slice(init_code);
+    calldata
+}
+
+// init code をファイルから読み込む
+// 16進数のテキスト、もしくは Foundry / Hardhat のアーティファクト (JSON の bytecode) に対応
+pub fn read_init_code_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    let content = std::fs::read_to_string(path)?;
+    let content = content.trim();
+
+    let hex_string = if content.starts_with('{') {
+        let artifact: serde_json::Value = serde_json::from_str(content)?;
+        // Foundry: { "bytecode": { "object": "0x..." } }, Hardhat: { "bytecode": "0x..." }
+        let bytecode = &artifact["bytecode"];
+        bytecode["object"]
+            .as_str()
+            .or_else(|| bytecode.as_str())
+            .ok_or_else(|| {
+                Error::InvalidArgument("artifact JSON has no 'bytecode' field".to_string())
+            })?
+            .to_string()
+    } else {
+        content.to_string()
+    };
+
+    let bytes = hex::decode(hex_string.strip_prefix("0x").unwrap_or(&hex_string))?;
+    if bytes.is_empty() {
+        return Err(Error::InvalidArgument("init code is empty".to_string()));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h160(s: &str) -> H160 {
+        s.parse().unwrap()
+    }
+
+    fn h256(s: &str) -> H256 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_create2_factory_address() {
+        assert_eq!(
+            CREATE2_FACTORY_ADDRESS,
+            h160("0x4e59b44847b379578588920cA78FbF26c0B4956C")
+        );
+    }
+
+    #[test]
+    fn test_known_create2_factories() {
+        assert_eq!(
+            KNOWN_CREATE2_FACTORIES.map(|factory| crate::address::to_checksum(&factory)),
+            [
+                "0x4e59b44847b379578588920cA78FbF26c0B4956C",
+                "0x914d7Fec6aaC8cd542e72Bca78B30650d45643d7",
+                "0xba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed",
+                "0x0000000000FFe8B47B3e2130213B802212439497",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create2_address_eip1014_examples() {
+        // EIP-1014 の Example 0
+        assert_eq!(
+            create2_address(
+                &H160::zero(),
+                &H256::zero(),
+                &keccak256(hex::decode("00").unwrap())
+            ),
+            h160("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38")
+        );
+
+        // Example 1
+        assert_eq!(
+            create2_address(
+                &h160("0xdeadbeef00000000000000000000000000000000"),
+                &H256::zero(),
+                &keccak256(hex::decode("00").unwrap())
+            ),
+            h160("0xB928f69Bb1D91Cd65274e3c79d8986362984fDA3")
+        );
+
+        // Example 5
+        assert_eq!(
+            create2_address(
+                &h160("0x00000000000000000000000000000000deadbeef"),
+                &h256("0x00000000000000000000000000000000000000000000000000000000cafebabe"),
+                &keccak256(hex::decode("deadbeef").unwrap())
+            ),
+            h160("0x60f3f640a8508fC6a86d45DF051962668E1e8AC7")
+        );
+
+        // 空の init code の例
+        assert_eq!(
+            create2_address(
+                &h160("0x0000000000000000000000000000000000000000"),
+                &H256::zero(),
+                &keccak256([])
+            ),
+            h160("0xE33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0")
+        );
+    }
+
+    #[test]
+    fn test_create_address() {
+        let sender = h160("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+
+        assert_eq!(
+            create_address(&sender, U256::zero()),
+            h160("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+        );
+        assert_eq!(
+            create_address(&sender, U256::one()),
+            h160("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+        );
+        assert_eq!(
+            create_address(&sender, U256::from(2)),
+            h160("0xf778b86fa74e846c4f0a1fbd1335fe81c00a0c91")
+        );
+    }
+
+    fn write_temp(content: &str) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_read_init_code_file_hex() {
+        let temp_file = write_temp("0x6080604052\n");
+        assert_eq!(
+            read_init_code_file(temp_file.path()).unwrap(),
+            vec![0x60, 0x80, 0x60, 0x40, 0x52]
+        );
+    }
+
+    #[test]
+    fn test_read_init_code_file_artifacts() {
+        let foundry = write_temp(r#"{ "abi": [], "bytecode": { "object": "0x6080" } }"#);
+        assert_eq!(
+            read_init_code_file(foundry.path()).unwrap(),
+            vec![0x60, 0x80]
+        );
+
+        let hardhat = write_temp(r#"{ "abi": [], "bytecode": "0x6080" }"#);
+        assert_eq!(
+            read_init_code_file(hardhat.path()).unwrap(),
+            vec![0x60, 0x80]
+        );
+
+        let missing = write_temp(r#"{ "abi": [] }"#);
+        assert!(matches!(
+            read_init_code_file(missing.path()),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_init_code_file_empty() {
+        let temp_file = write_temp("0x");
+        assert!(read_init_code_file(temp_file.path()).is_err());
+    }
+
+    #[test]
+    fn test_create2_factory_calldata() {
+        let salt = H256::repeat_byte(0x11);
+        let calldata = create2_factory_calldata(&salt, &[0x60, 0x00]);
+
+        assert_eq!(calldata.len(), 34);
+        assert_eq!(calldata[..32], [0x11; 32]);
+        assert_eq!(calldata[32..], [0x60, 0x00]);
+    }
+}
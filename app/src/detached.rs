@@ -0,0 +1,284 @@
+use crate::{
+    Result, abi,
+    de::deserialize_hex_bytes,
+    error::Error,
+    signer::{self, Signature},
+    transaction::{self, TxType},
+};
+use ethereum::{AccessList, EIP1559TransactionMessage};
+use ethereum_types::{H160, H256, U256};
+use rlp::Rlp;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+// EIP-2718 のトランザクションタイプ
+const EIP1559_TYPE: u8 = 0x02;
+
+// prepare の出力のうち assemble が使うもの
+// transaction は署名する人が内容を確かめるためのもので、assemble は unsigned から組み立てる
+#[derive(Debug, Deserialize)]
+pub struct Prepared {
+    #[serde(deserialize_with = "deserialize_hex_bytes")]
+    pub unsigned: Vec<u8>,
+    pub sighash: H256,
+}
+
+// 署名前のトランザクション (Type 2 は 0x02 + RLP、Legacy は EIP-155 の RLP)
+// Keccak-256 ハッシュがそのまま署名用ハッシュになる
+pub fn encode_unsigned(tx_type: &TxType, message: &EIP1559TransactionMessage) -> Result<Vec<u8>> {
+    match tx_type {
+        TxType::Eip1559 => Ok(transaction::encode_unsigned(message)),
+        TxType::Legacy => Ok(transaction::encode_legacy_unsigned(
+            &transaction::legacy_message(message),
+        )),
+        TxType::Zksync(_) | TxType::Celo(_) => Err(Error::InvalidArgument(
+            "prepare supports Type 2 and legacy transactions only".to_string(),
+        )),
+    }
+}
+
+// 外部で署名するための署名前のトランザクションと署名用ハッシュ
+pub fn prepare(tx_type: &TxType, message: &EIP1559TransactionMessage) -> Result<Value> {
+    let unsigned = encode_unsigned(tx_type, message)?;
+    Ok(json!({
+        "unsigned": format!("0x{}", hex::encode(&unsigned)),
+        "sighash": signer::keccak256(&unsigned),
+        "transaction": transaction::message_json(tx_type, message),
+    }))
+}
+
+// 署名前のトランザクションをデコードする
+pub fn decode_unsigned(unsigned: &[u8]) -> Result<(TxType, EIP1559TransactionMessage)> {
+    let invalid = |error: rlp::DecoderError| {
+        Error::InvalidArgument(format!("invalid unsigned transaction: {error}"))
+    };
+    let (tx_type, rlp) = match unsigned.split_first() {
+        Some((&EIP1559_TYPE, rlp_encoded)) => (TxType::Eip1559, Rlp::new(rlp_encoded)),
+        // RLP のリストで始まるものは Legacy
+        Some((&first, _)) if first >= 0xc0 => (TxType::Legacy, Rlp::new(unsigned)),
+        _ => {
+            return Err(Error::InvalidArgument(
+                "unsigned transaction must be Type 2 (0x02) or legacy (an RLP list)".to_string(),
+            ));
+        }
+    };
+    if rlp.item_count().map_err(invalid)? != 9 {
+        return Err(invalid(rlp::DecoderError::RlpIncorrectListLen));
+    }
+
+    let message = match tx_type {
+        // [chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gas, to, value, data, accessList]
+        TxType::Eip1559 => EIP1559TransactionMessage {
+            chain_id: rlp.val_at(0).map_err(invalid)?,
+            nonce: rlp.val_at(1).map_err(invalid)?,
+            max_priority_fee_per_gas: rlp.val_at(2).map_err(invalid)?,
+            max_fee_per_gas: rlp.val_at(3).map_err(invalid)?,
+            gas_limit: rlp.val_at(4).map_err(invalid)?,
+            action: rlp.val_at(5).map_err(invalid)?,
+            value: rlp.val_at(6).map_err(invalid)?,
+            input: rlp.val_at(7).map_err(invalid)?,
+            access_list: rlp.list_at(8).map_err(invalid)?,
+        },
+        // EIP-155: [nonce, gasPrice, gas, to, value, data, chainId, 0, 0]
+        _ => {
+            let gas_price: U256 = rlp.val_at(1).map_err(invalid)?;
+            EIP1559TransactionMessage {
+                chain_id: rlp.val_at(6).map_err(invalid)?,
+                nonce: rlp.val_at(0).map_err(invalid)?,
+                max_priority_fee_per_gas: gas_price,
+                max_fee_per_gas: gas_price,
+                gas_limit: rlp.val_at(2).map_err(invalid)?,
+                action: rlp.val_at(3).map_err(invalid)?,
+                value: rlp.val_at(4).map_err(invalid)?,
+                input: rlp.val_at(5).map_err(invalid)?,
+                access_list: AccessList::default(),
+            }
+        }
+    };
+
+    // 末尾の余分なバイトや EIP-155 の 0, 0 以外など、組み立て直すと変わるものは署名用ハッシュが食い違うため受け付けない
+    if encode_unsigned(&tx_type, &message)? != unsigned {
+        return Err(Error::InvalidArgument(
+            "unsigned transaction is not in canonical form".to_string(),
+        ));
+    }
+    Ok((tx_type, message))
+}
+
+// v (0/1、27/28、EIP-155 の chain_id * 2 + 35/36) から y のパリティを求める
+fn parity(v: u64, chain_id: u64) -> Result<bool> {
+    match v {
+        0 | 27 => Ok(false),
+        1 | 28 => Ok(true),
+        v if v == chain_id * 2 + 35 => Ok(false),
+        v if v == chain_id * 2 + 36 => Ok(true),
+        v => Err(Error::InvalidArgument(format!(
+            "v must be 0, 1, 27, 28, {} or {} for chain id {chain_id}, got {v}",
+            chain_id * 2 + 35,
+            chain_id * 2 + 36
+        ))),
+    }
+}
+
+// EIP-2 により s は曲線の位数の半分以下でなければならない
+// HSM などが大きい s を返した場合は位数 - s にして y のパリティを反転する (同じ署名者の署名のまま)
+pub fn normalize(signature: Signature) -> Result<Signature> {
+    let ecdsa_signature = k256::ecdsa::Signature::from_scalars(signature.r.0, signature.s.0)?;
+    Ok(match ecdsa_signature.normalize_s() {
+        Some(normalized) => Signature {
+            s: H256::from_slice(&normalized.s().to_bytes()),
+            odd_y_parity: !signature.odd_y_parity,
+            ..signature
+        },
+        None => signature,
+    })
+}
+
+// 外部で署名用ハッシュに署名した r, s, v を付けて raw トランザクションを作成し、署名者のアドレスとともに返す
+pub fn assemble(prepared: &Prepared, r: U256, s: U256, v: u64) -> Result<(Vec<u8>, H160)> {
+    let (tx_type, message) = decode_unsigned(&prepared.unsigned)?;
+    let sighash = signer::keccak256(&prepared.unsigned);
+    if sighash != prepared.sighash {
+        return Err(Error::InvalidArgument(format!(
+            "sighash {:?} does not match the unsigned transaction ({sighash:?})",
+            prepared.sighash
+        )));
+    }
+
+    let signature = normalize(Signature {
+        r: H256(abi::encode_u256(r)),
+        s: H256(abi::encode_u256(s)),
+        odd_y_parity: parity(v, message.chain_id)?,
+    })?;
+    let signer = signer::recover(&sighash, &signature)?;
+
+    let raw = match tx_type {
+        TxType::Legacy => {
+            transaction::encode_legacy(transaction::legacy_message(&message), &signature)?
+        }
+        _ => {
+            let mut raw = Vec::with_capacity(transaction::raw_capacity(&message));
+            transaction::encode_into(&transaction::with_signature(message, &signature), &mut raw);
+            raw
+        }
+    };
+    Ok((raw, signer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+    use ethereum::TransactionAction;
+
+    // secp256k1 の位数
+    const ORDER: &str = "0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    fn test_message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(3),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: U256::from(1_000_000_000_000_000u64),
+            input: vec![0x12, 0x34],
+            access_list: AccessList::default(),
+        }
+    }
+
+    // prepare の出力を読み、外部の署名者の代わりに sighash に署名する
+    fn prepare_and_sign(tx_type: &TxType) -> (Prepared, Signature) {
+        let output = prepare(tx_type, &test_message()).unwrap();
+        let prepared: Prepared = serde_json::from_value(output).unwrap();
+        let signature = signer::sign_hash(&test_signing_key(), &prepared.sighash).unwrap();
+        (prepared, signature)
+    }
+
+    fn u256(hash: H256) -> U256 {
+        U256::from_big_endian(hash.as_bytes())
+    }
+
+    #[test]
+    fn test_eip1559() {
+        let output = prepare(&TxType::Eip1559, &test_message()).unwrap();
+        assert_eq!(output["transaction"]["type"], "0x2");
+        assert_eq!(
+            output["sighash"],
+            json!(transaction::signing_hash(&test_message()))
+        );
+
+        let (prepared, signature) = prepare_and_sign(&TxType::Eip1559);
+        let expected = transaction::sign(test_message(), &test_signing_key()).unwrap();
+        // v は 0/1 でも 27/28 でもよい
+        for v in [
+            signature.odd_y_parity as u64,
+            27 + signature.odd_y_parity as u64,
+        ] {
+            let (raw, signer) =
+                assemble(&prepared, u256(signature.r), u256(signature.s), v).unwrap();
+            assert_eq!(raw, expected);
+            assert_eq!(signer, TEST_ADDRESS.parse().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_legacy() {
+        let output = prepare(&TxType::Legacy, &test_message()).unwrap();
+        assert_eq!(output["transaction"]["type"], "0x0");
+        assert_eq!(output["transaction"]["gasPrice"], "0x6fc23ac00");
+        assert!(output["transaction"].get("maxFeePerGas").is_none());
+
+        let (prepared, signature) = prepare_and_sign(&TxType::Legacy);
+        let expected = transaction::sign_legacy(&test_message(), &test_signing_key()).unwrap();
+        let v = 11155111 * 2 + 35 + signature.odd_y_parity as u64;
+        let (raw, signer) = assemble(&prepared, u256(signature.r), u256(signature.s), v).unwrap();
+        assert_eq!(raw, expected);
+        assert_eq!(signer, TEST_ADDRESS.parse().unwrap());
+
+        // 別のチェーンの EIP-155 の v は受け付けない
+        assert!(assemble(&prepared, u256(signature.r), u256(signature.s), 37).is_err());
+    }
+
+    #[test]
+    fn test_high_s() {
+        let (prepared, signature) = prepare_and_sign(&TxType::Eip1559);
+        let expected = transaction::sign(test_message(), &test_signing_key()).unwrap();
+        // 位数 - s とパリティの反転は同じ署名者の署名
+        let high_s = U256::from_str_radix(&ORDER[2..], 16).unwrap() - u256(signature.s);
+        let v = !signature.odd_y_parity as u64;
+        let (raw, signer) = assemble(&prepared, u256(signature.r), high_s, v).unwrap();
+        assert_eq!(raw, expected);
+        assert_eq!(signer, TEST_ADDRESS.parse().unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (mut prepared, signature) = prepare_and_sign(&TxType::Eip1559);
+        let (r, s) = (u256(signature.r), u256(signature.s));
+        // パリティを間違えると別のアドレスになる
+        let (_, signer) = assemble(&prepared, r, s, !signature.odd_y_parity as u64).unwrap();
+        assert_ne!(signer, TEST_ADDRESS.parse::<H160>().unwrap());
+
+        assert!(assemble(&prepared, r, s, 29).is_err());
+        assert!(assemble(&prepared, U256::zero(), s, 0).is_err());
+
+        // unsigned を書き換えると sighash と食い違う
+        let sighash = prepared.sighash;
+        prepared.sighash = H256::zero();
+        assert!(assemble(&prepared, r, s, 0).is_err());
+        prepared.sighash = sighash;
+        prepared.unsigned.push(0);
+        assert!(assemble(&prepared, r, s, 0).is_err());
+        prepared.unsigned = vec![0x7b, 0xc0];
+        assert!(assemble(&prepared, r, s, 0).is_err());
+
+        // zkSync と Celo は prepare できない
+        assert!(prepare(&TxType::Celo(H160::zero()), &test_message()).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    Result,
+    abi::{self, Arg},
+    error::Error,
+};
+use ethereum_types::{H160, U256};
+use std::path::Path;
+
+// Disperse (https://disperse.app) のコントラクト (多くのチェーンで同じアドレス)
+pub const DISPERSE_ADDRESS: H160 = H160([
+    0xd1, 0x52, 0xf5, 0x49, 0x54, 0x50, 0x93, 0x34, 0x7a, 0x16, 0x2d, 0xce, 0x21, 0x0e, 0x72, 0x93,
+    0xf1, 0x45, 0x21, 0x50,
+]);
+
+// 送金先と量 (量はトークン単位の文字列で、decimals でスケーリングする前)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payout {
+    pub recipient: H160,
+    pub amount: String,
+}
+
+// "address,amount" の行を読み込む
+// 空行と # で始まる行は無視する
+pub fn parse_payouts(content: &str) -> Result<Vec<Payout>> {
+    let mut payouts = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: &str| {
+            Error::InvalidArgument(format!("payouts line {}: {reason}: '{line}'", i + 1))
+        };
+        let (recipient, amount) = line
+            .split_once(',')
+            .ok_or_else(|| invalid("expected 'address,amount'"))?;
+        let recipient = recipient
+            .trim()
+            .parse()
+            .map_err(|_| invalid("invalid address"))?;
+        payouts.push(Payout {
+            recipient,
+            amount: amount.trim().to_string(),
+        });
+    }
+
+    if payouts.is_empty() {
+        return Err(Error::InvalidArgument("payouts: no recipients".to_string()));
+    }
+    Ok(payouts)
+}
+
+pub fn payouts_from_path<P: AsRef<Path>>(path: P) -> Result<Vec<Payout>> {
+    parse_payouts(&std::fs::read_to_string(path)?)
+}
+
+// 合計 (ETH モードでは value、トークンモードでは必要な allowance)
+pub fn total(values: &[U256]) -> Result<U256> {
+    values
+        .iter()
+        .try_fold(U256::zero(), |sum, value| sum.checked_add(*value))
+        .ok_or_else(|| Error::InvalidArgument("payouts: total overflows uint256".to_string()))
+}
+
+// disperseEther(address[],uint256[]) の calldata
+pub fn disperse_ether_calldata(recipients: &[H160], values: &[U256]) -> Vec<u8> {
+    abi::encode_call_with_args(
+        "disperseEther(address[],uint256[])",
+        &[
+            Arg::Dynamic(abi::encode_address_array(recipients)),
+            Arg::Dynamic(abi::encode_u256_array(values)),
+        ],
+    )
+}
+
+// disperseToken(address,address[],uint256[]) の calldata
+// 事前に Disperse コントラクトへの approve (合計以上) が必要
+pub fn disperse_token_calldata(token: &H160, recipients: &[H160], values: &[U256]) -> Vec<u8> {
+    abi::encode_call_with_args(
+        "disperseToken(address,address[],uint256[])",
+        &[
+            Arg::Word(abi::encode_address(token)),
+            Arg::Dynamic(abi::encode_address_array(recipients)),
+            Arg::Dynamic(abi::encode_u256_array(values)),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h160(s: &str) -> H160 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_disperse_address() {
+        assert_eq!(
+            DISPERSE_ADDRESS,
+            h160("0xD152f549545093347A162Dce210e7293f1452150")
+        );
+    }
+
+    #[test]
+    fn test_parse_payouts() {
+        let payouts = parse_payouts(
+            "# recipient,amount\n\
+             0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df, 1.5\n\
+             \n\
+             0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266,2\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            payouts,
+            vec![
+                Payout {
+                    recipient: h160("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"),
+                    amount: "1.5".to_string(),
+                },
+                Payout {
+                    recipient: h160("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"),
+                    amount: "2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_payouts_errors() {
+        assert!(parse_payouts("").is_err());
+        assert!(parse_payouts("# only comments\n").is_err());
+
+        // エラーメッセージに行番号が含まれる
+        match parse_payouts("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df,1\n0x1234,2\n") {
+            Err(Error::InvalidArgument(message)) => {
+                assert!(message.starts_with("payouts line 2"), "{message}")
+            }
+            result => panic!("Expected InvalidArgument, got: {result:?}"),
+        }
+        assert!(parse_payouts("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df 1\n").is_err());
+    }
+
+    #[test]
+    fn test_total() {
+        assert_eq!(
+            total(&[U256::from(1), U256::from(2)]).unwrap(),
+            U256::from(3)
+        );
+        assert!(total(&[U256::MAX, U256::one()]).is_err());
+    }
+
+    #[test]
+    fn test_disperse_ether_calldata() {
+        let calldata = disperse_ether_calldata(
+            &[h160("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df")],
+            &[U256::from(1000)],
+        );
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "e63d38ed",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000080",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "00000000000000000000000000000000000000000000000000000000000003e8",
+            )
+        );
+    }
+
+    #[test]
+    fn test_disperse_token_calldata() {
+        let token = h160("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let calldata = disperse_token_calldata(
+            &token,
+            &[h160("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df")],
+            &[U256::from(1000)],
+        );
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "c73a2d60",
+                "000000000000000000000000a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "00000000000000000000000000000000000000000000000000000000000003e8",
+            )
+        );
+    }
+}
@@ -0,0 +1,587 @@
+use crate::{Result, abi, error::Error, json, signer::keccak256};
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+// ドメインを表す型名
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+// 構造体のフィールド定義
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+pub type Types = BTreeMap<String, Vec<TypedDataField>>;
+
+// eth_signTypedData_v4 で渡される {types, domain, primaryType, message} 形式のJSON
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: Types,
+    pub domain: Map<String, Value>,
+    pub primary_type: String,
+    pub message: Value,
+}
+
+impl TypedData {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        json::parse(&json_content).map_err(Error::in_file(path))
+    }
+
+    // types に EIP712Domain の定義がなければ domain に含まれるフィールドから組み立てる
+    fn types_with_domain(&self) -> Cow<'_, Types> {
+        if self.types.contains_key(DOMAIN_TYPE) {
+            Cow::Borrowed(&self.types)
+        } else {
+            let mut types = self.types.clone();
+            types.insert(DOMAIN_TYPE.to_string(), domain_fields(&self.domain));
+            Cow::Owned(types)
+        }
+    }
+
+    // ドメインセパレータ (hashStruct(EIP712Domain, domain))
+    pub fn domain_separator(&self) -> Result<H256> {
+        let domain = Value::Object(self.domain.clone());
+        hash_struct(&self.types_with_domain(), DOMAIN_TYPE, &domain)
+    }
+
+    // メッセージの構造体ハッシュ (hashStruct(primaryType, message))
+    pub fn message_hash(&self) -> Result<H256> {
+        hash_struct(&self.types, &self.primary_type, &self.message)
+    }
+
+    // 署名対象のダイジェスト
+    // keccak256("\x19\x01" + domainSeparator + hashStruct(message))
+    pub fn digest(&self) -> Result<H256> {
+        let mut buf = Vec::with_capacity(2 + 32 + 32);
+        buf.extend_from_slice(&[0x19, 0x01]);
+        buf.extend_from_slice(self.domain_separator()?.as_bytes());
+        // primaryType が EIP712Domain の場合はメッセージ部分を含めない
+        if self.primary_type != DOMAIN_TYPE {
+            buf.extend_from_slice(self.message_hash()?.as_bytes());
+        }
+
+        Ok(keccak256(buf))
+    }
+
+    // デバッグ用: domain と message に含まれるすべての構造体のハッシュ値
+    pub fn struct_hashes(&self) -> Result<Vec<StructHash>> {
+        let types = self.types_with_domain();
+        let mut struct_hashes = Vec::new();
+        collect_struct_hashes(
+            &types,
+            DOMAIN_TYPE,
+            "domain",
+            &Value::Object(self.domain.clone()),
+            &mut struct_hashes,
+        )?;
+        if self.primary_type != DOMAIN_TYPE {
+            collect_struct_hashes(
+                &types,
+                &self.primary_type,
+                "message",
+                &self.message,
+                &mut struct_hashes,
+            )?;
+        }
+
+        Ok(struct_hashes)
+    }
+}
+
+// 構造体ごとのハッシュ値 (オンチェーンの実装と突き合わせるためのデバッグ情報)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructHash {
+    // "message.from" や "message.items[0]" のような値の位置
+    pub path: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub encoded_type: String,
+    pub type_hash: H256,
+    pub hash: H256,
+}
+
+// 構造体型の値を再帰的にたどってハッシュ値を収集
+fn collect_struct_hashes(
+    types: &Types,
+    type_name: &str,
+    path: &str,
+    value: &Value,
+    out: &mut Vec<StructHash>,
+) -> Result<()> {
+    // 配列は要素ごとにたどる
+    if let Some(open) = type_name.rfind('[') {
+        let item_type = &type_name[..open];
+        for (i, item) in value.as_array().into_iter().flatten().enumerate() {
+            collect_struct_hashes(types, item_type, &format!("{path}[{i}]"), item, out)?;
+        }
+        return Ok(());
+    }
+
+    let Some(fields) = types.get(type_name) else {
+        return Ok(());
+    };
+
+    out.push(StructHash {
+        path: path.to_string(),
+        type_name: type_name.to_string(),
+        encoded_type: encode_type(types, type_name)?,
+        type_hash: type_hash(types, type_name)?,
+        hash: hash_struct(types, type_name, value)?,
+    });
+    for field in fields {
+        if let Some(field_value) = value.get(&field.name) {
+            let field_path = format!("{path}.{}", field.name);
+            collect_struct_hashes(types, &field.type_name, &field_path, field_value, out)?;
+        }
+    }
+
+    Ok(())
+}
+
+// EIP712Domain で定義されている順序でフィールドを並べる
+fn domain_fields(domain: &Map<String, Value>) -> Vec<TypedDataField> {
+    [
+        ("name", "string"),
+        ("version", "string"),
+        ("chainId", "uint256"),
+        ("verifyingContract", "address"),
+        ("salt", "bytes32"),
+    ]
+    .into_iter()
+    .filter(|(name, _)| domain.contains_key(*name))
+    .map(|(name, type_name)| TypedDataField {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+    })
+    .collect()
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidTypedData(message.into())
+}
+
+// "Person[]" や "uint256[3][]" から配列部分を除いた型名
+fn base_type(type_name: &str) -> &str {
+    type_name.split('[').next().unwrap_or(type_name)
+}
+
+// 参照している構造体型を再帰的に収集
+fn collect_dependencies(types: &Types, type_name: &str, found: &mut BTreeSet<String>) {
+    let type_name = base_type(type_name);
+    if found.contains(type_name) {
+        return;
+    }
+    let Some(fields) = types.get(type_name) else {
+        return;
+    };
+
+    found.insert(type_name.to_string());
+    for field in fields {
+        collect_dependencies(types, &field.type_name, found);
+    }
+}
+
+// encodeType: "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+// primaryType に続けて、参照している型をアルファベット順に連結する
+pub fn encode_type(types: &Types, primary_type: &str) -> Result<String> {
+    if !types.contains_key(primary_type) {
+        return Err(invalid(format!("unknown type '{primary_type}'")));
+    }
+
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(types, primary_type, &mut dependencies);
+    dependencies.remove(primary_type);
+
+    let encoded = std::iter::once(primary_type)
+        .chain(dependencies.iter().map(String::as_str))
+        .map(|type_name| {
+            let fields = types[type_name]
+                .iter()
+                .map(|field| format!("{} {}", field.type_name, field.name))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("{type_name}({fields})")
+        })
+        .collect();
+
+    Ok(encoded)
+}
+
+pub fn type_hash(types: &Types, primary_type: &str) -> Result<H256> {
+    Ok(keccak256(encode_type(types, primary_type)?))
+}
+
+// hashStruct(s) = keccak256(typeHash + encodeData(s))
+pub fn hash_struct(types: &Types, type_name: &str, data: &Value) -> Result<H256> {
+    let object = data
+        .as_object()
+        .ok_or_else(|| invalid(format!("expected object for type '{type_name}'")))?;
+    let fields = types
+        .get(type_name)
+        .ok_or_else(|| invalid(format!("unknown type '{type_name}'")))?;
+
+    let mut buf = Vec::with_capacity(32 * (fields.len() + 1));
+    buf.extend_from_slice(type_hash(types, type_name)?.as_bytes());
+    for field in fields {
+        let value = object.get(&field.name).ok_or_else(|| {
+            invalid(format!(
+                "missing field '{}' in type '{type_name}'",
+                field.name
+            ))
+        })?;
+        // エラーメッセージにフィールドのパスを付与
+        let encoded = encode_value(types, &field.type_name, value).map_err(|e| match e {
+            Error::InvalidTypedData(message) => {
+                invalid(format!("{type_name}.{}: {message}", field.name))
+            }
+            e => e,
+        })?;
+        buf.extend_from_slice(&encoded);
+    }
+
+    Ok(keccak256(buf))
+}
+
+// 各フィールドの値を32バイトにエンコード
+fn encode_value(types: &Types, type_name: &str, value: &Value) -> Result<[u8; 32]> {
+    // 配列: 各要素のエンコード結果を連結してハッシュ
+    if let Some(open) = type_name.rfind('[') {
+        let item_type = &type_name[..open];
+        let length = &type_name[open + 1..type_name.len() - 1];
+        let items = value
+            .as_array()
+            .ok_or_else(|| invalid(format!("expected array for '{type_name}'")))?;
+        if !length.is_empty() && length.parse::<usize>().ok() != Some(items.len()) {
+            return Err(invalid(format!(
+                "expected {length} items for '{type_name}', got {}",
+                items.len()
+            )));
+        }
+
+        let mut buf = Vec::with_capacity(32 * items.len());
+        for item in items {
+            buf.extend_from_slice(&encode_value(types, item_type, item)?);
+        }
+        return Ok(keccak256(buf).0);
+    }
+
+    // 構造体: hashStruct
+    if types.contains_key(type_name) {
+        return Ok(hash_struct(types, type_name, value)?.0);
+    }
+
+    match type_name {
+        "string" => {
+            let s = value.as_str().ok_or_else(|| invalid("expected string"))?;
+            Ok(keccak256(s).0)
+        }
+        "bytes" => Ok(keccak256(abi::value_to_bytes(value).map_err(from_abi_error)?).0),
+        // 基本型は ABI と同じエンコード
+        _ => abi::ParamType::parse(type_name)
+            .and_then(|param_type| abi::encode_atomic(&param_type, value))
+            .map_err(from_abi_error),
+    }
+}
+
+// ABI のエラーを型付きデータのエラーとして扱う
+fn from_abi_error(e: Error) -> Error {
+    match e {
+        Error::InvalidAbiData(message) => invalid(message),
+        e => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer;
+    use ethereum_types::U256;
+    use k256::ecdsa::SigningKey;
+
+    // EIP-712 仕様の Example.js のデータ
+    const MAIL_TYPED_DATA: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": {
+                "name": "Cow",
+                "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+            },
+            "to": {
+                "name": "Bob",
+                "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"
+            },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn mail_typed_data() -> TypedData {
+        serde_json::from_str(MAIL_TYPED_DATA).unwrap()
+    }
+
+    fn h256(s: &str) -> H256 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_encode_type() {
+        let typed_data = mail_typed_data();
+        assert_eq!(
+            encode_type(&typed_data.types, "Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+    }
+
+    #[test]
+    fn test_type_hash() {
+        let typed_data = mail_typed_data();
+        assert_eq!(
+            type_hash(&typed_data.types, "Mail").unwrap(),
+            h256("0xa0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2")
+        );
+    }
+
+    #[test]
+    fn test_message_hash() {
+        assert_eq!(
+            mail_typed_data().message_hash().unwrap(),
+            h256("0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+    }
+
+    #[test]
+    fn test_domain_separator() {
+        assert_eq!(
+            mail_typed_data().domain_separator().unwrap(),
+            h256("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+    }
+
+    #[test]
+    fn test_domain_separator_without_domain_type() {
+        // types に EIP712Domain がなくても domain のフィールドから同じ値になる
+        let mut typed_data = mail_typed_data();
+        typed_data.types.remove(DOMAIN_TYPE);
+
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            h256("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+    }
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(
+            mail_typed_data().digest().unwrap(),
+            h256("0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+    }
+
+    #[test]
+    fn test_sign_digest() {
+        // 秘密鍵は keccak256("cow")
+        let signing_key = SigningKey::from_slice(keccak256("cow").as_bytes()).unwrap();
+        let signature =
+            signer::sign_hash(&signing_key, &mail_typed_data().digest().unwrap()).unwrap();
+
+        assert_eq!(
+            signature.r,
+            h256("0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d")
+        );
+        assert_eq!(
+            signature.s,
+            h256("0x07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562")
+        );
+        assert_eq!(signature.to_bytes()[64], 28);
+    }
+
+    #[test]
+    fn test_struct_hashes() {
+        let typed_data = mail_typed_data();
+        let struct_hashes = typed_data.struct_hashes().unwrap();
+
+        let paths: Vec<_> = struct_hashes.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, ["domain", "message", "message.from", "message.to"]);
+
+        // domain と message のハッシュはそれぞれドメインセパレータ、メッセージハッシュと一致する
+        assert_eq!(
+            struct_hashes[0].hash,
+            typed_data.domain_separator().unwrap()
+        );
+        assert_eq!(struct_hashes[1].hash, typed_data.message_hash().unwrap());
+        assert_eq!(
+            struct_hashes[1].type_hash,
+            h256("0xa0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2")
+        );
+        assert_eq!(struct_hashes[2].type_name, "Person");
+        assert_eq!(
+            struct_hashes[2].encoded_type,
+            "Person(string name,address wallet)"
+        );
+    }
+
+    #[test]
+    fn test_struct_hashes_array_path() {
+        let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "Item": [{ "name": "id", "type": "uint256" }],
+                "Order": [{ "name": "items", "type": "Item[]" }]
+            },
+            "primaryType": "Order",
+            "domain": { "name": "Shop" },
+            "message": { "items": [{ "id": 1 }, { "id": 2 }] }
+        }))
+        .unwrap();
+
+        let paths: Vec<_> = typed_data
+            .struct_hashes()
+            .unwrap()
+            .into_iter()
+            .map(|h| h.path)
+            .collect();
+        assert_eq!(
+            paths,
+            ["domain", "message", "message.items[0]", "message.items[1]"]
+        );
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let mut typed_data = mail_typed_data();
+        typed_data
+            .message
+            .as_object_mut()
+            .unwrap()
+            .remove("contents");
+
+        match typed_data.message_hash() {
+            Err(Error::InvalidTypedData(message)) => assert!(message.contains("contents")),
+            result => panic!("Expected InvalidTypedData error, got: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_unknown_primary_type() {
+        let mut typed_data = mail_typed_data();
+        typed_data.primary_type = "Letter".to_string();
+
+        assert!(typed_data.message_hash().is_err());
+    }
+
+    // 単一フィールドの型でエンコード結果を確認するヘルパー
+    fn encode_single(type_name: &str, value: Value) -> Result<[u8; 32]> {
+        encode_value(&Types::new(), type_name, &value)
+    }
+
+    #[test]
+    fn test_encode_uint() {
+        let expected = abi::encode_u256(U256::from(1000));
+        assert_eq!(encode_single("uint256", 1000.into()).unwrap(), expected);
+        assert_eq!(encode_single("uint256", "1000".into()).unwrap(), expected);
+        assert_eq!(encode_single("uint256", "0x3e8".into()).unwrap(), expected);
+
+        assert!(encode_single("uint8", 256.into()).is_err());
+        assert!(encode_single("uint256", (-1).into()).is_err());
+    }
+
+    #[test]
+    fn test_encode_int_negative() {
+        // -1 は全ビット1
+        assert_eq!(encode_single("int256", (-1).into()).unwrap(), [0xff; 32]);
+        assert_eq!(encode_single("int8", "-128".into()).unwrap()[31], 0x80);
+        assert!(encode_single("int8", "-129".into()).is_err());
+        assert!(encode_single("int8", 128.into()).is_err());
+    }
+
+    #[test]
+    fn test_encode_bytes_fixed() {
+        let word = encode_single("bytes4", "0xa9059cbb".into()).unwrap();
+        assert_eq!(word[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(word[4..], [0; 28]);
+
+        assert!(encode_single("bytes4", "0xa9059c".into()).is_err());
+        assert!(encode_single("bytes33", "0x00".into()).is_err());
+    }
+
+    #[test]
+    fn test_encode_dynamic_bytes_and_string() {
+        assert_eq!(
+            encode_single("bytes", "0x1234".into()).unwrap(),
+            keccak256([0x12, 0x34]).0
+        );
+        assert_eq!(
+            encode_single("string", "Hello".into()).unwrap(),
+            keccak256("Hello").0
+        );
+    }
+
+    #[test]
+    fn test_encode_bool_and_address() {
+        assert_eq!(encode_single("bool", true.into()).unwrap()[31], 1);
+        assert_eq!(encode_single("bool", false.into()).unwrap(), [0; 32]);
+
+        let word = encode_single(
+            "address",
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826".into(),
+        )
+        .unwrap();
+        assert_eq!(word[..12], [0; 12]);
+        assert_eq!(word[12], 0xcd);
+    }
+
+    #[test]
+    fn test_encode_array() {
+        let word = encode_single("uint256[]", serde_json::json!([1, 2])).unwrap();
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&abi::encode_u256(U256::from(1)));
+        expected.extend_from_slice(&abi::encode_u256(U256::from(2)));
+        assert_eq!(word, keccak256(expected).0);
+
+        // 固定長配列の要素数チェック
+        assert!(encode_single("uint256[3]", serde_json::json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_typed_data_from_path() {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(temp_file, "{}", MAIL_TYPED_DATA).unwrap();
+
+        let typed_data = TypedData::from_path(temp_file.path()).unwrap();
+        assert_eq!(typed_data.primary_type, "Mail");
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    Result,
+    abi::{self, Arg},
+    error::Error,
+};
+use ethereum_types::{H160, U256};
+
+// safeTransferFrom(address,address,uint256,uint256,bytes) の calldata
+pub fn safe_transfer_from_calldata(
+    from: &H160,
+    to: &H160,
+    id: U256,
+    amount: U256,
+    data: &[u8],
+) -> Vec<u8> {
+    abi::encode_call_with_args(
+        "safeTransferFrom(address,address,uint256,uint256,bytes)",
+        &[
+            Arg::Word(abi::encode_address(from)),
+            Arg::Word(abi::encode_address(to)),
+            Arg::Word(abi::encode_u256(id)),
+            Arg::Word(abi::encode_u256(amount)),
+            Arg::Dynamic(abi::encode_bytes(data)),
+        ],
+    )
+}
+
+// safeBatchTransferFrom(address,address,uint256[],uint256[],bytes) の calldata
+pub fn safe_batch_transfer_from_calldata(
+    from: &H160,
+    to: &H160,
+    ids: &[U256],
+    amounts: &[U256],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    // 長さが異なるとコントラクト側で revert する
+    if ids.len() != amounts.len() {
+        return Err(Error::InvalidArgument(format!(
+            "ids and amounts must have the same length ({} != {})",
+            ids.len(),
+            amounts.len()
+        )));
+    }
+    if ids.is_empty() {
+        return Err(Error::InvalidArgument("ids must not be empty".to_string()));
+    }
+
+    Ok(abi::encode_call_with_args(
+        "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+        &[
+            Arg::Word(abi::encode_address(from)),
+            Arg::Word(abi::encode_address(to)),
+            Arg::Dynamic(abi::encode_u256_array(ids)),
+            Arg::Dynamic(abi::encode_u256_array(amounts)),
+            Arg::Dynamic(abi::encode_bytes(data)),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from() -> H160 {
+        "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+            .parse()
+            .unwrap()
+    }
+
+    fn to() -> H160 {
+        "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_safe_transfer_from_calldata() {
+        let calldata =
+            safe_transfer_from_calldata(&from(), &to(), U256::from(7), U256::from(100), &[]);
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "f242432a",
+                "000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "0000000000000000000000000000000000000000000000000000000000000007",
+                "0000000000000000000000000000000000000000000000000000000000000064",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_safe_transfer_from_calldata_with_data() {
+        let calldata = safe_transfer_from_calldata(
+            &from(),
+            &to(),
+            U256::from(7),
+            U256::from(100),
+            &[0x12, 0x34],
+        );
+
+        assert_eq!(
+            hex::encode(&calldata[4 + 32 * 5..]),
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "1234000000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_safe_batch_transfer_from_calldata() {
+        let calldata = safe_batch_transfer_from_calldata(
+            &from(),
+            &to(),
+            &[U256::from(1), U256::from(2)],
+            &[U256::from(10), U256::from(20)],
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "2eb2c2d6",
+                "000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000100",
+                "0000000000000000000000000000000000000000000000000000000000000160",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "000000000000000000000000000000000000000000000000000000000000000a",
+                "0000000000000000000000000000000000000000000000000000000000000014",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_safe_batch_transfer_from_length_mismatch() {
+        let result = safe_batch_transfer_from_calldata(
+            &from(),
+            &to(),
+            &[U256::from(1), U256::from(2)],
+            &[U256::from(10)],
+            &[],
+        );
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+
+        assert!(safe_batch_transfer_from_calldata(&from(), &to(), &[], &[], &[]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Result, abi, error::Error, rpc::RpcClient};
+use ethereum_types::{H160, U256};
+
+// transfer(address,uint256) の calldata
+pub fn transfer_calldata(to: &H160, amount: U256) -> Vec<u8> {
+    abi::encode_call(
+        "transfer(address,uint256)",
+        &[abi::encode_address(to), abi::encode_u256(amount)],
+    )
+}
+
+// approve(address,uint256) の calldata
+pub fn approve_calldata(spender: &H160, amount: U256) -> Vec<u8> {
+    abi::encode_call(
+        "approve(address,uint256)",
+        &[abi::encode_address(spender), abi::encode_u256(amount)],
+    )
+}
+
+// 無期限 (無制限) の approve とみなす量か
+// type(uint256).max に限らず、2^255 以上は実質的に無制限として扱う
+pub fn is_unlimited_approval(amount: U256) -> bool {
+    amount >= U256::one() << 255
+}
+
+// トークンの decimals() を取得
+pub fn fetch_decimals(client: &RpcClient, token: &H160) -> Result<u8> {
+    let data = client.call(token, &abi::encode_call("decimals()", &[]))?;
+    let decimals = abi::decode_u256(&data)?;
+    if decimals > U256::from(u8::MAX) {
+        return Err(Error::InvalidAbiData(format!(
+            "decimals() returned {decimals}"
+        )));
+    }
+
+    Ok(decimals.as_u32() as u8)
+}
+
+// トークンの symbol() を取得
+pub fn fetch_symbol(client: &RpcClient, token: &H160) -> Result<String> {
+    let data = client.call(token, &abi::encode_call("symbol()", &[]))?;
+    abi::decode_string(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+    use serde_json::json;
+
+    fn token() -> H160 {
+        "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_transfer_calldata() {
+        let to: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+        let calldata = transfer_calldata(&to, U256::from(12_500_000));
+
+        assert_eq!(
+            hex::encode(calldata),
+            "a9059cbb000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0000000000000000000000000000000000000000000000000000000000bebc20"
+        );
+    }
+
+    #[test]
+    fn test_approve_calldata() {
+        let spender: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+        let calldata = approve_calldata(&spender, U256::MAX);
+
+        assert_eq!(
+            hex::encode(calldata),
+            "095ea7b3000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11dfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+        );
+    }
+
+    #[test]
+    fn test_is_unlimited_approval() {
+        assert!(is_unlimited_approval(U256::MAX));
+        assert!(is_unlimited_approval(U256::one() << 255));
+        assert!(!is_unlimited_approval((U256::one() << 255) - 1));
+        assert!(!is_unlimited_approval(U256::zero()));
+    }
+
+    #[test]
+    fn test_fetch_decimals() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!(format!(
+            "0x{}",
+            hex::encode(abi::encode_u256(U256::from(6)))
+        )))]);
+        let client = RpcClient::new(&server.url);
+
+        assert_eq!(fetch_decimals(&client, &token()).unwrap(), 6);
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["params"][0]["data"], "0x313ce567");
+    }
+
+    #[test]
+    fn test_fetch_decimals_out_of_range() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!(format!(
+            "0x{}",
+            hex::encode(abi::encode_u256(U256::from(256)))
+        )))]);
+        let client = RpcClient::new(&server.url);
+
+        assert!(matches!(
+            fetch_decimals(&client, &token()),
+            Err(Error::InvalidAbiData(_))
+        ));
+    }
+}
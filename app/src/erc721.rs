@@ -0,0 +1,41 @@
+use crate::abi;
+use ethereum_types::{H160, U256};
+
+// safeTransferFrom(address,address,uint256) の calldata
+// from は署名者のアドレスでなければ承認済みのオペレーターとして扱われるため、呼び出し元で署名者を渡す
+pub fn safe_transfer_from_calldata(from: &H160, to: &H160, token_id: U256) -> Vec<u8> {
+    abi::encode_call(
+        "safeTransferFrom(address,address,uint256)",
+        &[
+            abi::encode_address(from),
+            abi::encode_address(to),
+            abi::encode_u256(token_id),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_transfer_from_calldata() {
+        let from: H160 = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+            .parse()
+            .unwrap();
+        let to: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+        let calldata = safe_transfer_from_calldata(&from, &to, U256::from(1234));
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "42842e0e",
+                "000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "00000000000000000000000000000000000000000000000000000000000004d2",
+            )
+        );
+    }
+}
@@ -1,7 +1,38 @@
+use crate::i18n::t;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    // どこで起きたかを付けたエラー (source に元のエラー)
+    #[error("{}: {source}", self.context())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}: {source}", self.context())]
+    Env {
+        name: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}: {source}", self.context())]
+    Endpoint {
+        url: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}: {source}", self.context())]
+    Field {
+        field: String,
+        #[source]
+        source: Box<Error>,
+    },
+
     #[error(transparent)]
     Config(#[from] config::ConfigError),
 
@@ -14,6 +45,263 @@ pub enum Error {
     #[error(transparent)]
     FromHex(#[from] hex::FromHexError),
 
-    #[error("Invalid private key length (expected: 32, input: {0}).")]
+    #[error(transparent)]
+    Http(#[from] ureq::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("{}\n{snippet}", t!("error.invalid_json", message = .message, line = .line, column = .column))]
+    InvalidJson {
+        message: String,
+        line: usize,
+        column: usize,
+        // 該当する行と ^ で示した位置
+        snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("{}", t!("error.invalid_private_key_length", length = .0))]
     InvalidPrivateKeyLength(usize),
+
+    #[error("{}", t!("error.missing_private_key"))]
+    MissingPrivateKey,
+
+    #[error("{}", t!("error.invalid_key_file", reason = .0))]
+    InvalidKeyFile(String),
+
+    #[error("{}", t!("error.key_decryption_failed"))]
+    KeyDecryptionFailed,
+
+    #[error("{}", t!("error.key_locked"))]
+    KeyLocked,
+
+    #[error("{}", t!("error.invalid_environment", problems = .0.join("; ")))]
+    InvalidEnvironment(Vec<String>),
+
+    #[error("{}", t!("error.invalid_chain_id", reason = .0))]
+    InvalidChainId(String),
+
+    #[error("{}", t!("error.invalid_argument", reason = .0))]
+    InvalidArgument(String),
+
+    #[error("{}", t!("error.invalid_amount", reason = .0))]
+    InvalidAmount(String),
+
+    #[error("{}", t!("error.invalid_abi_data", reason = .0))]
+    InvalidAbiData(String),
+
+    #[error("{}", t!("error.invalid_tls_config", reason = .0))]
+    InvalidTlsConfig(String),
+
+    #[error("{}", t!("error.invalid_fee_data", reason = .0))]
+    InvalidFeeData(String),
+
+    #[error("{}", t!("error.invalid_price", reason = .0))]
+    InvalidPrice(String),
+
+    #[error("{}", t!("error.invalid_typed_data", reason = .0))]
+    InvalidTypedData(String),
+
+    #[error("{}", t!("error.invalid_policy", reason = .0))]
+    InvalidPolicy(String),
+
+    #[error("{}", t!("error.policy_violation", reason = .0))]
+    PolicyViolation(String),
+
+    #[error("{}", t!("error.audit_log_tampered", reason = .0))]
+    AuditLogTampered(String),
+
+    #[error("{}", t!("error.raw_hash_signing_not_confirmed"))]
+    RawHashSigningNotConfirmed,
+
+    #[error("{}", t!("error.raw_hash_signing_with_policy"))]
+    RawHashSigningWithPolicy,
+
+    #[error("{}", t!("error.unlimited_approval_not_confirmed"))]
+    UnlimitedApprovalNotConfirmed,
+
+    #[error("{}", t!("error.mainnet_not_confirmed"))]
+    MainnetNotConfirmed,
+
+    #[error("{}", t!("error.value_not_confirmed", value = .value, threshold = .threshold))]
+    ValueNotConfirmed { value: String, threshold: String },
+
+    #[error("{}", t!("error.unexpected_deployment_address", actual = .actual, expected = .expected))]
+    UnexpectedDeploymentAddress { expected: String, actual: String },
+
+    #[error("{}", t!("error.unexpected_signer", actual = .actual, expected = .expected))]
+    UnexpectedSigner { expected: String, actual: String },
+
+    #[error("{}", t!("error.unverified_contract", reason = .0))]
+    UnverifiedContract(String),
+
+    #[error("{}", t!("error.flagged_address", address = .address, label = .label))]
+    FlaggedAddress { address: String, label: String },
+
+    #[error("{}", t!("error.simulation_failed", reason = .0))]
+    SimulationFailed(String),
+
+    #[error("{}", t!("error.missing_rpc_url"))]
+    MissingRpcUrl,
+
+    #[error("{}", t!("error.missing_nonce"))]
+    MissingNonce,
+
+    #[error("{}", t!("error.nondeterministic", source = .0))]
+    Nondeterministic(&'static str),
+
+    #[error("{}", t!("error.invalid_nonce_store", reason = .0))]
+    InvalidNonceStore(String),
+
+    #[error("{}", t!("error.invalid_journal", reason = .0))]
+    InvalidJournal(String),
+
+    #[error("{}", t!("error.invalid_state_file", reason = .0))]
+    InvalidStateFile(String),
+
+    #[error("{}", t!("error.state_decryption_failed", path = .0))]
+    StateDecryptionFailed(String),
+
+    #[error("{}", t!("error.duplicate_transaction", hash = .0))]
+    DuplicateTransaction(String),
+
+    #[error("{}", t!("error.broadcast_failed", failed = .failed, total = .total))]
+    BroadcastFailed { failed: usize, total: usize },
+
+    #[error("{}", t!("error.rpc", code = .code, message = .message))]
+    Rpc { code: i64, message: String },
+
+    #[error("{}", t!("error.plugin", reason = .0))]
+    Plugin(String),
+
+    #[error("{}", t!("error.unknown_weth_address", chain_id = .0))]
+    UnknownWethAddress(u64),
+
+    #[error("{}", t!("error.unknown_safe_service_url", chain_id = .0))]
+    UnknownSafeServiceUrl(u64),
+}
+
+// map_err で文脈を付ける
+// std::fs::read_to_string(&path).map_err(Error::in_file(&path))?
+impl Error {
+    pub fn in_file<E: Into<Error>>(path: impl AsRef<Path>) -> impl FnOnce(E) -> Error {
+        let path = path.as_ref().to_path_buf();
+        move |error| Error::File {
+            path,
+            source: Box::new(error.into()),
+        }
+    }
+
+    pub fn in_env<E: Into<Error>>(name: &str) -> impl FnOnce(E) -> Error {
+        let name = name.to_string();
+        move |error| Error::Env {
+            name,
+            source: Box::new(error.into()),
+        }
+    }
+
+    pub fn at_endpoint<E: Into<Error>>(url: &str) -> impl FnOnce(E) -> Error {
+        let url = url.to_string();
+        move |error| Error::Endpoint {
+            url,
+            source: Box::new(error.into()),
+        }
+    }
+
+    pub fn in_field<E: Into<Error>>(field: &str) -> impl FnOnce(E) -> Error {
+        let field = field.to_string();
+        move |error| Error::Field {
+            field,
+            source: Box::new(error.into()),
+        }
+    }
+
+    // 文脈を外した元のエラー (テストで種類を確かめる)
+    #[cfg(test)]
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::File { source, .. }
+            | Error::Env { source, .. }
+            | Error::Endpoint { source, .. }
+            | Error::Field { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    // 文脈だけのメッセージ (Display は元のエラーを ": " でつなぐ)
+    // ("Failed to load params.json: to_address: '0x00' is 1 byte, expected 20")
+    fn context(&self) -> String {
+        match self {
+            Error::File { path, .. } => t!("context.file", path = path.display()),
+            Error::Env { name, .. } => t!("context.env", name = name),
+            Error::Endpoint { url, .. } => t!("context.endpoint", url = url),
+            Error::Field { field, .. } => t!("context.field", field = field),
+            error => error.to_string(),
+        }
+    }
+
+    // 元のエラーの原因 (ureq の I/O エラーなど) までたどった全体 (--verbose)
+    pub fn report(&self) -> String {
+        let mut report = t!("main.error", error = self);
+        let causes = std::iter::successors(std::error::Error::source(self), |error| error.source());
+        for (i, cause) in causes.enumerate() {
+            if i == 0 {
+                report.push_str(&format!("\n\n{}", t!("main.caused_by")));
+            }
+            // #[source] の Box<Error> は Box のまま渡される
+            let error = cause
+                .downcast_ref::<Box<Error>>()
+                .map(Box::as_ref)
+                .or_else(|| cause.downcast_ref::<Error>());
+            let message = match error {
+                Some(error) => error.context(),
+                None => cause.to_string(),
+            };
+            report.push_str(&format!("\n    {i}: {message}"));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let error = std::fs::read_to_string("missing.json")
+            .map_err(Error::in_file("missing.json"))
+            .unwrap_err();
+        assert!(matches!(error.root(), Error::Io(_)));
+        assert!(
+            error
+                .to_string()
+                .starts_with("Failed to load missing.json: "),
+            "{error}"
+        );
+
+        let error =
+            Error::in_env("PRIVATE_KEY")(Error::in_field("key")(hex::FromHexError::OddLength));
+        assert!(matches!(
+            error.root(),
+            Error::FromHex(hex::FromHexError::OddLength)
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Invalid environment variable PRIVATE_KEY: Invalid field 'key': Odd number of digits"
+        );
+        assert_eq!(
+            error.report(),
+            "Error: Invalid environment variable PRIVATE_KEY: Invalid field 'key': Odd number of digits\n\nCaused by:\n    0: Invalid field 'key'\n    1: Odd number of digits"
+        );
+
+        // 文脈のないエラーはそのまま
+        let error = Error::MissingRpcUrl;
+        assert_eq!(error.report(), "Error: RPC_URL is not set.");
+    }
 }
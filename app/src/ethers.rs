@@ -0,0 +1,275 @@
+use crate::{
+    Result,
+    config::Config,
+    de::{deserialize_optional_hex_bytes, deserialize_optional_u256},
+    error::Error,
+    json,
+};
+use ethereum::{AccessListItem, EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, H256, U256};
+use serde::Deserialize;
+use std::path::Path;
+
+// ethers-rs の TypedTransaction を serde_json で書き出した JSON
+// {"type": "0x02", "from": "0x…", "to": "0x…", "gas": "0x5208", "value": "0x1", "data": "0x", "nonce": "0x4", "chainId": "0xaa36a7", "maxFeePerGas": "0x…", "maxPriorityFeePerGas": "0x…", "accessList": []}
+// None のフィールドは書き出されないため、省略したものは設定値で補う
+// eth_signTransaction のトランザクションオブジェクト (txrequest) もこの形に直して変換する
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct RawTypedTransaction {
+    #[serde(rename = "type")]
+    pub(crate) tx_type: String,
+    #[serde(default)]
+    pub(crate) from: Option<H160>,
+    // ENS の名前は解決しない (アドレスのみ)
+    #[serde(default)]
+    pub(crate) to: Option<H160>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) gas_price: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) value: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+    pub(crate) data: Option<Vec<u8>>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) nonce: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) chain_id: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) max_fee_per_gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    pub(crate) max_priority_fee_per_gas: Option<U256>,
+    #[serde(default)]
+    pub(crate) access_list: Vec<RawAccessListItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct RawAccessListItem {
+    address: H160,
+    storage_keys: Vec<H256>,
+}
+
+// 署名するトランザクションに変換した TypedTransaction
+#[derive(Debug)]
+pub struct TypedTransaction {
+    // 指定されていれば署名する鍵のアドレスと一致する必要がある
+    pub from: Option<H160>,
+    pub chain_id: Option<U256>,
+    // 省略した場合は NONCE_STORE_PATH のストアか RPC で割り当てる
+    pub nonce: Option<U256>,
+    // Legacy (type 0x00) なら gasPrice で EIP-155 の署名をする
+    pub legacy: bool,
+    pub gas_limit: U256,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub action: TransactionAction,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+}
+
+impl TryFrom<RawTypedTransaction> for TypedTransaction {
+    type Error = String;
+
+    fn try_from(raw: RawTypedTransaction) -> std::result::Result<Self, String> {
+        let legacy = match raw.tx_type.as_str() {
+            "0x00" | "0x0" => true,
+            "0x02" | "0x2" => false,
+            "0x01" | "0x1" => {
+                return Err("type 0x01 (EIP-2930) is not supported, use 0x00 or 0x02".to_string());
+            }
+            other => return Err(format!("type: unknown transaction type '{other}'")),
+        };
+        let gas_limit = raw
+            .gas
+            .ok_or("gas: missing (fill the transaction before exporting it)")?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) = if legacy {
+            if raw.max_fee_per_gas.is_some() || raw.max_priority_fee_per_gas.is_some() {
+                return Err("maxFeePerGas: not allowed in a legacy (0x00) transaction".to_string());
+            }
+            if !raw.access_list.is_empty() {
+                return Err("accessList: not allowed in a legacy (0x00) transaction".to_string());
+            }
+            (raw.gas_price, raw.gas_price)
+        } else {
+            if raw.gas_price.is_some() {
+                return Err("gasPrice: not allowed in an EIP-1559 (0x02) transaction".to_string());
+            }
+            (raw.max_fee_per_gas, raw.max_priority_fee_per_gas)
+        };
+
+        Ok(Self {
+            from: raw.from,
+            chain_id: raw.chain_id,
+            nonce: raw.nonce,
+            legacy,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            action: match raw.to {
+                Some(to) => TransactionAction::Call(to),
+                None => TransactionAction::Create,
+            },
+            value: raw.value.unwrap_or_default(),
+            input: raw.data.unwrap_or_default(),
+            access_list: raw
+                .access_list
+                .into_iter()
+                .map(|item| AccessListItem {
+                    address: item.address,
+                    storage_keys: item.storage_keys,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for TypedTransaction {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawTypedTransaction::deserialize(deserializer)
+            .and_then(|raw| Self::try_from(raw).map_err(serde::de::Error::custom))
+    }
+}
+
+impl TypedTransaction {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        json::parse(&content).map_err(Error::in_file(path))
+    }
+
+    // chainId と from が設定と署名する鍵に合うか確認する
+    pub fn check(&self, chain_id: u64, signer: &H160) -> Result<()> {
+        if let Some(requested) = self.chain_id.filter(|id| *id != U256::from(chain_id)) {
+            return Err(Error::InvalidArgument(format!(
+                "chainId {requested} does not match CHAIN_ID {chain_id}"
+            )));
+        }
+        if let Some(from) = self.from.filter(|from| from != signer) {
+            return Err(Error::InvalidArgument(format!(
+                "from {from:?} is not the address of the signing key ({signer:?})"
+            )));
+        }
+        Ok(())
+    }
+
+    // 手数料を省略した場合は MAX_FEE_PER_GAS・MAX_PRIORITY_FEE_PER_GAS を使う
+    pub fn message(&self, config: &Config, nonce: U256) -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: config.chain_id,
+            nonce,
+            max_priority_fee_per_gas: self
+                .max_priority_fee_per_gas
+                .unwrap_or(config.max_priority_fee_per_gas),
+            max_fee_per_gas: self.max_fee_per_gas.unwrap_or(config.max_fee_per_gas),
+            gas_limit: self.gas_limit,
+            action: self.action,
+            value: self.value,
+            input: self.input.clone(),
+            access_list: self.access_list.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> Config {
+        serde_json::from_value(json!({
+            "chain_id": 11155111,
+            "max_fee_per_gas": "50000000000",
+            "max_priority_fee_per_gas": "2000000000",
+        }))
+        .unwrap()
+    }
+
+    fn parse(content: &str) -> std::result::Result<TypedTransaction, String> {
+        json::parse(content).map_err(|error: Error| error.to_string())
+    }
+
+    #[test]
+    fn test_eip1559() {
+        // ethers-rs 2.0 の serde_json::to_string(&TypedTransaction::Eip1559(..)) の出力
+        let tx = parse(
+            r#"{"type":"0x02","from":"0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df","to":"0x1c7d4b196cb0c7b01d743fbc6116a902379c7238","gas":"0xea60","value":"0x0","data":"0xa9059cbb","nonce":"0x7","accessList":[{"address":"0x1c7d4b196cb0c7b01d743fbc6116a902379c7238","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000001"]}],"maxPriorityFeePerGas":"0x3b9aca00","maxFeePerGas":"0x6fc23ac00","chainId":"0xaa36a7"}"#,
+        )
+        .unwrap();
+        assert!(!tx.legacy);
+        assert_eq!(tx.nonce, Some(U256::from(7)));
+        assert_eq!(tx.chain_id, Some(U256::from(11155111)));
+
+        let message = tx.message(&config(), U256::from(7));
+        assert_eq!(message.gas_limit, U256::from(60000));
+        assert_eq!(message.max_fee_per_gas, U256::from(30_000_000_000u64));
+        assert_eq!(message.max_priority_fee_per_gas, U256::from(1_000_000_000));
+        assert_eq!(message.input, [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(message.access_list.len(), 1);
+        assert_eq!(
+            message.access_list[0].storage_keys,
+            [H256::from_low_u64_be(1)]
+        );
+    }
+
+    #[test]
+    fn test_legacy_and_defaults() {
+        let tx = parse(r#"{"type":"0x00","gas":"0x5208","gasPrice":"0x3b9aca00"}"#).unwrap();
+        assert!(tx.legacy);
+        assert_eq!(tx.action, TransactionAction::Create);
+        assert_eq!(tx.nonce, None);
+        let message = tx.message(&config(), U256::one());
+        assert_eq!(message.max_fee_per_gas, U256::from(1_000_000_000));
+        assert_eq!(message.value, U256::zero());
+
+        // 手数料を省略した場合は設定値
+        let tx = parse(
+            r#"{"type":"0x2","to":"0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df","gas":"0x5208"}"#,
+        )
+        .unwrap();
+        let message = tx.message(&config(), U256::zero());
+        assert_eq!(message.max_fee_per_gas, U256::from(50_000_000_000u64));
+        assert_eq!(message.max_priority_fee_per_gas, U256::from(2_000_000_000));
+    }
+
+    #[test]
+    fn test_rejected() {
+        for (content, expected) in [
+            (r#"{"type":"0x01","gas":"0x5208"}"#, "EIP-2930"),
+            (r#"{"type":"0x02"}"#, "gas: missing"),
+            (
+                r#"{"type":"0x02","gas":"0x5208","gasPrice":"0x1"}"#,
+                "gasPrice",
+            ),
+            (
+                r#"{"type":"0x00","gas":"0x5208","maxFeePerGas":"0x1"}"#,
+                "maxFeePerGas",
+            ),
+            (
+                r#"{"type":"0x02","gas":"0x5208","gasLimit":"0x1"}"#,
+                "gasLimit",
+            ),
+            (r#"{"type":"0x02","gas":"0x5208","to":"vitalik.eth"}"#, "to"),
+            (r#"{"gas":"0x5208"}"#, "type"),
+        ] {
+            let error = parse(content).unwrap_err();
+            assert!(error.contains(expected), "{content}: {error}");
+        }
+
+        let signer: H160 = "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df"
+            .parse()
+            .unwrap();
+        let tx = parse(r#"{"type":"0x02","gas":"0x5208","chainId":"0x1"}"#).unwrap();
+        assert!(tx.check(11155111, &signer).is_err());
+        assert!(tx.check(1, &signer).is_ok());
+        let tx = parse(
+            r#"{"type":"0x02","gas":"0x5208","from":"0x0000000000000000000000000000000000000001"}"#,
+        )
+        .unwrap();
+        assert!(tx.check(1, &signer).is_err());
+    }
+}
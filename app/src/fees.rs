@@ -0,0 +1,340 @@
+use crate::{
+    Result, chains, config::Config, error::Error, i18n::t, rpc::RpcClient, transaction::TxType,
+    units,
+};
+use clap::ValueEnum;
+use ethereum_types::U256;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+const GWEI_DECIMALS: u8 = 9;
+
+// Polygon PoS のバリデーターが受け付ける優先手数料の下限 (25 gwei)
+// これより低いトランザクションは mempool に入ってもブロックに取り込まれない
+pub const POLYGON_MIN_PRIORITY_FEE: u64 = 25_000_000_000;
+
+// Gnosis Chain のバリデーター (Nethermind) が受け付ける優先手数料の下限 (1 gwei)
+// base fee が低く eth_feeHistory の優先手数料も 0 に近いため、そのままでは取り込まれない
+pub const GNOSIS_MIN_PRIORITY_FEE: u64 = 1_000_000_000;
+
+// BNB Smart Chain のバリデーターが受け付けるガス価格の下限 (0.1 gwei)
+// BSC は base fee が 0 で Legacy のガス価格がそのまま優先手数料になる
+pub const BSC_MIN_GAS_PRICE: u64 = 100_000_000;
+
+// eth_feeHistory でさかのぼるブロック数
+const FEE_HISTORY_BLOCKS: u64 = 20;
+
+// 手数料の目安 (--speed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Speed {
+    Safe,
+    Standard,
+    Fast,
+}
+
+impl Speed {
+    // Polygon の gas station のレスポンスのキー
+    fn gas_station_key(self) -> &'static str {
+        match self {
+            Speed::Safe => "safeLow",
+            Speed::Standard => "standard",
+            Speed::Fast => "fast",
+        }
+    }
+
+    // eth_feeHistory で集める優先手数料のパーセンタイル
+    fn percentile(self) -> u64 {
+        match self {
+            Speed::Safe => 25,
+            Speed::Standard => 50,
+            Speed::Fast => 75,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+// Polygon の公式の gas station
+pub fn polygon_gas_station_url(chain_id: u64) -> Option<&'static str> {
+    let url = match chain_id {
+        137 => "https://gasstation.polygon.technology/v2",
+        80002 => "https://gasstation.polygon.technology/amoy",
+        _ => return None,
+    };
+    Some(url)
+}
+
+// speed に合った手数料を取得する
+// Polygon は gas station から、それ以外は RPC_URL の eth_feeHistory から求める
+pub fn suggest(config: &Config, speed: Speed) -> Result<Fees> {
+    let gas_station_url = if chains::is_polygon(config.chain_id) {
+        config
+            .polygon_gas_station_url
+            .as_deref()
+            .or(polygon_gas_station_url(config.chain_id))
+    } else {
+        None
+    };
+    let fees = match gas_station_url {
+        Some(url) => from_gas_station(url, speed)?,
+        None => from_fee_history(&config.get_rpc_client()?, speed)?,
+    };
+    Ok(apply_minimum(config.chain_id, fees))
+}
+
+// Polygon の gas station (v2) の safeLow / standard / fast の gwei の値
+pub fn from_gas_station(url: &str, speed: Speed) -> Result<Fees> {
+    let response: Value = ureq::get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(Error::at_endpoint(url))?;
+
+    let key = speed.gas_station_key();
+    let gwei = |name: &str| match &response[key][name] {
+        Value::Number(number) => parse_gwei(&number.to_string()),
+        _ => Err(Error::InvalidFeeData(format!(
+            "gas station response has no numeric {key}.{name}"
+        ))),
+    };
+    Ok(Fees {
+        max_fee_per_gas: gwei("maxFee")?,
+        max_priority_fee_per_gas: gwei("maxPriorityFee")?,
+    })
+}
+
+// gas station は gwei を小数で返すため、1 wei 未満は切り捨てる
+fn parse_gwei(text: &str) -> Result<U256> {
+    let text = match text.split_once('.') {
+        Some((integer, fraction)) => format!(
+            "{integer}.{}",
+            fraction
+                .chars()
+                .take(GWEI_DECIMALS as usize)
+                .collect::<String>()
+        ),
+        None => text.to_string(),
+    };
+    units::parse_units(&text, GWEI_DECIMALS)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeHistory {
+    base_fee_per_gas: Vec<U256>,
+    #[serde(default)]
+    reward: Vec<Vec<U256>>,
+}
+
+// 直近のブロックの優先手数料の中央値と、次のブロックの base fee の2倍
+// (base fee が6ブロック続けて上限まで上がっても取り込まれる)
+pub fn from_fee_history(client: &RpcClient, speed: Speed) -> Result<Fees> {
+    let history: FeeHistory = client.request(
+        "eth_feeHistory",
+        json!([
+            format!("{FEE_HISTORY_BLOCKS:#x}"),
+            "latest",
+            [speed.percentile()]
+        ]),
+    )?;
+
+    // 最後の要素は次のブロックの base fee
+    let base_fee = history.base_fee_per_gas.last().copied().ok_or_else(|| {
+        Error::InvalidFeeData("eth_feeHistory returned no baseFeePerGas".to_string())
+    })?;
+    let mut rewards: Vec<U256> = history
+        .reward
+        .iter()
+        .filter_map(|rewards| rewards.first().copied())
+        .collect();
+    rewards.sort();
+    let priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+    Ok(Fees {
+        max_fee_per_gas: base_fee
+            .saturating_mul(U256::from(2))
+            .saturating_add(priority_fee),
+        max_priority_fee_per_gas: priority_fee,
+    })
+}
+
+// チェーンの下限を満たすように優先手数料を引き上げる (max fee も優先手数料以上にする)
+pub fn apply_minimum(chain_id: u64, fees: Fees) -> Fees {
+    let Some(minimum) = minimum_priority_fee(chain_id) else {
+        return fees;
+    };
+    let max_priority_fee_per_gas = fees.max_priority_fee_per_gas.max(minimum);
+    Fees {
+        max_fee_per_gas: fees.max_fee_per_gas.max(max_priority_fee_per_gas),
+        max_priority_fee_per_gas,
+    }
+}
+
+// チェーンが受け付ける優先手数料 (Legacy ではガス価格) の下限
+pub fn minimum_priority_fee(chain_id: u64) -> Option<U256> {
+    let minimum = match chain_id {
+        137 | 80002 => POLYGON_MIN_PRIORITY_FEE,
+        100 | 10200 => GNOSIS_MIN_PRIORITY_FEE,
+        56 | 97 => BSC_MIN_GAS_PRICE,
+        _ => return None,
+    };
+    Some(U256::from(minimum))
+}
+
+// 設定の手数料がチェーンの下限を下回っていれば警告する
+// Legacy は MAX_FEE_PER_GAS がガス価格になるため、そちらを確認する
+pub fn warning(config: &Config) -> Option<String> {
+    let minimum = minimum_priority_fee(config.chain_id)?;
+    let (name, fee) = match config.tx_type {
+        TxType::Legacy => ("MAX_FEE_PER_GAS", config.max_fee_per_gas),
+        _ => ("MAX_PRIORITY_FEE_PER_GAS", config.max_priority_fee_per_gas),
+    };
+    (fee < minimum).then(|| {
+        format!(
+            "{name} {} gwei is below the {} gwei minimum on chain {}; the transaction will not be mined (try --speed)",
+            units::format_units(fee, GWEI_DECIMALS),
+            units::format_units(minimum, GWEI_DECIMALS),
+            config.chain_id
+        )
+    })
+}
+
+// 確認用の表示
+pub fn summary(speed: Speed, fees: &Fees) -> String {
+    t!(
+        "fees.summary",
+        speed = speed
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default(),
+        max_fee = units::format_units(fees.max_fee_per_gas, GWEI_DECIMALS),
+        priority_fee = units::format_units(fees.max_priority_fee_per_gas, GWEI_DECIMALS)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_from_gas_station() {
+        let body = json!({
+            "safeLow": { "maxPriorityFee": 26.1, "maxFee": 40.5 },
+            "standard": { "maxPriorityFee": 30.123456789123, "maxFee": 45 },
+            "fast": { "maxPriorityFee": 35, "maxFee": 50 },
+            "estimatedBaseFee": 14.5,
+        })
+        .to_string();
+        let server = MockServer::start(vec![(200, body.clone()), (200, body)]);
+
+        let fees = from_gas_station(&server.url, Speed::Standard).unwrap();
+        assert_eq!(fees.max_fee_per_gas, U256::from(45 * GWEI));
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(30_123_456_789u64));
+        let fees = from_gas_station(&server.url, Speed::Safe).unwrap();
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(26_100_000_000u64));
+        assert_eq!(
+            summary(Speed::Safe, &fees),
+            "Fees (safe): max fee per gas 40.5 gwei, max priority fee per gas 26.1 gwei"
+        );
+
+        let server = MockServer::start(vec![(200, "{}".to_string())]);
+        assert!(matches!(
+            from_gas_station(&server.url, Speed::Fast),
+            Err(Error::InvalidFeeData(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_fee_history() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00", "0x77359400"],
+            "reward": [["0x5f5e100"], ["0x1dcd6500"], ["0x3b9aca00"]],
+        }))]);
+        let fees = from_fee_history(&RpcClient::new(&server.url), Speed::Fast).unwrap();
+        // 中央値 0.5 gwei + 次のブロックの base fee 2 gwei の2倍
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(GWEI / 2));
+        assert_eq!(fees.max_fee_per_gas, U256::from(4 * GWEI + GWEI / 2));
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["method"], "eth_feeHistory");
+        assert_eq!(requests[0]["params"], json!(["0x14", "latest", [75]]));
+    }
+
+    #[test]
+    fn test_minimum() {
+        let fees = Fees {
+            max_fee_per_gas: U256::from(20 * GWEI),
+            max_priority_fee_per_gas: U256::from(GWEI),
+        };
+        assert_eq!(apply_minimum(1, fees), fees);
+        assert_eq!(
+            apply_minimum(137, fees),
+            Fees {
+                max_fee_per_gas: U256::from(25 * GWEI),
+                max_priority_fee_per_gas: U256::from(25 * GWEI),
+            }
+        );
+        assert_eq!(apply_minimum(100, fees), fees);
+
+        // Gnosis と BSC は eth_feeHistory の手数料が下限を下回りやすい
+        let low = Fees {
+            max_fee_per_gas: U256::from(7),
+            max_priority_fee_per_gas: U256::zero(),
+        };
+        assert_eq!(
+            apply_minimum(100, low),
+            Fees {
+                max_fee_per_gas: U256::from(GWEI),
+                max_priority_fee_per_gas: U256::from(GWEI),
+            }
+        );
+        assert_eq!(
+            apply_minimum(56, low).max_fee_per_gas,
+            U256::from(BSC_MIN_GAS_PRICE)
+        );
+    }
+
+    #[test]
+    fn test_warning() {
+        let config = |chain_id: u64, max_fee: u64, priority_fee: u64| -> Config {
+            serde_json::from_value(json!({
+                "chain_id": chain_id,
+                "max_fee_per_gas": max_fee.to_string(),
+                "max_priority_fee_per_gas": priority_fee.to_string(),
+                "private_key": "0x01",
+            }))
+            .unwrap()
+        };
+        assert!(
+            warning(&config(137, 50 * GWEI, GWEI))
+                .unwrap()
+                .contains("25 gwei minimum")
+        );
+        assert_eq!(warning(&config(137, 50 * GWEI, 30 * GWEI)), None);
+        assert_eq!(warning(&config(1, 50 * GWEI, GWEI)), None);
+        assert!(
+            warning(&config(100, 2 * GWEI, 0))
+                .unwrap()
+                .starts_with("MAX_PRIORITY_FEE_PER_GAS 0 gwei is below the 1 gwei minimum")
+        );
+
+        // Legacy ではガス価格 (MAX_FEE_PER_GAS) を確認する
+        let mut bsc = config(56, GWEI / 20, 0);
+        assert!(warning(&bsc).unwrap().contains("MAX_PRIORITY_FEE_PER_GAS"));
+        bsc.tx_type = TxType::Legacy;
+        assert!(
+            warning(&bsc)
+                .unwrap()
+                .starts_with("MAX_FEE_PER_GAS 0.05 gwei is below the 0.1 gwei minimum on chain 56")
+        );
+        bsc.max_fee_per_gas = U256::from(GWEI);
+        assert_eq!(warning(&bsc), None);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{
+    Result,
+    error::Error,
+    transaction::TxType,
+    ur::{self, Cbor},
+};
+use clap::ValueEnum;
+use ethereum_types::{H256, U256};
+use serde_json::{Value, json};
+use std::{fmt, str::FromStr};
+
+// MetaMask などと同じ最初のアカウント
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+// eth-sign-request の data-type (ur-registry-eth)
+const DATA_TYPE_TRANSACTION: u64 = 1;
+const DATA_TYPE_TYPED_TRANSACTION: u64 = 4;
+// CBOR のタグ (uuid、crypto-keypath)
+const TAG_UUID: u64 = 37;
+const TAG_KEYPATH: u64 = 304;
+// eth-sign-request の origin (デバイスの確認画面に出る)
+const ORIGIN: &str = "ethereum-transaction-signer";
+
+// 署名前のトランザクションを渡すハードウェアウォレット
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Device {
+    // hw-app-eth の signTransaction に渡す rawTxHex
+    Ledger,
+    // eth-sign-request の UR (QR コードにしてデバイスで読み取る)
+    Keystone,
+}
+
+impl Device {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ledger => "ledger",
+            Self::Keystone => "keystone",
+        }
+    }
+}
+
+// BIP-32 の導出パス (44'/60'/0'/0/0、' か h が付いたものは hardened)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<(u32, bool)>);
+
+impl FromStr for DerivationPath {
+    type Err = String;
+
+    fn from_str(path: &str) -> std::result::Result<Self, String> {
+        let components = path.strip_prefix("m/").unwrap_or(path);
+        if components.is_empty() {
+            return Err("derivation path is empty".to_string());
+        }
+        components
+            .split('/')
+            .map(|component| {
+                let (index, hardened) = match component
+                    .strip_suffix('\'')
+                    .or_else(|| component.strip_suffix('h'))
+                {
+                    Some(index) => (index, true),
+                    None => (component, false),
+                };
+                match index.parse::<u32>() {
+                    Ok(index) if index < 0x8000_0000 => Ok((index, hardened)),
+                    _ => Err(format!("invalid derivation path component: {component}")),
+                }
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Self)
+    }
+}
+
+// Ledger の形式 (m/ を付けない)
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components: Vec<String> = self
+            .0
+            .iter()
+            .map(|(index, hardened)| format!("{index}{}", if *hardened { "'" } else { "" }))
+            .collect();
+        write!(f, "{}", components.join("/"))
+    }
+}
+
+// マスター鍵のフィンガープリント (Keystone がソフトウェアウォレットとの接続で表示する8桁の16進数)
+pub fn parse_master_fingerprint(value: &str) -> std::result::Result<u32, String> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    match digits.len() {
+        8 => u32::from_str_radix(digits, 16).map_err(|error| error.to_string()),
+        _ => Err(format!("master fingerprint must be 8 hex digits: {value}")),
+    }
+}
+
+// 署名用ハッシュから決まる UUID (v4 の形式)
+// 同じトランザクションは同じ ID になり、eth-signature の request-id と照合できる
+pub fn request_id(sighash: &H256) -> [u8; 16] {
+    let mut id = [0u8; 16];
+    id.copy_from_slice(&sighash.as_bytes()[..16]);
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+    id
+}
+
+fn format_uuid(id: &[u8; 16]) -> String {
+    let hex = hex::encode(id);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+// prepare の出力に加えるハードウェアウォレット向けのペイロード
+pub fn export(
+    device: Device,
+    tx_type: &TxType,
+    chain_id: u64,
+    unsigned: &[u8],
+    sighash: &H256,
+    path: &DerivationPath,
+    master_fingerprint: Option<u32>,
+) -> Result<Value> {
+    match device {
+        Device::Ledger => Ok(json!({
+            "derivationPath": path.to_string(),
+            "rawTxHex": hex::encode(unsigned),
+        })),
+        Device::Keystone => {
+            let data_type = match tx_type {
+                TxType::Eip1559 => DATA_TYPE_TYPED_TRANSACTION,
+                TxType::Legacy => DATA_TYPE_TRANSACTION,
+                TxType::Zksync(_) | TxType::Celo(_) => {
+                    return Err(Error::InvalidArgument(
+                        "keystone supports Type 2 and legacy transactions only".to_string(),
+                    ));
+                }
+            };
+            let id = request_id(sighash);
+            let request = Cbor::Map(vec![
+                (1, Cbor::Tag(TAG_UUID, Box::new(Cbor::Bytes(id.to_vec())))),
+                (2, Cbor::Bytes(unsigned.to_vec())),
+                (3, Cbor::Unsigned(data_type)),
+                (4, Cbor::Unsigned(chain_id)),
+                (
+                    5,
+                    Cbor::Tag(TAG_KEYPATH, Box::new(keypath(path, master_fingerprint))),
+                ),
+                (7, Cbor::Text(ORIGIN.to_string())),
+            ]);
+            Ok(json!({
+                "requestId": format_uuid(&id),
+                "derivationPath": format!("m/{path}"),
+                "ur": ur::encode("eth-sign-request", &request)?,
+            }))
+        }
+    }
+}
+
+// Keystone などが QR コードで返す署名 (ur:eth-signature)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthSignature {
+    pub request_id: Option<[u8; 16]>,
+    pub r: U256,
+    pub s: U256,
+    // 1バイトとは限らない (EIP-155 の chain_id * 2 + 35/36)
+    pub v: u64,
+}
+
+impl EthSignature {
+    // eth-signature: {1: request-id (uuid), 2: r (32バイト) + s (32バイト) + v, 3: origin}
+    pub fn parse(ur: &str) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidArgument(format!("eth-signature: {message}"));
+        let value = ur::decode(ur, "eth-signature")?;
+
+        let request_id = match value.get(1) {
+            None => None,
+            Some(Cbor::Tag(TAG_UUID, id)) => match id.as_ref() {
+                Cbor::Bytes(id) => Some(
+                    <[u8; 16]>::try_from(id.as_slice())
+                        .map_err(|_| invalid("request-id must be 16 bytes"))?,
+                ),
+                _ => return Err(invalid("request-id must be a uuid")),
+            },
+            Some(_) => return Err(invalid("request-id must be a uuid")),
+        };
+        let Some(Cbor::Bytes(signature)) = value.get(2) else {
+            return Err(invalid("signature is missing"));
+        };
+        if !(65..=72).contains(&signature.len()) {
+            return Err(invalid("signature must be r (32 bytes) + s (32 bytes) + v"));
+        }
+
+        Ok(Self {
+            request_id,
+            r: U256::from_big_endian(&signature[..32]),
+            s: U256::from_big_endian(&signature[32..64]),
+            v: signature[64..]
+                .iter()
+                .fold(0, |v, byte| (v << 8) | *byte as u64),
+        })
+    }
+
+    // prepare --device keystone の要求への署名か確かめる (request-id がなければ確かめない)
+    pub fn check_request(&self, sighash: &H256) -> Result<()> {
+        let expected = request_id(sighash);
+        match self.request_id {
+            Some(id) if id != expected => Err(Error::InvalidArgument(format!(
+                "eth-signature: request-id {} does not match this transaction ({})",
+                format_uuid(&id),
+                format_uuid(&expected)
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+// crypto-keypath (BCR-2020-007): {1: [index, hardened, ...], 2: source-fingerprint}
+fn keypath(path: &DerivationPath, master_fingerprint: Option<u32>) -> Cbor {
+    let components = path
+        .0
+        .iter()
+        .flat_map(|(index, hardened)| [Cbor::Unsigned(*index as u64), Cbor::Bool(*hardened)])
+        .collect();
+    let mut entries = vec![(1, Cbor::Array(components))];
+    if let Some(fingerprint) = master_fingerprint {
+        entries.push((2, Cbor::Unsigned(fingerprint as u64)));
+    }
+    Cbor::Map(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation_path() {
+        let path: DerivationPath = DEFAULT_DERIVATION_PATH.parse().unwrap();
+        assert_eq!(path.to_string(), "44'/60'/0'/0/0");
+        assert_eq!(
+            "44h/60h/1h/0/7"
+                .parse::<DerivationPath>()
+                .unwrap()
+                .to_string(),
+            "44'/60'/1'/0/7"
+        );
+        for invalid in ["", "m/", "44'/x", "2147483648"] {
+            assert!(invalid.parse::<DerivationPath>().is_err(), "{invalid}");
+        }
+        assert_eq!(parse_master_fingerprint("0x12ab34cd"), Ok(0x12ab34cd));
+        assert!(parse_master_fingerprint("12ab").is_err());
+    }
+
+    fn eth_signature(request_id: Option<[u8; 16]>, signature: Vec<u8>) -> String {
+        let mut entries = Vec::new();
+        if let Some(id) = request_id {
+            entries.push((1, Cbor::Tag(37, Box::new(Cbor::Bytes(id.to_vec())))));
+        }
+        entries.push((2, Cbor::Bytes(signature)));
+        entries.push((3, Cbor::Text("Keystone".to_string())));
+        ur::encode("eth-signature", &Cbor::Map(entries)).unwrap()
+    }
+
+    #[test]
+    fn test_eth_signature() {
+        let sighash = H256::repeat_byte(0xab);
+        let mut signature = vec![0x11; 32];
+        signature.extend([0x22; 32]);
+        signature.push(0x01);
+
+        let parsed = EthSignature::parse(&eth_signature(
+            Some(request_id(&sighash)),
+            signature.clone(),
+        ))
+        .unwrap();
+        assert_eq!(parsed.r, U256::from_big_endian(&[0x11; 32]));
+        assert_eq!(parsed.s, U256::from_big_endian(&[0x22; 32]));
+        assert_eq!(parsed.v, 1);
+        parsed.check_request(&sighash).unwrap();
+        let error = parsed
+            .check_request(&H256::repeat_byte(0xcd))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("abababab-abab-4bab-abab-abababababab"),
+            "{error}"
+        );
+
+        // request-id がない署名と、2バイトの v (EIP-155 で chain_id が大きい場合)
+        let mut long_v = signature[..64].to_vec();
+        long_v.extend([0x01, 0x5b]);
+        let parsed = EthSignature::parse(&eth_signature(None, long_v)).unwrap();
+        assert_eq!(parsed.v, 0x015b);
+        parsed.check_request(&H256::repeat_byte(0xcd)).unwrap();
+
+        assert!(EthSignature::parse(&eth_signature(None, vec![0x11; 64])).is_err());
+        let request = export(
+            Device::Keystone,
+            &TxType::Eip1559,
+            1,
+            &[0x02],
+            &sighash,
+            &DEFAULT_DERIVATION_PATH.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert!(EthSignature::parse(request["ur"].as_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let unsigned = hex::decode("02c9018080808094000080c0").unwrap();
+        let sighash = H256::repeat_byte(0xab);
+        let path: DerivationPath = DEFAULT_DERIVATION_PATH.parse().unwrap();
+
+        let ledger = export(
+            Device::Ledger,
+            &TxType::Eip1559,
+            1,
+            &unsigned,
+            &sighash,
+            &path,
+            None,
+        )
+        .unwrap();
+        assert_eq!(ledger["derivationPath"], "44'/60'/0'/0/0");
+        assert_eq!(ledger["rawTxHex"], "02c9018080808094000080c0");
+
+        let keystone = export(
+            Device::Keystone,
+            &TxType::Eip1559,
+            1,
+            &unsigned,
+            &sighash,
+            &path,
+            Some(0x12ab34cd),
+        )
+        .unwrap();
+        assert_eq!(
+            keystone["requestId"],
+            "abababab-abab-4bab-abab-abababababab"
+        );
+        assert_eq!(keystone["derivationPath"], "m/44'/60'/0'/0/0");
+        // a6 01 d825 50 <uuid> 02 4c <unsigned> 03 04 04 01 05 d90130 a2 01 8a [44' 60' 0' 0 0] 02 1a12ab34cd 07 781b <origin>
+        assert_eq!(
+            keystone["ur"],
+            "ur:eth-sign-request/oladtpdagdpypypypypypygrpypypypypypypypypyaogsaosoadlalalalamwaeaelartaxaaaaadahtaaddyoeadlecsdwykcsfnykaeykaewkaewkaocybgpyeesnatkscwihjyisihjpihkpjndpjyjphsjtjkhsiajyinjljtdpjkiniojtihjpfhtibkfr"
+        );
+
+        assert!(
+            export(
+                Device::Keystone,
+                &TxType::Celo(Default::default()),
+                42220,
+                &unsigned,
+                &sighash,
+                &path,
+                None
+            )
+            .is_err()
+        );
+    }
+}
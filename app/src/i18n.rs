@@ -0,0 +1,606 @@
+use clap::ValueEnum;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+// CLI のメッセージ (エラー・確認用の表示・確認の入力) の言語
+// JSON や raw トランザクションなど、ほかのプログラムが読む出力は変えない
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Ja,
+}
+
+// プロセス全体の言語 (main で一度だけ決める、テストでは英語のまま)
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set(lang: Lang) {
+    CURRENT.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn current() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Lang::Ja,
+        _ => Lang::En,
+    }
+}
+
+// LC_ALL・LC_MESSAGES・LANG のうち最初に設定されているもの (POSIX と同じ優先順位)
+// "ja_JP.UTF-8" や "ja" なら日本語、それ以外 (C・POSIX や未設定も) は英語
+pub fn detect(var: impl Fn(&str) -> Option<String>) -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(var)
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale)
+            if locale == "ja" || locale.starts_with("ja_") || locale.starts_with("ja.") =>
+        {
+            Lang::Ja
+        }
+        _ => Lang::En,
+    }
+}
+
+// メッセージの ID と文言 ({name} は引数で置き換える)
+// 英語と日本語で ID と引数をそろえる (テストで確かめる)
+const EN: &[(&str, &str)] = &[
+    // main
+    ("main.error", "Error: {error}"),
+    ("main.caused_by", "Caused by:"),
+    ("main.warning", "Warning: {warning}"),
+    ("main.strict", "{warning} (rejected by --strict)"),
+    // エラーの文脈
+    ("context.file", "Failed to load {path}"),
+    ("context.env", "Invalid environment variable {name}"),
+    ("context.endpoint", "Request to {url} failed"),
+    ("context.field", "Invalid field '{field}'"),
+    // エラー
+    (
+        "error.invalid_json",
+        "{message} (line {line}, column {column})",
+    ),
+    (
+        "error.invalid_private_key_length",
+        "Invalid private key length (expected: 32, input: {length}).",
+    ),
+    ("error.missing_private_key", "PRIVATE_KEY is not set."),
+    ("error.invalid_key_file", "Invalid key file: {reason}"),
+    (
+        "error.key_decryption_failed",
+        "Failed to decrypt the key file (wrong password?).",
+    ),
+    (
+        "error.key_locked",
+        "The signing key is locked, unlock it first.",
+    ),
+    (
+        "error.invalid_environment",
+        "Invalid environment: {problems}",
+    ),
+    ("error.invalid_chain_id", "Invalid chain id: {reason}"),
+    ("error.invalid_argument", "Invalid argument: {reason}"),
+    ("error.invalid_amount", "Invalid amount: {reason}"),
+    ("error.invalid_abi_data", "Invalid ABI data: {reason}"),
+    (
+        "error.invalid_tls_config",
+        "Invalid TLS configuration: {reason}",
+    ),
+    ("error.invalid_fee_data", "Invalid fee data: {reason}"),
+    ("error.invalid_price", "Invalid price data: {reason}"),
+    ("error.invalid_typed_data", "Invalid typed data: {reason}"),
+    ("error.invalid_policy", "Invalid policy: {reason}"),
+    ("error.policy_violation", "Policy violation: {reason}"),
+    (
+        "error.audit_log_tampered",
+        "Audit log verification failed: {reason}",
+    ),
+    (
+        "error.raw_hash_signing_not_confirmed",
+        "Refusing to sign a raw digest: it may authorize any transaction or permit. Pass --i-know-what-im-doing to proceed.",
+    ),
+    (
+        "error.raw_hash_signing_with_policy",
+        "Refusing to sign a raw digest while POLICY_PATH is set: the policy cannot check what the digest authorizes.",
+    ),
+    (
+        "error.unlimited_approval_not_confirmed",
+        "Refusing to sign an unlimited approval: the spender could move every token you hold. Pass --allow-unlimited to proceed.",
+    ),
+    (
+        "error.mainnet_not_confirmed",
+        "Refusing to use Ethereum mainnet (CHAIN_ID 1) without confirmation. Pass --mainnet to proceed.",
+    ),
+    (
+        "error.value_not_confirmed",
+        "Refusing to sign a value of {value} above CONFIRM_ABOVE ({threshold}) without confirmation. Pass --yes to proceed.",
+    ),
+    (
+        "error.unexpected_deployment_address",
+        "Predicted deployment address {actual} does not match the expected {expected}.",
+    ),
+    (
+        "error.unexpected_signer",
+        "The signature recovers to {actual}, not the expected {expected}.",
+    ),
+    (
+        "error.unverified_contract",
+        "Refusing to sign an approval to an unverified contract: {reason}",
+    ),
+    (
+        "error.flagged_address",
+        "Refusing to sign a transaction to {address}, which is on the denylist ({label}). Pass --allow-flagged to proceed.",
+    ),
+    (
+        "error.simulation_failed",
+        "Simulation failed: {reason}. Refusing to sign; sign without --simulate to proceed anyway.",
+    ),
+    ("error.missing_rpc_url", "RPC_URL is not set."),
+    (
+        "error.missing_nonce",
+        "Nonce is not specified: pass it explicitly or set NONCE_STORE_PATH or RPC_URL.",
+    ),
+    (
+        "error.nondeterministic",
+        "--deterministic does not use {source}, which can change between runs: pass the value explicitly.",
+    ),
+    ("error.invalid_nonce_store", "Invalid nonce store {reason}"),
+    ("error.invalid_journal", "Invalid journal: {reason}"),
+    ("error.invalid_state_file", "Invalid state file {reason}"),
+    (
+        "error.state_decryption_failed",
+        "Failed to decrypt {path} (wrong STATE_PASSPHRASE or STATE_KEY?).",
+    ),
+    (
+        "error.duplicate_transaction",
+        "Refusing to sign a duplicate of journaled transaction {hash} (same nonce or same payload still pending). Pass --allow-duplicate (or set ALLOW_DUPLICATE=true) to proceed.",
+    ),
+    (
+        "error.broadcast_failed",
+        "{failed} of {total} transactions were not broadcast.",
+    ),
+    ("error.rpc", "RPC error ({code}): {message}"),
+    ("error.plugin", "Signer plugin failed: {reason}"),
+    (
+        "error.unknown_weth_address",
+        "WETH address is unknown for chain id {chain_id}, please specify --weth.",
+    ),
+    (
+        "error.unknown_safe_service_url",
+        "Safe Transaction Service URL is unknown for chain id {chain_id}, please specify --service-url.",
+    ),
+    // 確認の入力
+    ("confirm.type", "{prompt} Type '{phrase}' to continue: "),
+    (
+        "confirm.mainnet",
+        "CHAIN_ID is 1: this will use Ethereum mainnet.",
+    ),
+    (
+        "confirm.value",
+        "Transaction value {amount} is above CONFIRM_ABOVE.",
+    ),
+    // 確認用の表示
+    ("cost.gas_limit", "Gas limit: {gas_limit}"),
+    ("cost.max_fee_per_gas", "Max fee per gas: {gwei} gwei"),
+    ("cost.max_execution_fee", "Max execution fee: {fee}"),
+    ("cost.l1_data_fee", "L1 data fee: {fee}"),
+    ("cost.max_total_fee", "Max total fee: {fee}"),
+    (
+        "cost.max_total_fee_fiat",
+        "Max total fee in {currency}: {fee} at {price}",
+    ),
+    (
+        "cost.l1_data_fee_not_included",
+        "L1 data fee is not included: set RPC_URL to query the GasPriceOracle",
+    ),
+    (
+        "fees.summary",
+        "Fees ({speed}): max fee per gas {max_fee} gwei, max priority fee per gas {priority_fee} gwei",
+    ),
+    (
+        "fiat.stale",
+        "could not fetch a current price, using one cached {age} seconds ago",
+    ),
+    (
+        "fiat.unavailable",
+        "fees are not converted to {currency}: {error}",
+    ),
+    (
+        "offline.signing",
+        "Signing nonce {nonce} on {chain} ({chain_id}) with a max fee of {fee}",
+    ),
+    ("simulation.summary", "Simulation: {summary}"),
+    (
+        "simulation.no_changes",
+        "no balance changes besides the fee",
+    ),
+    ("simulation.send", "you will send {sent}"),
+    ("simulation.receive", "you will receive {received}"),
+    (
+        "simulation.send_and_receive",
+        "you will send {sent} and receive {received}",
+    ),
+    (
+        "denylist.allowed",
+        "WARNING: {address} is on the denylist ({label}), signing because of --allow-flagged.",
+    ),
+    (
+        "report.transactions",
+        "Transactions: {total} (confirmed {confirmed}, failed {failed}, pending {pending}, dropped or replaced {not_included})",
+    ),
+    ("report.gas_used", "Gas used: {gas_used}"),
+    ("report.fees", "Fees: {fees}"),
+    ("report.fees_fiat", "Fees in {currency}: {fees} at {price}"),
+    ("report.l1_data_fees", "L1 data fees: {fees}"),
+    (
+        "report.average_gas_price",
+        "Average effective gas price: {gwei} gwei",
+    ),
+    (
+        "report.other_chains",
+        "Skipped {count} transactions on other chains than {chain_id} (RPC_URL)",
+    ),
+    ("report.wrote", "Wrote {count} transactions to {path}"),
+];
+
+const JA: &[(&str, &str)] = &[
+    // main
+    ("main.error", "エラー: {error}"),
+    ("main.caused_by", "原因:"),
+    ("main.warning", "警告: {warning}"),
+    ("main.strict", "{warning} (--strict のため拒否)"),
+    // エラーの文脈
+    ("context.file", "{path} を読み込めません"),
+    ("context.env", "環境変数 {name} が不正です"),
+    ("context.endpoint", "{url} へのリクエストに失敗しました"),
+    ("context.field", "フィールド '{field}' が不正です"),
+    // エラー
+    ("error.invalid_json", "{message} ({line} 行 {column} 列)"),
+    (
+        "error.invalid_private_key_length",
+        "秘密鍵の長さが不正です (32 バイトのところ {length} バイト)。",
+    ),
+    (
+        "error.missing_private_key",
+        "PRIVATE_KEY が設定されていません。",
+    ),
+    ("error.invalid_key_file", "鍵ファイルが不正です: {reason}"),
+    (
+        "error.key_decryption_failed",
+        "鍵ファイルを復号できません (パスワードの誤り?)。",
+    ),
+    (
+        "error.key_locked",
+        "署名の鍵がロックされています。先にロックを解除してください。",
+    ),
+    (
+        "error.invalid_environment",
+        "環境変数が不正です: {problems}",
+    ),
+    ("error.invalid_chain_id", "chain id が不正です: {reason}"),
+    ("error.invalid_argument", "引数が不正です: {reason}"),
+    ("error.invalid_amount", "量が不正です: {reason}"),
+    ("error.invalid_abi_data", "ABI のデータが不正です: {reason}"),
+    ("error.invalid_tls_config", "TLS の設定が不正です: {reason}"),
+    (
+        "error.invalid_fee_data",
+        "手数料のデータが不正です: {reason}",
+    ),
+    ("error.invalid_price", "価格のデータが不正です: {reason}"),
+    (
+        "error.invalid_typed_data",
+        "型付きデータが不正です: {reason}",
+    ),
+    ("error.invalid_policy", "ポリシーが不正です: {reason}"),
+    ("error.policy_violation", "ポリシー違反です: {reason}"),
+    (
+        "error.audit_log_tampered",
+        "監査ログの検証に失敗しました: {reason}",
+    ),
+    (
+        "error.raw_hash_signing_not_confirmed",
+        "ダイジェストへの署名を拒否しました: どんなトランザクションや permit も承認しうるためです。続けるには --i-know-what-im-doing を付けてください。",
+    ),
+    (
+        "error.raw_hash_signing_with_policy",
+        "POLICY_PATH が設定されているため、ダイジェストへの署名を拒否しました: ダイジェストが何を承認するかをポリシーで確認できないためです。",
+    ),
+    (
+        "error.unlimited_approval_not_confirmed",
+        "無制限の approve への署名を拒否しました: 相手が保有するすべてのトークンを動かせるためです。続けるには --allow-unlimited を付けてください。",
+    ),
+    (
+        "error.mainnet_not_confirmed",
+        "確認なしに Ethereum メインネット (CHAIN_ID 1) は使えません。続けるには --mainnet を付けてください。",
+    ),
+    (
+        "error.value_not_confirmed",
+        "CONFIRM_ABOVE ({threshold}) を超える {value} の value に確認なしに署名することを拒否しました。続けるには --yes を付けてください。",
+    ),
+    (
+        "error.unexpected_deployment_address",
+        "デプロイ先のアドレス {actual} が期待した {expected} と一致しません。",
+    ),
+    (
+        "error.unexpected_signer",
+        "署名から復元したアドレスは {actual} で、期待した {expected} ではありません。",
+    ),
+    (
+        "error.unverified_contract",
+        "確認していないコントラクトへの approve への署名を拒否しました: {reason}",
+    ),
+    (
+        "error.flagged_address",
+        "拒否リストにある {address} ({label}) へのトランザクションへの署名を拒否しました。続けるには --allow-flagged を付けてください。",
+    ),
+    (
+        "error.simulation_failed",
+        "シミュレーションに失敗しました: {reason}。署名を拒否しました。それでも署名するには --simulate を付けずに実行してください。",
+    ),
+    ("error.missing_rpc_url", "RPC_URL が設定されていません。"),
+    (
+        "error.missing_nonce",
+        "nonce が指定されていません: 直接指定するか、NONCE_STORE_PATH か RPC_URL を設定してください。",
+    ),
+    (
+        "error.nondeterministic",
+        "--deterministic では実行ごとに変わりうる {source} を使いません: 値を直接指定してください。",
+    ),
+    (
+        "error.invalid_nonce_store",
+        "nonce ストアが不正です {reason}",
+    ),
+    ("error.invalid_journal", "ジャーナルが不正です: {reason}"),
+    (
+        "error.invalid_state_file",
+        "状態のファイルが不正です {reason}",
+    ),
+    (
+        "error.state_decryption_failed",
+        "{path} を復号できません (STATE_PASSPHRASE か STATE_KEY の誤り?)。",
+    ),
+    (
+        "error.duplicate_transaction",
+        "ジャーナルのトランザクション {hash} と重複する署名を拒否しました (同じ nonce か、同じ内容が取り込み待ち)。続けるには --allow-duplicate を付けるか ALLOW_DUPLICATE=true を設定してください。",
+    ),
+    (
+        "error.broadcast_failed",
+        "{total} 件のうち {failed} 件のトランザクションを送信できませんでした。",
+    ),
+    ("error.rpc", "RPC のエラー ({code}): {message}"),
+    ("error.plugin", "署名プラグインのエラー: {reason}"),
+    (
+        "error.unknown_weth_address",
+        "chain id {chain_id} の WETH のアドレスが分かりません。--weth で指定してください。",
+    ),
+    (
+        "error.unknown_safe_service_url",
+        "chain id {chain_id} の Safe Transaction Service の URL が分かりません。--service-url で指定してください。",
+    ),
+    // 確認の入力
+    (
+        "confirm.type",
+        "{prompt} 続けるには '{phrase}' と入力してください: ",
+    ),
+    (
+        "confirm.mainnet",
+        "CHAIN_ID が 1 です: Ethereum メインネットを使います。",
+    ),
+    (
+        "confirm.value",
+        "トランザクションの value {amount} が CONFIRM_ABOVE を超えています。",
+    ),
+    // 確認用の表示
+    ("cost.gas_limit", "ガスの上限: {gas_limit}"),
+    ("cost.max_fee_per_gas", "ガス価格の上限: {gwei} gwei"),
+    ("cost.max_execution_fee", "実行手数料の上限: {fee}"),
+    ("cost.l1_data_fee", "L1 のデータ手数料: {fee}"),
+    ("cost.max_total_fee", "手数料の合計の上限: {fee}"),
+    (
+        "cost.max_total_fee_fiat",
+        "手数料の合計の上限 ({currency}): {fee} ({price})",
+    ),
+    (
+        "cost.l1_data_fee_not_included",
+        "L1 のデータ手数料は含まれていません: GasPriceOracle に問い合わせるには RPC_URL を設定してください",
+    ),
+    (
+        "fees.summary",
+        "手数料 ({speed}): ガス価格の上限 {max_fee} gwei、優先手数料の上限 {priority_fee} gwei",
+    ),
+    (
+        "fiat.stale",
+        "今の価格を取得できないため、{age} 秒前にキャッシュした価格を使います",
+    ),
+    (
+        "fiat.unavailable",
+        "手数料を {currency} に換算しません: {error}",
+    ),
+    (
+        "offline.signing",
+        "{chain} ({chain_id}) の nonce {nonce} に手数料の上限 {fee} で署名します",
+    ),
+    ("simulation.summary", "シミュレーション: {summary}"),
+    (
+        "simulation.no_changes",
+        "手数料のほかに残高の増減はありません",
+    ),
+    ("simulation.send", "{sent} を送ります"),
+    ("simulation.receive", "{received} を受け取ります"),
+    (
+        "simulation.send_and_receive",
+        "{sent} を送り、{received} を受け取ります",
+    ),
+    (
+        "denylist.allowed",
+        "警告: {address} は拒否リストにあります ({label})。--allow-flagged のため署名します。",
+    ),
+    (
+        "report.transactions",
+        "トランザクション: {total} 件 (取り込み {confirmed}、失敗 {failed}、取り込み待ち {pending}、破棄または置き換え {not_included})",
+    ),
+    ("report.gas_used", "使ったガス: {gas_used}"),
+    ("report.fees", "手数料: {fees}"),
+    ("report.fees_fiat", "手数料 ({currency}): {fees} ({price})"),
+    ("report.l1_data_fees", "L1 のデータ手数料: {fees}"),
+    (
+        "report.average_gas_price",
+        "平均の実効ガス価格: {gwei} gwei",
+    ),
+    (
+        "report.other_chains",
+        "{chain_id} (RPC_URL) 以外のチェーンの {count} 件のトランザクションを飛ばしました",
+    ),
+    (
+        "report.wrote",
+        "{count} 件のトランザクションを {path} に書き出しました",
+    ),
+];
+
+fn catalog(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::En => EN,
+        Lang::Ja => JA,
+    }
+}
+
+// id の文言の {name} を引数で置き換える (日本語のカタログになければ英語、どちらにもなければ id のまま)
+pub fn translate(lang: Lang, id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let find = |lang| {
+        catalog(lang)
+            .iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, template)| *template)
+    };
+    let template = find(lang).or_else(|| find(Lang::En)).unwrap_or(id);
+
+    // 引数の値に含まれる {…} は置き換えない
+    let mut message = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..];
+        let arg = placeholder.find('}').and_then(|end| {
+            let name = &placeholder[..end];
+            args.iter()
+                .find(|(arg, _)| *arg == name)
+                .map(|(_, value)| (end, value))
+        });
+        match arg {
+            Some((end, value)) => {
+                message.push_str(&value.to_string());
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = placeholder;
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+// 今の言語のメッセージ (t! から使う)
+pub fn text(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    translate(current(), id, args)
+}
+
+// t!("cost.gas_limit", gas_limit = cost.gas_limit)
+#[macro_export]
+macro_rules! t {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::text(
+            $id,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),*],
+        )
+    };
+}
+pub use t;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(template: &str) -> BTreeSet<&str> {
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs() {
+        // 英語と日本語で ID と引数がそろっている
+        let ids = |catalog: &[(&'static str, &str)]| {
+            catalog.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(EN), ids(JA));
+        assert_eq!(
+            ids(EN).into_iter().collect::<BTreeSet<_>>().len(),
+            EN.len(),
+            "duplicate ids"
+        );
+        for ((id, en), (_, ja)) in EN.iter().zip(JA) {
+            assert_eq!(placeholders(en), placeholders(ja), "{id}");
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let gas_limit = 21000;
+        let args: &[(&str, &dyn Display)] = &[("gas_limit", &gas_limit)];
+        assert_eq!(
+            translate(Lang::En, "cost.gas_limit", args),
+            "Gas limit: 21000"
+        );
+        assert_eq!(
+            translate(Lang::Ja, "cost.gas_limit", args),
+            "ガスの上限: 21000"
+        );
+        // 値の {…} はそのまま、引数のない {…} と知らない id もそのまま
+        let url = "https://example.com/?ids={id}";
+        assert_eq!(
+            translate(Lang::Ja, "context.endpoint", &[("url", &url)]),
+            "https://example.com/?ids={id} へのリクエストに失敗しました"
+        );
+        assert_eq!(
+            translate(Lang::En, "context.endpoint", &[]),
+            "Request to {url} failed"
+        );
+        assert_eq!(translate(Lang::Ja, "missing.id", &[]), "missing.id");
+
+        // 既定は英語
+        assert_eq!(t!("report.gas_used", gas_used = 84000), "Gas used: 84000");
+    }
+
+    #[test]
+    fn test_detect() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(detect(env(&[("LANG", "ja_JP.UTF-8")])), Lang::Ja);
+        assert_eq!(detect(env(&[("LANG", "ja")])), Lang::Ja);
+        assert_eq!(detect(env(&[("LANG", "en_US.UTF-8")])), Lang::En);
+        assert_eq!(detect(env(&[("LANG", "C")])), Lang::En);
+        assert_eq!(detect(env(&[])), Lang::En);
+        // LC_ALL が LANG より優先、空の値は飛ばす
+        assert_eq!(
+            detect(env(&[("LC_ALL", "C"), ("LANG", "ja_JP.UTF-8")])),
+            Lang::En
+        );
+        assert_eq!(
+            detect(env(&[("LC_ALL", ""), ("LC_MESSAGES", "ja_JP.UTF-8")])),
+            Lang::Ja
+        );
+        assert_eq!(detect(env(&[("LANG", "javanese")])), Lang::En);
+    }
+}
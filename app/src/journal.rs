@@ -0,0 +1,611 @@
+use crate::{
+    Result, address,
+    error::Error,
+    signer::keccak256,
+    state::{Position, StateFile, StateKey},
+    transaction::{self, TxType},
+};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, path::PathBuf, sync::Mutex, time::SystemTime};
+
+// 署名したトランザクションの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    // 署名したがブロードキャストしたかはわからない (CLI は raw トランザクションを出力するのみ)
+    Signed,
+    // 署名サーバーがノードに送信した
+    Broadcast,
+    Confirmed,
+    // ブロックに取り込まれたが revert した
+    Failed,
+    // 送信したがノードから消えた (mempool から追い出された)
+    Dropped,
+    // 同じ nonce の別のトランザクションが取り込まれた
+    Replaced,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Signed => "signed",
+            Self::Broadcast => "broadcast",
+            Self::Confirmed => "confirmed",
+            Self::Failed => "failed",
+            Self::Dropped => "dropped",
+            Self::Replaced => "replaced",
+        }
+    }
+
+    // まだブロックに取り込まれていない
+    pub fn is_pending(self) -> bool {
+        matches!(self, Self::Signed | Self::Broadcast)
+    }
+}
+
+// ジャーナルのエントリ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub hash: H256,
+    pub chain_id: u64,
+    pub from: H160,
+    pub nonce: U256,
+    // 署名したトランザクションのフィールド (transaction::message_json)
+    pub transaction: Value,
+    pub raw: String,
+    pub status: Status,
+    pub requester: String,
+    // 署名した時刻と状態が変わった時刻 (UNIX 時刻、秒)
+    pub signed_at: u64,
+    pub updated_at: u64,
+}
+
+impl Entry {
+    pub fn new(
+        tx_type: &TxType,
+        message: &EIP1559TransactionMessage,
+        from: H160,
+        raw: &[u8],
+        requester: &str,
+    ) -> Self {
+        let now = now();
+        Self {
+            hash: keccak256(raw),
+            chain_id: message.chain_id,
+            from,
+            nonce: message.nonce,
+            transaction: transaction::message_json(tx_type, message),
+            raw: format!("0x{}", hex::encode(raw)),
+            status: Status::Signed,
+            requester: requester.to_string(),
+            signed_at: now,
+            updated_at: now,
+        }
+    }
+
+    // raw トランザクションからエントリを作り直す (インポート用)
+    // ハッシュ・チェーンID・nonce・トランザクションのフィールドはファイルの値を使わず raw から求める
+    pub fn from_raw(
+        raw: &str,
+        from: H160,
+        status: Status,
+        requester: &str,
+        signed_at: u64,
+        updated_at: u64,
+    ) -> Result<Self> {
+        let raw = hex::decode(raw.strip_prefix("0x").unwrap_or(raw))?;
+        let message = EIP1559TransactionMessage::from(transaction::decode(&raw)?);
+        let tx_type = transaction::decode_tx_type(&raw)?;
+
+        Ok(Self {
+            status,
+            signed_at,
+            updated_at,
+            ..Self::new(&tx_type, &message, from, &raw, requester)
+        })
+    }
+
+    // 署名したトランザクション (raw をデコードする)
+    pub fn message(&self) -> Result<EIP1559TransactionMessage> {
+        let raw = hex::decode(self.raw.strip_prefix("0x").unwrap_or(&self.raw))?;
+        Ok(transaction::decode(&raw)?.into())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// 署名したトランザクションの記録 (JSON Lines、鍵があれば暗号化する)
+// 状態が変わるたびにエントリ全体を追記し、読むときは同じハッシュの最後のエントリを使う
+// SQLite ではなく追記のみのファイルにしているのは、レコードごとに暗号化でき (STATE_PASSPHRASE / STATE_KEY)、
+// 書き込み中に落ちても記録済みの行が壊れず、C のライブラリに依存しないため
+#[derive(Debug)]
+pub struct Journal {
+    file: StateFile,
+    // ハッシュで引く索引 (同じプロセス内の読み書きもこのロックで直列化する、別のプロセスとはファイルのロックで排他する)
+    index: Mutex<Index>,
+}
+
+// 読み込んだエントリ (署名した順) とハッシュからの位置
+// 2回目以降は追記された行だけを読み足す
+#[derive(Debug, Default)]
+struct Index {
+    position: Position,
+    // 読み込んだ行数 (エラーの行番号用)
+    lines: usize,
+    entries: Vec<Entry>,
+    by_hash: HashMap<H256, usize>,
+}
+
+impl Index {
+    fn insert(&mut self, entry: Entry) {
+        match self.by_hash.get(&entry.hash) {
+            Some(&i) => self.entries[i] = entry,
+            None => {
+                self.by_hash.insert(entry.hash, self.entries.len());
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    fn get(&self, hash: &H256) -> Option<&Entry> {
+        self.by_hash.get(hash).map(|&i| &self.entries[i])
+    }
+}
+
+impl Journal {
+    // STATE_PASSPHRASE / STATE_KEY があれば暗号化して保存する
+    pub fn new(path: impl Into<PathBuf>, key: Option<StateKey>) -> Self {
+        Self {
+            file: StateFile::new(path, key).with_role("journal"),
+            index: Mutex::new(Index::default()),
+        }
+    }
+
+    fn append(&self, entry: &Entry) -> Result<()> {
+        self.file.append(&serde_json::to_string(entry)?)
+    }
+
+    pub fn record(&self, entry: &Entry) -> Result<()> {
+        let _index = self.index.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        self.append(entry)
+    }
+
+    // 状態を更新する (記録されていないハッシュなら None)
+    pub fn set_status(&self, hash: &H256, status: Status) -> Result<Option<Entry>> {
+        let mut index = self.index.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        self.refresh(&mut index)?;
+        let Some(mut entry) = index.get(hash).cloned() else {
+            return Ok(None);
+        };
+        if entry.status != status {
+            entry.status = status;
+            entry.updated_at = now();
+            self.append(&entry)?;
+        }
+        Ok(Some(entry))
+    }
+
+    // 前に読んだ後に追記されたエントリを索引に加える (ファイルが置き換えられていれば読み直す)
+    fn refresh(&self, index: &mut Index) -> Result<()> {
+        let (records, position) = match self.file.read_from(index.position)? {
+            Some(read) => read,
+            None => {
+                *index = Index::default();
+                self.file
+                    .read_from(Position::default())?
+                    .unwrap_or_default()
+            }
+        };
+        for line in records.lines() {
+            index.lines += 1;
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => index.insert(entry),
+                Err(error) => {
+                    let line = index.lines;
+                    *index = Index::default();
+                    return Err(Error::InvalidJournal(format!("line {line}: {error}")));
+                }
+            }
+        }
+        index.position = position;
+        Ok(())
+    }
+
+    // 署名した順のエントリ (同じハッシュは最新の状態のみ)
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut index = self.index.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        self.refresh(&mut index)?;
+        Ok(index.entries.clone())
+    }
+
+    // 別のジャーナルのエントリを取り込み、取り込んだ数を返す
+    // 既にあるエントリは、取り込むエントリの方が新しい状態の場合のみ更新する
+    pub fn import(&self, entries: &[Entry]) -> Result<usize> {
+        let mut index = self.index.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        self.refresh(&mut index)?;
+        let mut imported = 0;
+        for entry in entries {
+            if index
+                .get(&entry.hash)
+                .is_some_and(|existing| existing.updated_at >= entry.updated_at)
+            {
+                continue;
+            }
+            self.append(entry)?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    pub fn get(&self, hash: &H256) -> Result<Option<Entry>> {
+        let mut index = self.index.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        self.refresh(&mut index)?;
+        Ok(index.get(hash).cloned())
+    }
+}
+
+// 既に署名したトランザクションとの重複
+#[derive(Debug, Clone, PartialEq)]
+pub enum Duplicate {
+    // 同じトランザクション (リトライ) なので、記録した署名を返せばよい
+    Cached(Entry),
+    // 同じ nonce の別のトランザクション (置き換えになる)、または同じ内容のトランザクションが取り込み待ち (二重送信になる)
+    Conflict(Entry),
+}
+
+// 送金先・value・calldata (nonce や手数料は比べない)
+fn payload(transaction: &Value) -> String {
+    ["chainId", "to", "value", "input"]
+        .map(|field| transaction[field].to_string())
+        .join(",")
+}
+
+// 1つのアドレスの記録を nonce と内容で引けるようにしたもの
+// まとめて署名する場合はジャーナルを1度だけ読んで、これで重複を確認する
+#[derive(Debug, Default)]
+pub struct DuplicateIndex {
+    // nonce ごとに最後に記録したもの
+    by_nonce: HashMap<U256, Entry>,
+    // 内容ごとに最後に記録した取り込み待ちのもの
+    pending: HashMap<String, Entry>,
+}
+
+impl DuplicateIndex {
+    pub fn new(entries: Vec<Entry>, chain_id: u64, from: &H160) -> Self {
+        let mut index = Self::default();
+        for entry in entries {
+            if entry.chain_id != chain_id || entry.from != *from {
+                continue;
+            }
+            if entry.status.is_pending() {
+                index
+                    .pending
+                    .insert(payload(&entry.transaction), entry.clone());
+            }
+            index.by_nonce.insert(entry.nonce, entry);
+        }
+        index
+    }
+
+    // nonce が省略された (これから割り当てる) 場合は、同じ内容で取り込み待ちのトランザクションをリトライとみなす
+    pub fn find(
+        &self,
+        tx_type: &TxType,
+        message: &EIP1559TransactionMessage,
+        nonce: Option<U256>,
+    ) -> Option<Duplicate> {
+        let transaction = transaction::message_json(tx_type, message);
+        if let Some(entry) = nonce.and_then(|nonce| self.by_nonce.get(&nonce)) {
+            return Some(if entry.transaction == transaction {
+                Duplicate::Cached(entry.clone())
+            } else {
+                Duplicate::Conflict(entry.clone())
+            });
+        }
+
+        let entry = self.pending.get(&payload(&transaction))?.clone();
+        Some(match nonce {
+            None => Duplicate::Cached(entry),
+            Some(_) => Duplicate::Conflict(entry),
+        })
+    }
+}
+
+impl Journal {
+    // 署名する前に重複を確認する
+    pub fn find_duplicate(
+        &self,
+        tx_type: &TxType,
+        message: &EIP1559TransactionMessage,
+        from: &H160,
+        nonce: Option<U256>,
+    ) -> Result<Option<Duplicate>> {
+        Ok(DuplicateIndex::new(self.entries()?, message.chain_id, from)
+            .find(tx_type, message, nonce))
+    }
+
+    // まとめて署名する前に、from の記録を読み込んでおく
+    pub fn duplicate_index(&self, chain_id: u64, from: &H160) -> Result<DuplicateIndex> {
+        Ok(DuplicateIndex::new(self.entries()?, chain_id, from))
+    }
+}
+
+// history list の1行
+pub fn summary(entry: &Entry) -> String {
+    let to = entry.transaction["to"]
+        .as_str()
+        .and_then(|to| to.parse::<H160>().ok())
+        .map_or("(create)".to_string(), |to| address::to_checksum(&to));
+    format!(
+        "{:?}  chain {}  nonce {}  {:<9}  to {}",
+        entry.hash,
+        entry.chain_id,
+        entry.nonce,
+        entry.status.as_str(),
+        to
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::secret::Secret;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+    use ethereum::{AccessList, TransactionAction};
+    use std::io::Write;
+
+    pub(crate) fn signed_entry(nonce: u64) -> Entry {
+        let message = EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(nonce),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(TEST_ADDRESS.parse().unwrap()),
+            value: U256::one(),
+            input: vec![],
+            access_list: AccessList::default(),
+        };
+        let raw = transaction::sign(message.clone(), &test_signing_key()).unwrap();
+        Entry::new(
+            &TxType::Eip1559,
+            &message,
+            TEST_ADDRESS.parse().unwrap(),
+            &raw,
+            "cli",
+        )
+    }
+
+    #[test]
+    fn test_record_and_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"), None);
+        assert!(journal.entries().unwrap().is_empty());
+
+        let first = signed_entry(0);
+        let second = signed_entry(1);
+        journal.record(&first).unwrap();
+        journal.record(&second).unwrap();
+
+        let updated = journal
+            .set_status(&first.hash, Status::Confirmed)
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.status, Status::Confirmed);
+        assert!(
+            journal
+                .set_status(&H256::zero(), Status::Confirmed)
+                .unwrap()
+                .is_none()
+        );
+
+        // 署名した順で、最新の状態を返す
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, first.hash);
+        assert_eq!(entries[0].status, Status::Confirmed);
+        assert_eq!(entries[1], second);
+        assert_eq!(journal.get(&second.hash).unwrap(), Some(second.clone()));
+
+        let pending: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.status.is_pending())
+            .collect();
+        assert_eq!(pending, vec![&second]);
+        assert!(summary(&second).contains("nonce 1  signed"));
+    }
+
+    #[test]
+    fn test_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let journal = Journal::new(&path, None);
+        let first = signed_entry(0);
+        journal.record(&first).unwrap();
+        assert_eq!(journal.get(&first.hash).unwrap(), Some(first.clone()));
+
+        // 別のプロセス (別のインスタンス) が追記した分も読み足す
+        let second = signed_entry(1);
+        let other = Journal::new(&path, None);
+        other.record(&second).unwrap();
+        other.set_status(&first.hash, Status::Confirmed).unwrap();
+        assert_eq!(journal.get(&second.hash).unwrap(), Some(second.clone()));
+        assert_eq!(
+            journal.get(&first.hash).unwrap().unwrap().status,
+            Status::Confirmed
+        );
+        assert_eq!(journal.entries().unwrap().len(), 2);
+
+        // ファイルが置き換えられたら読み直す
+        std::fs::rename(&path, dir.path().join("old.jsonl")).unwrap();
+        other.record(&second).unwrap();
+        assert_eq!(journal.get(&first.hash).unwrap(), None);
+        assert_eq!(journal.entries().unwrap(), vec![second.clone()]);
+
+        // 壊れた行を追記されてもエラーを返し、索引は読み直す
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{}\n")
+            .unwrap();
+        assert!(matches!(
+            journal.entries().unwrap_err(),
+            Error::InvalidJournal(message) if message.starts_with("line 2:")
+        ));
+        assert!(matches!(
+            journal.get(&second.hash).unwrap_err(),
+            Error::InvalidJournal(_)
+        ));
+    }
+
+    #[test]
+    fn test_find_duplicate() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"), None);
+        let from: H160 = TEST_ADDRESS.parse().unwrap();
+        let entry = signed_entry(3);
+        journal.record(&entry).unwrap();
+
+        let mut message = EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(3),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(TEST_ADDRESS.parse().unwrap()),
+            value: U256::one(),
+            input: vec![],
+            access_list: AccessList::default(),
+        };
+        let find = |message: &EIP1559TransactionMessage, nonce| {
+            journal
+                .find_duplicate(&TxType::Eip1559, message, &from, nonce)
+                .unwrap()
+        };
+
+        // 同じトランザクションのリトライ
+        assert_eq!(
+            find(&message, Some(U256::from(3))),
+            Some(Duplicate::Cached(entry.clone()))
+        );
+        // nonce を割り当てる場合も、同じ内容で取り込み待ちならリトライとみなす
+        assert_eq!(find(&message, None), Some(Duplicate::Cached(entry.clone())));
+        // 同じ内容を別の nonce で送ると二重送信になる
+        message.nonce = U256::from(4);
+        assert_eq!(
+            find(&message, Some(U256::from(4))),
+            Some(Duplicate::Conflict(entry.clone()))
+        );
+        // 同じ nonce の別のトランザクションは置き換えになる
+        message.nonce = U256::from(3);
+        message.value = U256::from(2);
+        assert_eq!(
+            find(&message, Some(U256::from(3))),
+            Some(Duplicate::Conflict(entry.clone()))
+        );
+        assert_eq!(find(&message, None), None);
+
+        // 取り込まれた後は同じ内容でも別のトランザクション
+        journal.set_status(&entry.hash, Status::Confirmed).unwrap();
+        message.value = U256::one();
+        message.nonce = U256::from(4);
+        assert_eq!(find(&message, Some(U256::from(4))), None);
+        // 別のチェーン
+        message.chain_id = 1;
+        message.nonce = U256::from(3);
+        assert_eq!(find(&message, Some(U256::from(3))), None);
+
+        // まとめて確認する場合も同じ
+        let index = journal.duplicate_index(11155111, &from).unwrap();
+        message.chain_id = 11155111;
+        assert!(matches!(
+            index.find(&TxType::Eip1559, &message, Some(U256::from(3))),
+            Some(Duplicate::Cached(cached)) if cached.hash == entry.hash
+        ));
+        message.nonce = U256::from(4);
+        assert_eq!(
+            index.find(&TxType::Eip1559, &message, Some(U256::from(4))),
+            None
+        );
+        assert_eq!(index.find(&TxType::Eip1559, &message, None), None);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"), None);
+        let first = signed_entry(0);
+        journal.record(&first).unwrap();
+
+        let confirmed = Entry {
+            status: Status::Confirmed,
+            updated_at: first.updated_at + 1,
+            ..first.clone()
+        };
+        let second = signed_entry(1);
+        // 新しい状態と未知のエントリのみ取り込む
+        assert_eq!(
+            journal
+                .import(&[first.clone(), confirmed.clone(), second.clone()])
+                .unwrap(),
+            2
+        );
+        assert_eq!(journal.entries().unwrap(), vec![confirmed.clone(), second]);
+        assert_eq!(journal.import(&[confirmed]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_invalid_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        std::fs::write(&path, "{}\n").unwrap();
+
+        assert!(matches!(
+            Journal::new(&path, None).entries().unwrap_err(),
+            Error::InvalidJournal(_)
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let key = Some(StateKey::DataKey(Secret::new([7; 32])));
+        let entry = signed_entry(0);
+        Journal::new(&path, key.clone()).record(&entry).unwrap();
+
+        // 送金先やトランザクションはファイルに平文で残らない
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains(&entry.raw[2..]));
+        assert!(
+            !content
+                .to_lowercase()
+                .contains(&TEST_ADDRESS[2..].to_lowercase())
+        );
+
+        let journal = Journal::new(&path, key);
+        assert_eq!(journal.entries().unwrap(), vec![entry]);
+        assert!(matches!(
+            Journal::new(&path, None).entries().unwrap_err(),
+            Error::InvalidStateFile(_)
+        ));
+    }
+}
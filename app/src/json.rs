@@ -0,0 +1,189 @@
+use crate::{Result, error::Error};
+use serde::de::DeserializeOwned;
+
+// 該当する行を表示するときの最大の文字数 (1行に詰めた JSON は列の前後だけ表示する)
+const MAX_SNIPPET_WIDTH: usize = 80;
+
+// 手で編集するファイル (params.json など) の JSON をパースする
+// エラーには行・列と、該当する行に ^ で位置を示したものを付ける
+pub fn parse<T: DeserializeOwned>(json: &str) -> Result<T> {
+    serde_json::from_str(json).map_err(|error| diagnose(json, error))
+}
+
+fn diagnose(json: &str, error: serde_json::Error) -> Error {
+    let message = error.to_string();
+    let (message, line, column) = match error.line() {
+        // フィールドの値の検証 (params の to_address など) は位置が分からないため、フィールドのキーを探す
+        0 => match locate_field(json, &message) {
+            Some((line, column)) => (message, line, column),
+            None => return Error::Json(error),
+        },
+        line => {
+            let position = format!(" at line {line} column {}", error.column());
+            let message = message.strip_suffix(&position).unwrap_or(&message);
+            (message.to_string(), line, error.column())
+        }
+    };
+
+    Error::InvalidJson {
+        snippet: snippet(json, line, column),
+        message,
+        line,
+        column,
+        source: error,
+    }
+}
+
+// "to_address: ..." や "unknown field `gaslimit`, ..." のフィールドの値の位置 (1始まりの行と列)
+fn locate_field(json: &str, message: &str) -> Option<(usize, usize)> {
+    let name = match message.split_once("field `") {
+        Some((_, rest)) => rest.split_once('`')?.0,
+        None => message.split_once(": ")?.0,
+    };
+    if name.is_empty()
+        || !name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+    {
+        return None;
+    }
+
+    let key = format!("\"{name}\"");
+    let offset = json.match_indices(&key).find_map(|(offset, _)| {
+        let rest = &json[offset + key.len()..];
+        let value = rest.trim_start().strip_prefix(':')?.trim_start();
+        Some(json.len() - value.len())
+    })?;
+    let before = &json[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    Some((line, column))
+}
+
+//   |
+// 4 |     "value": "1234",
+//   |              ^
+fn snippet(json: &str, line: usize, column: usize) -> String {
+    let text: Vec<char> = json
+        .lines()
+        .nth(line - 1)
+        .unwrap_or_default()
+        .chars()
+        .collect();
+    let caret = column.saturating_sub(1).min(text.len());
+    let start = caret.saturating_sub(MAX_SNIPPET_WIDTH / 2);
+    let end = (start + MAX_SNIPPET_WIDTH).min(text.len());
+
+    let mut shown = String::new();
+    let mut indent = String::new();
+    if start > 0 {
+        shown.push('…');
+        indent.push(' ');
+    }
+    shown.extend(&text[start..end]);
+    if end < text.len() {
+        shown.push('…');
+    }
+    // タブはそのまま残して ^ の位置を揃える
+    indent.extend(
+        text[start..caret]
+            .iter()
+            .map(|&c| if c == '\t' { '\t' } else { ' ' }),
+    );
+
+    let gutter = " ".repeat(line.to_string().len());
+    format!("{gutter} |\n{line} | {shown}\n{gutter} | {indent}^")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+
+    #[test]
+    fn test_syntax_error() {
+        let json = "{\n  \"value\": 1,\n  \"gas_limit\": 21000,\n}";
+        let error = parse::<serde_json::Value>(json).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                Error::InvalidJson {
+                    line: 4,
+                    column: 1,
+                    ..
+                }
+            ),
+            "{error:?}"
+        );
+        assert_eq!(
+            error.to_string(),
+            "trailing comma (line 4, column 1)\n  |\n4 | }\n  | ^"
+        );
+    }
+
+    #[test]
+    fn test_field_error() {
+        let json = r#"{
+    "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+    "value": "zz",
+    "gas_limit": 21000
+}"#;
+        assert_eq!(
+            parse::<Params>(json).unwrap_err().to_string(),
+            "value: 'zz' is not a number, expected a decimal string or a hex string (line 3, column 14)\n  |\n3 |     \"value\": \"zz\",\n  |              ^"
+        );
+
+        let json = "{\"to_address\":\"0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df\",\"value\":0,\"gas_limit\":21000,\n\t\"gaslimit\": 21000}";
+        let error = parse::<Params>(json).unwrap_err().to_string();
+        assert!(error.starts_with("unknown field `gaslimit`"), "{error}");
+        assert!(
+            error.ends_with(
+                "(line 2, column 14)\n  |\n2 | \t\"gaslimit\": 21000}\n  | \t            ^"
+            ),
+            "{error}"
+        );
+
+        // 足りないフィールドはオブジェクトの終わりを示す
+        let error = parse::<Params>(r#"{"value": 0}"#).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                Error::InvalidJson {
+                    line: 1,
+                    column: 12,
+                    ..
+                }
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn test_long_line() {
+        let json = format!("[{}true, tru]", "1, ".repeat(100));
+        let error = parse::<serde_json::Value>(&json).unwrap_err().to_string();
+        let lines: Vec<_> = error.lines().collect();
+        assert_eq!(lines.len(), 4, "{error}");
+        assert!(
+            lines[2].starts_with("1 | …") && lines[2].ends_with(']'),
+            "{error}"
+        );
+        let caret = lines[3].find('^').unwrap();
+        assert_eq!(&lines[2][caret + 2..caret + 3], "]", "{error}");
+    }
+
+    #[test]
+    fn test_object_key_order() {
+        // --deterministic の出力は JSON のオブジェクトのキーが常にソートされることに依存する
+        // (serde_json の preserve_order を有効にすると挿入順になる)
+        let value: serde_json::Value =
+            serde_json::from_str(r#"{"b":1,"a":{"d":2,"c":3}}"#).unwrap();
+        assert_eq!(value.to_string(), r#"{"a":{"c":3,"d":2},"b":1}"#);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{Result, address, de::deserialize_hex_bytes, error::Error};
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{num::NonZeroU32, path::Path};
+
+// 新しく暗号化するときの PBKDF2 の反復回数 (OWASP の推奨値)
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+const SALT_LEN: usize = 16;
+
+fn serialize_hex_bytes<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Kdf {
+    iterations: u32,
+    #[serde(
+        serialize_with = "serialize_hex_bytes",
+        deserialize_with = "deserialize_hex_bytes"
+    )]
+    salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cipher {
+    #[serde(
+        serialize_with = "serialize_hex_bytes",
+        deserialize_with = "deserialize_hex_bytes"
+    )]
+    nonce: Vec<u8>,
+    // 秘密鍵 (32バイト) と認証タグ (16バイト)
+    #[serde(
+        serialize_with = "serialize_hex_bytes",
+        deserialize_with = "deserialize_hex_bytes"
+    )]
+    ciphertext: Vec<u8>,
+}
+
+// パスワードで暗号化した秘密鍵 (PBKDF2-HMAC-SHA256 + AES-256-GCM)
+// 公開鍵は平文で持つため、ロック中もアドレスや公開鍵は返せる
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedKey {
+    version: u32,
+    #[serde(
+        serialize_with = "serialize_hex_bytes",
+        deserialize_with = "deserialize_hex_bytes"
+    )]
+    public_key: Vec<u8>,
+    kdf: Kdf,
+    cipher: Cipher,
+}
+
+fn random_bytes(bytes: &mut [u8]) -> Result<()> {
+    SystemRandom::new()
+        .fill(bytes)
+        .map_err(|_| Error::InvalidKeyFile("failed to generate random bytes".to_string()))
+}
+
+impl Kdf {
+    // ランダムなソルトで作る
+    pub(crate) fn generate(iterations: u32) -> Result<Self> {
+        let mut salt = vec![0u8; SALT_LEN];
+        random_bytes(&mut salt)?;
+        Ok(Self { iterations, salt })
+    }
+
+    // パスワードから AES-256 の鍵を導出する
+    pub(crate) fn derive(&self, password: &str) -> Result<[u8; 32]> {
+        let iterations = NonZeroU32::new(self.iterations)
+            .ok_or_else(|| Error::InvalidKeyFile("iterations must not be 0".to_string()))?;
+        let mut key = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &self.salt,
+            password.as_bytes(),
+            &mut key,
+        );
+        Ok(key)
+    }
+}
+
+pub(crate) fn cipher_key(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| Error::KeyDecryptionFailed)?;
+    Ok(LessSafeKey::new(key))
+}
+
+// ランダムな AES-GCM の nonce
+pub(crate) fn random_nonce() -> Result<[u8; NONCE_LEN]> {
+    let mut nonce = [0u8; NONCE_LEN];
+    random_bytes(&mut nonce)?;
+    Ok(nonce)
+}
+
+fn derive_key(password: &str, kdf: &Kdf) -> Result<LessSafeKey> {
+    cipher_key(&kdf.derive(password)?)
+}
+
+impl EncryptedKey {
+    pub fn encrypt(signing_key: &SigningKey, password: &str, iterations: u32) -> Result<Self> {
+        let kdf = Kdf::generate(iterations)?;
+        let nonce = random_nonce()?;
+
+        let public_key = signing_key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+        // 公開鍵を関連データにして、別の鍵のファイルと入れ替えられないようにする
+        let mut ciphertext = signing_key.to_bytes().to_vec();
+        derive_key(password, &kdf)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&public_key),
+                &mut ciphertext,
+            )
+            .map_err(|_| Error::KeyDecryptionFailed)?;
+
+        Ok(Self {
+            version: 1,
+            public_key,
+            kdf,
+            cipher: Cipher {
+                nonce: nonce.to_vec(),
+                ciphertext,
+            },
+        })
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let key: Self = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|error| Error::InvalidKeyFile(error.to_string()))?;
+        if key.version != 1 {
+            return Err(Error::InvalidKeyFile(format!(
+                "unsupported version {}",
+                key.version
+            )));
+        }
+        key.verifying_key()?;
+
+        Ok(key)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey> {
+        VerifyingKey::from_sec1_bytes(&self.public_key)
+            .map_err(|_| Error::InvalidKeyFile("invalid public_key".to_string()))
+    }
+
+    // パスワードが違う場合やファイルが改ざんされている場合は復号できない
+    pub fn decrypt(&self, password: &str) -> Result<SigningKey> {
+        let nonce = Nonce::try_assume_unique_for_key(&self.cipher.nonce)
+            .map_err(|_| Error::InvalidKeyFile("nonce must be 12 bytes".to_string()))?;
+        let mut ciphertext = self.cipher.ciphertext.clone();
+        let plaintext = derive_key(password, &self.kdf)?
+            .open_in_place(nonce, Aad::from(&self.public_key), &mut ciphertext)
+            .map_err(|_| Error::KeyDecryptionFailed)?;
+
+        let signing_key = SigningKey::from_slice(plaintext)?;
+        if signing_key.verifying_key() != &self.verifying_key()? {
+            return Err(Error::KeyDecryptionFailed);
+        }
+        Ok(signing_key)
+    }
+
+    pub fn address(&self) -> Result<ethereum_types::H160> {
+        Ok(address::from_verifying_key(&self.verifying_key()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.json");
+        EncryptedKey::encrypt(&test_signing_key(), "correct horse", 1000)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let key = EncryptedKey::from_path(&path).unwrap();
+        assert_eq!(key.address().unwrap(), TEST_ADDRESS.parse().unwrap());
+        assert_eq!(key.decrypt("correct horse").unwrap(), test_signing_key());
+        assert!(matches!(
+            key.decrypt("wrong").unwrap_err(),
+            Error::KeyDecryptionFailed
+        ));
+
+        // 秘密鍵は平文で保存されない
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains(crate::signer::tests::TEST_PRIVATE_KEY));
+    }
+
+    #[test]
+    fn test_tampered_key_file() {
+        let key = EncryptedKey::encrypt(&test_signing_key(), "password", 1000).unwrap();
+
+        // 別の鍵の公開鍵に差し替えても復号できない
+        let mut swapped = key.clone();
+        swapped.public_key = SigningKey::from_slice(&[1u8; 32])
+            .unwrap()
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+        assert!(swapped.decrypt("password").is_err());
+
+        let mut corrupted = key.clone();
+        corrupted.cipher.ciphertext[0] ^= 1;
+        assert!(corrupted.decrypt("password").is_err());
+
+        let mut zero = key;
+        zero.kdf.iterations = 0;
+        assert!(matches!(
+            zero.decrypt("password").unwrap_err(),
+            Error::InvalidKeyFile(_)
+        ));
+    }
+}
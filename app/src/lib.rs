@@ -0,0 +1,85 @@
+// 署名の本体 (CLI の main.rs のほか、Rust のツールに組み込んで使う)
+// EIP-1193 の provider.request に相当する窓口は Provider
+pub mod abi;
+pub mod address;
+#[cfg(feature = "alloy")]
+pub mod alloy;
+pub mod approval;
+pub mod approvals;
+pub mod arbitrum;
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod batch;
+pub mod bench;
+pub mod calldata;
+pub mod celo;
+pub mod chainlist;
+pub mod chains;
+pub mod cli;
+pub mod config;
+pub mod confirm;
+pub mod contracts;
+pub mod cost;
+pub mod csv;
+pub mod de;
+pub mod denylist;
+pub mod deploy;
+pub mod detached;
+pub mod disperse;
+pub mod eip712;
+pub mod erc1155;
+pub mod erc20;
+pub mod erc721;
+pub mod error;
+pub mod ethers;
+pub mod fees;
+pub mod hardware;
+pub mod i18n;
+pub mod journal;
+pub mod json;
+pub mod keystore;
+pub mod message;
+pub mod metamask;
+pub mod metrics;
+pub mod mmap;
+pub mod multicall;
+pub mod nonce;
+pub mod offline;
+pub mod params;
+pub mod permit;
+pub mod plugin;
+pub mod policy;
+pub mod price;
+pub mod provider;
+pub mod ratelimit;
+pub mod rawtx;
+pub mod rebump;
+pub mod report;
+pub mod rpc;
+pub mod safe;
+pub mod secret;
+pub mod server;
+pub mod session;
+pub mod sigcache;
+pub mod signal;
+pub mod signer;
+pub mod simulation;
+pub mod state;
+pub mod tls;
+pub mod tracker;
+pub mod transaction;
+pub mod txrequest;
+pub mod units;
+pub mod ur;
+pub mod web3keystore;
+pub mod web3signer;
+pub mod webhook;
+pub mod weth;
+pub mod zksync;
+
+pub use config::Config;
+pub use provider::Provider;
+pub use server::Server;
+
+pub type Result<T> = std::result::Result<T, error::Error>;
@@ -1,79 +1,2574 @@
-use ethereum::{AccessList, EIP1559Transaction, EIP1559TransactionMessage, TransactionAction};
-use ethereum_types::H256;
-use k256::ecdsa::SigningKey;
+use clap::Parser;
+use cli::{
+    AuditCommand, BenchCommand, CalldataCommand, ChainsCommand, Cli, Command, Create2Command,
+    DisperseCommand, Erc20Command, Erc721Command, Erc1155Command, HistoryCommand, ImportCommand,
+    InitCodeArgs, KeystoreCommand, NonceCommand, OfflineCommand, OnlineCommand, PluginCommand,
+    PolicyCommand, SafeCommand, ServeArgs, TransactionArgs, WethCommand,
+};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_transaction_signer::{
+    Result, address, approval, approvals, archive, audit, auth, batch, bench, calldata, chainlist,
+    chains, cli, config, confirm, cost, csv, denylist, deploy, detached, disperse, eip712, erc20,
+    erc721, erc1155, error, ethers, fees, hardware, i18n, journal, json, keystore, message,
+    metamask, multicall, nonce, offline, params, permit, plugin, policy, price, provider,
+    ratelimit, rebump, report, safe, secret, server, session, sigcache, signal, signer, simulation,
+    tls, tracker, transaction, txrequest, units, web3keystore, webhook, weth,
+};
+use ethereum_types::{H160, H256, U256};
+use i18n::t;
+use secret::Secret;
+use signer::HashSigner;
+use std::{net::TcpListener, path::Path, process::ExitCode, time::Duration};
 
-mod config;
-mod de;
-mod error;
-mod params;
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let verbose = cli.verbose;
+    // 署名サーバーの JSON-RPC のエラーはクライアントが読むため、--lang がなければ英語のまま
+    let lang = match (&cli.lang, &cli.command) {
+        (Some(lang), _) => *lang,
+        (None, Some(Command::Serve(_))) => i18n::Lang::En,
+        (None, _) => i18n::detect(|name| std::env::var(name).ok()),
+    };
+    i18n::set(lang);
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            // --verbose では原因をたどって全体を出す
+            match verbose {
+                true => eprintln!("{}", error.report()),
+                false => eprintln!("{}", t!("main.error", error = error)),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    // 署名しないコマンドは環境変数を読み込まずに実行
+    let command = match cli.command {
+        Some(Command::Calldata { command }) => return encode_calldata(command),
+        Some(Command::Assemble {
+            prepared,
+            r,
+            s,
+            v,
+            signature,
+            from,
+        }) => {
+            let signature = match (signature, r, s, v) {
+                (Some(ur), ..) => AssembleSignature::Ur(ur),
+                (None, Some(r), Some(s), Some(v)) => AssembleSignature::Rsv(r, s, v),
+                _ => unreachable!("required by clap"),
+            };
+            return assemble_transaction(prepared, signature, from);
+        }
+        Some(Command::Audit {
+            command: AuditCommand::Verify { log },
+        }) => return verify_audit_log(log),
+        Some(Command::Import {
+            command: ImportCommand::Metamask { path, id },
+        }) => return import_metamask(path, id.as_deref()),
+        Some(Command::Keystore {
+            command: KeystoreCommand::Inspect { path, decrypt },
+        }) => return inspect_keystore(path, decrypt),
+        Some(Command::Keystore {
+            command:
+                KeystoreCommand::Import {
+                    path,
+                    output,
+                    iterations,
+                },
+        }) => return import_keystore(path, &output, iterations),
+        Some(Command::Approve {
+            request_id,
+            server,
+            token,
+            reject,
+        }) => return decide_approval(&server, &request_id, token.as_ref(), !reject),
+        Some(Command::Unlock { server, token }) => {
+            return unlock_server(&server, token.as_ref(), true);
+        }
+        Some(Command::Lock { server, token }) => {
+            return unlock_server(&server, token.as_ref(), false);
+        }
+        Some(Command::Create2 {
+            command:
+                Create2Command::Address {
+                    factory,
+                    salt,
+                    init_code_hash,
+                    init_code,
+                },
+        }) => return print_create2_address(factory, &salt, init_code_hash, init_code),
+        Some(Command::Bench {
+            command:
+                BenchCommand::Sign {
+                    count,
+                    jobs,
+                    calldata,
+                },
+        }) => {
+            return bench::print_sign(
+                count as usize,
+                jobs.map(|jobs| jobs as usize),
+                calldata as usize,
+            );
+        }
+        command => command,
+    };
+
+    // 署名サーバーのリロードで .env を読み直すため、読み込む前の環境変数を覚えておく
+    let environment = std::env::vars().collect();
+    let dotenv_path = dotenv::dotenv()?;
 
-type Result<T> = std::result::Result<T, error::Error>;
+    // 手数料を法定通貨でも表示する (cost・offline sign・history report)
+    let fiat = price::FiatOptions {
+        currency: cli.fiat,
+        price: cli.eth_price,
+    };
 
-fn main() -> Result<()> {
-    dotenv::dotenv()?;
+    // ジャーナルやチェーンの定義だけを使うコマンドは署名の設定と秘密鍵を読み込まずに実行
+    let command = match command {
+        Some(Command::History { command }) => {
+            return history(&config::JournalConfig::from_env()?, command, fiat);
+        }
+        Some(Command::Chains { command }) => return chain_presets(command),
+        Some(Command::Policy {
+            command: PolicyCommand::Test { examples, policy },
+        }) => return test_policy(&examples, policy),
+        command => command,
+    };
 
     // 環境変数で渡される設定値
-    let config = crate::config::Config::from_env()?;
+    let mut config = config::Config::from_env()?;
+    config.allow_duplicate |= cli.allow_duplicate;
+    config.yes = cli.yes;
+    config.allow_flagged = cli.allow_flagged;
+    config.deterministic = cli.deterministic;
+    config.simulate = cli.simulate;
+
+    if let Some(warning) = chains::warning(config.chain_id) {
+        warn(cli.strict, warning)?;
+    }
+    confirm::mainnet(
+        config.chain_id,
+        cli.mainnet,
+        confirm::is_interactive(),
+        &mut std::io::stdin().lock(),
+    )?;
+    // プラグインとしてはハッシュに署名するだけ (トランザクションの確認は呼び出し元で行う)
+    if let Some(Command::Plugin {
+        command: PluginCommand::Serve {
+            i_know_what_im_doing,
+        },
+    }) = command
+    {
+        return serve_plugin(&config, i_know_what_im_doing);
+    }
+    if let Some(speed) = cli.speed {
+        let suggested = fees::suggest(&config, speed)?;
+        eprintln!("{}", fees::summary(speed, &suggested));
+        config.max_fee_per_gas = suggested.max_fee_per_gas;
+        config.max_priority_fee_per_gas = suggested.max_priority_fee_per_gas;
+    }
+    // TX_TYPE の形式、指定がなければ RPC_URL のチェーンが EIP-1559 に対応していなければ Legacy で署名する
+    // 問い合わせはトランザクションに署名するコマンドだけで行う
+    // 署名サーバーは Type 2 のみ (eth_signTransaction の gasPrice はエラー)
+    if signs_transactions(command.as_ref()) {
+        match config.explicit_tx_type.clone() {
+            Some(tx_type) => config.tx_type = tx_type,
+            None => detect_tx_type(&mut config),
+        }
+    }
+    // 手数料がチェーンの下限を下回っていないか (Legacy はガス価格) を確認する
+    // --speed は取得した値を下限まで引き上げている
+    if let (None, Some(warning)) = (cli.speed, fees::warning(&config)) {
+        warn(cli.strict, warning)?;
+    }
+    let parse_options = params::ParseOptions {
+        strict_hex: cli.strict_hex,
+        lenient: cli.lenient,
+        decimal_strings: cli.decimal_strings,
+    };
 
+    match command {
+        Some(Command::Calldata { .. })
+        | Some(Command::Assemble { .. })
+        | Some(Command::Audit { .. })
+        | Some(Command::Import { .. })
+        | Some(Command::Keystore { .. })
+        | Some(Command::Approve { .. })
+        | Some(Command::Unlock { .. })
+        | Some(Command::Lock { .. })
+        | Some(Command::Create2 {
+            command: Create2Command::Address { .. },
+        })
+        | Some(Command::Chains { .. })
+        | Some(Command::Policy { .. })
+        | Some(Command::Bench { .. })
+        | Some(Command::Plugin { .. })
+        | Some(Command::History { .. }) => unreachable!(),
+        Some(Command::Deploy {
+            init_code,
+            constructor,
+            args,
+            value,
+            tx,
+        }) => deploy_contract(&config, init_code, constructor, &args, value, tx),
+        Some(Command::Create2 {
+            command:
+                Create2Command::Deploy {
+                    factory,
+                    salt,
+                    init_code,
+                    expect_address,
+                    tx,
+                },
+        }) => create2_deploy(&config, factory, &salt, init_code, expect_address, tx),
+        Some(Command::Request { method, params }) => provider_request(config, &method, &params),
+        Some(Command::Serve(args)) => serve(
+            config,
+            config::ConfigSource::new(environment, Some(dotenv_path)),
+            args,
+        ),
+        Some(Command::Nonce {
+            command: NonceCommand::Show { address },
+        }) => show_nonce(&config, address),
+        Some(Command::Nonce {
+            command: NonceCommand::Set { nonce, address },
+        }) => set_nonce(&config, nonce, address),
+        Some(Command::SignBatch {
+            params,
+            jobs,
+            broadcast,
+            concurrency,
+        }) => sign_batch(
+            &config,
+            &params,
+            jobs,
+            broadcast.then_some(concurrency as usize),
+            parse_options,
+            cli.strict,
+        ),
+        Some(Command::Cost { params }) => print_cost(&config, params, parse_options, fiat),
+        Some(Command::Prepare {
+            params,
+            device,
+            derivation_path,
+            master_fingerprint,
+        }) => {
+            let device = device.map(|device| (device, derivation_path, master_fingerprint));
+            prepare_transaction(&config, params, device, parse_options, cli.strict)
+        }
+        Some(Command::Offline {
+            command:
+                OfflineCommand::Prepare {
+                    params,
+                    from,
+                    expires_in,
+                },
+        }) => prepare_signing_request(&config, params, from, expires_in, parse_options, cli.strict),
+        Some(Command::Offline {
+            command: OfflineCommand::Sign { request },
+        }) => sign_signing_request(&config, request, fiat),
+        Some(Command::Online {
+            command: OnlineCommand::Broadcast { signed },
+        }) => broadcast_signed_transaction(&config, signed),
+        Some(Command::FillGaps) => fill_gaps(&config),
+        Some(Command::Rebump {
+            blocks,
+            percent,
+            max_fee,
+            max_total_fee,
+        }) => rebump_transactions(
+            &config,
+            blocks,
+            percent,
+            &rebump::Limits {
+                max_fee,
+                max_total_fee,
+            },
+        ),
+        Some(Command::Status { watch }) => track_status(&config, watch),
+        Some(Command::EncryptKey { output, iterations }) => {
+            encrypt_key(&config, &output, iterations)
+        }
+        Some(Command::Disperse {
+            command:
+                DisperseCommand::Eth {
+                    payouts,
+                    disperse,
+                    tx,
+                },
+        }) => disperse_eth(&config, payouts, disperse, tx),
+        Some(Command::Disperse {
+            command:
+                DisperseCommand::Token {
+                    payouts,
+                    token,
+                    decimals,
+                    disperse,
+                    tx,
+                },
+        }) => disperse_token(&config, payouts, token, decimals, disperse, tx),
+        Some(Command::Weth {
+            command: WethCommand::Wrap { amount, weth, tx },
+        }) => weth_wrap(&config, &amount, weth, tx),
+        Some(Command::Weth {
+            command: WethCommand::Unwrap { amount, weth, tx },
+        }) => weth_unwrap(&config, &amount, weth, tx),
+        Some(Command::Multicall {
+            calls,
+            multicall,
+            tx,
+        }) => sign_multicall(&config, calls, multicall, tx),
+        Some(Command::Sign { params, format }) => match format {
+            params::InputFormat::Params => {
+                sign_transaction(&config, params, parse_options, cli.strict)
+            }
+            params::InputFormat::Ethers => sign_typed_transaction(
+                &config,
+                ethers::TypedTransaction::from_path(params)?,
+                cli.strict,
+            ),
+            params::InputFormat::Rpc => {
+                sign_typed_transaction(&config, txrequest::from_path(params)?, cli.strict)
+            }
+        },
+        Some(Command::SignMessage {
+            message,
+            hex,
+            validator,
+        }) => sign_message(&config, &message, hex, validator),
+        Some(Command::SignHash {
+            hash,
+            i_know_what_im_doing,
+        }) => sign_hash(&config, &hash, i_know_what_im_doing),
+        Some(Command::SignTypedData {
+            typed_data,
+            debug_hashes,
+        }) => sign_typed_data(&config, typed_data, debug_hashes),
+        Some(Command::Permit {
+            token,
+            spender,
+            value,
+            amount,
+            decimals,
+            deadline,
+            nonce,
+            name,
+            version,
+        }) => {
+            let value = match (value, amount) {
+                (Some(value), _) => value,
+                (None, Some(amount)) => token_amount(&config, &token, &amount, decimals)?,
+                (None, None) => unreachable!("clap requires either --value or --amount"),
+            };
+            sign_permit(
+                &config, token, spender, value, deadline, nonce, name, version,
+            )
+        }
+        Some(Command::Safe {
+            command:
+                SafeCommand::Sign {
+                    safe_tx,
+                    propose,
+                    service_url,
+                    origin,
+                },
+        }) => sign_safe_transaction(&config, safe_tx, propose, service_url, origin),
+        Some(Command::Safe {
+            command: SafeCommand::Export { params, safe, name },
+        }) => export_safe_batch(&config, &params, safe, &name, parse_options),
+        Some(Command::Erc20 {
+            command:
+                Erc20Command::Transfer {
+                    token,
+                    to,
+                    amount,
+                    decimals,
+                    tx,
+                },
+        }) => erc20_transfer(&config, token, to, &amount, decimals, tx),
+        Some(Command::Erc20 {
+            command:
+                Erc20Command::Approve {
+                    token,
+                    spender,
+                    amount,
+                    revoke,
+                    decimals,
+                    allow_unlimited,
+                    tx,
+                },
+        }) => erc20_approve(
+            &config,
+            token,
+            spender,
+            amount.filter(|_| !revoke),
+            decimals,
+            allow_unlimited,
+            tx,
+        ),
+        Some(Command::Erc721 {
+            command:
+                Erc721Command::Transfer {
+                    token,
+                    to,
+                    token_id,
+                    tx,
+                },
+        }) => erc721_transfer(&config, token, to, token_id, tx),
+        Some(Command::Erc1155 {
+            command:
+                Erc1155Command::Transfer {
+                    token,
+                    to,
+                    id,
+                    amount,
+                    data,
+                    tx,
+                },
+        }) => erc1155_transfer(&config, token, to, &[id], &[amount], &data, tx),
+        Some(Command::Erc1155 {
+            command:
+                Erc1155Command::BatchTransfer {
+                    token,
+                    to,
+                    ids,
+                    amounts,
+                    data,
+                    tx,
+                },
+        }) => erc1155_transfer(&config, token, to, &ids, &amounts, &data, tx),
+        None => {
+            let params_json_path = cli
+                .params
+                .expect("Missing argument: Please provide the path to parameter json file.");
+            sign_transaction(&config, params_json_path, parse_options, cli.strict)
+        }
+    }
+}
+
+fn sign_transaction<P: AsRef<Path>>(
+    config: &config::Config,
+    params_json_path: P,
+    options: params::ParseOptions,
+    strict: bool,
+) -> Result<()> {
     // パラメータJSONをパース
-    let params_json_path = std::env::args()
-        .nth(1)
-        .expect("Missing argument: Please provide the path to parameter json file.");
-    let params = params::Params::from_path(params_json_path);
+    let params = options
+        .apply(|| params::Params::from_path(params_json_path))?
+        .encode_function()?;
+    params.check_chain_id(config.chain_id)?;
+    if let Some(warning) = params.nonce_warning() {
+        warn(strict, warning)?;
+    }
 
-    // 署名値 (odd_y_parity, r, s) を含まないトランザクションデータを作成
-    let transaction_message = EIP1559TransactionMessage {
-        chain_id: config.chain_id,
-        nonce: params.nonce,
-        max_priority_fee_per_gas: config.max_priority_fee_per_gas,
-        max_fee_per_gas: config.max_fee_per_gas,
-        gas_limit: params.gas_limit,
-        action: TransactionAction::Call(params.to_address),
-        value: params.value,
-        input: params.input,
-        access_list: AccessList::default(),
-    };
-
-    // 署名用ハッシュを計算
-    let transaction_hash = transaction_message.hash();
-
-    // 秘密鍵
-    let private_key_bytes = config.get_private_key_bytes()?;
-    // 秘密鍵から SigningKey 作成
-    let signing_key = SigningKey::from_slice(&private_key_bytes)?;
-
-    // 署名と recovery_id を取得
-    let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&transaction_hash.0)?;
-
-    // トランザクションデータを作成
-    let (r_bytes, s_bytes) = signature.split_bytes();
-    let transaction = EIP1559Transaction {
-        chain_id: transaction_message.chain_id,
-        nonce: transaction_message.nonce,
-        max_priority_fee_per_gas: transaction_message.max_priority_fee_per_gas,
-        max_fee_per_gas: transaction_message.max_fee_per_gas,
-        gas_limit: transaction_message.gas_limit,
-        action: transaction_message.action,
-        value: transaction_message.value,
-        input: transaction_message.input,
-        access_list: transaction_message.access_list,
-        odd_y_parity: (recovery_id.to_byte() & 1) == 1, // recovery_id が奇数かどうかを判定
-        r: H256::from_slice(&r_bytes),
-        s: H256::from_slice(&s_bytes),
-    };
-
-    // 署名済みトランザクションをRLPエンコード
-    let rlp_encoded_transaction_bytes = rlp::encode(&transaction);
-
-    // Type 2 プレフィックスを付与
-    let signed_transaction = {
-        let mut buf = vec![0x02];
-        buf.extend_from_slice(&rlp_encoded_transaction_bytes);
-        buf
+    sign_params(config, params)
+}
+
+// ethers-rs の TypedTransaction か eth_signTransaction のトランザクションオブジェクトに署名する
+// 省略した nonce と手数料は params.json と同じように補い、Legacy (type 0x00) は gasPrice で EIP-155 の署名をする
+fn sign_typed_transaction(
+    config: &config::Config,
+    tx: ethers::TypedTransaction,
+    strict: bool,
+) -> Result<()> {
+    let key = config.get_key()?;
+    tx.check(config.chain_id, &key.address())?;
+    if let Some(warning) = params::nonce_warning(tx.nonce) {
+        warn(strict, warning)?;
+    }
+
+    // RPC_URL のチェーンから判定した形式ではなく、type で指定された形式で署名する
+    let config = &config::Config {
+        tx_type: match tx.legacy {
+            true => transaction::TxType::Legacy,
+            false => transaction::TxType::Eip1559,
+        },
+        ..config.clone()
     };
+    let (_, signed_transaction) = sign_with_nonce(config, "sign", tx.nonce, &key, |nonce| {
+        tx.message(config, nonce)
+    })?;
+    println!("0x{}", hex::encode(signed_transaction));
+
+    Ok(())
+}
+
+// 署名せずに、外部で署名する署名前のトランザクションと署名用ハッシュを出す
+// 署名者のアドレスは署名するまで分からないため、nonce は params で指定する
+fn prepare_transaction<P: AsRef<Path>>(
+    config: &config::Config,
+    params_json_path: P,
+    device: Option<(hardware::Device, hardware::DerivationPath, Option<u32>)>,
+    options: params::ParseOptions,
+    strict: bool,
+) -> Result<()> {
+    let params = options
+        .apply(|| params::Params::from_path(params_json_path))?
+        .encode_function()?;
+    params.check_chain_id(config.chain_id)?;
+    if params.paymaster.is_some() || params.fee_currency.is_some() {
+        return Err(error::Error::InvalidArgument(
+            "params: paymaster and fee_currency are not supported by prepare, use sign".to_string(),
+        ));
+    }
+    let Some(nonce) = params.nonce else {
+        return Err(error::Error::InvalidArgument(
+            "params: nonce is required by prepare (the sender is only known once the transaction is signed)"
+                .to_string(),
+        ));
+    };
+    if let Some(warning) = params.nonce_warning() {
+        warn(strict, warning)?;
+    }
+
+    let message = transaction::build_message(config, nonce, &params);
+    let mut prepared = detached::prepare(&config.tx_type, &message)?;
+    // ハードウェアウォレット向けのペイロードを加える (assemble は unsigned と sighash のみ使う)
+    if let Some((device, path, master_fingerprint)) = device {
+        let unsigned = detached::encode_unsigned(&config.tx_type, &message)?;
+        prepared[device.name()] = hardware::export(
+            device,
+            &config.tx_type,
+            config.chain_id,
+            &unsigned,
+            &signer::keccak256(&unsigned),
+            &path,
+            master_fingerprint,
+        )?;
+    }
+    println!("{}", serde_json::to_string_pretty(&prepared)?);
+
+    Ok(())
+}
+
+// assemble に渡す署名 (--r・--s・--v か、Keystone の ur:eth-signature)
+enum AssembleSignature {
+    Rsv(U256, U256, u64),
+    Ur(String),
+}
+
+// prepare の出力に外部の署名を付けて raw トランザクションを出す
+fn assemble_transaction<P: AsRef<Path>>(
+    prepared_path: P,
+    signature: AssembleSignature,
+    from: Option<H160>,
+) -> Result<()> {
+    let prepared: detached::Prepared = read_json_file(prepared_path)?;
+
+    let (r, s, v) = match signature {
+        AssembleSignature::Rsv(r, s, v) => (r, s, v),
+        AssembleSignature::Ur(ur) => {
+            let signature = hardware::EthSignature::parse(&ur)?;
+            signature.check_request(&prepared.sighash)?;
+            (signature.r, signature.s, signature.v)
+        }
+    };
+    let (raw, signer) = detached::assemble(&prepared, r, s, v)?;
+    if let Some(expected) = from.filter(|expected| *expected != signer) {
+        return Err(error::Error::UnexpectedSigner {
+            expected: address::to_checksum(&expected),
+            actual: address::to_checksum(&signer),
+        });
+    }
+    eprintln!("Signer: {}", address::to_checksum(&signer));
+    println!("0x{}", hex::encode(raw));
+
+    Ok(())
+}
+
+// オンラインのマシンで、オフラインのマシンで署名する要求を作る
+// nonce を省略した場合は --from のアドレスで NONCE_STORE_PATH のストアか RPC から割り当てる
+fn prepare_signing_request<P: AsRef<Path>>(
+    config: &config::Config,
+    params_json_path: P,
+    from: H160,
+    expires_in: u64,
+    options: params::ParseOptions,
+    strict: bool,
+) -> Result<()> {
+    // 期限は実行した時刻で決まる
+    if config.deterministic {
+        return Err(error::Error::Nondeterministic("the clock for expiresAt"));
+    }
+    let params = options
+        .apply(|| params::Params::from_path(params_json_path))?
+        .encode_function()?;
+    params.check_chain_id(config.chain_id)?;
+    if params.paymaster.is_some() || params.fee_currency.is_some() {
+        return Err(error::Error::InvalidArgument(
+            "params: paymaster and fee_currency are not supported by offline prepare, use sign"
+                .to_string(),
+        ));
+    }
+    if let Some(warning) = params.nonce_warning() {
+        warn(strict, warning)?;
+    }
+    let nonce = match params.nonce {
+        Some(nonce) => nonce,
+        None => allocate_nonce(config, config.get_nonce_store()?.as_ref(), &from, 1)?,
+    };
+
+    let message = transaction::build_message(config, nonce, &params);
+    let request = offline::SigningRequest::new(
+        &config.tx_type,
+        &message,
+        from,
+        offline::now() + expires_in * 60,
+    )?;
+    println!("{}", serde_json::to_string_pretty(&request)?);
+
+    Ok(())
+}
+
+// オフラインのマシンで署名要求に署名する
+// 署名の形式と手数料は要求のものを使い、ポリシー・監査ログ・ジャーナルは通常の署名と同じく使う
+fn sign_signing_request<P: AsRef<Path>>(
+    config: &config::Config,
+    request_path: P,
+    fiat: price::FiatOptions,
+) -> Result<()> {
+    let request: offline::SigningRequest = read_json_file(request_path)?;
+    let (tx_type, message) = request.message(config.chain_id, offline::now())?;
+    let key = config.get_key()?;
+    if key.address() != request.from {
+        return Err(error::Error::InvalidArgument(format!(
+            "signing request is from {}, but PRIVATE_KEY is {}",
+            address::to_checksum(&request.from),
+            address::to_checksum(&key.address())
+        )));
+    }
+    // オフラインのマシンでは価格を取得できないため、--eth-price で渡す
+    let fiat_fee = fiat_price(fiat, request.chain_id)
+        .map(|price| format!(" ({})", price.format(request.max_fee, 18)))
+        .unwrap_or_default();
+    let fee = format!(
+        "{} {}{fiat_fee}",
+        units::format_units(request.max_fee, 18),
+        chains::symbol(request.chain_id)
+    );
+    eprintln!(
+        "{}",
+        t!(
+            "offline.signing",
+            nonce = message.nonce,
+            chain = request.chain,
+            chain_id = request.chain_id,
+            fee = fee
+        )
+    );
+
+    let config = config::Config {
+        tx_type,
+        ..config.clone()
+    };
+    let (_, raw) = sign_with_nonce(&config, "offline_sign", Some(message.nonce), &key, |_| {
+        message.clone()
+    })?;
+    let signed = offline::SignedTransaction::new(&request, &raw);
+    println!("{}", serde_json::to_string_pretty(&signed)?);
+
+    Ok(())
+}
+
+// オンラインのマシンでオフラインのマシンが署名したトランザクションを送信する
+fn broadcast_signed_transaction<P: AsRef<Path>>(
+    config: &config::Config,
+    signed_path: P,
+) -> Result<()> {
+    let signed: offline::SignedTransaction = read_json_file(signed_path)?;
+    let raw = signed.raw(config.chain_id, offline::now())?;
+    let hash = config.get_rpc_client()?.send_raw_transaction(&raw)?;
+    if let Some(journal) = config.get_journal()? {
+        journal.set_status(&hash, journal::Status::Broadcast)?;
+    }
+    println!("{hash:?}");
+
+    Ok(())
+}
+
+// 署名せずに手数料の上限を出す (nonce を省略した場合は 0 として見積もる)
+fn print_cost<P: AsRef<Path>>(
+    config: &config::Config,
+    params_json_path: P,
+    options: params::ParseOptions,
+    fiat: price::FiatOptions,
+) -> Result<()> {
+    let params = options
+        .apply(|| params::Params::from_path(params_json_path))?
+        .encode_function()?;
+    params.check_chain_id(config.chain_id)?;
+    let message = transaction::build_message(config, params.nonce.unwrap_or_default(), &params);
+    let client = config.get_rpc_client().ok();
+
+    let cost = cost::estimate(&message, client.as_ref())?;
+    let price = fiat_price(fiat, config.chain_id);
+    println!("{}", cost::summary(&cost, price.as_ref()));
+    if client.is_none() && chains::is_op_stack(config.chain_id) {
+        eprintln!("{}", t!("cost.l1_data_fee_not_included"));
+    }
+    Ok(())
+}
+
+// 手数料を換算する価格 (--eth-price、なければ PRICE_API_URL から取得する)
+// 表示のためだけに使うため、取得できなければ警告して換算しない
+fn fiat_price(fiat: price::FiatOptions, chain_id: u64) -> Option<price::Price> {
+    let currency = fiat.currency.unwrap_or_default();
+    if let Some(value) = fiat.price {
+        return Some(price::Price { currency, value });
+    }
+    if !fiat.is_enabled() {
+        return None;
+    }
+    let quote = config::PriceConfig::from_env().and_then(|config| {
+        let source = config.get_price_source();
+        let now = offline::now();
+        let quote = source.quote(chain_id, currency, now)?;
+        let age = now.saturating_sub(quote.fetched_at);
+        if age > source.ttl() {
+            let warning = t!("fiat.stale", age = age);
+            eprintln!("{}", t!("main.warning", warning = warning));
+        }
+        Ok(quote)
+    });
+    match quote {
+        Ok(quote) => Some(quote.price),
+        Err(error) => {
+            let currency = currency.code().to_ascii_uppercase();
+            let warning = t!("fiat.unavailable", currency = currency, error = error);
+            eprintln!("{}", t!("main.warning", warning = warning));
+            None
+        }
+    }
+}
+
+// 設定の形式 (config.tx_type) でトランザクションに署名するコマンド
+// 署名要求の形式を使う offline sign、type を指定したファイルを読む sign --format ethers / rpc、
+// メッセージや型付きデータに署名するコマンドなどは含めない
+fn signs_transactions(command: Option<&Command>) -> bool {
+    matches!(
+        command,
+        None | Some(Command::Deploy { .. })
+            | Some(Command::Create2 {
+                command: Create2Command::Deploy { .. },
+            })
+            | Some(Command::SignBatch { .. })
+            | Some(Command::Prepare { .. })
+            | Some(Command::Offline {
+                command: OfflineCommand::Prepare { .. },
+            })
+            | Some(Command::FillGaps)
+            | Some(Command::Rebump { .. })
+            | Some(Command::Disperse { .. })
+            | Some(Command::Weth { .. })
+            | Some(Command::Multicall { .. })
+            | Some(Command::Sign {
+                format: params::InputFormat::Params,
+                ..
+            })
+            | Some(Command::Erc20 { .. })
+            | Some(Command::Erc721 { .. })
+            | Some(Command::Erc1155 { .. })
+    )
+}
+
+// 判定できなければ (ノードに接続できないなど) Type 2 のまま署名する
+fn detect_tx_type(config: &mut config::Config) {
+    // chains.toml で Legacy と定義したチェーンは問い合わせない
+    if chains::preset_for(config.chain_id).is_some_and(|preset| !preset.eip1559) {
+        config.tx_type = transaction::TxType::Legacy;
+        return;
+    }
+    let Ok(client) = config.get_rpc_client() else {
+        return;
+    };
+    match client.supports_eip1559() {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!(
+                "Chain {} has no baseFeePerGas: signing legacy transactions with gasPrice = MAX_FEE_PER_GAS",
+                config.chain_id
+            );
+            config.tx_type = transaction::TxType::Legacy;
+        }
+        Err(error) => eprintln!("Warning: could not detect EIP-1559 support: {error}"),
+    }
+}
+
+// 警告を標準エラー出力に出す (--strict ならエラーにする)
+fn warn(strict: bool, warning: String) -> Result<()> {
+    match strict {
+        true => Err(error::Error::InvalidArgument(t!(
+            "main.strict",
+            warning = warning
+        ))),
+        false => {
+            eprintln!("{}", t!("main.warning", warning = warning));
+            Ok(())
+        }
+    }
+}
+
+fn sign_params(config: &config::Config, params: params::Params) -> Result<()> {
+    // paymaster を指定した場合は zkSync の EIP-712 トランザクション、
+    // fee_currency を指定した場合は Celo の CIP-64 トランザクションに署名する
+    let tx_type_config;
+    let tx_type = match (&params.paymaster, params.fee_currency) {
+        (Some(_), _) if !chains::is_zksync(config.chain_id) => {
+            return Err(error::Error::InvalidArgument(format!(
+                "params: paymaster is only supported on zkSync (CHAIN_ID 324 or 300), not {}",
+                config.chain_id
+            )));
+        }
+        (_, Some(_)) if !chains::is_celo(config.chain_id) => {
+            return Err(error::Error::InvalidArgument(format!(
+                "params: fee_currency is only supported on Celo (CHAIN_ID 42220, 44787 or 11142220), not {}",
+                config.chain_id
+            )));
+        }
+        (Some(paymaster), _) => Some(transaction::TxType::Zksync(paymaster.clone())),
+        (None, Some(fee_currency)) => Some(transaction::TxType::Celo(fee_currency)),
+        (None, None) => None,
+    };
+    let config = match tx_type {
+        Some(tx_type) => {
+            tx_type_config = config::Config {
+                tx_type,
+                ..config.clone()
+            };
+            &tx_type_config
+        }
+        None => config,
+    };
+
+    // 署名して raw トランザクションを作成
+    let key = config.get_key()?;
+    let (_, signed_transaction) = sign_with_nonce(config, "sign", params.nonce, &key, |nonce| {
+        // 署名値を含まないトランザクションデータを作成
+        transaction::build_message(config, nonce, &params)
+    })?;
 
     // 16進数文字列として出力
     println!("0x{}", hex::encode(signed_transaction));
 
     Ok(())
 }
+
+// sign-batch のチャンクをまたいで使うもの
+struct BatchContext<'a> {
+    config: &'a config::Config,
+    key: signer::Key,
+    signatures: sigcache::SignatureCache,
+    store: Option<nonce::NonceStore>,
+    policy: Option<policy::Policy>,
+    denylist: Option<denylist::Denylist>,
+    jobs: usize,
+    broadcast: Option<batch::Broadcast<'a>>,
+}
+
+// パラメータの配列にまとめて署名し、raw トランザクションを順番に1行ずつ出力する
+// ファイルは batch::CHUNK_SIZE 件ずつ読み込み、チャンクごとに署名して出力する
+// broadcast (同時送信数) があれば、記録したものから署名と重ねて送信する
+fn sign_batch(
+    config: &config::Config,
+    path: &Path,
+    jobs: Option<usize>,
+    broadcast: Option<usize>,
+    options: params::ParseOptions,
+    strict: bool,
+) -> Result<()> {
+    let client = match broadcast {
+        Some(_) => Some(config.get_rpc_client()?),
+        None => None,
+    };
+    let context = BatchContext {
+        config,
+        key: config.get_key()?,
+        signatures: sigcache::SignatureCache::default().with_tx_type(config.tx_type.clone()),
+        store: config.get_nonce_store()?,
+        policy: config.get_policy()?,
+        denylist: config.get_denylist()?,
+        jobs: jobs.unwrap_or_else(batch::default_jobs),
+        broadcast: client
+            .as_ref()
+            .zip(broadcast)
+            .map(|(client, concurrency)| batch::Broadcast {
+                client,
+                concurrency,
+            }),
+    };
+
+    let mut params = batch::read_params(path, options)?;
+    let (mut offset, mut sent, mut failed) = (0, 0, 0);
+    loop {
+        let chunk = params
+            .by_ref()
+            .take(batch::CHUNK_SIZE)
+            .collect::<Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        for (i, params) in chunk.iter().enumerate() {
+            params.check_chain_id(config.chain_id).map_err(|error| {
+                error::Error::InvalidArgument(format!("transaction {}: {error}", offset + i))
+            })?;
+            if let Some(warning) = params.nonce_warning() {
+                warn(strict, format!("transaction {}: {warning}", offset + i))?;
+            }
+            if params.paymaster.is_some() {
+                return Err(error::Error::InvalidArgument(format!(
+                    "transaction {}: paymaster is not supported by sign-batch, use sign",
+                    offset + i
+                )));
+            }
+            if params.fee_currency.is_some() {
+                return Err(error::Error::InvalidArgument(format!(
+                    "transaction {}: fee_currency is not supported by sign-batch, use sign",
+                    offset + i
+                )));
+            }
+        }
+        let len = chunk.len();
+        let (chunk_sent, chunk_failed) = sign_batch_chunk(&context, offset, chunk)?;
+        sent += chunk_sent;
+        failed += chunk_failed;
+        offset += len;
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(error::Error::BroadcastFailed {
+            failed,
+            total: sent,
+        }),
+    }
+}
+
+// チャンクに署名して出力し、送信したトランザクションの数と失敗した数を返す (offset はチャンクの先頭の番号)
+// nonce の割り当て・ポリシーの確認・記録は順番に行い、ハッシュの計算と署名だけを並列にする
+fn sign_batch_chunk(
+    context: &BatchContext,
+    offset: usize,
+    params: Vec<params::Params>,
+) -> Result<(usize, usize)> {
+    let config = context.config;
+    let sender = context.key.address();
+
+    // リトライした場合など、ジャーナルにある同じトランザクションは署名し直さない
+    let duplicates = match config.get_journal()?.filter(|_| !config.allow_duplicate) {
+        Some(journal) => Some(journal.duplicate_index(config.chain_id, &sender)?),
+        None => None,
+    };
+    let mut signed = Vec::with_capacity(params.len());
+    for params in &params {
+        let message = transaction::build_message(config, params.nonce.unwrap_or_default(), params);
+        let duplicate = duplicates
+            .as_ref()
+            .and_then(|duplicates| duplicates.find(&config.tx_type, &message, params.nonce));
+        signed.push(journaled(duplicate)?.map(|(_, raw)| raw));
+    }
+
+    // nonce を省略したトランザクションには連続した nonce を割り当てる
+    let store = context.store.as_ref();
+    let count = params
+        .iter()
+        .zip(&signed)
+        .filter(|(params, signed)| params.nonce.is_none() && signed.is_none())
+        .count() as u64;
+    let first = match count {
+        0 => U256::zero(),
+        count => allocate_nonce(config, store, &sender, count)?,
+    };
+    let release = || match store {
+        Some(store) if count > 0 => store.release_range(config.chain_id, &sender, first, count),
+        _ => Ok(()),
+    };
+
+    let mut next = first;
+    let mut unsigned = Vec::new();
+    for (i, params) in params.iter().enumerate() {
+        if signed[i].is_some() {
+            continue;
+        }
+        let nonce = params.nonce.unwrap_or_else(|| {
+            next += U256::one();
+            next - 1
+        });
+        unsigned.push((i, transaction::build_message(config, nonce, params)));
+    }
+
+    // 1件でもポリシーに違反すればチャンクの残りも署名しない
+    let policy = context.policy.as_ref();
+    for (i, message) in &unsigned {
+        let checked: Result<()> = policy
+            .map_or(Ok(()), |policy| {
+                policy
+                    .check_transaction(message)
+                    .and_then(|()| policy.check_approval_threshold(message))
+                    .map_err(Into::into)
+            })
+            .and_then(|()| check_denylist(config, context.denylist.as_ref(), message))
+            .and_then(|()| config.verify_approval(message))
+            .and_then(|()| confirm_value(config, message));
+        if let Err(error) = checked {
+            eprintln!("Transaction {} was rejected", offset + i);
+            audit_signing(
+                config,
+                "sign_batch",
+                transaction::message_json(&config.tx_type, message),
+                Err(&error),
+            )?;
+            release()?;
+            return Err(error);
+        }
+    }
+
+    // 送金額の1日の上限は、すべての確認が通ってからチャンクの合計で1度だけ数える
+    let total = unsigned.iter().fold(U256::zero(), |total, (_, message)| {
+        total.saturating_add(message.value)
+    });
+    if let Some(Err(violation)) = policy.map(|policy| policy.reserve_value(total)) {
+        let error = error::Error::from(violation);
+        eprintln!(
+            "Transactions {}..{} were rejected",
+            offset,
+            offset + params.len()
+        );
+        for (_, message) in &unsigned {
+            audit_signing(
+                config,
+                "sign_batch",
+                transaction::message_json(&config.tx_type, message),
+                Err(&error),
+            )?;
+        }
+        release()?;
+        return Err(error);
+    }
+
+    let messages = unsigned
+        .iter()
+        .map(|(_, message)| message.clone())
+        .collect();
+    // 1件も記録しないまま失敗した場合だけ、割り当てた nonce を戻す
+    // 記録しなかったトランザクションの value は1日の合計から戻す
+    let mut recorded = 0;
+    let mut recorded_value = U256::zero();
+    let results = batch::sign_pipeline(
+        messages,
+        &context.key,
+        &context.signatures,
+        context.jobs,
+        context.broadcast,
+        |j, raw| {
+            let (i, message) = &unsigned[j];
+            audit_signing(
+                config,
+                "sign_batch",
+                transaction::message_json(&config.tx_type, message),
+                Ok(signer::keccak256(raw)),
+            )?;
+            record_transaction(config, message, sender, raw)?;
+            if let (Some(store), Some(nonce)) = (store, params[*i].nonce) {
+                store.commit(config.chain_id, &sender, nonce)?;
+            }
+            recorded += 1;
+            recorded_value = recorded_value.saturating_add(message.value);
+            Ok(())
+        },
+    )
+    .inspect_err(|_| {
+        if recorded == 0 {
+            let _ = release();
+        }
+        if let Some(policy) = policy
+            && let Err(error) = policy.release_value(total.saturating_sub(recorded_value))
+        {
+            eprintln!("Failed to update the daily value ledger: {error}");
+        }
+    })?;
+
+    // 送信の結果は標準エラー出力に出す (ジャーナルから返したトランザクションは送信し直さない)
+    let journal = config.get_journal()?;
+    let (mut sent, mut failed) = (0, 0);
+    for ((i, _), result) in unsigned.iter().zip(results) {
+        match result.sent {
+            Some(Ok(hash)) => {
+                eprintln!("Transaction {} broadcast: {hash:?}", offset + i);
+                if let Some(journal) = &journal {
+                    // 送信済みのトランザクションは出力するため、記録に失敗しても出力のみ
+                    if let Err(error) = journal.set_status(&hash, journal::Status::Broadcast) {
+                        eprintln!("Failed to write the journal: {error}");
+                    }
+                }
+                sent += 1;
+            }
+            Some(Err(error)) => {
+                eprintln!("Transaction {} was not broadcast: {error}", offset + i);
+                sent += 1;
+                failed += 1;
+            }
+            None => {}
+        }
+        signed[*i] = Some(result.raw);
+    }
+
+    for raw in signed.into_iter().flatten() {
+        println!("0x{}", hex::encode(raw));
+    }
+
+    Ok((sent, failed))
+}
+
+// nonce が省略されていれば割り当ててから署名し、署名したトランザクションと nonce を返す
+// 署名しなかった場合は割り当てた nonce をストアに戻し、署名した場合は指定された nonce もストアに記録する
+fn sign_with_nonce(
+    config: &config::Config,
+    method: &str,
+    nonce: Option<U256>,
+    key: &signer::Key,
+    build: impl Fn(U256) -> EIP1559TransactionMessage,
+) -> Result<(U256, Vec<u8>)> {
+    let sender = key.address();
+    if let Some(signed) =
+        check_duplicate(config, &sender, nonce, &build(nonce.unwrap_or_default()))?
+    {
+        return Ok(signed);
+    }
+
+    let store = config.get_nonce_store()?;
+    let (nonce, allocated) = match nonce {
+        Some(nonce) => (nonce, false),
+        None => (allocate_nonce(config, store.as_ref(), &sender, 1)?, true),
+    };
+
+    let result = sign_with_policy(config, method, build(nonce), key);
+    if let Some(store) = store {
+        match (&result, allocated) {
+            (Ok(_), false) => store.commit(config.chain_id, &sender, nonce)?,
+            (Err(_), true) => store.release(config.chain_id, &sender, nonce)?,
+            _ => {}
+        }
+    }
+    result.map(|raw| (nonce, raw))
+}
+
+// JOURNAL_PATH が設定されていれば、既に署名したトランザクションとの重複を確認する
+// リトライならジャーナルの署名を返し、置き換えや二重送信になる場合は (--allow-duplicate がなければ) 拒否する
+fn check_duplicate(
+    config: &config::Config,
+    sender: &H160,
+    nonce: Option<U256>,
+    message: &EIP1559TransactionMessage,
+) -> Result<Option<(U256, Vec<u8>)>> {
+    let Some(journal) = config.get_journal()?.filter(|_| !config.allow_duplicate) else {
+        return Ok(None);
+    };
+
+    journaled(journal.find_duplicate(&config.tx_type, message, sender, nonce)?)
+}
+
+// ジャーナルとの重複がリトライならジャーナルの署名を返し、置き換えや二重送信になる場合は拒否する
+fn journaled(duplicate: Option<journal::Duplicate>) -> Result<Option<(U256, Vec<u8>)>> {
+    match duplicate {
+        Some(journal::Duplicate::Cached(entry)) => {
+            eprintln!(
+                "Already signed, returning the journaled transaction {:?}",
+                entry.hash
+            );
+            let raw = hex::decode(entry.raw.trim_start_matches("0x"))?;
+            Ok(Some((entry.nonce, raw)))
+        }
+        Some(journal::Duplicate::Conflict(entry)) => Err(error::Error::DuplicateTransaction(
+            format!("{:?}", entry.hash),
+        )),
+        None => Ok(None),
+    }
+}
+
+// NONCE_STORE_PATH のストアで連続した count 個を割り当て、最初の nonce を返す
+// RPC_URL があればノードの pending の nonce より前は使わない。ストアがなければノードの pending の nonce から使う
+fn allocate_nonce(
+    config: &config::Config,
+    store: Option<&nonce::NonceStore>,
+    sender: &H160,
+    count: u64,
+) -> Result<U256> {
+    // nonce ストアは実行するたびに進む
+    if config.deterministic {
+        return Err(error::Error::Nondeterministic(
+            "a nonce from NONCE_STORE_PATH or RPC_URL",
+        ));
+    }
+    let pending = match &config.rpc_url {
+        Some(_) => Some(config.get_rpc_client()?.pending_nonce(sender)?),
+        None => None,
+    };
+
+    match (store, pending) {
+        (Some(store), pending) => store.allocate_range(config.chain_id, sender, pending, count),
+        (None, Some(pending)) => Ok(pending),
+        (None, None) => Err(error::Error::MissingNonce),
+    }
+}
+
+fn nonce_store(config: &config::Config) -> Result<nonce::NonceStore> {
+    config
+        .get_nonce_store()?
+        .ok_or_else(|| error::Error::InvalidArgument("NONCE_STORE_PATH is not set".to_string()))
+}
+
+// --address がなければ PRIVATE_KEY のアドレス
+fn sender_address(config: &config::Config, address: Option<H160>) -> Result<H160> {
+    match address {
+        Some(address) => Ok(address),
+        None => Ok(config.get_key()?.address()),
+    }
+}
+
+fn show_nonce(config: &config::Config, address: Option<H160>) -> Result<()> {
+    let store = nonce_store(config)?;
+    let sender = sender_address(config, address)?;
+    let next = store.next(config.chain_id, &sender)?;
+
+    println!("Address: {}", address::to_checksum(&sender));
+    println!(
+        "Next nonce: {}",
+        next.map_or("(none allocated)".to_string(), |next| next.to_string())
+    );
+    if config.rpc_url.is_some() {
+        let pending = config.get_rpc_client()?.pending_nonce(&sender)?;
+        println!("Pending nonce (RPC): {pending}");
+    }
+
+    Ok(())
+}
+
+fn set_nonce(config: &config::Config, nonce: Option<U256>, address: Option<H160>) -> Result<()> {
+    let store = nonce_store(config)?;
+    let sender = sender_address(config, address)?;
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None => config.get_rpc_client()?.pending_nonce(&sender)?,
+    };
+    store.set(config.chain_id, &sender, nonce)?;
+    println!("Next nonce: {nonce}");
+
+    Ok(())
+}
+
+fn require_journal(journal: Option<journal::Journal>) -> Result<journal::Journal> {
+    journal.ok_or_else(|| error::Error::InvalidArgument("JOURNAL_PATH is not set".to_string()))
+}
+
+// policy test: 例のトランザクションをポリシーで確認し、1つでも expect と違えばエラー
+// 例に chain_id がなければ CHAIN_ID を使う (署名の設定は読み込まない)
+fn test_policy(examples: &Path, policy: Option<std::path::PathBuf>) -> Result<()> {
+    let path = match policy {
+        Some(path) => path,
+        None => std::env::var("POLICY_PATH")
+            .map(Into::into)
+            .map_err(|_| error::Error::InvalidArgument("POLICY_PATH is not set".to_string()))?,
+    };
+    let policy = policy::Policy::from_path(&path)?;
+    let chain_id = match std::env::var("CHAIN_ID") {
+        Ok(chain_id) => Some(chain_id.parse().map_err(|_| {
+            error::Error::InvalidChainId(format!("CHAIN_ID '{chain_id}' is not a number"))
+        })?),
+        Err(_) => None,
+    };
+
+    let reports = policy::test_examples(&policy, examples, chain_id)?;
+    for report in &reports {
+        println!("{}", report.line());
+    }
+    let failed = reports.iter().filter(|report| !report.passed()).count();
+    println!(
+        "{} examples, {} allowed, {} denied, {failed} unexpected",
+        reports.len(),
+        reports
+            .iter()
+            .filter(|report| report.result.is_ok())
+            .count(),
+        reports
+            .iter()
+            .filter(|report| report.result.is_err())
+            .count()
+    );
+    match failed {
+        0 => Ok(()),
+        _ => Err(error::Error::PolicyViolation(format!(
+            "{failed} of {} examples did not match their expectation under {}",
+            reports.len(),
+            path.display()
+        ))),
+    }
+}
+
+// chains: プリセットの一覧、chains import: chainlist のチェーンを CHAINS_PATH に追記
+fn chain_presets(command: Option<ChainsCommand>) -> Result<()> {
+    let path = std::env::var("CHAINS_PATH").ok();
+    match command {
+        None => {
+            if let Some(path) = path {
+                chains::load(path)?;
+            }
+            println!("{}", chains::preset_table());
+        }
+        Some(ChainsCommand::Import { chain_ids, refresh }) => {
+            let path = path.ok_or_else(|| {
+                error::Error::InvalidArgument("CHAINS_PATH is not set".to_string())
+            })?;
+            let dataset = match refresh {
+                Some(url) => chainlist::fetch(&url)?,
+                None => chainlist::snapshot(),
+            };
+            for line in chainlist::import(&path, &chain_ids, &dataset)? {
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn history(
+    config: &config::JournalConfig,
+    command: HistoryCommand,
+    fiat: price::FiatOptions,
+) -> Result<()> {
+    let journal = require_journal(config.get_journal()?)?;
+    match command {
+        HistoryCommand::List { pending } => list_history(&journal, pending),
+        HistoryCommand::Show { hash } => show_history(&journal, &hash),
+        HistoryCommand::Export {
+            output,
+            since,
+            until,
+        } => export_history(&journal, &output, since, until),
+        HistoryCommand::Import { input } => import_history(&journal, &input),
+        HistoryCommand::Report {
+            output,
+            since,
+            until,
+        } => report_history(config, &journal, output.as_deref(), since, until, fiat),
+    }
+}
+
+fn list_history(journal: &journal::Journal, pending: bool) -> Result<()> {
+    for entry in journal
+        .entries()?
+        .iter()
+        .filter(|entry| !pending || entry.status.is_pending())
+    {
+        println!("{}", journal::summary(entry));
+    }
+
+    Ok(())
+}
+
+fn show_history(journal: &journal::Journal, hash: &H256) -> Result<()> {
+    let entry = journal.get(hash)?.ok_or_else(|| {
+        error::Error::InvalidArgument(format!("transaction {hash:?} is not in the journal"))
+    })?;
+    println!("{}", serde_json::to_string_pretty(&entry)?);
+
+    Ok(())
+}
+
+fn export_history(
+    journal: &journal::Journal,
+    output: &Path,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<()> {
+    let count = archive::export(output, journal.entries()?, since, until)?;
+    println!("Exported {count} transactions to {}", output.display());
+
+    Ok(())
+}
+
+fn import_history(journal: &journal::Journal, input: &Path) -> Result<()> {
+    let entries = archive::read(input)?;
+    let imported = journal.import(&entries)?;
+    println!(
+        "Imported {imported} transactions ({} already in the journal)",
+        entries.len() - imported
+    );
+
+    Ok(())
+}
+
+// ジャーナルのトランザクションのレシートから手数料と結果を集計する
+// RPC_URL のチェーンのトランザクションのみ (チェーンごとにネイティブ通貨が違うため)
+fn report_history(
+    config: &config::JournalConfig,
+    journal: &journal::Journal,
+    output: Option<&Path>,
+    since: Option<u64>,
+    until: Option<u64>,
+    fiat: price::FiatOptions,
+) -> Result<()> {
+    let client = config.get_rpc_client()?;
+    let chain_id: U256 = client.request("eth_chainId", serde_json::json!([]))?;
+    let chain_id = chain_id.low_u64();
+
+    let (entries, other_chains): (Vec<_>, Vec<_>) = journal
+        .entries()?
+        .into_iter()
+        .filter(|entry| since.is_none_or(|since| entry.signed_at >= since))
+        .filter(|entry| until.is_none_or(|until| entry.signed_at < until))
+        .partition(|entry| entry.chain_id == chain_id);
+    if !other_chains.is_empty() {
+        eprintln!(
+            "{}",
+            t!(
+                "report.other_chains",
+                count = other_chains.len(),
+                chain_id = chain_id
+            )
+        );
+    }
+
+    let rows = report::build(entries, &client)?;
+    let summary = report::summarize(&rows);
+    let price = fiat_price(fiat, chain_id);
+    let price = price.as_ref();
+    match output {
+        Some(output) => {
+            let content = if csv::is_csv(output) {
+                report::to_csv(&rows)
+            } else {
+                serde_json::to_string_pretty(&report::to_json(chain_id, &summary, &rows, price))?
+                    + "\n"
+            };
+            std::fs::write(output, content)?;
+            println!("{}", report::summary_text(chain_id, &summary, price));
+            println!(
+                "{}",
+                t!("report.wrote", count = rows.len(), path = output.display())
+            );
+        }
+        None => {
+            eprintln!("{}", report::summary_text(chain_id, &summary, price));
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report::to_json(chain_id, &summary, &rows, price))?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// ジャーナルの取り込み待ちのトランザクションを RPC で確認する (watch があれば繰り返す)
+fn track_status(config: &config::Config, watch: Option<u64>) -> Result<()> {
+    let journal = require_journal(config.get_journal()?)?;
+    let client = config.get_rpc_client()?;
+
+    loop {
+        let report = tracker::check(&journal, &client, config.chain_id)?;
+        for (before, entry) in &report.changed {
+            println!(
+                "{:?}  nonce {}  {} -> {}",
+                entry.hash,
+                entry.nonce,
+                before.as_str(),
+                entry.status.as_str()
+            );
+        }
+        for gap in &report.gaps {
+            let join = |nonces: &[U256]| {
+                nonces
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            println!(
+                "Nonce gap for {}: missing {}, blocking {}",
+                address::to_checksum(&gap.from),
+                join(&gap.missing),
+                join(&gap.blocked)
+            );
+        }
+        println!("{} pending", report.pending.len());
+        if !report.gaps.is_empty() && watch.is_none() {
+            println!("Run `fill-gaps` to sign self-transfers for the missing nonces");
+        }
+
+        match watch {
+            Some(interval) => std::thread::sleep(Duration::from_secs(interval)),
+            None => return Ok(()),
+        }
+    }
+}
+
+// 自分のアドレスの nonce の抜けを、送信者自身への value 0 の送金で埋める
+// 抜けを埋めるトランザクションは同じ内容になるため、ジャーナルとの重複は確認しない
+fn fill_gaps(config: &config::Config) -> Result<()> {
+    let key = config.get_key()?;
+    let sender = key.address();
+    let report = tracker::check(
+        &require_journal(config.get_journal()?)?,
+        &config.get_rpc_client()?,
+        config.chain_id,
+    )?;
+    let store = config.get_nonce_store()?;
+
+    for gap in &report.gaps {
+        if gap.from != sender {
+            eprintln!(
+                "Skipping the nonce gap for {} (not the address of PRIVATE_KEY)",
+                address::to_checksum(&gap.from)
+            );
+            continue;
+        }
+        for nonce in &gap.missing {
+            let message = transaction::build_filler_message(config, *nonce, sender);
+            let raw = sign_with_policy(config, "fill_gaps", message, &key)?;
+            if let Some(store) = &store {
+                store.commit(config.chain_id, &sender, *nonce)?;
+            }
+            eprintln!("Filler for nonce {nonce}: {:?}", signer::keccak256(&raw));
+            println!("0x{}", hex::encode(raw));
+        }
+    }
+    if report.gaps.is_empty() {
+        eprintln!("No nonce gaps");
+    }
+
+    Ok(())
+}
+
+// 取り込まれないまま blocks ブロック以上経ったトランザクションを、手数料を上げて同じ nonce で署名し直す
+// 置き換えるため、ジャーナルとの重複は確認しない
+fn rebump_transactions(
+    config: &config::Config,
+    blocks: u64,
+    percent: u64,
+    limits: &rebump::Limits,
+) -> Result<()> {
+    let key = config.get_key()?;
+    let sender = key.address();
+    let client = config.get_rpc_client()?;
+    let report = tracker::check(
+        &require_journal(config.get_journal()?)?,
+        &client,
+        config.chain_id,
+    )?;
+    let cutoff = rebump::cutoff(&client, blocks)?;
+
+    let plan = rebump::plan(&report.pending, &sender, cutoff, limits, |message| {
+        rebump::bump(
+            message,
+            percent,
+            config.max_fee_per_gas,
+            config.max_priority_fee_per_gas,
+        )
+    })?;
+    for (entry, reason) in &plan.skipped {
+        eprintln!(
+            "Skipping nonce {} ({:?}): {reason}",
+            entry.nonce, entry.hash
+        );
+    }
+    for bump in plan.bumps {
+        let before = bump.entry.message()?.max_fee_per_gas;
+        let after = bump.message.max_fee_per_gas;
+        let raw = sign_with_policy(config, "rebump", bump.message, &key)?;
+        eprintln!(
+            "Bumped nonce {}: {:?} -> {:?} (max fee per gas {before} -> {after})",
+            bump.entry.nonce,
+            bump.entry.hash,
+            signer::keccak256(&raw)
+        );
+        println!("0x{}", hex::encode(raw));
+    }
+
+    Ok(())
+}
+
+// ポリシーを確認してからトランザクションに署名 (CLI のトランザクション署名はすべてここを通る)
+fn sign_with_policy(
+    config: &config::Config,
+    method: &str,
+    transaction_message: EIP1559TransactionMessage,
+    key: &signer::Key,
+) -> Result<Vec<u8>> {
+    let params = transaction::message_json(&config.tx_type, &transaction_message);
+    let message = transaction_message.clone();
+    let result = check_transaction_policy(config, &key.address(), &transaction_message)
+        .and_then(|()| config.tx_type.sign(transaction_message, key));
+
+    audit_signing(
+        config,
+        method,
+        params,
+        result.as_ref().map(signer::keccak256),
+    )?;
+    if let Ok(raw) = &result {
+        record_transaction(config, &message, key.address(), raw)?;
+    }
+    result
+}
+
+// JOURNAL_PATH・RAW_TX_DIR が設定されていれば記録する (記録できなかった場合は署名を出力しない)
+fn record_transaction(
+    config: &config::Config,
+    message: &EIP1559TransactionMessage,
+    sender: H160,
+    raw: &[u8],
+) -> Result<()> {
+    let entry = journal::Entry::new(&config.tx_type, message, sender, raw, "cli");
+    if let Some(journal) = config.get_journal()? {
+        journal.record(&entry)?;
+    }
+    if let Some(raw_tx_dir) = config.get_raw_tx_dir() {
+        raw_tx_dir.save(&entry)?;
+    }
+    Ok(())
+}
+
+fn check_transaction_policy(
+    config: &config::Config,
+    sender: &H160,
+    transaction_message: &EIP1559TransactionMessage,
+) -> Result<()> {
+    let policy = config.get_policy()?;
+    if let Some(policy) = &policy {
+        policy.check_transaction(transaction_message)?;
+        // 承認待ちのキューは署名サーバーにしかないため、閾値を超える場合は拒否する
+        policy.check_approval_threshold(transaction_message)?;
+    }
+    check_denylist(config, config.get_denylist()?.as_ref(), transaction_message)?;
+    config.verify_approval(transaction_message)?;
+    // 確認の入力の前に、何が送られて何を受け取るかを表示する
+    if config.simulate {
+        let client = config.get_rpc_client()?;
+        let changes = simulation::balance_changes_of(&client, sender, transaction_message)?;
+        let summary = simulation::report(&client, config.chain_id, &changes);
+        eprintln!("{}", t!("simulation.summary", summary = summary));
+    }
+    // 確認しなかった value は1日の合計に数えない
+    confirm_value(config, transaction_message)?;
+    if let Some(policy) = &policy {
+        policy.reserve_value(transaction_message.value)?;
+    }
+
+    Ok(())
+}
+
+// DENYLIST_PATH のアドレスへの送金や approve は拒否する (--allow-flagged なら警告のみ)
+fn check_denylist(
+    config: &config::Config,
+    denylist: Option<&denylist::Denylist>,
+    transaction_message: &EIP1559TransactionMessage,
+) -> Result<()> {
+    let Some(denylist) = denylist else {
+        return Ok(());
+    };
+    match denylist.check(transaction_message) {
+        Err(error::Error::FlaggedAddress { address, label }) if config.allow_flagged => {
+            eprintln!(
+                "{}",
+                t!("denylist.allowed", address = address, label = label)
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+// CONFIRM_ABOVE を超える value は確認する (端末以外からは --yes が必要)
+fn confirm_value(
+    config: &config::Config,
+    transaction_message: &EIP1559TransactionMessage,
+) -> Result<()> {
+    confirm::value(
+        config.chain_id,
+        transaction_message.value,
+        config.confirm_above,
+        config.yes,
+        confirm::is_interactive(),
+        &mut std::io::stdin().lock(),
+    )
+}
+
+// ポリシーを確認してからトランザクション以外 (メッセージ、EIP-712 など) のダイジェストに署名
+fn sign_digest(
+    config: &config::Config,
+    method: &str,
+    params: serde_json::Value,
+    key: &signer::Key,
+    digest: &H256,
+) -> Result<signer::Signature> {
+    let result = check_message_policy(config).and_then(|()| key.sign_hash(digest));
+
+    audit_signing(config, method, params, result.as_ref().map(|_| *digest))?;
+    result
+}
+
+// トランザクション以外への署名がポリシーで許可されているか確認
+fn check_message_policy(config: &config::Config) -> Result<()> {
+    if let Some(policy) = config.get_policy()? {
+        policy.check_message()?;
+    }
+
+    Ok(())
+}
+
+// AUDIT_LOG_PATH が設定されていれば CLI での署名も記録し、WEBHOOKS_PATH が設定されていれば通知する
+// 記録できなかった場合は署名を出力しない
+fn audit_signing(
+    config: &config::Config,
+    method: &str,
+    params: serde_json::Value,
+    result: std::result::Result<H256, &error::Error>,
+) -> Result<()> {
+    let record = audit::Record {
+        requester: "cli".to_string(),
+        method: method.to_string(),
+        params,
+        decision: if result.is_ok() {
+            audit::Decision::Signed
+        } else {
+            audit::Decision::Rejected
+        },
+        reason: result.as_ref().err().map(ToString::to_string),
+        hash: result.ok(),
+    };
+
+    let webhooks = config.get_webhooks()?;
+    if let Some(audit_log) = config.get_audit_log() {
+        audit_log.append(&record)?;
+    }
+    // CLI はすぐ終了するため、送り終えるまで待つ
+    if let Some(webhooks) = webhooks {
+        webhooks.deliver(&webhook::Event::from_record(&record));
+    }
+
+    Ok(())
+}
+
+// コントラクト呼び出しのトランザクションに署名
+fn sign_contract_call(
+    config: &config::Config,
+    contract: H160,
+    value: U256,
+    input: Vec<u8>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let params = params::Params {
+        nonce: tx.nonce,
+        to_address: contract,
+        value,
+        gas_limit: tx.gas_limit,
+        input,
+        function: None,
+        args: Vec::new(),
+        paymaster: None,
+        fee_currency: None,
+        chain_id: None,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+    };
+
+    sign_params(config, params)
+}
+
+// "12.5" のようなトークン単位の量を最小単位の整数に変換
+// decimals が指定されなければ RPC でトークンの decimals() を取得する
+fn token_amount(
+    config: &config::Config,
+    token: &H160,
+    amount: &str,
+    decimals: Option<u8>,
+) -> Result<U256> {
+    let decimals = match decimals {
+        Some(decimals) => decimals,
+        None => erc20::fetch_decimals(&config.get_rpc_client()?, token)?,
+    };
+
+    units::parse_units(amount, decimals)
+}
+
+fn erc20_transfer(
+    config: &config::Config,
+    token: H160,
+    to: H160,
+    amount: &str,
+    decimals: Option<u8>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let amount = token_amount(config, &token, amount, decimals)?;
+
+    sign_contract_call(
+        config,
+        token,
+        U256::zero(),
+        erc20::transfer_calldata(&to, amount),
+        tx,
+    )
+}
+
+// amount が None の場合は allowance を 0 にする (--revoke)
+fn erc20_approve(
+    config: &config::Config,
+    token: H160,
+    spender: H160,
+    amount: Option<String>,
+    decimals: Option<u8>,
+    allow_unlimited: bool,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let amount = match amount.as_deref() {
+        None => U256::zero(),
+        Some("max") => U256::MAX,
+        Some(amount) => token_amount(config, &token, amount, decimals)?,
+    };
+
+    if erc20::is_unlimited_approval(amount) {
+        if !allow_unlimited {
+            return Err(error::Error::UnlimitedApprovalNotConfirmed);
+        }
+        eprintln!(
+            "WARNING: signing an UNLIMITED approval. {} will be able to transfer all of your tokens until the allowance is revoked.",
+            address::to_checksum(&spender)
+        );
+    }
+
+    sign_contract_call(
+        config,
+        token,
+        U256::zero(),
+        erc20::approve_calldata(&spender, amount),
+        tx,
+    )
+}
+
+fn erc721_transfer(
+    config: &config::Config,
+    token: H160,
+    to: H160,
+    token_id: U256,
+    tx: TransactionArgs,
+) -> Result<()> {
+    // from には署名者のアドレスを使う
+    let from = config.get_key()?.address();
+    let input = erc721::safe_transfer_from_calldata(&from, &to, token_id);
+
+    sign_contract_call(config, token, U256::zero(), input, tx)
+}
+
+// ID が1つの場合は safeTransferFrom、複数の場合は safeBatchTransferFrom
+fn erc1155_transfer(
+    config: &config::Config,
+    token: H160,
+    to: H160,
+    ids: &[U256],
+    amounts: &[U256],
+    data: &[u8],
+    tx: TransactionArgs,
+) -> Result<()> {
+    // from には署名者のアドレスを使う
+    let from = config.get_key()?.address();
+    let input = match (ids, amounts) {
+        ([id], [amount]) => erc1155::safe_transfer_from_calldata(&from, &to, *id, *amount, data),
+        _ => erc1155::safe_batch_transfer_from_calldata(&from, &to, ids, amounts, data)?,
+    };
+
+    sign_contract_call(config, token, U256::zero(), input, tx)
+}
+
+// WETH は ETH と同じく18桁
+const WETH_DECIMALS: u8 = 18;
+
+fn weth_address(config: &config::Config, weth: Option<H160>) -> Result<H160> {
+    weth.or_else(|| weth::default_address(config.chain_id))
+        .ok_or(error::Error::UnknownWethAddress(config.chain_id))
+}
+
+fn weth_wrap(
+    config: &config::Config,
+    amount: &str,
+    weth: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let weth = weth_address(config, weth)?;
+    let value = units::parse_units(amount, WETH_DECIMALS)?;
+
+    sign_contract_call(config, weth, value, weth::deposit_calldata(), tx)
+}
+
+fn weth_unwrap(
+    config: &config::Config,
+    amount: &str,
+    weth: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let weth = weth_address(config, weth)?;
+    let amount = units::parse_units(amount, WETH_DECIMALS)?;
+
+    sign_contract_call(
+        config,
+        weth,
+        U256::zero(),
+        weth::withdraw_calldata(amount),
+        tx,
+    )
+}
+
+// 送金先ごとの量を decimals でスケーリング
+fn scale_payouts(
+    payouts: &[disperse::Payout],
+    decimals: u8,
+) -> Result<(Vec<H160>, Vec<U256>, U256)> {
+    let recipients = payouts.iter().map(|payout| payout.recipient).collect();
+    let values = payouts
+        .iter()
+        .map(|payout| units::parse_units(&payout.amount, decimals))
+        .collect::<Result<Vec<_>>>()?;
+    let total = disperse::total(&values)?;
+
+    Ok((recipients, values, total))
+}
+
+fn disperse_eth<P: AsRef<Path>>(
+    config: &config::Config,
+    payouts_path: P,
+    disperse: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let payouts = disperse::payouts_from_path(payouts_path)?;
+    let (recipients, values, total) = scale_payouts(&payouts, WETH_DECIMALS)?;
+    eprintln!("Total: {total} wei to {} recipients", recipients.len());
+
+    let disperse = disperse.unwrap_or(disperse::DISPERSE_ADDRESS);
+    let input = disperse::disperse_ether_calldata(&recipients, &values);
+    sign_contract_call(config, disperse, total, input, tx)
+}
+
+fn disperse_token<P: AsRef<Path>>(
+    config: &config::Config,
+    payouts_path: P,
+    token: H160,
+    decimals: Option<u8>,
+    disperse: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let payouts = disperse::payouts_from_path(payouts_path)?;
+    let decimals = match decimals {
+        Some(decimals) => decimals,
+        None => erc20::fetch_decimals(&config.get_rpc_client()?, &token)?,
+    };
+    let (recipients, values, total) = scale_payouts(&payouts, decimals)?;
+
+    let disperse = disperse.unwrap_or(disperse::DISPERSE_ADDRESS);
+    // disperseToken は transferFrom で送金元から引き出すため allowance が必要
+    eprintln!(
+        "Total: {total} base units to {} recipients (requires an allowance of at least {total} for {})",
+        recipients.len(),
+        address::to_checksum(&disperse)
+    );
+
+    let input = disperse::disperse_token_calldata(&token, &recipients, &values);
+    sign_contract_call(config, disperse, U256::zero(), input, tx)
+}
+
+fn sign_multicall<P: AsRef<Path>>(
+    config: &config::Config,
+    calls_json_path: P,
+    multicall: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let calls = multicall::calls_from_path(calls_json_path)?;
+    let input = multicall::aggregate3_calldata(&calls)?;
+    let multicall = multicall.unwrap_or(multicall::MULTICALL3_ADDRESS);
+
+    sign_contract_call(config, multicall, U256::zero(), input, tx)
+}
+
+// --init-code もしくは --init-code-file で指定された init code
+fn read_init_code(args: InitCodeArgs) -> Result<Vec<u8>> {
+    match (args.hex, args.file) {
+        (Some(init_code), _) => Ok(init_code),
+        (None, Some(path)) => deploy::read_init_code_file(path),
+        (None, None) => Err(error::Error::InvalidArgument(
+            "--init-code or --init-code-file is required".to_string(),
+        )),
+    }
+}
+
+fn deploy_contract(
+    config: &config::Config,
+    init_code: InitCodeArgs,
+    constructor: Option<String>,
+    args: &[String],
+    value: U256,
+    tx: TransactionArgs,
+) -> Result<()> {
+    // バイトコードの後ろにABIエンコードしたコンストラクタ引数を連結
+    let mut input = read_init_code(init_code)?;
+    if let Some(constructor) = constructor {
+        input.extend_from_slice(&calldata::encode_constructor_args(&constructor, args)?);
+    }
+
+    let key = config.get_key()?;
+    let (nonce, signed_transaction) = sign_with_nonce(config, "deploy", tx.nonce, &key, |nonce| {
+        transaction::build_create_message(config, nonce, value, tx.gas_limit, input.clone())
+    })?;
+    let address = deploy::create_address(&key.address(), nonce);
+    // 標準出力は raw トランザクションのみにするため標準エラー出力に出す
+    eprintln!("Predicted address: {}", address::to_checksum(&address));
+    println!("0x{}", hex::encode(signed_transaction));
+
+    Ok(())
+}
+
+fn print_create2_address(
+    factory: Option<H160>,
+    salt: &H256,
+    init_code_hash: Option<H256>,
+    init_code: InitCodeArgs,
+) -> Result<()> {
+    let factory = factory.unwrap_or(deploy::CREATE2_FACTORY_ADDRESS);
+    let init_code_hash = match init_code_hash {
+        Some(hash) => hash,
+        None => signer::keccak256(read_init_code(init_code)?),
+    };
+
+    let address = deploy::create2_address(&factory, salt, &init_code_hash);
+    println!("{}", address::to_checksum(&address));
+
+    Ok(())
+}
+
+fn create2_deploy(
+    config: &config::Config,
+    factory: Option<H160>,
+    salt: &H256,
+    init_code: InitCodeArgs,
+    expect_address: Option<H160>,
+    tx: TransactionArgs,
+) -> Result<()> {
+    let factory = factory.unwrap_or(deploy::CREATE2_FACTORY_ADDRESS);
+    // 既知でないファクトリーでもコントラクトの作成としてポリシーを確認する
+    if let Some(policy) = config.get_policy()? {
+        policy.check_create2(&factory)?;
+    }
+    let init_code = read_init_code(init_code)?;
+
+    // 署名する前に予測アドレスを確認
+    let address = deploy::create2_address(&factory, salt, &signer::keccak256(&init_code));
+    match expect_address {
+        Some(expected) if expected != address => {
+            return Err(error::Error::UnexpectedDeploymentAddress {
+                expected: address::to_checksum(&expected),
+                actual: address::to_checksum(&address),
+            });
+        }
+        _ => {}
+    }
+    // 標準出力は raw トランザクションのみにするため標準エラー出力に出す
+    eprintln!("Predicted address: {}", address::to_checksum(&address));
+
+    let input = deploy::create2_factory_calldata(salt, &init_code);
+    sign_contract_call(config, factory, U256::zero(), input, tx)
+}
+
+fn serve(config: config::Config, source: config::ConfigSource, args: ServeArgs) -> Result<()> {
+    let mut server = match &args.key_file {
+        Some(path) => {
+            let idle_timeout =
+                Some(Duration::from_secs(args.unlock_timeout)).filter(|timeout| !timeout.is_zero());
+            let key = session::KeySession::locked(
+                keystore::EncryptedKey::from_path(path)?,
+                idle_timeout,
+            )?;
+            eprintln!("The key file is locked; unlock it with the unlock command");
+            server::Server::with_key_session(config, key)?
+        }
+        None => server::Server::new(config)?,
+    };
+    server = server.with_config_source(source);
+    // SIGHUP で終了せずに設定を読み直す (鍵ファイルはロックする)
+    signal::handle_hangup()?;
+    if let Some(command) = args.approval_command {
+        server = server.with_approval_hook(approval::ApprovalHook::new(command));
+    }
+    if let Some(path) = &args.auth_config {
+        server = server.with_authenticator(auth::Authenticator::from_path(path)?);
+    }
+    server = server.with_rate_limits(ratelimit::RateLimits {
+        requests_per_minute: args.max_requests_per_minute,
+        value_per_day: args
+            .max_value_per_day
+            .as_deref()
+            .map(|value| units::parse_units(value, WETH_DECIMALS))
+            .transpose()?,
+    });
+    let account = address::to_checksum(&server.address());
+
+    // ファイルシステムのパーミッションでアクセスを制御する
+    if let Some(path) = args.unix_socket {
+        let listener = server::bind_unix_socket(&path, args.socket_mode)?;
+        eprintln!(
+            "Serving {account} on unix:{} (mode {:o})",
+            path.display(),
+            args.socket_mode
+        );
+        return server.run_unix(listener);
+    }
+
+    let listener = TcpListener::bind(args.listen)?;
+    let tls_config = match (&args.tls_cert, &args.tls_key) {
+        (Some(certificate), Some(private_key)) => Some(tls::server_config(
+            certificate,
+            private_key,
+            args.tls_client_ca.as_deref(),
+        )?),
+        _ => None,
+    };
+
+    // クライアント認証がないため、ループバック以外で待ち受ける場合は警告する
+    if !args.listen.ip().is_loopback() && args.tls_client_ca.is_none() && args.auth_config.is_none()
+    {
+        eprintln!(
+            "WARNING: listening on {} exposes signing to the network without authentication.",
+            args.listen
+        );
+    }
+
+    match tls_config {
+        Some(tls_config) => {
+            eprintln!("Serving {account} on https://{}", listener.local_addr()?);
+            server.run_tls(listener, tls_config)
+        }
+        None => {
+            eprintln!("Serving {account} on http://{}", listener.local_addr()?);
+            server.run(listener)
+        }
+    }
+}
+
+// EIP-1193 の provider.request と同じように1つのメソッドを呼び、結果を出力する
+fn provider_request(config: config::Config, method: &str, params: &str) -> Result<()> {
+    let params: serde_json::Value = json::parse(params)?;
+    let provider = provider::Provider::new(server::Server::new(config)?);
+    let result = provider.request(method, params)?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+
+    Ok(())
+}
+
+// stdout は応答だけに使う
+// 依頼されたハッシュにそのまま署名するため sign-hash と同じく明示的なフラグを要求し、
+// 内容を確認できないポリシーは適用したふりをせずに拒否する
+fn serve_plugin(config: &config::Config, confirmed: bool) -> Result<()> {
+    if !confirmed {
+        return Err(error::Error::RawHashSigningNotConfirmed);
+    }
+    if config.policy_path.is_some() {
+        return Err(error::Error::InvalidArgument(
+            "plugin serve signs raw hashes and cannot apply POLICY_PATH; set the policy in the process that starts the plugin".to_string(),
+        ));
+    }
+
+    let signing_key = config.get_signing_key()?;
+    plugin::serve(
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        &signing_key,
+        config.chain_id,
+        |hash| {
+            audit_signing(
+                config,
+                "plugin-sign-hash",
+                serde_json::json!({ "hash": hash }),
+                Ok(*hash),
+            )
+        },
+    )
+}
+
+fn encrypt_key(config: &config::Config, output: &Path, iterations: u32) -> Result<()> {
+    let signing_key = config.get_signing_key()?;
+    let password = read_password()?;
+    if password.expose().is_empty() {
+        return Err(error::Error::InvalidArgument(
+            "password must not be empty".to_string(),
+        ));
+    }
+
+    let key = keystore::EncryptedKey::encrypt(&signing_key, password.expose(), iterations)?;
+    key.save(output)?;
+    eprintln!(
+        "Encrypted the key of {} into {}",
+        address::to_checksum(&key.address()?),
+        output.display()
+    );
+
+    Ok(())
+}
+
+// geth・Parity の鍵ファイルで見つかった形式と癖を表示する (--decrypt でアドレスも確かめる)
+fn inspect_keystore<P: AsRef<Path>>(path: P, decrypt: bool) -> Result<()> {
+    let keystore = web3keystore::Web3Keystore::from_path(&path)?;
+    let mut report = keystore.describe();
+    if decrypt {
+        let password = read_password()?;
+        let signing_key = keystore.decrypt(password.expose())?;
+        report["decrypted_address"] = serde_json::json!(address::to_checksum(
+            &address::from_signing_key(&signing_key)
+        ));
+    }
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
+
+// geth・Parity の鍵ファイルを復号し、同じパスワードで serve --key-file の鍵ファイルに暗号化し直す
+fn import_keystore<P: AsRef<Path>>(path: P, output: &Path, iterations: u32) -> Result<()> {
+    let keystore = web3keystore::Web3Keystore::from_path(&path)?;
+    for quirk in &keystore.quirks {
+        eprintln!("Note: {quirk}");
+    }
+    let password = read_password()?;
+    if password.expose().is_empty() {
+        return Err(error::Error::InvalidArgument(
+            "password must not be empty".to_string(),
+        ));
+    }
+    let signing_key = keystore.decrypt(password.expose())?;
+
+    let key = keystore::EncryptedKey::encrypt(&signing_key, password.expose(), iterations)?;
+    key.save(output)?;
+    eprintln!(
+        "Imported the {} keystore of {} into {}",
+        keystore.format,
+        address::to_checksum(&key.address()?),
+        output.display()
+    );
+
+    Ok(())
+}
+
+// 標準入力の1行目をパスワードとして読む (末尾の改行は除く)
+fn read_password() -> Result<Secret<String>> {
+    eprint!("Password: ");
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+    Ok(Secret::new(
+        password.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+fn unlock_server(server: &str, token: Option<&Secret<String>>, unlock: bool) -> Result<()> {
+    let password = if unlock { Some(read_password()?) } else { None };
+    let status = session::request(server, token, password.as_ref())?;
+    println!("{}", serde_json::to_string_pretty(&status)?);
+
+    Ok(())
+}
+
+fn decide_approval(
+    server: &str,
+    request_id: &str,
+    token: Option<&Secret<String>>,
+    approve: bool,
+) -> Result<()> {
+    let request = approvals::decide(server, request_id, token, approve)?;
+    println!("{}", serde_json::to_string_pretty(&request)?);
+
+    Ok(())
+}
+
+// 入力ファイルの JSON を読む (監査ログに記録するものは Value のまま読む)
+fn read_json_file<T: serde::de::DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let json = std::fs::read_to_string(&path).map_err(error::Error::in_file(&path))?;
+    json::parse(&json).map_err(error::Error::in_file(path))
+}
+
+fn verify_audit_log(log: std::path::PathBuf) -> Result<()> {
+    let (count, head) = audit::verify(log)?;
+    println!("{count} entries verified");
+    println!("Head hash: {head:?}");
+
+    Ok(())
+}
+
+fn encode_calldata(command: CalldataCommand) -> Result<()> {
+    let CalldataCommand::Encode {
+        signature,
+        args,
+        file,
+    } = command;
+
+    let calldata = match (file, signature) {
+        (Some(path), _) => calldata::FunctionCall::from_path(path)?.encode()?,
+        (None, Some(signature)) => calldata::encode_from_args(&signature, &args)?,
+        (None, None) => unreachable!("clap requires either signature or --file"),
+    };
+    println!("0x{}", hex::encode(calldata));
+
+    Ok(())
+}
+
+fn sign_message(
+    config: &config::Config,
+    message: &str,
+    is_hex: bool,
+    validator: Option<H160>,
+) -> Result<()> {
+    let message_bytes = if is_hex {
+        hex::decode(message.strip_prefix("0x").unwrap_or(message))?
+    } else {
+        message.as_bytes().to_vec()
+    };
+
+    let hash = match validator {
+        Some(validator) => message::intended_validator_hash(&validator, &message_bytes),
+        None => message::personal_message_hash(&message_bytes),
+    };
+    let params = serde_json::json!({
+        "message": message,
+        "hex": is_hex,
+        "validator": validator,
+    });
+    let key = config.get_key()?;
+    let signature = sign_digest(config, "sign-message", params, &key, &hash)?;
+
+    // 65バイトの署名 (r, s, v) を16進数文字列として出力
+    println!("0x{}", hex::encode(signature.to_bytes()));
+
+    Ok(())
+}
+
+fn sign_hash(config: &config::Config, hash: &H256, confirmed: bool) -> Result<()> {
+    // 任意のダイジェストへの署名はトランザクションや Permit の承認にもなり得るため明示的なフラグを要求
+    if !confirmed {
+        return Err(error::Error::RawHashSigningNotConfirmed);
+    }
+
+    let params = serde_json::json!({ "hash": hash });
+    // ポリシーはダイジェストの中身を確認できないため、ポリシーで制限している場合は署名しない
+    if config.policy_path.is_some() {
+        let error = error::Error::RawHashSigningWithPolicy;
+        audit_signing(config, "sign-hash", params, Err(&error))?;
+        return Err(error);
+    }
+    let key = config.get_key()?;
+    let signature = sign_digest(config, "sign-hash", params, &key, hash)?;
+
+    // 65バイトの署名 (r, s, v) を16進数文字列として出力
+    println!("0x{}", hex::encode(signature.to_bytes()));
+
+    Ok(())
+}
+
+fn sign_typed_data<P: AsRef<Path>>(
+    config: &config::Config,
+    typed_data_json_path: P,
+    debug_hashes: bool,
+) -> Result<()> {
+    let typed_data = eip712::TypedData::from_path(&typed_data_json_path)?;
+    let params = read_json_file(typed_data_json_path)?;
+
+    let digest = typed_data.digest()?;
+    let key = config.get_key()?;
+    let signature = sign_digest(config, "sign-typed-data", params, &key, &digest)?;
+
+    // 検証用にダイジェストも合わせて出力
+    let mut output = serde_json::json!({
+        "digest": digest,
+        "signature": format!("0x{}", hex::encode(signature.to_bytes())),
+    });
+    // コントラクトの DOMAIN_SEPARATOR などと突き合わせるための中間値
+    if debug_hashes {
+        output["domainSeparator"] = serde_json::to_value(typed_data.domain_separator()?)?;
+        output["messageHash"] = serde_json::to_value(typed_data.message_hash()?)?;
+        output["structHashes"] = serde_json::to_value(typed_data.struct_hashes()?)?;
+    }
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+fn sign_safe_transaction<P: AsRef<Path>>(
+    config: &config::Config,
+    safe_tx_json_path: P,
+    propose: bool,
+    service_url: Option<String>,
+    origin: Option<String>,
+) -> Result<()> {
+    let safe_transaction = safe::SafeTransaction::from_path(&safe_tx_json_path)?;
+    let params = read_json_file(safe_tx_json_path)?;
+
+    // オーナーとして safeTxHash に署名
+    let safe_tx_hash = safe_transaction.safe_tx_hash(config.chain_id)?;
+    let key = config.get_key()?;
+    let signature = sign_digest(config, "safe-sign", params, &key, &safe_tx_hash)?;
+    let sender = key.address();
+
+    if propose {
+        let service_url = match service_url {
+            Some(url) => url,
+            None => safe::default_service_url(config.chain_id)
+                .ok_or(error::Error::UnknownSafeServiceUrl(config.chain_id))?
+                .to_string(),
+        };
+        let proposal =
+            safe::Proposal::new(&safe_transaction, safe_tx_hash, &sender, signature, origin);
+        safe::propose(&service_url, &safe_transaction.safe, &proposal)?;
+    }
+
+    let output = serde_json::json!({
+        "safeTxHash": safe_tx_hash,
+        "sender": address::to_checksum(&sender),
+        "signature": format!("0x{}", hex::encode(signature.to_bytes())),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+// MetaMask のトランザクションを params.json にして出力する
+// 要求元や MetaMask の手数料は使わないため、確認用に標準エラー出力に出す
+fn import_metamask<P: AsRef<Path>>(path: P, id: Option<&str>) -> Result<()> {
+    let imported = metamask::from_path(path, id)?;
+    if let Some(id) = &imported.id {
+        eprintln!(
+            "MetaMask transaction {id}: {} on chain {} ({})",
+            imported.origin.as_deref().unwrap_or("unknown origin"),
+            imported.chain_id.as_deref().unwrap_or("unknown"),
+            imported.status.as_deref().unwrap_or("unknown status"),
+        );
+    }
+    if let Some(from) = imported.from {
+        eprintln!(
+            "from: {} (sign with this key; fees come from MAX_FEE_PER_GAS and MAX_PRIORITY_FEE_PER_GAS)",
+            address::to_checksum(&from)
+        );
+    }
+    println!("{}", serde_json::to_string_pretty(&imported.params)?);
+
+    Ok(())
+}
+
+// params.json を Safe の Transaction Builder のバッチとして出力する
+// 同じパラメータを EOA で署名するか Safe で実行するかを選べる (nonce と gas_limit は Safe 側で決まるため使わない)
+fn export_safe_batch(
+    config: &config::Config,
+    paths: &[std::path::PathBuf],
+    safe: Option<H160>,
+    name: &str,
+    options: params::ParseOptions,
+) -> Result<()> {
+    let params = paths
+        .iter()
+        .map(|path| {
+            let params = options
+                .apply(|| params::Params::from_path(path))?
+                .encode_function()?;
+            params
+                .check_chain_id(config.chain_id)
+                .map_err(error::Error::in_file(path))?;
+            if params.paymaster.is_some() || params.fee_currency.is_some() {
+                return Err(error::Error::InvalidArgument(format!(
+                    "{}: paymaster and fee_currency are not supported by safe export",
+                    path.display()
+                )));
+            }
+            Ok(params)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let created_at = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let batch =
+        safe::transaction_builder_batch(config.chain_id, safe.as_ref(), name, &params, created_at);
+    println!("{}", serde_json::to_string_pretty(&batch)?);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sign_permit(
+    config: &config::Config,
+    token: H160,
+    spender: H160,
+    value: U256,
+    deadline: U256,
+    nonce: Option<U256>,
+    name: Option<String>,
+    version: Option<String>,
+) -> Result<()> {
+    // RPC で値を取得する前に拒否する
+    check_message_policy(config)?;
+    let key = config.get_key()?;
+    let owner = key.address();
+
+    // 指定されなかった値は RPC で取得
+    let name = match name {
+        Some(name) => name,
+        None => permit::fetch_name(&config.get_rpc_client()?, &token)?,
+    };
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None => permit::fetch_nonce(&config.get_rpc_client()?, &token, &owner)?,
+    };
+    let version = match version {
+        Some(version) => version,
+        None => config
+            .get_rpc_client()
+            .and_then(|client| permit::fetch_version(&client, &token))
+            .unwrap_or_else(|_| "1".to_string()),
+    };
+
+    let permit = permit::Permit {
+        token,
+        name,
+        version,
+        chain_id: config.chain_id,
+        owner,
+        spender,
+        value,
+        nonce,
+        deadline,
+    };
+    let digest = permit.digest()?;
+    let params = serde_json::json!({
+        "token": address::to_checksum(&permit.token),
+        "name": permit.name,
+        "version": permit.version,
+        "chainId": permit.chain_id,
+        "owner": address::to_checksum(&permit.owner),
+        "spender": address::to_checksum(&permit.spender),
+        "value": permit.value,
+        "nonce": permit.nonce,
+        "deadline": permit.deadline,
+    });
+    let signature = sign_digest(config, "permit", params, &key, &digest)?;
+
+    // permit(owner, spender, value, deadline, v, r, s) の引数として使える形で出力
+    let output = serde_json::json!({
+        "owner": address::to_checksum(&permit.owner),
+        "spender": address::to_checksum(&permit.spender),
+        "value": permit.value.to_string(),
+        "nonce": permit.nonce.to_string(),
+        "deadline": permit.deadline.to_string(),
+        "digest": digest,
+        "signature": format!("0x{}", hex::encode(signature.to_bytes())),
+        "v": signature.to_bytes()[64],
+        "r": signature.r,
+        "s": signature.s,
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::signer::keccak256;
+use ethereum_types::{H160, H256};
+
+// EIP-191 version 0x45 (personal_sign) のプレフィックス
+const PERSONAL_MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+// personal_sign 用のハッシュを計算
+// keccak256("\x19Ethereum Signed Message:\n" + len(message) + message)
+pub fn personal_message_hash(message: &[u8]) -> H256 {
+    let mut buf = Vec::with_capacity(PERSONAL_MESSAGE_PREFIX.len() + 20 + message.len());
+    buf.extend_from_slice(PERSONAL_MESSAGE_PREFIX.as_bytes());
+    // 長さは10進数の文字列で付与する
+    buf.extend_from_slice(message.len().to_string().as_bytes());
+    buf.extend_from_slice(message);
+
+    keccak256(buf)
+}
+
+// EIP-191 version 0x00 (intended validator) 用のハッシュを計算
+// keccak256(0x19 + 0x00 + validator address + data)
+pub fn intended_validator_hash(validator: &H160, data: &[u8]) -> H256 {
+    let mut buf = Vec::with_capacity(2 + 20 + data.len());
+    buf.extend_from_slice(&[0x19, 0x00]);
+    buf.extend_from_slice(validator.as_bytes());
+    buf.extend_from_slice(data);
+
+    keccak256(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{
+        self,
+        tests::{TEST_ADDRESS, recover_address, test_signing_key},
+    };
+
+    #[test]
+    fn test_personal_message_hash_hello_world() {
+        // ethers.js の hashMessage("Hello World") と同じ値
+        assert_eq!(
+            personal_message_hash(b"Hello World"),
+            "0xa1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_personal_message_hash_length_is_decimal() {
+        // 10バイト以上のメッセージでも長さは10進数で付与される
+        let message = b"0123456789abcdef";
+        let expected = keccak256(b"\x19Ethereum Signed Message:\n160123456789abcdef");
+
+        assert_eq!(personal_message_hash(message), expected);
+    }
+
+    #[test]
+    fn test_personal_message_hash_empty() {
+        assert_eq!(
+            personal_message_hash(b""),
+            keccak256(b"\x19Ethereum Signed Message:\n0")
+        );
+    }
+
+    #[test]
+    fn test_personal_sign_recoverable() {
+        let hash = personal_message_hash(b"Hello World");
+        let signature = signer::sign_hash(&test_signing_key(), &hash).unwrap();
+
+        assert_eq!(
+            recover_address(&hash, &signature),
+            TEST_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_intended_validator_hash() {
+        let validator: H160 = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        let expected = keccak256(
+            hex::decode("19005aaeb6053f3e94c9b9a09f33669435e7ef1beaed48656c6c6f").unwrap(),
+        );
+
+        assert_eq!(intended_validator_hash(&validator, b"Hello"), expected);
+    }
+
+    #[test]
+    fn test_intended_validator_hash_differs_by_validator() {
+        let a = H160::repeat_byte(0xaa);
+        let b = H160::repeat_byte(0xbb);
+
+        assert_ne!(
+            intended_validator_hash(&a, b"data"),
+            intended_validator_hash(&b, b"data")
+        );
+        // personal_sign とも異なる
+        assert_ne!(
+            intended_validator_hash(&a, b"data"),
+            personal_message_hash(b"data")
+        );
+    }
+}
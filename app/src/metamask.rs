@@ -0,0 +1,294 @@
+use crate::{
+    Result,
+    de::{deserialize_optional_hex_bytes, deserialize_optional_u256},
+    error::Error,
+    json,
+};
+use ethereum_types::{H160, U256};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::Path;
+
+// MetaMask の txParams (dapp が eth_sendTransaction で渡し、確認画面の「16進データ」に出るトランザクション)
+// 手数料は MetaMask の推定値のため読まない (MAX_FEE_PER_GAS・MAX_PRIORITY_FEE_PER_GAS を使う)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TxParams {
+    #[serde(default)]
+    from: Option<H160>,
+    #[serde(default)]
+    to: Option<H160>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    value: Option<U256>,
+    #[serde(
+        default,
+        alias = "gasLimit",
+        deserialize_with = "deserialize_optional_u256"
+    )]
+    gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    nonce: Option<U256>,
+    #[serde(
+        default,
+        alias = "input",
+        deserialize_with = "deserialize_optional_hex_bytes"
+    )]
+    data: Option<Vec<u8>>,
+}
+
+// 状態ログ (設定 → 高度な設定 → 状態ログのダウンロード) の1件のトランザクション
+// id は新しい版では UUID の文字列、古い版では数値
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionMeta {
+    #[serde(default)]
+    id: Value,
+    #[serde(default)]
+    chain_id: Option<String>,
+    #[serde(default)]
+    origin: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    tx_params: TxParams,
+}
+
+// 取り込んだトランザクション
+#[derive(Debug)]
+pub struct Imported {
+    // 状態ログなどから取り出した場合の id・チェーン・要求元・状態 (確認用)
+    pub id: Option<String>,
+    pub chain_id: Option<String>,
+    pub origin: Option<String>,
+    pub status: Option<String>,
+    pub from: Option<H160>,
+    // このツールの params.json
+    pub params: Value,
+}
+
+fn id_string(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+// 状態ログのトランザクションの一覧 (版によって置き場所と形が違う)
+fn transaction_list(log: &Value) -> Option<Vec<Value>> {
+    let metamask = &log["metamask"];
+    [
+        &metamask["transactions"],
+        &metamask["TransactionController"]["transactions"],
+        &log["transactions"],
+    ]
+    .into_iter()
+    .find_map(|transactions| match transactions {
+        Value::Array(transactions) => Some(transactions.clone()),
+        // 古い版は id → トランザクションのオブジェクト
+        Value::Object(transactions) => Some(transactions.values().cloned().collect()),
+        _ => None,
+    })
+}
+
+// id が指定されていればそのトランザクション、なければ承認待ち (unapproved) の1件を選ぶ
+fn select(transactions: Vec<TransactionMeta>, id: Option<&str>) -> Result<TransactionMeta> {
+    let candidates = |transactions: &[TransactionMeta]| {
+        transactions
+            .iter()
+            .map(|transaction| {
+                format!(
+                    "{} ({}, {})",
+                    id_string(&transaction.id).unwrap_or_default(),
+                    transaction.status.as_deref().unwrap_or("unknown status"),
+                    transaction.origin.as_deref().unwrap_or("unknown origin")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if let Some(id) = id {
+        let list = candidates(&transactions);
+        return transactions
+            .into_iter()
+            .find(|transaction| id_string(&transaction.id).as_deref() == Some(id))
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("no transaction with id {id} (found: {list})"))
+            });
+    }
+
+    let unapproved: Vec<usize> = transactions
+        .iter()
+        .enumerate()
+        .filter(|(_, transaction)| transaction.status.as_deref() == Some("unapproved"))
+        .map(|(index, _)| index)
+        .collect();
+    match (transactions.len(), unapproved.as_slice()) {
+        (1, _) => Ok(transactions.into_iter().next().unwrap()),
+        (_, [index]) => Ok(transactions.into_iter().nth(*index).unwrap()),
+        (0, _) => Err(Error::InvalidArgument(
+            "no transactions in the file".to_string(),
+        )),
+        _ => Err(Error::InvalidArgument(format!(
+            "{} transactions in the file, choose one with --id: {}",
+            transactions.len(),
+            candidates(&transactions)
+        ))),
+    }
+}
+
+fn to_params(tx_params: &TxParams) -> Result<Value> {
+    let Some(to) = tx_params.to else {
+        return Err(Error::InvalidArgument(
+            "txParams: to is missing (contract creation), use deploy instead".to_string(),
+        ));
+    };
+    let Some(gas) = tx_params.gas else {
+        return Err(Error::InvalidArgument(
+            "txParams: gas is missing (confirm the transaction in MetaMask to estimate it)"
+                .to_string(),
+        ));
+    };
+
+    let mut params = json!({
+        "to_address": to,
+        "value": format!("{:#x}", tx_params.value.unwrap_or_default()),
+        "gas_limit": format!("{gas:#x}"),
+        "input": format!("0x{}", hex::encode(tx_params.data.as_deref().unwrap_or_default())),
+    });
+    if let Some(nonce) = tx_params.nonce {
+        params["nonce"] = json!(format!("{nonce:#x}"));
+    }
+    Ok(params)
+}
+
+// txParams、1件のトランザクション、その配列、状態ログのいずれかを読む
+pub fn parse(content: &str, id: Option<&str>) -> Result<Imported> {
+    let value: Value = json::parse(content)?;
+    let meta = if value.get("txParams").is_some() {
+        serde_json::from_value(value)?
+    } else if let Some(transactions) = value
+        .as_array()
+        .cloned()
+        .or_else(|| transaction_list(&value))
+    {
+        let transactions = transactions
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<std::result::Result<Vec<TransactionMeta>, _>>()?;
+        select(transactions, id)?
+    } else {
+        TransactionMeta {
+            id: Value::Null,
+            chain_id: None,
+            origin: None,
+            status: None,
+            tx_params: serde_json::from_value(value)?,
+        }
+    };
+
+    Ok(Imported {
+        id: id_string(&meta.id),
+        params: to_params(&meta.tx_params)?,
+        from: meta.tx_params.from,
+        chain_id: meta.chain_id,
+        origin: meta.origin,
+        status: meta.status,
+    })
+}
+
+pub fn from_path<P: AsRef<Path>>(path: P, id: Option<&str>) -> Result<Imported> {
+    let content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+    parse(&content, id).map_err(Error::in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+
+    const TX_PARAMS: &str = r#"{
+        "from": "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+        "to": "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238",
+        "value": "0x0",
+        "data": "0xa9059cbb",
+        "gas": "0xea60",
+        "maxFeePerGas": "0x6fc23ac00",
+        "maxPriorityFeePerGas": "0x3b9aca00",
+        "type": "0x2"
+    }"#;
+
+    fn meta(id: &str, status: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "chainId": "0xaa36a7", "origin": "https://app.uniswap.org", "status": "{status}", "time": 1700000000000, "txParams": {TX_PARAMS}}}"#
+        )
+    }
+
+    #[test]
+    fn test_tx_params() {
+        let imported = parse(TX_PARAMS, None).unwrap();
+        assert_eq!(imported.id, None);
+        assert!(imported.from.is_some());
+
+        // そのまま params.json として読める
+        let params: Params = serde_json::from_value(imported.params).unwrap();
+        assert_eq!(params.gas_limit, U256::from(60000));
+        assert_eq!(params.input, [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(params.nonce, None);
+    }
+
+    #[test]
+    fn test_state_log() {
+        let log = format!(
+            r#"{{"metamask": {{"transactions": [{}, {}]}}}}"#,
+            meta("a1", "confirmed"),
+            meta("b2", "unapproved")
+        );
+        let imported = parse(&log, None).unwrap();
+        assert_eq!(imported.id.as_deref(), Some("b2"));
+        assert_eq!(imported.chain_id.as_deref(), Some("0xaa36a7"));
+        assert_eq!(imported.origin.as_deref(), Some("https://app.uniswap.org"));
+        assert_eq!(
+            parse(&log, Some("a1")).unwrap().status.as_deref(),
+            Some("confirmed")
+        );
+        assert!(parse(&log, Some("c3")).is_err());
+
+        // 古い版 (TransactionController の下に id → トランザクション)
+        let log = format!(
+            r#"{{"metamask": {{"TransactionController": {{"transactions": {{"1": {}}}}}}}}}"#,
+            meta("1", "submitted")
+        );
+        assert_eq!(parse(&log, None).unwrap().id.as_deref(), Some("1"));
+
+        // 1件のトランザクション
+        assert_eq!(
+            parse(&meta("c3", "approved"), None).unwrap().id.as_deref(),
+            Some("c3")
+        );
+    }
+
+    #[test]
+    fn test_rejected() {
+        // 承認待ちが複数あれば --id が必要
+        let log = format!(
+            "[{}, {}]",
+            meta("a1", "unapproved"),
+            meta("b2", "unapproved")
+        );
+        let error = parse(&log, None).unwrap_err().to_string();
+        assert!(error.contains("--id") && error.contains("b2"), "{error}");
+
+        let error = parse(r#"{"gas": "0x5208", "data": "0x6080"}"#, None)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("deploy"), "{error}");
+        let error = parse(
+            r#"{"to": "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"}"#,
+            None,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("gas"), "{error}");
+    }
+}
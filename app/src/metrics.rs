@@ -0,0 +1,230 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+// 署名にかかった時間のヒストグラムのバケット (秒)
+// 承認フックで人の操作を待つ場合もあるため長めまで用意する
+const SIGNING_DURATION_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0];
+
+// ラベル付きのカウンター
+struct CounterVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl CounterVec {
+    fn new(name: &'static str, help: &'static str, label_names: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn inc(&self, labels: &[&str]) {
+        debug_assert_eq!(labels.len(), self.label_names.len());
+        let labels = labels.iter().map(|label| label.to_string()).collect();
+        *self.values.lock().unwrap().entry(labels).or_default() += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", self.name);
+        for (labels, value) in self.values.lock().unwrap().iter() {
+            let labels: Vec<_> = self
+                .label_names
+                .iter()
+                .zip(labels)
+                .map(|(name, value)| format!("{name}=\"{}\"", escape_label(value)))
+                .collect();
+            let _ = writeln!(out, "{}{{{}}} {value}", self.name, labels.join(","));
+        }
+    }
+}
+
+#[derive(Default)]
+struct HistogramState {
+    // 各バケット以下の観測数 (累積ではない)
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+// ラベルなしのヒストグラム
+struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    buckets: &'static [f64],
+    state: Mutex<HistogramState>,
+}
+
+impl Histogram {
+    fn new(name: &'static str, help: &'static str, buckets: &'static [f64]) -> Self {
+        Self {
+            name,
+            help,
+            buckets,
+            state: Mutex::new(HistogramState {
+                counts: vec![0; buckets.len()],
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn observe(&self, value: f64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(i) = self.buckets.iter().position(|bound| value <= *bound) {
+            state.counts[i] += 1;
+        }
+        state.sum += value;
+        state.count += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let state = self.state.lock().unwrap();
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        let mut cumulative = 0;
+        for (bound, count) in self.buckets.iter().zip(&state.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{}_bucket{{le=\"{bound}\"}} {cumulative}", self.name);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", self.name, state.count);
+        let _ = writeln!(out, "{}_sum {}", self.name, state.sum);
+        let _ = writeln!(out, "{}_count {}", self.name, state.count);
+    }
+}
+
+// ラベル値のエスケープ (\ と " と改行)
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// 署名サーバーのメトリクス (Prometheus のテキスト形式で出力する)
+pub struct Metrics {
+    requests: CounterVec,
+    signatures: CounterVec,
+    rejections: CounterVec,
+    rpc_errors: CounterVec,
+    signing_duration: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            requests: CounterVec::new(
+                "signer_requests_total",
+                "Requests handled, by method or endpoint and status.",
+                &["method", "status"],
+            ),
+            signatures: CounterVec::new(
+                "signer_signatures_total",
+                "Signatures produced, by chain id and kind.",
+                &["chain_id", "kind"],
+            ),
+            rejections: CounterVec::new(
+                "signer_rejections_total",
+                "Signing requests rejected, by rule.",
+                &["rule"],
+            ),
+            rpc_errors: CounterVec::new(
+                "signer_rpc_errors_total",
+                "Failed requests to the upstream JSON-RPC node, by method.",
+                &["method"],
+            ),
+            signing_duration: Histogram::new(
+                "signer_signing_duration_seconds",
+                "Time from receiving a signing request to producing the signature.",
+                SIGNING_DURATION_BUCKETS,
+            ),
+        }
+    }
+
+    pub fn record_request(&self, method: &str, ok: bool) {
+        self.requests
+            .inc(&[method, if ok { "ok" } else { "error" }]);
+    }
+
+    pub fn record_signature(&self, chain_id: u64, kind: &str, duration: Duration) {
+        self.signatures.inc(&[&chain_id.to_string(), kind]);
+        self.signing_duration.observe(duration.as_secs_f64());
+    }
+
+    pub fn record_rejection(&self, rule: &str) {
+        self.rejections.inc(&[rule]);
+    }
+
+    pub fn record_rpc_error(&self, method: &str) {
+        self.rpc_errors.inc(&[method]);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.requests.render(&mut out);
+        self.signatures.render(&mut out);
+        self.rejections.render(&mut out);
+        self.rpc_errors.render(&mut out);
+        self.signing_duration.render(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters() {
+        let metrics = Metrics::new();
+        metrics.record_request("eth_accounts", true);
+        metrics.record_request("eth_accounts", true);
+        metrics.record_request("eth_signTransaction", false);
+        metrics.record_rejection("approval_hook");
+        metrics.record_rpc_error("eth_estimateGas");
+
+        let out = metrics.render();
+        assert!(out.contains("# TYPE signer_requests_total counter\n"));
+        assert!(out.contains("signer_requests_total{method=\"eth_accounts\",status=\"ok\"} 2\n"));
+        assert!(out.contains(
+            "signer_requests_total{method=\"eth_signTransaction\",status=\"error\"} 1\n"
+        ));
+        assert!(out.contains("signer_rejections_total{rule=\"approval_hook\"} 1\n"));
+        assert!(out.contains("signer_rpc_errors_total{method=\"eth_estimateGas\"} 1\n"));
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let metrics = Metrics::new();
+        metrics.record_signature(1, "transaction", Duration::from_millis(3));
+        metrics.record_signature(1, "transaction", Duration::from_millis(70));
+        metrics.record_signature(10, "eth1_sign", Duration::from_secs(60));
+
+        let out = metrics.render();
+        assert!(out.contains("signer_signatures_total{chain_id=\"1\",kind=\"transaction\"} 2\n"));
+        assert!(out.contains("signer_signatures_total{chain_id=\"10\",kind=\"eth1_sign\"} 1\n"));
+        assert!(out.contains("# TYPE signer_signing_duration_seconds histogram\n"));
+        // バケットは累積値
+        assert!(out.contains("signer_signing_duration_seconds_bucket{le=\"0.001\"} 0\n"));
+        assert!(out.contains("signer_signing_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(out.contains("signer_signing_duration_seconds_bucket{le=\"0.1\"} 2\n"));
+        assert!(out.contains("signer_signing_duration_seconds_bucket{le=\"30\"} 2\n"));
+        assert!(out.contains("signer_signing_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("signer_signing_duration_seconds_count 3\n"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label("a\nb"), "a\\nb");
+    }
+}
@@ -0,0 +1,96 @@
+use crate::Result;
+use std::{fs::File, ops::Deref, os::fd::AsRawFd, path::Path, ptr::NonNull};
+
+// 読み取り専用でメモリにマップしたファイル (ドロップで解除する)
+// 読み込んだ内容を自前のバッファにコピーしないため、数GBのファイルでもページキャッシュ以外のメモリを使わない
+// マップしている間に別のプロセスがファイルを切り詰めると、読んだときに SIGBUS で落ちる
+#[derive(Debug)]
+pub struct Mmap {
+    // 空のファイルはマップできないため None
+    pointer: Option<NonNull<u8>>,
+    length: usize,
+}
+
+// SAFETY: 読み取り専用のマップで、書き換えるものはない
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let length = usize::try_from(file.metadata()?.len()).map_err(std::io::Error::other)?;
+        if length == 0 {
+            return Ok(Self {
+                pointer: None,
+                length,
+            });
+        }
+
+        // SAFETY: 開いているファイルのディスクリプタを、ファイルの長さだけ読み取り専用でマップする
+        // マップはファイルを閉じても残る
+        let pointer = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                length,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if pointer == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // 先頭から順番に読むため、先読みを増やして読み終えたページは早めに手放してもらう (失敗しても読める)
+        // SAFETY: 上でマップした範囲
+        unsafe { libc::madvise(pointer, length, libc::MADV_SEQUENTIAL) };
+
+        Ok(Self {
+            pointer: NonNull::new(pointer.cast()),
+            length,
+        })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.pointer {
+            // SAFETY: マップしている間は length バイトを読める
+            Some(pointer) => unsafe { std::slice::from_raw_parts(pointer.as_ptr(), self.length) },
+            None => &[],
+        }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if let Some(pointer) = self.pointer {
+            // SAFETY: open でマップした範囲で、以降は参照されない
+            unsafe { libc::munmap(pointer.as_ptr().cast(), self.length) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_mmap() {
+        let file = NamedTempFile::new().unwrap();
+        assert!(Mmap::open(file.path()).unwrap().is_empty());
+
+        std::fs::write(file.path(), b"{\"a\":1}\n{\"a\":2}\n").unwrap();
+        let map = Mmap::open(file.path()).unwrap();
+        assert_eq!(&*map, b"{\"a\":1}\n{\"a\":2}\n");
+
+        // 別のスレッドに渡して読める
+        let length = std::thread::spawn(move || map.len()).join().unwrap();
+        assert_eq!(length, 16);
+
+        assert!(Mmap::open(file.path().with_extension("missing")).is_err());
+    }
+}
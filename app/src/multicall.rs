@@ -0,0 +1,196 @@
+use crate::{Result, abi::Function, de::deserialize_hex_bytes, error::Error, json};
+use ethereum_types::H160;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+// Multicall3 は多くのチェーンで同じアドレスにデプロイされている
+// https://github.com/mds1/multicall
+pub const MULTICALL3_ADDRESS: H160 = H160([
+    0xca, 0x11, 0xbd, 0xe0, 0x59, 0x77, 0xb3, 0x63, 0x11, 0x67, 0x02, 0x88, 0x62, 0xbe, 0x2a, 0x17,
+    0x39, 0x76, 0xca, 0x11,
+]);
+
+// aggregate3 に渡す個々の呼び出し
+// callData の代わりに function と args でも指定できる
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Call {
+    pub target: H160,
+    // true の場合はこの呼び出しが revert しても全体を revert しない
+    #[serde(default)]
+    pub allow_failure: bool,
+    #[serde(default, deserialize_with = "deserialize_hex_bytes")]
+    pub call_data: Vec<u8>,
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub args: Vec<Value>,
+}
+
+impl Call {
+    fn encoded_call_data(&self) -> Result<Vec<u8>> {
+        match &self.function {
+            Some(_) if !self.call_data.is_empty() => Err(Error::InvalidArgument(
+                "multicall: specify either 'callData' or 'function', not both".to_string(),
+            )),
+            Some(function) => Function::parse(function)?.encode_call(&self.args),
+            None => Ok(self.call_data.clone()),
+        }
+    }
+}
+
+// 呼び出しの配列をJSONファイルから読み込む
+pub fn calls_from_path<P: AsRef<Path>>(path: P) -> Result<Vec<Call>> {
+    let json_content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+    json::parse(&json_content).map_err(Error::in_file(path))
+}
+
+// aggregate3((address,bool,bytes)[]) の calldata
+pub fn aggregate3_calldata(calls: &[Call]) -> Result<Vec<u8>> {
+    if calls.is_empty() {
+        return Err(Error::InvalidArgument(
+            "multicall: at least one call is required".to_string(),
+        ));
+    }
+
+    let tuples = calls
+        .iter()
+        .enumerate()
+        .map(|(i, call)| {
+            let call_data = call.encoded_call_data().map_err(|e| match e {
+                Error::InvalidAbiData(message) => {
+                    Error::InvalidAbiData(format!("call {i}: {message}"))
+                }
+                e => e,
+            })?;
+            Ok(serde_json::json!([
+                call.target,
+                call.allow_failure,
+                format!("0x{}", hex::encode(call_data)),
+            ]))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Function::parse("aggregate3((address,bool,bytes)[])")?.encode_call(&[Value::Array(tuples)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi;
+    use serde_json::json;
+
+    fn call(value: Value) -> Call {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_multicall3_address() {
+        assert_eq!(
+            MULTICALL3_ADDRESS,
+            "0xcA11bde05977b3631167028862bE2a173976CA11"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_aggregate3_calldata() {
+        let calls = [call(json!({
+            "target": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "allowFailure": true,
+            "callData": "0x12345678"
+        }))];
+
+        assert_eq!(
+            hex::encode(aggregate3_calldata(&calls).unwrap()),
+            concat!(
+                "82ad56cb",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "0000000000000000000000000000000000000000000000000000000000000004",
+                "1234567800000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_aggregate3_calldata_with_function() {
+        let with_function = [call(json!({
+            "target": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "function": "approve(address,uint256)",
+            "args": ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "0"]
+        }))];
+        let approve = abi::encode_call(
+            "approve(address,uint256)",
+            &[
+                abi::encode_address(
+                    &"0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                        .parse()
+                        .unwrap(),
+                ),
+                [0; 32],
+            ],
+        );
+        let with_call_data = [call(json!({
+            "target": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "callData": format!("0x{}", hex::encode(approve))
+        }))];
+
+        assert_eq!(
+            aggregate3_calldata(&with_function).unwrap(),
+            aggregate3_calldata(&with_call_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_aggregate3_calldata_errors() {
+        assert!(aggregate3_calldata(&[]).is_err());
+
+        let both = [call(json!({
+            "target": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "callData": "0x12345678",
+            "function": "foo()"
+        }))];
+        assert!(matches!(
+            aggregate3_calldata(&both),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let bad_args = [call(json!({
+            "target": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "function": "setOwner(address)",
+            "args": ["0x1234"]
+        }))];
+        match aggregate3_calldata(&bad_args) {
+            Err(Error::InvalidAbiData(message)) => assert!(message.starts_with("call 0:")),
+            result => panic!("Expected InvalidAbiData, got: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_calls_from_path() {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            temp_file,
+            "{}",
+            json!([
+                { "target": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "callData": "0x" },
+                { "target": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "function": "foo()", "allowFailure": true }
+            ])
+        )
+        .unwrap();
+
+        let calls = calls_from_path(temp_file.path()).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(!calls[0].allow_failure);
+        assert!(calls[1].allow_failure);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    Result,
+    error::Error,
+    state::{StateFile, StateKey},
+};
+use ethereum_types::{H160, U256};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+// (chain_id, 送信者) ごとの次に使う nonce
+type Nonces = BTreeMap<String, U256>;
+
+fn key(chain_id: u64, sender: &H160) -> String {
+    format!("{chain_id}:{sender:?}")
+}
+
+// 実行をまたいで nonce を割り当てるローカルのストア (JSON ファイル、鍵があれば暗号化する)
+// 割り当てた nonce はすぐに記録するため、ブロードキャスト前でも同じ nonce を二度割り当てない
+#[derive(Debug)]
+pub struct NonceStore {
+    file: StateFile,
+    // 同じプロセス内の読み書きを直列化する (別のプロセスとはファイルのロックで排他する)
+    lock: Mutex<()>,
+}
+
+impl NonceStore {
+    // STATE_PASSPHRASE / STATE_KEY があれば暗号化して保存する
+    pub fn new(path: impl Into<PathBuf>, key: Option<StateKey>) -> Self {
+        Self {
+            file: StateFile::new(path, key).with_role("nonces"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn invalid(&self, error: impl std::fmt::Display) -> Error {
+        Error::InvalidNonceStore(format!("{}: {error}", self.file.path().display()))
+    }
+
+    fn read(&self) -> Result<Nonces> {
+        let content = self.file.read()?;
+        if content.trim().is_empty() {
+            return Ok(Nonces::new());
+        }
+        serde_json::from_str(&content).map_err(|error| self.invalid(error))
+    }
+
+    fn write(&self, nonces: &Nonces) -> Result<()> {
+        self.file
+            .write(&(serde_json::to_string_pretty(nonces)? + "\n"))
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Nonces) -> T) -> Result<T> {
+        let _guard = self.lock.lock().unwrap();
+        // 読んでから書くまでの間に別のプロセスが同じ nonce を割り当てないようにする
+        let _file_lock = self.file.lock()?;
+        let mut nonces = self.read()?;
+        let result = f(&mut nonces);
+        self.write(&nonces)?;
+        Ok(result)
+    }
+
+    // 次に割り当てる nonce (まだ割り当てたことがなければ None)
+    pub fn next(&self, chain_id: u64, sender: &H160) -> Result<Option<U256>> {
+        let _guard = self.lock.lock().unwrap();
+        let _file_lock = self.file.lock()?;
+        Ok(self.read()?.get(&key(chain_id, sender)).copied())
+    }
+
+    // nonce を割り当てる
+    // pending はノードの pending の nonce で、ストアより進んでいれば (別の署名者が送った場合など) そちらに合わせる
+    pub fn allocate(&self, chain_id: u64, sender: &H160, pending: Option<U256>) -> Result<U256> {
+        self.allocate_range(chain_id, sender, pending, 1)
+    }
+
+    // 連続した count 個の nonce を割り当て、最初の nonce を返す (まとめて署名する場合)
+    pub fn allocate_range(
+        &self,
+        chain_id: u64,
+        sender: &H160,
+        pending: Option<U256>,
+        count: u64,
+    ) -> Result<U256> {
+        self.update(|nonces| {
+            let next = nonces.entry(key(chain_id, sender)).or_default();
+            let nonce = (*next).max(pending.unwrap_or_default());
+            *next = nonce + count;
+            nonce
+        })
+    }
+
+    // 署名しなかった nonce を戻す (後から別の nonce が割り当てられていれば戻さない)
+    pub fn release(&self, chain_id: u64, sender: &H160, nonce: U256) -> Result<()> {
+        self.release_range(chain_id, sender, nonce, 1)
+    }
+
+    pub fn release_range(
+        &self,
+        chain_id: u64,
+        sender: &H160,
+        first: U256,
+        count: u64,
+    ) -> Result<()> {
+        self.update(|nonces| {
+            if let Some(next) = nonces
+                .get_mut(&key(chain_id, sender))
+                .filter(|next| **next == first + count)
+            {
+                *next = first;
+            }
+        })
+    }
+
+    // 明示的に指定された nonce で署名した場合、それより前の nonce を割り当てないようにする
+    pub fn commit(&self, chain_id: u64, sender: &H160, nonce: U256) -> Result<()> {
+        self.update(|nonces| {
+            let next = nonces.entry(key(chain_id, sender)).or_default();
+            *next = (*next).max(nonce + 1);
+        })
+    }
+
+    // 次に割り当てる nonce を設定する (ノードと食い違った場合の手動での修正)
+    pub fn set(&self, chain_id: u64, sender: &H160, next: U256) -> Result<()> {
+        self.update(|nonces| {
+            nonces.insert(key(chain_id, sender), next);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::TEST_ADDRESS;
+
+    #[test]
+    fn test_allocate_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nonces.json");
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+
+        let store = NonceStore::new(&path, None);
+        assert_eq!(store.next(1, &sender).unwrap(), None);
+        assert_eq!(store.allocate(1, &sender, None).unwrap(), U256::zero());
+        assert_eq!(store.allocate(1, &sender, None).unwrap(), U256::one());
+
+        // 別の実行でも続きから割り当てる
+        let store = NonceStore::new(&path, None);
+        assert_eq!(store.allocate(1, &sender, None).unwrap(), U256::from(2));
+        // チェーンごとに別
+        assert_eq!(store.allocate(5, &sender, None).unwrap(), U256::zero());
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(3)));
+    }
+
+    #[test]
+    fn test_reconcile_with_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NonceStore::new(dir.path().join("nonces.json"), None);
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+
+        // ノードの方が進んでいればそちらに合わせる
+        assert_eq!(
+            store.allocate(1, &sender, Some(U256::from(7))).unwrap(),
+            U256::from(7)
+        );
+        // まだブロードキャストされていない nonce はノードに見えないため、ストアを優先する
+        assert_eq!(
+            store.allocate(1, &sender, Some(U256::from(7))).unwrap(),
+            U256::from(8)
+        );
+    }
+
+    #[test]
+    fn test_release_and_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NonceStore::new(dir.path().join("nonces.json"), None);
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+
+        let first = store.allocate(1, &sender, None).unwrap();
+        let second = store.allocate(1, &sender, None).unwrap();
+        // 最後に割り当てた nonce のみ戻せる
+        store.release(1, &sender, first).unwrap();
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(2)));
+        store.release(1, &sender, second).unwrap();
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::one()));
+
+        store.commit(1, &sender, U256::from(10)).unwrap();
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(11)));
+        store.commit(1, &sender, U256::from(3)).unwrap();
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(11)));
+
+        store.set(1, &sender, U256::from(4)).unwrap();
+        assert_eq!(store.allocate(1, &sender, None).unwrap(), U256::from(4));
+
+        // まとめて割り当てて戻す
+        let first = store.allocate_range(1, &sender, None, 3).unwrap();
+        assert_eq!(first, U256::from(5));
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(8)));
+        store.release_range(1, &sender, first, 3).unwrap();
+        assert_eq!(store.next(1, &sender).unwrap(), Some(U256::from(5)));
+    }
+
+    #[test]
+    fn test_allocate_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nonces.json");
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+
+        // 別々に開いたストア (同時に実行した CLI と同じ) でも同じ nonce を割り当てない
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let store = NonceStore::new(&path, None);
+                    (0..10)
+                        .map(|_| store.allocate(1, &sender, None).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut nonces: Vec<_> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        nonces.sort();
+        nonces.dedup();
+
+        assert_eq!(nonces.len(), 80);
+        assert_eq!(
+            NonceStore::new(&path, None).next(1, &sender).unwrap(),
+            Some(U256::from(80))
+        );
+    }
+
+    #[test]
+    fn test_invalid_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nonces.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+        assert!(matches!(
+            NonceStore::new(&path, None)
+                .allocate(1, &sender, None)
+                .unwrap_err(),
+            Error::InvalidNonceStore(_)
+        ));
+    }
+}
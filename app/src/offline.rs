@@ -0,0 +1,330 @@
+use crate::{
+    Result, chains, detached,
+    error::Error,
+    signer,
+    transaction::{self, TxType},
+};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::SystemTime;
+
+// ファイル形式の版 (知らない版のファイルは読まない)
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    SigningRequest,
+    SignedTransaction,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::SigningRequest => "signing request",
+            Kind::SignedTransaction => "signed transaction",
+        }
+    }
+}
+
+// オンラインのマシンで作り (offline prepare)、オフラインのマシンで署名する (offline sign) 要求
+// transaction は署名する人が確認するためのもので、署名するのは unsigned (sighash はその Keccak-256)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SigningRequest {
+    pub version: u32,
+    pub kind: Kind,
+    pub chain_id: u64,
+    pub chain: String,
+    pub from: H160,
+    // UNIX 秒 (過ぎたら署名も送信もしない)
+    pub expires_at: u64,
+    // 手数料の上限 (gas limit × max fee per gas、wei)
+    pub max_fee: U256,
+    pub transaction: Value,
+    pub unsigned: String,
+    pub sighash: H256,
+}
+
+// オフラインのマシンで署名し (offline sign)、オンラインのマシンで送信する (online broadcast) トランザクション
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignedTransaction {
+    pub version: u32,
+    pub kind: Kind,
+    pub chain_id: u64,
+    pub chain: String,
+    pub from: H160,
+    pub expires_at: u64,
+    pub max_fee: U256,
+    pub transaction: Value,
+    pub hash: H256,
+    pub raw: String,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn chain_name(chain_id: u64) -> String {
+    chains::name(chain_id)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("chain {chain_id}"))
+}
+
+// 版・種類・チェーン・期限を確認する
+fn check(
+    version: u32,
+    kind: Kind,
+    expected: Kind,
+    chain_id: u64,
+    expected_chain_id: u64,
+    expires_at: u64,
+    now: u64,
+) -> Result<()> {
+    if version != FORMAT_VERSION {
+        return Err(Error::InvalidArgument(format!(
+            "unsupported {} version {version} (expected {FORMAT_VERSION})",
+            expected.as_str()
+        )));
+    }
+    if kind != expected {
+        return Err(Error::InvalidArgument(format!(
+            "expected a {}, got a {}",
+            expected.as_str(),
+            kind.as_str()
+        )));
+    }
+    if chain_id != expected_chain_id {
+        return Err(Error::InvalidArgument(format!(
+            "{} is for chain id {chain_id}, but CHAIN_ID is {expected_chain_id}",
+            expected.as_str()
+        )));
+    }
+    if expires_at <= now {
+        return Err(Error::InvalidArgument(format!(
+            "{} expired at {expires_at} (UNIX seconds): prepare a new one",
+            expected.as_str()
+        )));
+    }
+    Ok(())
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(Error::in_field(field))
+}
+
+impl SigningRequest {
+    pub fn new(
+        tx_type: &TxType,
+        message: &EIP1559TransactionMessage,
+        from: H160,
+        expires_at: u64,
+    ) -> Result<Self> {
+        let unsigned = detached::encode_unsigned(tx_type, message)?;
+        Ok(Self {
+            version: FORMAT_VERSION,
+            kind: Kind::SigningRequest,
+            chain_id: message.chain_id,
+            chain: chain_name(message.chain_id),
+            from,
+            expires_at,
+            max_fee: message.gas_limit.saturating_mul(message.max_fee_per_gas),
+            transaction: transaction::message_json(tx_type, message),
+            sighash: signer::keccak256(&unsigned),
+            unsigned: format!("0x{}", hex::encode(unsigned)),
+        })
+    }
+
+    // 署名するトランザクション
+    // 確認用の transaction と手数料の上限が unsigned の内容と一致しなければ、見せたものと違うものに署名しないよう拒否する
+    pub fn message(&self, chain_id: u64, now: u64) -> Result<(TxType, EIP1559TransactionMessage)> {
+        check(
+            self.version,
+            self.kind,
+            Kind::SigningRequest,
+            self.chain_id,
+            chain_id,
+            self.expires_at,
+            now,
+        )?;
+        let unsigned = decode_hex("unsigned", &self.unsigned)?;
+        if signer::keccak256(&unsigned) != self.sighash {
+            return Err(Error::InvalidArgument(
+                "signing request: sighash does not match unsigned".to_string(),
+            ));
+        }
+        let (tx_type, message) = detached::decode_unsigned(&unsigned)?;
+        if message.chain_id != self.chain_id
+            || transaction::message_json(&tx_type, &message) != self.transaction
+            || message.gas_limit.saturating_mul(message.max_fee_per_gas) != self.max_fee
+        {
+            return Err(Error::InvalidArgument(
+                "signing request: transaction or maxFee does not match unsigned".to_string(),
+            ));
+        }
+        Ok((tx_type, message))
+    }
+}
+
+impl SignedTransaction {
+    pub fn new(request: &SigningRequest, raw: &[u8]) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            kind: Kind::SignedTransaction,
+            chain_id: request.chain_id,
+            chain: request.chain.clone(),
+            from: request.from,
+            expires_at: request.expires_at,
+            max_fee: request.max_fee,
+            transaction: request.transaction.clone(),
+            hash: signer::keccak256(raw),
+            raw: format!("0x{}", hex::encode(raw)),
+        }
+    }
+
+    // 送信する raw トランザクション
+    // ハッシュ・チェーン・署名者がファイルの記載と一致することを確認する
+    pub fn raw(&self, chain_id: u64, now: u64) -> Result<Vec<u8>> {
+        check(
+            self.version,
+            self.kind,
+            Kind::SignedTransaction,
+            self.chain_id,
+            chain_id,
+            self.expires_at,
+            now,
+        )?;
+        let raw = decode_hex("raw", &self.raw)?;
+        if signer::keccak256(&raw) != self.hash {
+            return Err(Error::InvalidArgument(
+                "signed transaction: hash does not match raw".to_string(),
+            ));
+        }
+
+        let tx_type = match raw.first() {
+            Some(0x02) => TxType::Eip1559,
+            Some(&first) if first >= 0xc0 => TxType::Legacy,
+            _ => {
+                return Err(Error::InvalidArgument(
+                    "signed transaction must be Type 2 or legacy".to_string(),
+                ));
+            }
+        };
+        let signed = transaction::decode(&raw)?;
+        let signature = signer::Signature {
+            r: signed.r,
+            s: signed.s,
+            odd_y_parity: signed.odd_y_parity,
+        };
+        let message = EIP1559TransactionMessage::from(signed);
+        let sender = signer::recover(&tx_type.signing_hash(&message, self.from)?, &signature)?;
+        if message.chain_id != self.chain_id || sender != self.from {
+            return Err(Error::InvalidArgument(format!(
+                "signed transaction: raw is signed by {sender:?} for chain id {}, not {:?} for {}",
+                message.chain_id, self.from, self.chain_id
+            )));
+        }
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+    use ethereum::{AccessList, TransactionAction};
+    use k256::ecdsa::SigningKey;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn test_message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(4),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: U256::from(1_000_000_000_000_000u64),
+            input: vec![],
+            access_list: AccessList::default(),
+        }
+    }
+
+    // ファイルに書いて読み直したもの
+    fn round_trip<T: Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        serde_json::from_str(&serde_json::to_string_pretty(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let from = TEST_ADDRESS.parse().unwrap();
+        for tx_type in [TxType::Eip1559, TxType::Legacy] {
+            let request = round_trip(
+                &SigningRequest::new(&tx_type, &test_message(), from, NOW + 3600).unwrap(),
+            );
+            assert_eq!(request.chain, "Sepolia");
+            assert_eq!(request.max_fee, U256::from(630_000_000_000_000u64));
+
+            // Legacy は優先手数料を持たないため、署名したものが同じであることを確かめる
+            let (decoded_type, message) = request.message(11155111, NOW).unwrap();
+            assert_eq!(decoded_type, tx_type);
+            let raw = tx_type.sign(message, &test_signing_key()).unwrap();
+            assert_eq!(
+                raw,
+                tx_type.sign(test_message(), &test_signing_key()).unwrap()
+            );
+            let signed = round_trip(&SignedTransaction::new(&request, &raw));
+            assert_eq!(signed.raw(11155111, NOW).unwrap(), raw);
+            assert_eq!(
+                serde_json::to_value(&signed).unwrap()["kind"],
+                "signed-transaction"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejected() {
+        let from = TEST_ADDRESS.parse().unwrap();
+        let request = SigningRequest::new(&TxType::Eip1559, &test_message(), from, NOW).unwrap();
+        // 期限切れと別のチェーン
+        assert!(request.message(11155111, NOW).is_err());
+        let request =
+            SigningRequest::new(&TxType::Eip1559, &test_message(), from, NOW + 60).unwrap();
+        assert!(request.message(1, NOW).is_err());
+
+        // 確認用のフィールドだけ書き換えたもの
+        let mut tampered = round_trip(&request);
+        tampered.transaction["value"] = "0x1".into();
+        assert!(tampered.message(11155111, NOW).is_err());
+        let mut tampered = round_trip(&request);
+        tampered.max_fee = U256::one();
+        assert!(tampered.message(11155111, NOW).is_err());
+
+        // 別の鍵で署名したものと、種類の違うファイル
+        let raw =
+            transaction::sign(test_message(), &SigningKey::from_slice(&[7; 32]).unwrap()).unwrap();
+        let signed = SignedTransaction::new(&request, &raw);
+        assert!(signed.raw(11155111, NOW).is_err());
+        let mut signed: SignedTransaction =
+            serde_json::from_value(serde_json::to_value(&signed).unwrap()).unwrap();
+        signed.kind = Kind::SigningRequest;
+        assert!(signed.raw(11155111, NOW).is_err());
+
+        // 知らない版
+        let mut json = serde_json::to_value(&request).unwrap();
+        json["version"] = 2.into();
+        let request: SigningRequest = serde_json::from_value(json).unwrap();
+        assert!(request.message(11155111, NOW).is_err());
+    }
+}
@@ -1,26 +1,256 @@
-use crate::de::{deserialize_hex_bytes, deserialize_u256};
+use crate::{
+    Result,
+    abi::Function,
+    de::{
+        deserialize_address, deserialize_hex_bytes, deserialize_optional_u256, deserialize_u256,
+        with_decimal_strings, with_strict_hex,
+    },
+    error::Error,
+    json,
+    zksync::Paymaster,
+};
+use clap::ValueEnum;
 use ethereum_types::{H160, U256};
 use serde::Deserialize;
-use std::path::Path;
+use serde_json::{Map, Value};
+use std::{cell::Cell, path::Path};
+
+// sign に渡すファイルの形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    // このツールの params.json
+    #[default]
+    Params,
+    // ethers-rs の TypedTransaction の JSON
+    Ethers,
+    // eth_signTransaction のトランザクションオブジェクト (または params の配列)
+    Rpc,
+}
+
+// params.json と sign-batch のパラメータの読み方
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    // 16進数に 0x を必須にする (--strict-hex)
+    pub strict_hex: bool,
+    // 知らないフィールドを無視する (--lenient)
+    pub lenient: bool,
+    // 数字だけの文字列を10進数として読む (--decimal-strings)
+    pub decimal_strings: bool,
+}
+
+impl ParseOptions {
+    // f の中で Params をこの読み方でパースする (パースするスレッドで呼ぶ)
+    pub fn apply<T>(self, f: impl FnOnce() -> T) -> T {
+        let previous = LENIENT.replace(self.lenient);
+        let result = with_strict_hex(self.strict_hex, || {
+            with_decimal_strings(self.decimal_strings, f)
+        });
+        LENIENT.set(previous);
+        result
+    }
+}
+
+thread_local! {
+    static LENIENT: Cell<bool> = const { Cell::new(false) };
+}
 
 // params.json で渡すパラメータ
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "RawParams")]
 pub struct Params {
-    #[serde(deserialize_with = "deserialize_u256")]
-    pub nonce: U256,
+    // 省略した場合は NONCE_STORE_PATH のストアか RPC で割り当てる
+    pub nonce: Option<U256>,
     pub to_address: H160,
-    #[serde(deserialize_with = "deserialize_u256")]
     pub value: U256,
-    #[serde(deserialize_with = "deserialize_u256")]
     pub gas_limit: U256,
-    #[serde(default, deserialize_with = "deserialize_hex_bytes")]
     pub input: Vec<u8>,
+    // input の代わりに関数シグネチャ ("setOwner(address)") と引数で calldata を指定できる
+    pub function: Option<String>,
+    pub args: Vec<Value>,
+    // zkSync で手数料を paymaster に払わせる場合 (zkSync の EIP-712 トランザクションになる)
+    pub paymaster: Option<Paymaster>,
+    // Celo で手数料を ERC-20 (cUSD など) で払う場合のトークン (CIP-64 のトランザクションになる)
+    pub fee_currency: Option<H160>,
+    // 指定した場合は CHAIN_ID と一致している必要がある (check_chain_id)
+    pub chain_id: Option<u64>,
+    // 指定した場合は MAX_FEE_PER_GAS・MAX_PRIORITY_FEE_PER_GAS の代わりに使う
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+// フィールドごとにパースして、エラーにフィールド名を付けるためにいったん Value で受け取る
+// ("to_address: '0x742d…11D' is 19 bytes, expected 20")
+// "gaslimit" のような書き間違いで意図と違うトランザクションにならないよう、知らないフィールドはエラー (--lenient で無視)
+// 同じキーが2回あればどちらを使ったか分からないため、常にエラー
+// web3.py・viem などで書き出した JSON をそのまま読めるよう、JSON-RPC の名前 (to, gas, data) と camelCase も受け付ける
+#[derive(Deserialize)]
+struct RawParams {
+    #[serde(default)]
+    nonce: Value,
+    #[serde(alias = "to", alias = "toAddress")]
+    to_address: Value,
+    value: Value,
+    #[serde(alias = "gas", alias = "gasLimit")]
+    gas_limit: Value,
+    #[serde(default, alias = "data")]
+    input: Value,
+    #[serde(default)]
+    function: Option<String>,
+    #[serde(default)]
+    args: Vec<Value>,
+    #[serde(default)]
+    paymaster: Value,
+    #[serde(default, alias = "paymasterInput")]
+    paymaster_input: Value,
+    #[serde(default, alias = "feeCurrency")]
+    fee_currency: Value,
+    #[serde(default, alias = "chainId")]
+    chain_id: Value,
+    #[serde(default, alias = "maxFeePerGas")]
+    max_fee_per_gas: Value,
+    #[serde(default, alias = "maxPriorityFeePerGas")]
+    max_priority_fee_per_gas: Value,
+    #[serde(flatten)]
+    unknown: Map<String, Value>,
+}
+
+const FIELDS: &[&str] = &[
+    "nonce",
+    "to_address",
+    "value",
+    "gas_limit",
+    "input",
+    "function",
+    "args",
+    "paymaster",
+    "paymaster_input",
+    "fee_currency",
+    "chain_id",
+    "max_fee_per_gas",
+    "max_priority_fee_per_gas",
+];
+
+impl TryFrom<RawParams> for Params {
+    type Error = String;
+
+    fn try_from(raw: RawParams) -> std::result::Result<Self, String> {
+        if let Some(name) = raw.unknown.keys().next().filter(|_| !LENIENT.get()) {
+            return Err(format!(
+                "unknown field `{name}`, expected one of {} (pass --lenient to ignore unknown fields)",
+                FIELDS
+                    .iter()
+                    .map(|field| format!("`{field}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        Ok(Self {
+            nonce: field("nonce", deserialize_optional_u256(raw.nonce))?,
+            to_address: field("to_address", deserialize_address(raw.to_address))?,
+            value: field("value", deserialize_u256(raw.value))?,
+            gas_limit: field("gas_limit", deserialize_u256(raw.gas_limit))?,
+            input: match raw.input {
+                Value::Null => Vec::new(),
+                input => field("input", deserialize_hex_bytes(input))?,
+            },
+            function: raw.function,
+            args: raw.args,
+            paymaster: match (raw.paymaster, raw.paymaster_input) {
+                (Value::Null, Value::Null) => None,
+                (Value::Null, _) => {
+                    return Err("paymaster_input: requires `paymaster`".to_string());
+                }
+                (address, input) => Some(Paymaster {
+                    address: field("paymaster", deserialize_address(address))?,
+                    input: match input {
+                        Value::Null => Vec::new(),
+                        input => field("paymaster_input", deserialize_hex_bytes(input))?,
+                    },
+                }),
+            },
+            fee_currency: match raw.fee_currency {
+                Value::Null => None,
+                address => Some(field("fee_currency", deserialize_address(address))?),
+            },
+            chain_id: field("chain_id", deserialize_optional_u256(raw.chain_id))?
+                .map(|chain_id| {
+                    u64::try_from(chain_id)
+                        .map_err(|_| format!("chain_id: {chain_id} does not fit in 64 bits"))
+                })
+                .transpose()?,
+            max_fee_per_gas: field(
+                "max_fee_per_gas",
+                deserialize_optional_u256(raw.max_fee_per_gas),
+            )?,
+            max_priority_fee_per_gas: field(
+                "max_priority_fee_per_gas",
+                deserialize_optional_u256(raw.max_priority_fee_per_gas),
+            )?,
+        })
+    }
+}
+
+fn field<T>(
+    name: &str,
+    result: std::result::Result<T, serde_json::Error>,
+) -> std::result::Result<T, String> {
+    result.map_err(|error| format!("{name}: {error}"))
+}
+
+// これを超える nonce はアカウントが実際に使い切れる数ではなく、value などと取り違えた可能性が高い
+pub const MAX_PLAUSIBLE_NONCE: u64 = 1 << 32;
+
+// Params と ethers の TypedTransaction で共通の nonce の警告
+pub fn nonce_warning(nonce: Option<U256>) -> Option<String> {
+    nonce
+        .filter(|nonce| *nonce > U256::from(MAX_PLAUSIBLE_NONCE))
+        .map(|nonce| {
+            format!(
+                "nonce {nonce} is implausibly large (over 2^32); check that nonce and value are not swapped"
+            )
+        })
 }
 
 impl Params {
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
-        let json_content = std::fs::read_to_string(path).unwrap();
-        serde_json::from_str(&json_content).unwrap()
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        json::parse(&json_content).map_err(Error::in_file(path))
+    }
+
+    // viem・web3.py などで書き出した chainId が CHAIN_ID と違えば、別のチェーンで署名しないよう拒否する
+    pub fn check_chain_id(&self, chain_id: u64) -> Result<()> {
+        match self.chain_id {
+            Some(requested) if requested != chain_id => Err(Error::InvalidArgument(format!(
+                "chainId {requested} does not match CHAIN_ID {chain_id}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // nonce がありえないほど大きければ警告のメッセージを返す
+    pub fn nonce_warning(&self) -> Option<String> {
+        nonce_warning(self.nonce)
+    }
+
+    // function と args が指定されていれば calldata にエンコードして input に設定
+    pub fn encode_function(mut self) -> Result<Self> {
+        match self.function.take() {
+            Some(_) if !self.input.is_empty() => Err(Error::InvalidArgument(
+                "params: specify either 'input' or 'function', not both".to_string(),
+            )),
+            Some(function) => {
+                self.input = Function::parse(&function)
+                    .map_err(Error::in_field("function"))?
+                    .encode_call(&self.args)
+                    .map_err(Error::in_field("args"))?;
+                self.args.clear();
+                Ok(self)
+            }
+            None if !self.args.is_empty() => Err(Error::InvalidArgument(
+                "params: 'args' requires 'function'".to_string(),
+            )),
+            None => Ok(self),
+        }
     }
 }
 
@@ -41,7 +271,7 @@ mod tests {
 
         let params: Params = serde_json::from_str(json).unwrap();
 
-        assert_eq!(params.nonce, U256::from(1));
+        assert_eq!(params.nonce, Some(U256::from(1)));
         assert_eq!(
             params.to_address,
             "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
@@ -68,7 +298,7 @@ mod tests {
 
         let params: Params = serde_json::from_str(json).unwrap();
 
-        assert_eq!(params.nonce, U256::zero());
+        assert_eq!(params.nonce, Some(U256::zero()));
         assert_eq!(params.value, U256::zero());
         assert!(!params.input.is_empty());
         // ERC20 transfer function selector (0xa9059cbb)
@@ -89,9 +319,9 @@ mod tests {
         write!(temp_file, "{}", json_content).unwrap();
 
         // ファイルから読み込み
-        let params = Params::from_path(temp_file.path());
+        let params = Params::from_path(temp_file.path()).unwrap();
 
-        assert_eq!(params.nonce, U256::from(0x42));
+        assert_eq!(params.nonce, Some(U256::from(0x42)));
         assert_eq!(
             params.value,
             U256::from_str_radix("1bc16d674ec80000", 16).unwrap()
@@ -110,7 +340,7 @@ mod tests {
 
         let params: Params = serde_json::from_str(json).unwrap();
 
-        assert_eq!(params.nonce, U256::MAX);
+        assert_eq!(params.nonce, Some(U256::MAX));
         assert_eq!(params.to_address, H160::zero());
         assert_eq!(params.gas_limit, U256::from(2_000_000));
     }
@@ -126,7 +356,7 @@ mod tests {
 
         let params: Params = serde_json::from_str(json).unwrap();
 
-        assert_eq!(params.nonce, U256::zero());
+        assert_eq!(params.nonce, Some(U256::zero()));
         assert_eq!(params.to_address, H160::zero());
         assert_eq!(params.value, U256::zero());
         assert_eq!(params.gas_limit, U256::from(21000));
@@ -145,6 +375,18 @@ mod tests {
         let _: Params = serde_json::from_str(json).unwrap();
     }
 
+    #[test]
+    fn test_params_without_nonce() {
+        let json = r#"{
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208"
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        assert_eq!(params.nonce, None);
+    }
+
     #[test]
     #[should_panic]
     fn test_params_invalid_hex_format() {
@@ -172,9 +414,411 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "No such file or directory")]
+    fn test_params_field_errors() {
+        let error = |field: &str, value: &str| {
+            let mut params = serde_json::json!({
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x0",
+                "gas_limit": "0x5208",
+            });
+            params[field] = serde_json::from_str(value).unwrap();
+            serde_json::from_value::<Params>(params)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(
+                "to_address",
+                r#""0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11""#
+            ),
+            "to_address: '0x742d35Cc…2d11' is 19 bytes, expected 20"
+        );
+        assert_eq!(
+            error(
+                "to_address",
+                r#""0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11D""#
+            ),
+            "to_address: '0x742d35Cc…d11D' has 39 hex digits, expected 40"
+        );
+        assert_eq!(
+            error("to_address", r#""0xinvalid_address""#),
+            "to_address: '0xinvalid_address' is not hex, expected a 0x-prefixed 20-byte address"
+        );
+        assert_eq!(
+            error("nonce", r#""invalid_hex""#),
+            "nonce: 'invalid_hex' is not a number, expected a decimal string or a hex string"
+        );
+        assert_eq!(error("value", "-1"), "value: negative number -1");
+        assert_eq!(
+            error("gas_limit", "true"),
+            "gas_limit: invalid type: boolean `true`, expected a non-negative integer, a decimal string or a hex string"
+        );
+        assert_eq!(
+            error("input", r#""0x123""#),
+            "input: '0x123' is not hex bytes (odd number of digits), expected a 0x-prefixed string of hex digit pairs"
+        );
+
+        // ファイルから読んだ場合も同じメッセージ
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(
+            temp_file,
+            r#"{{"to_address": "0x00", "value": "0x0", "gas_limit": "0x5208"}}"#
+        )
+        .unwrap();
+        let error = Params::from_path(temp_file.path()).unwrap_err();
+        assert!(matches!(
+            error.root(),
+            Error::InvalidJson { message, line: 1, column: 16, .. }
+                if message == "to_address: '0x00' is 1 byte, expected 20"
+        ));
+        assert!(matches!(&error, Error::File { path, .. } if path == temp_file.path()));
+    }
+
+    #[test]
+    fn test_params_paymaster() {
+        let params: Params = serde_json::from_str(
+            r#"{
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "500000",
+                "paymaster": "0x1111111111111111111111111111111111111111",
+                "paymaster_input": "0x8c5a3445"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            params.paymaster,
+            Some(Paymaster {
+                address: H160::repeat_byte(0x11),
+                input: vec![0x8c, 0x5a, 0x34, 0x45],
+            })
+        );
+
+        // paymaster_input だけでは使えない
+        let error = serde_json::from_str::<Params>(
+            r#"{
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "500000",
+                "paymaster_input": "0x8c5a3445"
+            }"#,
+        )
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("paymaster_input: requires `paymaster`"),
+            "{error}"
+        );
+
+        let params: Params = serde_json::from_str(
+            r#"{
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "21000"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.paymaster, None);
+        assert_eq!(params.fee_currency, None);
+    }
+
+    #[test]
+    fn test_params_fee_currency() {
+        let params: Params = serde_json::from_str(
+            r#"{
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "100000",
+                "fee_currency": "0x765DE816845861e75A25fCA122bb6898B8B1282a"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            params.fee_currency,
+            Some(
+                "0x765DE816845861e75A25fCA122bb6898B8B1282a"
+                    .parse()
+                    .unwrap()
+            )
+        );
+
+        let error = serde_json::from_str::<Params>(
+            r#"{
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "100000",
+                "fee_currency": "cUSD"
+            }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().starts_with("fee_currency: "), "{error}");
+    }
+
+    #[test]
+    fn test_params_unknown_and_duplicate_fields() {
+        let typo = r#"{
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "gaslimit": "0x7530"
+        }"#;
+        let error = serde_json::from_str::<Params>(typo)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with("unknown field `gaslimit`, expected one of `nonce`"),
+            "{error}"
+        );
+        assert!(error.contains("--lenient"), "{error}");
+
+        // --lenient では無視する
+        let lenient = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let params = lenient
+            .apply(|| serde_json::from_str::<Params>(typo))
+            .unwrap();
+        assert_eq!(params.gas_limit, U256::from(21000));
+
+        let duplicate = r#"{
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "value": "0xde0b6b3a7640000"
+        }"#;
+        for options in [ParseOptions::default(), lenient] {
+            let error = options
+                .apply(|| serde_json::from_str::<Params>(duplicate))
+                .unwrap_err();
+            assert!(
+                error.to_string().contains("duplicate field `value`"),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_params_aliases() {
+        // web3.py / viem のトランザクションの名前
+        let params: Params = serde_json::from_str(
+            r#"{
+                "nonce": "0x1",
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x0",
+                "gas": "0x5208",
+                "data": "0xa9059cbb"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.gas_limit, U256::from(21000));
+        assert_eq!(params.input, [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let params: Params = serde_json::from_str(
+            r#"{
+                "toAddress": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gasLimit": "21000",
+                "feeCurrency": "0x765DE816845861e75A25fCA122bb6898B8B1282a"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.gas_limit, U256::from(21000));
+        assert!(params.fee_currency.is_some());
+
+        // 同じフィールドを別の名前で2回書いたもの
+        let error = serde_json::from_str::<Params>(
+            r#"{
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0",
+                "gas_limit": "21000",
+                "gas": "30000"
+            }"#,
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("duplicate field `gas_limit`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_params_chain_id_and_fees() {
+        // viem・web3.py のトランザクションの JSON そのもの
+        let params: Params = serde_json::from_str(
+            r#"{
+                "chainId": 11155111,
+                "nonce": "0x1",
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x0",
+                "gas": "0x5208",
+                "maxFeePerGas": "0xba43b7400",
+                "maxPriorityFeePerGas": "0x77359400"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.chain_id, Some(11155111));
+        assert_eq!(params.max_fee_per_gas, Some(U256::from(50_000_000_000u64)));
+        assert_eq!(
+            params.max_priority_fee_per_gas,
+            Some(U256::from(2_000_000_000u64))
+        );
+        assert!(params.check_chain_id(11155111).is_ok());
+        let error = params.check_chain_id(1).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: chainId 11155111 does not match CHAIN_ID 1"
+        );
+
+        // 省略した場合はどのチェーンでも通す
+        let params: Params = serde_json::from_str(
+            r#"{
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x0",
+                "gas": "0x5208"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.chain_id, None);
+        assert_eq!(params.max_fee_per_gas, None);
+        assert!(params.check_chain_id(1).is_ok());
+
+        let error = serde_json::from_str::<Params>(
+            r#"{
+                "chain_id": "0x10000000000000000",
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x0",
+                "gas": "0x5208"
+            }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("chain_id"), "{error}");
+    }
+
+    #[test]
+    fn test_params_nonce_warning() {
+        let params = |nonce: &str| {
+            serde_json::from_str::<Params>(&format!(
+                r#"{{
+                    "nonce": {nonce},
+                    "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                    "value": "0x0",
+                    "gas_limit": "0x5208"
+                }}"#
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(params("null").nonce_warning(), None);
+        assert_eq!(params("42").nonce_warning(), None);
+        assert_eq!(params("4294967296").nonce_warning(), None);
+        let warning = params("4294967297").nonce_warning().unwrap();
+        assert!(
+            warning.starts_with("nonce 4294967297 is implausibly large"),
+            "{warning}"
+        );
+        assert!(
+            params(r#""0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff""#)
+                .nonce_warning()
+                .is_some()
+        );
+    }
+
+    #[test]
     fn test_params_from_nonexistent_path() {
-        Params::from_path("nonexistent_file.json");
+        assert!(matches!(
+            Params::from_path("nonexistent_file.json").as_ref().map_err(Error::root),
+            Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_params_with_function() {
+        let json = r#"{
+            "nonce": "0x0",
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0xea60",
+            "function": "transfer(address,uint256)",
+            "args": ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "1000000000000000000"]
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        let params = params.encode_function().unwrap();
+
+        assert_eq!(
+            hex::encode(&params.input),
+            "a9059cbb000000000000000000000000742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        );
+        assert!(params.function.is_none());
+    }
+
+    #[test]
+    fn test_params_without_function_keeps_input() {
+        let json = r#"{
+            "nonce": "0x0",
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "input": "0x1234"
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        let params = params.encode_function().unwrap();
+
+        assert_eq!(params.input, vec![0x12, 0x34]);
+    }
+
+    #[test]
+    fn test_params_function_conflicts_with_input() {
+        let json = r#"{
+            "nonce": "0x0",
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "input": "0x1234",
+            "function": "setOwner(address)",
+            "args": ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            params.encode_function(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_params_args_without_function() {
+        let json = r#"{
+            "nonce": "0x0",
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "args": ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        assert!(params.encode_function().is_err());
+    }
+
+    #[test]
+    fn test_params_function_invalid_args() {
+        let json = r#"{
+            "nonce": "0x0",
+            "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "value": "0x0",
+            "gas_limit": "0x5208",
+            "function": "setOwner(address)",
+            "args": ["not an address"]
+        }"#;
+
+        let params: Params = serde_json::from_str(json).unwrap();
+        let error = params.encode_function().unwrap_err();
+        assert!(matches!(&error, Error::Field { field, .. } if field == "args"));
+        assert!(matches!(error.root(), Error::InvalidAbiData(_)));
     }
 
     #[test]
@@ -0,0 +1,193 @@
+use crate::{Result, abi, eip712::TypedData, rpc::RpcClient};
+use ethereum_types::{H160, H256, U256};
+
+// ERC-2612 の Permit
+#[derive(Debug, Clone)]
+pub struct Permit {
+    // トークンコントラクト (EIP-712 ドメインの verifyingContract)
+    pub token: H160,
+    // EIP-712 ドメインの name / version
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub owner: H160,
+    pub spender: H160,
+    pub value: U256,
+    pub nonce: U256,
+    pub deadline: U256,
+}
+
+impl Permit {
+    pub fn typed_data(&self) -> Result<TypedData> {
+        let typed_data = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Permit": [
+                    { "name": "owner", "type": "address" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "deadline", "type": "uint256" }
+                ]
+            },
+            "primaryType": "Permit",
+            "domain": {
+                "name": self.name,
+                "version": self.version,
+                "chainId": self.chain_id,
+                "verifyingContract": self.token,
+            },
+            "message": {
+                "owner": self.owner,
+                "spender": self.spender,
+                "value": self.value.to_string(),
+                "nonce": self.nonce.to_string(),
+                "deadline": self.deadline.to_string(),
+            }
+        });
+
+        serde_json::from_value(typed_data).map_err(Into::into)
+    }
+
+    pub fn digest(&self) -> Result<H256> {
+        self.typed_data()?.digest()
+    }
+}
+
+// トークンの name() を取得
+pub fn fetch_name(client: &RpcClient, token: &H160) -> Result<String> {
+    let data = client.call(token, &abi::encode_call("name()", &[]))?;
+    abi::decode_string(&data)
+}
+
+// トークンの version() を取得
+// version() を実装していないトークンも多いため、失敗した場合は呼び出し元で "1" を使う
+pub fn fetch_version(client: &RpcClient, token: &H160) -> Result<String> {
+    let data = client.call(token, &abi::encode_call("version()", &[]))?;
+    abi::decode_string(&data)
+}
+
+// トークンの nonces(owner) を取得
+pub fn fetch_nonce(client: &RpcClient, token: &H160, owner: &H160) -> Result<U256> {
+    let data = client.call(
+        token,
+        &abi::encode_call("nonces(address)", &[abi::encode_address(owner)]),
+    )?;
+    abi::decode_u256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eip712, rpc::tests::MockServer, signer::keccak256};
+    use serde_json::json;
+
+    fn test_permit() -> Permit {
+        Permit {
+            token: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                .parse()
+                .unwrap(),
+            name: "USD Coin".to_string(),
+            version: "2".to_string(),
+            chain_id: 1,
+            owner: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                .parse()
+                .unwrap(),
+            spender: "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                .parse()
+                .unwrap(),
+            value: U256::from(1_000_000),
+            nonce: U256::zero(),
+            deadline: U256::from(1_700_000_000u64),
+        }
+    }
+
+    #[test]
+    fn test_permit_type_hash() {
+        // ERC-2612 の PERMIT_TYPEHASH
+        let typed_data = test_permit().typed_data().unwrap();
+        assert_eq!(
+            eip712::type_hash(&typed_data.types, "Permit").unwrap(),
+            keccak256(
+                "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_permit_domain_separator_usdc() {
+        // Ethereum mainnet の USDC の DOMAIN_SEPARATOR()
+        let typed_data = test_permit().typed_data().unwrap();
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            "0x06c37168a7db5138defc7866392bb87a741f9b3d104deb5094588ce041cae335"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_permit_digest_depends_on_nonce() {
+        let permit = test_permit();
+        let next = Permit {
+            nonce: U256::one(),
+            ..permit.clone()
+        };
+
+        assert_ne!(permit.digest().unwrap(), next.digest().unwrap());
+    }
+
+    // 文字列の戻り値をABIエンコード
+    fn encode_string_result(s: &str) -> String {
+        let mut data = Vec::new();
+        data.extend_from_slice(&abi::encode_u256(U256::from(32)));
+        data.extend_from_slice(&abi::encode_u256(U256::from(s.len())));
+        let mut padded = s.as_bytes().to_vec();
+        padded.resize(s.len().div_ceil(32) * 32, 0);
+        data.extend_from_slice(&padded);
+        format!("0x{}", hex::encode(data))
+    }
+
+    #[test]
+    fn test_fetch_name_and_version() {
+        let server = MockServer::start(vec![
+            MockServer::rpc_result(json!(encode_string_result("USD Coin"))),
+            MockServer::rpc_result(json!(encode_string_result("2"))),
+        ]);
+        let client = RpcClient::new(&server.url);
+        let token = test_permit().token;
+
+        assert_eq!(fetch_name(&client, &token).unwrap(), "USD Coin");
+        assert_eq!(fetch_version(&client, &token).unwrap(), "2");
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["params"][0]["data"], "0x06fdde03");
+        assert_eq!(requests[1]["params"][0]["data"], "0x54fd4d50");
+    }
+
+    #[test]
+    fn test_fetch_nonce() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!(format!(
+            "0x{}",
+            hex::encode(abi::encode_u256(U256::from(7)))
+        )))]);
+        let client = RpcClient::new(&server.url);
+        let permit = test_permit();
+
+        assert_eq!(
+            fetch_nonce(&client, &permit.token, &permit.owner).unwrap(),
+            U256::from(7)
+        );
+
+        let requests = server.json_requests();
+        assert_eq!(
+            requests[0]["params"][0]["data"],
+            "0x7ecebe00000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+    }
+}
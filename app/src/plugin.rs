@@ -0,0 +1,646 @@
+use crate::{
+    Result, address, detached,
+    error::Error,
+    signer::{self, Signature},
+};
+use ethereum_types::{H160, H256};
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+// 署名プラグインのプロトコルのバージョン (互換性のない変更をしたら上げる)
+pub const PROTOCOL_VERSION: u64 = 1;
+
+// stdin を閉じてから終了を待つ時間 (過ぎれば kill する)
+const EXIT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+// プラグインとは標準入出力で1行に1つの JSON-RPC 2.0 メッセージをやり取りする
+#[derive(Debug, Deserialize)]
+struct Request {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: Option<u64>,
+    result: Option<Value>,
+    error: Option<ErrorObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+}
+
+// initialize の結果
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Initialized {
+    protocol_version: u64,
+    name: String,
+    address: H160,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignHashParams {
+    hash: H256,
+    #[serde(default)]
+    address: Option<H160>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Signed {
+    signature: String,
+}
+
+struct Connection {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+    next_id: u64,
+}
+
+impl Connection {
+    // リクエストを1つ送り、そのレスポンスを待つ
+    fn request<T: DeserializeOwned>(&mut self, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .and_then(|()| self.writer.flush())
+            .map_err(|error| Error::Plugin(format!("failed to send {method}: {error}")))?;
+
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|error| Error::Plugin(format!("failed to read {method}: {error}")))?;
+        if read == 0 {
+            return Err(Error::Plugin(format!(
+                "the plugin exited without answering {method}"
+            )));
+        }
+        let response: Response = serde_json::from_str(&line)
+            .map_err(|error| Error::Plugin(format!("invalid response to {method}: {error}")))?;
+        if response.id != Some(id) {
+            return Err(Error::Plugin(format!(
+                "response id {:?} does not match the request id {id}",
+                response.id
+            )));
+        }
+        if let Some(error) = response.error {
+            return Err(Error::Plugin(format!("{} ({})", error.message, error.code)));
+        }
+        let result = response
+            .result
+            .ok_or_else(|| Error::Plugin(format!("empty result for {method}")))?;
+        serde_json::from_value(result)
+            .map_err(|error| Error::Plugin(format!("invalid result for {method}: {error}")))
+    }
+}
+
+// 別のプロセスで動く署名のバックエンド (HSM やカストディの API など)
+// 秘密鍵はこのプロセスに渡らず、プラグインには32バイトのハッシュだけを送る
+pub struct Plugin {
+    name: String,
+    address: H160,
+    connection: Mutex<Connection>,
+    child: Option<Child>,
+}
+
+impl Plugin {
+    // プラグインの実行ファイルを起動してハンドシェイクする (stderr はそのまま端末に出す)
+    pub fn spawn(path: &str, chain_id: u64) -> Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|error| Error::Plugin(format!("failed to start {path}: {error}")))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            unreachable!("stdin and stdout are piped");
+        };
+
+        Self::connect(BufReader::new(stdout), stdin, chain_id, Some(child))
+    }
+
+    // initialize でプロトコルのバージョンを確かめ、署名するアドレスを受け取る
+    fn connect(
+        reader: impl BufRead + Send + 'static,
+        writer: impl Write + Send + 'static,
+        chain_id: u64,
+        child: Option<Child>,
+    ) -> Result<Self> {
+        // 失敗してもプロセスを待てるように、先に Plugin を作っておく
+        let mut plugin = Self {
+            name: String::new(),
+            address: H160::zero(),
+            connection: Mutex::new(Connection {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                next_id: 1,
+            }),
+            child,
+        };
+
+        let initialized: Initialized = plugin.connection.get_mut().unwrap().request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "chainId": chain_id,
+            }),
+        )?;
+        if initialized.protocol_version != PROTOCOL_VERSION {
+            return Err(Error::Plugin(format!(
+                "unsupported protocol version {} (expected {PROTOCOL_VERSION})",
+                initialized.protocol_version
+            )));
+        }
+        plugin.name = initialized.name;
+        plugin.address = initialized.address;
+        Ok(plugin)
+    }
+
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    // 署名から復元したアドレスが initialize のアドレスと違えばエラー
+    pub fn sign_hash(&self, hash: &H256) -> Result<Signature> {
+        let signed: Signed = self.connection.lock().unwrap().request(
+            "sign_hash",
+            json!({
+                "hash": hash,
+                "address": address::to_checksum(&self.address),
+            }),
+        )?;
+        let signature = parse_signature(&signed.signature)?;
+
+        let signer = signer::recover(hash, &signature)?;
+        if signer != self.address {
+            return Err(Error::UnexpectedSigner {
+                expected: address::to_checksum(&self.address),
+                actual: address::to_checksum(&signer),
+            });
+        }
+        Ok(signature)
+    }
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.name)
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Plugin {
+    // stdin を閉じるとプラグインは終了する
+    // 終了しないプラグインで CLI が止まらないよう、待っても終了しなければ kill する
+    fn drop(&mut self) {
+        if let Ok(connection) = self.connection.get_mut() {
+            connection.writer = Box::new(io::sink());
+        }
+        if let Some(child) = &mut self.child
+            && let Ok(None) = wait_or_kill(child, EXIT_GRACE_PERIOD)
+        {
+            eprintln!("Killed the signer plugin {} (it did not exit)", self.name);
+        }
+    }
+}
+
+// grace の間に終了すれば終了ステータスを返し、終了しなければ kill して None を返す
+fn wait_or_kill(child: &mut Child, grace: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + grace;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    child.kill()?;
+    child.wait()?;
+    Ok(None)
+}
+
+// r (32バイト) + s (32バイト) + v (0, 1, 27 or 28) の65バイト (s が大きければ正規化する)
+fn parse_signature(signature: &str) -> Result<Signature> {
+    let invalid = |message: String| Error::Plugin(format!("invalid signature: {message}"));
+    let bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
+        .map_err(|error| invalid(error.to_string()))?;
+    if bytes.len() != 65 {
+        return Err(invalid(format!("expected 65 bytes, got {}", bytes.len())));
+    }
+    let odd_y_parity = match bytes[64] {
+        0 | 27 => false,
+        1 | 28 => true,
+        v => return Err(invalid(format!("v must be 0, 1, 27 or 28, got {v}"))),
+    };
+
+    detached::normalize(Signature {
+        r: H256::from_slice(&bytes[..32]),
+        s: H256::from_slice(&bytes[32..64]),
+        odd_y_parity,
+    })
+}
+
+// PRIVATE_KEY で署名するプラグインとして動く (plugin serve、プロトコルの参照実装)
+// 入力が閉じられるまでリクエストに答える
+// initialize の chainId が chain_id (CHAIN_ID) と違えば拒否し、署名は audit で記録できた場合のみ返す
+pub fn serve(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    signing_key: &SigningKey,
+    chain_id: u64,
+    mut audit: impl FnMut(&H256) -> Result<()>,
+) -> Result<()> {
+    let address = address::from_signing_key(signing_key);
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => match handle(&request, signing_key, address, chain_id, &mut audit) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+                Err((code, message)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": request.id,
+                    "error": { "code": code, "message": message },
+                }),
+            },
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": error.to_string() },
+            }),
+        };
+        serde_json::to_writer(&mut writer, &response)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// エラーは JSON-RPC のエラーコードとメッセージ
+fn handle(
+    request: &Request,
+    signing_key: &SigningKey,
+    address: H160,
+    chain_id: u64,
+    audit: &mut impl FnMut(&H256) -> Result<()>,
+) -> std::result::Result<Value, (i64, String)> {
+    match request.method.as_str() {
+        "initialize" => {
+            let version = &request.params["protocolVersion"];
+            if version.as_u64() != Some(PROTOCOL_VERSION) {
+                return Err((-32602, format!("unsupported protocol version {version}")));
+            }
+            let requested = &request.params["chainId"];
+            if requested.as_u64() != Some(chain_id) {
+                return Err((
+                    -32602,
+                    format!("chainId {requested} does not match CHAIN_ID {chain_id}"),
+                ));
+            }
+            Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "name": env!("CARGO_PKG_NAME"),
+                "address": address::to_checksum(&address),
+            }))
+        }
+        "sign_hash" => {
+            let params: SignHashParams = serde_json::from_value(request.params.clone())
+                .map_err(|error| (-32602, error.to_string()))?;
+            if let Some(requested) = params.address
+                && requested != address
+            {
+                return Err((
+                    -32602,
+                    format!("unknown address {}", address::to_checksum(&requested)),
+                ));
+            }
+            let signature = signer::sign_hash(signing_key, &params.hash)
+                .map_err(|error| (-32603, error.to_string()))?;
+            audit(&params.hash).map_err(|error| (-32603, error.to_string()))?;
+            Ok(json!({ "signature": format!("0x{}", hex::encode(signature.to_bytes())) }))
+        }
+        method => Err((-32601, format!("method not found: {method}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bench::synthetic_message,
+        signer::{
+            Key,
+            tests::{TEST_ADDRESS, test_signing_key},
+        },
+        transaction,
+    };
+    use std::io::Cursor;
+
+    // plugin serve をスレッドで動かしてつなぐ
+    fn connect_serve() -> (Plugin, thread::JoinHandle<()>) {
+        let (request_reader, request_writer) = io::pipe().unwrap();
+        let (response_reader, response_writer) = io::pipe().unwrap();
+        let handle = thread::spawn(move || {
+            serve(
+                BufReader::new(request_reader),
+                response_writer,
+                &test_signing_key(),
+                1,
+                |_| Ok(()),
+            )
+            .unwrap();
+        });
+        let plugin =
+            Plugin::connect(BufReader::new(response_reader), request_writer, 1, None).unwrap();
+        (plugin, handle)
+    }
+
+    // 決められた結果 (result または error) をリクエストの id を付けて順番に返す
+    fn connect_fake(answers: Vec<Value>, chain_id: u64) -> Result<Plugin> {
+        let (request_reader, request_writer) = io::pipe().unwrap();
+        let (response_reader, mut response_writer) = io::pipe().unwrap();
+        thread::spawn(move || {
+            let mut lines = BufReader::new(request_reader).lines();
+            for mut answer in answers {
+                let Some(Ok(line)) = lines.next() else {
+                    return;
+                };
+                let request: Value = serde_json::from_str(&line).unwrap();
+                answer["jsonrpc"] = json!("2.0");
+                answer["id"] = request["id"].clone();
+                writeln!(response_writer, "{answer}").unwrap();
+            }
+        });
+        Plugin::connect(
+            BufReader::new(response_reader),
+            request_writer,
+            chain_id,
+            None,
+        )
+    }
+
+    fn initialized(address: &str) -> Value {
+        json!({ "result": { "protocolVersion": 1, "name": "fake", "address": address } })
+    }
+
+    #[test]
+    fn test_sign() {
+        let (plugin, handle) = connect_serve();
+        assert_eq!(plugin.address(), TEST_ADDRESS.parse::<H160>().unwrap());
+
+        // RFC 6979 で k が決まるため、ローカルの鍵と同じ署名になる
+        let key = Key::from_plugin(plugin);
+        assert_eq!(key.signing_key(), None);
+        let message = synthetic_message(1, 0);
+        assert_eq!(
+            transaction::sign(message.clone(), &key).unwrap(),
+            transaction::sign(message, &test_signing_key()).unwrap()
+        );
+
+        // Key を捨てると stdin が閉じられ、serve が終わる
+        drop(key);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_sign_wrong_signer() {
+        // initialize と違う鍵で署名する
+        let hash = H256::repeat_byte(0x11);
+        let other = SigningKey::from_slice(&[0x22; 32]).unwrap();
+        let signature = signer::sign_hash(&other, &hash).unwrap();
+        let plugin = connect_fake(
+            vec![
+                initialized(TEST_ADDRESS),
+                json!({ "result": { "signature": format!("0x{}", hex::encode(signature.to_bytes())) } }),
+            ],
+            1,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            plugin.sign_hash(&hash),
+            Err(Error::UnexpectedSigner { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sign_high_s() {
+        // s を n - s にした署名も受け付け、正規化する
+        let hash = H256::repeat_byte(0x11);
+        let signature = signer::sign_hash(&test_signing_key(), &hash).unwrap();
+        let ecdsa_signature =
+            k256::ecdsa::Signature::from_scalars(signature.r.0, signature.s.0).unwrap();
+        let (r, s) = ecdsa_signature.split_scalars();
+        let high_s = H256::from_slice(&(-*s).to_bytes());
+        let mut bytes = signature.to_bytes();
+        bytes[32..64].copy_from_slice(high_s.as_bytes());
+        bytes[64] = !signature.odd_y_parity as u8;
+        assert_eq!(H256::from_slice(&r.to_bytes()), signature.r);
+
+        let plugin = connect_fake(
+            vec![
+                initialized(TEST_ADDRESS),
+                json!({ "result": { "signature": format!("0x{}", hex::encode(bytes)) } }),
+            ],
+            1,
+        )
+        .unwrap();
+        assert_eq!(plugin.sign_hash(&hash).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_plugin_errors() {
+        // プラグインが返したエラー
+        let plugin = connect_fake(
+            vec![
+                initialized(TEST_ADDRESS),
+                json!({ "error": { "code": -32000, "message": "approval denied" } }),
+            ],
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            plugin.sign_hash(&H256::zero()).unwrap_err().to_string(),
+            "Signer plugin failed: approval denied (-32000)"
+        );
+
+        // プロトコルのバージョンが違う
+        let error = connect_fake(
+            vec![json!({ "result": { "protocolVersion": 2, "name": "fake", "address": TEST_ADDRESS } })],
+            1,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unsupported protocol version 2"));
+
+        // 答えずに終了した
+        let error = connect_fake(vec![], 1).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("exited without answering initialize")
+        );
+
+        // 65バイトでない署名
+        let plugin = connect_fake(
+            vec![
+                initialized(TEST_ADDRESS),
+                json!({ "result": { "signature": "0x1234" } }),
+            ],
+            1,
+        )
+        .unwrap();
+        assert!(matches!(
+            plugin.sign_hash(&H256::zero()),
+            Err(Error::Plugin(message)) if message.contains("expected 65 bytes")
+        ));
+    }
+
+    #[test]
+    fn test_wait_or_kill() {
+        // stdin を閉じれば終了するプラグイン
+        let mut child = Command::new("cat").stdin(Stdio::piped()).spawn().unwrap();
+        drop(child.stdin.take());
+        assert!(
+            wait_or_kill(&mut child, Duration::from_secs(5))
+                .unwrap()
+                .unwrap()
+                .success()
+        );
+
+        // stdin を閉じても終了しないプラグインは kill する
+        let mut child = Command::new("sleep")
+            .arg("60")
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdin.take());
+        let started = Instant::now();
+        assert!(
+            wait_or_kill(&mut child, Duration::from_millis(100))
+                .unwrap()
+                .is_none()
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_spawn_missing() {
+        assert!(matches!(
+            Plugin::spawn("/nonexistent/signer-plugin", 1),
+            Err(Error::Plugin(message)) if message.contains("/nonexistent/signer-plugin")
+        ));
+    }
+
+    #[test]
+    fn test_serve() {
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":1,"chainId":1}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"sign_hash","params":{"hash":"0x1111111111111111111111111111111111111111111111111111111111111111"}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"sign_hash","params":{"hash":"0x1111111111111111111111111111111111111111111111111111111111111111","address":"0x0000000000000000000000000000000000000001"}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+            "not json",
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        let mut audited = Vec::new();
+        serve(
+            Cursor::new(input),
+            &mut output,
+            &test_signing_key(),
+            1,
+            |hash| {
+                audited.push(*hash);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["address"], TEST_ADDRESS);
+        assert_eq!(responses[0]["result"]["protocolVersion"], 1);
+
+        let signature = signer::sign_hash(&test_signing_key(), &H256::repeat_byte(0x11)).unwrap();
+        assert_eq!(
+            responses[1]["result"]["signature"],
+            format!("0x{}", hex::encode(signature.to_bytes()))
+        );
+        assert_eq!(responses[2]["error"]["code"], -32602);
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(responses[3]["error"]["code"], -32601);
+        assert_eq!(responses[4]["id"], Value::Null);
+        assert_eq!(responses[4]["error"]["code"], -32700);
+        // 署名したハッシュのみ記録する
+        assert_eq!(audited, [H256::repeat_byte(0x11)]);
+    }
+
+    #[test]
+    fn test_serve_rejects() {
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":1,"chainId":11155111}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"sign_hash","params":{"hash":"0x1111111111111111111111111111111111111111111111111111111111111111"}}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        // 記録できなければ署名を返さない
+        serve(
+            Cursor::new(input),
+            &mut output,
+            &test_signing_key(),
+            1,
+            |_| Err(Error::Plugin("audit log is unavailable".to_string())),
+        )
+        .unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses[0]["error"]["code"], -32602);
+        assert!(
+            responses[0]["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("does not match CHAIN_ID 1")
+        );
+        assert_eq!(responses[1]["error"]["code"], -32603);
+        assert!(responses[1].get("result").is_none());
+    }
+}
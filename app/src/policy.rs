@@ -0,0 +1,1114 @@
+use crate::{Result, abi, de, deploy, error::Error, json, params::Params, state::StateFile, units};
+use ethereum::{EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, U256};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+const ETHER_DECIMALS: u8 = 18;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn default_true() -> bool {
+    true
+}
+
+// ポリシーファイル (TOML / YAML、拡張子で判別)
+// 省略した項目は制限しない
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    // ETH 単位 ("0.5")
+    #[serde(default)]
+    max_value_per_tx: Option<String>,
+    #[serde(default)]
+    max_value_per_day: Option<String>,
+    // これを超える value は2人目のオペレーターの承認が必要 (署名サーバーのみ)
+    #[serde(default)]
+    approval_threshold: Option<String>,
+    // max_value_per_day の集計を保存するファイル (CLI の実行をまたいで数えるため必須)
+    #[serde(default)]
+    ledger: Option<PathBuf>,
+    #[serde(default)]
+    allowed_chains: Option<Vec<u64>>,
+    #[serde(default)]
+    allowed_recipients: Option<Vec<H160>>,
+    // "0xa9059cbb" または "transfer(address,uint256)"
+    #[serde(default)]
+    allowed_selectors: Option<Vec<String>>,
+    // コントラクトごとに呼び出せる関数
+    #[serde(default)]
+    allowed_calls: Option<Vec<AllowedCall>>,
+    #[serde(default)]
+    max_calldata_size: Option<usize>,
+    #[serde(default = "default_true")]
+    allow_contract_creation: bool,
+    // personal_sign / EIP-712 / ダイジェストなどトランザクション以外への署名
+    #[serde(default = "default_true")]
+    allow_message_signing: bool,
+}
+
+// [[allowed_calls]] の1つ (同じ contract を複数回書いた場合は合わせる)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllowedCall {
+    contract: H160,
+    // allowed_selectors と同じ形式
+    selectors: Vec<String>,
+}
+
+// トランザクションに対して確認するルール (policy test の結果に出す順)
+const TRANSACTION_RULES: &[&str] = &[
+    "allowed_chains",
+    "allow_contract_creation",
+    "allowed_recipients",
+    "allowed_selectors",
+    "allowed_calls",
+    "max_calldata_size",
+    "max_value_per_tx",
+    "approval_threshold",
+];
+
+// ポリシーに違反した署名要求 (rule はメトリクスのラベルに使う)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+impl Violation {
+    fn new(rule: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            rule,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl From<Violation> for Error {
+    fn from(violation: Violation) -> Self {
+        Error::PolicyViolation(violation.reason)
+    }
+}
+
+// 1日あたりの value の合計 (UNIX 時間の日数ごと)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Ledger {
+    day: u64,
+    value: U256,
+}
+
+#[derive(Debug)]
+struct DailyLimit {
+    limit: U256,
+    ledger: PathBuf,
+    // 同じプロセス内の読み書きを直列化する (別のプロセスとは集計ファイルの .lock で排他する)
+    lock: Mutex<()>,
+}
+
+impl DailyLimit {
+    fn reserve(&self, value: U256, day: u64) -> std::result::Result<(), Violation> {
+        let _guard = self.lock.lock().unwrap();
+        let violation = |reason: String| Violation::new("max_value_per_day", reason);
+        let ledger_error = |error: &dyn std::fmt::Display| {
+            violation(format!(
+                "failed to update the ledger {}: {error}",
+                self.ledger.display()
+            ))
+        };
+        // 同時に実行した CLI が同じ合計を読んで、合わせて上限を超えないようにする
+        let _file_lock = StateFile::new(&self.ledger, None)
+            .lock()
+            .map_err(|error| ledger_error(&error))?;
+
+        let mut ledger = match std::fs::read(&self.ledger) {
+            Ok(content) => {
+                serde_json::from_slice::<Ledger>(&content).map_err(|error| ledger_error(&error))?
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ledger::default(),
+            Err(error) => return Err(ledger_error(&error)),
+        };
+        if ledger.day != day {
+            ledger = Ledger {
+                day,
+                value: U256::zero(),
+            };
+        }
+
+        match ledger.value.checked_add(value) {
+            Some(total) if total <= self.limit => ledger.value = total,
+            _ => {
+                return Err(violation(format!(
+                    "value would exceed the daily limit of {} wei ({} wei already signed today)",
+                    self.limit, ledger.value
+                )));
+            }
+        }
+
+        let content = serde_json::to_vec(&ledger).map_err(|error| ledger_error(&error))?;
+        std::fs::write(&self.ledger, content).map_err(|error| ledger_error(&error))
+    }
+
+    // reserve で加えたが署名しなかった value を戻す (日付が変わっていれば何もしない)
+    fn release(&self, value: U256, day: u64) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let _file_lock = StateFile::new(&self.ledger, None).lock()?;
+        let mut ledger = match std::fs::read(&self.ledger) {
+            Ok(content) => serde_json::from_slice::<Ledger>(&content)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        if ledger.day != day {
+            return Ok(());
+        }
+        ledger.value = ledger.value.saturating_sub(value);
+        std::fs::write(&self.ledger, serde_json::to_vec(&ledger)?).map_err(Into::into)
+    }
+}
+
+// 署名前に確認するルール
+#[derive(Debug)]
+pub struct Policy {
+    max_value_per_tx: Option<U256>,
+    daily: Option<DailyLimit>,
+    approval_threshold: Option<U256>,
+    allowed_chains: Option<Vec<u64>>,
+    allowed_recipients: Option<Vec<H160>>,
+    allowed_selectors: Option<Vec<[u8; 4]>>,
+    allowed_calls: Option<HashMap<H160, Vec<[u8; 4]>>>,
+    max_calldata_size: Option<usize>,
+    allow_contract_creation: bool,
+    allow_message_signing: bool,
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidPolicy(reason.into())
+}
+
+fn parse_selector(s: &str) -> Result<[u8; 4]> {
+    if let Some(hex_str) = s.strip_prefix("0x") {
+        return hex::decode(hex_str)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid(format!("invalid selector '{s}' (expected 4 bytes)")));
+    }
+
+    abi::Function::parse(s)
+        .map(|function| function.selector())
+        .map_err(|error| invalid(format!("invalid selector '{s}': {error}")))
+}
+
+fn parse_allowed_calls(calls: Vec<AllowedCall>) -> Result<HashMap<H160, Vec<[u8; 4]>>> {
+    let mut allowed: HashMap<H160, Vec<[u8; 4]>> = HashMap::new();
+    for call in calls {
+        let selectors = call
+            .selectors
+            .iter()
+            .map(|s| parse_selector(s))
+            .collect::<Result<Vec<_>>>()?;
+        allowed.entry(call.contract).or_default().extend(selectors);
+    }
+    Ok(allowed)
+}
+
+fn parse_ether(field: &str, amount: &str) -> Result<U256> {
+    units::parse_units(amount, ETHER_DECIMALS).map_err(|error| invalid(format!("{field}: {error}")))
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECONDS_PER_DAY
+}
+
+impl Policy {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file: PolicyFile = config::Config::builder()
+            .add_source(config::File::from(path.as_ref()))
+            .build()
+            .and_then(config::Config::try_deserialize)
+            .map_err(Error::in_file(&path))?;
+
+        Self::from_file(file)
+    }
+
+    fn from_file(file: PolicyFile) -> Result<Self> {
+        let daily = match (file.max_value_per_day, file.ledger) {
+            (Some(limit), Some(ledger)) => Some(DailyLimit {
+                limit: parse_ether("max_value_per_day", &limit)?,
+                ledger,
+                lock: Mutex::new(()),
+            }),
+            (Some(_), None) => return Err(invalid("max_value_per_day requires ledger")),
+            (None, _) => None,
+        };
+
+        Ok(Self {
+            max_value_per_tx: file
+                .max_value_per_tx
+                .map(|value| parse_ether("max_value_per_tx", &value))
+                .transpose()?,
+            daily,
+            approval_threshold: file
+                .approval_threshold
+                .map(|value| parse_ether("approval_threshold", &value))
+                .transpose()?,
+            allowed_chains: file.allowed_chains,
+            allowed_recipients: file.allowed_recipients,
+            allowed_selectors: file
+                .allowed_selectors
+                .map(|selectors| selectors.iter().map(|s| parse_selector(s)).collect())
+                .transpose()?,
+            allowed_calls: file.allowed_calls.map(parse_allowed_calls).transpose()?,
+            max_calldata_size: file.max_calldata_size,
+            allow_contract_creation: file.allow_contract_creation,
+            allow_message_signing: file.allow_message_signing,
+        })
+    }
+
+    // トランザクションがルールに合うか確認する (1日の合計は reserve_value で数える)
+    pub fn check_transaction(
+        &self,
+        message: &EIP1559TransactionMessage,
+    ) -> std::result::Result<(), Violation> {
+        if self
+            .allowed_chains
+            .as_ref()
+            .is_some_and(|chains| !chains.contains(&message.chain_id))
+        {
+            return Err(Violation::new(
+                "allowed_chains",
+                format!("chain id {} is not allowed", message.chain_id),
+            ));
+        }
+
+        // CREATE2 のファクトリー (create2 deploy など) の呼び出しもコントラクトの作成とみなす
+        if let TransactionAction::Call(factory) = message.action
+            && deploy::KNOWN_CREATE2_FACTORIES.contains(&factory)
+        {
+            self.check_create2(&factory)?;
+        }
+        match message.action {
+            TransactionAction::Create if !self.allow_contract_creation => {
+                return Err(Violation::new(
+                    "allow_contract_creation",
+                    "contract creation is not allowed",
+                ));
+            }
+            TransactionAction::Create => {}
+            TransactionAction::Call(to) => {
+                if self
+                    .allowed_recipients
+                    .as_ref()
+                    .is_some_and(|recipients| !recipients.contains(&to))
+                {
+                    return Err(Violation::new(
+                        "allowed_recipients",
+                        format!("recipient {to:?} is not allowed"),
+                    ));
+                }
+                // calldata なし (ETH の送金) はセレクタを確認しない
+                let is_allowed = |selectors: &Vec<[u8; 4]>| {
+                    message.input.is_empty()
+                        || message
+                            .input
+                            .get(..4)
+                            .is_some_and(|selector| selectors.iter().any(|s| s == selector))
+                };
+                if self
+                    .allowed_selectors
+                    .as_ref()
+                    .is_some_and(|selectors| !is_allowed(selectors))
+                {
+                    return Err(Violation::new(
+                        "allowed_selectors",
+                        format!(
+                            "function selector 0x{} is not allowed",
+                            hex::encode(&message.input[..message.input.len().min(4)])
+                        ),
+                    ));
+                }
+                if !message.input.is_empty()
+                    && self
+                        .allowed_calls
+                        .as_ref()
+                        .is_some_and(|calls| !calls.get(&to).is_some_and(is_allowed))
+                {
+                    return Err(Violation::new(
+                        "allowed_calls",
+                        format!(
+                            "function selector 0x{} is not allowed on {to:?}",
+                            hex::encode(&message.input[..message.input.len().min(4)])
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(limit) = self
+            .max_calldata_size
+            .filter(|limit| message.input.len() > *limit)
+        {
+            return Err(Violation::new(
+                "max_calldata_size",
+                format!(
+                    "calldata is {} bytes, more than the limit of {limit}",
+                    message.input.len()
+                ),
+            ));
+        }
+        if let Some(limit) = self.max_value_per_tx.filter(|limit| message.value > *limit) {
+            return Err(Violation::new(
+                "max_value_per_tx",
+                format!(
+                    "value {} wei exceeds the limit of {limit} wei",
+                    message.value
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // CREATE2 のファクトリーを呼び出してコントラクトを作成できるか
+    // create2 deploy は --factory で指定した既知でないファクトリーもここで確認する
+    pub fn check_create2(&self, factory: &H160) -> std::result::Result<(), Violation> {
+        match self.allow_contract_creation {
+            true => Ok(()),
+            false => Err(Violation::new(
+                "allow_contract_creation",
+                format!("contract creation through the CREATE2 factory {factory:?} is not allowed"),
+            )),
+        }
+    }
+
+    // 2人目のオペレーターの承認なしで署名できるか
+    pub fn check_approval_threshold(
+        &self,
+        message: &EIP1559TransactionMessage,
+    ) -> std::result::Result<(), Violation> {
+        match self.approval_threshold {
+            Some(threshold) if message.value > threshold => Err(Violation::new(
+                "approval_threshold",
+                format!(
+                    "value {} wei is above the approval threshold of {threshold} wei and needs a second operator's approval",
+                    message.value
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // 署名する value を当日の合計に加える (上限を超える場合は加えない)
+    pub fn reserve_value(&self, value: U256) -> std::result::Result<(), Violation> {
+        match &self.daily {
+            Some(daily) => daily.reserve(value, today()),
+            None => Ok(()),
+        }
+    }
+
+    // reserve_value したが署名しなかった value を1日の合計から戻す
+    pub fn release_value(&self, value: U256) -> Result<()> {
+        match &self.daily {
+            Some(daily) => daily.release(value, today()),
+            None => Ok(()),
+        }
+    }
+
+    // トランザクションに設定されているルール
+    fn transaction_rules(&self) -> Vec<&'static str> {
+        let configured = [
+            self.allowed_chains.is_some(),
+            !self.allow_contract_creation,
+            self.allowed_recipients.is_some(),
+            self.allowed_selectors.is_some(),
+            self.allowed_calls.is_some(),
+            self.max_calldata_size.is_some(),
+            self.max_value_per_tx.is_some(),
+            self.approval_threshold.is_some(),
+        ];
+        TRANSACTION_RULES
+            .iter()
+            .zip(configured)
+            .filter_map(|(rule, configured)| configured.then_some(*rule))
+            .collect()
+    }
+
+    // トランザクション以外への署名を許可するか
+    pub fn check_message(&self) -> std::result::Result<(), Violation> {
+        if self.allow_message_signing {
+            Ok(())
+        } else {
+            Err(Violation::new(
+                "allow_message_signing",
+                "signing messages is not allowed",
+            ))
+        }
+    }
+}
+
+// policy test で確認するトランザクションの例 (1ファイルに1つ)
+// expect は "allow"・"deny" または拒否するはずのルール名 (省略した場合は結果を出すだけ)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Example {
+    #[serde(default)]
+    chain_id: Option<u64>,
+    #[serde(default)]
+    expect: Option<String>,
+    // transaction か deploy (コントラクトの作成) のどちらか
+    #[serde(default)]
+    transaction: Option<Params>,
+    #[serde(default)]
+    deploy: Option<DeployExample>,
+}
+
+// コントラクトを作成するトランザクションの例 (allow_contract_creation・max_calldata_size の確認用)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeployExample {
+    #[serde(deserialize_with = "de::deserialize_hex_bytes")]
+    init_code: Vec<u8>,
+    #[serde(default, deserialize_with = "de::deserialize_optional_u256")]
+    value: Option<U256>,
+    #[serde(deserialize_with = "de::deserialize_u256")]
+    gas_limit: U256,
+}
+
+// 例を1つ確認した結果
+#[derive(Debug)]
+pub struct ExampleReport {
+    pub name: String,
+    pub result: std::result::Result<(), Violation>,
+    pub expect: Option<String>,
+    // 許可した場合に確認したルール
+    pub rules: Vec<&'static str>,
+}
+
+impl ExampleReport {
+    // expect どおりの結果か (expect がなければ常に true)
+    pub fn passed(&self) -> bool {
+        match (self.expect.as_deref(), &self.result) {
+            (None, _) | (Some("allow"), Ok(())) | (Some("deny"), Err(_)) => true,
+            (Some(rule), Err(violation)) => violation.rule == rule,
+            (Some(_), Ok(())) => false,
+        }
+    }
+
+    pub fn line(&self) -> String {
+        let status = match (self.passed(), &self.result) {
+            (false, _) => "FAIL ",
+            (true, Ok(())) => "ALLOW",
+            (true, Err(_)) => "DENY ",
+        };
+        let outcome = match &self.result {
+            Ok(()) if self.rules.is_empty() => "allowed (no transaction rules)".to_string(),
+            Ok(()) => format!("allowed by {}", self.rules.join(", ")),
+            Err(violation) => format!("denied by {}: {}", violation.rule, violation.reason),
+        };
+        match &self.expect {
+            Some(expect) if !self.passed() => {
+                format!("{status} {}: {outcome} (expected {expect})", self.name)
+            }
+            _ => format!("{status} {}: {outcome}", self.name),
+        }
+    }
+}
+
+// ディレクトリの *.json の例をファイル名の順にポリシーで確認する
+// 1日の上限 (max_value_per_day) は実際の集計を変えないよう確認しない
+pub fn test_examples(
+    policy: &Policy,
+    dir: &Path,
+    default_chain_id: Option<u64>,
+) -> Result<Vec<ExampleReport>> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(Error::in_file(dir))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(Error::in_file(dir))?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let content = std::fs::read_to_string(path).map_err(Error::in_file(path))?;
+            let example: Example = json::parse(&content).map_err(Error::in_file(path))?;
+            test_example(policy, example, default_chain_id)
+                .map(|(result, expect)| ExampleReport {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    result,
+                    expect,
+                    rules: policy.transaction_rules(),
+                })
+                .map_err(Error::in_file(path))
+        })
+        .collect()
+}
+
+type ExampleResult = (std::result::Result<(), Violation>, Option<String>);
+
+fn test_example(
+    policy: &Policy,
+    example: Example,
+    default_chain_id: Option<u64>,
+) -> Result<ExampleResult> {
+    if let Some(expect) = example.expect.as_deref().filter(|expect| {
+        !matches!(*expect, "allow" | "deny") && !TRANSACTION_RULES.contains(expect)
+    }) {
+        return Err(invalid(format!(
+            "expect: unknown value '{expect}', expected allow, deny or one of {}",
+            TRANSACTION_RULES.join(", ")
+        )));
+    }
+    let chain_id = example
+        .chain_id
+        .or(default_chain_id)
+        .ok_or_else(|| invalid("chain_id: missing (set it in the example or CHAIN_ID)"))?;
+    let (nonce, gas_limit, action, value, input) = match (example.transaction, example.deploy) {
+        (Some(transaction), None) => {
+            let params = transaction.encode_function()?;
+            (
+                params.nonce.unwrap_or_default(),
+                params.gas_limit,
+                TransactionAction::Call(params.to_address),
+                params.value,
+                params.input,
+            )
+        }
+        (None, Some(deploy)) => (
+            U256::zero(),
+            deploy.gas_limit,
+            TransactionAction::Create,
+            deploy.value.unwrap_or_default(),
+            deploy.init_code,
+        ),
+        _ => return Err(invalid("specify either transaction or deploy")),
+    };
+    let message = EIP1559TransactionMessage {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: U256::zero(),
+        max_fee_per_gas: U256::zero(),
+        gas_limit,
+        action,
+        value,
+        input,
+        access_list: Default::default(),
+    };
+    let result = policy
+        .check_transaction(&message)
+        .and_then(|()| policy.check_approval_threshold(&message));
+    Ok((result, example.expect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum::AccessList;
+    use ethereum_types::H256;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn policy_file(extension: &str, content: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{extension}"))
+            .tempfile()
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn message(action: TransactionAction, value: u64, input: Vec<u8>) -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::zero(),
+            gas_limit: U256::from(21000),
+            action,
+            value: U256::from(value),
+            input,
+            access_list: AccessList::default(),
+        }
+    }
+
+    fn recipient() -> H160 {
+        "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let file = policy_file(
+            "toml",
+            r#"
+            max_value_per_tx = "0.5"
+            allowed_chains = [1, 11155111]
+            allowed_recipients = ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]
+            allowed_selectors = ["0xa9059cbb", "approve(address spender, uint256 amount)"]
+            max_calldata_size = 68
+            allow_contract_creation = false
+            "#,
+        );
+        let policy = Policy::from_path(file.path()).unwrap();
+
+        assert_eq!(
+            policy.max_value_per_tx,
+            Some(U256::from(500_000_000_000_000_000u64))
+        );
+        assert_eq!(policy.allowed_chains, Some(vec![1, 11155111]));
+        assert_eq!(policy.allowed_recipients, Some(vec![recipient()]));
+        assert_eq!(
+            policy.allowed_selectors,
+            Some(vec![[0xa9, 0x05, 0x9c, 0xbb], [0x09, 0x5e, 0xa7, 0xb3]])
+        );
+        assert_eq!(policy.max_calldata_size, Some(68));
+        assert!(!policy.allow_contract_creation);
+        assert!(policy.allow_message_signing);
+    }
+
+    #[test]
+    fn test_from_yaml() {
+        let file = policy_file(
+            "yaml",
+            "max_value_per_tx: \"1\"\nallowed_chains:\n  - 10\nallow_message_signing: false\n",
+        );
+        let policy = Policy::from_path(file.path()).unwrap();
+
+        assert_eq!(
+            policy.max_value_per_tx,
+            Some(U256::from(1_000_000_000_000_000_000u64))
+        );
+        assert_eq!(policy.allowed_chains, Some(vec![10]));
+        assert!(policy.allow_contract_creation);
+        assert_eq!(
+            policy.check_message().unwrap_err().rule,
+            "allow_message_signing"
+        );
+    }
+
+    #[test]
+    fn test_from_path_errors() {
+        // 綴りを間違えたルールが無視されないようにする
+        let file = policy_file("toml", "max_value_per_txn = \"1\"\n");
+        assert!(Policy::from_path(file.path()).is_err());
+
+        let file = policy_file("toml", "max_value_per_day = \"1\"\n");
+        assert!(matches!(
+            Policy::from_path(file.path()),
+            Err(Error::InvalidPolicy(_))
+        ));
+
+        let file = policy_file("toml", "allowed_selectors = [\"0xa9059c\"]\n");
+        assert!(matches!(
+            Policy::from_path(file.path()),
+            Err(Error::InvalidPolicy(_))
+        ));
+
+        let file = policy_file("toml", "max_value_per_tx = \"0.1234567890123456789\"\n");
+        assert!(matches!(
+            Policy::from_path(file.path()),
+            Err(Error::InvalidPolicy(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_transaction() {
+        let file = policy_file(
+            "toml",
+            r#"
+            max_value_per_tx = "0.000000000000001"
+            allowed_chains = [11155111]
+            allowed_recipients = ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]
+            allowed_selectors = ["transfer(address,uint256)"]
+            max_calldata_size = 68
+            allow_contract_creation = false
+            "#,
+        );
+        let policy = Policy::from_path(file.path()).unwrap();
+        let call = TransactionAction::Call(recipient());
+        let rule = |message| policy.check_transaction(&message).unwrap_err().rule;
+
+        assert!(
+            policy
+                .check_transaction(&message(call, 1000, vec![]))
+                .is_ok()
+        );
+        let transfer = [vec![0xa9, 0x05, 0x9c, 0xbb], vec![0; 64]].concat();
+        assert!(
+            policy
+                .check_transaction(&message(call, 0, transfer.clone()))
+                .is_ok()
+        );
+
+        assert_eq!(rule(message(call, 1001, vec![])), "max_value_per_tx");
+        assert_eq!(
+            rule(message(TransactionAction::Call(H160::zero()), 0, vec![])),
+            "allowed_recipients"
+        );
+        assert_eq!(
+            rule(message(call, 0, vec![0x09, 0x5e, 0xa7, 0xb3])),
+            "allowed_selectors"
+        );
+        // 4バイトに満たない calldata
+        assert_eq!(rule(message(call, 0, vec![0xa9])), "allowed_selectors");
+        assert_eq!(
+            rule(message(call, 0, [transfer, vec![0]].concat())),
+            "max_calldata_size"
+        );
+        assert_eq!(
+            rule(message(TransactionAction::Create, 0, vec![0x60])),
+            "allow_contract_creation"
+        );
+
+        let mut other_chain = message(call, 0, vec![]);
+        other_chain.chain_id = 1;
+        assert_eq!(
+            policy.check_transaction(&other_chain),
+            Err(Violation::new(
+                "allowed_chains",
+                "chain id 1 is not allowed"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_contract_creation_through_create2_factory() {
+        // create2 deploy が呼び出す calldata (salt + init code)
+        let deploy = |factory| {
+            message(
+                TransactionAction::Call(factory),
+                0,
+                deploy::create2_factory_calldata(&H256::zero(), &[0x60, 0x80]),
+            )
+        };
+        let policy =
+            Policy::from_path(policy_file("toml", "allow_contract_creation = false\n").path())
+                .unwrap();
+        for factory in deploy::KNOWN_CREATE2_FACTORIES {
+            let violation = policy.check_transaction(&deploy(factory)).unwrap_err();
+            assert_eq!(violation.rule, "allow_contract_creation");
+            assert!(
+                violation.reason.contains("CREATE2 factory"),
+                "{violation:?}"
+            );
+        }
+        // ファクトリー以外への呼び出しと、作成を許可した場合は通る
+        assert!(policy.check_transaction(&deploy(recipient())).is_ok());
+        assert_eq!(
+            policy.check_create2(&recipient()).unwrap_err().rule,
+            "allow_contract_creation"
+        );
+        let policy =
+            Policy::from_path(policy_file("toml", "max_calldata_size = 1024\n").path()).unwrap();
+        assert!(
+            policy
+                .check_transaction(&deploy(deploy::CREATE2_FACTORY_ADDRESS))
+                .is_ok()
+        );
+        assert!(policy.check_create2(&recipient()).is_ok());
+    }
+
+    #[test]
+    fn test_allowed_calls() {
+        let file = policy_file(
+            "toml",
+            r#"
+            [[allowed_calls]]
+            contract = "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"
+            selectors = ["transfer(address,uint256)"]
+
+            [[allowed_calls]]
+            contract = "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"
+            selectors = ["0x095ea7b3"]
+            "#,
+        );
+        let policy = Policy::from_path(file.path()).unwrap();
+        let usdc = TransactionAction::Call(
+            "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"
+                .parse()
+                .unwrap(),
+        );
+        let transfer = [vec![0xa9, 0x05, 0x9c, 0xbb], vec![0; 64]].concat();
+        let approve = [vec![0x09, 0x5e, 0xa7, 0xb3], vec![0; 64]].concat();
+
+        for input in [transfer.clone(), approve] {
+            assert!(policy.check_transaction(&message(usdc, 0, input)).is_ok());
+        }
+        // ETH の送金は対象外
+        let call = TransactionAction::Call(recipient());
+        assert!(policy.check_transaction(&message(call, 1, vec![])).is_ok());
+
+        // 許可していない関数と、許可していないコントラクトの呼び出し
+        let violation = policy
+            .check_transaction(&message(usdc, 0, vec![0x23, 0xb8, 0x72, 0xdd]))
+            .unwrap_err();
+        assert_eq!(violation.rule, "allowed_calls");
+        assert!(
+            violation.reason.contains("0x23b872dd"),
+            "{}",
+            violation.reason
+        );
+        assert_eq!(
+            policy
+                .check_transaction(&message(call, 0, transfer))
+                .unwrap_err()
+                .rule,
+            "allowed_calls"
+        );
+        assert_eq!(policy.transaction_rules(), ["allowed_calls"]);
+    }
+
+    #[test]
+    fn test_approval_threshold() {
+        let policy = Policy::from_path(
+            policy_file("toml", "approval_threshold = \"0.000000000000001\"\n").path(),
+        )
+        .unwrap();
+        let call = TransactionAction::Call(recipient());
+
+        assert!(
+            policy
+                .check_approval_threshold(&message(call, 1000, vec![]))
+                .is_ok()
+        );
+        assert_eq!(
+            policy
+                .check_approval_threshold(&message(call, 1001, vec![]))
+                .unwrap_err()
+                .rule,
+            "approval_threshold"
+        );
+        // 閾値はほかのルールとは別に確認する
+        assert!(
+            policy
+                .check_transaction(&message(call, 1001, vec![]))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_empty_policy_allows_everything() {
+        let policy = Policy::from_path(policy_file("toml", "").path()).unwrap();
+
+        assert!(
+            policy
+                .check_transaction(&message(
+                    TransactionAction::Create,
+                    u64::MAX,
+                    vec![0; 100_000]
+                ))
+                .is_ok()
+        );
+        assert!(policy.reserve_value(U256::MAX).is_ok());
+        assert!(
+            policy
+                .check_approval_threshold(&message(TransactionAction::Create, u64::MAX, vec![]))
+                .is_ok()
+        );
+        assert!(policy.check_message().is_ok());
+    }
+
+    #[test]
+    fn test_daily_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let daily = DailyLimit {
+            limit: U256::from(100),
+            ledger: dir.path().join("ledger.json"),
+            lock: Mutex::new(()),
+        };
+
+        assert!(daily.reserve(U256::from(60), 1).is_ok());
+        let violation = daily.reserve(U256::from(41), 1).unwrap_err();
+        assert_eq!(violation.rule, "max_value_per_day");
+        assert_eq!(
+            violation.reason,
+            "value would exceed the daily limit of 100 wei (60 wei already signed today)"
+        );
+        assert!(daily.reserve(U256::from(40), 1).is_ok());
+
+        // 署名しなかった分は戻せる (前の日の分は戻さない)
+        daily.release(U256::from(30), 1).unwrap();
+        assert!(daily.reserve(U256::from(31), 1).is_err());
+        assert!(daily.reserve(U256::from(30), 1).is_ok());
+        daily.release(U256::from(100), 0).unwrap();
+        assert!(daily.reserve(U256::one(), 1).is_err());
+
+        // 別のプロセス (CLI の次の実行) からもファイルで引き継ぐ
+        let next_run = DailyLimit {
+            limit: U256::from(100),
+            ledger: daily.ledger.clone(),
+            lock: Mutex::new(()),
+        };
+        assert!(next_run.reserve(U256::one(), 1).is_err());
+        // 日付が変わるとリセットされる
+        assert!(next_run.reserve(U256::from(100), 2).is_ok());
+
+        // 壊れた集計ファイルでは署名しない
+        std::fs::write(&daily.ledger, "not json").unwrap();
+        assert!(daily.reserve(U256::zero(), 2).is_err());
+    }
+
+    #[test]
+    fn test_daily_limit_concurrent() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = dir.path().join("ledger.json");
+
+        // 別のプロセスと同じく、それぞれ別の DailyLimit から同じ集計ファイルに加える
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let ledger = ledger.clone();
+                std::thread::spawn(move || {
+                    let daily = DailyLimit {
+                        limit: U256::from(50),
+                        ledger,
+                        lock: Mutex::new(()),
+                    };
+                    (0..20)
+                        .filter(|_| daily.reserve(U256::one(), 1).is_ok())
+                        .count()
+                })
+            })
+            .collect();
+        let reserved: usize = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+
+        assert_eq!(reserved, 50);
+        let ledger: Ledger = serde_json::from_slice(&std::fs::read(&ledger).unwrap()).unwrap();
+        assert_eq!(ledger.value, U256::from(50));
+    }
+
+    #[test]
+    fn test_example_reports() {
+        let policy = Policy::from_path(
+            policy_file(
+                "toml",
+                r#"
+                allowed_chains = [11155111]
+                allowed_recipients = ["0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]
+                allowed_selectors = ["transfer(address,uint256)"]
+                approval_threshold = "1"
+                "#,
+            )
+            .path(),
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let write =
+            |name: &str, content: &str| std::fs::write(dir.path().join(name), content).unwrap();
+        let to = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df";
+        write(
+            "1-transfer.json",
+            &format!(
+                r#"{{"expect": "allow", "transaction": {{"to_address": "{to}", "value": "0", "gas_limit": "60000", "function": "transfer(address,uint256)", "args": ["{to}", "1"]}}}}"#
+            ),
+        );
+        write(
+            "2-approve.json",
+            &format!(
+                r#"{{"expect": "allowed_selectors", "transaction": {{"to_address": "{to}", "value": "0", "gas_limit": "60000", "function": "approve(address,uint256)", "args": ["{to}", "1"]}}}}"#
+            ),
+        );
+        write(
+            "3-mainnet.json",
+            &format!(
+                r#"{{"chain_id": 1, "expect": "deny", "transaction": {{"to_address": "{to}", "value": "0", "gas_limit": "21000"}}}}"#
+            ),
+        );
+        // 期待と違う結果
+        write(
+            "4-large.json",
+            &format!(
+                r#"{{"expect": "allow", "transaction": {{"to_address": "{to}", "value": "2000000000000000000", "gas_limit": "21000"}}}}"#
+            ),
+        );
+        write("README.md", "not an example");
+
+        let reports = test_examples(&policy, dir.path(), Some(11155111)).unwrap();
+        let lines: Vec<_> = reports.iter().map(ExampleReport::line).collect();
+        assert_eq!(lines.len(), 4, "{lines:?}");
+        assert_eq!(
+            lines[0],
+            "ALLOW 1-transfer.json: allowed by allowed_chains, allowed_recipients, allowed_selectors, approval_threshold"
+        );
+        assert!(
+            lines[1].starts_with("DENY  2-approve.json: denied by allowed_selectors: "),
+            "{}",
+            lines[1]
+        );
+        assert!(
+            lines[2].starts_with("DENY  3-mainnet.json: denied by allowed_chains: "),
+            "{}",
+            lines[2]
+        );
+        assert!(
+            lines[3].starts_with("FAIL  4-large.json: denied by approval_threshold: ")
+                && lines[3].ends_with("(expected allow)"),
+            "{}",
+            lines[3]
+        );
+        assert_eq!(reports.iter().filter(|report| !report.passed()).count(), 1);
+
+        // chain id が決まらない例、知らない expect、読めない例はファイル名を付けてエラー
+        assert!(test_examples(&policy, dir.path(), None).is_err());
+        write(
+            "5-typo.json",
+            &format!(
+                r#"{{"chain_id": 1, "expect": "allowed_recipient", "transaction": {{"to_address": "{to}", "value": "0", "gas_limit": "21000"}}}}"#
+            ),
+        );
+        let error = test_examples(&policy, dir.path(), Some(11155111)).unwrap_err();
+        assert!(
+            matches!(&error, Error::File { path, .. } if path.ends_with("5-typo.json")),
+            "{error}"
+        );
+        write("5-typo.json", r#"{"transaction": {}, "expected": "allow"}"#);
+        assert!(test_examples(&policy, dir.path(), Some(11155111)).is_err());
+    }
+
+    #[test]
+    fn test_deploy_example() {
+        let policy = Policy::from_path(
+            policy_file(
+                "toml",
+                "allow_contract_creation = false\nmax_calldata_size = 4\n",
+            )
+            .path(),
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("deploy.json"),
+            r#"{"expect": "allow_contract_creation", "deploy": {"init_code": "0x6080", "gas_limit": "100000"}}"#,
+        )
+        .unwrap();
+        let reports = test_examples(&policy, dir.path(), Some(11155111)).unwrap();
+        assert!(reports[0].passed(), "{}", reports[0].line());
+
+        // transaction と deploy の両方、またはどちらもない例はエラー
+        for example in [
+            r#"{"expect": "deny"}"#,
+            r#"{"transaction": {"to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "value": "0", "gas_limit": "21000"}, "deploy": {"init_code": "0x", "gas_limit": "1"}}"#,
+        ] {
+            std::fs::write(dir.path().join("deploy.json"), example).unwrap();
+            assert!(test_examples(&policy, dir.path(), Some(11155111)).is_err());
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{Result, chains, error::Error, units};
+use clap::ValueEnum;
+use ethereum_types::U256;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::PathBuf};
+
+// 価格の小数点以下の桁数 (Chainlink の USD のフィードと同じ8桁、それより細かい桁は切り捨てる)
+const PRICE_DECIMALS: u8 = 8;
+
+// 既定の取得先 (CoinGecko の simple/price、{id} と {currency} を置き換える)
+pub const DEFAULT_PRICE_API_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids={id}&vs_currencies={currency}";
+
+// 取得した価格をキャッシュから使う秒数の既定値
+pub const DEFAULT_CACHE_TTL: u64 = 300;
+
+// 手数料を換算する法定通貨
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Jpy,
+}
+
+impl Currency {
+    // API とキャッシュで使う小文字のコード
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "usd",
+            Currency::Eur => "eur",
+            Currency::Jpy => "jpy",
+        }
+    }
+
+    fn sign(self) -> &'static str {
+        match self {
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Jpy => "¥",
+        }
+    }
+
+    // 補助単位の桁数 (セント、円は補助単位を使わない)
+    fn decimals(self) -> u8 {
+        match self {
+            Currency::Usd | Currency::Eur => 2,
+            Currency::Jpy => 0,
+        }
+    }
+}
+
+// ネイティブ通貨1単位 (1 ETH) の法定通貨での価格 (PRICE_DECIMALS 桁でスケーリング)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Price {
+    pub currency: Currency,
+    pub value: U256,
+}
+
+impl Price {
+    // amount (ネイティブ通貨の最小単位) を法定通貨の補助単位に換算する (四捨五入)
+    pub fn convert(&self, amount: U256, decimals: u8) -> U256 {
+        let Some(divisor) = pow10(decimals as usize + PRICE_DECIMALS as usize) else {
+            return U256::zero();
+        };
+        let scaled = amount
+            .saturating_mul(self.value)
+            .saturating_mul(pow10(self.currency.decimals() as usize).unwrap_or_default());
+        scaled.saturating_add(divisor / 2) / divisor
+    }
+
+    // 換算した金額の10進数 ("3.16"、補助単位の桁は末尾のゼロも省かない)
+    pub fn decimal(&self, amount: U256, decimals: u8) -> String {
+        let places = self.currency.decimals() as usize;
+        let digits = format!(
+            "{:0>width$}",
+            self.convert(amount, decimals).to_string(),
+            width = places + 1
+        );
+        let (integer, fraction) = digits.split_at(digits.len() - places);
+        match fraction {
+            "" => integer.to_string(),
+            fraction => format!("{integer}.{fraction}"),
+        }
+    }
+
+    // 換算した金額の表示 ("$3.16"、1セント未満は "< $0.01")
+    pub fn format(&self, amount: U256, decimals: u8) -> String {
+        let sign = self.currency.sign();
+        if self.convert(amount, decimals).is_zero() && !amount.is_zero() {
+            let smallest = units::format_units(U256::one(), self.currency.decimals());
+            return format!("< {sign}{smallest}");
+        }
+        format!("{sign}{}", self.decimal(amount, decimals))
+    }
+
+    // 価格の10進数 ("3012.45")
+    pub fn amount(&self) -> String {
+        units::format_units(self.value, PRICE_DECIMALS)
+    }
+
+    // 確認用の表示 ("3012.45 USD/ETH")
+    pub fn describe(&self, symbol: &str) -> String {
+        format!(
+            "{} {}/{symbol}",
+            self.amount(),
+            self.currency.code().to_ascii_uppercase()
+        )
+    }
+}
+
+// --fiat と --eth-price (どちらもなければ換算しない)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FiatOptions {
+    pub currency: Option<Currency>,
+    // ネイティブ通貨1単位の価格 (PRICE_DECIMALS 桁でスケーリング)
+    pub price: Option<U256>,
+}
+
+impl FiatOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.currency.is_some() || self.price.is_some()
+    }
+}
+
+fn pow10(exponent: usize) -> Option<U256> {
+    U256::from(10).checked_pow(U256::from(exponent))
+}
+
+// "3012.45" のような価格 (PRICE_DECIMALS 桁より細かい桁は切り捨てる)
+pub fn parse_price(text: &str) -> std::result::Result<U256, String> {
+    let text = match text.split_once('.') {
+        Some((integer, fraction)) => format!(
+            "{integer}.{}",
+            fraction
+                .chars()
+                .take(PRICE_DECIMALS as usize)
+                .collect::<String>()
+        ),
+        None => text.to_string(),
+    };
+    units::parse_units(&text, PRICE_DECIMALS).map_err(|error| error.to_string())
+}
+
+// chain id のネイティブ通貨の CoinGecko の id
+// テストネットの通貨には市場の価格がないため None
+pub fn token_id(chain_id: u64) -> Option<&'static str> {
+    if chains::is_testnet(chain_id) {
+        return None;
+    }
+    let id = match chains::symbol(chain_id) {
+        "ETH" => "ethereum",
+        "XDAI" => "xdai",
+        "BNB" => "binancecoin",
+        "POL" | "MATIC" => "polygon-ecosystem-token",
+        "CELO" => "celo",
+        "AVAX" => "avalanche-2",
+        _ => return None,
+    };
+    Some(id)
+}
+
+// 取得した価格と取得した時刻 (UNIX 秒)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    pub price: Price,
+    pub fetched_at: u64,
+}
+
+// PRICE_CACHE_PATH の1件 ("ethereum/usd" ごと)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedPrice {
+    price: String,
+    fetched_at: u64,
+}
+
+// 価格の取得先 (PRICE_API_URL) とキャッシュ (PRICE_CACHE_PATH)
+#[derive(Debug, Clone)]
+pub struct PriceSource {
+    url: String,
+    cache_path: Option<PathBuf>,
+    ttl: u64,
+}
+
+impl PriceSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            cache_path: None,
+            ttl: DEFAULT_CACHE_TTL,
+        }
+    }
+
+    pub fn with_cache(mut self, path: impl Into<PathBuf>, ttl: u64) -> Self {
+        self.cache_path = Some(path.into());
+        self.ttl = ttl;
+        self
+    }
+
+    // キャッシュの価格を使う秒数 (これより古い価格は取得に失敗した場合だけ使う)
+    pub fn ttl(&self) -> u64 {
+        self.ttl
+    }
+
+    // キャッシュの価格が ttl 秒以内ならそれを使い、古ければ取得してキャッシュに書き込む
+    // 取得に失敗した場合は古いキャッシュの価格を使う (fetched_at で古さが分かる)
+    pub fn quote(&self, chain_id: u64, currency: Currency, now: u64) -> Result<Quote> {
+        let id = token_id(chain_id).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "no market price for {} on chain {chain_id}{}: pass --eth-price to convert fees with a price of your own",
+                chains::symbol(chain_id),
+                if chains::is_testnet(chain_id) {
+                    " (a testnet)"
+                } else {
+                    ""
+                }
+            ))
+        })?;
+        let key = format!("{id}/{}", currency.code());
+
+        let mut cache = self.read_cache()?;
+        let cached = cache.get(&key).and_then(|cached| {
+            let value = parse_price(&cached.price).ok()?;
+            Some(Quote {
+                price: Price { currency, value },
+                fetched_at: cached.fetched_at,
+            })
+        });
+        if let Some(quote) = &cached
+            && now.saturating_sub(quote.fetched_at) <= self.ttl
+        {
+            return Ok(quote.clone());
+        }
+
+        let value = match self.fetch(id, currency) {
+            Ok(value) => value,
+            Err(error) => return cached.ok_or(error),
+        };
+        let quote = Quote {
+            price: Price { currency, value },
+            fetched_at: now,
+        };
+        if let Some(path) = &self.cache_path {
+            cache.insert(
+                key,
+                CachedPrice {
+                    price: quote.price.amount(),
+                    fetched_at: now,
+                },
+            );
+            std::fs::write(path, serde_json::to_string_pretty(&cache)? + "\n")
+                .map_err(Error::in_file(path))?;
+        }
+        Ok(quote)
+    }
+
+    // 応答は CoinGecko と同じ {"<id>": {"<currency>": 3012.45}} の形
+    fn fetch(&self, id: &str, currency: Currency) -> Result<U256> {
+        let url = self
+            .url
+            .replace("{id}", id)
+            .replace("{currency}", currency.code());
+        let response: Value = ureq::get(&url)
+            .call()
+            .and_then(|mut response| response.body_mut().read_json())
+            .map_err(Error::at_endpoint(&url))?;
+
+        let price = match &response[id][currency.code()] {
+            Value::Number(number) => number.to_string(),
+            Value::String(text) => text.clone(),
+            _ => {
+                return Err(Error::InvalidPrice(format!(
+                    "{url} has no {id}.{}",
+                    currency.code()
+                )));
+            }
+        };
+        parse_price(&price).map_err(Error::InvalidPrice)
+    }
+
+    // キャッシュがない (まだ書き込んでいない) 場合は空
+    fn read_cache(&self) -> Result<BTreeMap<String, CachedPrice>> {
+        let Some(path) = &self.cache_path else {
+            return Ok(BTreeMap::new());
+        };
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(Error::in_file(path)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(error) => Err(Error::in_file(path)(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+    use serde_json::json;
+
+    fn price(currency: Currency, text: &str) -> Price {
+        Price {
+            currency,
+            value: parse_price(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let fee = U256::from(1_050_000_000_000_000u64);
+        let usd = price(Currency::Usd, "3012.45");
+        assert_eq!(usd.convert(fee, 18), U256::from(316));
+        assert_eq!(usd.format(fee, 18), "$3.16");
+        assert_eq!(usd.format(fee * 100, 18), "$316.31");
+        assert_eq!(usd.format(U256::from(1000), 18), "< $0.01");
+        assert_eq!(usd.format(U256::zero(), 18), "$0.00");
+        assert_eq!(usd.describe("ETH"), "3012.45 USD/ETH");
+
+        assert_eq!(price(Currency::Eur, "2750").format(fee, 18), "€2.89");
+        assert_eq!(
+            price(Currency::Jpy, "452318.123456789").format(fee, 18),
+            "¥475"
+        );
+        assert_eq!(
+            price(Currency::Jpy, "452318").format(U256::from(1000), 18),
+            "< ¥1"
+        );
+        // 8桁より細かい桁は切り捨てる
+        assert_eq!(parse_price("0.123456789").unwrap(), U256::from(12_345_678));
+        assert!(parse_price("3,000").is_err());
+    }
+
+    #[test]
+    fn test_token_id() {
+        assert_eq!(token_id(1), Some("ethereum"));
+        assert_eq!(token_id(8453), Some("ethereum"));
+        assert_eq!(token_id(56), Some("binancecoin"));
+        assert_eq!(token_id(11155111), None);
+    }
+
+    #[test]
+    fn test_quote() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("prices.json");
+        let server = MockServer::start(vec![
+            (200, json!({ "ethereum": { "usd": 3012.45 } }).to_string()),
+            (200, json!({ "ethereum": { "usd": 3100 } }).to_string()),
+            (500, "{}".to_string()),
+        ]);
+        let url = format!("{}/price?ids={{id}}&vs={{currency}}", server.url);
+        let source = PriceSource::new(&url).with_cache(&cache_path, 60);
+
+        let quote = source.quote(1, Currency::Usd, 1_700_000_000).unwrap();
+        assert_eq!(quote.price, price(Currency::Usd, "3012.45"));
+        let cache: Value =
+            serde_json::from_str(&std::fs::read_to_string(&cache_path).unwrap()).unwrap();
+        assert_eq!(
+            cache["ethereum/usd"],
+            json!({ "price": "3012.45", "fetchedAt": 1_700_000_000 })
+        );
+
+        // ttl 以内はキャッシュを使い、過ぎたら取得し直す
+        let quote = source.quote(10, Currency::Usd, 1_700_000_060).unwrap();
+        assert_eq!(quote.fetched_at, 1_700_000_000);
+        let quote = source.quote(1, Currency::Usd, 1_700_000_061).unwrap();
+        assert_eq!(quote.price, price(Currency::Usd, "3100"));
+
+        // 取得に失敗したら古いキャッシュを使う
+        let quote = source.quote(1, Currency::Usd, 1_700_001_000).unwrap();
+        assert_eq!(
+            (quote.price, quote.fetched_at),
+            (price(Currency::Usd, "3100"), 1_700_000_061)
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests[0].0.starts_with("GET /price?ids=ethereum&vs=usd "),
+            "{}",
+            requests[0].0
+        );
+
+        // テストネットは取得しない
+        let error = source
+            .quote(11155111, Currency::Usd, 1_700_000_000)
+            .unwrap_err();
+        assert!(error.to_string().contains("(a testnet)"), "{error}");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{Result, de::parse_u256, error::Error, server::Server};
+use serde_json::{Value, json};
+
+// EIP-3326 の Unrecognized chain ID
+const UNRECOGNIZED_CHAIN: i64 = 4902;
+
+// EIP-1193 の provider.request({ method, params }) に相当する窓口
+// 鍵を使うメソッドは署名サーバーと同じ経路 (ポリシー・nonce・ジャーナル・監査ログ) で処理し、
+// それ以外 (eth_blockNumber、eth_call など) は RPC_URL のノードに中継する
+pub struct Provider {
+    server: Server,
+}
+
+impl Provider {
+    pub fn new(server: Server) -> Self {
+        Self {
+            server: server.with_passthrough(),
+        }
+    }
+
+    pub fn request(&self, method: &str, params: Value) -> Result<Value> {
+        match method {
+            // 接続の許可を求めるメソッド (この鍵のアカウントをそのまま返す)
+            "eth_requestAccounts" => self.server.request("eth_accounts", json!([])),
+            // CHAIN_ID と同じチェーンのみ受け付ける
+            "wallet_switchEthereumChain" => self.switch_chain(&params),
+            method => self.server.request(method, params),
+        }
+    }
+
+    fn switch_chain(&self, params: &Value) -> Result<Value> {
+        let requested = params[0]["chainId"].as_str().ok_or_else(|| {
+            Error::InvalidArgument("wallet_switchEthereumChain: chainId is missing".to_string())
+        })?;
+        let requested = parse_u256(requested).map_err(Error::InvalidArgument)?;
+        let current = self.server.request("eth_chainId", json!([]))?;
+        let current =
+            parse_u256(current.as_str().unwrap_or_default()).map_err(Error::InvalidArgument)?;
+
+        if requested != current {
+            return Err(Error::Rpc {
+                code: UNRECOGNIZED_CHAIN,
+                message: format!("chain {requested} is not configured (CHAIN_ID is {current})"),
+            });
+        }
+        Ok(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        rpc::tests::MockServer,
+        signer::tests::{TEST_ADDRESS, TEST_PRIVATE_KEY},
+    };
+
+    fn test_provider(rpc_url: Option<String>) -> Provider {
+        let config: Config = serde_json::from_value(json!({
+            "chain_id": 11155111,
+            "max_fee_per_gas": "50000000000",
+            "max_priority_fee_per_gas": "2000000000",
+            "private_key": TEST_PRIVATE_KEY,
+            "rpc_url": rpc_url,
+        }))
+        .unwrap();
+        Provider::new(Server::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_signer_methods() {
+        // 鍵を使うメソッドはノードに渡さない
+        let mock = MockServer::start(vec![]);
+        let provider = test_provider(Some(mock.url.clone()));
+
+        let accounts = provider.request("eth_requestAccounts", json!([])).unwrap();
+        assert_eq!(accounts, json!([TEST_ADDRESS]));
+        assert_eq!(
+            provider.request("eth_chainId", json!([])).unwrap(),
+            "0xaa36a7"
+        );
+        let raw = provider
+            .request(
+                "eth_signTransaction",
+                json!([{"to": "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df", "gas": "0x5208", "nonce": "0x0", "value": "0x1"}]),
+            )
+            .unwrap();
+        assert!(raw.as_str().unwrap().starts_with("0x02"));
+
+        // personal_sign などもこの鍵で署名し、ノードに中継しない
+        let signature = provider
+            .request("personal_sign", json!(["0x68656c6c6f", TEST_ADDRESS]))
+            .unwrap();
+        assert_eq!(signature.as_str().unwrap().len(), 2 + 130);
+        let error = provider
+            .request("wallet_addEthereumChain", json!([]))
+            .unwrap_err();
+        assert!(matches!(error, Error::Rpc { code: -32601, .. }), "{error}");
+
+        assert_eq!(
+            provider
+                .request(
+                    "wallet_switchEthereumChain",
+                    json!([{"chainId": "0xaa36a7"}])
+                )
+                .unwrap(),
+            Value::Null
+        );
+        let error = provider
+            .request("wallet_switchEthereumChain", json!([{"chainId": "0x1"}]))
+            .unwrap_err();
+        assert!(matches!(error, Error::Rpc { code: 4902, .. }), "{error}");
+
+        assert!(mock.json_requests().is_empty());
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mock = MockServer::start(vec![
+            MockServer::rpc_result(json!("0x10")),
+            MockServer::rpc_result(Value::Null),
+            (
+                200,
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":3,"message":"execution reverted"}}"#
+                    .to_string(),
+            ),
+        ]);
+        let provider = test_provider(Some(mock.url.clone()));
+
+        assert_eq!(
+            provider.request("eth_blockNumber", json!([])).unwrap(),
+            "0x10"
+        );
+        // null の結果もそのまま返す
+        let hash = format!("0x{}", "11".repeat(32));
+        assert_eq!(
+            provider
+                .request("eth_getTransactionReceipt", json!([hash]))
+                .unwrap(),
+            Value::Null
+        );
+        // ノードのエラーはコードごと返す
+        let error = provider
+            .request(
+                "eth_call",
+                json!([{"to": "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df"}, "latest"]),
+            )
+            .unwrap_err();
+        assert!(matches!(error, Error::Rpc { code: 3, .. }), "{error}");
+
+        let requests = mock.json_requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0]["method"], "eth_blockNumber");
+        assert_eq!(requests[1]["params"], json!([hash]));
+
+        // RPC_URL がなければ中継できない
+        let error = test_provider(None)
+            .request("eth_blockNumber", json!([]))
+            .unwrap_err();
+        assert!(error.to_string().contains("RPC_URL"), "{error}");
+    }
+}
@@ -0,0 +1,232 @@
+use ethereum_types::U256;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+const WINDOW: Duration = Duration::from_secs(60);
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// クライアント (トークン名、または認証なしの場合は接続元 IP) ごとの制限
+// None の項目は制限しない
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    // UTC の1日あたりに署名できる value の合計 (wei)
+    pub value_per_day: Option<U256>,
+}
+
+#[derive(Debug, Default)]
+struct ClientState {
+    // 直近1分間のリクエストの時刻
+    requests: VecDeque<Instant>,
+    // 何日目 (UNIX 時間) の合計か
+    day: u64,
+    value: U256,
+}
+
+pub struct RateLimiter {
+    limits: RateLimits,
+    clients: Mutex<HashMap<String, ClientState>>,
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECONDS_PER_DAY
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // リクエストを1件数え、1分あたりの上限を超える場合は理由を返す
+    pub fn check_request(&self, client: &str) -> Result<(), String> {
+        self.check_request_at(client, Instant::now())
+    }
+
+    fn check_request_at(&self, client: &str, now: Instant) -> Result<(), String> {
+        let Some(limit) = self.limits.requests_per_minute else {
+            return Ok(());
+        };
+
+        let mut clients = self.clients.lock().unwrap();
+        let requests = &mut clients.entry(client.to_string()).or_default().requests;
+        while requests
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= WINDOW)
+        {
+            requests.pop_front();
+        }
+        if requests.len() >= limit as usize {
+            return Err(format!(
+                "'{client}' exceeded the rate limit of {limit} requests per minute"
+            ));
+        }
+        requests.push_back(now);
+
+        Ok(())
+    }
+
+    // 署名しなかったリクエスト (ポリシーで拒否したものなど) を1分あたりの件数から戻す
+    pub fn refund_request(&self, client: &str) {
+        if self.limits.requests_per_minute.is_none() {
+            return;
+        }
+        if let Some(state) = self.clients.lock().unwrap().get_mut(client) {
+            state.requests.pop_back();
+        }
+    }
+
+    // 署名する value を当日の合計に加え、上限を超える場合は加えずに理由を返す
+    pub fn reserve_value(&self, client: &str, value: U256) -> Result<(), String> {
+        self.reserve_value_on(client, value, today())
+    }
+
+    fn reserve_value_on(&self, client: &str, value: U256, day: u64) -> Result<(), String> {
+        let Some(limit) = self.limits.value_per_day else {
+            return Ok(());
+        };
+
+        let mut clients = self.clients.lock().unwrap();
+        let state = clients.entry(client.to_string()).or_default();
+        if state.day != day {
+            state.day = day;
+            state.value = U256::zero();
+        }
+        match state.value.checked_add(value) {
+            Some(total) if total <= limit => {
+                state.value = total;
+                Ok(())
+            }
+            _ => Err(format!(
+                "'{client}' exceeded the daily value limit of {limit} wei ({} wei already signed today)",
+                state.value
+            )),
+        }
+    }
+
+    // reserve_value で加えたが署名しなかった value を当日の合計から戻す
+    pub fn release_value(&self, client: &str, value: U256) {
+        self.release_value_on(client, value, today())
+    }
+
+    fn release_value_on(&self, client: &str, value: U256, day: u64) {
+        if self.limits.value_per_day.is_none() {
+            return;
+        }
+        if let Some(state) = self.clients.lock().unwrap().get_mut(client)
+            && state.day == day
+        {
+            state.value = state.value.saturating_sub(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limiter = RateLimiter::new(RateLimits {
+            requests_per_minute: Some(2),
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        assert!(limiter.check_request_at("a", start).is_ok());
+        assert!(
+            limiter
+                .check_request_at("a", start + Duration::from_secs(10))
+                .is_ok()
+        );
+        assert_eq!(
+            limiter.check_request_at("a", start + Duration::from_secs(20)),
+            Err("'a' exceeded the rate limit of 2 requests per minute".to_string())
+        );
+        // クライアントごとに数える
+        assert!(
+            limiter
+                .check_request_at("b", start + Duration::from_secs(20))
+                .is_ok()
+        );
+        // 1分経つと最初のリクエストが枠から外れる
+        assert!(
+            limiter
+                .check_request_at("a", start + Duration::from_secs(60))
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_request_at("a", start + Duration::from_secs(61))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_value_per_day() {
+        let limiter = RateLimiter::new(RateLimits {
+            value_per_day: Some(U256::from(100)),
+            ..Default::default()
+        });
+
+        assert!(limiter.reserve_value_on("a", U256::from(60), 1).is_ok());
+        assert_eq!(
+            limiter.reserve_value_on("a", U256::from(41), 1),
+            Err(
+                "'a' exceeded the daily value limit of 100 wei (60 wei already signed today)"
+                    .to_string()
+            )
+        );
+        // 拒否された分は数えない
+        assert!(limiter.reserve_value_on("a", U256::from(40), 1).is_ok());
+        assert!(limiter.reserve_value_on("a", U256::zero(), 1).is_ok());
+        assert!(limiter.reserve_value_on("a", U256::one(), 1).is_err());
+        assert!(limiter.reserve_value_on("b", U256::from(100), 1).is_ok());
+        // 日付が変わるとリセットされる
+        assert!(limiter.reserve_value_on("a", U256::from(100), 2).is_ok());
+        assert!(limiter.reserve_value_on("a", U256::MAX, 3).is_err());
+    }
+
+    #[test]
+    fn test_refund() {
+        let limiter = RateLimiter::new(RateLimits {
+            requests_per_minute: Some(1),
+            value_per_day: Some(U256::from(100)),
+        });
+        let start = Instant::now();
+
+        assert!(limiter.check_request_at("a", start).is_ok());
+        assert!(limiter.check_request_at("a", start).is_err());
+        limiter.refund_request("a");
+        assert!(limiter.check_request_at("a", start).is_ok());
+        // 知らないクライアントは何もしない
+        limiter.refund_request("b");
+
+        assert!(limiter.reserve_value_on("a", U256::from(100), 1).is_ok());
+        limiter.release_value_on("a", U256::from(60), 1);
+        assert!(limiter.reserve_value_on("a", U256::from(60), 1).is_ok());
+        assert!(limiter.reserve_value_on("a", U256::one(), 1).is_err());
+        // 前の日の分は戻さない
+        assert!(limiter.reserve_value_on("a", U256::from(100), 2).is_ok());
+        limiter.release_value_on("a", U256::from(100), 1);
+        assert!(limiter.reserve_value_on("a", U256::one(), 2).is_err());
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let limiter = RateLimiter::new(RateLimits::default());
+        for _ in 0..1000 {
+            assert!(limiter.check_request("a").is_ok());
+        }
+        assert!(limiter.reserve_value("a", U256::MAX).is_ok());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Result, journal::Entry};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+// 署名した raw トランザクションを書き出すディレクトリ
+// <chain_id>/<送信者>/<nonce>-<ハッシュ>.rawtx に raw トランザクション、同じ名前の .json にメタデータを書く
+// エアギャップ環境でブロードキャストする側に渡すファイルの置き場所を決めておく
+#[derive(Debug, Clone)]
+pub struct RawTxDir {
+    root: PathBuf,
+}
+
+// 一時ファイルに書いてから置き換える (受け取る側が書き込み途中のファイルを読まないようにする)
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let mut temporary = path.to_path_buf().into_os_string();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, content)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+impl RawTxDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // 書き出す raw トランザクションのパス
+    pub fn path(&self, entry: &Entry) -> PathBuf {
+        self.root
+            .join(entry.chain_id.to_string())
+            .join(format!("{:?}", entry.from))
+            .join(format!("{}-{:?}.rawtx", entry.nonce, entry.hash))
+    }
+
+    pub fn save(&self, entry: &Entry) -> Result<PathBuf> {
+        let path = self.path(entry);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let metadata = json!({
+            "hash": entry.hash,
+            "chainId": entry.chain_id,
+            "from": entry.from,
+            "nonce": entry.nonce,
+            "requester": entry.requester,
+            "signedAt": entry.signed_at,
+            "transaction": entry.transaction,
+        });
+        // メタデータを先に書き、.rawtx があればメタデータもあるようにする
+        write_atomically(
+            &path.with_extension("json"),
+            &(serde_json::to_string_pretty(&metadata)? + "\n"),
+        )?;
+        write_atomically(&path, &format!("{}\n", entry.raw))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::tests::signed_entry;
+    use serde_json::Value;
+
+    #[test]
+    fn test_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw_tx_dir = RawTxDir::new(dir.path().join("out"));
+        let entry = signed_entry(7);
+
+        let path = raw_tx_dir.save(&entry).unwrap();
+        assert_eq!(
+            path,
+            dir.path()
+                .join("out/11155111/0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266")
+                .join(format!("7-{:?}.rawtx", entry.hash))
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", entry.raw)
+        );
+
+        let metadata: Value =
+            serde_json::from_str(&std::fs::read_to_string(path.with_extension("json")).unwrap())
+                .unwrap();
+        assert_eq!(metadata["hash"], json!(entry.hash));
+        assert_eq!(metadata["nonce"], "0x7");
+        assert_eq!(metadata["requester"], "cli");
+        assert_eq!(metadata["transaction"], entry.transaction);
+        // 一時ファイルは残らない
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{Result, journal::Entry, rpc::RpcClient};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::{H160, U256};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+// 手数料を上げ直すときの上限
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    // 1件あたりの最大の手数料 (gas limit × max fee per gas、wei)
+    pub max_fee: Option<U256>,
+    // 手数料を上げるトランザクションの最大の手数料の合計 (wei)
+    pub max_total_fee: Option<U256>,
+}
+
+// 手数料を上げて署名し直すトランザクション
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bump {
+    // 置き換えるトランザクション (同じ nonce の最後に署名したもの)
+    pub entry: Entry,
+    pub message: EIP1559TransactionMessage,
+}
+
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub bumps: Vec<Bump>,
+    // 上限を超えるため上げなかったトランザクションと理由
+    pub skipped: Vec<(Entry, String)>,
+}
+
+// 最大の手数料 (実際には base fee によってこれより少なくなる)
+pub fn worst_case_fee(message: &EIP1559TransactionMessage) -> U256 {
+    message.gas_limit.saturating_mul(message.max_fee_per_gas)
+}
+
+// percent だけ上げる (切り上げ)
+fn increase(fee: U256, percent: u64) -> U256 {
+    let increased = fee.saturating_mul(U256::from(100 + percent));
+    let (quotient, remainder) = increased.div_mod(U256::from(100));
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+// 手数料を上げたトランザクション (環境変数の手数料の方が高ければそちらを使う)
+pub fn bump(
+    message: &EIP1559TransactionMessage,
+    percent: u64,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+) -> EIP1559TransactionMessage {
+    let max_fee_per_gas = increase(message.max_fee_per_gas, percent).max(max_fee_per_gas);
+    let max_priority_fee_per_gas = increase(message.max_priority_fee_per_gas, percent)
+        .max(max_priority_fee_per_gas)
+        .min(max_fee_per_gas);
+
+    EIP1559TransactionMessage {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        ..message.clone()
+    }
+}
+
+// blocks ブロック前のブロックの時刻 (これより前に署名して取り込まれていないものを上げる)
+pub fn cutoff(client: &RpcClient, blocks: u64) -> Result<u64> {
+    let latest: U256 = client.request("eth_blockNumber", json!([]))?;
+    if latest < U256::from(blocks) {
+        return Ok(0);
+    }
+    let block: Value = client.request(
+        "eth_getBlockByNumber",
+        json!([latest - U256::from(blocks), false]),
+    )?;
+    let timestamp: U256 = serde_json::from_value(block["timestamp"].clone())?;
+    Ok(timestamp.low_u64())
+}
+
+// 取り込み待ちのエントリから、cutoff より前に署名したものの手数料を上げる
+// 同じ nonce のエントリ (前に上げたものなど) は最後に署名したものだけを見る
+pub fn plan(
+    pending: &[Entry],
+    sender: &H160,
+    cutoff: u64,
+    limits: &Limits,
+    bump: impl Fn(&EIP1559TransactionMessage) -> EIP1559TransactionMessage,
+) -> Result<Plan> {
+    let mut latest: BTreeMap<U256, &Entry> = BTreeMap::new();
+    for entry in pending.iter().filter(|entry| entry.from == *sender) {
+        latest.insert(entry.nonce, entry);
+    }
+
+    let mut plan = Plan::default();
+    let mut total = U256::zero();
+    for entry in latest
+        .into_values()
+        .filter(|entry| entry.signed_at < cutoff)
+    {
+        let message = bump(&entry.message()?);
+        let fee = worst_case_fee(&message);
+        if limits.max_fee.is_some_and(|max_fee| fee > max_fee) {
+            plan.skipped
+                .push((entry.clone(), format!("fee {fee} would exceed --max-fee")));
+            continue;
+        }
+        if limits
+            .max_total_fee
+            .is_some_and(|max_total_fee| total + fee > max_total_fee)
+        {
+            plan.skipped.push((
+                entry.clone(),
+                format!("total fee {} would exceed --max-total-fee", total + fee),
+            ));
+            continue;
+        }
+        total += fee;
+        plan.bumps.push(Bump {
+            entry: entry.clone(),
+            message,
+        });
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{journal::tests::signed_entry, rpc::tests::MockServer};
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_bump() {
+        let message = signed_entry(0).message().unwrap();
+        // 50 gwei / 2 gwei の12%増し
+        let bumped = bump(&message, 12, U256::zero(), U256::zero());
+        assert_eq!(bumped.max_fee_per_gas, U256::from(56 * GWEI));
+        assert_eq!(
+            bumped.max_priority_fee_per_gas,
+            U256::from(2_240_000_000u64)
+        );
+        assert_eq!(bumped.nonce, message.nonce);
+        assert_eq!(bumped.input, message.input);
+
+        // 環境変数の手数料の方が高い
+        let bumped = bump(&message, 10, U256::from(80 * GWEI), U256::from(3 * GWEI));
+        assert_eq!(bumped.max_fee_per_gas, U256::from(80 * GWEI));
+        assert_eq!(bumped.max_priority_fee_per_gas, U256::from(3 * GWEI));
+
+        assert_eq!(increase(U256::from(7), 10), U256::from(8));
+    }
+
+    #[test]
+    fn test_plan() {
+        let entry = |nonce: u64, signed_at: u64| Entry {
+            signed_at,
+            ..signed_entry(nonce)
+        };
+        let sender = signed_entry(0).from;
+        // nonce 1 は前に上げたもの (新しい方だけを見る)
+        let pending = vec![
+            entry(0, 100),
+            entry(1, 100),
+            entry(1, 150),
+            entry(2, 100),
+            entry(3, 500),
+        ];
+        let increase =
+            |message: &EIP1559TransactionMessage| bump(message, 10, U256::zero(), U256::zero());
+
+        let planned = plan(&pending, &sender, 200, &Limits::default(), increase).unwrap();
+        let nonces: Vec<_> = planned
+            .bumps
+            .iter()
+            .map(|bump| bump.entry.nonce.as_u64())
+            .collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert_eq!(planned.bumps[1].entry.signed_at, 150);
+        assert_eq!(
+            planned.bumps[0].message.max_fee_per_gas,
+            U256::from(55 * GWEI)
+        );
+
+        // 1件あたり 21000 × 55 gwei
+        let fee = U256::from(21000 * 55 * GWEI);
+        let limits = Limits {
+            max_fee: Some(fee),
+            max_total_fee: Some(fee * 2),
+        };
+        let planned = plan(&pending, &sender, 200, &limits, increase).unwrap();
+        assert_eq!(planned.bumps.len(), 2);
+        assert_eq!(planned.skipped.len(), 1);
+        assert!(planned.skipped[0].1.contains("--max-total-fee"));
+
+        let limits = Limits {
+            max_fee: Some(fee - 1),
+            max_total_fee: None,
+        };
+        let planned = plan(&pending, &sender, 200, &limits, increase).unwrap();
+        assert!(planned.bumps.is_empty());
+        assert_eq!(planned.skipped.len(), 3);
+
+        // 別の送信者は上げない
+        let planned = plan(&pending, &H160::zero(), 200, &Limits::default(), increase).unwrap();
+        assert!(planned.bumps.is_empty());
+    }
+
+    #[test]
+    fn test_cutoff() {
+        let rpc = MockServer::start(vec![
+            MockServer::rpc_result(json!("0x64")),
+            MockServer::rpc_result(json!({ "number": "0x50", "timestamp": "0x65f00000" })),
+        ]);
+        assert_eq!(cutoff(&RpcClient::new(&rpc.url), 20).unwrap(), 0x65f00000);
+        let requests = rpc.json_requests();
+        assert_eq!(requests[1]["method"], "eth_getBlockByNumber");
+        assert_eq!(requests[1]["params"], json!(["0x50", false]));
+
+        let rpc = MockServer::start(vec![MockServer::rpc_result(json!("0x5"))]);
+        assert_eq!(cutoff(&RpcClient::new(&rpc.url), 20).unwrap(), 0);
+    }
+}
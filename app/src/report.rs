@@ -0,0 +1,376 @@
+use crate::{
+    Result, chains,
+    de::parse_u256,
+    i18n::t,
+    journal::{Entry, Status},
+    price::Price,
+    rpc::RpcClient,
+    units,
+};
+use ethereum_types::U256;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+// CSV の列 (金額は wei、ガス価格は wei/gas の10進数)
+const CSV_HEADER: [&str; 12] = [
+    "hash",
+    "from",
+    "nonce",
+    "to",
+    "value",
+    "status",
+    "signedAt",
+    "blockNumber",
+    "gasUsed",
+    "effectiveGasPrice",
+    "l1Fee",
+    "fee",
+];
+
+// eth_getTransactionReceipt のうち手数料の計算に使うもの
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    // Byzantium より前のレシートにはない
+    #[serde(default)]
+    pub status: Option<U256>,
+    pub block_number: U256,
+    pub gas_used: U256,
+    pub effective_gas_price: U256,
+    // OP Stack のチェーンで L1 にデータを載せた手数料
+    #[serde(default)]
+    pub l1_fee: Option<U256>,
+}
+
+impl Receipt {
+    fn execution_fee(&self) -> U256 {
+        self.gas_used.saturating_mul(self.effective_gas_price)
+    }
+
+    pub fn fee(&self) -> U256 {
+        self.execution_fee()
+            .saturating_add(self.l1_fee.unwrap_or_default())
+    }
+}
+
+// 1件のトランザクションの結果 (レシートがなければ手数料は空)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub entry: Entry,
+    pub status: Status,
+    pub receipt: Option<Receipt>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub transactions: usize,
+    pub confirmed: usize,
+    // ブロックに取り込まれたが revert した
+    pub failed: usize,
+    pub pending: usize,
+    // 取り込まれずに消えたか、同じ nonce の別のトランザクションが取り込まれた
+    pub not_included: usize,
+    pub gas_used: U256,
+    pub execution_fees: U256,
+    pub l1_fees: U256,
+}
+
+impl Summary {
+    pub fn fees(&self) -> U256 {
+        self.execution_fees.saturating_add(self.l1_fees)
+    }
+
+    // ガスの量で重み付けした平均 (L1 のデータ手数料は含めない)
+    pub fn average_effective_gas_price(&self) -> Option<U256> {
+        (!self.gas_used.is_zero()).then(|| self.execution_fees / self.gas_used)
+    }
+}
+
+// ジャーナルのエントリのレシートを取得する
+// レシートがあればその結果、なければジャーナルの状態 (status で更新したもの) を使う
+pub fn build(entries: Vec<Entry>, client: &RpcClient) -> Result<Vec<Row>> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let receipt: Option<Receipt> =
+                client.request_optional("eth_getTransactionReceipt", json!([entry.hash]))?;
+            let status = match &receipt {
+                Some(receipt) if receipt.status == Some(U256::zero()) => Status::Failed,
+                Some(_) => Status::Confirmed,
+                None => entry.status,
+            };
+            Ok(Row {
+                entry,
+                status,
+                receipt,
+            })
+        })
+        .collect()
+}
+
+pub fn summarize(rows: &[Row]) -> Summary {
+    let mut summary = Summary {
+        transactions: rows.len(),
+        ..Summary::default()
+    };
+    for row in rows {
+        match row.status {
+            Status::Confirmed => summary.confirmed += 1,
+            Status::Failed => summary.failed += 1,
+            Status::Signed | Status::Broadcast => summary.pending += 1,
+            Status::Dropped | Status::Replaced => summary.not_included += 1,
+        }
+        // revert したトランザクションも手数料はかかる
+        if let Some(receipt) = &row.receipt {
+            summary.gas_used = summary.gas_used.saturating_add(receipt.gas_used);
+            summary.execution_fees = summary
+                .execution_fees
+                .saturating_add(receipt.execution_fee());
+            summary.l1_fees = summary
+                .l1_fees
+                .saturating_add(receipt.l1_fee.unwrap_or_default());
+        }
+    }
+    summary
+}
+
+// 10進数にする (値がなければ空)
+fn decimal(value: Option<U256>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn transaction_field(entry: &Entry, field: &str) -> Option<String> {
+    entry.transaction[field].as_str().map(str::to_string)
+}
+
+fn value(entry: &Entry) -> Option<U256> {
+    transaction_field(entry, "value").and_then(|value| parse_u256(&value).ok())
+}
+
+fn row_fields(row: &Row) -> [String; 12] {
+    let receipt = row.receipt.as_ref();
+    [
+        format!("{:?}", row.entry.hash),
+        format!("{:?}", row.entry.from),
+        row.entry.nonce.to_string(),
+        transaction_field(&row.entry, "to").unwrap_or_default(),
+        decimal(value(&row.entry)),
+        row.status.as_str().to_string(),
+        row.entry.signed_at.to_string(),
+        decimal(receipt.map(|receipt| receipt.block_number)),
+        decimal(receipt.map(|receipt| receipt.gas_used)),
+        decimal(receipt.map(|receipt| receipt.effective_gas_price)),
+        decimal(receipt.and_then(|receipt| receipt.l1_fee)),
+        decimal(receipt.map(Receipt::fee)),
+    ]
+}
+
+// 1行に1件のトランザクション (値にカンマや引用符は含まれない)
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut csv = CSV_HEADER.join(",") + "\n";
+    for row in rows {
+        csv += &(row_fields(row).join(",") + "\n");
+    }
+    csv
+}
+
+// 集計とトランザクションごとの内訳 (金額は wei の10進数の文字列)
+// price があれば手数料の合計を法定通貨でも示す (署名した時点ではなく今の価格で換算する)
+pub fn to_json(chain_id: u64, summary: &Summary, rows: &[Row], price: Option<&Price>) -> Value {
+    let transactions: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let fields = CSV_HEADER.iter().zip(row_fields(row)).map(|(name, field)| {
+                let field = if field.is_empty() {
+                    Value::Null
+                } else {
+                    Value::String(field)
+                };
+                (name.to_string(), field)
+            });
+            Value::Object(fields.collect())
+        })
+        .collect();
+
+    let fiat = price.map(|price| {
+        json!({
+            "currency": price.currency.code(),
+            "price": price.amount(),
+            "fees": price.decimal(summary.fees(), chains::decimals(chain_id)),
+        })
+    });
+    json!({
+        "chainId": chain_id,
+        "symbol": chains::symbol(chain_id),
+        "fiat": fiat,
+        "summary": {
+            "transactions": summary.transactions,
+            "confirmed": summary.confirmed,
+            "failed": summary.failed,
+            "pending": summary.pending,
+            "notIncluded": summary.not_included,
+            "gasUsed": summary.gas_used.to_string(),
+            "executionFees": summary.execution_fees.to_string(),
+            "l1Fees": summary.l1_fees.to_string(),
+            "fees": summary.fees().to_string(),
+            "averageEffectiveGasPrice": summary.average_effective_gas_price().map(|price| price.to_string()),
+        },
+        "transactions": transactions,
+    })
+}
+
+// 確認用の表示
+pub fn summary_text(chain_id: u64, summary: &Summary, price: Option<&Price>) -> String {
+    let native = |amount: U256| {
+        format!(
+            "{} {}",
+            units::format_units(amount, chains::decimals(chain_id)),
+            chains::symbol(chain_id)
+        )
+    };
+    let mut lines = vec![
+        t!(
+            "report.transactions",
+            total = summary.transactions,
+            confirmed = summary.confirmed,
+            failed = summary.failed,
+            pending = summary.pending,
+            not_included = summary.not_included
+        ),
+        t!("report.gas_used", gas_used = summary.gas_used),
+        t!("report.fees", fees = native(summary.fees())),
+    ];
+    if let Some(price) = price {
+        lines.push(t!(
+            "report.fees_fiat",
+            currency = price.currency.code().to_ascii_uppercase(),
+            fees = price.format(summary.fees(), chains::decimals(chain_id)),
+            price = price.describe(chains::symbol(chain_id))
+        ));
+    }
+    if !summary.l1_fees.is_zero() {
+        lines.push(t!("report.l1_data_fees", fees = native(summary.l1_fees)));
+    }
+    if let Some(price) = summary.average_effective_gas_price() {
+        lines.push(t!(
+            "report.average_gas_price",
+            gwei = units::format_units(price, 9)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        journal::tests::signed_entry,
+        price::{self, Currency},
+        rpc::tests::MockServer,
+    };
+
+    fn receipt(status: &str, gas_used: u64, effective_gas_price: u64) -> Value {
+        json!({
+            "status": status,
+            "blockNumber": "0x10",
+            "gasUsed": format!("{gas_used:#x}"),
+            "effectiveGasPrice": format!("{effective_gas_price:#x}"),
+            "logs": [],
+        })
+    }
+
+    #[test]
+    fn test_report() {
+        let mut l1 = receipt("0x1", 21000, 3_000_000_000);
+        l1["l1Fee"] = json!("0x3e8");
+        let server = MockServer::start(vec![
+            MockServer::rpc_result(receipt("0x1", 21000, 1_000_000_000)),
+            MockServer::rpc_result(receipt("0x0", 42000, 2_000_000_000)),
+            MockServer::rpc_result(l1),
+            MockServer::rpc_result(Value::Null),
+        ]);
+        let client = RpcClient::new(&server.url);
+
+        let mut replaced = signed_entry(3);
+        replaced.status = Status::Replaced;
+        let mut entries = vec![signed_entry(0), signed_entry(1), signed_entry(2), replaced];
+        for entry in &mut entries {
+            entry.signed_at = 1_700_000_000;
+        }
+        let rows = build(entries, &client).unwrap();
+        assert_eq!(rows[1].status, Status::Failed);
+        assert_eq!(rows[3].status, Status::Replaced);
+        assert_eq!(rows[3].receipt, None);
+
+        let summary = summarize(&rows);
+        assert_eq!(
+            (
+                summary.confirmed,
+                summary.failed,
+                summary.pending,
+                summary.not_included
+            ),
+            (2, 1, 0, 1)
+        );
+        assert_eq!(summary.gas_used, U256::from(84000));
+        // 21000 * 1 gwei + 42000 * 2 gwei + 21000 * 3 gwei
+        assert_eq!(summary.execution_fees, U256::from(168_000_000_000_000u64));
+        assert_eq!(summary.fees(), U256::from(168_000_000_001_000u64));
+        assert_eq!(
+            summary.average_effective_gas_price(),
+            Some(U256::from(2_000_000_000u64))
+        );
+        assert_eq!(
+            summary_text(11155111, &summary, None),
+            "Transactions: 4 (confirmed 2, failed 1, pending 0, dropped or replaced 1)\n\
+             Gas used: 84000\n\
+             Fees: 0.000168000000001 ETH\n\
+             L1 data fees: 0.000000000000001 ETH\n\
+             Average effective gas price: 2 gwei"
+        );
+
+        let csv = to_csv(&rows);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert!(
+            lines[2].ends_with(",failed,1700000000,16,42000,2000000000,,84000000000000"),
+            "{}",
+            lines[2]
+        );
+        assert!(
+            lines[4].ends_with(",replaced,1700000000,,,,,"),
+            "{}",
+            lines[4]
+        );
+
+        let report = to_json(11155111, &summary, &rows, None);
+        assert_eq!(report["summary"]["fees"], "168000000001000");
+        assert_eq!(report["fiat"], Value::Null);
+        assert_eq!(report["transactions"][2]["l1Fee"], "1000");
+        assert_eq!(report["transactions"][3]["fee"], Value::Null);
+
+        // --eth-price で換算する
+        let price = Price {
+            currency: Currency::Eur,
+            value: price::parse_price("2750").unwrap(),
+        };
+        assert!(
+            summary_text(11155111, &summary, Some(&price))
+                .contains("\nFees in EUR: €0.46 at 2750 EUR/ETH\n")
+        );
+        let report = to_json(11155111, &summary, &rows, Some(&price));
+        assert_eq!(
+            report["fiat"],
+            json!({ "currency": "eur", "price": "2750", "fees": "0.46" })
+        );
+
+        let requests = server.json_requests();
+        assert_eq!(requests.len(), 4);
+        assert!(
+            requests
+                .iter()
+                .all(|request| request["method"] == "eth_getTransactionReceipt")
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{Result, error::Error};
+use ethereum_types::{H160, H256, U256};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+// JSON-RPC のレスポンス
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<ErrorObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+}
+
+// Ethereum ノードの JSON-RPC クライアント
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        self.request_optional(method, params)?
+            .ok_or_else(|| Error::Rpc {
+                code: 0,
+                message: format!("empty result for {method}"),
+            })
+    }
+
+    // result が null になりうるメソッド (eth_getTransactionReceipt など) 用
+    pub fn request_optional<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Option<T>> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: Response<T> = ureq::post(&self.url)
+            .send_json(&body)
+            .and_then(|mut response| response.body_mut().read_json())
+            .map_err(Error::at_endpoint(&self.url))?;
+
+        if let Some(error) = response.error {
+            return Err(Error::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        Ok(response.result)
+    }
+
+    // まだブロックに取り込まれていないトランザクションを含めた次の nonce
+    pub fn pending_nonce(&self, address: &H160) -> Result<U256> {
+        self.request("eth_getTransactionCount", json!([address, "pending"]))
+    }
+
+    // 署名済みのトランザクションを送信し、トランザクションハッシュを返す
+    pub fn send_raw_transaction(&self, raw: &[u8]) -> Result<H256> {
+        self.request(
+            "eth_sendRawTransaction",
+            json!([format!("0x{}", hex::encode(raw))]),
+        )
+    }
+
+    // 最新のブロックに baseFeePerGas があれば EIP-1559 に対応している
+    pub fn supports_eip1559(&self) -> Result<bool> {
+        let block: Value = self.request("eth_getBlockByNumber", json!(["latest", false]))?;
+        Ok(!block["baseFeePerGas"].is_null())
+    }
+
+    // アドレスにデプロイされているコード (コントラクトでなければ空)
+    pub fn code(&self, address: &H160) -> Result<Vec<u8>> {
+        let result: String = self.request("eth_getCode", json!([address, "latest"]))?;
+        hex::decode(result.strip_prefix("0x").unwrap_or(&result)).map_err(Into::into)
+    }
+
+    // eth_call でコントラクトの関数を呼び出し、戻り値のバイト列を返す
+    pub fn call(&self, to: &H160, data: &[u8]) -> Result<Vec<u8>> {
+        let result: String = self.request(
+            "eth_call",
+            json!([
+                { "to": to, "data": format!("0x{}", hex::encode(data)) },
+                "latest",
+            ]),
+        )?;
+
+        hex::decode(result.strip_prefix("0x").unwrap_or(&result)).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    // 受信したリクエスト (リクエストライン, ヘッダー (名前は小文字), ボディ)
+    pub(crate) type RecordedRequest = (String, Vec<(String, String)>, Vec<u8>);
+
+    // 決められたレスポンスを順番に返すテスト用HTTPサーバー
+    pub(crate) struct MockServer {
+        pub(crate) url: String,
+        handle: JoinHandle<Vec<RecordedRequest>>,
+    }
+
+    impl MockServer {
+        // レスポンスは (ステータスコード, ボディ) のリスト
+        pub(crate) fn start(responses: Vec<(u16, String)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+
+            let handle = std::thread::spawn(move || {
+                let mut recorded = Vec::new();
+                let mut responses = responses.into_iter().peekable();
+                while responses.peek().is_some() {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+
+                    // 同じ接続で複数のリクエストが来る場合がある
+                    loop {
+                        let mut request_line = String::new();
+                        if reader.read_line(&mut request_line).unwrap() == 0 {
+                            break;
+                        }
+                        let mut content_length = 0;
+                        let mut headers = Vec::new();
+                        loop {
+                            let mut line = String::new();
+                            reader.read_line(&mut line).unwrap();
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some((name, value)) = line.split_once(':') {
+                                let name = name.to_ascii_lowercase();
+                                if name == "content-length" {
+                                    content_length = value.trim().parse().unwrap();
+                                }
+                                headers.push((name, value.trim().to_string()));
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        reader.read_exact(&mut body).unwrap();
+                        recorded.push((request_line.trim_end().to_string(), headers, body));
+
+                        let (status, response_body) = responses.next().unwrap();
+                        let response = format!(
+                            "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response_body}",
+                            response_body.len()
+                        );
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+
+                        if responses.peek().is_none() {
+                            return recorded;
+                        }
+                    }
+                }
+                recorded
+            });
+
+            Self { url, handle }
+        }
+
+        // JSON-RPC の result を返すレスポンス
+        pub(crate) fn rpc_result(result: Value) -> (u16, String) {
+            (
+                200,
+                json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string(),
+            )
+        }
+
+        pub(crate) fn requests(self) -> Vec<RecordedRequest> {
+            self.handle.join().unwrap()
+        }
+
+        // 受信したリクエストボディをJSONとして取得
+        pub(crate) fn json_requests(self) -> Vec<Value> {
+            self.requests()
+                .into_iter()
+                .map(|(_, _, body)| serde_json::from_slice(&body).unwrap())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_request_result() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!("0xaa36a7"))]);
+        let client = RpcClient::new(&server.url);
+
+        let chain_id: String = client.request("eth_chainId", json!([])).unwrap();
+        assert_eq!(chain_id, "0xaa36a7");
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["method"], "eth_chainId");
+        assert_eq!(requests[0]["jsonrpc"], "2.0");
+    }
+
+    #[test]
+    fn test_supports_eip1559() {
+        let server = MockServer::start(vec![
+            MockServer::rpc_result(json!({ "number": "0x10", "baseFeePerGas": "0x7" })),
+            MockServer::rpc_result(json!({ "number": "0x10" })),
+        ]);
+        let client = RpcClient::new(&server.url);
+
+        assert!(client.supports_eip1559().unwrap());
+        assert!(!client.supports_eip1559().unwrap());
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["method"], "eth_getBlockByNumber");
+        assert_eq!(requests[0]["params"], json!(["latest", false]));
+    }
+
+    #[test]
+    fn test_request_error() {
+        let server = MockServer::start(vec![(
+            200,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32000, "message": "execution reverted" }
+            })
+            .to_string(),
+        )]);
+        let client = RpcClient::new(&server.url);
+
+        let result: Result<String> = client.request("eth_call", json!([]));
+        match result {
+            Err(Error::Rpc { code, message }) => {
+                assert_eq!(code, -32000);
+                assert_eq!(message, "execution reverted");
+            }
+            _ => panic!("Expected Rpc error, got: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_call() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!(
+            "0x000000000000000000000000000000000000000000000000000000000000002a"
+        ))]);
+        let client = RpcClient::new(&server.url);
+        let token: H160 = "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+            .parse()
+            .unwrap();
+
+        let result = client.call(&token, &[0x06, 0xfd, 0xde, 0x03]).unwrap();
+        assert_eq!(result.len(), 32);
+        assert_eq!(result[31], 0x2a);
+
+        let requests = server.json_requests();
+        assert_eq!(requests[0]["method"], "eth_call");
+        assert_eq!(requests[0]["params"][0]["data"], "0x06fdde03");
+        assert_eq!(
+            requests[0]["params"][0]["to"],
+            "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df"
+        );
+        assert_eq!(requests[0]["params"][1], "latest");
+    }
+}
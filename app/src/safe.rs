@@ -0,0 +1,438 @@
+use crate::{
+    Result, address,
+    de::{deserialize_hex_bytes, deserialize_u256},
+    eip712::TypedData,
+    error::Error,
+    json,
+    params::Params,
+    signer::{self, Signature},
+};
+use ethereum_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::Path;
+
+// Safe{Wallet} のマルチシグトランザクション (SafeTx)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTransaction {
+    // Safe コントラクトのアドレス
+    pub safe: H160,
+    pub to: H160,
+    #[serde(default, deserialize_with = "deserialize_u256")]
+    pub value: U256,
+    #[serde(default, deserialize_with = "deserialize_hex_bytes")]
+    pub data: Vec<u8>,
+    // 0: CALL, 1: DELEGATECALL
+    #[serde(default)]
+    pub operation: u8,
+    #[serde(default, deserialize_with = "deserialize_u256")]
+    pub safe_tx_gas: U256,
+    #[serde(default, deserialize_with = "deserialize_u256")]
+    pub base_gas: U256,
+    #[serde(default, deserialize_with = "deserialize_u256")]
+    pub gas_price: U256,
+    #[serde(default)]
+    pub gas_token: H160,
+    #[serde(default)]
+    pub refund_receiver: H160,
+    #[serde(deserialize_with = "deserialize_u256")]
+    pub nonce: U256,
+}
+
+impl SafeTransaction {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+        json::parse(&json_content).map_err(Error::in_file(path))
+    }
+
+    // SafeTx の EIP-712 型付きデータ (Safe v1.3.0 以降のドメイン)
+    pub fn typed_data(&self, chain_id: u64) -> Result<TypedData> {
+        let typed_data = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "SafeTx": [
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "operation", "type": "uint8" },
+                    { "name": "safeTxGas", "type": "uint256" },
+                    { "name": "baseGas", "type": "uint256" },
+                    { "name": "gasPrice", "type": "uint256" },
+                    { "name": "gasToken", "type": "address" },
+                    { "name": "refundReceiver", "type": "address" },
+                    { "name": "nonce", "type": "uint256" }
+                ]
+            },
+            "primaryType": "SafeTx",
+            "domain": {
+                "chainId": chain_id,
+                "verifyingContract": self.safe,
+            },
+            "message": {
+                "to": self.to,
+                "value": self.value.to_string(),
+                "data": format!("0x{}", hex::encode(&self.data)),
+                "operation": self.operation,
+                "safeTxGas": self.safe_tx_gas.to_string(),
+                "baseGas": self.base_gas.to_string(),
+                "gasPrice": self.gas_price.to_string(),
+                "gasToken": self.gas_token,
+                "refundReceiver": self.refund_receiver,
+                "nonce": self.nonce.to_string(),
+            }
+        });
+
+        serde_json::from_value(typed_data).map_err(Into::into)
+    }
+
+    // オーナーが署名する safeTxHash
+    pub fn safe_tx_hash(&self, chain_id: u64) -> Result<H256> {
+        self.typed_data(chain_id)?.digest()
+    }
+}
+
+// Safe Transaction Service に送るトランザクション提案
+// アドレスはチェックサム付き、数値は10進数の文字列で送る
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proposal {
+    pub to: String,
+    pub value: String,
+    pub data: Option<String>,
+    pub operation: u8,
+    pub safe_tx_gas: String,
+    pub base_gas: String,
+    pub gas_price: String,
+    pub gas_token: String,
+    pub refund_receiver: String,
+    pub nonce: String,
+    pub contract_transaction_hash: H256,
+    pub sender: String,
+    pub signature: String,
+    pub origin: Option<String>,
+}
+
+impl Proposal {
+    pub fn new(
+        safe_transaction: &SafeTransaction,
+        safe_tx_hash: H256,
+        sender: &H160,
+        signature: Signature,
+        origin: Option<String>,
+    ) -> Self {
+        Self {
+            to: address::to_checksum(&safe_transaction.to),
+            value: safe_transaction.value.to_string(),
+            data: (!safe_transaction.data.is_empty())
+                .then(|| format!("0x{}", hex::encode(&safe_transaction.data))),
+            operation: safe_transaction.operation,
+            safe_tx_gas: safe_transaction.safe_tx_gas.to_string(),
+            base_gas: safe_transaction.base_gas.to_string(),
+            gas_price: safe_transaction.gas_price.to_string(),
+            gas_token: address::to_checksum(&safe_transaction.gas_token),
+            refund_receiver: address::to_checksum(&safe_transaction.refund_receiver),
+            nonce: safe_transaction.nonce.to_string(),
+            contract_transaction_hash: safe_tx_hash,
+            sender: address::to_checksum(sender),
+            signature: format!("0x{}", hex::encode(signature.to_bytes())),
+            origin,
+        }
+    }
+}
+
+// チェーンごとの Safe Transaction Service のURL
+pub fn default_service_url(chain_id: u64) -> Option<&'static str> {
+    let url = match chain_id {
+        1 => "https://safe-transaction-mainnet.safe.global",
+        10 => "https://safe-transaction-optimism.safe.global",
+        56 => "https://safe-transaction-bsc.safe.global",
+        100 => "https://safe-transaction-gnosis-chain.safe.global",
+        137 => "https://safe-transaction-polygon.safe.global",
+        8453 => "https://safe-transaction-base.safe.global",
+        42161 => "https://safe-transaction-arbitrum.safe.global",
+        11155111 => "https://safe-transaction-sepolia.safe.global",
+        _ => return None,
+    };
+
+    Some(url)
+}
+
+// トランザクション提案を Safe Transaction Service に送信
+pub fn propose(service_url: &str, safe: &H160, proposal: &Proposal) -> Result<()> {
+    let url = format!(
+        "{}/api/v1/safes/{}/multisig-transactions/",
+        service_url.trim_end_matches('/'),
+        address::to_checksum(safe)
+    );
+    ureq::post(&url).send_json(proposal).map_err(Error::from)?;
+
+    Ok(())
+}
+
+// Transaction Builder のバッチファイルの形式のバージョン
+const BATCH_FILE_VERSION: &str = "1.0";
+const TX_BUILDER_VERSION: &str = "1.16.5";
+
+// Safe{Wallet} の Transaction Builder で読み込めるバッチファイル (params.json ごとに1つのトランザクション)
+// calldata はそのまま data に入れ、contractMethod は付けない (nonce・gas_limit は Safe 側で決まるため使わない)
+// created_at は Unix 時間のミリ秒
+pub fn transaction_builder_batch(
+    chain_id: u64,
+    safe: Option<&H160>,
+    name: &str,
+    params: &[Params],
+    created_at: u128,
+) -> Value {
+    let transactions: Vec<Value> = params
+        .iter()
+        .map(|params| {
+            json!({
+                "to": address::to_checksum(&params.to_address),
+                "value": params.value.to_string(),
+                "data": format!("0x{}", hex::encode(&params.input)),
+                "contractMethod": null,
+                "contractInputsValues": null,
+            })
+        })
+        .collect();
+    let mut batch = json!({
+        "version": BATCH_FILE_VERSION,
+        "chainId": chain_id.to_string(),
+        "createdAt": created_at as u64,
+        "meta": {
+            "name": name,
+            "description": "",
+            "txBuilderVersion": TX_BUILDER_VERSION,
+            "createdFromSafeAddress": safe.map(address::to_checksum).unwrap_or_default(),
+            "createdFromOwnerAddress": "",
+        },
+        "transactions": transactions,
+    });
+    batch["meta"]["checksum"] = json!(batch_checksum(&batch));
+    batch
+}
+
+// Transaction Builder が読み込み時に確かめるチェックサム
+// meta.name を null にしたバッチを、キーを並べた配列 + 各値 + "," の独自の形式で直列化した Keccak-256
+fn batch_checksum(batch: &Value) -> H256 {
+    let mut batch = batch.clone();
+    batch["meta"]["name"] = Value::Null;
+    signer::keccak256(serialize_for_checksum(&batch))
+}
+
+fn serialize_for_checksum(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(serialize_for_checksum).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::Object(map) => {
+            // Map は BTreeMap のためキーは並べ替え済み
+            let keys: Vec<&String> = map.keys().collect();
+            let mut out = format!(
+                "{{{}",
+                Value::from_iter(keys.iter().map(|key| key.as_str()))
+            );
+            for key in keys {
+                out.push_str(&serialize_for_checksum(&map[key]));
+                out.push(',');
+            }
+            out.push('}');
+            out
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eip712,
+        rpc::tests::MockServer,
+        signer::{
+            self,
+            tests::{TEST_ADDRESS, recover_address, test_signing_key},
+        },
+    };
+
+    fn test_safe_transaction() -> SafeTransaction {
+        serde_json::from_str(
+            r#"{
+                "safe": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0xde0b6b3a7640000",
+                "nonce": 3
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_safe_transaction_defaults() {
+        let safe_transaction = test_safe_transaction();
+
+        assert!(safe_transaction.data.is_empty());
+        assert_eq!(safe_transaction.operation, 0);
+        assert_eq!(safe_transaction.safe_tx_gas, U256::zero());
+        assert_eq!(safe_transaction.gas_token, H160::zero());
+        assert_eq!(safe_transaction.refund_receiver, H160::zero());
+        assert_eq!(safe_transaction.nonce, U256::from(3));
+    }
+
+    #[test]
+    fn test_safe_tx_type_hashes() {
+        // Safe コントラクトの SAFE_TX_TYPEHASH / DOMAIN_SEPARATOR_TYPEHASH と一致する
+        let typed_data = test_safe_transaction().typed_data(1).unwrap();
+
+        assert_eq!(
+            eip712::type_hash(&typed_data.types, "SafeTx").unwrap(),
+            "0xbb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            eip712::type_hash(&typed_data.types, "EIP712Domain").unwrap(),
+            "0x47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_safe_tx_hash_depends_on_chain() {
+        let safe_transaction = test_safe_transaction();
+
+        assert_ne!(
+            safe_transaction.safe_tx_hash(1).unwrap(),
+            safe_transaction.safe_tx_hash(11155111).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_proposal_serialization() {
+        let safe_transaction = test_safe_transaction();
+        let safe_tx_hash = safe_transaction.safe_tx_hash(1).unwrap();
+        let signature = signer::sign_hash(&test_signing_key(), &safe_tx_hash).unwrap();
+        let sender = TEST_ADDRESS.parse().unwrap();
+
+        let proposal = Proposal::new(&safe_transaction, safe_tx_hash, &sender, signature, None);
+        let json = serde_json::to_value(&proposal).unwrap();
+
+        assert_eq!(json["to"], "0x742d35CC6634c0532925a3b8d2f8E0C4eD2d11dF");
+        assert_eq!(json["value"], "1000000000000000000");
+        assert_eq!(json["data"], serde_json::Value::Null);
+        assert_eq!(json["nonce"], "3");
+        assert_eq!(json["sender"], TEST_ADDRESS);
+        assert_eq!(json["contractTransactionHash"], format!("{safe_tx_hash:?}"));
+        assert_eq!(
+            recover_address(&safe_tx_hash, &signature),
+            TEST_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_default_service_url() {
+        assert_eq!(
+            default_service_url(11155111),
+            Some("https://safe-transaction-sepolia.safe.global")
+        );
+        assert_eq!(default_service_url(31337), None);
+    }
+
+    #[test]
+    fn test_propose_posts_to_service() {
+        let server = MockServer::start(vec![(201, String::new())]);
+
+        let safe_transaction = test_safe_transaction();
+        let safe_tx_hash = safe_transaction.safe_tx_hash(1).unwrap();
+        let signature = signer::sign_hash(&test_signing_key(), &safe_tx_hash).unwrap();
+        let proposal = Proposal::new(
+            &safe_transaction,
+            safe_tx_hash,
+            &TEST_ADDRESS.parse().unwrap(),
+            signature,
+            Some("test".to_string()),
+        );
+        propose(&server.url, &safe_transaction.safe, &proposal).unwrap();
+
+        let (request_line, _, body) = server.requests().remove(0);
+        assert!(request_line.starts_with(
+            "POST /api/v1/safes/0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed/multisig-transactions/ "
+        ));
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["origin"], "test");
+        assert_eq!(json["sender"], TEST_ADDRESS);
+    }
+
+    #[test]
+    fn test_transaction_builder_batch() {
+        let params = || -> Params {
+            serde_json::from_str(
+                r#"{
+                    "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                    "value": 1000,
+                    "gas_limit": 21000,
+                    "input": "0xa9059cbb"
+                }"#,
+            )
+            .unwrap()
+        };
+        let safe: H160 = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        let batch = transaction_builder_batch(
+            11155111,
+            Some(&safe),
+            "Payroll",
+            &[params(), params()],
+            1_700_000_000_000,
+        );
+
+        assert_eq!(batch["chainId"], "11155111");
+        assert_eq!(batch["createdAt"], 1_700_000_000_000u64);
+        assert_eq!(
+            batch["meta"]["createdFromSafeAddress"],
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        let transactions = batch["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0]["value"], "1000");
+        assert_eq!(transactions[0]["data"], "0xa9059cbb");
+        assert_eq!(transactions[0]["contractMethod"], Value::Null);
+
+        // Transaction Builder (JavaScript) の serializeJSONObject と同じ直列化
+        let mut unnamed = batch.clone();
+        unnamed["meta"].as_object_mut().unwrap().remove("checksum");
+        unnamed["meta"]["name"] = Value::Null;
+        let serialized = serialize_for_checksum(&unnamed);
+        assert!(
+            serialized.starts_with(
+                r#"{["chainId","createdAt","meta","transactions","version"]"11155111",1700000000000,{["createdFromOwnerAddress","createdFromSafeAddress","description","name","txBuilderVersion"]"","0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed","",null,"1.16.5",},[{["#
+            ),
+            "{serialized}"
+        );
+        assert!(serialized.ends_with(r#"}],"1.0",}"#), "{serialized}");
+        assert_eq!(
+            batch["meta"]["checksum"],
+            json!(signer::keccak256(&serialized))
+        );
+
+        // 名前はチェックサムに含まれない
+        let renamed = transaction_builder_batch(
+            11155111,
+            Some(&safe),
+            "Renamed",
+            &[params(), params()],
+            1_700_000_000_000,
+        );
+        assert_eq!(renamed["meta"]["checksum"], batch["meta"]["checksum"]);
+        let without_safe =
+            transaction_builder_batch(11155111, None, "Payroll", &[params()], 1_700_000_000_000);
+        assert_eq!(without_safe["meta"]["createdFromSafeAddress"], "");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Result, error::Error};
+use serde::Deserialize;
+use std::{convert::Infallible, fmt, str::FromStr};
+
+// 秘密鍵、パスワード、API の鍵などの秘密の値
+// Debug / Display では中身を出さないため、設定や引数の構造体をログやエラーに出しても漏れない
+// 値は expose() で明示的に取り出す
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+// clap の引数 (--token) として受け取る
+impl FromStr for Secret<String> {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self(value.to_string()))
+    }
+}
+
+// 16進数の秘密の値をデコードする
+// hex の InvalidHexCharacter は不正な文字と位置を表示するため、エラーに値の一部を含めない
+pub fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(|error| match error {
+        hex::FromHexError::InvalidHexCharacter { .. } => {
+            Error::InvalidArgument("contains a non-hex character".to_string())
+        }
+        error => error.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("correct horse".to_string());
+        assert_eq!(format!("{secret:?}"), "[REDACTED]");
+        assert_eq!(format!("{secret}"), "[REDACTED]");
+        assert_eq!(format!("{:?}", Some(&secret)), "Some([REDACTED])");
+        assert_eq!(secret.expose(), "correct horse");
+
+        let secret: Secret<String> = serde_json::from_str(r#""correct horse""#).unwrap();
+        assert_eq!(secret.expose(), "correct horse");
+        assert_eq!("token".parse::<Secret<String>>().unwrap().expose(), "token");
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("0x0aff").unwrap(), [0x0a, 0xff]);
+        assert_eq!(decode_hex("0aff").unwrap(), [0x0a, 0xff]);
+        let error = decode_hex("0a7z").unwrap_err();
+        assert!(!error.to_string().contains('z'), "{error}");
+        assert!(matches!(
+            decode_hex("0af"),
+            Err(Error::FromHex(hex::FromHexError::OddLength))
+        ));
+    }
+}
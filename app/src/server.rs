@@ -0,0 +1,3567 @@
+use crate::{
+    Result, address,
+    approval::ApprovalHook,
+    approvals::{APPROVALS_PATH, ApprovalQueue},
+    arbitrum,
+    audit::{AuditLog, Decision, Record},
+    auth::{Authenticator, Grant, Permission},
+    chains,
+    config::{Config, ConfigSource},
+    de::{deserialize_optional_hex_bytes, deserialize_optional_u256, parse_u256},
+    detached,
+    eip712::TypedData,
+    error::Error,
+    journal::{self, Journal},
+    message,
+    metrics::Metrics,
+    nonce::NonceStore,
+    policy::{Policy, Violation},
+    ratelimit::{RateLimiter, RateLimits},
+    rawtx::RawTxDir,
+    rpc::RpcClient,
+    secret::Secret,
+    session::{KEY_PATH, KeySession},
+    sigcache::SignatureCache,
+    signal,
+    signer::{HashSigner, Signature, keccak256},
+    tls,
+    transaction::{self, TxType},
+    web3signer,
+    webhook::{Event, EventKind, Notifier},
+};
+use ethereum::{AccessList, EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, H256, U256};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+// リクエストボディの上限
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+// ヘッダー1行の上限と行数の上限
+const MAX_HEADER_LINE_SIZE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// JSON-RPC のエラーコード
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+// EIP-1193 の Unauthorized
+const UNAUTHORIZED: i64 = 4100;
+// EIP-1474 の Limit exceeded
+const LIMIT_EXCEEDED: i64 = -32005;
+
+// Prometheus 形式のメトリクスを返すパス
+const METRICS_PATH: &str = "/metrics";
+
+// 対応している Clef の外部 API のバージョン
+const CLEF_API_VERSION: &str = "6.1.0";
+
+// 待ち受けた接続
+// 閉じる前の処理が必要なもの (TLS の close_notify) は shutdown で行う
+pub trait Connection: Read + Write {
+    fn shutdown(&mut self) {}
+
+    // 接続元の IP アドレス (Unix ドメインソケットでは None)
+    fn peer_ip(&self) -> Option<IpAddr> {
+        None
+    }
+}
+
+impl Connection for TcpStream {
+    fn peer_ip(&self) -> Option<IpAddr> {
+        self.peer_addr().ok().map(|address| address.ip())
+    }
+}
+
+impl Connection for UnixStream {}
+
+impl Connection for tls::TlsStream {
+    fn peer_ip(&self) -> Option<IpAddr> {
+        self.sock.peer_ip()
+    }
+
+    fn shutdown(&mut self) {
+        self.conn.send_close_notify();
+        let _ = self.flush();
+    }
+}
+
+// JSON-RPC のエラーオブジェクト
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(error: Error) -> Self {
+        Self::new(SERVER_ERROR, error.to_string())
+    }
+}
+
+// eth_signTransaction / eth_sendTransaction に渡されるトランザクション
+// 省略されたフィールドは設定値や RPC から補う
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionRequest {
+    from: Option<H160>,
+    to: Option<H160>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    value: Option<U256>,
+    #[serde(
+        default,
+        alias = "gasLimit",
+        deserialize_with = "deserialize_optional_u256"
+    )]
+    gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    nonce: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    gas_price: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    chain_id: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+    data: Option<Vec<u8>>,
+    #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+    input: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    peer: Option<IpAddr>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct HttpResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl HttpResponse {
+    fn json(body: &Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn text(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    fn error(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": reason_phrase(status) }).to_string(),
+        }
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+// 上限付きで1行読み込む (改行は除く)
+fn read_limited_line<R: BufRead>(reader: &mut R) -> std::result::Result<Option<String>, u16> {
+    let mut line = String::new();
+    let read = reader
+        .take(MAX_HEADER_LINE_SIZE)
+        .read_line(&mut line)
+        .map_err(|_| 400u16)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(431);
+    }
+
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+// HTTP/1.1 のリクエストを1つ読み込む
+// 接続が閉じられた場合は None、不正なリクエストはエラーのステータスコード
+fn read_request<R: BufRead>(reader: &mut R) -> std::result::Result<Option<HttpRequest>, u16> {
+    let Some(request_line) = read_limited_line(reader)? else {
+        return Ok(None);
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(400);
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_limited_line(reader)?.ok_or(400u16)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() >= MAX_HEADERS {
+            return Err(431);
+        }
+        let (name, value) = line.split_once(':').ok_or(400u16)?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let mut request = HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body: Vec::new(),
+        peer: None,
+    };
+
+    // chunked には対応しない
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(411);
+    }
+    let content_length = match request.header("Content-Length") {
+        Some(value) => value.parse::<usize>().map_err(|_| 400u16)?,
+        None => 0,
+    };
+    if content_length > MAX_BODY_SIZE {
+        return Err(413);
+    }
+
+    request.body = vec![0; content_length];
+    reader.read_exact(&mut request.body).map_err(|_| 400u16)?;
+
+    Ok(Some(request))
+}
+
+fn write_response<W: Write>(
+    writer: &mut W,
+    response: &HttpResponse,
+    close: bool,
+) -> std::io::Result<()> {
+    let connection = if close { "close" } else { "keep-alive" };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: {connection}\r\n\r\n{}",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    writer.flush()
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut response = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    });
+    if let Some(data) = error.data {
+        response["error"]["data"] = data;
+    }
+    response
+}
+
+fn parse_quantity(quantity: &str) -> Result<U256> {
+    U256::from_str_radix(quantity.strip_prefix("0x").unwrap_or(quantity), 16).map_err(|_| {
+        Error::Rpc {
+            code: 0,
+            message: format!("invalid quantity '{quantity}'"),
+        }
+    })
+}
+
+// eth_accounts / eth_signTransaction / eth_sendTransaction と
+// Clef 互換の account_* を提供する署名サーバー
+pub struct Server {
+    // 設定・認証・ポリシーはリロードで入れ替わる
+    config: RwLock<Arc<Config>>,
+    key: KeySession,
+    address: H160,
+    approval: Option<ApprovalHook>,
+    authenticator: RwLock<Option<Arc<Authenticator>>>,
+    rate_limiter: RateLimiter,
+    policy: RwLock<Option<Arc<Policy>>>,
+    approvals: ApprovalQueue,
+    audit_log: Option<AuditLog>,
+    notifier: Option<Notifier>,
+    // nonce を省略した要求に割り当てるストア (なければノードの pending の nonce)
+    nonces: Option<NonceStore>,
+    journal: Option<Journal>,
+    raw_tx_dir: Option<RawTxDir>,
+    // 署名した raw トランザクション (再送に同じものを返す)
+    signatures: SignatureCache,
+    metrics: Metrics,
+    // リロードで読み直す環境変数と .env (なければポリシーと認証の設定のみ読み直す)
+    source: Option<ConfigSource>,
+    // 監視しているファイルの更新時刻
+    modified: Mutex<Option<HashMap<PathBuf, Option<SystemTime>>>>,
+    // 署名しないメソッドを RPC_URL のノードに中継する (provider.rs)
+    passthrough: bool,
+}
+
+impl Server {
+    pub fn new(config: Config) -> Result<Self> {
+        let key = KeySession::unlocked(config.get_key()?);
+        Self::with_key_session(config, key)
+    }
+
+    // 暗号化された鍵ファイルなど、ロックできる鍵で起動する
+    pub fn with_key_session(config: Config, key: KeySession) -> Result<Self> {
+        let address = key.address();
+        let policy = config.get_policy()?.map(Arc::new);
+        let audit_log = config.get_audit_log();
+        let notifier = config.get_webhooks()?.map(Notifier::new);
+        let nonces = config.get_nonce_store()?;
+        let journal = config.get_journal()?;
+        let raw_tx_dir = config.get_raw_tx_dir();
+
+        Ok(Self {
+            config: RwLock::new(Arc::new(config)),
+            key,
+            address,
+            approval: None,
+            authenticator: RwLock::new(None),
+            rate_limiter: RateLimiter::new(RateLimits::default()),
+            policy: RwLock::new(policy),
+            approvals: ApprovalQueue::new(),
+            audit_log,
+            notifier,
+            nonces,
+            journal,
+            raw_tx_dir,
+            signatures: SignatureCache::default(),
+            metrics: Metrics::new(),
+            source: None,
+            modified: Mutex::new(None),
+            passthrough: false,
+        })
+    }
+
+    // 署名の前に承認フックを呼ぶようにする
+    pub fn with_approval_hook(mut self, approval: ApprovalHook) -> Self {
+        self.approval = Some(approval);
+        self
+    }
+
+    // Authorization ヘッダーのトークンで認証し、トークンごとの権限で制限する
+    pub fn with_authenticator(self, authenticator: Authenticator) -> Self {
+        *self.authenticator.write().unwrap() = Some(Arc::new(authenticator));
+        self
+    }
+
+    // SIGHUP や .env の変更で設定を読み直せるようにする
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    // クライアントごとのリクエスト数と1日の value の上限
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limiter = RateLimiter::new(limits);
+        self
+    }
+
+    // eth_blockNumber や eth_call など、署名しないメソッドをノードに中継する
+    pub fn with_passthrough(mut self) -> Self {
+        self.passthrough = true;
+        self
+    }
+
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    // HTTP を通さず、このプロセスから JSON-RPC のメソッドを呼ぶ (認証とレート制限はない)
+    pub fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let grant = Grant::anonymous("in-process".to_string());
+        self.call(method, params, &grant)
+            .map_err(|error| Error::Rpc {
+                code: error.code,
+                message: error.message,
+            })
+    }
+
+    fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config.read().unwrap())
+    }
+
+    fn policy(&self) -> Option<Arc<Policy>> {
+        self.policy.read().unwrap().clone()
+    }
+
+    fn authenticator(&self) -> Option<Arc<Authenticator>> {
+        self.authenticator.read().unwrap().clone()
+    }
+
+    // 設定 (手数料や RPC_URL)、ポリシー、認証の設定を読み直す (鍵はそのまま)
+    // すべて読み込めた場合のみ入れ替え、失敗した場合はそれまでの設定を使い続ける
+    pub fn reload(&self) -> Result<()> {
+        let current = self.config();
+        let config = match &self.source {
+            Some(source) => Arc::new(source.load()?),
+            None => Arc::clone(&current),
+        };
+        // 署名するトランザクションの chain id が途中で変わらないようにする
+        if config.chain_id != current.chain_id {
+            return Err(Error::InvalidArgument(
+                "CHAIN_ID cannot be changed without a restart".to_string(),
+            ));
+        }
+        let policy = config.get_policy()?.map(Arc::new);
+        let authenticator = match self.authenticator() {
+            Some(authenticator) => match authenticator.path() {
+                Some(path) => Some(Arc::new(Authenticator::from_path(path)?)),
+                None => Some(authenticator),
+            },
+            None => None,
+        };
+
+        *self.config.write().unwrap() = config;
+        *self.policy.write().unwrap() = policy;
+        *self.authenticator.write().unwrap() = authenticator;
+        Ok(())
+    }
+
+    // .env、ポリシー、認証の設定ファイル
+    fn watched_files(&self) -> Vec<PathBuf> {
+        let config = self.config();
+        let authenticator = self.authenticator();
+        self.source
+            .as_ref()
+            .and_then(ConfigSource::dotenv_path)
+            .cloned()
+            .into_iter()
+            .chain(config.policy_path.as_ref().map(PathBuf::from))
+            .chain(
+                authenticator
+                    .as_ref()
+                    .and_then(|authenticator| authenticator.path())
+                    .map(Path::to_path_buf),
+            )
+            .collect()
+    }
+
+    // 前回の確認から監視しているファイルが更新されていれば true
+    fn files_changed(&self) -> bool {
+        let current: HashMap<_, _> = self
+            .watched_files()
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                (path, modified)
+            })
+            .collect();
+        let mut previous = self.modified.lock().unwrap();
+        let changed = previous
+            .as_ref()
+            .is_some_and(|previous| *previous != current);
+        *previous = Some(current);
+        changed
+    }
+
+    pub fn run(self, listener: TcpListener) -> Result<()> {
+        self.accept(listener.incoming())
+    }
+
+    pub fn run_unix(self, listener: UnixListener) -> Result<()> {
+        self.accept(listener.incoming())
+    }
+
+    pub fn run_tls(self, listener: TcpListener, config: Arc<rustls::ServerConfig>) -> Result<()> {
+        self.accept(
+            listener
+                .incoming()
+                .map(|stream| stream.and_then(|stream| tls::accept(&config, stream))),
+        )
+    }
+
+    // 接続ごとにスレッドを起動してリクエストを処理する
+    fn accept<S: Connection + Send + 'static>(
+        self,
+        incoming: impl Iterator<Item = std::io::Result<S>>,
+    ) -> Result<()> {
+        let server = Arc::new(self);
+        {
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || server.run_housekeeping());
+        }
+        for stream in incoming {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Failed to accept a connection: {error}");
+                    continue;
+                }
+            };
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || server.handle_connection(stream));
+        }
+
+        Ok(())
+    }
+
+    // SIGHUP と設定ファイルの変更で設定を読み直し、
+    // SIGHUP と操作のない時間で鍵をロックする
+    fn run_housekeeping(&self) {
+        self.files_changed();
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let hangup = signal::take_hangup();
+            if hangup && self.key.lock() {
+                eprintln!("Locked the signing key (SIGHUP)");
+            }
+            if self.key.expire_idle() {
+                eprintln!("Locked the signing key after the idle timeout");
+            }
+
+            if hangup || self.files_changed() {
+                match self.reload() {
+                    Ok(()) => eprintln!("Reloaded the configuration"),
+                    Err(error) => {
+                        eprintln!(
+                            "Failed to reload the configuration, keeping the previous one: {error}"
+                        )
+                    }
+                }
+                // 監視するファイルが変わっている場合があるため記録し直す
+                self.files_changed();
+            }
+        }
+    }
+
+    // 1つの接続で keep-alive の間リクエストを処理する
+    fn handle_connection<S: Connection>(&self, stream: S) {
+        let peer = stream.peer_ip();
+        let mut reader = BufReader::new(stream);
+        loop {
+            let (response, close) = match read_request(&mut reader) {
+                Ok(None) => return,
+                Ok(Some(mut request)) => {
+                    request.peer = peer;
+                    let close = request
+                        .header("Connection")
+                        .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+                    (self.handle_http(&request), close)
+                }
+                Err(status) => (HttpResponse::error(status), true),
+            };
+
+            if write_response(reader.get_mut(), &response, close).is_err() {
+                return;
+            }
+            if close {
+                reader.get_mut().shutdown();
+                return;
+            }
+        }
+    }
+
+    fn handle_http(&self, request: &HttpRequest) -> HttpResponse {
+        // ヘルスチェックは認証なしで応答する
+        if request.method == "GET" && request.path == web3signer::UPCHECK_PATH {
+            self.metrics.record_request("upcheck", true);
+            return HttpResponse::text("OK");
+        }
+
+        let grant = match self.authenticate(request) {
+            Ok(grant) => grant,
+            Err(reason) => {
+                eprintln!("Unauthorized request to {}: {reason}", request.path);
+                self.metrics.record_rejection("unauthenticated");
+                return HttpResponse::error(401);
+            }
+        };
+
+        // JSON-RPC はメソッドごとに記録する (レート制限もバッチの要素ごと)
+        if request.method == "POST" && request.path == "/" {
+            return HttpResponse::json(&self.handle_rpc(&request.body, &grant));
+        }
+        if self.check_rate_limit(&grant).is_err() {
+            return HttpResponse::error(429);
+        }
+
+        let (endpoint, response) = self.route(request, &grant);
+        self.metrics.record_request(endpoint, response.status < 400);
+        response
+    }
+
+    // 認証なしの場合は接続元ごとにレート制限する
+    fn authenticate(&self, request: &HttpRequest) -> std::result::Result<Grant, String> {
+        match self.authenticator() {
+            Some(authenticator) => authenticator.authenticate(request.header("Authorization")),
+            None => Ok(Grant::anonymous(
+                request
+                    .peer
+                    .map_or_else(|| "local".to_string(), |ip| ip.to_string()),
+            )),
+        }
+    }
+
+    fn check_rate_limit(&self, grant: &Grant) -> std::result::Result<(), String> {
+        self.rate_limiter
+            .check_request(&grant.name)
+            .inspect_err(|reason| {
+                eprintln!("Rate limited: {reason}");
+                self.metrics.record_rejection("rate_limit");
+            })
+    }
+
+    // トークンの権限で署名できるか確認する
+    fn check_permission(
+        &self,
+        grant: &Grant,
+        permission: Permission,
+    ) -> std::result::Result<(), String> {
+        grant
+            .check(permission, &self.address, self.config().chain_id)
+            .inspect_err(|reason| {
+                eprintln!("Permission denied: {reason}");
+                self.metrics.record_rejection("permission");
+            })
+    }
+
+    // トークンに許可されたアカウントのみ返す
+    fn visible_accounts(&self, grant: &Grant) -> Vec<H160> {
+        Some(self.address)
+            .filter(|account| grant.allows_account(account))
+            .into_iter()
+            .collect()
+    }
+
+    // JSON-RPC 以外のエンドポイント (メトリクスのラベル, レスポンス)
+    fn route(&self, request: &HttpRequest, grant: &Grant) -> (&'static str, HttpResponse) {
+        let path = request.path.as_str();
+        let method = request.method.as_str();
+
+        // Web3Signer 互換の REST API
+        if let Some(identifier) = path.strip_prefix(web3signer::SIGN_PATH_PREFIX) {
+            let response = match method {
+                "POST" => self.handle_web3signer_sign(identifier, &request.body, grant),
+                _ => HttpResponse::error(405),
+            };
+            return ("eth1_sign", response);
+        }
+        if let Some(rest) = path.strip_prefix(APPROVALS_PATH) {
+            return ("approvals", self.handle_approvals(method, rest, grant));
+        }
+        if let Some(rest) = path.strip_prefix(KEY_PATH) {
+            return ("key", self.handle_key(method, rest, &request.body, grant));
+        }
+        match (method, path) {
+            ("GET", web3signer::PUBLIC_KEYS_PATH) => {
+                // SIGNER_PLUGIN の鍵は公開鍵がわからないため返さない
+                let public_keys: Vec<_> = self
+                    .key
+                    .verifying_key()
+                    .filter(|_| !self.visible_accounts(grant).is_empty())
+                    .map(web3signer::public_key_hex)
+                    .into_iter()
+                    .collect();
+                ("eth1_publicKeys", HttpResponse::json(&json!(public_keys)))
+            }
+            ("GET", METRICS_PATH) => (
+                "metrics",
+                HttpResponse {
+                    status: 200,
+                    content_type: "text/plain; version=0.0.4; charset=utf-8",
+                    body: self.metrics.render(),
+                },
+            ),
+            (_, "/" | web3signer::PUBLIC_KEYS_PATH | web3signer::UPCHECK_PATH | METRICS_PATH) => {
+                ("unknown", HttpResponse::error(405))
+            }
+            _ => ("unknown", HttpResponse::error(404)),
+        }
+    }
+
+    // 鍵ファイルのロック
+    // GET /api/v1/key: 状態
+    // POST /api/v1/key/unlock ({"password": ...}), POST /api/v1/key/lock
+    fn handle_key(&self, method: &str, rest: &str, body: &[u8], grant: &Grant) -> HttpResponse {
+        #[derive(Deserialize)]
+        struct UnlockRequest {
+            password: Secret<String>,
+        }
+
+        let status = || {
+            HttpResponse::json(&json!({
+                "address": address::to_checksum(&self.address),
+                "lockable": self.key.is_lockable(),
+                "locked": self.key.is_locked(),
+            }))
+        };
+        match (method, rest) {
+            ("GET", "") => status(),
+            (_, "/unlock" | "/lock")
+                if self.check_permission(grant, Permission::Unlock).is_err() =>
+            {
+                HttpResponse::error(403)
+            }
+            ("POST", "/unlock") => {
+                let Ok(request) = serde_json::from_slice::<UnlockRequest>(body) else {
+                    return HttpResponse::error(400);
+                };
+                match self.key.unlock(request.password.expose()) {
+                    Ok(()) => {
+                        eprintln!("Unlocked the signing key by '{}'", grant.name);
+                        status()
+                    }
+                    Err(error) => {
+                        eprintln!("Failed to unlock the signing key: {error}");
+                        self.metrics.record_rejection("unlock");
+                        let mut response = HttpResponse::error(403);
+                        response.body = json!({ "error": error.to_string() }).to_string();
+                        response
+                    }
+                }
+            }
+            ("POST", "/lock") => {
+                if self.key.lock() {
+                    eprintln!("Locked the signing key by '{}'", grant.name);
+                }
+                status()
+            }
+            (_, "" | "/unlock" | "/lock") => HttpResponse::error(405),
+            _ => HttpResponse::error(404),
+        }
+    }
+
+    // 2人目のオペレーターによる承認
+    // GET /api/v1/approvals: 承認待ちの一覧
+    // GET /api/v1/approvals/{id}: 状態 (承認済みなら raw を含む)
+    // POST /api/v1/approvals/{id}/approve, /api/v1/approvals/{id}/reject
+    fn handle_approvals(&self, method: &str, rest: &str, grant: &Grant) -> HttpResponse {
+        let can_approve = || self.check_permission(grant, Permission::Approve).is_ok();
+        let segments: Vec<_> = rest.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (method, segments.as_slice()) {
+            ("GET", []) if can_approve() => HttpResponse::json(&json!(self.approvals.pending())),
+            ("GET", [id]) => match self.approvals.get(id) {
+                // 要求者本人か承認できるオペレーターのみ参照できる
+                Some((requester, request)) if requester == grant.name || can_approve() => {
+                    HttpResponse::json(&request)
+                }
+                Some(_) => HttpResponse::error(403),
+                None => HttpResponse::error(404),
+            },
+            ("POST", [id, "approve"]) if can_approve() => self.approve_request(id, grant),
+            ("POST", [id, "reject"]) => match self.approvals.get(id) {
+                // 要求者本人は取り下げられる
+                Some((requester, _)) if requester == grant.name || can_approve() => {
+                    match self.approvals.reject(id, &grant.name) {
+                        Ok(request) => {
+                            eprintln!("Request {id} was rejected by '{}'", grant.name);
+                            let _ = self.audit(Record {
+                                requester: grant.name.clone(),
+                                method: "approvals_reject".to_string(),
+                                params: json!({ "requestId": id, "requester": request["requester"] }),
+                                decision: Decision::Rejected,
+                                reason: None,
+                                hash: None,
+                            });
+                            HttpResponse::json(&request)
+                        }
+                        Err(error) => HttpResponse::error(error.status_code()),
+                    }
+                }
+                Some(_) => HttpResponse::error(403),
+                None => HttpResponse::error(404),
+            },
+            ("GET", []) | ("POST", [_, "approve"]) => HttpResponse::error(403),
+            (_, [] | [_] | [_, "approve" | "reject"]) => HttpResponse::error(405),
+            _ => HttpResponse::error(404),
+        }
+    }
+
+    fn approve_request(&self, id: &str, grant: &Grant) -> HttpResponse {
+        let started = Instant::now();
+        let (requester, message) = match self.approvals.start_approval(id, &grant.name) {
+            Ok(request) => request,
+            Err(error) => return HttpResponse::error(error.status_code()),
+        };
+        let params = json!({
+            "requestId": id,
+            "requester": requester,
+            "transaction": transaction::message_json(&TxType::Eip1559, &message),
+        });
+
+        // 承認を待つ間にポリシーや DENYLIST_PATH が変わっていることがあるため、今のルールで確認し直す
+        // 上限は要求者の分として数える
+        let checked = self.check_transaction_rules(&grant.name, &message);
+        let rejected = checked.is_err();
+        let result = checked
+            .and_then(|()| self.release_signature(&grant.name, &requester, message, started));
+        let audited = self.audit(Record {
+            requester: grant.name.clone(),
+            method: "approvals_approve".to_string(),
+            params,
+            decision: if result.is_ok() {
+                Decision::Signed
+            } else {
+                Decision::Rejected
+            },
+            reason: result.as_ref().err().map(|error| error.message.clone()),
+            hash: result.as_ref().ok().map(keccak256),
+        });
+        match result.and_then(|raw| audited.map(|()| raw).map_err(RpcError::from)) {
+            Ok(raw) => {
+                eprintln!(
+                    "Request {id} from '{requester}' was approved by '{}'",
+                    grant.name
+                );
+                let request = self.approvals.finish_approval(id, Some(raw));
+                HttpResponse::json(&request.unwrap_or(Value::Null))
+            }
+            // 今のルールで署名できない要求は拒否し、それ以外の失敗は承認待ちに戻す
+            Err(error) if rejected => {
+                eprintln!(
+                    "Request {id} from '{requester}' was rejected: {}",
+                    error.message
+                );
+                let request = self.approvals.reject_approval(id, &error.message);
+                let mut response = HttpResponse::error(403);
+                response.body = json!({
+                    "error": error.message,
+                    "request": request.unwrap_or(Value::Null),
+                })
+                .to_string();
+                response
+            }
+            Err(error) => {
+                self.approvals.finish_approval(id, None);
+                let mut response = HttpResponse::error(403);
+                response.body = json!({ "error": error.message }).to_string();
+                response
+            }
+        }
+    }
+
+    // POST /api/v1/eth1/sign/{identifier}
+    // data の Keccak-256 ハッシュへの署名を text/plain で返す
+    fn handle_web3signer_sign(&self, identifier: &str, body: &[u8], grant: &Grant) -> HttpResponse {
+        let started = Instant::now();
+        // 許可されていないアカウントは存在しないものとして扱う
+        if !self
+            .key
+            .verifying_key()
+            .is_some_and(|verifying_key| web3signer::matches_identifier(verifying_key, identifier))
+            || !grant.allows_account(&self.address)
+        {
+            return HttpResponse::error(404);
+        }
+        let Ok(request) = serde_json::from_slice::<web3signer::SignRequest>(body) else {
+            return HttpResponse::error(400);
+        };
+
+        let result = self.web3signer_sign(&request, grant);
+        let audited = self.audit(Record {
+            requester: grant.name.clone(),
+            method: "eth1_sign".to_string(),
+            params: json!({
+                "identifier": identifier,
+                "data": format!("0x{}", hex::encode(&request.data)),
+            }),
+            decision: if result.is_ok() {
+                Decision::Signed
+            } else {
+                Decision::Rejected
+            },
+            reason: result.as_ref().err().map(|(_, reason)| reason.clone()),
+            hash: result.as_ref().ok().map(|_| keccak256(&request.data)),
+        });
+
+        match result {
+            Ok(_) if audited.is_err() => HttpResponse::error(500),
+            Ok(signature) => {
+                self.metrics.record_signature(
+                    self.config().chain_id,
+                    "eth1_sign",
+                    started.elapsed(),
+                );
+                HttpResponse::text(signature)
+            }
+            Err((status, _)) => HttpResponse::error(status),
+        }
+    }
+
+    // 署名するか、拒否する場合はステータスコードと理由を返す
+    fn web3signer_sign(
+        &self,
+        request: &web3signer::SignRequest,
+        grant: &Grant,
+    ) -> std::result::Result<String, (u16, String)> {
+        let rejected = |error: RpcError| {
+            let status = match error.code {
+                LIMIT_EXCEEDED => 429,
+                INVALID_PARAMS => 400,
+                _ => 403,
+            };
+            (status, error.message)
+        };
+        // keccak256(data) が署名用ハッシュになるトランザクションは eth_signTransaction と同じ確認を通す
+        let transaction = if web3signer::is_transaction_payload(&request.data) {
+            Some(
+                self.check_transaction_payload(&request.data, grant)
+                    .map_err(rejected)?,
+            )
+        } else {
+            self.check_permission(grant, Permission::SignMessage)
+                .map_err(|reason| (403, reason))?;
+            self.check_policy(&grant.name, Policy::check_message)
+                .map_err(|error| (403, error.message))?;
+            None
+        };
+
+        let mut approval_request = json!({
+            "method": "eth1_sign",
+            "from": address::to_checksum(&self.address),
+            "data": format!("0x{}", hex::encode(&request.data)),
+        });
+        if let Some((tx_type, message)) = &transaction {
+            approval_request["transaction"] = transaction::message_json(tx_type, message);
+        }
+        match self.is_approved(&approval_request) {
+            Ok(true) => {}
+            Ok(false) => return Err((403, "Request denied".to_string())),
+            Err(error) => {
+                eprintln!("Approval hook failed: {error}");
+                return Err((500, error.to_string()));
+            }
+        }
+
+        let key = self.key.key().map_err(|error| (503, error.to_string()))?;
+        if let Some((_, message)) = &transaction {
+            self.reserve_value(&grant.name, &grant.name, message.value)
+                .map_err(rejected)?;
+        }
+        web3signer::sign(&key, &request.data).map_err(|error| {
+            eprintln!("Failed to sign: {error}");
+            (500, error.to_string())
+        })
+    }
+
+    // eth1 sign で渡された署名前のトランザクションを確かめる
+    // 承認待ちにはできないため、2人目の承認が必要なものは eth_signTransaction に回す
+    fn check_transaction_payload(
+        &self,
+        data: &[u8],
+        grant: &Grant,
+    ) -> std::result::Result<(TxType, EIP1559TransactionMessage), RpcError> {
+        self.check_permission(grant, Permission::SignTransaction)
+            .map_err(|reason| RpcError::new(UNAUTHORIZED, reason))?;
+        let (tx_type, message) = detached::decode_unsigned(data).map_err(|error| {
+            RpcError::invalid_params(format!(
+                "data is a transaction that cannot be checked: {error}"
+            ))
+        })?;
+        let chain_id = self.config().chain_id;
+        if message.chain_id != chain_id {
+            return Err(RpcError::invalid_params(format!(
+                "chainId {} does not match the configured chain id {chain_id}",
+                message.chain_id
+            )));
+        }
+        self.check_transaction_rules(&grant.name, &message)?;
+        if self
+            .policy()
+            .is_some_and(|policy| policy.check_approval_threshold(&message).is_err())
+        {
+            return Err(RpcError::new(
+                SERVER_ERROR,
+                "the transaction needs a second operator's approval; send it with eth_signTransaction",
+            ));
+        }
+        Ok((tx_type, message))
+    }
+
+    // JSON-RPC のリクエスト (バッチを含む) を処理
+    fn handle_rpc(&self, body: &[u8], grant: &Grant) -> Value {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(error) => {
+                return error_response(Value::Null, RpcError::new(PARSE_ERROR, error.to_string()));
+            }
+        };
+
+        match request {
+            Value::Array(requests) if !requests.is_empty() => Value::Array(
+                requests
+                    .iter()
+                    .map(|request| self.handle_call(request, grant))
+                    .collect(),
+            ),
+            Value::Array(_) => {
+                error_response(Value::Null, RpcError::new(INVALID_REQUEST, "empty batch"))
+            }
+            request => self.handle_call(&request, grant),
+        }
+    }
+
+    fn handle_call(&self, request: &Value, grant: &Grant) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return error_response(id, RpcError::new(INVALID_REQUEST, "missing method"));
+        };
+        let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+        if let Err(reason) = self.check_rate_limit(grant) {
+            return error_response(id, RpcError::new(LIMIT_EXCEEDED, reason));
+        }
+
+        match self.call(method, params, grant) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        }
+    }
+
+    fn call(
+        &self,
+        method: &str,
+        params: Value,
+        grant: &Grant,
+    ) -> std::result::Result<Value, RpcError> {
+        let forwarded = self.forwards(method);
+        let result = if forwarded {
+            self.forward(method, params)
+        } else {
+            self.dispatch(method, params, grant)
+        };
+
+        // 任意のメソッド名でラベルが増えないよう、存在しないメソッドと中継したメソッドはまとめる
+        let label = match &result {
+            _ if forwarded => "passthrough",
+            Err(error) if error.code == METHOD_NOT_FOUND => "unknown",
+            _ => method,
+        };
+        self.metrics.record_request(label, result.is_ok());
+        result
+    }
+
+    // 中継するメソッドか (鍵を使うメソッドとウォレットのメソッドはノードに渡さない)
+    fn forwards(&self, method: &str) -> bool {
+        const SIGNER_METHODS: [&str; 3] = ["eth_accounts", "eth_chainId", "eth_sendTransaction"];
+        const SIGNER_PREFIXES: [&str; 4] = ["eth_sign", "personal_", "account_", "wallet_"];
+
+        self.passthrough
+            && !SIGNER_METHODS.contains(&method)
+            && !SIGNER_PREFIXES
+                .iter()
+                .any(|prefix| method.starts_with(prefix))
+    }
+
+    // ノードのエラーはコードごと返す
+    fn forward(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        let client = self.config().get_rpc_client()?;
+        match self.rpc_request_optional(&client, method, params) {
+            Ok(result) => Ok(result.unwrap_or(Value::Null)),
+            Err(Error::Rpc { code, message }) => Err(RpcError::new(code, message)),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn dispatch(
+        &self,
+        method: &str,
+        params: Value,
+        grant: &Grant,
+    ) -> std::result::Result<Value, RpcError> {
+        let accounts = || {
+            self.visible_accounts(grant)
+                .iter()
+                .map(address::to_checksum)
+                .collect::<Vec<_>>()
+        };
+
+        match method {
+            "eth_accounts" => Ok(json!(accounts())),
+            "eth_chainId" => Ok(json!(format!("{:#x}", self.config().chain_id))),
+            "eth_signTransaction" => {
+                let raw = self.sign_transaction(method, params, grant)?;
+                Ok(json!(format!("0x{}", hex::encode(raw))))
+            }
+            "eth_sendTransaction" => {
+                let client = self.config().get_rpc_client()?;
+                let raw = self.sign_transaction(method, params, grant)?;
+                let hash: H256 = self.rpc_request(
+                    &client,
+                    "eth_sendRawTransaction",
+                    json!([format!("0x{}", hex::encode(raw))]),
+                )?;
+                if let Some(journal) = &self.journal {
+                    // 送信済みのトランザクションは返すため、記録に失敗しても出力のみ
+                    if let Err(error) = journal.set_status(&hash, journal::Status::Broadcast) {
+                        eprintln!("Failed to write the journal: {error}");
+                    }
+                }
+                if let Some(notifier) = &self.notifier {
+                    let data = json!({ "requester": grant.name, "hash": hash });
+                    notifier.notify(Event::new(EventKind::Broadcast, data));
+                    notifier.watch_confirmation(client, hash, grant.name.clone());
+                }
+                Ok(json!(hash))
+            }
+            // EIP-191 (personal_sign / eth_sign) と EIP-712 のメッセージへの署名
+            "personal_sign" | "eth_sign" | "eth_signTypedData_v4" => {
+                let signature = self.sign_message(method, params, grant)?;
+                Ok(json!(format!("0x{}", hex::encode(signature.to_bytes()))))
+            }
+            // Clef の外部 API (geth の --signer から使われる)
+            "account_version" => Ok(json!(CLEF_API_VERSION)),
+            "account_list" => Ok(json!(accounts())),
+            "account_signTransaction" => {
+                let raw = self.sign_transaction(method, params, grant)?;
+                Ok(json!({
+                    "raw": format!("0x{}", hex::encode(&raw)),
+                    "tx": transaction::signed_json(&raw)?,
+                }))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("the method {method} does not exist/is not available"),
+            )),
+        }
+    }
+
+    // 結果を監査ログに記録する (記録するハッシュは署名したダイジェスト)
+    fn sign_message(
+        &self,
+        method: &str,
+        params: Value,
+        grant: &Grant,
+    ) -> std::result::Result<Signature, RpcError> {
+        let started = Instant::now();
+        let result = message_param(method, params.clone(), self.config().chain_id).and_then(
+            |(from, digest)| {
+                self.try_sign_message(method, from, &digest, grant)
+                    .map(|signature| (digest, signature))
+            },
+        );
+        self.audit(Record {
+            requester: grant.name.clone(),
+            method: method.to_string(),
+            params,
+            decision: if result.is_ok() {
+                Decision::Signed
+            } else {
+                Decision::Rejected
+            },
+            reason: result.as_ref().err().map(|error| error.message.clone()),
+            hash: result.as_ref().ok().map(|(digest, _)| *digest),
+        })?;
+
+        let (_, signature) = result?;
+        self.metrics
+            .record_signature(self.config().chain_id, "message", started.elapsed());
+        Ok(signature)
+    }
+
+    // CLI の sign-message・sign-typed-data と同じく、ポリシーの allow_message_signing で許可されている場合のみ署名する
+    fn try_sign_message(
+        &self,
+        method: &str,
+        from: H160,
+        digest: &H256,
+        grant: &Grant,
+    ) -> std::result::Result<Signature, RpcError> {
+        self.check_permission(grant, Permission::SignMessage)
+            .map_err(|reason| RpcError::new(UNAUTHORIZED, reason))?;
+        if from != self.address {
+            return Err(RpcError::invalid_params(format!(
+                "{} is not an account of this signer",
+                address::to_checksum(&from)
+            )));
+        }
+        self.check_policy(&grant.name, Policy::check_message)?;
+
+        let approval_request = json!({
+            "method": method,
+            "from": address::to_checksum(&self.address),
+            "digest": digest,
+        });
+        if !self.is_approved(&approval_request)? {
+            return Err(RpcError::new(SERVER_ERROR, "Request denied"));
+        }
+
+        Ok(self.key.key()?.sign_hash(digest)?)
+    }
+
+    // 結果 (拒否・承認待ちを含む) を監査ログに記録する
+    fn sign_transaction(
+        &self,
+        method: &str,
+        params: Value,
+        grant: &Grant,
+    ) -> std::result::Result<Vec<u8>, RpcError> {
+        let result = transaction_param(params.clone())
+            .and_then(|request| self.try_sign_transaction(method, request, grant));
+        let (decision, reason) = match &result {
+            Ok(_) => (Decision::Signed, None),
+            Err(error) if error.data.is_some() => {
+                (Decision::PendingApproval, Some(error.message.clone()))
+            }
+            Err(error) => (Decision::Rejected, Some(error.message.clone())),
+        };
+        self.audit(Record {
+            requester: grant.name.clone(),
+            method: method.to_string(),
+            params,
+            decision,
+            reason,
+            hash: result.as_ref().ok().map(keccak256),
+        })?;
+        result
+    }
+
+    fn try_sign_transaction(
+        &self,
+        method: &str,
+        request: TransactionRequest,
+        grant: &Grant,
+    ) -> std::result::Result<Vec<u8>, RpcError> {
+        let started = Instant::now();
+        self.check_permission(grant, Permission::SignTransaction)
+            .map_err(|reason| RpcError::new(UNAUTHORIZED, reason))?;
+        let requested_nonce = request.nonce;
+        let mut message = self.build_message(request)?;
+        if let Some(raw) = self.check_duplicate(&message, requested_nonce)? {
+            return Ok(raw);
+        }
+        let allocated = requested_nonce.is_none();
+        if allocated {
+            message.nonce = self.allocate_nonce()?;
+        }
+        let nonce = message.nonce;
+
+        let result = self.sign_transaction_message(method, message, grant, started);
+        self.settle_nonce(nonce, allocated, &result)?;
+        result
+    }
+
+    // ジャーナルがあれば既に署名したトランザクションとの重複を確認する
+    // リトライならジャーナルの署名を返し、置き換えや二重送信になる場合は拒否する
+    fn check_duplicate(
+        &self,
+        message: &EIP1559TransactionMessage,
+        nonce: Option<U256>,
+    ) -> std::result::Result<Option<Vec<u8>>, RpcError> {
+        let Some(journal) = self
+            .journal
+            .as_ref()
+            .filter(|_| !self.config().allow_duplicate)
+        else {
+            return Ok(None);
+        };
+
+        match journal.find_duplicate(&TxType::Eip1559, message, &self.address, nonce)? {
+            Some(journal::Duplicate::Cached(entry)) => {
+                let raw = hex::decode(entry.raw.trim_start_matches("0x")).map_err(Error::from)?;
+                Ok(Some(raw))
+            }
+            Some(journal::Duplicate::Conflict(entry)) => {
+                self.metrics.record_rejection("duplicate");
+                Err(RpcError::new(
+                    SERVER_ERROR,
+                    format!(
+                        "duplicate of the journaled transaction {:?} (same nonce or same payload still pending)",
+                        entry.hash
+                    ),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // 署名しなかった場合は割り当てた nonce をストアに戻し、署名した場合は指定された nonce もストアに記録する
+    // 承認待ちの nonce は承認されるまで割り当てたままにする
+    fn settle_nonce(
+        &self,
+        nonce: U256,
+        allocated: bool,
+        result: &std::result::Result<Vec<u8>, RpcError>,
+    ) -> std::result::Result<(), RpcError> {
+        let Some(nonces) = &self.nonces else {
+            return Ok(());
+        };
+
+        let chain_id = self.config().chain_id;
+        match (result, allocated) {
+            (Ok(_), false) => nonces.commit(chain_id, &self.address, nonce)?,
+            (Err(error), true) if error.data.is_none() => {
+                nonces.release(chain_id, &self.address, nonce)?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn sign_transaction_message(
+        &self,
+        method: &str,
+        message: EIP1559TransactionMessage,
+        grant: &Grant,
+        started: Instant,
+    ) -> std::result::Result<Vec<u8>, RpcError> {
+        self.check_transaction_rules(&grant.name, &message)?;
+
+        let approval_request = json!({
+            "method": method,
+            "from": address::to_checksum(&self.address),
+            "transaction": transaction::message_json(&TxType::Eip1559, &message),
+        });
+        if !self.is_approved(&approval_request)? {
+            return Err(RpcError::new(SERVER_ERROR, "Request denied"));
+        }
+
+        // 閾値を超える場合は2人目のオペレーターが承認するまで署名しない
+        if self
+            .policy()
+            .is_some_and(|policy| policy.check_approval_threshold(&message).is_err())
+        {
+            let id = self.approvals.submit(&grant.name, method, message);
+            eprintln!(
+                "Request {id} from '{}' is waiting for a second operator's approval",
+                grant.name
+            );
+            return Err(RpcError {
+                data: Some(json!({ "requestId": id })),
+                ..RpcError::new(
+                    SERVER_ERROR,
+                    format!("Approval required: request {id} is waiting for a second operator"),
+                )
+            });
+        }
+
+        self.release_signature(&grant.name, &grant.name, message, started)
+    }
+
+    // ポリシー・DENYLIST_PATH・approve の spender の確認
+    fn check_transaction_rules(
+        &self,
+        client: &str,
+        message: &EIP1559TransactionMessage,
+    ) -> std::result::Result<(), RpcError> {
+        self.check_policy(client, |policy| policy.check_transaction(message))?;
+        if let Some(denylist) = self.config().get_denylist()? {
+            denylist.check(message)?;
+        }
+        self.config().verify_approval(message)?;
+        Ok(())
+    }
+
+    // 要求者の1日の value と、ポリシーの1日の value の上限に加える
+    // ポリシーで拒否した場合は要求者の当日の合計に加えた分を戻す
+    fn reserve_value(
+        &self,
+        client: &str,
+        requester: &str,
+        value: U256,
+    ) -> std::result::Result<(), RpcError> {
+        self.rate_limiter
+            .reserve_value(requester, value)
+            .map_err(|reason| {
+                eprintln!("Rate limited: {reason}");
+                self.metrics.record_rejection("daily_value_limit");
+                RpcError::new(LIMIT_EXCEEDED, reason)
+            })?;
+        self.check_policy(client, |policy| policy.reserve_value(value))
+            .inspect_err(|_| self.rate_limiter.release_value(requester, value))
+    }
+
+    // 上限を数えて署名する
+    // client はこのリクエストでレート制限の枠を使ったクライアント (承認では承認者、それ以外は requester)
+    fn release_signature(
+        &self,
+        client: &str,
+        requester: &str,
+        message: EIP1559TransactionMessage,
+        started: Instant,
+    ) -> std::result::Result<Vec<u8>, RpcError> {
+        // ロック中は上限を数えない
+        let key = self.key.key()?;
+        // 同じトランザクションの再送には署名し直さずに同じ raw トランザクションを返す
+        // ポリシーや承認は確認し直すが、同じトランザクションは1度しか取り込まれないため上限は数え直さない
+        let hash = transaction::signing_hash(&message);
+        if let Some(raw) = self.signatures.get(self.address, &hash) {
+            self.metrics
+                .record_signature(self.config().chain_id, "transaction", started.elapsed());
+            return Ok(raw);
+        }
+        self.reserve_value(client, requester, message.value)?;
+
+        let recorded =
+            (self.journal.is_some() || self.raw_tx_dir.is_some()).then(|| message.clone());
+        let raw = transaction::sign(message, &key)?;
+        // 記録を残せない場合は署名を返さない
+        if let Some(message) = recorded {
+            let entry =
+                journal::Entry::new(&TxType::Eip1559, &message, self.address, &raw, requester);
+            if let Some(journal) = &self.journal {
+                journal
+                    .record(&entry)
+                    .inspect_err(|error| eprintln!("Failed to write the journal: {error}"))?;
+            }
+            if let Some(raw_tx_dir) = &self.raw_tx_dir {
+                raw_tx_dir.save(&entry).inspect_err(|error| {
+                    eprintln!("Failed to write the raw transaction: {error}")
+                })?;
+            }
+        }
+        self.signatures.insert(self.address, hash, &raw);
+        self.metrics
+            .record_signature(self.config().chain_id, "transaction", started.elapsed());
+        Ok(raw)
+    }
+
+    // 監査ログが設定されていれば署名要求を記録し、Webhook が設定されていれば通知する
+    // 署名の記録を残せない場合は署名を返さない (通知もしない)
+    fn audit(&self, record: Record) -> Result<()> {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log
+                .append(&record)
+                .inspect_err(|error| eprintln!("Failed to write the audit log: {error}"))?;
+        }
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Event::from_record(&record));
+        }
+        Ok(())
+    }
+
+    // ポリシーが設定されていれば確認し、違反はルールごとに記録する
+    // 拒否した場合は client のレート制限の枠を戻す (署名しなかったリクエストは数えない)
+    fn check_policy(
+        &self,
+        client: &str,
+        check: impl FnOnce(&Policy) -> std::result::Result<(), Violation>,
+    ) -> std::result::Result<(), RpcError> {
+        let Some(policy) = self.policy() else {
+            return Ok(());
+        };
+
+        check(&policy).map_err(|violation| {
+            eprintln!("Policy violation: {}", violation.reason);
+            self.metrics.record_rejection(violation.rule);
+            self.rate_limiter.refund_request(client);
+            Error::from(violation).into()
+        })
+    }
+
+    // 上流ノードへの JSON-RPC リクエスト (失敗はメトリクスに記録する)
+    fn rpc_request<T: DeserializeOwned>(
+        &self,
+        client: &RpcClient,
+        method: &str,
+        params: Value,
+    ) -> Result<T> {
+        client
+            .request(method, params)
+            .inspect_err(|_| self.metrics.record_rpc_error(method))
+    }
+
+    fn rpc_request_optional(
+        &self,
+        client: &RpcClient,
+        method: &str,
+        params: Value,
+    ) -> Result<Option<Value>> {
+        client
+            .request_optional(method, params)
+            .inspect_err(|_| self.metrics.record_rpc_error("passthrough"))
+    }
+
+    // 承認フックが設定されていれば、署名してよいか確認する
+    fn is_approved(&self, request: &Value) -> Result<bool> {
+        let Some(approval) = &self.approval else {
+            return Ok(true);
+        };
+
+        let approved = approval.approve(request)?;
+        if !approved {
+            self.metrics.record_rejection("approval_hook");
+            eprintln!("Request denied by the approval hook: {}", request["method"]);
+        }
+        Ok(approved)
+    }
+
+    // 省略されたフィールドを補って署名対象のトランザクションを作成
+    fn build_message(
+        &self,
+        request: TransactionRequest,
+    ) -> std::result::Result<EIP1559TransactionMessage, RpcError> {
+        if let Some(from) = request.from.filter(|from| *from != self.address) {
+            return Err(RpcError::invalid_params(format!(
+                "from {} is not an account of this signer",
+                address::to_checksum(&from)
+            )));
+        }
+        if let Some(chain_id) = request
+            .chain_id
+            .filter(|chain_id| *chain_id != U256::from(self.config().chain_id))
+        {
+            return Err(RpcError::invalid_params(format!(
+                "chainId {chain_id} does not match the configured chain id {}",
+                self.config().chain_id
+            )));
+        }
+        // 署名できるのは EIP-1559 (Type 2) のみ
+        if request.gas_price.is_some() {
+            return Err(RpcError::invalid_params(
+                "gasPrice (legacy transactions) is not supported, use maxFeePerGas",
+            ));
+        }
+
+        let input = match (request.input, request.data) {
+            (Some(input), Some(data)) if input != data => {
+                return Err(RpcError::invalid_params(
+                    "both input and data are set but differ",
+                ));
+            }
+            (Some(input), _) | (None, Some(input)) => input,
+            (None, None) => Vec::new(),
+        };
+        let value = request.value.unwrap_or_default();
+        // 省略された場合は重複を確認してから割り当てる
+        let nonce = request.nonce.unwrap_or_default();
+        let gas_limit = match request.gas {
+            Some(gas) => gas,
+            None => self.estimate_gas(request.to, value, &input)?,
+        };
+
+        Ok(EIP1559TransactionMessage {
+            chain_id: self.config().chain_id,
+            nonce,
+            max_priority_fee_per_gas: request
+                .max_priority_fee_per_gas
+                .unwrap_or(self.config().max_priority_fee_per_gas),
+            max_fee_per_gas: request
+                .max_fee_per_gas
+                .unwrap_or(self.config().max_fee_per_gas),
+            gas_limit,
+            action: match request.to {
+                Some(to) => TransactionAction::Call(to),
+                None => TransactionAction::Create,
+            },
+            value,
+            input,
+            access_list: AccessList::default(),
+        })
+    }
+
+    // nonce が省略された場合はストアで割り当てる (RPC_URL があればノードの pending の nonce より前は使わない)
+    // ストアがなければ pending の nonce を取得
+    fn allocate_nonce(&self) -> std::result::Result<U256, RpcError> {
+        let Some(nonces) = &self.nonces else {
+            return self.pending_nonce();
+        };
+
+        let pending = match self.config().rpc_url {
+            Some(_) => Some(self.pending_nonce()?),
+            None => None,
+        };
+        Ok(nonces.allocate(self.config().chain_id, &self.address, pending)?)
+    }
+
+    fn pending_nonce(&self) -> std::result::Result<U256, RpcError> {
+        let client = self
+            .config()
+            .get_rpc_client()
+            .map_err(|_| RpcError::invalid_params("nonce is required when RPC_URL is not set"))?;
+        let nonce: String = self.rpc_request(
+            &client,
+            "eth_getTransactionCount",
+            json!([self.address, "pending"]),
+        )?;
+
+        Ok(parse_quantity(&nonce)?)
+    }
+
+    // gas が省略された場合は eth_estimateGas で見積もる
+    fn estimate_gas(
+        &self,
+        to: Option<H160>,
+        value: U256,
+        input: &[u8],
+    ) -> std::result::Result<U256, RpcError> {
+        let client = self
+            .config()
+            .get_rpc_client()
+            .map_err(|_| RpcError::invalid_params("gas is required when RPC_URL is not set"))?;
+        let mut call = json!({
+            "from": self.address,
+            "value": format!("{value:#x}"),
+            "data": format!("0x{}", hex::encode(input)),
+        });
+        if chains::is_arbitrum(self.config().chain_id) {
+            return self.estimate_arbitrum_gas(&client, call, to, input);
+        }
+        if let Some(to) = to {
+            call["to"] = json!(to);
+        }
+        let gas: String = self.rpc_request(&client, "eth_estimateGas", json!([call]))?;
+
+        Ok(parse_quantity(&gas)?)
+    }
+
+    // Arbitrum では NodeInterface.gasEstimateComponents で L1 の分を含めた gas を求める
+    // L2 の base fee が MAX_FEE_PER_GAS を超えていると送れないため、署名する前にエラーにする
+    fn estimate_arbitrum_gas(
+        &self,
+        client: &RpcClient,
+        mut call: Value,
+        to: Option<H160>,
+        input: &[u8],
+    ) -> std::result::Result<U256, RpcError> {
+        call["to"] = json!(arbitrum::NODE_INTERFACE);
+        call["data"] = json!(format!(
+            "0x{}",
+            hex::encode(arbitrum::gas_estimate_components_calldata(to, input))
+        ));
+        let result: String = self.rpc_request(client, "eth_call", json!([call, "latest"]))?;
+        let data =
+            hex::decode(result.strip_prefix("0x").unwrap_or(&result)).map_err(Error::from)?;
+        let estimate = arbitrum::decode_gas_estimate(&data)?;
+
+        let max_fee_per_gas = self.config().max_fee_per_gas;
+        if estimate.base_fee > max_fee_per_gas {
+            return Err(RpcError::invalid_params(format!(
+                "MAX_FEE_PER_GAS {max_fee_per_gas} is below the current Arbitrum base fee {}",
+                estimate.base_fee
+            )));
+        }
+        Ok(estimate.gas_estimate)
+    }
+}
+
+// Unix ドメインソケットを作成し、パーミッションを設定する
+// 前回の実行で残ったソケットファイルは削除するが、使用中なら失敗する
+pub fn bind_unix_socket(path: &Path, mode: u32) -> Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(Error::InvalidArgument(format!(
+                "{} exists and is not a socket",
+                path.display()
+            )));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(Error::InvalidArgument(format!(
+                "{} is already in use",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+
+    Ok(listener)
+}
+
+// params の先頭のトランザクションオブジェクトを取り出す
+fn transaction_param(params: Value) -> std::result::Result<TransactionRequest, RpcError> {
+    let transaction = match params {
+        Value::Array(mut params) if !params.is_empty() => params.swap_remove(0),
+        _ => {
+            return Err(RpcError::invalid_params(
+                "expected a transaction object as the first parameter",
+            ));
+        }
+    };
+
+    serde_json::from_value(transaction)
+        .map_err(|error| RpcError::invalid_params(format!("invalid transaction: {error}")))
+}
+
+// personal_sign・eth_sign・eth_signTypedData_v4 の params から署名するアカウントとダイジェストを取り出す
+// eth_sign は geth と同じく EIP-191 のプレフィックスを付けて署名する (任意のハッシュには署名しない)
+fn message_param(
+    method: &str,
+    params: Value,
+    chain_id: u64,
+) -> std::result::Result<(H160, H256), RpcError> {
+    let params = match params {
+        Value::Array(params) => params,
+        _ => return Err(RpcError::invalid_params("expected an array of parameters")),
+    };
+    // personal_sign のみデータが先 (geth の3番目のパスワードは使わない)
+    let (from, data) = match (method, params.as_slice()) {
+        ("personal_sign", [data, from, ..]) => (from, data),
+        (_, [from, data, ..]) => (from, data),
+        _ => {
+            return Err(RpcError::invalid_params(format!(
+                "{method} expects an account and the data to sign"
+            )));
+        }
+    };
+    let from: H160 = serde_json::from_value(from.clone())
+        .map_err(|error| RpcError::invalid_params(format!("invalid account: {error}")))?;
+
+    let digest = match method {
+        "eth_signTypedData_v4" => typed_data_digest(data, chain_id)?,
+        _ => {
+            // 16進数でなければ UTF-8 の文字列として署名する (MetaMask と同じ)
+            let data = data
+                .as_str()
+                .ok_or_else(|| RpcError::invalid_params("expected the data as a string"))?;
+            let bytes = data
+                .strip_prefix("0x")
+                .and_then(|data| hex::decode(data).ok())
+                .unwrap_or_else(|| data.as_bytes().to_vec());
+            message::personal_message_hash(&bytes)
+        }
+    };
+    Ok((from, digest))
+}
+
+// JSON の文字列 (MetaMask) とオブジェクトのどちらも受け付ける
+// domain の chainId が設定と違えば拒否する
+fn typed_data_digest(data: &Value, chain_id: u64) -> std::result::Result<H256, RpcError> {
+    let typed_data: TypedData = match data {
+        Value::String(json) => serde_json::from_str(json),
+        data => serde_json::from_value(data.clone()),
+    }
+    .map_err(|error| RpcError::invalid_params(format!("invalid typed data: {error}")))?;
+
+    if let Some(requested) = typed_data.domain.get("chainId") {
+        let matches = match requested {
+            Value::String(requested) => parse_u256(requested).ok(),
+            requested => requested.as_u64().map(U256::from),
+        } == Some(U256::from(chain_id));
+        if !matches {
+            return Err(RpcError::invalid_params(format!(
+                "domain chainId {requested} does not match the configured chain id {chain_id}"
+            )));
+        }
+    }
+
+    typed_data
+        .digest()
+        .map_err(|error| RpcError::invalid_params(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abi,
+        auth::tests::{TEST_TOKEN, test_authenticator, test_jwt},
+        keystore::EncryptedKey,
+        rpc::tests::MockServer,
+        signer::tests::{TEST_ADDRESS, TEST_PRIVATE_KEY, test_signing_key},
+    };
+    use ethereum::EIP1559Transaction;
+    use std::io::Cursor;
+
+    fn test_config(rpc_url: Option<String>) -> Config {
+        Config {
+            chain_id: 11155111,
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            private_key: Secret::new(TEST_PRIVATE_KEY.to_string()),
+            signer_plugin: None,
+            rpc_url,
+            policy_path: None,
+            verified_contracts_path: None,
+            denylist_path: None,
+            audit_log_path: None,
+            webhooks_path: None,
+            nonce_store_path: None,
+            journal_path: None,
+            allow_duplicate: false,
+            raw_tx_dir: None,
+            state_passphrase: None,
+            state_key: None,
+            polygon_gas_station_url: None,
+            confirm_above: None,
+            tx_type: transaction::TxType::default(),
+            explicit_tx_type: None,
+            yes: false,
+            allow_flagged: false,
+            simulate: false,
+            deterministic: false,
+        }
+    }
+
+    fn test_server() -> Server {
+        Server::new(test_config(None)).unwrap()
+    }
+
+    fn call(server: &Server, method: &str, params: Value) -> Value {
+        server.handle_rpc(
+            json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params })
+                .to_string()
+                .as_bytes(),
+            &Grant::unrestricted(),
+        )
+    }
+
+    fn decode_raw(raw: &Value) -> EIP1559Transaction {
+        let raw = hex::decode(raw.as_str().unwrap().strip_prefix("0x").unwrap()).unwrap();
+        assert_eq!(raw[0], 0x02);
+        rlp::decode(&raw[1..]).unwrap()
+    }
+
+    #[test]
+    fn test_eth_accounts() {
+        let response = call(&test_server(), "eth_accounts", json!([]));
+
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"], json!([TEST_ADDRESS]));
+    }
+
+    #[test]
+    fn test_eth_chain_id() {
+        let response = call(&test_server(), "eth_chainId", json!([]));
+        assert_eq!(response["result"], "0xaa36a7");
+    }
+
+    #[test]
+    fn test_eth_sign_transaction() {
+        let response = call(
+            &test_server(),
+            "eth_signTransaction",
+            json!([{
+                "from": TEST_ADDRESS,
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": "0x1",
+                "gas": "0x5208",
+                "nonce": "0x1",
+            }]),
+        );
+
+        // transaction::tests::test_sign_known_vector と同じトランザクション
+        assert_eq!(
+            response["result"],
+            "0x02f86e83aa36a7018477359400850ba43b740082520894742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0180c080a09f0ecb460a1113ea92f4d1c03349dd277ec2f389f6227d60480ce919c24903e6a01115dee43f7b3ffcbaf0355d05a1f26a743e8279568ffd71c9638e6a4cc056bd"
+        );
+    }
+
+    #[test]
+    fn test_eth_sign_transaction_overrides_and_create() {
+        let response = call(
+            &test_server(),
+            "eth_signTransaction",
+            json!([{
+                "gasLimit": "0x100000",
+                "nonce": "0x0",
+                "maxFeePerGas": "0x3b9aca00",
+                "maxPriorityFeePerGas": "0x1",
+                "chainId": "0xaa36a7",
+                "input": "0x6080",
+            }]),
+        );
+
+        let transaction = decode_raw(&response["result"]);
+        assert_eq!(transaction.action, TransactionAction::Create);
+        assert_eq!(transaction.max_fee_per_gas, U256::from(1_000_000_000u64));
+        assert_eq!(transaction.max_priority_fee_per_gas, U256::one());
+        assert_eq!(transaction.gas_limit, U256::from(0x100000));
+        assert_eq!(transaction.input, vec![0x60, 0x80]);
+    }
+
+    #[test]
+    fn test_eth_sign_transaction_invalid_params() {
+        let server = test_server();
+        let base = json!({ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" });
+
+        let cases = [
+            json!({ "from": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df" }),
+            json!({ "chainId": "0x1" }),
+            json!({ "gasPrice": "0x1" }),
+            json!({ "input": "0x01", "data": "0x02" }),
+            json!({ "value": "not a number" }),
+        ];
+        for case in cases {
+            let mut transaction = base.clone();
+            for (key, value) in case.as_object().unwrap() {
+                transaction[key] = value.clone();
+            }
+            let response = call(&server, "eth_signTransaction", json!([transaction]));
+            assert_eq!(response["error"]["code"], INVALID_PARAMS, "{case}");
+        }
+
+        // RPC_URL がなければ nonce と gas は省略できない
+        let response = call(
+            &server,
+            "eth_signTransaction",
+            json!([{ "to": TEST_ADDRESS, "gas": "0x5208" }]),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = call(&server, "eth_signTransaction", json!([]));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_eth_send_transaction_fills_from_rpc() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        // nonce はジャーナルとの重複を確認してから割り当てるため、gas の見積もりの後に取得する
+        let rpc = MockServer::start(vec![
+            MockServer::rpc_result(json!("0x5208")),
+            MockServer::rpc_result(json!("0x7")),
+            MockServer::rpc_result(json!(hash)),
+        ]);
+        let server = Server::new(test_config(Some(rpc.url.clone()))).unwrap();
+
+        let response = call(
+            &server,
+            "eth_sendTransaction",
+            json!([{ "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "value": "0x1" }]),
+        );
+        assert_eq!(response["result"], hash);
+
+        let requests = rpc.json_requests();
+        assert_eq!(requests[0]["method"], "eth_estimateGas");
+        assert_eq!(requests[0]["params"][0]["value"], "0x1");
+        assert_eq!(requests[1]["method"], "eth_getTransactionCount");
+        assert_eq!(requests[1]["params"][1], "pending");
+        assert_eq!(requests[2]["method"], "eth_sendRawTransaction");
+
+        let transaction = decode_raw(&requests[2]["params"][0]);
+        assert_eq!(transaction.nonce, U256::from(7));
+        assert_eq!(transaction.gas_limit, U256::from(21000));
+    }
+
+    #[test]
+    fn test_eth_sign_transaction_estimates_arbitrum_gas() {
+        let components = |gas: u64, base_fee: u64| {
+            let data: Vec<u8> = [gas, 9_000, base_fee, 20_000_000_000]
+                .into_iter()
+                .flat_map(|value| abi::encode_u256(U256::from(value)))
+                .collect();
+            MockServer::rpc_result(json!(format!("0x{}", hex::encode(data))))
+        };
+        let rpc = MockServer::start(vec![
+            components(30_000, 10_000_000),
+            components(30_000, 60_000_000_000),
+        ]);
+        let mut config = test_config(Some(rpc.url.clone()));
+        config.chain_id = 42161;
+        let server = Server::new(config).unwrap();
+        let params =
+            json!([{ "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "nonce": "0x0" }]);
+
+        let response = call(&server, "eth_signTransaction", params.clone());
+        let transaction = decode_raw(&response["result"]);
+        assert_eq!(transaction.gas_limit, U256::from(30_000));
+
+        // base fee が MAX_FEE_PER_GAS を超えていたら署名しない
+        let response = call(&server, "eth_signTransaction", params);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let requests = rpc.json_requests();
+        assert_eq!(requests[0]["method"], "eth_call");
+        let request = &requests[0]["params"][0];
+        assert_eq!(request["to"], "0x00000000000000000000000000000000000000c8");
+        assert_eq!(request["from"], TEST_ADDRESS.to_lowercase());
+        assert!(request["data"].as_str().unwrap().starts_with(&format!(
+            "0x{}",
+            hex::encode(abi::selector("gasEstimateComponents(address,bool,bytes)"))
+        )));
+    }
+
+    #[test]
+    fn test_webhooks() {
+        let hash = H256::repeat_byte(0xab);
+        let rpc = MockServer::start(vec![
+            MockServer::rpc_result(json!(hash)),
+            MockServer::rpc_result(json!({ "blockNumber": "0x10", "status": "0x1" })),
+        ]);
+        let hooks = MockServer::start(vec![(200, "{}".to_string()); 3]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("webhooks.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "confirmation_poll_interval = 0\n[[webhooks]]\nurl = \"{}\"\nsecret = \"s\"\n",
+                hooks.url
+            ),
+        )
+        .unwrap();
+        let config = Config {
+            webhooks_path: Some(path.to_string_lossy().to_string()),
+            ..test_config(Some(rpc.url.clone()))
+        };
+        let server = Server::new(config).unwrap();
+
+        let response = call(
+            &server,
+            "eth_sendTransaction",
+            json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }]),
+        );
+        assert_eq!(response["result"], json!(hash));
+
+        let events = hooks.json_requests();
+        assert_eq!(events[0]["event"], "signed");
+        assert_eq!(events[0]["data"]["method"], "eth_sendTransaction");
+        assert!(events[0]["data"]["txHash"].is_string());
+        assert_eq!(events[1]["event"], "broadcast");
+        assert_eq!(events[1]["data"]["hash"], json!(hash));
+        assert_eq!(events[2]["event"], "confirmed");
+        assert_eq!(events[2]["data"]["blockNumber"], "0x10");
+        assert_eq!(
+            rpc.json_requests()[1]["method"],
+            "eth_getTransactionReceipt"
+        );
+    }
+
+    #[test]
+    fn test_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = test_server();
+        server.journal = Some(Journal::new(dir.path().join("journal.jsonl"), None));
+
+        let transaction = json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x3" }]);
+        let signed = call(&server, "eth_signTransaction", transaction.clone());
+        let raw = hex::decode(signed["result"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+        let hash = keccak256(&raw);
+        let journal = server.journal.as_ref().unwrap();
+        let entry = journal.get(&hash).unwrap().unwrap();
+        assert_eq!(entry.status, journal::Status::Signed);
+        assert_eq!(entry.nonce, U256::from(3));
+        assert_eq!(entry.from, TEST_ADDRESS.parse().unwrap());
+        assert_eq!(entry.requester, Grant::unrestricted().name);
+
+        // 同じトランザクションを送信すると状態を更新する
+        let rpc = MockServer::start(vec![MockServer::rpc_result(json!(hash))]);
+        server.config = RwLock::new(Arc::new(test_config(Some(rpc.url.clone()))));
+        call(&server, "eth_sendTransaction", transaction);
+        rpc.requests();
+
+        let entries = server.journal.as_ref().unwrap().entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, journal::Status::Broadcast);
+    }
+
+    #[test]
+    fn test_raw_tx_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = test_server();
+        server.raw_tx_dir = Some(RawTxDir::new(dir.path()));
+
+        let signed = call(
+            &server,
+            "eth_signTransaction",
+            json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x3" }]),
+        );
+        let raw = signed["result"].as_str().unwrap();
+        let hash = keccak256(hex::decode(raw.trim_start_matches("0x")).unwrap());
+        let path = dir
+            .path()
+            .join("11155111/0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266")
+            .join(format!("3-{hash:?}.rawtx"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().trim_end(), raw);
+        assert!(path.with_extension("json").exists());
+    }
+
+    #[test]
+    fn test_duplicate_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = test_server();
+        server.journal = Some(Journal::new(dir.path().join("journal.jsonl"), None));
+        let sign = |server: &Server, nonce: &str, value: &str| {
+            call(
+                server,
+                "eth_signTransaction",
+                json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": nonce, "value": value }]),
+            )
+        };
+
+        let signed = sign(&server, "0x3", "0x1");
+        // リトライには同じ署名を返す
+        assert_eq!(sign(&server, "0x3", "0x1")["result"], signed["result"]);
+        // 同じ nonce の別のトランザクションや、取り込み待ちと同じ内容は拒否する
+        let response = sign(&server, "0x3", "0x2");
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert!(
+            response["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("duplicate")
+        );
+        assert!(sign(&server, "0x4", "0x1")["error"].is_object());
+        assert!(sign(&server, "0x4", "0x2")["result"].is_string());
+
+        server.config = RwLock::new(Arc::new(Config {
+            allow_duplicate: true,
+            ..test_config(None)
+        }));
+        assert!(sign(&server, "0x3", "0x2")["result"].is_string());
+    }
+
+    #[test]
+    fn test_signature_cache() {
+        // ジャーナルがなくても、同じ要求の再送には同じ署名を返し、上限も数え直さない
+        let server = test_server().with_rate_limits(RateLimits {
+            value_per_day: Some(U256::from(1000)),
+            ..Default::default()
+        });
+        let transaction = |nonce: &str| json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": nonce, "value": "0x258" }]);
+
+        let signed = call(&server, "eth_signTransaction", transaction("0x3"));
+        assert!(signed["result"].is_string());
+        let retried = call(&server, "account_signTransaction", transaction("0x3"));
+        assert_eq!(retried["result"]["raw"], signed["result"]);
+        assert_eq!(
+            call(&server, "eth_signTransaction", transaction("0x4"))["error"]["code"],
+            LIMIT_EXCEEDED
+        );
+    }
+
+    #[test]
+    fn test_eth_send_transaction_requires_rpc_url() {
+        let response = call(
+            &test_server(),
+            "eth_sendTransaction",
+            json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }]),
+        );
+
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_rpc_errors_and_batch() {
+        let server = test_server();
+
+        let response = call(&server, "eth_signTypedData", json!([]));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        // 中継は provider.rs のみ
+        let response = call(&server, "eth_blockNumber", json!([]));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = server.handle_rpc(b"{not json", &Grant::unrestricted());
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+
+        let response = server.handle_rpc(b"[]", &Grant::unrestricted());
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        let response = server.handle_rpc(
+            br#"[{"id":1,"method":"eth_chainId"},{"id":2,"method":"eth_accounts"},{"id":3}]"#,
+            &Grant::unrestricted(),
+        );
+        assert_eq!(response[0]["result"], "0xaa36a7");
+        assert_eq!(response[1]["id"], 2);
+        assert_eq!(response[2]["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_clef_account_methods() {
+        let server = test_server();
+
+        assert_eq!(
+            call(&server, "account_version", json!([]))["result"],
+            "6.1.0"
+        );
+        assert_eq!(
+            call(&server, "account_list", json!([]))["result"],
+            json!([TEST_ADDRESS])
+        );
+    }
+
+    #[test]
+    fn test_clef_account_sign_transaction() {
+        // geth の external signer が送る形式 (data と input の両方、メソッドセレクタ)
+        let response = call(
+            &test_server(),
+            "account_signTransaction",
+            json!([
+                {
+                    "from": TEST_ADDRESS,
+                    "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                    "gas": "0x5208",
+                    "maxFeePerGas": "0xba43b7400",
+                    "maxPriorityFeePerGas": "0x77359400",
+                    "value": "0x1",
+                    "nonce": "0x1",
+                    "data": "0x",
+                    "input": "0x",
+                    "chainId": "0xaa36a7",
+                },
+                null,
+            ]),
+        );
+
+        let result = &response["result"];
+        assert_eq!(
+            result["raw"],
+            "0x02f86e83aa36a7018477359400850ba43b740082520894742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0180c080a09f0ecb460a1113ea92f4d1c03349dd277ec2f389f6227d60480ce919c24903e6a01115dee43f7b3ffcbaf0355d05a1f26a743e8279568ffd71c9638e6a4cc056bd"
+        );
+        assert_eq!(result["tx"]["nonce"], "0x1");
+        assert_eq!(result["tx"]["yParity"], "0x0");
+        assert_eq!(
+            result["tx"]["r"],
+            "0x9f0ecb460a1113ea92f4d1c03349dd277ec2f389f6227d60480ce919c24903e6"
+        );
+    }
+
+    #[test]
+    fn test_approval_hook() {
+        let transaction = json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }]);
+
+        let server = test_server().with_approval_hook(ApprovalHook::new("false"));
+        for method in [
+            "eth_signTransaction",
+            "account_signTransaction",
+            "eth_sendTransaction",
+        ] {
+            let response = call(&server, method, transaction.clone());
+            assert_eq!(response["error"]["code"], SERVER_ERROR, "{method}");
+        }
+        assert_eq!(
+            call(&server, "eth_signTransaction", transaction.clone())["error"]["message"],
+            "Request denied"
+        );
+
+        // フックには署名対象のトランザクションが渡される
+        let server = test_server().with_approval_hook(ApprovalHook::new(
+            "grep -q '\"method\":\"eth_signTransaction\"'",
+        ));
+        assert!(call(&server, "eth_signTransaction", transaction.clone())["result"].is_string());
+        assert!(call(&server, "account_signTransaction", transaction)["error"].is_object());
+    }
+
+    fn http(server: &Server, method: &str, path: &str, body: &str) -> HttpResponse {
+        server.handle_http(&HttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            peer: None,
+        })
+    }
+
+    #[test]
+    fn test_web3signer_public_keys_and_upcheck() {
+        let server = test_server();
+
+        let response = http(&server, "GET", "/api/v1/eth1/publicKeys", "");
+        assert_eq!(response.status, 200);
+        let keys: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(
+            keys,
+            json!([web3signer::public_key_hex(
+                server.key.verifying_key().unwrap()
+            )])
+        );
+
+        let response = http(&server, "GET", "/upcheck", "");
+        assert_eq!((response.status, response.body.as_str()), (200, "OK"));
+        assert_eq!(response.content_type, "text/plain; charset=utf-8");
+
+        assert_eq!(http(&server, "POST", "/upcheck", "").status, 405);
+        assert_eq!(
+            http(&server, "GET", "/api/v1/eth2/publicKeys", "").status,
+            404
+        );
+    }
+
+    #[test]
+    fn test_web3signer_sign() {
+        let server = test_server();
+        let path = format!(
+            "/api/v1/eth1/sign/{}",
+            web3signer::public_key_hex(server.key.verifying_key().unwrap())
+        );
+
+        let response = http(&server, "POST", &path, r#"{"data": "0x68656c6c6f"}"#);
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body,
+            web3signer::sign(&test_signing_key(), b"hello").unwrap()
+        );
+
+        // アドレスでも指定できる
+        let response = http(
+            &server,
+            "POST",
+            &format!("/api/v1/eth1/sign/{TEST_ADDRESS}"),
+            r#"{"data": "0x68656c6c6f"}"#,
+        );
+        assert_eq!(response.status, 200);
+
+        let unknown = "/api/v1/eth1/sign/0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df";
+        assert_eq!(
+            http(&server, "POST", unknown, r#"{"data": "0x00"}"#).status,
+            404
+        );
+        assert_eq!(
+            http(&server, "POST", &path, r#"{"data": "0xzz"}"#).status,
+            400
+        );
+        assert_eq!(http(&server, "POST", &path, "{}").status, 400);
+        assert_eq!(http(&server, "GET", &path, "").status, 405);
+
+        let server = test_server().with_approval_hook(ApprovalHook::new("false"));
+        assert_eq!(
+            http(&server, "POST", &path, r#"{"data": "0x00"}"#).status,
+            403
+        );
+    }
+
+    #[test]
+    fn test_web3signer_sign_transaction() {
+        let (_dir, server) = policy_server(&format!(
+            "allowed_recipients = [\"{TEST_ADDRESS}\"]\napproval_threshold = \"1\"\n"
+        ));
+        let server = server.with_authenticator(test_authenticator());
+        let path = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        let unsigned = |to: H160, value: U256, chain_id: u64, tx_type: TxType| {
+            let message = EIP1559TransactionMessage {
+                chain_id,
+                action: TransactionAction::Call(to),
+                value,
+                ..crate::bench::synthetic_message(0, 0)
+            };
+            let unsigned = detached::encode_unsigned(&tx_type, &message).unwrap();
+            json!({ "data": format!("0x{}", hex::encode(unsigned)) }).to_string()
+        };
+        let token = |permissions: Value| {
+            test_jwt(json!({
+                "sub": "relayer",
+                "iss": "issuer",
+                "aud": "signer",
+                "exp": 4_000_000_000u64,
+                "permissions": permissions,
+            }))
+        };
+        let sign_message = token(json!(["sign_message"]));
+        let sign_transaction = token(json!(["sign_transaction"]));
+        let allowed: H160 = TEST_ADDRESS.parse().unwrap();
+        let other = H160::repeat_byte(0x42);
+
+        // sign_message の権限ではトランザクションに署名しない
+        let body = unsigned(allowed, U256::one(), 11155111, TxType::Eip1559);
+        let response = http_with_token(&server, &path, Some(&sign_message), &body);
+        assert_eq!(response.status, 403);
+        // メッセージには署名できる
+        let response = http_with_token(
+            &server,
+            &path,
+            Some(&sign_message),
+            r#"{"data": "0x68656c6c6f"}"#,
+        );
+        assert_eq!(response.status, 200);
+
+        for tx_type in [TxType::Eip1559, TxType::Legacy] {
+            let body = unsigned(allowed, U256::one(), 11155111, tx_type.clone());
+            let response = http_with_token(&server, &path, Some(&sign_transaction), &body);
+            assert_eq!(response.status, 200);
+            let data: Value = serde_json::from_str(&body).unwrap();
+            let data = hex::decode(&data["data"].as_str().unwrap()[2..]).unwrap();
+            assert_eq!(
+                response.body,
+                web3signer::sign(&test_signing_key(), &data).unwrap()
+            );
+
+            // ポリシーの宛先・承認の閾値・チェーンを確認する
+            for (body, status) in [
+                (unsigned(other, U256::one(), 11155111, tx_type.clone()), 403),
+                (
+                    unsigned(allowed, U256::exp10(18) * 2, 11155111, tx_type.clone()),
+                    403,
+                ),
+                (unsigned(allowed, U256::one(), 1, tx_type.clone()), 400),
+            ] {
+                let response = http_with_token(&server, &path, Some(&sign_transaction), &body);
+                assert_eq!(response.status, status, "{body}");
+            }
+        }
+        // 検証できないタイプ (EIP-2930) は署名しない
+        let response = http_with_token(
+            &server,
+            &path,
+            Some(&sign_transaction),
+            r#"{"data": "0x01c0"}"#,
+        );
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn test_serve_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = bind_unix_socket(&path, 0o600).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        std::thread::spawn(move || test_server().run_unix(listener));
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_accounts"}"#;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.ends_with(&format!(r#""result":["{TEST_ADDRESS}"]}}"#)));
+
+        // 使用中のソケットは上書きしない
+        assert!(matches!(
+            bind_unix_socket(&path, 0o600),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_bind_unix_socket_replaces_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+
+        // 待ち受けていないソケットファイルは削除して作り直す
+        drop(UnixListener::bind(&path).unwrap());
+        let _listener = bind_unix_socket(&path, 0o660).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        // ソケット以外のファイルは削除しない
+        let file = dir.path().join("file");
+        std::fs::write(&file, "data").unwrap();
+        assert!(bind_unix_socket(&file, 0o600).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "data");
+    }
+
+    fn http_with_token(
+        server: &Server,
+        path: &str,
+        token: Option<&str>,
+        body: &str,
+    ) -> HttpResponse {
+        server.handle_http(&HttpRequest {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            headers: token
+                .map(|token| ("authorization".to_string(), format!("Bearer {token}")))
+                .into_iter()
+                .collect(),
+            body: body.as_bytes().to_vec(),
+            peer: None,
+        })
+    }
+
+    #[test]
+    fn test_authentication_required() {
+        let server = test_server().with_authenticator(test_authenticator());
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_accounts" }).to_string();
+
+        assert_eq!(http_with_token(&server, "/", None, &body).status, 401);
+        assert_eq!(
+            http_with_token(&server, "/", Some("wrong"), &body).status,
+            401
+        );
+        assert_eq!(http(&server, "GET", "/metrics", "").status, 401);
+        // ヘルスチェックは認証なし
+        assert_eq!(http(&server, "GET", "/upcheck", "").status, 200);
+
+        let response = http_with_token(&server, "/", Some(TEST_TOKEN), &body);
+        assert_eq!(response.status, 200);
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(response["result"], json!([TEST_ADDRESS]));
+
+        assert!(
+            server
+                .metrics
+                .render()
+                .contains("signer_rejections_total{rule=\"unauthenticated\"} 3\n")
+        );
+    }
+
+    #[test]
+    fn test_token_permissions() {
+        let server = test_server().with_authenticator(test_authenticator());
+        let sign_transaction = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_signTransaction",
+            "params": [{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }],
+        })
+        .to_string();
+
+        // トークンには sign_transaction のみ許可されている
+        let response = http_with_token(&server, "/", Some(TEST_TOKEN), &sign_transaction);
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert!(response["result"].is_string());
+
+        let path = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        let response = http_with_token(&server, &path, Some(TEST_TOKEN), r#"{"data": "0x00"}"#);
+        assert_eq!(response.status, 403);
+
+        // 別のチェーンのみ許可された JWT
+        let jwt = test_jwt(json!({
+            "sub": "mainnet-bot",
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "chains": [1],
+            "permissions": ["sign_transaction", "sign_message"],
+        }));
+        let response = http_with_token(&server, "/", Some(&jwt), &sign_transaction);
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+        assert_eq!(
+            response["error"]["message"],
+            "token 'mainnet-bot' is not permitted on chain id 11155111"
+        );
+        let response = http_with_token(&server, &path, Some(&jwt), r#"{"data": "0x00"}"#);
+        assert_eq!(response.status, 403);
+
+        assert!(
+            server
+                .metrics
+                .render()
+                .contains("signer_rejections_total{rule=\"permission\"} 3\n")
+        );
+    }
+
+    #[test]
+    fn test_token_accounts() {
+        let server = test_server().with_authenticator(test_authenticator());
+        // 別のアカウントのみ許可された JWT からは署名者のアカウントが見えない
+        let jwt = test_jwt(json!({
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "accounts": ["0x0000000000000000000000000000000000000001"],
+            "permissions": ["sign_message"],
+        }));
+
+        for method in ["eth_accounts", "account_list"] {
+            let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method }).to_string();
+            let response = http_with_token(&server, "/", Some(&jwt), &body);
+            let response: Value = serde_json::from_str(&response.body).unwrap();
+            assert_eq!(response["result"], json!([]));
+        }
+
+        let response = server.handle_http(&HttpRequest {
+            method: "GET".to_string(),
+            path: "/api/v1/eth1/publicKeys".to_string(),
+            headers: vec![("authorization".to_string(), format!("Bearer {jwt}"))],
+            body: Vec::new(),
+            peer: None,
+        });
+        assert_eq!(response.body, "[]");
+
+        let path = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        let response = http_with_token(&server, &path, Some(&jwt), r#"{"data": "0x00"}"#);
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn test_rate_limit_requests() {
+        let server = test_server().with_rate_limits(RateLimits {
+            requests_per_minute: Some(3),
+            ..Default::default()
+        });
+
+        // バッチは要素ごとに数える
+        let response = http(
+            &server,
+            "POST",
+            "/",
+            r#"[{"id":1,"method":"eth_chainId"},{"id":2,"method":"eth_chainId"}]"#,
+        );
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(response[1]["result"], "0xaa36a7");
+        assert_eq!(
+            http(&server, "GET", "/api/v1/eth1/publicKeys", "").status,
+            200
+        );
+
+        let response = http(&server, "POST", "/", r#"{"id":3,"method":"eth_chainId"}"#);
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(response["error"]["code"], LIMIT_EXCEEDED);
+        assert_eq!(http(&server, "GET", "/metrics", "").status, 429);
+        // ヘルスチェックは数えない
+        assert_eq!(http(&server, "GET", "/upcheck", "").status, 200);
+
+        // 接続元が異なれば別に数える
+        let response = server.handle_http(&HttpRequest {
+            method: "GET".to_string(),
+            path: "/api/v1/eth1/publicKeys".to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            peer: Some("10.0.0.1".parse().unwrap()),
+        });
+        assert_eq!(response.status, 200);
+
+        assert!(
+            server
+                .metrics
+                .render()
+                .contains("signer_rejections_total{rule=\"rate_limit\"} 2\n")
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_daily_value() {
+        let server = test_server().with_rate_limits(RateLimits {
+            value_per_day: Some(U256::from(1000)),
+            ..Default::default()
+        });
+        let transaction = |value: &str| json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0", "value": value }]);
+
+        let response = call(&server, "eth_signTransaction", transaction("0x258"));
+        assert!(response["result"].is_string());
+        let response = call(&server, "account_signTransaction", transaction("0x191"));
+        assert_eq!(response["error"]["code"], LIMIT_EXCEEDED);
+        assert_eq!(
+            response["error"]["message"],
+            "'anonymous' exceeded the daily value limit of 1000 wei (600 wei already signed today)"
+        );
+        let response = call(&server, "eth_signTransaction", transaction("0x190"));
+        assert!(response["result"].is_string());
+
+        assert!(
+            server
+                .metrics
+                .render()
+                .contains("signer_rejections_total{rule=\"daily_value_limit\"} 1\n")
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_policy_rejection() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy_path = dir.path().join("policy.toml");
+        std::fs::write(
+            &policy_path,
+            format!(
+                "allowed_recipients = [\"{TEST_ADDRESS}\"]\nmax_value_per_day = \"0.000000000000001\"\nledger = {:?}\n",
+                dir.path().join("ledger.json")
+            ),
+        )
+        .unwrap();
+        let mut config = test_config(None);
+        config.policy_path = Some(policy_path.to_string_lossy().into_owned());
+        let server = Server::new(config).unwrap();
+        let server = server.with_rate_limits(RateLimits {
+            requests_per_minute: Some(2),
+            value_per_day: Some(U256::from(2000)),
+        });
+        let sign = |to: &str, value: &str| {
+            call(
+                &server,
+                "eth_signTransaction",
+                json!([{ "to": to, "gas": "0x5208", "nonce": "0x0", "value": value }]),
+            )
+        };
+
+        // ポリシーで拒否した要求はレート制限の枠を使わない
+        for _ in 0..3 {
+            let response = sign("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "0x1");
+            assert_eq!(response["error"]["code"], SERVER_ERROR);
+        }
+        // ポリシーの1日の上限 (1000 wei) を超える要求は、レート制限の1日の合計 (2000 wei) にも数えない
+        let response = sign(TEST_ADDRESS, "0x3e9");
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        let response = sign(TEST_ADDRESS, "0x3e8");
+        assert!(response["result"].is_string());
+
+        // 署名した1件だけが数えられている
+        let response = call(&server, "eth_chainId", json!([]));
+        assert!(response["result"].is_string());
+        let response = call(&server, "eth_chainId", json!([]));
+        assert_eq!(response["error"]["code"], LIMIT_EXCEEDED);
+    }
+
+    // ポリシーファイルを書き出してサーバーを作成 (ディレクトリはテストの間保持する)
+    fn policy_server(policy: &str) -> (tempfile::TempDir, Server) {
+        let dir = tempfile::tempdir().unwrap();
+        let policy_path = dir.path().join("policy.toml");
+        std::fs::write(&policy_path, policy).unwrap();
+        let mut config = test_config(None);
+        config.policy_path = Some(policy_path.to_string_lossy().into_owned());
+        (dir, Server::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_nonce_store() {
+        let (dir, mut server) =
+            policy_server(&format!("allowed_recipients = [\"{TEST_ADDRESS}\"]\n"));
+        server.nonces = Some(NonceStore::new(dir.path().join("nonces.json"), None));
+        let sign = |to: &str, nonce: Option<&str>| {
+            let mut transaction = json!({ "to": to, "gas": "0x5208" });
+            if let Some(nonce) = nonce {
+                transaction["nonce"] = json!(nonce);
+            }
+            call(&server, "eth_signTransaction", json!([transaction]))
+        };
+        let signed_nonce = |response: &Value| decode_raw(&response["result"]).nonce;
+
+        assert_eq!(signed_nonce(&sign(TEST_ADDRESS, None)), U256::zero());
+        assert_eq!(signed_nonce(&sign(TEST_ADDRESS, None)), U256::one());
+        // 拒否された要求の nonce は次の要求に割り当てる
+        let response = sign("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", None);
+        assert!(response["error"].is_object());
+        assert_eq!(signed_nonce(&sign(TEST_ADDRESS, None)), U256::from(2));
+        // 指定された nonce で署名した場合はその次から割り当てる
+        assert_eq!(
+            signed_nonce(&sign(TEST_ADDRESS, Some("0x9"))),
+            U256::from(9)
+        );
+        assert_eq!(signed_nonce(&sign(TEST_ADDRESS, None)), U256::from(10));
+    }
+
+    #[test]
+    fn test_policy() {
+        let (dir, server) = policy_server(&format!(
+            "max_value_per_tx = \"0.000000000000001\"\nallowed_recipients = [\"{TEST_ADDRESS}\"]\nallow_message_signing = false\n"
+        ));
+        let transaction = |to: &str, value: &str| json!([{ "to": to, "gas": "0x5208", "nonce": "0x0", "value": value }]);
+
+        let response = call(
+            &server,
+            "eth_signTransaction",
+            transaction(TEST_ADDRESS, "0x3e8"),
+        );
+        assert!(response["result"].is_string());
+
+        let response = call(
+            &server,
+            "eth_signTransaction",
+            transaction(TEST_ADDRESS, "0x3e9"),
+        );
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert_eq!(
+            response["error"]["message"],
+            "Policy violation: value 1001 wei exceeds the limit of 1000 wei"
+        );
+        let response = call(
+            &server,
+            "account_signTransaction",
+            transaction("0x0000000000000000000000000000000000000001", "0x0"),
+        );
+        assert!(
+            response["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("is not allowed")
+        );
+
+        let path = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        assert_eq!(
+            http(&server, "POST", &path, r#"{"data": "0x00"}"#).status,
+            403
+        );
+
+        let metrics = server.metrics.render();
+        for rule in [
+            "max_value_per_tx",
+            "allowed_recipients",
+            "allow_message_signing",
+        ] {
+            assert!(metrics.contains(&format!("signer_rejections_total{{rule=\"{rule}\"}} 1\n")));
+        }
+
+        // 読み込めないポリシーでは起動しない
+        let mut config = test_config(None);
+        config.policy_path = Some(
+            dir.path()
+                .join("missing.toml")
+                .to_string_lossy()
+                .into_owned(),
+        );
+        assert!(Server::new(config).is_err());
+    }
+
+    #[test]
+    fn test_two_person_approval() {
+        let (_dir, server) = policy_server("approval_threshold = \"0.000000000000001\"\n");
+        let server = server.with_authenticator(test_authenticator());
+        let approver = test_jwt(json!({
+            "sub": "approver",
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "permissions": ["approve"],
+        }));
+        let sign = |value: &str| {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_signTransaction",
+                "params": [{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0", "value": value }],
+            });
+            let response = http_with_token(&server, "/", Some(TEST_TOKEN), &body.to_string());
+            serde_json::from_str::<Value>(&response.body).unwrap()
+        };
+        let get = |path: &str, token: &str| {
+            server.handle_http(&HttpRequest {
+                method: "GET".to_string(),
+                path: path.to_string(),
+                headers: vec![("authorization".to_string(), format!("Bearer {token}"))],
+                body: Vec::new(),
+                peer: None,
+            })
+        };
+
+        // 閾値以下はそのまま署名する
+        assert!(sign("0x3e8")["result"].is_string());
+
+        let response = sign("0x3e9");
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        let id = response["error"]["data"]["requestId"].as_str().unwrap();
+        assert!(
+            response["error"]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Approval required")
+        );
+
+        // 一覧は承認できるオペレーターのみ
+        let pending: Value =
+            serde_json::from_str(&get("/api/v1/approvals", &approver).body).unwrap();
+        assert_eq!(pending[0]["id"], id);
+        assert_eq!(pending[0]["requester"], "payments");
+        assert_eq!(get("/api/v1/approvals", TEST_TOKEN).status, 403);
+
+        // 要求者は状態を確認できるが承認はできない
+        let path = format!("/api/v1/approvals/{id}");
+        let status: Value = serde_json::from_str(&get(&path, TEST_TOKEN).body).unwrap();
+        assert_eq!(status["status"], "pending");
+        assert!(status.get("raw").is_none());
+        let approve = format!("{path}/approve");
+        assert_eq!(
+            http_with_token(&server, &approve, Some(TEST_TOKEN), "").status,
+            403
+        );
+
+        let response = http_with_token(&server, &approve, Some(&approver), "");
+        assert_eq!(response.status, 200);
+        let approved: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(approved["status"], "approved");
+        assert_eq!(approved["approver"], "approver");
+        let transaction = decode_raw(&approved["raw"]);
+        assert_eq!(transaction.value, U256::from(0x3e9));
+
+        let status: Value = serde_json::from_str(&get(&path, TEST_TOKEN).body).unwrap();
+        assert_eq!(status["raw"], approved["raw"]);
+        assert_eq!(
+            http_with_token(&server, &approve, Some(&approver), "").status,
+            409
+        );
+        assert_eq!(
+            http_with_token(
+                &server,
+                "/api/v1/approvals/missing/approve",
+                Some(&approver),
+                ""
+            )
+            .status,
+            404
+        );
+
+        // 要求者は取り下げられる
+        let id = sign("0x3e9")["error"]["data"]["requestId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = http_with_token(
+            &server,
+            &format!("/api/v1/approvals/{id}/reject"),
+            Some(TEST_TOKEN),
+            "",
+        );
+        let rejected: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(rejected["status"], "rejected");
+        assert_eq!(
+            http_with_token(
+                &server,
+                &format!("/api/v1/approvals/{id}/approve"),
+                Some(&approver),
+                ""
+            )
+            .status,
+            409
+        );
+    }
+
+    #[test]
+    fn test_approval_rechecks_policy() {
+        let threshold = "approval_threshold = \"0.000000000000001\"\n";
+        let (dir, server) = policy_server(threshold);
+        let server = server.with_authenticator(test_authenticator());
+        let approver = test_jwt(json!({
+            "sub": "approver",
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "permissions": ["approve"],
+        }));
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_signTransaction",
+            "params": [{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0", "value": "0x3e9" }],
+        });
+        let response = http_with_token(&server, "/", Some(TEST_TOKEN), &body.to_string());
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        let id = response["error"]["data"]["requestId"].as_str().unwrap();
+
+        // 承認を待つ間に送金先を制限したポリシーを読み直す
+        std::fs::write(
+            dir.path().join("policy.toml"),
+            format!(
+                "{threshold}allowed_recipients = [\"0x{}\"]\n",
+                "11".repeat(20)
+            ),
+        )
+        .unwrap();
+        server.reload().unwrap();
+
+        let approve = format!("/api/v1/approvals/{id}/approve");
+        let response = http_with_token(&server, &approve, Some(&approver), "");
+        assert_eq!(response.status, 403);
+        let body: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["request"]["status"], "rejected");
+        assert_eq!(body["request"]["rejectedBy"], "approver");
+        assert!(body["request"].get("raw").is_none());
+        assert_eq!(
+            http_with_token(&server, &approve, Some(&approver), "").status,
+            409
+        );
+    }
+
+    fn signature_address(signature: &Value, digest: &H256) -> H160 {
+        let bytes = hex::decode(signature.as_str().unwrap().trim_start_matches("0x")).unwrap();
+        let signature = Signature {
+            r: H256::from_slice(&bytes[..32]),
+            s: H256::from_slice(&bytes[32..64]),
+            odd_y_parity: bytes[64] == 28,
+        };
+        crate::signer::tests::recover_address(digest, &signature)
+    }
+
+    #[test]
+    fn test_message_signing() {
+        let (dir, mut server) = policy_server("");
+        let audit_path = dir.path().join("audit.log");
+        server.audit_log = Some(AuditLog::new(&audit_path));
+        let address: H160 = TEST_ADDRESS.parse().unwrap();
+
+        // 16進数のデータと UTF-8 の文字列は同じメッセージ
+        let digest = message::personal_message_hash(b"hello");
+        for (method, params) in [
+            ("personal_sign", json!(["0x68656c6c6f", TEST_ADDRESS])),
+            ("personal_sign", json!(["hello", TEST_ADDRESS])),
+            ("eth_sign", json!([TEST_ADDRESS, "0x68656c6c6f"])),
+        ] {
+            let signature = &call(&server, method, params)["result"];
+            assert_eq!(signature_address(signature, &digest), address, "{method}");
+        }
+
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" }
+                ],
+                "Greeting": [{ "name": "contents", "type": "string" }]
+            },
+            "primaryType": "Greeting",
+            "domain": { "name": "Test", "chainId": "0xaa36a7" },
+            "message": { "contents": "hello" }
+        });
+        let digest = serde_json::from_value::<TypedData>(typed_data.clone())
+            .unwrap()
+            .digest()
+            .unwrap();
+        // MetaMask は typed data を JSON の文字列で渡す
+        for data in [typed_data.clone(), json!(typed_data.to_string())] {
+            let response = call(&server, "eth_signTypedData_v4", json!([TEST_ADDRESS, data]));
+            assert_eq!(signature_address(&response["result"], &digest), address);
+        }
+
+        let mut other_chain = typed_data;
+        other_chain["domain"]["chainId"] = json!(1);
+        let response = call(
+            &server,
+            "eth_signTypedData_v4",
+            json!([TEST_ADDRESS, other_chain]),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call(
+            &server,
+            "personal_sign",
+            json!(["0x68656c6c6f", "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"]),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call(&server, "personal_sign", json!(["0x68656c6c6f"]));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let entries: Vec<Value> = std::fs::read_to_string(&audit_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 8);
+        assert_eq!(entries[0]["method"], "personal_sign");
+        assert_eq!(entries[0]["decision"], "signed");
+        assert_eq!(
+            entries[0]["txHash"],
+            json!(message::personal_message_hash(b"hello"))
+        );
+        assert_eq!(entries[7]["decision"], "rejected");
+
+        // CLI と同じくポリシーで拒否できる
+        let (_dir, server) = policy_server("allow_message_signing = false\n");
+        let response = call(
+            &server,
+            "personal_sign",
+            json!(["0x68656c6c6f", TEST_ADDRESS]),
+        );
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert!(
+            response["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("signing messages is not allowed"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn test_message_signing_permission() {
+        let server = test_server().with_authenticator(test_authenticator());
+        // sign_message の権限がなければ署名しない (TEST_TOKEN は sign_transaction のみ)
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "personal_sign",
+            "params": ["0x68656c6c6f", TEST_ADDRESS],
+        });
+        let response = http_with_token(&server, "/", Some(TEST_TOKEN), &body.to_string());
+        let response: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let (dir, mut server) = policy_server(&format!(
+            "approval_threshold = \"0.000000000000001\"\nallowed_recipients = [\"{TEST_ADDRESS}\"]\n"
+        ));
+        let audit_path = dir.path().join("audit.log");
+        server.audit_log = Some(AuditLog::new(&audit_path));
+        let server = server.with_authenticator(test_authenticator());
+        let approver = test_jwt(json!({
+            "sub": "approver",
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "permissions": ["approve", "sign_message"],
+        }));
+        let sign = |to: &str, value: &str| {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_signTransaction",
+                "params": [{ "to": to, "gas": "0x5208", "nonce": "0x0", "value": value }],
+            });
+            let response = http_with_token(&server, "/", Some(TEST_TOKEN), &body.to_string());
+            serde_json::from_str::<Value>(&response.body).unwrap()
+        };
+
+        let raw = sign(TEST_ADDRESS, "0x3e8")["result"].clone();
+        sign("0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df", "0x3e8");
+        let id = sign(TEST_ADDRESS, "0x3e9")["error"]["data"]["requestId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let approve = format!("/api/v1/approvals/{id}/approve");
+        assert_eq!(
+            http_with_token(&server, &approve, Some(&approver), "").status,
+            200
+        );
+        let path = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        let response = http_with_token(
+            &server,
+            &path,
+            Some(&approver),
+            r#"{"data": "0x68656c6c6f"}"#,
+        );
+        assert_eq!(response.status, 200);
+
+        let (count, _) = crate::audit::verify(&audit_path).unwrap();
+        assert_eq!(count, 5);
+        let entries: Vec<Value> = std::fs::read_to_string(&audit_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(entries[0]["requester"], "payments");
+        assert_eq!(entries[0]["method"], "eth_signTransaction");
+        assert_eq!(entries[0]["params"][0]["value"], "0x3e8");
+        assert_eq!(entries[0]["decision"], "signed");
+        let raw = hex::decode(raw.as_str().unwrap().trim_start_matches("0x")).unwrap();
+        assert_eq!(entries[0]["txHash"], json!(keccak256(&raw)));
+
+        assert_eq!(entries[1]["decision"], "rejected");
+        assert!(
+            entries[1]["reason"]
+                .as_str()
+                .unwrap()
+                .contains("is not allowed")
+        );
+        assert!(entries[1].get("txHash").is_none());
+
+        assert_eq!(entries[2]["decision"], "pending_approval");
+        assert_eq!(entries[3]["requester"], "approver");
+        assert_eq!(entries[3]["method"], "approvals_approve");
+        assert_eq!(entries[3]["params"]["requestId"], json!(id));
+        assert_eq!(entries[3]["params"]["requester"], "payments");
+        assert_eq!(entries[3]["decision"], "signed");
+
+        assert_eq!(entries[4]["method"], "eth1_sign");
+        assert_eq!(entries[4]["params"]["data"], "0x68656c6c6f");
+        assert_eq!(entries[4]["txHash"], json!(keccak256(b"hello")));
+    }
+
+    #[test]
+    fn test_key_file_lock() {
+        let encrypted = EncryptedKey::encrypt(&test_signing_key(), "password", 1000).unwrap();
+        let mut config = test_config(None);
+        config.private_key = Secret::default();
+        let server =
+            Server::with_key_session(config, KeySession::locked(encrypted, None).unwrap()).unwrap();
+        let sign = || {
+            call(
+                &server,
+                "eth_signTransaction",
+                json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0", "value": "0x1" }]),
+            )
+        };
+        let status =
+            |response: HttpResponse| serde_json::from_str::<Value>(&response.body).unwrap();
+
+        // ロック中もアカウントは返すが署名はしない
+        assert_eq!(
+            call(&server, "eth_accounts", json!([]))["result"][0],
+            TEST_ADDRESS
+        );
+        assert_eq!(
+            sign()["error"]["message"],
+            "The signing key is locked, unlock it first."
+        );
+        let eth1_sign = format!("/api/v1/eth1/sign/{TEST_ADDRESS}");
+        assert_eq!(
+            http(&server, "POST", &eth1_sign, r#"{"data": "0x00"}"#).status,
+            503
+        );
+        let locked = status(http(&server, "GET", "/api/v1/key", ""));
+        assert_eq!(locked["locked"], true);
+        assert_eq!(locked["lockable"], true);
+
+        let response = http(
+            &server,
+            "POST",
+            "/api/v1/key/unlock",
+            r#"{"password": "wrong"}"#,
+        );
+        assert_eq!(response.status, 403);
+        assert_eq!(
+            http(&server, "POST", "/api/v1/key/unlock", "{}").status,
+            400
+        );
+
+        let response = http(
+            &server,
+            "POST",
+            "/api/v1/key/unlock",
+            r#"{"password": "password"}"#,
+        );
+        assert_eq!(status(response)["locked"], false);
+        assert!(sign()["result"].is_string());
+
+        assert_eq!(
+            status(http(&server, "POST", "/api/v1/key/lock", ""))["locked"],
+            true
+        );
+        assert!(sign()["error"].is_object());
+        assert_eq!(http(&server, "GET", "/api/v1/key/unlock", "").status, 405);
+    }
+
+    #[test]
+    fn test_key_unlock_permission() {
+        let server = test_server().with_authenticator(test_authenticator());
+        let response = http_with_token(&server, "/api/v1/key/lock", Some(TEST_TOKEN), "");
+        assert_eq!(response.status, 403);
+
+        let operator = test_jwt(json!({
+            "sub": "operator",
+            "iss": "issuer",
+            "aud": "signer",
+            "exp": 4_000_000_000u64,
+            "permissions": ["unlock"],
+        }));
+        let response = http_with_token(&server, "/api/v1/key/lock", Some(&operator), "");
+        assert_eq!(response.status, 200);
+        // PRIVATE_KEY の鍵はロックできない
+        let status: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(status["lockable"], false);
+        assert_eq!(status["locked"], false);
+    }
+
+    #[test]
+    fn test_reload_policy() {
+        let (dir, server) = policy_server("max_value_per_tx = \"0.000000000000001\"\n");
+        let policy_path = dir.path().join("policy.toml");
+        let sign = || {
+            call(
+                &server,
+                "eth_signTransaction",
+                json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0", "value": "0x3e9" }]),
+            )
+        };
+        assert!(sign()["error"].is_object());
+        assert!(!server.files_changed());
+
+        std::fs::write(&policy_path, "max_value_per_tx = \"1\"\n").unwrap();
+        // 同じ時刻に書き換えられても検出できるよう更新時刻をずらす
+        std::fs::File::options()
+            .write(true)
+            .open(&policy_path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert!(server.files_changed());
+        assert!(!server.files_changed());
+        server.reload().unwrap();
+        assert!(sign()["result"].is_string());
+
+        // 読み込めないポリシーではそれまでのポリシーを使い続ける
+        std::fs::write(&policy_path, "max_value_per_tx = \"not a number\"\n").unwrap();
+        assert!(server.reload().is_err());
+        assert!(sign()["result"].is_string());
+        std::fs::write(&policy_path, "max_value_per_tx = \"0\"\n").unwrap();
+        server.reload().unwrap();
+        assert!(sign()["error"].is_object());
+    }
+
+    #[test]
+    fn test_reload_config_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let dotenv_path = dir.path().join(".env");
+        let dotenv = |chain_id: u64, max_fee_per_gas: u64| {
+            std::fs::write(
+                &dotenv_path,
+                format!(
+                    "CHAIN_ID={chain_id}\nMAX_FEE_PER_GAS={max_fee_per_gas:#x}\nMAX_PRIORITY_FEE_PER_GAS=1\nPRIVATE_KEY={TEST_PRIVATE_KEY}\n"
+                ),
+            )
+            .unwrap()
+        };
+        dotenv(11155111, 100);
+        let source = ConfigSource::new(HashMap::new(), Some(dotenv_path.clone()));
+        let server = Server::new(source.load().unwrap())
+            .unwrap()
+            .with_config_source(source);
+        let max_fee_per_gas = || {
+            let response = call(
+                &server,
+                "eth_signTransaction",
+                json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }]),
+            );
+            decode_raw(&response["result"]).max_fee_per_gas
+        };
+        assert_eq!(max_fee_per_gas(), U256::from(100));
+        assert_eq!(server.watched_files(), vec![dotenv_path.clone()]);
+
+        dotenv(11155111, 200);
+        server.reload().unwrap();
+        assert_eq!(max_fee_per_gas(), U256::from(200));
+
+        // chain id は再起動しないと変えられない
+        dotenv(1, 300);
+        assert!(
+            server
+                .reload()
+                .unwrap_err()
+                .to_string()
+                .contains("CHAIN_ID cannot be changed")
+        );
+        assert_eq!(max_fee_per_gas(), U256::from(200));
+    }
+
+    #[test]
+    fn test_approve_command() {
+        let (_dir, server) = policy_server("approval_threshold = \"0\"\n");
+        let id = server.approvals.submit(
+            "payments",
+            "eth_signTransaction",
+            server
+                .build_message(TransactionRequest {
+                    to: Some(TEST_ADDRESS.parse().unwrap()),
+                    value: Some(U256::one()),
+                    gas: Some(U256::from(21000)),
+                    nonce: Some(U256::zero()),
+                    ..Default::default()
+                })
+                .unwrap(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || server.run(listener));
+
+        let approved = crate::approvals::decide(&url, &id, None, true).unwrap();
+        assert_eq!(approved["status"], "approved");
+        assert!(crate::approvals::decide(&url, &id, None, false).is_err());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let server = test_server().with_approval_hook(ApprovalHook::new(
+            "grep -q '\"method\":\"eth_signTransaction\"'",
+        ));
+        let transaction = json!([{ "to": TEST_ADDRESS, "gas": "0x5208", "nonce": "0x0" }]);
+
+        call(&server, "eth_accounts", json!([]));
+        call(&server, "eth_signTransaction", transaction.clone());
+        call(&server, "account_signTransaction", transaction);
+        call(&server, "no_such_method", json!([]));
+        call(&server, "eth_sendTransaction", json!([]));
+        http(&server, "GET", "/upcheck", "");
+
+        let response = http(&server, "GET", "/metrics", "");
+        assert_eq!(response.status, 200);
+        assert!(
+            response
+                .content_type
+                .starts_with("text/plain; version=0.0.4")
+        );
+
+        let body = response.body;
+        for line in [
+            "signer_requests_total{method=\"eth_accounts\",status=\"ok\"} 1",
+            "signer_requests_total{method=\"eth_signTransaction\",status=\"ok\"} 1",
+            "signer_requests_total{method=\"account_signTransaction\",status=\"error\"} 1",
+            "signer_requests_total{method=\"unknown\",status=\"error\"} 1",
+            "signer_requests_total{method=\"eth_sendTransaction\",status=\"error\"} 1",
+            "signer_requests_total{method=\"upcheck\",status=\"ok\"} 1",
+            "signer_signatures_total{chain_id=\"11155111\",kind=\"transaction\"} 1",
+            "signer_rejections_total{rule=\"approval_hook\"} 1",
+            "signer_signing_duration_seconds_count 1",
+        ] {
+            assert!(body.contains(&format!("{line}\n")), "{line}\n{body}");
+        }
+        assert!(!body.contains("no_such_method"));
+    }
+
+    #[test]
+    fn test_metrics_rpc_errors() {
+        let rpc = MockServer::start(vec![(
+            200,
+            json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "boom" } })
+                .to_string(),
+        )]);
+        let server = Server::new(test_config(Some(rpc.url.clone()))).unwrap();
+
+        let response = call(
+            &server,
+            "eth_signTransaction",
+            json!([{ "to": TEST_ADDRESS, "nonce": "0x0" }]),
+        );
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        rpc.requests();
+
+        assert!(
+            server
+                .metrics
+                .render()
+                .contains("signer_rpc_errors_total{method=\"eth_estimateGas\"} 1\n")
+        );
+    }
+
+    // TLS 接続で eth_accounts を呼び出してレスポンスを返す
+    fn tls_eth_accounts(
+        address: std::net::SocketAddr,
+        client_auth: bool,
+    ) -> std::io::Result<String> {
+        let mut stream = tls::tests::connect(address, client_auth);
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_accounts"}"#;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        stream.flush()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    #[test]
+    fn test_serve_over_tls() {
+        let config = tls::server_config(
+            &tls::tests::testdata("server.pem"),
+            &tls::tests::testdata("server.key"),
+            None,
+        )
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || test_server().run_tls(listener, config));
+
+        let response = tls_eth_accounts(address, false).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains(TEST_ADDRESS));
+    }
+
+    #[test]
+    fn test_serve_over_mutual_tls() {
+        let config = tls::server_config(
+            &tls::tests::testdata("server.pem"),
+            &tls::tests::testdata("server.key"),
+            Some(&tls::tests::testdata("ca.pem")),
+        )
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || test_server().run_tls(listener, config));
+
+        let response = tls_eth_accounts(address, true).unwrap();
+        assert!(response.contains(TEST_ADDRESS), "{response}");
+
+        // クライアント証明書がなければハンドシェイクが失敗する
+        assert!(tls_eth_accounts(address, false).is_err());
+    }
+
+    #[test]
+    fn test_read_request() {
+        let mut reader = Cursor::new(
+            b"POST / HTTP/1.1\r\nHost: x\r\ncontent-length: 2\r\n\r\n{}GET /a HTTP/1.1\r\n\r\n"
+                .to_vec(),
+        );
+
+        let request = read_request(&mut reader).unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.header("Content-Length"), Some("2"));
+        assert_eq!(request.body, b"{}");
+
+        let request = read_request(&mut reader).unwrap().unwrap();
+        assert_eq!(request.path, "/a");
+        assert!(request.body.is_empty());
+
+        assert!(read_request(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_request_errors() {
+        let read = |raw: &[u8]| read_request(&mut Cursor::new(raw.to_vec())).unwrap_err();
+
+        assert_eq!(read(b"POST\r\n\r\n"), 400);
+        assert_eq!(read(b"POST / HTTP/1.1\r\nbad header\r\n\r\n"), 400);
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"),
+            411
+        );
+        assert_eq!(
+            read(
+                format!(
+                    "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                    MAX_BODY_SIZE + 1
+                )
+                .as_bytes()
+            ),
+            413
+        );
+        assert_eq!(read(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n{}"), 400);
+    }
+
+    #[test]
+    fn test_serve_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || test_server().run(listener));
+
+        let response: Value = ureq::post(&url)
+            .send_json(json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_accounts" }))
+            .unwrap()
+            .body_mut()
+            .read_json()
+            .unwrap();
+        assert_eq!(response["result"], json!([TEST_ADDRESS]));
+
+        // 同じ接続で続けて送れる
+        let response: Value = ureq::post(&url)
+            .send_json(json!({ "jsonrpc": "2.0", "id": 2, "method": "eth_chainId" }))
+            .unwrap()
+            .body_mut()
+            .read_json()
+            .unwrap();
+        assert_eq!(response["result"], "0xaa36a7");
+
+        let error = ureq::get(&url).call().unwrap_err();
+        assert!(matches!(error, ureq::Error::StatusCode(405)));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{Result, address, error::Error, keystore::EncryptedKey, secret::Secret, signer::Key};
+use ethereum_types::H160;
+use k256::ecdsa::VerifyingKey;
+use serde_json::{Value, json};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// 鍵の状態とロック・ロック解除の REST API
+pub const KEY_PATH: &str = "/api/v1/key";
+
+struct Unlocked {
+    key: Key,
+    last_used: Instant,
+}
+
+// 署名サーバーが使う鍵
+// 暗号化された鍵ファイルの場合は unlock されている間だけ復号した鍵をメモリに置き、
+// 一定時間使われなければ (または SIGHUP で) 破棄する
+pub struct KeySession {
+    address: H160,
+    // 署名のプラグインは公開鍵を返さないため None (Web3Signer の API では使えない)
+    verifying_key: Option<VerifyingKey>,
+    // None なら PRIVATE_KEY または SIGNER_PLUGIN の鍵で、常に署名できる
+    encrypted: Option<EncryptedKey>,
+    idle_timeout: Option<Duration>,
+    unlocked: Mutex<Option<Unlocked>>,
+}
+
+impl KeySession {
+    // ロックしない鍵 (PRIVATE_KEY・SIGNER_PLUGIN)
+    pub fn unlocked(key: Key) -> Self {
+        Self {
+            address: key.address(),
+            verifying_key: key
+                .signing_key()
+                .map(|signing_key| *signing_key.verifying_key()),
+            encrypted: None,
+            idle_timeout: None,
+            unlocked: Mutex::new(Some(Unlocked {
+                key,
+                last_used: Instant::now(),
+            })),
+        }
+    }
+
+    // ロックされた状態で始まる鍵ファイル
+    pub fn locked(encrypted: EncryptedKey, idle_timeout: Option<Duration>) -> Result<Self> {
+        let verifying_key = encrypted.verifying_key()?;
+        Ok(Self {
+            address: address::from_verifying_key(&verifying_key),
+            verifying_key: Some(verifying_key),
+            encrypted: Some(encrypted),
+            idle_timeout,
+            unlocked: Mutex::new(None),
+        })
+    }
+
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    pub fn verifying_key(&self) -> Option<&VerifyingKey> {
+        self.verifying_key.as_ref()
+    }
+
+    // ロックできる鍵か (PRIVATE_KEY・SIGNER_PLUGIN の鍵は常に unlock されている)
+    pub fn is_lockable(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.expire_idle();
+        self.unlocked.lock().unwrap().is_none()
+    }
+
+    pub fn unlock(&self, password: &str) -> Result<()> {
+        let Some(encrypted) = &self.encrypted else {
+            return Ok(());
+        };
+
+        let key = Key::new(encrypted.decrypt(password)?);
+        *self.unlocked.lock().unwrap() = Some(Unlocked {
+            key,
+            last_used: Instant::now(),
+        });
+        Ok(())
+    }
+
+    // 復号した鍵を破棄する (ロックできない鍵では何もしない)
+    // 鍵を破棄した場合は true
+    pub fn lock(&self) -> bool {
+        if !self.is_lockable() {
+            return false;
+        }
+        self.unlocked.lock().unwrap().take().is_some()
+    }
+
+    // 一定時間使われていなければロックする
+    // ロックした場合は true
+    pub fn expire_idle(&self) -> bool {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return false;
+        };
+        let mut unlocked = self.unlocked.lock().unwrap();
+        if unlocked
+            .as_ref()
+            .is_some_and(|unlocked| unlocked.last_used.elapsed() >= idle_timeout)
+        {
+            *unlocked = None;
+            return true;
+        }
+        false
+    }
+
+    // 署名に使う鍵 (使ったことを記録してタイムアウトを延ばす)
+    pub fn key(&self) -> Result<Key> {
+        self.expire_idle();
+        let mut unlocked = self.unlocked.lock().unwrap();
+        let unlocked = unlocked.as_mut().ok_or(Error::KeyLocked)?;
+        unlocked.last_used = Instant::now();
+        Ok(unlocked.key.clone())
+    }
+}
+
+// 署名サーバーの鍵をロック解除 (password が None ならロック) する
+pub fn request(
+    server: &str,
+    token: Option<&Secret<String>>,
+    password: Option<&Secret<String>>,
+) -> Result<Value> {
+    let url = format!(
+        "{}{KEY_PATH}/{}",
+        server.trim_end_matches('/'),
+        if password.is_some() { "unlock" } else { "lock" }
+    );
+    let mut request = ureq::post(&url);
+    if let Some(token) = token {
+        request = request.header("Authorization", &format!("Bearer {}", token.expose()));
+    }
+
+    let mut response = match password {
+        Some(password) => request.send_json(json!({ "password": password.expose() }))?,
+        None => request.send_empty()?,
+    };
+    response.body_mut().read_json().map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, test_signing_key};
+
+    fn encrypted() -> EncryptedKey {
+        EncryptedKey::encrypt(&test_signing_key(), "password", 1000).unwrap()
+    }
+
+    #[test]
+    fn test_unlocked_key() {
+        let session = KeySession::unlocked(Key::new(test_signing_key()));
+        assert!(!session.is_lockable());
+        assert!(!session.lock());
+        assert_eq!(session.address(), TEST_ADDRESS.parse().unwrap());
+        assert_eq!(
+            session.key().unwrap().signing_key(),
+            Some(&test_signing_key())
+        );
+    }
+
+    #[test]
+    fn test_unlock_and_lock() {
+        let session = KeySession::locked(encrypted(), None).unwrap();
+        assert_eq!(
+            session.verifying_key(),
+            Some(test_signing_key().verifying_key())
+        );
+        assert_eq!(session.address(), TEST_ADDRESS.parse().unwrap());
+        assert!(session.is_locked());
+        assert!(matches!(session.key().unwrap_err(), Error::KeyLocked));
+
+        assert!(matches!(
+            session.unlock("wrong").unwrap_err(),
+            Error::KeyDecryptionFailed
+        ));
+        assert!(session.is_locked());
+
+        session.unlock("password").unwrap();
+        assert_eq!(
+            session.key().unwrap().signing_key(),
+            Some(&test_signing_key())
+        );
+        assert!(session.lock());
+        assert!(!session.lock());
+        assert!(session.key().is_err());
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let session = KeySession::locked(encrypted(), Some(Duration::from_millis(50))).unwrap();
+        session.unlock("password").unwrap();
+
+        // 使うたびにタイムアウトが延びる
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(20));
+            assert!(session.key().is_ok());
+        }
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(session.expire_idle());
+        assert!(session.is_locked());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Result, signer::Key, transaction::TxType};
+use ethereum::EIP1559TransactionMessage;
+use ethereum_types::{H160, H256};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+// 既定で覚えておく署名の数
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+// 署名した raw トランザクションを (鍵のアドレス, 署名用ハッシュ) ごとに覚えておく
+// 署名用ハッシュはトランザクションのすべてのフィールドから求めるため、同じハッシュなら raw トランザクションも同じになる
+// 再送された同じ要求には署名し直さずに同じ raw トランザクションを返す
+// 上限を超えたら古いものから忘れる
+#[derive(Debug)]
+pub struct SignatureCache {
+    capacity: usize,
+    tx_type: TxType,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    raw: HashMap<(H160, H256), Vec<u8>>,
+    // 覚えた順番 (古いものから忘れる)
+    order: VecDeque<(H160, H256)>,
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl SignatureCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tx_type: TxType::default(),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    // 署名するトランザクションの形式 (署名用ハッシュも形式ごとに異なる)
+    pub fn with_tx_type(mut self, tx_type: TxType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    pub fn get(&self, address: H160, hash: &H256) -> Option<Vec<u8>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .raw
+            .get(&(address, *hash))
+            .cloned()
+    }
+
+    pub fn insert(&self, address: H160, hash: H256, raw: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.raw.insert((address, hash), raw.to_vec()).is_some() {
+            return;
+        }
+        entries.order.push_back((address, hash));
+        let Entries { raw, order } = &mut *entries;
+        let excess = order.len().saturating_sub(self.capacity);
+        for oldest in order.drain(..excess) {
+            raw.remove(&oldest);
+        }
+    }
+
+    // 覚えていればそれを返し、なければ署名して覚える
+    pub fn sign(&self, key: &Key, message: EIP1559TransactionMessage) -> Result<Vec<u8>> {
+        let hash = self.tx_type.signing_hash(&message, key.address())?;
+        if let Some(raw) = self.get(key.address(), &hash) {
+            return Ok(raw);
+        }
+        let raw = self.tx_type.sign(message, key)?;
+        self.insert(key.address(), hash, &raw);
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bench::synthetic_message, signer::tests::test_signing_key, transaction};
+
+    #[test]
+    fn test_signature_cache() {
+        let key = Key::new(test_signing_key());
+        let cache = SignatureCache::new(2);
+        let message = synthetic_message(0, 68);
+        let hash = transaction::signing_hash(&message);
+
+        let raw = cache.sign(&key, message.clone()).unwrap();
+        assert_eq!(raw, transaction::sign(message.clone(), &key).unwrap());
+        assert_eq!(cache.get(key.address(), &hash), Some(raw.clone()));
+        // 別の鍵では使わない
+        assert_eq!(cache.get(H160::zero(), &hash), None);
+
+        // 覚えているものは署名し直さずに返す
+        cache.insert(key.address(), hash, b"cached");
+        assert_eq!(cache.sign(&key, message.clone()).unwrap(), b"cached");
+
+        // 上限を超えたら古いものから忘れる
+        cache.insert(key.address(), H256::repeat_byte(1), b"1");
+        cache.insert(key.address(), H256::repeat_byte(2), b"2");
+        assert_eq!(cache.get(key.address(), &hash), None);
+        assert_eq!(
+            cache.get(key.address(), &H256::repeat_byte(2)).unwrap(),
+            b"2"
+        );
+        assert_eq!(cache.sign(&key, message.clone()).unwrap(), raw);
+
+        // Legacy は Legacy の署名用ハッシュで覚える
+        let legacy = SignatureCache::default().with_tx_type(TxType::Legacy);
+        let legacy_raw = legacy.sign(&key, message.clone()).unwrap();
+        assert_eq!(
+            legacy_raw,
+            transaction::sign_legacy(&message, &key).unwrap()
+        );
+        assert_eq!(
+            legacy.get(
+                key.address(),
+                &TxType::Legacy
+                    .signing_hash(&message, key.address())
+                    .unwrap()
+            ),
+            Some(legacy_raw)
+        );
+
+        // 上限が 0 なら覚えない
+        let cache = SignatureCache::new(0);
+        cache.insert(key.address(), hash, &raw);
+        assert_eq!(cache.get(key.address(), &hash), None);
+    }
+}
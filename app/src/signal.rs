@@ -0,0 +1,25 @@
+use crate::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static HANGUP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_hangup(_: libc::c_int) {
+    // シグナルハンドラではフラグを立てるだけにする
+    HANGUP.store(true, Ordering::SeqCst);
+}
+
+// SIGHUP で終了せず、take_hangup で受け取れるようにする
+pub fn handle_hangup() -> Result<()> {
+    let handler = on_hangup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: ハンドラはアトミック変数への書き込みのみで async-signal-safe
+    if unsafe { libc::signal(libc::SIGHUP, handler) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+// 前回の呼び出し以降に SIGHUP を受け取ったか
+pub fn take_hangup() -> bool {
+    HANGUP.swap(false, Ordering::SeqCst)
+}
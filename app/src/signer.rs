@@ -0,0 +1,200 @@
+use crate::{Result, address, plugin::Plugin};
+use ethereum_types::{H160, H256};
+use k256::ecdsa::{RecoveryId, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+
+// Keccak-256 ハッシュを計算
+pub fn keccak256(data: impl AsRef<[u8]>) -> H256 {
+    H256::from_slice(&Keccak256::digest(data.as_ref()))
+}
+
+// 署名値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub r: H256,
+    pub s: H256,
+    // 公開鍵のy座標が奇数かどうか (recovery_id の下位1ビット)
+    pub odd_y_parity: bool,
+}
+
+impl Signature {
+    // r (32バイト) + s (32バイト) + v (1バイト, 27 or 28) の65バイト形式
+    pub fn to_bytes(self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(self.r.as_bytes());
+        bytes[32..64].copy_from_slice(self.s.as_bytes());
+        bytes[64] = 27 + self.odd_y_parity as u8;
+        bytes
+    }
+}
+
+// 32バイトのハッシュに署名するもの (ローカルの秘密鍵か署名のプラグイン)
+pub trait HashSigner {
+    fn sign_hash(&self, hash: &H256) -> Result<Signature>;
+
+    // 署名から復元されるアドレス
+    fn address(&self) -> H160;
+}
+
+impl HashSigner for SigningKey {
+    fn sign_hash(&self, hash: &H256) -> Result<Signature> {
+        sign_hash(self, hash)
+    }
+
+    fn address(&self) -> H160 {
+        address::from_signing_key(self)
+    }
+}
+
+// 署名に使う鍵とそのアドレス
+// 秘密鍵のデコードと公開鍵 (アドレス) の計算は作るときに1度だけ行い、まとめて署名する間は使い回す
+#[derive(Debug, Clone)]
+pub struct Key {
+    backend: Backend,
+    address: H160,
+}
+
+#[derive(Debug, Clone)]
+enum Backend {
+    Local(SigningKey),
+    // 署名は別のプロセスで行う (スレッド間で1つのプロセスを共有する)
+    Plugin(Arc<Plugin>),
+}
+
+impl Key {
+    pub fn new(signing_key: SigningKey) -> Self {
+        let address = address::from_signing_key(&signing_key);
+        Self {
+            backend: Backend::Local(signing_key),
+            address,
+        }
+    }
+
+    pub fn from_plugin(plugin: Plugin) -> Self {
+        Self {
+            address: plugin.address(),
+            backend: Backend::Plugin(Arc::new(plugin)),
+        }
+    }
+
+    // ローカルの秘密鍵 (プラグインで署名する場合はない)
+    pub fn signing_key(&self) -> Option<&SigningKey> {
+        match &self.backend {
+            Backend::Local(signing_key) => Some(signing_key),
+            Backend::Plugin(_) => None,
+        }
+    }
+
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+}
+
+impl HashSigner for Key {
+    fn sign_hash(&self, hash: &H256) -> Result<Signature> {
+        match &self.backend {
+            Backend::Local(signing_key) => sign_hash(signing_key, hash),
+            Backend::Plugin(plugin) => plugin.sign_hash(hash),
+        }
+    }
+
+    fn address(&self) -> H160 {
+        self.address
+    }
+}
+
+// 32バイトのハッシュ値に署名
+pub fn sign_hash(signing_key: &SigningKey, hash: &H256) -> Result<Signature> {
+    // 署名と recovery_id を取得
+    let (signature, recovery_id) = signing_key.sign_prehash_recoverable(hash.as_bytes())?;
+
+    let (r_bytes, s_bytes) = signature.split_bytes();
+    Ok(Signature {
+        r: H256::from_slice(&r_bytes),
+        s: H256::from_slice(&s_bytes),
+        odd_y_parity: (recovery_id.to_byte() & 1) == 1, // recovery_id が奇数かどうかを判定
+    })
+}
+
+// 署名から公開鍵を復元して署名者のアドレスを返す
+pub fn recover(hash: &H256, signature: &Signature) -> Result<H160> {
+    let ecdsa_signature = k256::ecdsa::Signature::from_scalars(signature.r.0, signature.s.0)?;
+    let recovery_id = RecoveryId::new(signature.odd_y_parity, false);
+    let verifying_key =
+        VerifyingKey::recover_from_prehash(hash.as_bytes(), &ecdsa_signature, recovery_id)?;
+    Ok(address::from_verifying_key(&verifying_key))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ethereum_types::H160;
+
+    // Hardhat / Anvil のテスト用アカウント #0
+    pub(crate) const TEST_PRIVATE_KEY: &str =
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    pub(crate) const TEST_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    pub(crate) fn test_signing_key() -> SigningKey {
+        SigningKey::from_slice(&hex::decode(TEST_PRIVATE_KEY).unwrap()).unwrap()
+    }
+
+    // 署名から公開鍵を復元してアドレスを返すヘルパー
+    pub(crate) fn recover_address(hash: &H256, signature: &Signature) -> H160 {
+        recover(hash, signature).unwrap()
+    }
+
+    #[test]
+    fn test_keccak256_empty() {
+        assert_eq!(
+            keccak256([]),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_hash_recoverable() {
+        let hash = keccak256(b"test");
+        let signature = sign_hash(&test_signing_key(), &hash).unwrap();
+
+        assert_eq!(
+            recover_address(&hash, &signature),
+            TEST_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_hash_deterministic() {
+        // RFC 6979 により同じ入力からは同じ署名が得られる
+        let hash = keccak256(b"test");
+        let signing_key = test_signing_key();
+
+        assert_eq!(
+            sign_hash(&signing_key, &hash).unwrap(),
+            sign_hash(&signing_key, &hash).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_signature_to_bytes() {
+        let signature = Signature {
+            r: H256::repeat_byte(0x11),
+            s: H256::repeat_byte(0x22),
+            odd_y_parity: true,
+        };
+
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes[..32], [0x11; 32]);
+        assert_eq!(bytes[32..64], [0x22; 32]);
+        assert_eq!(bytes[64], 28);
+
+        let signature = Signature {
+            odd_y_parity: false,
+            ..signature
+        };
+        assert_eq!(signature.to_bytes()[64], 27);
+    }
+}
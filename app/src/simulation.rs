@@ -0,0 +1,362 @@
+use crate::{
+    Result, abi, address, chains, erc20, error::Error, i18n::t, rpc::RpcClient, signer, units,
+};
+use ethereum::{EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, H256, U256};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+// eth_simulateV1 の traceTransfers で、ネイティブ通貨の移動を Transfer のログとして出すアドレス
+pub const NATIVE_TRANSFER_ADDRESS: H160 = H160([0xee; 20]);
+
+#[derive(Debug, Deserialize)]
+struct Log {
+    address: H160,
+    topics: Vec<H256>,
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallResult {
+    status: U256,
+    #[serde(default)]
+    logs: Vec<Log>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulatedBlock {
+    calls: Vec<CallResult>,
+}
+
+// 残高が変わる資産 (ERC-721 はトークン ID ごと)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Asset {
+    Native,
+    Erc20(H160),
+    Erc721(H160, U256),
+}
+
+// owner の資産ごとの増減 (送った量と受け取った量は相殺する)
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BalanceChanges {
+    pub sent: BTreeMap<Asset, U256>,
+    pub received: BTreeMap<Asset, U256>,
+}
+
+fn transfer_topic() -> H256 {
+    signer::keccak256("Transfer(address,address,uint256)")
+}
+
+fn topic_address(topic: &H256) -> H160 {
+    H160::from_slice(&topic[12..])
+}
+
+// 署名する前のトランザクションを最新のブロックの上で実行し、ログを返す
+// 手数料や nonce は確認しない (validation: false)。実行が失敗 (revert) した場合はエラー
+fn simulate(
+    client: &RpcClient,
+    from: &H160,
+    message: &EIP1559TransactionMessage,
+) -> Result<Vec<Log>> {
+    let mut call = json!({
+        "from": from,
+        "value": message.value,
+        "gas": message.gas_limit,
+        "input": format!("0x{}", hex::encode(&message.input)),
+    });
+    if let TransactionAction::Call(to) = message.action {
+        call["to"] = json!(to);
+    }
+    let blocks: Vec<SimulatedBlock> = client.request(
+        "eth_simulateV1",
+        json!([
+            {
+                "blockStateCalls": [{ "calls": [call] }],
+                "traceTransfers": true,
+                "validation": false,
+            },
+            "latest",
+        ]),
+    )?;
+
+    let Some(result) = blocks.into_iter().flat_map(|block| block.calls).next() else {
+        return Err(Error::SimulationFailed(
+            "the node returned no call result".to_string(),
+        ));
+    };
+    if result.status.is_zero() {
+        let reason = result
+            .error
+            .as_ref()
+            .and_then(|error| error["message"].as_str())
+            .unwrap_or("execution reverted");
+        return Err(Error::SimulationFailed(reason.to_string()));
+    }
+    Ok(result.logs)
+}
+
+// Transfer のログから owner の残高の増減を求める
+// topics が3つなら ERC-20 (data に量)、4つなら ERC-721 (topics の最後にトークン ID)
+fn balance_changes(logs: &[Log], owner: &H160) -> Result<BalanceChanges> {
+    let mut sent: BTreeMap<Asset, U256> = BTreeMap::new();
+    let mut received: BTreeMap<Asset, U256> = BTreeMap::new();
+    for log in logs {
+        if log.topics.first() != Some(&transfer_topic()) {
+            continue;
+        }
+        let (asset, amount) = match log.topics.len() {
+            3 => {
+                let data = hex::decode(log.data.strip_prefix("0x").unwrap_or(&log.data))?;
+                let asset = if log.address == NATIVE_TRANSFER_ADDRESS {
+                    Asset::Native
+                } else {
+                    Asset::Erc20(log.address)
+                };
+                (asset, abi::decode_u256(&data)?)
+            }
+            4 => (
+                Asset::Erc721(log.address, U256::from_big_endian(log.topics[3].as_bytes())),
+                U256::one(),
+            ),
+            _ => continue,
+        };
+
+        let from = topic_address(&log.topics[1]);
+        let to = topic_address(&log.topics[2]);
+        if from == *owner && to != *owner {
+            let total = sent.entry(asset).or_default();
+            *total = total.saturating_add(amount);
+        } else if to == *owner && from != *owner {
+            let total = received.entry(asset).or_default();
+            *total = total.saturating_add(amount);
+        }
+    }
+
+    // 同じ資産を送って受け取った場合は差し引きだけを残す
+    for (asset, amount) in sent.iter_mut() {
+        if let Some(incoming) = received.get_mut(asset) {
+            let net = (*amount).min(*incoming);
+            *amount -= net;
+            *incoming -= net;
+        }
+    }
+    sent.retain(|_, amount| !amount.is_zero());
+    received.retain(|_, amount| !amount.is_zero());
+
+    Ok(BalanceChanges { sent, received })
+}
+
+impl BalanceChanges {
+    // "you will send 100 USDC and receive 0.05 WETH"
+    // format は資産と量の表示 (トークンの symbol や decimals は呼び出し元が取得する)
+    pub fn summary(&self, format: impl Fn(&Asset, U256) -> String) -> String {
+        let list = |changes: &BTreeMap<Asset, U256>| {
+            changes
+                .iter()
+                .map(|(asset, amount)| format(asset, *amount))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match (self.sent.is_empty(), self.received.is_empty()) {
+            (true, true) => t!("simulation.no_changes"),
+            (false, true) => t!("simulation.send", sent = list(&self.sent)),
+            (true, false) => t!("simulation.receive", received = list(&self.received)),
+            (false, false) => t!(
+                "simulation.send_and_receive",
+                sent = list(&self.sent),
+                received = list(&self.received)
+            ),
+        }
+    }
+}
+
+// トランザクションを実行したときの from の残高の増減を RPC のノードで求める
+pub fn balance_changes_of(
+    client: &RpcClient,
+    from: &H160,
+    message: &EIP1559TransactionMessage,
+) -> Result<BalanceChanges> {
+    balance_changes(&simulate(client, from, message)?, from)
+}
+
+// 署名前に表示する報告 ("you will send 100 USDC and receive 0.05 WETH")
+// symbol() や decimals() を取得できないトークンはアドレスと最小単位の量で表示する
+pub fn report(client: &RpcClient, chain_id: u64, changes: &BalanceChanges) -> String {
+    changes.summary(|asset, amount| match asset {
+        Asset::Native => format!(
+            "{} {}",
+            units::format_units(amount, chains::decimals(chain_id)),
+            chains::symbol(chain_id)
+        ),
+        Asset::Erc20(token) => match (
+            erc20::fetch_symbol(client, token),
+            erc20::fetch_decimals(client, token),
+        ) {
+            (Ok(symbol), Ok(decimals)) => {
+                format!("{} {symbol}", units::format_units(amount, decimals))
+            }
+            _ => format!("{amount} of token {}", address::to_checksum(token)),
+        },
+        Asset::Erc721(token, id) => match erc20::fetch_symbol(client, token) {
+            Ok(symbol) => format!("{symbol} #{id}"),
+            Err(_) => format!("NFT #{id} of {}", address::to_checksum(token)),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+    use ethereum::AccessList;
+
+    const OWNER: &str = "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df";
+    const USDC: &str = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238";
+    const WETH: &str = "0xfff9976782d46cc05630d1f6ebab18b2324d6b14";
+
+    fn topic(address: &str) -> String {
+        format!("0x{:0>64}", address.trim_start_matches("0x"))
+    }
+
+    fn transfer_log(token: &str, from: &str, to: &str, amount: u64) -> Value {
+        json!({
+            "address": token,
+            "topics": [format!("{:?}", transfer_topic()), topic(from), topic(to)],
+            "data": format!("0x{}", hex::encode(abi::encode_u256(U256::from(amount)))),
+        })
+    }
+
+    fn message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::zero(),
+            gas_limit: U256::from(200_000),
+            action: TransactionAction::Call(USDC.parse().unwrap()),
+            value: U256::from(1_000_000_000_000_000u64),
+            input: vec![0xab],
+            access_list: AccessList::default(),
+        }
+    }
+
+    fn simulated(logs: Vec<Value>) -> (u16, String) {
+        MockServer::rpc_result(json!([{
+            "calls": [{ "status": "0x1", "returnData": "0x", "gasUsed": "0x5208", "logs": logs }],
+        }]))
+    }
+
+    fn format(asset: &Asset, amount: U256) -> String {
+        match asset {
+            Asset::Native => format!("{amount} wei"),
+            Asset::Erc20(token) => format!("{amount} {token:?}"),
+            Asset::Erc721(token, id) => format!("{token:?} #{id}"),
+        }
+    }
+
+    #[test]
+    fn test_balance_changes() {
+        let pool = "0x00000000000000000000000000000000000000aa";
+        let native = format!("{NATIVE_TRANSFER_ADDRESS:?}");
+        let server = MockServer::start(vec![simulated(vec![
+            transfer_log(&native, OWNER, USDC, 1_000),
+            transfer_log(USDC, OWNER, pool, 100_000_000),
+            transfer_log(WETH, pool, OWNER, 50),
+            // 送って戻ってきた分は相殺する
+            transfer_log(USDC, pool, OWNER, 1_000_000),
+            // owner に関係しない移動
+            transfer_log(WETH, pool, USDC, 7),
+        ])]);
+        let client = RpcClient::new(&server.url);
+        let owner: H160 = OWNER.parse().unwrap();
+        let changes = balance_changes_of(&client, &owner, &message()).unwrap();
+
+        assert_eq!(
+            changes.summary(format),
+            format!("you will send 1000 wei, 99000000 {USDC} and receive 50 {WETH}")
+        );
+
+        let request = &server.json_requests()[0];
+        assert_eq!(request["method"], "eth_simulateV1");
+        let call = &request["params"][0]["blockStateCalls"][0]["calls"][0];
+        assert_eq!(call["from"], OWNER);
+        assert_eq!(call["to"], USDC);
+        assert_eq!(call["input"], "0xab");
+        assert_eq!(request["params"][0]["traceTransfers"], true);
+    }
+
+    #[test]
+    fn test_erc721_and_empty() {
+        let mut log = transfer_log(WETH, OWNER, USDC, 0);
+        log["topics"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!(format!("0x{:064x}", 42)));
+        log["data"] = json!("0x");
+        let owner: H160 = OWNER.parse().unwrap();
+        let logs: Vec<Log> = serde_json::from_value(json!([log])).unwrap();
+        assert_eq!(
+            balance_changes(&logs, &owner).unwrap().summary(format),
+            format!("you will send {WETH} #42")
+        );
+        assert_eq!(
+            balance_changes(&[], &owner).unwrap().summary(format),
+            "no balance changes besides the fee"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let string = |value: &str| {
+            let mut data = abi::encode_u256(U256::from(32)).to_vec();
+            data.extend(abi::encode_bytes(value.as_bytes()));
+            MockServer::rpc_result(json!(format!("0x{}", hex::encode(data))))
+        };
+        let uint = |value: u64| {
+            MockServer::rpc_result(json!(format!(
+                "0x{}",
+                hex::encode(abi::encode_u256(U256::from(value)))
+            )))
+        };
+        let server = MockServer::start(vec![string("USDC"), uint(6), string("WETH"), uint(18)]);
+        let client = RpcClient::new(&server.url);
+        let changes = BalanceChanges {
+            sent: BTreeMap::from([
+                (Asset::Native, U256::from(10u64.pow(15))),
+                (Asset::Erc20(USDC.parse().unwrap()), U256::from(100_000_000)),
+            ]),
+            received: BTreeMap::from([(
+                Asset::Erc20(WETH.parse().unwrap()),
+                U256::from(5 * 10u64.pow(16)),
+            )]),
+        };
+        assert_eq!(
+            report(&client, 11155111, &changes),
+            "you will send 0.001 ETH, 100 USDC and receive 0.05 WETH"
+        );
+        server.json_requests();
+    }
+
+    #[test]
+    fn test_reverted() {
+        let server = MockServer::start(vec![MockServer::rpc_result(json!([{
+            "calls": [{
+                "status": "0x0",
+                "returnData": "0x",
+                "gasUsed": "0x5208",
+                "logs": [],
+                "error": { "code": 3, "message": "execution reverted: insufficient balance" },
+            }],
+        }]))]);
+        let client = RpcClient::new(&server.url);
+        let error = balance_changes_of(&client, &OWNER.parse().unwrap(), &message()).unwrap_err();
+        assert!(
+            error.to_string().contains("insufficient balance"),
+            "{error}"
+        );
+        server.json_requests();
+    }
+}
@@ -0,0 +1,582 @@
+use crate::{
+    Result,
+    error::Error,
+    keystore::{Kdf, cipher_key, random_nonce},
+    secret::Secret,
+};
+use ring::aead::{Aad, LessSafeKey, NONCE_LEN, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    os::{fd::AsRawFd, unix::fs::MetadataExt},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+// ジャーナルや nonce ストアを暗号化する鍵
+#[derive(Debug, Clone)]
+pub enum StateKey {
+    // オペレーターのパスフレーズ (ファイルごとのソルトで PBKDF2 により鍵を導出する)
+    Passphrase {
+        passphrase: Secret<String>,
+        iterations: u32,
+    },
+    // KMS などで復号したデータキー (32バイト)
+    DataKey(Secret<[u8; 32]>),
+}
+
+// 暗号化したファイルの1行目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Header {
+    encrypted: Encryption,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Encryption {
+    version: u32,
+    // パスフレーズの場合のみ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<Kdf>,
+    // ファイルの用途 (journal や nonces)。用途を書く前に作ったファイルにはない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+}
+
+// ファイルをどこまで読んだか (追記された分だけ読むため)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    inode: u64,
+    len: u64,
+}
+
+// 別のプロセスとの排他 (flock によるアドバイザリロック、ドロップで解除する)
+#[derive(Debug)]
+pub struct FileLock(File);
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // SAFETY: 開いているファイルのディスクリプタ
+        unsafe { libc::flock(self.0.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
+
+// ローカルの状態を保存するファイル
+// 鍵があれば、1行目に鍵の導出方法を書いたヘッダー、以降はレコードごとに AES-256-GCM で暗号化した行 (0x + nonce + 暗号文) を書く
+// 各行は用途を含むヘッダーを関連データにして暗号化し、用途の違うファイルの行と入れ替えられないようにする
+// パスフレーズではファイルごとにソルトが違うため、同じ用途のファイルどうしでも入れ替えられない
+// STATE_KEY では同じ用途のファイルのヘッダーは同じになるため、それらの間の入れ替えは防げない
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    key: Option<StateKey>,
+    role: Option<&'static str>,
+}
+
+// パスフレーズから導出した鍵 (導出は遅いため、同じパスフレーズとソルトはプロセス内で1度だけ導出する)
+// パスフレーズそのものではなく SHA-256 ハッシュで見分ける
+type DerivedKey = (Kdf, [u8; 32], [u8; 32]);
+static DERIVED_KEYS: Mutex<Vec<DerivedKey>> = Mutex::new(Vec::new());
+
+fn derive(passphrase: &str, kdf: &Kdf) -> Result<[u8; 32]> {
+    let fingerprint: [u8; 32] = Sha256::digest(passphrase.as_bytes()).into();
+    let mut derived = DERIVED_KEYS.lock().unwrap();
+    if let Some((_, _, key)) = derived
+        .iter()
+        .find(|(cached, cached_fingerprint, _)| cached == kdf && *cached_fingerprint == fingerprint)
+    {
+        return Ok(*key);
+    }
+    let key = kdf.derive(passphrase)?;
+    derived.push((kdf.clone(), fingerprint, key));
+    Ok(key)
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>, key: Option<StateKey>) -> Self {
+        Self {
+            path: path.into(),
+            key,
+            role: None,
+        }
+    }
+
+    // 暗号化したファイルのヘッダーに用途を書き、別の用途のファイルとして読めないようにする
+    pub fn with_role(mut self, role: &'static str) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 読み書きの間、同じファイルを使う別のプロセス (同時に実行した CLI など) を待たせる
+    // 本体は置き換えて書き込むため、隣の .lock ファイルをロックする
+    pub fn lock(&self) -> Result<FileLock> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        loop {
+            // SAFETY: 開いているファイルのディスクリプタ
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                return Ok(FileLock(file));
+            }
+            let error = std::io::Error::last_os_error();
+            if error.kind() != std::io::ErrorKind::Interrupted {
+                return Err(error.into());
+            }
+        }
+    }
+
+    fn invalid(&self, message: &str) -> Error {
+        Error::InvalidStateFile(format!("{}: {message}", self.path.display()))
+    }
+
+    fn new_header(&self) -> Result<Option<Header>> {
+        let kdf = match &self.key {
+            None => return Ok(None),
+            Some(StateKey::Passphrase { iterations, .. }) => Some(Kdf::generate(*iterations)?),
+            Some(StateKey::DataKey(_)) => None,
+        };
+        Ok(Some(Header {
+            encrypted: Encryption {
+                version: 1,
+                kdf,
+                role: self.role.map(str::to_string),
+            },
+        }))
+    }
+
+    // 1行目がヘッダーならそれを返す
+    fn read_header(&self) -> Result<Option<Header>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        Ok(serde_json::from_str(&line).ok())
+    }
+
+    fn cipher(&self, header: &Header) -> Result<LessSafeKey> {
+        if header.encrypted.version != 1 {
+            return Err(self.invalid(&format!(
+                "unsupported encryption version {}",
+                header.encrypted.version
+            )));
+        }
+        if let (Some(role), Some(expected)) = (&header.encrypted.role, self.role)
+            && role != expected
+        {
+            return Err(self.invalid(&format!("is a {role} file, not a {expected} file")));
+        }
+        match (&self.key, &header.encrypted.kdf) {
+            (Some(StateKey::DataKey(key)), None) => cipher_key(key.expose()),
+            (Some(StateKey::Passphrase { passphrase, .. }), Some(kdf)) => {
+                cipher_key(&derive(passphrase.expose(), kdf)?)
+            }
+            (None, _) => Err(self.invalid("is encrypted; set STATE_PASSPHRASE or STATE_KEY")),
+            (Some(_), Some(_)) => Err(self.invalid(
+                "was encrypted with a passphrase; set STATE_PASSPHRASE instead of STATE_KEY",
+            )),
+            (Some(_), None) => Err(self.invalid(
+                "was encrypted with a data key; set STATE_KEY instead of STATE_PASSPHRASE",
+            )),
+        }
+    }
+
+    fn seal(&self, header: &Header, record: &str) -> Result<String> {
+        let nonce = random_nonce()?;
+        let mut ciphertext = record.as_bytes().to_vec();
+        self.cipher(header)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(serde_json::to_vec(header)?),
+                &mut ciphertext,
+            )
+            .map_err(|_| self.invalid("failed to encrypt"))?;
+        Ok(format!(
+            "0x{}{}",
+            hex::encode(nonce),
+            hex::encode(ciphertext)
+        ))
+    }
+
+    fn open(&self, cipher: &LessSafeKey, header: &Header, line: &str) -> Result<String> {
+        let decryption_failed = || Error::StateDecryptionFailed(self.path.display().to_string());
+        let sealed = hex::decode(line.strip_prefix("0x").unwrap_or(line))
+            .map_err(|_| self.invalid("invalid encrypted record"))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(self.invalid("invalid encrypted record"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let mut ciphertext = ciphertext.to_vec();
+        let plaintext = cipher
+            .open_in_place(
+                Nonce::try_assume_unique_for_key(nonce).map_err(|_| decryption_failed())?,
+                Aad::from(serde_json::to_vec(header)?),
+                &mut ciphertext,
+            )
+            .map_err(|_| decryption_failed())?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| decryption_failed())
+    }
+
+    // 復号した内容 (ファイルがなければ空)
+    // 暗号化したファイルはレコードを改行でつなげて返す
+    pub fn read(&self) -> Result<String> {
+        Ok(self
+            .read_from(Position::default())?
+            .map(|(records, _)| records)
+            .unwrap_or_default())
+    }
+
+    // from より後に追記されたレコードと、読み終えた位置
+    // ファイルが置き換えられたか短くなっていれば (from から続けて読めなければ) None
+    pub fn read_from(&self, from: Position) -> Result<Option<(String, Position)>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok((from.len == 0).then(|| (String::new(), Position::default())));
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let metadata = file.metadata()?;
+        if from.len > 0 && (metadata.ino() != from.inode || metadata.len() < from.len) {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::Start(from.len))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let position = Position {
+            inode: metadata.ino(),
+            len: from.len + content.len() as u64,
+        };
+
+        let Some(header) = self.read_header()? else {
+            if self.key.is_some() && !content.trim().is_empty() {
+                return Err(self.invalid(
+                    "is not encrypted; migrate it before setting STATE_PASSPHRASE or STATE_KEY",
+                ));
+            }
+            return Ok(Some((content, position)));
+        };
+
+        let cipher = self.cipher(&header)?;
+        let mut lines = content.lines();
+        if from.len == 0 {
+            lines.next();
+        }
+        let mut records = String::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            records += &self.open(&cipher, &header, line)?;
+            records.push('\n');
+        }
+        Ok(Some((records, position)))
+    }
+
+    // 1件のレコード (改行を含まない) を追記する
+    pub fn append(&self, record: &str) -> Result<()> {
+        let mut line = String::new();
+        if self.key.is_some() {
+            let header = match self.read_header()? {
+                Some(header) => header,
+                None => {
+                    if std::fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() > 0) {
+                        return Err(self.invalid(
+                            "is not encrypted; migrate it before setting STATE_PASSPHRASE or STATE_KEY",
+                        ));
+                    }
+                    let header = self.new_header()?.expect("key is set");
+                    line += &(serde_json::to_string(&header)? + "\n");
+                    header
+                }
+            };
+            line += &self.seal(&header, record)?;
+        } else {
+            if self.read_header()?.is_some() {
+                return Err(self.invalid("is encrypted; set STATE_PASSPHRASE or STATE_KEY"));
+            }
+            line += record;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(format!("{line}\n").as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    // 内容全体を書き換える
+    // 書き込み途中で落ちても壊れないよう、一時ファイルに書いてから置き換える
+    pub fn write(&self, content: &str) -> Result<()> {
+        let content = match self.key {
+            None => content.to_string(),
+            Some(_) => {
+                // ソルトを変えると鍵を導出し直すことになるため、既存のヘッダーを使う
+                let header = match self.read_header()? {
+                    Some(header) => header,
+                    None => self.new_header()?.expect("key is set"),
+                };
+                // 読むときにレコードの後に改行を付けるため、最後の改行は含めない
+                let record = content.strip_suffix('\n').unwrap_or(content);
+                format!(
+                    "{}\n{}\n",
+                    serde_json::to_string(&header)?,
+                    self.seal(&header, record)?
+                )
+            }
+        };
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(passphrase: &str) -> Option<StateKey> {
+        Some(StateKey::Passphrase {
+            passphrase: Secret::new(passphrase.to_string()),
+            iterations: 1000,
+        })
+    }
+
+    #[test]
+    fn test_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = StateFile::new(dir.path().join("state"), None);
+        assert_eq!(file.read().unwrap(), "");
+
+        file.append(r#"{"a":1}"#).unwrap();
+        file.append(r#"{"a":2}"#).unwrap();
+        assert_eq!(file.read().unwrap(), "{\"a\":1}\n{\"a\":2}\n");
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            file.read().unwrap()
+        );
+
+        file.write("{\n  \"b\": 1\n}\n").unwrap();
+        assert_eq!(file.read().unwrap(), "{\n  \"b\": 1\n}\n");
+    }
+
+    #[test]
+    fn test_read_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        for key in [None, passphrase("correct horse")] {
+            let _ = std::fs::remove_file(&path);
+            let file = StateFile::new(&path, key);
+            let (records, start) = file.read_from(Position::default()).unwrap().unwrap();
+            assert_eq!(records, "");
+            // ファイルがなければ続きからは読めない
+            assert!(
+                file.read_from(Position { inode: 1, len: 1 })
+                    .unwrap()
+                    .is_none()
+            );
+
+            file.append(r#"{"a":1}"#).unwrap();
+            let (records, position) = file.read_from(start).unwrap().unwrap();
+            assert_eq!(records, "{\"a\":1}\n");
+
+            // 追記された分だけ読む
+            file.append(r#"{"a":2}"#).unwrap();
+            let (records, next) = file.read_from(position).unwrap().unwrap();
+            assert_eq!(records, "{\"a\":2}\n");
+            assert_eq!(file.read_from(next).unwrap().unwrap().0, "");
+
+            // 置き換えられたファイルは最初から読み直す
+            file.write("{\"b\":1}\n{\"b\":2}\n{\"b\":3}\n").unwrap();
+            assert!(file.read_from(next).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_derived_key_is_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        StateFile::new(&path, passphrase("shared"))
+            .append("{}")
+            .unwrap();
+        // 同じファイルを別のインスタンスで開いても、鍵は導出し直さない
+        for _ in 0..3 {
+            assert_eq!(
+                StateFile::new(&path, passphrase("shared")).read().unwrap(),
+                "{}\n"
+            );
+        }
+        let kdf = StateFile::new(&path, None)
+            .read_header()
+            .unwrap()
+            .unwrap()
+            .encrypted
+            .kdf
+            .unwrap();
+        let derived = DERIVED_KEYS.lock().unwrap();
+        assert_eq!(
+            derived.iter().filter(|(cached, ..)| *cached == kdf).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        for key in [
+            passphrase("correct horse"),
+            Some(StateKey::DataKey(Secret::new([7; 32]))),
+        ] {
+            let _ = std::fs::remove_file(&path);
+            let file = StateFile::new(&path, key.clone());
+            file.append(r#"{"to":"0x742d"}"#).unwrap();
+            file.append(r#"{"to":"0x1111"}"#).unwrap();
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            assert!(content.starts_with(r#"{"encrypted":{"version":1"#));
+            assert!(!content.contains("0x742d"));
+            assert_eq!(content.lines().count(), 3);
+
+            // 別の実行でも復号できる
+            let file = StateFile::new(&path, key);
+            assert_eq!(
+                file.read().unwrap(),
+                "{\"to\":\"0x742d\"}\n{\"to\":\"0x1111\"}\n"
+            );
+            file.write("{\n  \"b\": 1\n}\n").unwrap();
+            assert_eq!(file.read().unwrap(), "{\n  \"b\": 1\n}\n");
+            assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_role() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = Some(StateKey::DataKey(Secret::new([7; 32])));
+        let journal_path = dir.path().join("journal");
+        let nonces_path = dir.path().join("nonces");
+        let journal = StateFile::new(&journal_path, key.clone()).with_role("journal");
+        let nonces = StateFile::new(&nonces_path, key.clone()).with_role("nonces");
+        journal.append(r#"{"journal":1}"#).unwrap();
+        nonces.append(r#"{"nonces":1}"#).unwrap();
+        assert!(
+            std::fs::read_to_string(&journal_path)
+                .unwrap()
+                .starts_with(r#"{"encrypted":{"version":1,"role":"journal"}}"#)
+        );
+
+        // 同じ鍵でも、用途の違うファイルに移した行は復号できない
+        let line = std::fs::read_to_string(&journal_path)
+            .unwrap()
+            .lines()
+            .nth(1)
+            .unwrap()
+            .to_string();
+        let mut content = std::fs::read_to_string(&nonces_path).unwrap();
+        content += &(line + "\n");
+        std::fs::write(&nonces_path, content).unwrap();
+        assert!(matches!(
+            nonces.read().unwrap_err(),
+            Error::StateDecryptionFailed(_)
+        ));
+
+        // ファイルごと入れ替えても読まない
+        assert!(matches!(
+            StateFile::new(&journal_path, key.clone())
+                .with_role("nonces")
+                .read()
+                .unwrap_err(),
+            Error::InvalidStateFile(_)
+        ));
+
+        // 用途を書く前のファイルは今までどおり読める
+        let legacy_path = dir.path().join("legacy");
+        StateFile::new(&legacy_path, key.clone())
+            .append("{}")
+            .unwrap();
+        assert_eq!(
+            StateFile::new(&legacy_path, key)
+                .with_role("journal")
+                .read()
+                .unwrap(),
+            "{}\n"
+        );
+    }
+
+    #[test]
+    fn test_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        let lock = StateFile::new(&path, None).lock().unwrap();
+
+        // 別に開いたファイル (別のプロセスと同じ) はロックが解除されるまで待つ
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let _lock = StateFile::new(&path, None).lock().unwrap();
+                sender.send(()).unwrap();
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(receiver.try_recv().is_err());
+
+        drop(lock);
+        handle.join().unwrap();
+        assert!(receiver.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        StateFile::new(&path, passphrase("correct horse"))
+            .append("{}")
+            .unwrap();
+
+        assert!(matches!(
+            StateFile::new(&path, passphrase("wrong"))
+                .read()
+                .unwrap_err(),
+            Error::StateDecryptionFailed(_)
+        ));
+        assert!(matches!(
+            StateFile::new(&path, Some(StateKey::DataKey(Secret::new([7; 32]))))
+                .read()
+                .unwrap_err(),
+            Error::InvalidStateFile(_)
+        ));
+        // 鍵がなければ読み書きできない
+        assert!(matches!(
+            StateFile::new(&path, None).read().unwrap_err(),
+            Error::InvalidStateFile(_)
+        ));
+        assert!(StateFile::new(&path, None).append("{}").is_err());
+
+        // 暗号化していないファイルに鍵を設定した
+        let path = dir.path().join("plain");
+        StateFile::new(&path, None).append("{}").unwrap();
+        let file = StateFile::new(&path, passphrase("correct horse"));
+        assert!(matches!(
+            file.read().unwrap_err(),
+            Error::InvalidStateFile(_)
+        ));
+        assert!(file.append("{}").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{Result, error::Error};
+use rustls::{
+    RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::WebPkiClientVerifier,
+};
+use std::{net::TcpStream, path::Path, sync::Arc};
+
+// TLS で包んだ TCP 接続 (ハンドシェイクは最初の読み書きで行われる)
+pub type TlsStream = StreamOwned<ServerConnection, TcpStream>;
+
+fn invalid(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::InvalidTlsConfig(format!("{}: {error}", path.display()))
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .map_err(|error| invalid(path, error))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|error| invalid(path, error))?;
+    if certificates.is_empty() {
+        return Err(invalid(path, "no certificates found"));
+    }
+
+    Ok(certificates)
+}
+
+// サーバー証明書 (チェーン) と秘密鍵から TLS の設定を作成
+// client_ca を指定すると、その CA が発行したクライアント証明書を必須にする (mTLS)
+pub fn server_config(
+    certificate: &Path,
+    private_key: &Path,
+    client_ca: Option<&Path>,
+) -> Result<Arc<ServerConfig>> {
+    let provider = Arc::new(ring::default_provider());
+    let certificates = read_certificates(certificate)?;
+    let private_key =
+        PrivateKeyDer::from_pem_file(private_key).map_err(|error| invalid(private_key, error))?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|error| Error::InvalidTlsConfig(error.to_string()))?;
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for ca in read_certificates(client_ca)? {
+                roots.add(ca).map_err(|error| invalid(client_ca, error))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|error| invalid(client_ca, error))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let config = builder
+        .with_single_cert(certificates, private_key)
+        .map_err(|error| invalid(certificate, error))?;
+
+    Ok(Arc::new(config))
+}
+
+pub fn accept(config: &Arc<ServerConfig>, stream: TcpStream) -> std::io::Result<TlsStream> {
+    let connection = ServerConnection::new(Arc::clone(config)).map_err(std::io::Error::other)?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rustls::{ClientConfig, ClientConnection, pki_types::ServerName};
+    use std::{
+        io::{Read, Write},
+        path::PathBuf,
+    };
+
+    // testdata/tls の証明書 (CA が発行した localhost 用のサーバー証明書とクライアント証明書)
+    pub(crate) fn testdata(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/tls")
+            .join(name)
+    }
+
+    // テスト用の TLS クライアント
+    // client_auth を指定するとクライアント証明書を提示する
+    pub(crate) fn connect(
+        address: std::net::SocketAddr,
+        client_auth: bool,
+    ) -> StreamOwned<ClientConnection, TcpStream> {
+        let provider = Arc::new(ring::default_provider());
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_file(testdata("ca.pem")).unwrap())
+            .unwrap();
+
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = if client_auth {
+            builder
+                .with_client_auth_cert(
+                    read_certificates(&testdata("client.pem")).unwrap(),
+                    PrivateKeyDer::from_pem_file(testdata("client.key")).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let connection =
+            ClientConnection::new(Arc::new(config), ServerName::try_from("localhost").unwrap())
+                .unwrap();
+        StreamOwned::new(connection, TcpStream::connect(address).unwrap())
+    }
+
+    #[test]
+    fn test_server_config() {
+        assert!(server_config(&testdata("server.pem"), &testdata("server.key"), None).is_ok());
+        assert!(
+            server_config(
+                &testdata("server.pem"),
+                &testdata("server.key"),
+                Some(&testdata("ca.pem"))
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_server_config_errors() {
+        // 証明書と鍵が一致しない
+        assert!(matches!(
+            server_config(&testdata("server.pem"), &testdata("client.key"), None),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+        // 証明書のファイルに鍵を指定
+        assert!(server_config(&testdata("server.key"), &testdata("server.key"), None).is_err());
+        assert!(server_config(&testdata("missing.pem"), &testdata("server.key"), None).is_err());
+        assert!(
+            server_config(
+                &testdata("server.pem"),
+                &testdata("server.key"),
+                Some(&testdata("client.key"))
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_accept_handshake() {
+        let config = server_config(&testdata("server.pem"), &testdata("server.key"), None).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = accept(&config, stream).unwrap();
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+            stream.flush().unwrap();
+        });
+
+        let mut client = connect(address, false);
+        client.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{
+    Result,
+    journal::{Entry, Journal, Status},
+    rpc::RpcClient,
+};
+use ethereum_types::{H160, U256};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+
+// 後の nonce のトランザクションを止めている nonce の抜け
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub from: H160,
+    // ジャーナルにもノードにもない nonce
+    pub missing: Vec<U256>,
+    // 抜けのために取り込まれない nonce
+    pub blocked: Vec<U256>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    // 状態が変わったエントリと変わる前の状態
+    pub changed: Vec<(Status, Entry)>,
+    // まだ取り込まれていないエントリ
+    pub pending: Vec<Entry>,
+    pub gaps: Vec<Gap>,
+}
+
+// 送信者ごとの取り込み済みの nonce (何度も問い合わせないよう覚えておく)
+struct LatestNonces<'a> {
+    client: &'a RpcClient,
+    nonces: HashMap<H160, U256>,
+}
+
+impl LatestNonces<'_> {
+    fn get(&mut self, from: &H160) -> Result<U256> {
+        if let Some(nonce) = self.nonces.get(from) {
+            return Ok(*nonce);
+        }
+        let nonce: U256 = self
+            .client
+            .request("eth_getTransactionCount", json!([from, "latest"]))?;
+        self.nonces.insert(*from, nonce);
+        Ok(nonce)
+    }
+}
+
+// ノードに問い合わせて取り込み待ちのトランザクションの状態を決める
+fn current_status(client: &RpcClient, latest: &mut LatestNonces, entry: &Entry) -> Result<Status> {
+    if let Some(receipt) =
+        client.request_optional::<Value>("eth_getTransactionReceipt", json!([entry.hash]))?
+    {
+        return Ok(if receipt["status"] == "0x0" {
+            Status::Failed
+        } else {
+            Status::Confirmed
+        });
+    }
+    // ノードの mempool にある
+    if client
+        .request_optional::<Value>("eth_getTransactionByHash", json!([entry.hash]))?
+        .is_some()
+    {
+        return Ok(Status::Broadcast);
+    }
+
+    Ok(if latest.get(&entry.from)? > entry.nonce {
+        Status::Replaced
+    } else if entry.status == Status::Broadcast {
+        Status::Dropped
+    } else {
+        // CLI で署名して、まだ送信していない
+        Status::Signed
+    })
+}
+
+// ジャーナルの取り込み待ちのトランザクションを RPC で確認して状態を更新し、nonce の抜けを探す
+pub fn check(journal: &Journal, client: &RpcClient, chain_id: u64) -> Result<Report> {
+    let mut latest = LatestNonces {
+        client,
+        nonces: HashMap::new(),
+    };
+    let mut report = Report::default();
+
+    for entry in journal
+        .entries()?
+        .into_iter()
+        .filter(|entry| entry.chain_id == chain_id && entry.status.is_pending())
+    {
+        let status = current_status(client, &mut latest, &entry)?;
+        let entry = match journal.set_status(&entry.hash, status)? {
+            Some(updated) if updated.status != entry.status => {
+                report.changed.push((entry.status, updated.clone()));
+                updated
+            }
+            _ => entry,
+        };
+        if entry.status.is_pending() {
+            report.pending.push(entry);
+        }
+    }
+
+    let mut pending_nonces: BTreeMap<H160, Vec<U256>> = BTreeMap::new();
+    for entry in &report.pending {
+        pending_nonces
+            .entry(entry.from)
+            .or_default()
+            .push(entry.nonce);
+    }
+    for (from, mut nonces) in pending_nonces {
+        nonces.sort();
+        nonces.dedup();
+        let mut next = latest.get(&from)?;
+        let mut missing = Vec::new();
+        for nonce in &nonces {
+            while next < *nonce {
+                missing.push(next);
+                next += U256::one();
+            }
+            next = *nonce + 1;
+        }
+        if let Some(first) = missing.first() {
+            report.gaps.push(Gap {
+                from,
+                blocked: nonces.into_iter().filter(|nonce| nonce > first).collect(),
+                missing,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc::tests::MockServer, signer::tests::test_signing_key, transaction};
+    use ethereum::{AccessList, EIP1559TransactionMessage, TransactionAction};
+
+    fn entry(nonce: u64, status: Status) -> Entry {
+        let message = EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(nonce),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(H160::zero()),
+            value: U256::one(),
+            input: vec![],
+            access_list: AccessList::default(),
+        };
+        let raw = transaction::sign(message.clone(), &test_signing_key()).unwrap();
+        let from = crate::address::from_signing_key(&test_signing_key());
+        Entry {
+            status,
+            ..Entry::new(&transaction::TxType::Eip1559, &message, from, &raw, "cli")
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"), None);
+        // 取り込み済みの nonce は 2 まで
+        let entries = [
+            entry(1, Status::Broadcast),
+            entry(2, Status::Broadcast),
+            entry(3, Status::Broadcast),
+            entry(4, Status::Broadcast),
+            entry(6, Status::Signed),
+            entry(0, Status::Confirmed),
+        ];
+        for entry in &entries {
+            journal.record(entry).unwrap();
+        }
+
+        let null = MockServer::rpc_result(Value::Null);
+        let rpc = MockServer::start(vec![
+            // nonce 1: 取り込まれた
+            MockServer::rpc_result(json!({ "status": "0x1", "blockNumber": "0x10" })),
+            // nonce 2: 別のトランザクションに置き換えられた
+            null.clone(),
+            null.clone(),
+            MockServer::rpc_result(json!("0x3")),
+            // nonce 3: mempool にある
+            null.clone(),
+            MockServer::rpc_result(json!({ "hash": entries[2].hash })),
+            // nonce 4: mempool から消えた
+            null.clone(),
+            null.clone(),
+            // nonce 6: まだ送信していない
+            null.clone(),
+            null,
+        ]);
+
+        let report = check(&journal, &RpcClient::new(&rpc.url), 11155111).unwrap();
+        let changed: Vec<_> = report
+            .changed
+            .iter()
+            .map(|(before, entry)| (entry.nonce.as_u64(), *before, entry.status))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (1, Status::Broadcast, Status::Confirmed),
+                (2, Status::Broadcast, Status::Replaced),
+                (4, Status::Broadcast, Status::Dropped),
+            ]
+        );
+        let pending: Vec<_> = report
+            .pending
+            .iter()
+            .map(|entry| entry.nonce.as_u64())
+            .collect();
+        assert_eq!(pending, vec![3, 6]);
+        // nonce 4 と 5 がないため 6 が取り込まれない
+        assert_eq!(
+            report.gaps,
+            vec![Gap {
+                from: entries[0].from,
+                missing: vec![U256::from(4), U256::from(5)],
+                blocked: vec![U256::from(6)],
+            }]
+        );
+
+        assert_eq!(
+            journal.get(&entries[0].hash).unwrap().unwrap().status,
+            Status::Confirmed
+        );
+        let requests = rpc.json_requests();
+        assert_eq!(requests.len(), 10);
+        // 取り込み済みの nonce は1度だけ問い合わせる
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| request["method"] == "eth_getTransactionCount")
+                .count(),
+            1
+        );
+    }
+}
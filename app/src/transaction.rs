@@ -0,0 +1,758 @@
+use crate::{
+    Result, celo,
+    config::Config,
+    error::Error,
+    params::Params,
+    signer::{self, HashSigner, keccak256},
+    zksync::{self, Paymaster},
+};
+#[cfg(not(feature = "alloy"))]
+use bytes::{Bytes, BytesMut};
+use ethereum::{
+    AccessList, EIP1559Transaction, EIP1559TransactionMessage, LegacyTransaction,
+    LegacyTransactionMessage, TransactionAction, TransactionSignature,
+};
+use ethereum_types::{H160, H256, U256};
+#[cfg(not(feature = "alloy"))]
+use rlp::{Encodable, RlpStream};
+use serde_json::{Value, json};
+
+// 署名値 (odd_y_parity, r, s) を含まないトランザクションデータを作成
+// nonce は params で省略された場合に割り当てたもの
+// 手数料は params で指定されていればそれを使う (chainId は読み込んだときに確認している)
+pub fn build_message(config: &Config, nonce: U256, params: &Params) -> EIP1559TransactionMessage {
+    EIP1559TransactionMessage {
+        chain_id: config.chain_id,
+        nonce,
+        max_priority_fee_per_gas: params
+            .max_priority_fee_per_gas
+            .unwrap_or(config.max_priority_fee_per_gas),
+        max_fee_per_gas: params.max_fee_per_gas.unwrap_or(config.max_fee_per_gas),
+        gas_limit: params.gas_limit,
+        action: TransactionAction::Call(params.to_address),
+        value: params.value,
+        input: params.input.clone(),
+        access_list: AccessList::default(),
+    }
+}
+
+// コントラクト作成 (to なし) のトランザクションデータを作成
+pub fn build_create_message(
+    config: &Config,
+    nonce: U256,
+    value: U256,
+    gas_limit: U256,
+    init_code: Vec<u8>,
+) -> EIP1559TransactionMessage {
+    EIP1559TransactionMessage {
+        chain_id: config.chain_id,
+        nonce,
+        max_priority_fee_per_gas: config.max_priority_fee_per_gas,
+        max_fee_per_gas: config.max_fee_per_gas,
+        gas_limit,
+        action: TransactionAction::Create,
+        value,
+        input: init_code,
+        access_list: AccessList::default(),
+    }
+}
+
+// nonce の抜けを埋める、送信者自身への value 0 の送金
+pub fn build_filler_message(
+    config: &Config,
+    nonce: U256,
+    sender: H160,
+) -> EIP1559TransactionMessage {
+    EIP1559TransactionMessage {
+        chain_id: config.chain_id,
+        nonce,
+        max_priority_fee_per_gas: config.max_priority_fee_per_gas,
+        max_fee_per_gas: config.max_fee_per_gas,
+        gas_limit: U256::from(21000),
+        action: TransactionAction::Call(sender),
+        value: U256::zero(),
+        input: Vec::new(),
+        access_list: AccessList::default(),
+    }
+}
+
+// EIP-2718 のトランザクションタイプ
+const EIP1559_TYPE: u8 = 0x02;
+
+// 署名して Type 2 の raw トランザクションを作成
+pub fn sign<K: HashSigner + ?Sized>(
+    transaction_message: EIP1559TransactionMessage,
+    key: &K,
+) -> Result<Vec<u8>> {
+    let mut signed_transaction = Vec::with_capacity(raw_capacity(&transaction_message));
+    sign_into(transaction_message, key, &mut signed_transaction)?;
+    Ok(signed_transaction)
+}
+
+// 署名して Type 2 の raw トランザクションを out の後ろに書き込む
+// 署名用ハッシュの計算にも out の後ろの領域を使うため、out に raw_capacity の空きがあれば確保し直さない
+pub fn sign_into<K: HashSigner + ?Sized>(
+    transaction_message: EIP1559TransactionMessage,
+    key: &K,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    // 署名用ハッシュを計算
+    let transaction_hash = hash_into(&transaction_message, out);
+
+    // 署名
+    let signature = key.sign_hash(&transaction_hash)?;
+
+    // トランザクションデータを作成
+    encode_into(&with_signature(transaction_message, &signature), out);
+    Ok(())
+}
+
+// 署名前のトランザクションに署名値を付ける
+pub fn with_signature(
+    transaction_message: EIP1559TransactionMessage,
+    signature: &signer::Signature,
+) -> EIP1559Transaction {
+    EIP1559Transaction {
+        chain_id: transaction_message.chain_id,
+        nonce: transaction_message.nonce,
+        max_priority_fee_per_gas: transaction_message.max_priority_fee_per_gas,
+        max_fee_per_gas: transaction_message.max_fee_per_gas,
+        gas_limit: transaction_message.gas_limit,
+        action: transaction_message.action,
+        value: transaction_message.value,
+        input: transaction_message.input,
+        access_list: transaction_message.access_list,
+        odd_y_parity: signature.odd_y_parity,
+        r: signature.r,
+        s: signature.s,
+    }
+}
+
+// 署名して出力するトランザクションの形式
+// RPC_URL のチェーンが EIP-1559 に対応していなければ Legacy (EIP-155) にする
+// zkSync で paymaster を指定した場合は zkSync の EIP-712 トランザクション (0x71) にする
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TxType {
+    #[default]
+    Eip1559,
+    Legacy,
+    Zksync(Paymaster),
+    // Celo で feeCurrency を指定した場合は CIP-64 のトランザクション (0x7b)
+    Celo(H160),
+}
+
+impl TxType {
+    pub fn sign<K: HashSigner + ?Sized>(
+        &self,
+        transaction_message: EIP1559TransactionMessage,
+        key: &K,
+    ) -> Result<Vec<u8>> {
+        match self {
+            TxType::Eip1559 => sign(transaction_message, key),
+            TxType::Legacy => sign_legacy(&transaction_message, key),
+            TxType::Zksync(paymaster) => zksync::sign(&transaction_message, paymaster, key),
+            TxType::Celo(fee_currency) => celo::sign(&transaction_message, *fee_currency, key),
+        }
+    }
+
+    // from は zkSync の署名用ハッシュにだけ含まれる
+    pub fn signing_hash(
+        &self,
+        transaction_message: &EIP1559TransactionMessage,
+        from: H160,
+    ) -> Result<H256> {
+        match self {
+            TxType::Eip1559 => Ok(signing_hash(transaction_message)),
+            TxType::Legacy => Ok(legacy_signing_hash(&legacy_message(transaction_message))),
+            TxType::Zksync(paymaster) => zksync::signing_hash(transaction_message, from, paymaster),
+            TxType::Celo(fee_currency) => {
+                Ok(celo::signing_hash(transaction_message, *fee_currency))
+            }
+        }
+    }
+}
+
+// Legacy では gasPrice に max_fee_per_gas を使う (優先手数料の区別はない)
+pub fn legacy_message(transaction_message: &EIP1559TransactionMessage) -> LegacyTransactionMessage {
+    LegacyTransactionMessage {
+        nonce: transaction_message.nonce,
+        gas_price: transaction_message.max_fee_per_gas,
+        gas_limit: transaction_message.gas_limit,
+        action: transaction_message.action,
+        value: transaction_message.value,
+        input: transaction_message.input.clone(),
+        chain_id: Some(transaction_message.chain_id),
+    }
+}
+
+// 署名して EIP-155 の Legacy の raw トランザクション (RLP) を作成
+pub fn sign_legacy<K: HashSigner + ?Sized>(
+    transaction_message: &EIP1559TransactionMessage,
+    key: &K,
+) -> Result<Vec<u8>> {
+    let message = legacy_message(transaction_message);
+    let signature = key.sign_hash(&legacy_signing_hash(&message))?;
+    encode_legacy(message, &signature)
+}
+
+// 署名値を付けて EIP-155 の Legacy の raw トランザクション (RLP) を作成
+pub fn encode_legacy(
+    message: LegacyTransactionMessage,
+    signature: &signer::Signature,
+) -> Result<Vec<u8>> {
+    // v = chain_id * 2 + 35 + y_parity
+    let v = message.chain_id.unwrap_or_default() * 2 + 35 + signature.odd_y_parity as u64;
+    let signature = TransactionSignature::new(v, signature.r, signature.s)
+        .ok_or_else(|| Error::InvalidArgument("invalid legacy signature".to_string()))?;
+    let transaction = LegacyTransaction {
+        nonce: message.nonce,
+        gas_price: message.gas_price,
+        gas_limit: message.gas_limit,
+        action: message.action,
+        value: message.value,
+        input: message.input,
+        signature,
+    };
+    #[cfg(not(feature = "alloy"))]
+    return Ok(rlp::encode(&transaction).to_vec());
+    #[cfg(feature = "alloy")]
+    return Ok(crate::alloy::encode_legacy(&transaction));
+}
+
+// 署名前の EIP-155 の Legacy のトランザクション (RLP)
+pub fn encode_legacy_unsigned(message: &LegacyTransactionMessage) -> Vec<u8> {
+    #[cfg(not(feature = "alloy"))]
+    return rlp::encode(message).to_vec();
+    #[cfg(feature = "alloy")]
+    return crate::alloy::encode_legacy_message(message);
+}
+
+// Legacy の署名用ハッシュ (RLP(署名前のトランザクション) の Keccak-256)
+pub fn legacy_signing_hash(message: &LegacyTransactionMessage) -> H256 {
+    keccak256(encode_legacy_unsigned(message))
+}
+
+// 署名用ハッシュ (0x02 + RLP(署名前のトランザクション) の Keccak-256)
+pub fn signing_hash(transaction_message: &EIP1559TransactionMessage) -> H256 {
+    hash_into(
+        transaction_message,
+        &mut Vec::with_capacity(raw_capacity(transaction_message)),
+    )
+}
+
+// 署名前のトランザクション (0x02 + RLP)
+pub fn encode_unsigned(transaction_message: &EIP1559TransactionMessage) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw_capacity(transaction_message));
+    encode_typed_into(transaction_message, &mut out);
+    out
+}
+
+// out の後ろの領域でエンコードして署名用ハッシュを計算する (out の内容は変えない)
+fn hash_into(transaction_message: &EIP1559TransactionMessage, out: &mut Vec<u8>) -> H256 {
+    let start = out.len();
+    encode_typed_into(transaction_message, out);
+    let hash = keccak256(&out[start..]);
+    out.truncate(start);
+    hash
+}
+
+// 署名済みトランザクションを Type 2 の raw トランザクション (0x02 + RLP) として out の後ろに書き込む
+pub fn encode_into(transaction: &EIP1559Transaction, out: &mut Vec<u8>) {
+    encode_typed_into(transaction, out);
+}
+
+// 0x02 + RLP を out の後ろに書き込む
+// out の領域をそのまま RLP のバッファにするため、プレフィックスとの連結でコピーしない
+#[cfg(not(feature = "alloy"))]
+fn encode_typed_into(item: &impl Encodable, out: &mut Vec<u8>) {
+    let mut buffer = BytesMut::from(Bytes::from(std::mem::take(out)));
+    buffer.extend_from_slice(&[EIP1559_TYPE]);
+    let mut stream = RlpStream::new_with_buffer(buffer);
+    stream.append(item);
+    *out = stream.out().into();
+}
+
+// alloy フィーチャーでは alloy-rlp で out の後ろに直接書き込む
+#[cfg(feature = "alloy")]
+fn encode_typed_into(item: &impl crate::alloy::Eip1559Encodable, out: &mut Vec<u8>) {
+    out.push(EIP1559_TYPE);
+    item.encode_rlp(out);
+}
+
+// 署名済みの raw トランザクションの長さの上限 (input とアクセスリスト以外の部分は最大 290 バイト)
+pub fn raw_capacity(message: &EIP1559TransactionMessage) -> usize {
+    let access_list: usize = message
+        .access_list
+        .iter()
+        .map(|item| 39 + 33 * item.storage_keys.len())
+        .sum();
+    290 + message.input.len() + access_list
+}
+
+// 署名前のトランザクションを JSON-RPC と同じ形式 (数値は 0x 付き16進数) の JSON に変換
+// 監査ログとジャーナルに残すため、tx_type の形式のタイプとフィールドにする
+// - Legacy: gasPrice (= max fee) のみでアクセスリストはない
+// - zkSync (0x71): paymaster を eip712Meta に入れる (zkSync の JSON-RPC と同じ)
+// - Celo (0x7b): Type 2 のフィールドに feeCurrency を加える
+pub fn message_json(tx_type: &TxType, message: &EIP1559TransactionMessage) -> Value {
+    let mut json = json!({
+        "type": "0x2",
+        "chainId": format!("{:#x}", message.chain_id),
+        "nonce": format!("{:#x}", message.nonce),
+        "to": match message.action {
+            TransactionAction::Call(to) => json!(to),
+            TransactionAction::Create => Value::Null,
+        },
+        "gas": format!("{:#x}", message.gas_limit),
+        "maxFeePerGas": format!("{:#x}", message.max_fee_per_gas),
+        "maxPriorityFeePerGas": format!("{:#x}", message.max_priority_fee_per_gas),
+        "value": format!("{:#x}", message.value),
+        "input": format!("0x{}", hex::encode(&message.input)),
+        "accessList": [],
+    });
+    let fields = json.as_object_mut().unwrap();
+    match tx_type {
+        TxType::Eip1559 => {}
+        TxType::Legacy => {
+            fields.remove("maxPriorityFeePerGas");
+            fields.remove("accessList");
+            let gas_price = fields.remove("maxFeePerGas").unwrap_or_default();
+            fields.insert("gasPrice".to_string(), gas_price);
+            fields.insert("type".to_string(), json!("0x0"));
+        }
+        TxType::Zksync(paymaster) => {
+            fields.remove("accessList");
+            fields.insert(
+                "type".to_string(),
+                json!(format!("{:#x}", zksync::EIP712_TX_TYPE)),
+            );
+            fields.insert(
+                "eip712Meta".to_string(),
+                json!({
+                    "gasPerPubdata": format!("{:#x}", zksync::DEFAULT_GAS_PER_PUBDATA),
+                    "paymasterParams": {
+                        "paymaster": paymaster.address,
+                        "paymasterInput": format!("0x{}", hex::encode(&paymaster.input)),
+                    },
+                }),
+            );
+        }
+        TxType::Celo(fee_currency) => {
+            fields.insert(
+                "type".to_string(),
+                json!(format!("{:#x}", celo::CIP64_TX_TYPE)),
+            );
+            fields.insert("feeCurrency".to_string(), json!(fee_currency));
+        }
+    }
+    json
+}
+
+// 署名済みの raw トランザクションの形式 (zkSync の paymaster と Celo の feeCurrency を含む)
+pub fn decode_tx_type(raw: &[u8]) -> Result<TxType> {
+    match raw.first() {
+        Some(&EIP1559_TYPE) => Ok(TxType::Eip1559),
+        Some(&zksync::EIP712_TX_TYPE) => Ok(TxType::Zksync(zksync::decode_paymaster(raw)?)),
+        Some(&celo::CIP64_TX_TYPE) => Ok(TxType::Celo(celo::decode_fee_currency(raw)?)),
+        Some(&first) if first >= 0xc0 => Ok(TxType::Legacy),
+        _ => Err(Error::InvalidArgument(
+            "invalid transaction: unsupported transaction type".to_string(),
+        )),
+    }
+}
+
+// 署名済みの Type 2 の raw トランザクションをデコード
+// Legacy (EIP-155) は gasPrice を max fee と優先手数料の両方にして同じ形にする
+pub fn decode(raw: &[u8]) -> Result<EIP1559Transaction> {
+    let invalid =
+        |error: rlp::DecoderError| Error::InvalidArgument(format!("invalid transaction: {error}"));
+    match raw.split_first() {
+        Some((&EIP1559_TYPE, rlp_encoded)) => rlp::decode(rlp_encoded).map_err(invalid),
+        Some((&zksync::EIP712_TX_TYPE, _)) => zksync::decode(raw),
+        Some((&celo::CIP64_TX_TYPE, _)) => celo::decode(raw),
+        // RLP のリストで始まるものは Legacy
+        Some((&first, _)) if first >= 0xc0 => {
+            let transaction: LegacyTransaction = rlp::decode(raw).map_err(invalid)?;
+            let signature = &transaction.signature;
+            Ok(EIP1559Transaction {
+                chain_id: signature.chain_id().unwrap_or_default(),
+                nonce: transaction.nonce,
+                max_priority_fee_per_gas: transaction.gas_price,
+                max_fee_per_gas: transaction.gas_price,
+                gas_limit: transaction.gas_limit,
+                action: transaction.action,
+                value: transaction.value,
+                input: transaction.input,
+                access_list: AccessList::default(),
+                odd_y_parity: signature.standard_v() == 1,
+                r: *signature.r(),
+                s: *signature.s(),
+            })
+        }
+        _ => Err(Error::InvalidArgument(
+            "not an EIP-1559 transaction".to_string(),
+        )),
+    }
+}
+
+// 署名済みの raw トランザクションを geth の types.Transaction と同じ形式の JSON に変換
+pub fn signed_json(raw: &[u8]) -> Result<Value> {
+    let transaction = decode(raw)?;
+    let y_parity = format!("{:#x}", transaction.odd_y_parity as u8);
+    let r = U256::from_big_endian(transaction.r.as_bytes());
+    let s = U256::from_big_endian(transaction.s.as_bytes());
+
+    let mut json = message_json(
+        &decode_tx_type(raw)?,
+        &EIP1559TransactionMessage::from(transaction),
+    );
+    json["v"] = json!(y_parity);
+    json["yParity"] = json!(y_parity);
+    json["r"] = json!(format!("{r:#x}"));
+    json["s"] = json!(format!("{s:#x}"));
+    json["hash"] = json!(keccak256(raw));
+
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, recover_address, test_signing_key};
+    use ethereum_types::H160;
+    use k256::ecdsa::SigningKey;
+
+    fn test_message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 11155111,
+            nonce: U256::from(1),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(50_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: U256::one(),
+            input: vec![],
+            access_list: AccessList::default(),
+        }
+    }
+
+    #[test]
+    fn test_build_message() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "chain_id": 11155111,
+                "max_fee_per_gas": 50000000000,
+                "max_priority_fee_per_gas": 2000000000,
+                "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            }"#,
+        )
+        .unwrap();
+        let params: Params = serde_json::from_str(
+            r#"{
+                "nonce": 1,
+                "to_address": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": 1,
+                "gas_limit": 21000
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            build_message(&config, params.nonce.unwrap(), &params),
+            test_message()
+        );
+
+        // params の手数料は設定より優先する
+        let params: Params = serde_json::from_str(
+            r#"{
+                "nonce": 1,
+                "to": "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+                "value": 1,
+                "gas": 21000,
+                "maxFeePerGas": "0x12a05f200",
+                "maxPriorityFeePerGas": "0x3b9aca00"
+            }"#,
+        )
+        .unwrap();
+        let message = build_message(&config, U256::one(), &params);
+        assert_eq!(message.max_fee_per_gas, U256::from(5_000_000_000u64));
+        assert_eq!(
+            message.max_priority_fee_per_gas,
+            U256::from(1_000_000_000u64)
+        );
+        assert_eq!(message.chain_id, 11155111);
+    }
+
+    #[test]
+    fn test_build_create_message() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "chain_id": 11155111,
+                "max_fee_per_gas": 50000000000,
+                "max_priority_fee_per_gas": 2000000000,
+                "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            }"#,
+        )
+        .unwrap();
+
+        let message = build_create_message(
+            &config,
+            U256::from(5),
+            U256::zero(),
+            U256::from(1_000_000),
+            vec![0x60, 0x80],
+        );
+
+        assert_eq!(message.action, TransactionAction::Create);
+        assert_eq!(message.nonce, U256::from(5));
+        assert_eq!(message.input, vec![0x60, 0x80]);
+        assert_eq!(message.chain_id, 11155111);
+
+        // to が空 (0x80) でエンコードされ、署名できる
+        let raw = sign(message, &test_signing_key()).unwrap();
+        let decoded: EIP1559Transaction = rlp::decode(&raw[1..]).unwrap();
+        assert_eq!(decoded.action, TransactionAction::Create);
+    }
+
+    #[test]
+    fn test_build_filler_message() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "chain_id": 11155111,
+                "max_fee_per_gas": 50000000000,
+                "max_priority_fee_per_gas": 2000000000
+            }"#,
+        )
+        .unwrap();
+        let sender: H160 = TEST_ADDRESS.parse().unwrap();
+
+        let message = build_filler_message(&config, U256::from(12), sender);
+        assert_eq!(message.action, TransactionAction::Call(sender));
+        assert_eq!(message.nonce, U256::from(12));
+        assert_eq!(message.value, U256::zero());
+        assert_eq!(message.gas_limit, U256::from(21000));
+        assert!(message.input.is_empty());
+        assert_eq!(message.max_fee_per_gas, config.max_fee_per_gas);
+    }
+
+    #[test]
+    fn test_sign_known_vector() {
+        // Sepolia, nonce 1, 2 Gwei / 50 Gwei, 1 wei 送金を署名した結果
+        let raw = sign(test_message(), &test_signing_key()).unwrap();
+        assert_eq!(
+            hex::encode(raw),
+            "02f86e83aa36a7018477359400850ba43b740082520894742d35cc6634c0532925a3b8d2f8e0c4ed2d11df0180c080a09f0ecb460a1113ea92f4d1c03349dd277ec2f389f6227d60480ce919c24903e6a01115dee43f7b3ffcbaf0355d05a1f26a743e8279568ffd71c9638e6a4cc056bd"
+        );
+    }
+
+    #[test]
+    fn test_sign_type2_prefix() {
+        let raw = sign(test_message(), &test_signing_key()).unwrap();
+        assert_eq!(raw[0], 0x02);
+    }
+
+    #[test]
+    fn test_sign_decodable_and_recoverable() {
+        let transaction_message = test_message();
+        let raw = sign(transaction_message.clone(), &test_signing_key()).unwrap();
+
+        let transaction: EIP1559Transaction = rlp::decode(&raw[1..]).unwrap();
+        assert_eq!(transaction.chain_id, transaction_message.chain_id);
+        assert_eq!(transaction.nonce, transaction_message.nonce);
+        assert_eq!(transaction.value, transaction_message.value);
+
+        let signature = signer::Signature {
+            r: transaction.r,
+            s: transaction.s,
+            odd_y_parity: transaction.odd_y_parity,
+        };
+        assert_eq!(
+            recover_address(&transaction_message.hash(), &signature),
+            TEST_ADDRESS.parse::<H160>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_legacy() {
+        // EIP-155 の例 (chain id 1, nonce 9, 20 Gwei, 1 ETH 送金)
+        let signing_key = SigningKey::from_slice(&[0x46; 32]).unwrap();
+        let message = EIP1559TransactionMessage {
+            chain_id: 1,
+            nonce: U256::from(9),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::from(20_000_000_000u64),
+            gas_limit: U256::from(21000),
+            action: TransactionAction::Call(H160::repeat_byte(0x35)),
+            value: U256::from(1_000_000_000_000_000_000u64),
+            input: vec![],
+            access_list: AccessList::default(),
+        };
+        let raw = TxType::Legacy.sign(message.clone(), &signing_key).unwrap();
+        assert_eq!(
+            hex::encode(&raw),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        assert_eq!(
+            TxType::Legacy.signing_hash(&message, H160::zero()).unwrap(),
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+                .parse::<H256>()
+                .unwrap()
+        );
+
+        // デコードすると gasPrice が max fee と優先手数料になる
+        let transaction = decode(&raw).unwrap();
+        assert_eq!(transaction.chain_id, 1);
+        assert_eq!(transaction.max_fee_per_gas, message.max_fee_per_gas);
+        assert_eq!(
+            transaction.max_priority_fee_per_gas,
+            message.max_fee_per_gas
+        );
+        assert!(!transaction.odd_y_parity);
+
+        assert_eq!(
+            TxType::Eip1559
+                .sign(test_message(), &test_signing_key())
+                .unwrap(),
+            sign(test_message(), &test_signing_key()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_into() {
+        // 各値が最大の長さになるトランザクション
+        let message = EIP1559TransactionMessage {
+            chain_id: u64::MAX,
+            nonce: U256::MAX,
+            max_priority_fee_per_gas: U256::MAX,
+            max_fee_per_gas: U256::MAX,
+            gas_limit: U256::MAX,
+            value: U256::MAX,
+            input: vec![0xff; 100_000],
+            access_list: vec![ethereum::AccessListItem {
+                address: H160::repeat_byte(0xff),
+                storage_keys: vec![H256::repeat_byte(0xff); 3],
+            }],
+            ..test_message()
+        };
+        for message in [test_message(), message] {
+            let expected = sign(message.clone(), &test_signing_key()).unwrap();
+            assert_eq!(signing_hash(&message), message.hash());
+
+            // 先に書き込まれている内容は残し、確保した領域に収まる
+            let mut out = Vec::with_capacity(3 + raw_capacity(&message));
+            out.extend_from_slice(b"abc");
+            let capacity = out.capacity();
+            sign_into(message, &test_signing_key(), &mut out).unwrap();
+            assert_eq!(&out[..3], b"abc");
+            assert_eq!(out[3..], expected);
+            assert_eq!(out.capacity(), capacity);
+
+            let mut encoded = vec![0x01];
+            encode_into(&decode(&expected).unwrap(), &mut encoded);
+            assert_eq!(encoded[0], 0x01);
+            assert_eq!(encoded[1..], expected);
+        }
+    }
+
+    #[test]
+    fn test_message_json() {
+        let json = message_json(&TxType::Eip1559, &test_message());
+
+        assert_eq!(json["chainId"], "0xaa36a7");
+        assert_eq!(json["nonce"], "0x1");
+        assert_eq!(json["to"], "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df");
+        assert_eq!(json["gas"], "0x5208");
+        assert_eq!(json["maxFeePerGas"], "0xba43b7400");
+        assert_eq!(json["value"], "0x1");
+        assert_eq!(json["input"], "0x");
+
+        let mut message = test_message();
+        message.action = TransactionAction::Create;
+        assert_eq!(message_json(&TxType::Eip1559, &message)["to"], Value::Null);
+        assert_eq!(json["type"], "0x2");
+        assert_eq!(json["maxPriorityFeePerGas"], "0x77359400");
+
+        // Legacy は gasPrice のみ
+        let json = message_json(&TxType::Legacy, &test_message());
+        assert_eq!(json["type"], "0x0");
+        assert_eq!(json["gasPrice"], "0xba43b7400");
+        assert!(json.get("maxFeePerGas").is_none());
+        assert!(json.get("maxPriorityFeePerGas").is_none());
+        assert!(json.get("accessList").is_none());
+
+        // zkSync と Celo はそれぞれのタイプと paymaster・feeCurrency
+        let paymaster = Paymaster {
+            address: H160::repeat_byte(0x11),
+            input: vec![0xab],
+        };
+        let json = message_json(&TxType::Zksync(paymaster), &test_message());
+        assert_eq!(json["type"], "0x71");
+        assert_eq!(
+            json["eip712Meta"]["paymasterParams"]["paymaster"],
+            "0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(
+            json["eip712Meta"]["paymasterParams"]["paymasterInput"],
+            "0xab"
+        );
+        assert_eq!(json["eip712Meta"]["gasPerPubdata"], "0xc350");
+        let json = message_json(&TxType::Celo(H160::repeat_byte(0x22)), &test_message());
+        assert_eq!(json["type"], "0x7b");
+        assert_eq!(
+            json["feeCurrency"],
+            "0x2222222222222222222222222222222222222222"
+        );
+        assert_eq!(json["maxFeePerGas"], "0xba43b7400");
+    }
+
+    #[test]
+    fn test_decode_tx_type() {
+        let key = test_signing_key();
+        let paymaster = Paymaster {
+            address: H160::repeat_byte(0x11),
+            input: vec![0xab, 0xcd],
+        };
+        for tx_type in [
+            TxType::Eip1559,
+            TxType::Legacy,
+            TxType::Zksync(paymaster),
+            TxType::Celo(H160::repeat_byte(0x22)),
+        ] {
+            let raw = tx_type.sign(test_message(), &key).unwrap();
+            assert_eq!(decode_tx_type(&raw).unwrap(), tx_type);
+            assert_eq!(
+                signed_json(&raw).unwrap()["type"],
+                message_json(&tx_type, &test_message())["type"]
+            );
+        }
+        assert!(decode_tx_type(&[]).is_err());
+        assert!(decode_tx_type(&[0x05]).is_err());
+    }
+
+    #[test]
+    fn test_signed_json() {
+        let raw = sign(test_message(), &test_signing_key()).unwrap();
+        let json = signed_json(&raw).unwrap();
+
+        assert_eq!(json["type"], "0x2");
+        assert_eq!(json["nonce"], "0x1");
+        assert_eq!(json["v"], "0x0");
+        assert_eq!(json["yParity"], "0x0");
+        assert_eq!(
+            json["r"],
+            "0x9f0ecb460a1113ea92f4d1c03349dd277ec2f389f6227d60480ce919c24903e6"
+        );
+        assert_eq!(
+            json["s"],
+            "0x1115dee43f7b3ffcbaf0355d05a1f26a743e8279568ffd71c9638e6a4cc056bd"
+        );
+        assert_eq!(json["hash"], json!(keccak256(&raw)));
+
+        assert!(signed_json(&raw[1..]).is_err());
+        assert!(signed_json(&[0x02, 0xc0]).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    Result,
+    de::{deserialize_optional_hex_bytes, deserialize_optional_u256},
+    error::Error,
+    ethers::{RawAccessListItem, RawTypedTransaction, TypedTransaction},
+    json,
+};
+use ethereum_types::{H160, U256};
+use serde::Deserialize;
+use std::path::Path;
+
+// eth_signTransaction の params のトランザクションオブジェクト (ウォレットからのリクエストをそのまま渡せる形)
+// {"from": "0x…", "to": "0x…", "gas": "0x5208", "maxFeePerGas": "0x…", "maxPriorityFeePerGas": "0x…", "value": "0x1", "input": "0x", "nonce": "0x4", "chainId": "0xaa36a7"}
+// type を省略した場合は gasPrice があれば Legacy、なければ EIP-1559 とする
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RawTransactionRequest {
+    #[serde(default, rename = "type")]
+    tx_type: Option<String>,
+    #[serde(default)]
+    from: Option<H160>,
+    #[serde(default)]
+    to: Option<H160>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    gas_price: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    value: Option<U256>,
+    // 仕様の名前は input、geth などは data も受け付ける
+    #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+    input: Option<Vec<u8>>,
+    #[serde(default, deserialize_with = "deserialize_optional_hex_bytes")]
+    data: Option<Vec<u8>>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    nonce: Option<U256>,
+    #[serde(default, deserialize_with = "deserialize_optional_u256")]
+    chain_id: Option<U256>,
+    #[serde(default)]
+    access_list: Vec<RawAccessListItem>,
+}
+
+impl TryFrom<RawTransactionRequest> for TypedTransaction {
+    type Error = String;
+
+    fn try_from(raw: RawTransactionRequest) -> std::result::Result<Self, String> {
+        let data = match (raw.input, raw.data) {
+            (Some(input), Some(data)) if input != data => {
+                return Err("both input and data are set but differ".to_string());
+            }
+            (Some(input), _) | (None, Some(input)) => Some(input),
+            (None, None) => None,
+        };
+        let tx_type = raw.tx_type.unwrap_or_else(|| {
+            match raw.gas_price {
+                Some(_) => "0x0",
+                None => "0x2",
+            }
+            .to_string()
+        });
+
+        Self::try_from(RawTypedTransaction {
+            tx_type,
+            from: raw.from,
+            to: raw.to,
+            gas: raw.gas,
+            gas_price: raw.gas_price,
+            value: raw.value,
+            data,
+            nonce: raw.nonce,
+            chain_id: raw.chain_id,
+            max_fee_per_gas: raw.max_fee_per_gas,
+            max_priority_fee_per_gas: raw.max_priority_fee_per_gas,
+            access_list: raw.access_list,
+        })
+    }
+}
+
+// トランザクションオブジェクトか、JSON-RPC の params ([トランザクションオブジェクト]) をそのまま読む
+pub fn parse(content: &str) -> Result<TypedTransaction> {
+    let raw = if content.trim_start().starts_with('[') {
+        json::parse::<(RawTransactionRequest,)>(content)?.0
+    } else {
+        json::parse::<RawTransactionRequest>(content)?
+    };
+    TypedTransaction::try_from(raw).map_err(Error::InvalidArgument)
+}
+
+pub fn from_path<P: AsRef<Path>>(path: P) -> Result<TypedTransaction> {
+    let content = std::fs::read_to_string(&path).map_err(Error::in_file(&path))?;
+    parse(&content).map_err(Error::in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum::TransactionAction;
+
+    #[test]
+    fn test_parse() {
+        // MetaMask などが eth_signTransaction に渡すオブジェクト
+        let request = r#"{
+            "from": "0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df",
+            "to": "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238",
+            "gas": "0xea60",
+            "maxFeePerGas": "0x6fc23ac00",
+            "maxPriorityFeePerGas": "0x3b9aca00",
+            "value": "0x0",
+            "input": "0xa9059cbb",
+            "nonce": "0x7",
+            "chainId": "0xaa36a7"
+        }"#;
+        let tx = parse(request).unwrap();
+        assert!(!tx.legacy);
+        assert_eq!(tx.nonce, Some(U256::from(7)));
+        assert_eq!(tx.gas_limit, U256::from(60000));
+        assert_eq!(tx.input, [0xa9, 0x05, 0x9c, 0xbb]);
+
+        // JSON-RPC の params の配列のまま
+        let from_params = parse(&format!("[{request}]")).unwrap();
+        assert_eq!(from_params.input, tx.input);
+        assert_eq!(from_params.action, tx.action);
+
+        // type がなく gasPrice があれば Legacy、data は input と同じ
+        let tx = parse(r#"{"gas":"0x5208","gasPrice":"0x1","data":"0x01"}"#).unwrap();
+        assert!(tx.legacy);
+        assert_eq!(tx.action, TransactionAction::Create);
+        assert_eq!(tx.input, [0x01]);
+        let tx = parse(r#"{"gas":"0x5208","input":"0x01","data":"0x01","type":"0x2"}"#).unwrap();
+        assert!(!tx.legacy);
+    }
+
+    #[test]
+    fn test_rejected() {
+        for (content, expected) in [
+            (r#"{"gas":"0x5208","input":"0x01","data":"0x02"}"#, "differ"),
+            (
+                r#"{"to":"0x742d35cc6634c0532925a3b8d2f8e0c4ed2d11df"}"#,
+                "gas: missing",
+            ),
+            (
+                r#"{"gas":"0x5208","type":"0x2","gasPrice":"0x1"}"#,
+                "gasPrice",
+            ),
+            (
+                r#"{"gas":"0x5208","maxFeePerBlobGas":"0x1"}"#,
+                "maxFeePerBlobGas",
+            ),
+            (r#"[{"gas":"0x5208"},{"gas":"0x5208"}]"#, "trailing"),
+        ] {
+            let error = parse(content).unwrap_err().to_string();
+            assert!(error.contains(expected), "{content}: {error}");
+        }
+    }
+}
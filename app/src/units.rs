@@ -0,0 +1,179 @@
+use crate::{Result, error::Error};
+use ethereum_types::U256;
+
+// 10^77 < 2^256 < 10^78
+const MAX_DECIMALS: u8 = 77;
+
+fn invalid(amount: &str, reason: impl std::fmt::Display) -> Error {
+    Error::InvalidAmount(format!("'{amount}' ({reason})"))
+}
+
+// "12.5" のような10進数の量を decimals 桁でスケーリングした整数に変換
+// 精度が失われる場合 (小数部が decimals 桁を超える場合) はエラー
+pub fn parse_units(amount: &str, decimals: u8) -> Result<U256> {
+    if decimals > MAX_DECIMALS {
+        return Err(invalid(amount, format!("decimals {decimals} is too large")));
+    }
+
+    let (integer, fraction) = match amount.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amount, ""),
+    };
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction) {
+        return Err(invalid(amount, "expected a decimal number"));
+    }
+
+    // 末尾のゼロは精度に影響しない
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(invalid(
+            amount,
+            format!("more than {decimals} decimal places would lose precision"),
+        ));
+    }
+
+    let overflow = || invalid(amount, "too large");
+    let scale = U256::from(10).pow(U256::from(decimals));
+    let integer = if integer.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(integer).map_err(|_| overflow())?
+    };
+    let fraction = if fraction.is_empty() {
+        U256::zero()
+    } else {
+        let padded = format!("{fraction:0<width$}", width = decimals as usize);
+        U256::from_dec_str(&padded).map_err(|_| overflow())?
+    };
+
+    integer
+        .checked_mul(scale)
+        .and_then(|scaled| scaled.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+// "0.5 eth"・"30 gwei"・"1000 wei" のような単位付きの量を wei に変換 (単位を省略した場合は ETH)
+pub fn parse_amount(amount: &str) -> Result<U256> {
+    let trimmed = amount.trim();
+    let (number, unit) = match trimmed.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(index) => (trimmed[..index].trim_end(), &trimmed[index..]),
+        None => (trimmed, "eth"),
+    };
+    let decimals = match unit.to_ascii_lowercase().as_str() {
+        "eth" | "ether" => 18,
+        "gwei" => 9,
+        "wei" => 0,
+        _ => return Err(invalid(amount, "unknown unit, expected eth, gwei or wei")),
+    };
+    parse_units(number, decimals)
+}
+
+// decimals 桁でスケーリングした整数を10進数の量に戻す (末尾のゼロは省く、"12.5")
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    // U256 の Display は幅の指定を無視するため、文字列にしてから揃える
+    let digits = format!(
+        "{:0>width$}",
+        amount.to_string(),
+        width = decimals as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => integer.to_string(),
+        fraction => format!("{integer}.{fraction}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units_integer() {
+        assert_eq!(parse_units("12", 6).unwrap(), U256::from(12_000_000));
+        assert_eq!(parse_units("0", 18).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn test_parse_units_fraction() {
+        assert_eq!(parse_units("12.5", 6).unwrap(), U256::from(12_500_000));
+        assert_eq!(parse_units("0.000001", 6).unwrap(), U256::one());
+        assert_eq!(parse_units(".5", 1).unwrap(), U256::from(5));
+        assert_eq!(parse_units("1.", 2).unwrap(), U256::from(100));
+        assert_eq!(
+            parse_units("1.5", 18).unwrap(),
+            U256::from(1_500_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn test_parse_units_trailing_zeros() {
+        // 小数部が decimals 桁を超えても末尾がゼロなら精度は失われない
+        assert_eq!(
+            parse_units("12.5000000", 6).unwrap(),
+            U256::from(12_500_000)
+        );
+        assert_eq!(parse_units("3.0", 0).unwrap(), U256::from(3));
+    }
+
+    #[test]
+    fn test_parse_units_precision_loss() {
+        assert!(matches!(
+            parse_units("0.0000001", 6),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(parse_units("1.5", 0).is_err());
+        assert!(parse_units("12.345", 2).is_err());
+        assert_eq!(
+            parse_units("12.345", 18).unwrap(),
+            U256::from(12_345_000_000_000_000_000u128)
+        );
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let ether = U256::from(1_000_000_000_000_000_000u64);
+        assert_eq!(parse_amount("0.5 eth").unwrap(), ether / 2);
+        assert_eq!(parse_amount("0.5ETH").unwrap(), ether / 2);
+        assert_eq!(parse_amount("2 ether").unwrap(), ether * 2);
+        assert_eq!(parse_amount("0.5").unwrap(), ether / 2);
+        assert_eq!(
+            parse_amount("30 gwei").unwrap(),
+            U256::from(30_000_000_000u64)
+        );
+        assert_eq!(parse_amount(" 1000 wei ").unwrap(), U256::from(1000));
+        for amount in ["0.5 btc", "1.5 wei", "eth", "1e6", ""] {
+            assert!(
+                matches!(parse_amount(amount), Err(Error::InvalidAmount(_))),
+                "{amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_units_invalid() {
+        for amount in ["", ".", "abc", "-1", "+1", "1.2.3", "1e6", " 1", "1,000"] {
+            assert!(parse_units(amount, 6).is_err(), "{amount}");
+        }
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::from(12_500_000), 6), "12.5");
+        assert_eq!(format_units(U256::from(12_000_000), 6), "12");
+        assert_eq!(format_units(U256::one(), 6), "0.000001");
+        assert_eq!(format_units(U256::zero(), 18), "0");
+        assert_eq!(format_units(U256::from(42), 0), "42");
+        for amount in ["1.5", "0.000000001", "123456789.123456789"] {
+            assert_eq!(format_units(parse_units(amount, 18).unwrap(), 18), amount);
+        }
+    }
+
+    #[test]
+    fn test_parse_units_overflow() {
+        assert!(parse_units(&"9".repeat(80), 0).is_err());
+        assert!(parse_units("1", 78).is_err());
+        // 2^256 - 1 はちょうど収まる
+        assert_eq!(parse_units(&U256::MAX.to_string(), 0).unwrap(), U256::MAX);
+    }
+}
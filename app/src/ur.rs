@@ -0,0 +1,385 @@
+use crate::{Result, error::Error};
+
+// Uniform Resources (BCR-2020-005) の1パートの UR と、その中身の CBOR
+// Keystone などのエアギャップのウォレットと QR コードでやり取りする (複数パートの fountain コードには対応しない)
+// ur:eth-sign-request/<CBOR を bytewords (minimal) にしたもの + CRC32>
+
+// bytewords の単語 (minimal では最初と最後の文字で1バイトを表す)
+const BYTEWORDS: [&str; 256] = [
+    "able", "acid", "also", "apex", "aqua", "arch", "atom", "aunt", "away", "axis", "back", "bald",
+    "barn", "belt", "beta", "bias", "blue", "body", "brag", "brew", "bulb", "buzz", "calm", "cash",
+    "cats", "chef", "city", "claw", "code", "cola", "cook", "cost", "crux", "curl", "cusp", "cyan",
+    "dark", "data", "days", "deli", "dice", "diet", "door", "down", "draw", "drop", "drum", "dull",
+    "duty", "each", "easy", "echo", "edge", "epic", "even", "exam", "exit", "eyes", "fact", "fair",
+    "fern", "figs", "film", "fish", "fizz", "flap", "flew", "flux", "foxy", "free", "frog", "fuel",
+    "fund", "gala", "game", "gear", "gems", "gift", "girl", "glow", "good", "gray", "grim", "guru",
+    "gush", "gyro", "half", "hang", "hard", "hawk", "heat", "help", "high", "hill", "holy", "hope",
+    "horn", "huts", "iced", "idea", "idle", "inch", "inky", "into", "iris", "iron", "item", "jade",
+    "jazz", "join", "jolt", "jowl", "judo", "jugs", "jump", "junk", "jury", "keep", "keno", "kept",
+    "keys", "kick", "kiln", "king", "kite", "kiwi", "knob", "lamb", "lava", "lazy", "leaf", "legs",
+    "liar", "limp", "lion", "list", "logo", "loud", "love", "luau", "luck", "lung", "main", "many",
+    "math", "maze", "memo", "menu", "meow", "mild", "mint", "miss", "monk", "nail", "navy", "need",
+    "news", "next", "noon", "note", "numb", "obey", "oboe", "omit", "onyx", "open", "oval", "owls",
+    "paid", "part", "peck", "play", "plus", "poem", "pool", "pose", "puff", "puma", "purr", "quad",
+    "quiz", "race", "ramp", "real", "redo", "rich", "road", "rock", "roof", "ruby", "ruin", "runs",
+    "rust", "safe", "saga", "scar", "sets", "silk", "skew", "slot", "soap", "solo", "song", "stub",
+    "surf", "swan", "taco", "task", "taxi", "tent", "tied", "time", "tiny", "toil", "tomb", "toys",
+    "trip", "tuna", "twin", "ugly", "undo", "unit", "urge", "user", "vast", "very", "veto", "vial",
+    "vibe", "view", "visa", "void", "vows", "wall", "wand", "warm", "wasp", "wave", "waxy", "webs",
+    "what", "when", "whiz", "wolf", "work", "yank", "yawn", "yell", "yoga", "yurt", "zaps", "zero",
+    "zest", "zinc", "zone", "zoom",
+];
+
+// CBOR (RFC 8949) のうち UR のレジストリで使うもの
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cbor {
+    Unsigned(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    // キーは整数で、並び順のまま書き出す
+    Map(Vec<(u64, Cbor)>),
+    Tag(u64, Box<Cbor>),
+    Bool(bool),
+}
+
+impl Cbor {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    // 1つの値をデコードする (後ろに余分なバイトがあればエラー)
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, position: 0 };
+        let value = reader.read(0)?;
+        if reader.position != bytes.len() {
+            return Err(invalid("trailing bytes after the CBOR value"));
+        }
+        Ok(value)
+    }
+
+    // マップの整数のキーの値
+    pub fn get(&self, key: u64) -> Option<&Cbor> {
+        match self {
+            Self::Map(entries) => entries
+                .iter()
+                .find_map(|(found, value)| (*found == key).then_some(value)),
+            _ => None,
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Unsigned(value) => write_head(out, 0, *value),
+            Self::Bytes(bytes) => {
+                write_head(out, 2, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            Self::Text(text) => {
+                write_head(out, 3, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Self::Array(items) => {
+                write_head(out, 4, items.len() as u64);
+                for item in items {
+                    item.encode_into(out);
+                }
+            }
+            Self::Map(entries) => {
+                write_head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    write_head(out, 0, *key);
+                    value.encode_into(out);
+                }
+            }
+            Self::Tag(tag, item) => {
+                write_head(out, 6, *tag);
+                item.encode_into(out);
+            }
+            Self::Bool(value) => out.push(if *value { 0xf5 } else { 0xf4 }),
+        }
+    }
+}
+
+// 主要型と引数 (最短の長さで書く)
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+// bytewords (minimal) に CRC32 を付けて変換する
+fn to_bytewords(bytes: &[u8]) -> String {
+    let checksum = crc32fast::hash(bytes).to_be_bytes();
+    bytes
+        .iter()
+        .chain(&checksum)
+        .flat_map(|byte| {
+            let word = BYTEWORDS[*byte as usize].as_bytes();
+            [word[0] as char, word[3] as char]
+        })
+        .collect()
+}
+
+fn invalid(message: impl std::fmt::Display) -> Error {
+    Error::InvalidArgument(format!("invalid UR: {message}"))
+}
+
+// 入れ子の深さの上限 (不正な入力でスタックを使い切らないように)
+const MAX_DEPTH: usize = 16;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated CBOR"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    // 主要型と引数
+    fn head(&mut self) -> Result<(u8, u8, u64)> {
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let value = match info {
+            0..=23 => info as u64,
+            24..=27 => self
+                .take(1 << (info - 24))?
+                .iter()
+                .fold(0, |value, byte| (value << 8) | *byte as u64),
+            _ => return Err(invalid("indefinite-length CBOR is not supported")),
+        };
+        Ok((major, info, value))
+    }
+
+    fn length(&self, value: u64) -> Result<usize> {
+        usize::try_from(value)
+            .ok()
+            .filter(|len| *len <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated CBOR"))
+    }
+
+    fn read(&mut self, depth: usize) -> Result<Cbor> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("CBOR is nested too deeply"));
+        }
+        let (major, info, value) = self.head()?;
+        match major {
+            0 => Ok(Cbor::Unsigned(value)),
+            2 => {
+                let len = self.length(value)?;
+                Ok(Cbor::Bytes(self.take(len)?.to_vec()))
+            }
+            3 => {
+                let len = self.length(value)?;
+                let text = std::str::from_utf8(self.take(len)?).map_err(invalid)?;
+                Ok(Cbor::Text(text.to_string()))
+            }
+            4 => (0..self.length(value)?)
+                .map(|_| self.read(depth + 1))
+                .collect::<Result<_>>()
+                .map(Cbor::Array),
+            5 => (0..self.length(value)?)
+                .map(|_| match self.read(depth + 1)? {
+                    Cbor::Unsigned(key) => Ok((key, self.read(depth + 1)?)),
+                    _ => Err(invalid("only integer map keys are supported")),
+                })
+                .collect::<Result<_>>()
+                .map(Cbor::Map),
+            6 => Ok(Cbor::Tag(value, Box::new(self.read(depth + 1)?))),
+            7 if info == 20 || info == 21 => Ok(Cbor::Bool(info == 21)),
+            _ => Err(invalid(format!("unsupported CBOR major type {major}"))),
+        }
+    }
+}
+
+// 1パートの UR (QR コードの英数字モードで小さくなるよう、表示するときは大文字にしてもよい)
+pub fn encode(ur_type: &str, cbor: &Cbor) -> Result<String> {
+    if !ur_type
+        .bytes()
+        .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+    {
+        return Err(Error::InvalidArgument(format!(
+            "invalid UR type: {ur_type}"
+        )));
+    }
+    Ok(format!("ur:{ur_type}/{}", to_bytewords(&cbor.encode())))
+}
+
+// bytewords (minimal) から戻して CRC32 を確かめる
+fn from_bytewords(words: &str) -> Result<Vec<u8>> {
+    if !words.len().is_multiple_of(2) {
+        return Err(invalid("odd number of letters"));
+    }
+    let mut bytes = words
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            BYTEWORDS
+                .iter()
+                .position(|word| {
+                    let word = word.as_bytes();
+                    word[0] == pair[0] && word[3] == pair[1]
+                })
+                .map(|byte| byte as u8)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "unknown byteword {}",
+                        String::from_utf8_lossy(pair)
+                    ))
+                })
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    if bytes.len() < 4 {
+        return Err(invalid("missing checksum"));
+    }
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if crc32fast::hash(&bytes).to_be_bytes() != checksum.as_slice() {
+        return Err(invalid("checksum mismatch"));
+    }
+    Ok(bytes)
+}
+
+// ur:<type>/<bytewords> を読む (QR コードから読み取った大文字も受け付ける)
+pub fn decode(ur: &str, expected_type: &str) -> Result<Cbor> {
+    let ur = ur.trim().to_ascii_lowercase();
+    let Some((ur_type, payload)) = ur.strip_prefix("ur:").and_then(|rest| rest.split_once('/'))
+    else {
+        return Err(invalid("expected ur:<type>/<payload>"));
+    };
+    if ur_type != expected_type {
+        return Err(invalid(format!("expected {expected_type}, got {ur_type}")));
+    }
+    if payload.contains('/') {
+        return Err(invalid(
+            "multi-part URs are not supported, show the signature as a single QR code",
+        ));
+    }
+    Cbor::decode(&from_bytewords(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytewords() {
+        // bc-ur のテストベクタ
+        assert_eq!(
+            to_bytewords(&[0x00, 0x01, 0x02, 0x80, 0xff]),
+            "aeadaolazmjendeoti"
+        );
+    }
+
+    #[test]
+    fn test_cbor() {
+        // RFC 8949 の付録 A の例
+        for (value, expected) in [
+            (Cbor::Unsigned(23), "17"),
+            (Cbor::Unsigned(24), "1818"),
+            (Cbor::Unsigned(1000), "1903e8"),
+            (Cbor::Unsigned(1_000_000), "1a000f4240"),
+            (Cbor::Unsigned(1_000_000_000_000), "1b000000e8d4a51000"),
+            (Cbor::Bytes(vec![1, 2, 3, 4]), "4401020304"),
+            (Cbor::Text("IETF".to_string()), "6449455446"),
+            (Cbor::Bool(true), "f5"),
+            (
+                Cbor::Array(vec![
+                    Cbor::Unsigned(1),
+                    Cbor::Array(vec![Cbor::Unsigned(2)]),
+                ]),
+                "82018102",
+            ),
+            (
+                Cbor::Map(vec![(1, Cbor::Unsigned(2)), (3, Cbor::Unsigned(4))]),
+                "a201020304",
+            ),
+            (
+                Cbor::Tag(1, Box::new(Cbor::Unsigned(1363896240))),
+                "c11a514b67b0",
+            ),
+        ] {
+            assert_eq!(hex::encode(value.encode()), expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let value = Cbor::Map(vec![
+            (1, Cbor::Tag(37, Box::new(Cbor::Bytes(vec![0xab; 16])))),
+            (
+                2,
+                Cbor::Array(vec![Cbor::Unsigned(1_000_000), Cbor::Bool(false)]),
+            ),
+            (3, Cbor::Text("origin".to_string())),
+        ]);
+        let ur = encode("eth-signature", &value).unwrap();
+        assert!(ur.starts_with("ur:eth-signature/"), "{ur}");
+        assert_eq!(decode(&ur, "eth-signature").unwrap(), value);
+        // QR コードの英数字モードの大文字
+        assert_eq!(decode(&ur.to_uppercase(), "eth-signature").unwrap(), value);
+        assert_eq!(value.get(3), Some(&Cbor::Text("origin".to_string())));
+        assert_eq!(value.get(4), None);
+        assert!(encode("Bytes", &Cbor::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejected() {
+        let ur = encode("eth-signature", &Cbor::Unsigned(1)).unwrap();
+        // 1文字変えると CRC32 が合わない
+        let tampered = format!(
+            "{}{}",
+            &ur[..ur.len() - 1],
+            if ur.ends_with('a') { 'e' } else { 'a' }
+        );
+        assert!(decode(&ur, "eth-signature").is_ok());
+        let error = decode(&ur, "eth-sign-request").unwrap_err().to_string();
+        assert!(error.contains("expected eth-sign-request"), "{error}");
+        for (ur, expected) in [
+            ("eth-signature/aeadaolazmjendeoti", "ur:<type>"),
+            ("ur:eth-signature/1-3/lpadaxcs", "multi-part"),
+            ("ur:eth-signature/aeadaolazmjendeot", "odd number"),
+            ("ur:eth-signature/qqqq", "unknown byteword"),
+            (tampered.as_str(), "checksum"),
+        ] {
+            let error = decode(ur, "eth-signature").unwrap_err().to_string();
+            assert!(error.contains(expected), "{ur}: {error}");
+        }
+
+        for (bytes, expected) in [
+            (&[0x42, 0x01][..], "truncated"),
+            (&[0x01, 0x02], "trailing"),
+            (&[0x5f], "indefinite"),
+            (&[0x20], "major type 1"),
+            (&[0xa1, 0x61, 0x61, 0x01], "integer map keys"),
+            (&[0x81; 20], "nested"),
+        ] {
+            let error = Cbor::decode(bytes).unwrap_err().to_string();
+            assert!(error.contains(expected), "{bytes:?}: {error}");
+        }
+    }
+}
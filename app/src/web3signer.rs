@@ -0,0 +1,166 @@
+use crate::{
+    Result, address,
+    de::deserialize_hex_bytes,
+    signer::{HashSigner, keccak256},
+};
+use k256::ecdsa::VerifyingKey;
+use rlp::Rlp;
+use serde::Deserialize;
+
+// Web3Signer の eth1 署名エンドポイントのパス (末尾に識別子が付く)
+pub const SIGN_PATH_PREFIX: &str = "/api/v1/eth1/sign/";
+pub const PUBLIC_KEYS_PATH: &str = "/api/v1/eth1/publicKeys";
+pub const UPCHECK_PATH: &str = "/upcheck";
+
+// POST /api/v1/eth1/sign/{identifier} のボディ
+#[derive(Debug, Deserialize)]
+pub struct SignRequest {
+    #[serde(deserialize_with = "deserialize_hex_bytes")]
+    pub data: Vec<u8>,
+}
+
+// Web3Signer と同じく、0x04 を除いた非圧縮公開鍵 (64バイト) の16進数表現
+pub fn public_key_hex(verifying_key: &VerifyingKey) -> String {
+    let public_key = verifying_key.to_encoded_point(false);
+    format!("0x{}", hex::encode(&public_key.as_bytes()[1..]))
+}
+
+// 識別子がこの鍵を指しているか
+// 公開鍵 (64バイト、0x04 付き65バイト、圧縮33バイト) かアドレスを受け付ける
+pub fn matches_identifier(verifying_key: &VerifyingKey, identifier: &str) -> bool {
+    let Ok(bytes) = hex::decode(identifier.strip_prefix("0x").unwrap_or(identifier)) else {
+        return false;
+    };
+
+    match bytes.len() {
+        20 => bytes == address::from_verifying_key(verifying_key).as_bytes(),
+        33 => bytes == verifying_key.to_encoded_point(true).as_bytes(),
+        64 => bytes == verifying_key.to_encoded_point(false).as_bytes()[1..],
+        65 => bytes == verifying_key.to_encoded_point(false).as_bytes(),
+        _ => false,
+    }
+}
+
+// data が署名前のトランザクション (RLP のリスト、または EIP-2718 のタイプ + RLP のリスト) か
+// keccak256(data) はそのトランザクションの署名用ハッシュになるため、メッセージとしては署名しない
+pub fn is_transaction_payload(data: &[u8]) -> bool {
+    let is_list = |rlp: &[u8]| {
+        let rlp = Rlp::new(rlp);
+        rlp.is_list()
+            && rlp
+                .payload_info()
+                .is_ok_and(|info| info.header_len + info.value_len == rlp.as_raw().len())
+    };
+    match data.split_first() {
+        Some((&first, rest)) if first <= 0x7f => is_list(rest),
+        Some(_) => is_list(data),
+        None => false,
+    }
+}
+
+// data の Keccak-256 ハッシュに署名し、r + s + v (27 or 28) の16進数を返す
+pub fn sign<K: HashSigner + ?Sized>(key: &K, data: &[u8]) -> Result<String> {
+    let signature = key.sign_hash(&keccak256(data))?;
+    Ok(format!("0x{}", hex::encode(signature.to_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{
+        self,
+        tests::{TEST_ADDRESS, recover_address, test_signing_key},
+    };
+    use ethereum_types::H256;
+
+    // Hardhat / Anvil のテスト用アカウント #0 の公開鍵
+    const TEST_PUBLIC_KEY: &str = "0x8318535b54105d4a7aae60c08fc45f9687181b4fdfc625bd1a753fa7397fed753547f11ca8696646f2f3acb08e31016afac23e630c5d11f59f61fef57b0d2aa5";
+
+    #[test]
+    fn test_public_key_hex() {
+        assert_eq!(
+            public_key_hex(test_signing_key().verifying_key()),
+            TEST_PUBLIC_KEY
+        );
+    }
+
+    #[test]
+    fn test_matches_identifier() {
+        let signing_key = test_signing_key();
+        let uncompressed = format!("0x04{}", &TEST_PUBLIC_KEY[2..]);
+        let compressed = format!(
+            "0x{}",
+            hex::encode(
+                signing_key
+                    .verifying_key()
+                    .to_encoded_point(true)
+                    .as_bytes()
+            )
+        );
+
+        for identifier in [
+            TEST_PUBLIC_KEY,
+            &TEST_PUBLIC_KEY[2..],
+            &uncompressed,
+            &compressed,
+            TEST_ADDRESS,
+            &TEST_ADDRESS.to_lowercase(),
+        ] {
+            assert!(
+                matches_identifier(signing_key.verifying_key(), identifier),
+                "{identifier}"
+            );
+        }
+
+        for identifier in [
+            "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df",
+            "0x1234",
+            "not hex",
+            "",
+        ] {
+            assert!(
+                !matches_identifier(signing_key.verifying_key(), identifier),
+                "{identifier}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_transaction_payload() {
+        let unsigned = |tx_type| {
+            let message = crate::bench::synthetic_message(1, 0);
+            crate::detached::encode_unsigned(&tx_type, &message).unwrap()
+        };
+        assert!(is_transaction_payload(&unsigned(
+            crate::transaction::TxType::Eip1559
+        )));
+        assert!(is_transaction_payload(&unsigned(
+            crate::transaction::TxType::Legacy
+        )));
+        // EIP-2930 など、署名できないタイプも含める
+        assert!(is_transaction_payload(&[0x01, 0xc0]));
+
+        assert!(!is_transaction_payload(b""));
+        assert!(!is_transaction_payload(b"hello"));
+        assert!(!is_transaction_payload(&[0x02, 0xc0, 0x00]));
+        assert!(!is_transaction_payload(&[0x02]));
+    }
+
+    #[test]
+    fn test_sign() {
+        let signature = sign(&test_signing_key(), b"hello").unwrap();
+        let bytes = hex::decode(&signature[2..]).unwrap();
+        assert_eq!(bytes.len(), 65);
+        assert!(bytes[64] == 27 || bytes[64] == 28);
+
+        let signature = signer::Signature {
+            r: H256::from_slice(&bytes[..32]),
+            s: H256::from_slice(&bytes[32..64]),
+            odd_y_parity: bytes[64] == 28,
+        };
+        assert_eq!(
+            recover_address(&keccak256(b"hello"), &signature),
+            TEST_ADDRESS.parse().unwrap()
+        );
+    }
+}
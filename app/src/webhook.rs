@@ -0,0 +1,366 @@
+use crate::{
+    Result,
+    audit::{Decision, Record},
+    error::Error,
+    rpc::RpcClient,
+    secret::Secret,
+};
+use ethereum_types::H256;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use std::{
+    path::Path,
+    sync::{Arc, mpsc},
+    time::{Duration, Instant, SystemTime},
+};
+
+// 失敗した配信を再送する回数 (最初の送信を含む) と最初の待ち時間 (毎回2倍にする)
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+fn default_confirmation_timeout() -> u64 {
+    600
+}
+
+// 通知するイベント
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Signed,
+    // ポリシー違反や権限不足などで署名しなかった
+    Rejected,
+    // 2人目のオペレーターの承認待ち
+    PendingApproval,
+    // eth_sendTransaction でノードに送信した
+    Broadcast,
+    // 送信したトランザクションがブロックに取り込まれた
+    Confirmed,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Signed => "signed",
+            Self::Rejected => "rejected",
+            Self::PendingApproval => "pending_approval",
+            Self::Broadcast => "broadcast",
+            Self::Confirmed => "confirmed",
+        }
+    }
+}
+
+impl From<Decision> for EventKind {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Signed => Self::Signed,
+            Decision::Rejected => Self::Rejected,
+            Decision::PendingApproval => Self::PendingApproval,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Endpoint {
+    url: String,
+    // ボディの HMAC-SHA256 の鍵
+    secret: Secret<String>,
+    // 省略した場合はすべてのイベント
+    #[serde(default)]
+    events: Option<Vec<EventKind>>,
+}
+
+impl Endpoint {
+    fn subscribes(&self, kind: EventKind) -> bool {
+        self.events
+            .as_ref()
+            .is_none_or(|events| events.contains(&kind))
+    }
+}
+
+// Webhook の設定ファイル (TOML / YAML、拡張子で判別)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Webhooks {
+    webhooks: Vec<Endpoint>,
+    // 送信したトランザクションの receipt を確認する間隔と、諦めるまでの時間 (秒)
+    #[serde(default = "default_poll_interval")]
+    confirmation_poll_interval: u64,
+    #[serde(default = "default_confirmation_timeout")]
+    confirmation_timeout: u64,
+}
+
+// 送信する通知
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    pub body: String,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, data: Value) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let body = json!({
+            "event": kind.as_str(),
+            "timestamp": timestamp,
+            "data": data,
+        });
+
+        Self {
+            kind,
+            body: body.to_string(),
+        }
+    }
+
+    // 監査ログと同じ内容の署名要求の結果
+    pub fn from_record(record: &Record) -> Self {
+        Self::new(record.decision.into(), record.to_json())
+    }
+}
+
+// 受信側が検証する署名 (X-Signer-Signature ヘッダーの値)
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+impl Webhooks {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let webhooks: Self = config::Config::builder()
+            .add_source(config::File::from(path.as_ref()))
+            .build()
+            .and_then(config::Config::try_deserialize)
+            .map_err(Error::in_file(&path))?;
+
+        if let Some(endpoint) = webhooks
+            .webhooks
+            .iter()
+            .find(|endpoint| endpoint.secret.expose().is_empty())
+        {
+            return Err(Error::InvalidArgument(format!(
+                "webhook {}: secret must not be empty",
+                endpoint.url
+            )));
+        }
+
+        Ok(webhooks)
+    }
+
+    pub fn subscribes(&self, kind: EventKind) -> bool {
+        self.webhooks
+            .iter()
+            .any(|endpoint| endpoint.subscribes(kind))
+    }
+
+    // 購読しているすべての宛先に送る (失敗は再送し、それでも失敗したら出力して諦める)
+    pub fn deliver(&self, event: &Event) {
+        for endpoint in self
+            .webhooks
+            .iter()
+            .filter(|endpoint| endpoint.subscribes(event.kind))
+        {
+            let signature = signature(endpoint.secret.expose(), &event.body);
+            let mut delay = RETRY_DELAY;
+            for attempt in 1..=MAX_ATTEMPTS {
+                let result = ureq::post(&endpoint.url)
+                    .header("Content-Type", "application/json")
+                    .header("X-Signer-Event", event.kind.as_str())
+                    .header("X-Signer-Signature", &signature)
+                    .send(&event.body);
+                match result {
+                    Ok(_) => break,
+                    Err(error) if attempt == MAX_ATTEMPTS => {
+                        eprintln!("Failed to deliver a webhook to {}: {error}", endpoint.url);
+                    }
+                    Err(_) => {
+                        std::thread::sleep(delay);
+                        delay *= 2;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 署名サーバーの通知 (署名の応答を待たせないよう別スレッドで送る)
+pub struct Notifier {
+    webhooks: Arc<Webhooks>,
+    sender: mpsc::Sender<Event>,
+}
+
+impl Notifier {
+    pub fn new(webhooks: Webhooks) -> Self {
+        let webhooks = Arc::new(webhooks);
+        let (sender, receiver) = mpsc::channel::<Event>();
+        {
+            let webhooks = Arc::clone(&webhooks);
+            std::thread::spawn(move || {
+                for event in receiver {
+                    webhooks.deliver(&event);
+                }
+            });
+        }
+
+        Self { webhooks, sender }
+    }
+
+    pub fn notify(&self, event: Event) {
+        if self.webhooks.subscribes(event.kind) {
+            let _ = self.sender.send(event);
+        }
+    }
+
+    // ブロックに取り込まれるまで receipt を確認し、confirmed を通知する
+    pub fn watch_confirmation(&self, client: RpcClient, hash: H256, requester: String) {
+        if !self.webhooks.subscribes(EventKind::Confirmed) {
+            return;
+        }
+
+        let interval = Duration::from_secs(self.webhooks.confirmation_poll_interval);
+        let timeout = Duration::from_secs(self.webhooks.confirmation_timeout);
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let started = Instant::now();
+            while started.elapsed() < timeout {
+                std::thread::sleep(interval);
+                // 取り込まれるまで result は null (RPC のエラーも次の確認まで待つ)
+                let receipt =
+                    match client.request::<Value>("eth_getTransactionReceipt", json!([hash])) {
+                        Ok(receipt) if !receipt.is_null() => receipt,
+                        _ => continue,
+                    };
+                let data = json!({
+                    "requester": requester,
+                    "hash": hash,
+                    "blockNumber": receipt["blockNumber"],
+                    "status": receipt["status"],
+                });
+                let _ = sender.send(Event::new(EventKind::Confirmed, data));
+                return;
+            }
+            eprintln!("Transaction {hash:?} was not confirmed within {timeout:?}");
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::MockServer;
+
+    fn webhooks(url: &str, events: Option<Vec<EventKind>>) -> Webhooks {
+        Webhooks {
+            webhooks: vec![Endpoint {
+                url: url.to_string(),
+                secret: Secret::new("webhook-secret".to_string()),
+                events,
+            }],
+            confirmation_poll_interval: 0,
+            confirmation_timeout: 5,
+        }
+    }
+
+    #[test]
+    fn test_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("webhooks.toml");
+        std::fs::write(
+            &path,
+            "[[webhooks]]\nurl = \"https://hooks.example.com\"\nsecret = \"s\"\nevents = [\"signed\", \"pending_approval\"]\n",
+        )
+        .unwrap();
+
+        let webhooks = Webhooks::from_path(&path).unwrap();
+        assert!(webhooks.subscribes(EventKind::Signed));
+        assert!(webhooks.subscribes(EventKind::PendingApproval));
+        assert!(!webhooks.subscribes(EventKind::Rejected));
+        assert_eq!(webhooks.confirmation_poll_interval, 5);
+
+        std::fs::write(
+            &path,
+            "[[webhooks]]\nurl = \"https://hooks.example.com\"\nsecret = \"\"\n",
+        )
+        .unwrap();
+        assert!(Webhooks::from_path(&path).is_err());
+        std::fs::write(
+            &path,
+            "[[webhooks]]\nurl = \"u\"\nsecret = \"s\"\nevents = [\"unknown\"]\n",
+        )
+        .unwrap();
+        assert!(Webhooks::from_path(&path).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        // echo -n '{"event":"signed"}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            signature("secret", r#"{"event":"signed"}"#),
+            "sha256=46692ca1e8c1133bc500e348683e993c1a1f4a8070fe6389b391e6ac3ba20c58"
+        );
+    }
+
+    #[test]
+    fn test_deliver() {
+        // 1回目の失敗は再送する
+        let server = MockServer::start(vec![(500, "{}".to_string()), (200, "{}".to_string())]);
+        let event = Event::new(EventKind::Signed, json!({ "requester": "payments" }));
+        webhooks(&server.url, None).deliver(&event);
+        // 購読していないイベントは送らない
+        webhooks(&server.url, Some(vec![EventKind::Rejected])).deliver(&event);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let (request_line, headers, body) = &requests[1];
+        assert!(request_line.starts_with("POST / "));
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(header("x-signer-event"), Some("signed"));
+        let body = String::from_utf8(body.clone()).unwrap();
+        assert_eq!(
+            header("x-signer-signature"),
+            Some(signature("webhook-secret", &body).as_str())
+        );
+
+        let json: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["event"], "signed");
+        assert_eq!(json["data"]["requester"], "payments");
+        assert!(json["timestamp"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_watch_confirmation() {
+        let hash = H256::repeat_byte(0xab);
+        let rpc = MockServer::start(vec![
+            MockServer::rpc_result(Value::Null),
+            MockServer::rpc_result(json!({ "blockNumber": "0x10", "status": "0x1" })),
+        ]);
+        let hooks = MockServer::start(vec![(200, "{}".to_string())]);
+        let notifier = Notifier::new(webhooks(&hooks.url, Some(vec![EventKind::Confirmed])));
+
+        notifier.watch_confirmation(RpcClient::new(&rpc.url), hash, "payments".to_string());
+
+        assert_eq!(
+            rpc.json_requests()[1]["method"],
+            "eth_getTransactionReceipt"
+        );
+        let json = &hooks.json_requests()[0];
+        assert_eq!(json["event"], "confirmed");
+        assert_eq!(json["data"]["hash"], json!(hash));
+        assert_eq!(json["data"]["blockNumber"], "0x10");
+        assert_eq!(json["data"]["requester"], "payments");
+    }
+}
@@ -0,0 +1,58 @@
+use crate::abi;
+use ethereum_types::{H160, U256};
+
+// チェーンごとの公式な WETH のアドレス
+pub fn default_address(chain_id: u64) -> Option<H160> {
+    let address = match chain_id {
+        1 => "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        // OP Stack のチェーンは共通の predeploy
+        10 | 8453 | 84532 | 11155420 => "0x4200000000000000000000000000000000000006",
+        42161 => "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        11155111 => "0xfFf9976782d46CC05630D1f6eBAb18b2324d6B14",
+        _ => return None,
+    };
+
+    address.parse().ok()
+}
+
+// deposit() の calldata (ETH は value で送る)
+pub fn deposit_calldata() -> Vec<u8> {
+    abi::encode_call("deposit()", &[])
+}
+
+// withdraw(uint256) の calldata
+pub fn withdraw_calldata(amount: U256) -> Vec<u8> {
+    abi::encode_call("withdraw(uint256)", &[abi::encode_u256(amount)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_address() {
+        assert_eq!(
+            default_address(1),
+            Some(
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap()
+            )
+        );
+        assert_eq!(default_address(10), default_address(8453));
+        assert_eq!(default_address(31337), None);
+    }
+
+    #[test]
+    fn test_deposit_calldata() {
+        assert_eq!(hex::encode(deposit_calldata()), "d0e30db0");
+    }
+
+    #[test]
+    fn test_withdraw_calldata() {
+        assert_eq!(
+            hex::encode(withdraw_calldata(U256::exp10(18))),
+            "2e1a7d4d0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{
+    Result,
+    eip712::TypedData,
+    error::Error,
+    signer::{HashSigner, Signature},
+};
+use ethereum::{AccessList, EIP1559Transaction, EIP1559TransactionMessage, TransactionAction};
+use ethereum_types::{H160, H256, U256};
+use rlp::{Rlp, RlpStream};
+
+// zkSync の EIP-712 トランザクション (paymaster を指定できる) の EIP-2718 タイプ
+pub const EIP712_TX_TYPE: u8 = 0x71;
+
+// pubdata 1バイトあたりの gas の上限 (zksync-ethers の既定値)
+pub const DEFAULT_GAS_PER_PUBDATA: u64 = 50_000;
+
+// 手数料を代わりに払う paymaster と、paymaster に渡すデータ
+// (approvalBased の場合は approvalBased(address,uint256,bytes) の calldata)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paymaster {
+    pub address: H160,
+    pub input: Vec<u8>,
+}
+
+// 署名する EIP-712 の Transaction 構造体
+// アドレスも uint256 として扱い、factoryDeps (コントラクトのバイトコード) は使わない
+pub fn typed_data(
+    message: &EIP1559TransactionMessage,
+    from: H160,
+    paymaster: &Paymaster,
+) -> Result<TypedData> {
+    let to = match message.action {
+        TransactionAction::Call(to) => to,
+        TransactionAction::Create => {
+            return Err(Error::InvalidArgument(
+                "paymaster transactions must have a to address".to_string(),
+            ));
+        }
+    };
+    let uint = |address: H160| U256::from_big_endian(address.as_bytes()).to_string();
+    let typed_data = serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" }
+            ],
+            "Transaction": [
+                { "name": "txType", "type": "uint256" },
+                { "name": "from", "type": "uint256" },
+                { "name": "to", "type": "uint256" },
+                { "name": "gasLimit", "type": "uint256" },
+                { "name": "gasPerPubdataByteLimit", "type": "uint256" },
+                { "name": "maxFeePerGas", "type": "uint256" },
+                { "name": "maxPriorityFeePerGas", "type": "uint256" },
+                { "name": "paymaster", "type": "uint256" },
+                { "name": "nonce", "type": "uint256" },
+                { "name": "value", "type": "uint256" },
+                { "name": "data", "type": "bytes" },
+                { "name": "factoryDeps", "type": "bytes32[]" },
+                { "name": "paymasterInput", "type": "bytes" }
+            ]
+        },
+        "primaryType": "Transaction",
+        "domain": {
+            "name": "zkSync",
+            "version": "2",
+            "chainId": message.chain_id,
+        },
+        "message": {
+            "txType": EIP712_TX_TYPE,
+            "from": uint(from),
+            "to": uint(to),
+            "gasLimit": message.gas_limit.to_string(),
+            "gasPerPubdataByteLimit": DEFAULT_GAS_PER_PUBDATA,
+            "maxFeePerGas": message.max_fee_per_gas.to_string(),
+            "maxPriorityFeePerGas": message.max_priority_fee_per_gas.to_string(),
+            "paymaster": uint(paymaster.address),
+            "nonce": message.nonce.to_string(),
+            "value": message.value.to_string(),
+            "data": format!("0x{}", hex::encode(&message.input)),
+            "factoryDeps": [],
+            "paymasterInput": format!("0x{}", hex::encode(&paymaster.input)),
+        }
+    });
+
+    serde_json::from_value(typed_data).map_err(Into::into)
+}
+
+pub fn signing_hash(
+    message: &EIP1559TransactionMessage,
+    from: H160,
+    paymaster: &Paymaster,
+) -> Result<H256> {
+    typed_data(message, from, paymaster)?.digest()
+}
+
+// 署名して 0x71 + RLP の raw トランザクションを作成
+// 署名は v, r, s ではなく customSignature (r + s + v の65バイト) に入れ、v, r, s の位置には chain id と空の値を置く
+pub fn sign<K: HashSigner + ?Sized>(
+    message: &EIP1559TransactionMessage,
+    paymaster: &Paymaster,
+    key: &K,
+) -> Result<Vec<u8>> {
+    let from = key.address();
+    let signature = key.sign_hash(&signing_hash(message, from, paymaster)?)?;
+    Ok(encode(message, from, paymaster, &signature))
+}
+
+fn encode(
+    message: &EIP1559TransactionMessage,
+    from: H160,
+    paymaster: &Paymaster,
+    signature: &Signature,
+) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(16);
+    stream
+        .append(&message.nonce)
+        .append(&message.max_priority_fee_per_gas)
+        .append(&message.max_fee_per_gas)
+        .append(&message.gas_limit)
+        .append(&message.action)
+        .append(&message.value)
+        .append(&message.input)
+        .append(&message.chain_id)
+        .append_empty_data()
+        .append_empty_data()
+        .append(&message.chain_id)
+        .append(&from)
+        .append(&U256::from(DEFAULT_GAS_PER_PUBDATA));
+    stream.begin_list(0);
+    stream.append(&signature.to_bytes().to_vec());
+    stream
+        .begin_list(2)
+        .append(&paymaster.address)
+        .append(&paymaster.input);
+
+    let mut raw = vec![EIP712_TX_TYPE];
+    raw.extend_from_slice(&stream.out());
+    raw
+}
+
+// 0x71 の raw トランザクションを Type 2 と同じ形にデコードする (ジャーナルの読み込み用、paymaster は含めない)
+pub fn decode(raw: &[u8]) -> Result<EIP1559Transaction> {
+    let invalid = |error: rlp::DecoderError| {
+        Error::InvalidArgument(format!("invalid zkSync transaction: {error}"))
+    };
+    let rlp = Rlp::new(raw.get(1..).unwrap_or_default());
+    if rlp.item_count().map_err(invalid)? != 16 {
+        return Err(invalid(rlp::DecoderError::RlpIncorrectListLen));
+    }
+    let signature: Vec<u8> = rlp.val_at(14).map_err(invalid)?;
+    if signature.len() != 65 {
+        return Err(Error::InvalidArgument(format!(
+            "invalid zkSync transaction: customSignature is {} bytes, expected 65",
+            signature.len()
+        )));
+    }
+
+    Ok(EIP1559Transaction {
+        chain_id: rlp.val_at(10).map_err(invalid)?,
+        nonce: rlp.val_at(0).map_err(invalid)?,
+        max_priority_fee_per_gas: rlp.val_at(1).map_err(invalid)?,
+        max_fee_per_gas: rlp.val_at(2).map_err(invalid)?,
+        gas_limit: rlp.val_at(3).map_err(invalid)?,
+        action: rlp.val_at(4).map_err(invalid)?,
+        value: rlp.val_at(5).map_err(invalid)?,
+        input: rlp.val_at(6).map_err(invalid)?,
+        access_list: AccessList::default(),
+        odd_y_parity: signature[64] == 28,
+        r: H256::from_slice(&signature[..32]),
+        s: H256::from_slice(&signature[32..64]),
+    })
+}
+
+// 0x71 の raw トランザクションの paymaster (ジャーナルと監査ログの形式に使う)
+pub fn decode_paymaster(raw: &[u8]) -> Result<Paymaster> {
+    let invalid = |error: rlp::DecoderError| {
+        Error::InvalidArgument(format!("invalid zkSync transaction: {error}"))
+    };
+    let rlp = Rlp::new(raw.get(1..).unwrap_or_default());
+    let paymaster = rlp.at(15).map_err(invalid)?;
+    Ok(Paymaster {
+        address: paymaster.val_at(0).map_err(invalid)?,
+        input: paymaster.val_at(1).map_err(invalid)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::tests::{TEST_ADDRESS, recover_address, test_signing_key};
+
+    fn test_message() -> EIP1559TransactionMessage {
+        EIP1559TransactionMessage {
+            chain_id: 324,
+            nonce: U256::from(3),
+            max_priority_fee_per_gas: U256::zero(),
+            max_fee_per_gas: U256::from(250_000_000u64),
+            gas_limit: U256::from(500_000),
+            action: TransactionAction::Call(
+                "0x742d35Cc6634C0532925a3b8D2f8e0C4eD2d11Df"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: U256::zero(),
+            input: vec![0xa9, 0x05, 0x9c, 0xbb],
+            access_list: AccessList::default(),
+        }
+    }
+
+    fn test_paymaster() -> Paymaster {
+        Paymaster {
+            address: H160::repeat_byte(0x11),
+            // general(bytes) に空の bytes を渡す
+            input: hex::decode("8c5a344500000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000000").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_typed_data() {
+        let from: H160 = TEST_ADDRESS.parse().unwrap();
+        let typed_data = typed_data(&test_message(), from, &test_paymaster()).unwrap();
+        assert_eq!(typed_data.message["txType"], 113);
+        assert_eq!(
+            typed_data.message["paymaster"],
+            U256::from_big_endian(&[0x11; 20]).to_string()
+        );
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            crate::signer::keccak256(
+                [
+                    crate::signer::keccak256(
+                        "EIP712Domain(string name,string version,uint256 chainId)"
+                    )
+                    .as_bytes(),
+                    crate::signer::keccak256("zkSync").as_bytes(),
+                    crate::signer::keccak256("2").as_bytes(),
+                    &crate::abi::encode_u256(U256::from(324)),
+                ]
+                .concat()
+            )
+        );
+
+        let mut create = test_message();
+        create.action = TransactionAction::Create;
+        assert!(super::typed_data(&create, from, &test_paymaster()).is_err());
+    }
+
+    #[test]
+    fn test_sign() {
+        let message = test_message();
+        let paymaster = test_paymaster();
+        let raw = sign(&message, &paymaster, &test_signing_key()).unwrap();
+        assert_eq!(raw[0], EIP712_TX_TYPE);
+
+        let rlp = Rlp::new(&raw[1..]);
+        assert_eq!(rlp.item_count().unwrap(), 16);
+        assert_eq!(rlp.val_at::<u64>(7).unwrap(), 324);
+        assert_eq!(
+            rlp.val_at::<H160>(11).unwrap(),
+            TEST_ADDRESS.parse().unwrap()
+        );
+        assert_eq!(rlp.at(13).unwrap().item_count().unwrap(), 0);
+        let paymaster_params = rlp.at(15).unwrap();
+        assert_eq!(
+            paymaster_params.val_at::<H160>(0).unwrap(),
+            paymaster.address
+        );
+        assert_eq!(
+            paymaster_params.val_at::<Vec<u8>>(1).unwrap(),
+            paymaster.input
+        );
+
+        // customSignature は EIP-712 のダイジェストへの署名
+        let transaction = decode(&raw).unwrap();
+        assert_eq!(
+            EIP1559TransactionMessage::from(transaction.clone()),
+            message
+        );
+        let from = TEST_ADDRESS.parse().unwrap();
+        let signature = Signature {
+            r: transaction.r,
+            s: transaction.s,
+            odd_y_parity: transaction.odd_y_parity,
+        };
+        assert_eq!(
+            recover_address(
+                &signing_hash(&message, from, &paymaster).unwrap(),
+                &signature
+            ),
+            from
+        );
+
+        assert!(decode(&[EIP712_TX_TYPE, 0xc0]).is_err());
+    }
+}